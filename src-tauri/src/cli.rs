@@ -0,0 +1,278 @@
+//! Headless commands for scripts and power users.
+//!
+//! `main` checks the arguments before Tauri starts; anything that is not one
+//! of the options below starts the GUI, or is forwarded to the one already
+//! running. The commands use the same data directory and core modules as the
+//! GUI, and run in their own process even while the GUI is open; both
+//! re-read `instances.json` before changing it, so neither loses instances
+//! the other added:
+//!
+//! - `--list-instances` prints one `id<TAB>name<TAB>version` line per instance
+//! - `--launch <instance>` runs an installed instance, by id or name, and
+//!   exits with the game's exit code
+//! - `--install-fabric <minecraft version> [loader version]` adds a Fabric
+//!   version, by default with the newest stable loader
+//! - `--import-modpack <path>` imports a MultiMC, Prism Launcher or
+//!   ATLauncher instance from a folder or exported zip
+//!
+//! Release builds on Windows are GUI programs without a console, so output
+//! is only visible when redirected, e.g. `dropout --list-instances | more`.
+
+use std::path::{Path, PathBuf};
+
+use crate::core;
+use crate::core::error::LauncherError;
+
+/// Matches the `identifier` in `tauri.conf.json`, which names Tauri's
+/// app data directory.
+const APP_IDENTIFIER: &str = "com.dropout.launcher";
+
+const USAGE: &str = "Usage:
+  dropout --list-instances
+  dropout --launch <instance id or name>
+  dropout --install-fabric <minecraft version> [loader version]
+  dropout --import-modpack <folder or zip>";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    ListInstances,
+    Launch {
+        instance: String,
+    },
+    InstallFabric {
+        minecraft_version: String,
+        loader_version: Option<String>,
+    },
+    ImportModpack {
+        path: PathBuf,
+    },
+    Help,
+}
+
+/// The CLI command in `args` (without the program name), or `None` to start
+/// the GUI.
+pub fn parse(args: &[String]) -> Option<Result<Command, String>> {
+    let (flag, rest) = args.split_first()?;
+    let command = match (flag.as_str(), rest) {
+        ("--list-instances", []) => Command::ListInstances,
+        ("--launch", [instance]) => Command::Launch {
+            instance: instance.clone(),
+        },
+        ("--install-fabric", [minecraft_version]) => Command::InstallFabric {
+            minecraft_version: minecraft_version.clone(),
+            loader_version: None,
+        },
+        ("--install-fabric", [minecraft_version, loader_version]) => Command::InstallFabric {
+            minecraft_version: minecraft_version.clone(),
+            loader_version: Some(loader_version.clone()),
+        },
+        ("--import-modpack", [path]) => Command::ImportModpack {
+            path: PathBuf::from(path),
+        },
+        ("--help" | "-h", []) => Command::Help,
+        (
+            "--list-instances" | "--launch" | "--install-fabric" | "--import-modpack" | "--help"
+            | "-h",
+            _,
+        ) => return Some(Err(format!("Invalid arguments for {}\n{}", flag, USAGE))),
+        _ => return None,
+    };
+    Some(Ok(command))
+}
+
+/// Run `command` and return the process exit code.
+pub fn run(command: Command) -> i32 {
+    if command == Command::Help {
+        println!("{}", USAGE);
+        return 0;
+    }
+    let Some(data_dir) = dirs::data_dir().map(|dir| dir.join(APP_IDENTIFIER)) else {
+        eprintln!("Cannot find the data directory");
+        return 1;
+    };
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start: {}", e);
+            return 1;
+        }
+    };
+    match runtime.block_on(execute(command, &data_dir)) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    }
+}
+
+async fn execute(command: Command, data_dir: &Path) -> Result<i32, LauncherError> {
+    let config_state = core::config::ConfigState::load(data_dir.join("config.json"));
+    core::http::configure(&config_state.config.lock().unwrap());
+    core::logging::configure(&config_state.config.lock().unwrap());
+    let instances = core::instance::InstanceManager::new(data_dir);
+
+    match command {
+        Command::ListInstances => {
+            for instance in instances.list() {
+                println!(
+                    "{}\t{}\t{}",
+                    instance.id, instance.name, instance.version_id
+                );
+            }
+            Ok(0)
+        }
+        Command::Launch { instance } => {
+            let instance = find_instance(&instances, &instance)?;
+            if instance.archived.is_some() {
+                return Err(LauncherError::Invalid(format!(
+                    "{} is archived; unarchive it in the launcher first",
+                    instance.name
+                )));
+            }
+            let account = launch_account(data_dir, &instance).await?;
+            let config = config_state.for_instance(&instance);
+            for warning in core::launch::check_memory(&config)? {
+                eprintln!("Warning: {}", warning);
+            }
+            let launch_paths = core::instance::LaunchPaths::for_instance(
+                data_dir,
+                &instances.instance_dir(&instance.id),
+                &instance,
+            );
+            let auth = core::launch::LaunchAuth {
+                username: account.username(),
+                uuid: account.uuid(),
+                access_token: account.access_token(),
+            };
+            let mut args =
+                crate::installed_launch_arguments(&launch_paths, &instance, &config, &auth).await?;
+            let mut display = core::display::setup(config.display_backend);
+            display.extend(core::gpu::setup(config.gpu_preference, &config.java_path));
+            for note in &display.notes {
+                eprintln!("Display: {}", note);
+            }
+            args.splice(0..0, display.jvm_args.iter().cloned());
+            println!("Launching {} ({})", instance.name, instance.version_id);
+            let mut command = tokio::process::Command::new(&config.java_path);
+            display.apply(&mut command);
+            let status = command
+                .args(&args)
+                .current_dir(&launch_paths.game_dir)
+                .status()
+                .await
+                .map_err(|e| format!("Failed to launch java: {}", e))?;
+            core::account_storage::AccountStorage::new(data_dir.to_path_buf())
+                .mark_used(&account.uuid(), Some(&instance.id))?;
+            Ok(status.code().unwrap_or(-1))
+        }
+        Command::InstallFabric {
+            minecraft_version,
+            loader_version,
+        } => {
+            let loader_version = match loader_version {
+                Some(version) => version,
+                None => crate::newest_fabric_loader(&minecraft_version).await?,
+            };
+            let installed = core::fabric::install_fabric(
+                &data_dir.to_path_buf(),
+                &minecraft_version,
+                &loader_version,
+            )
+            .await?;
+            println!("Installed {}", installed.id);
+            Ok(0)
+        }
+        Command::ImportModpack { path } => {
+            let result =
+                crate::import_foreign_instance(core::events::NoEvents, &instances, data_dir, path)
+                    .await?;
+            println!(
+                "Imported {} as {}",
+                result.instance.name, result.instance.id
+            );
+            if !result.version_installed {
+                println!(
+                    "Version {} is not installed yet; launch the instance from the launcher once",
+                    result.instance.version_id
+                );
+            }
+            Ok(0)
+        }
+        Command::Help => Ok(0),
+    }
+}
+
+/// Instance with id `query`, or else the only one named `query`.
+fn find_instance(
+    instances: &core::instance::InstanceManager,
+    query: &str,
+) -> Result<core::instance::Instance, LauncherError> {
+    if let Ok(instance) = instances.get(query) {
+        return Ok(instance);
+    }
+    let mut matches: Vec<_> = instances
+        .list()
+        .into_iter()
+        .filter(|instance| instance.name.eq_ignore_ascii_case(query))
+        .collect();
+    match matches.len() {
+        1 => Ok(matches.remove(0)),
+        0 => Err(LauncherError::Invalid(format!(
+            "No instance named {}",
+            query
+        ))),
+        _ => Err(LauncherError::Invalid(format!(
+            "Several instances are named {}; use the id from --list-instances",
+            query
+        ))),
+    }
+}
+
+/// The account that launches `instance`: the one bound to it, or else the
+/// one selected in the launcher.
+async fn launch_account(
+    data_dir: &Path,
+    instance: &core::instance::Instance,
+) -> Result<core::auth::Account, LauncherError> {
+    let storage = core::account_storage::AccountStorage::new(data_dir.to_path_buf());
+    let (stored, ms_refresh) = match &instance.account_id {
+        Some(uuid) => storage.get_account(uuid).ok_or(format!(
+            "The account bound to {} was removed; bind another one in the launcher",
+            instance.name
+        ))?,
+        None => storage
+            .get_active_account()
+            .ok_or("No active account found. Please login in the launcher first.")?,
+    };
+    crate::usable_account(&core::events::NoEvents, data_dir, stored, ms_refresh).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(&args(&[])), None);
+        assert_eq!(parse(&args(&["dropout://join/example.com"])), None);
+        assert_eq!(
+            parse(&args(&["--launch", "Survival"])),
+            Some(Ok(Command::Launch {
+                instance: "Survival".to_string()
+            }))
+        );
+        assert_eq!(
+            parse(&args(&["--install-fabric", "1.20.4"])),
+            Some(Ok(Command::InstallFabric {
+                minecraft_version: "1.20.4".to_string(),
+                loader_version: None,
+            }))
+        );
+        assert!(matches!(parse(&args(&["--launch"])), Some(Err(_))));
+    }
+}
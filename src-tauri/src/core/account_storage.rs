@@ -1,15 +1,77 @@
 use crate::core::auth::{Account, MicrosoftAccount, OfflineAccount};
+use crate::core::events::LauncherEvent;
+use crate::utils::file_utils;
 use serde::{Deserialize, Serialize};
-use std::fs;
 use std::path::PathBuf;
 
 /// Stored account data for persistence
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AccountStore {
-    pub accounts: Vec<StoredAccount>,
+    pub accounts: Vec<AccountRecord>,
     pub active_account_id: Option<String>,
 }
 
+/// A stored account with its bookkeeping, which survives logging in again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountRecord {
+    #[serde(flatten)]
+    pub account: StoredAccount,
+    /// Unix time the account was first added, 0 for accounts stored before
+    /// this was tracked
+    #[serde(default)]
+    pub added_at: u64,
+    /// Unix time the account last launched a game
+    #[serde(default)]
+    pub last_used_at: Option<u64>,
+    /// Instance the account last launched, if the game ran in one
+    #[serde(default)]
+    pub last_instance_id: Option<String>,
+}
+
+/// An account as listed in the account switcher, without its tokens.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountSummary {
+    pub uuid: String,
+    pub username: String,
+    /// "Offline" or "Microsoft"
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub active: bool,
+    pub added_at: u64,
+    pub last_used_at: Option<u64>,
+    pub last_instance_id: Option<String>,
+}
+
+/// Whether `add_or_update_account` stored a new account or replaced the
+/// credentials of one with the same UUID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountChange {
+    Added,
+    Updated,
+}
+
+/// A Microsoft profile whose name or UUID changed when its token was
+/// refreshed. Cached heads of the account have been fetched again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AccountProfileUpdated {
+    pub uuid: String,
+    pub username: String,
+    pub previous_uuid: String,
+    pub previous_username: String,
+}
+
+impl LauncherEvent for AccountProfileUpdated {
+    const NAME: &'static str = "account-profile-updated";
+}
+
+/// A login and whether it added a new account.
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedAccount {
+    pub account: Account,
+    pub change: AccountChange,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum StoredAccount {
@@ -76,71 +138,169 @@ impl AccountStorage {
     }
 
     pub fn load(&self) -> AccountStore {
-        if self.file_path.exists() {
-            let content = fs::read_to_string(&self.file_path).unwrap_or_default();
-            serde_json::from_str(&content).unwrap_or_default()
-        } else {
-            AccountStore::default()
-        }
+        file_utils::read_json(&self.file_path).unwrap_or_default()
     }
 
     pub fn save(&self, store: &AccountStore) -> Result<(), String> {
-        let content = serde_json::to_string_pretty(store).map_err(|e| e.to_string())?;
-        if let Some(parent) = self.file_path.parent() {
-            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
-        }
-        fs::write(&self.file_path, content).map_err(|e| e.to_string())?;
-        Ok(())
+        file_utils::write_json_atomic(&self.file_path, store).map_err(|e| e.to_string())
     }
 
+    /// Store `account` and make it active. An account with the same UUID is
+    /// updated in place, keeping its position and history.
     pub fn add_or_update_account(
         &self,
         account: &Account,
         ms_refresh_token: Option<String>,
-    ) -> Result<(), String> {
+    ) -> Result<AccountChange, String> {
         let mut store = self.load();
         let stored = StoredAccount::from_account(account, ms_refresh_token);
         let id = stored.id();
 
-        // Remove existing account with same ID
-        store.accounts.retain(|a| a.id() != id);
-        store.accounts.push(stored);
+        let change = match store.accounts.iter_mut().find(|r| r.account.id() == id) {
+            Some(record) => {
+                record.account = stored;
+                AccountChange::Updated
+            }
+            None => {
+                store.accounts.push(AccountRecord {
+                    account: stored,
+                    added_at: now(),
+                    last_used_at: None,
+                    last_instance_id: None,
+                });
+                AccountChange::Added
+            }
+        };
         store.active_account_id = Some(id);
 
+        self.save(&store)?;
+        Ok(change)
+    }
+
+    /// Replace the credentials of the stored account with `previous_uuid`,
+    /// e.g. after refreshing its token, without making it active. Returns the
+    /// change if the refreshed profile has another name or UUID.
+    pub fn update_account(
+        &self,
+        previous_uuid: &str,
+        account: &Account,
+        ms_refresh_token: Option<String>,
+    ) -> Result<Option<AccountProfileUpdated>, String> {
+        let mut store = self.load();
+        let stored = StoredAccount::from_account(account, ms_refresh_token);
+        let uuid = stored.id();
+        let position = store
+            .accounts
+            .iter()
+            .position(|r| r.account.id() == previous_uuid)
+            .ok_or("Account not found")?;
+        let previous = std::mem::replace(&mut store.accounts[position].account, stored);
+        let previous = previous.to_account();
+
+        if uuid != previous_uuid {
+            // Keep one record per profile
+            let mut index = 0;
+            store.accounts.retain(|r| {
+                let keep = index == position || r.account.id() != uuid;
+                index += 1;
+                keep
+            });
+            if store.active_account_id.as_deref() == Some(previous_uuid) {
+                store.active_account_id = Some(uuid.clone());
+            }
+        }
+        self.save(&store)?;
+
+        let username = account.username();
+        Ok((uuid != previous_uuid || username != previous.username()).then(|| {
+            AccountProfileUpdated {
+                uuid,
+                username,
+                previous_uuid: previous_uuid.to_string(),
+                previous_username: previous.username(),
+            }
+        }))
+    }
+
+    /// Record that the account with `uuid` launched a game, in `instance_id`
+    /// if the game ran in an instance.
+    pub fn mark_used(&self, uuid: &str, instance_id: Option<&str>) -> Result<(), String> {
+        let mut store = self.load();
+        let record = store
+            .accounts
+            .iter_mut()
+            .find(|r| r.account.id() == uuid)
+            .ok_or("Account not found")?;
+        record.last_used_at = Some(now());
+        record.last_instance_id = instance_id.map(str::to_string);
         self.save(&store)
     }
 
+    /// All accounts, most recently used first. Accounts never used follow,
+    /// newest first.
+    pub fn list(&self) -> Vec<AccountSummary> {
+        let store = self.load();
+        let mut accounts: Vec<AccountSummary> = store
+            .accounts
+            .into_iter()
+            .map(|r| {
+                let (username, kind) = match &r.account {
+                    StoredAccount::Offline(a) => (a.username.clone(), "Offline"),
+                    StoredAccount::Microsoft(a) => (a.username.clone(), "Microsoft"),
+                };
+                let uuid = r.account.id();
+                AccountSummary {
+                    active: store.active_account_id.as_deref() == Some(uuid.as_str()),
+                    uuid,
+                    username,
+                    kind,
+                    added_at: r.added_at,
+                    last_used_at: r.last_used_at,
+                    last_instance_id: r.last_instance_id,
+                }
+            })
+            .collect();
+        accounts.sort_by(|a, b| {
+            b.last_used_at
+                .cmp(&a.last_used_at)
+                .then(b.added_at.cmp(&a.added_at))
+        });
+        accounts
+    }
+
     pub fn remove_account(&self, uuid: &str) -> Result<(), String> {
         let mut store = self.load();
-        store.accounts.retain(|a| a.id() != uuid);
+        store.accounts.retain(|r| r.account.id() != uuid);
         if store.active_account_id.as_deref() == Some(uuid) {
-            store.active_account_id = store.accounts.first().map(|a| a.id());
+            store.active_account_id = store.accounts.first().map(|r| r.account.id());
         }
         self.save(&store)
     }
 
     pub fn get_active_account(&self) -> Option<(StoredAccount, Option<String>)> {
+        let active_id = self.load().active_account_id?;
+        self.get_account(&active_id)
+    }
+
+    /// The account with `uuid` and its Microsoft refresh token.
+    pub fn get_account(&self, uuid: &str) -> Option<(StoredAccount, Option<String>)> {
         let store = self.load();
-        if let Some(active_id) = &store.active_account_id {
-            store
-                .accounts
-                .iter()
-                .find(|a| &a.id() == active_id)
-                .map(|a| {
-                    let ms_token = match a {
-                        StoredAccount::Microsoft(m) => m.ms_refresh_token.clone(),
-                        _ => None,
-                    };
-                    (a.clone(), ms_token)
-                })
-        } else {
-            None
-        }
+        store
+            .accounts
+            .into_iter()
+            .find(|r| r.account.id() == uuid)
+            .map(|r| {
+                let ms_token = match &r.account {
+                    StoredAccount::Microsoft(m) => m.ms_refresh_token.clone(),
+                    _ => None,
+                };
+                (r.account, ms_token)
+            })
     }
 
     pub fn set_active_account(&self, uuid: &str) -> Result<(), String> {
         let mut store = self.load();
-        if store.accounts.iter().any(|a| a.id() == uuid) {
+        if store.accounts.iter().any(|r| r.account.id() == uuid) {
             store.active_account_id = Some(uuid.to_string());
             self.save(&store)
         } else {
@@ -148,7 +308,100 @@ impl AccountStorage {
         }
     }
 
-    pub fn get_all_accounts(&self) -> Vec<StoredAccount> {
+    pub fn get_all_accounts(&self) -> Vec<AccountRecord> {
         self.load().accounts
     }
 }
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offline(username: &str, uuid: &str) -> Account {
+        Account::Offline(OfflineAccount {
+            username: username.to_string(),
+            uuid: uuid.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_add_merges_by_uuid() {
+        let dir = std::env::temp_dir().join(format!("dropout-accounts-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let storage = AccountStorage::new(dir.clone());
+
+        let change = storage.add_or_update_account(&offline("Steve", "1"), None);
+        assert_eq!(change, Ok(AccountChange::Added));
+        storage.add_or_update_account(&offline("Alex", "2"), None).unwrap();
+
+        let mut store = storage.load();
+        store.accounts[0].added_at = 100;
+        store.accounts[0].last_used_at = Some(200);
+        storage.save(&store).unwrap();
+
+        let change = storage.add_or_update_account(&offline("Steve2", "1"), None);
+        assert_eq!(change, Ok(AccountChange::Updated));
+        let store = storage.load();
+        assert_eq!(store.accounts.len(), 2);
+        assert_eq!(store.accounts[0].account.to_account().username(), "Steve2");
+        assert_eq!(store.accounts[0].added_at, 100);
+        assert_eq!(store.accounts[0].last_used_at, Some(200));
+        assert_eq!(store.active_account_id.as_deref(), Some("1"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_update_reports_profile_changes() {
+        let dir = std::env::temp_dir().join(format!("dropout-accounts-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let storage = AccountStorage::new(dir.clone());
+        storage.add_or_update_account(&offline("Steve", "1"), None).unwrap();
+
+        assert_eq!(storage.update_account("1", &offline("Steve", "1"), None), Ok(None));
+        let update = storage.update_account("1", &offline("Herobrine", "1"), None);
+        assert_eq!(
+            update,
+            Ok(Some(AccountProfileUpdated {
+                uuid: "1".to_string(),
+                username: "Herobrine".to_string(),
+                previous_uuid: "1".to_string(),
+                previous_username: "Steve".to_string(),
+            }))
+        );
+        assert_eq!(storage.list()[0].username, "Herobrine");
+        assert!(storage.update_account("2", &offline("Alex", "2"), None).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_list_sorts_by_recency() {
+        let dir = std::env::temp_dir().join(format!("dropout-accounts-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let storage = AccountStorage::new(dir.clone());
+        for (name, uuid) in [("Steve", "1"), ("Alex", "2"), ("Notch", "3")] {
+            storage.add_or_update_account(&offline(name, uuid), None).unwrap();
+        }
+        storage.mark_used("2", Some("survival")).unwrap();
+        let mut store = storage.load();
+        store.accounts[0].added_at = 1;
+        store.accounts[2].added_at = 2;
+        storage.save(&store).unwrap();
+
+        let list = storage.list();
+        let order: Vec<&str> = list.iter().map(|a| a.uuid.as_str()).collect();
+        assert_eq!(order, ["2", "3", "1"]);
+        assert_eq!(list[0].last_instance_id.as_deref(), Some("survival"));
+        assert!(list[1].active);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
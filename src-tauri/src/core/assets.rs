@@ -0,0 +1,171 @@
+//! Asset index handling.
+//!
+//! Modern versions read assets straight from the hashed object store
+//! (`assets/objects/xx/xxxx...`). Versions older than 1.7 instead expect
+//! a tree of named files:
+//! - `pre-1.6` indexes set `map_to_resources` and are read from `<game_dir>/resources/`
+//! - `legacy` indexes set `virtual` and are read from `assets/virtual/legacy/`
+//!
+//! Without this tree, old versions start without sounds and languages.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A single object entry in an asset index.
+#[derive(Debug, Deserialize, Clone)]
+pub struct AssetObject {
+    pub hash: String,
+    #[serde(default)]
+    pub size: u64,
+}
+
+/// Parsed asset index JSON (`assets/indexes/<id>.json`).
+#[derive(Debug, Deserialize, Clone)]
+pub struct AssetIndexJson {
+    pub objects: HashMap<String, AssetObject>,
+    /// Set by the `pre-1.6` index: assets are read from `<game_dir>/resources/`
+    #[serde(default)]
+    pub map_to_resources: bool,
+    /// Set by the `legacy` index: assets are read from `assets/virtual/<id>/`
+    #[serde(default, rename = "virtual")]
+    pub is_virtual: bool,
+}
+
+/// How a version expects its assets to be laid out on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetLayout {
+    /// Hashed object store only (1.7+)
+    Objects,
+    /// Named copy under `assets/virtual/<id>/`
+    Virtual,
+    /// Named copy under `<game_dir>/resources/`
+    Resources,
+}
+
+/// Determine the asset layout for an index.
+///
+/// The flags inside the index are authoritative, but the well-known ids are
+/// also matched so that hand-edited or truncated indexes still work.
+pub fn detect_layout(index_id: &str, index: &AssetIndexJson) -> AssetLayout {
+    if index.map_to_resources || index_id == "pre-1.6" {
+        AssetLayout::Resources
+    } else if index.is_virtual || index_id == "legacy" {
+        AssetLayout::Virtual
+    } else {
+        AssetLayout::Objects
+    }
+}
+
+/// Get the directory that should be passed as `${game_assets}` / `${assets_root}`
+/// for legacy versions.
+///
+/// # Arguments
+/// * `game_dir` - The .minecraft directory path
+/// * `assets_dir` - The `assets` directory
+/// * `index_id` - The asset index id (e.g. "legacy")
+/// * `layout` - The detected layout
+pub fn game_assets_dir(
+    game_dir: &Path,
+    assets_dir: &Path,
+    index_id: &str,
+    layout: AssetLayout,
+) -> PathBuf {
+    match layout {
+        AssetLayout::Objects => assets_dir.to_path_buf(),
+        AssetLayout::Virtual => assets_dir.join("virtual").join(index_id),
+        AssetLayout::Resources => game_dir.join("resources"),
+    }
+}
+
+/// Reconstruct the named asset tree for legacy versions from the object store.
+///
+/// Files that already exist with the expected size are left untouched, so this
+/// is cheap to call on every launch. Does nothing for modern (object-only) indexes.
+///
+/// # Returns
+/// The number of files copied.
+pub fn reconstruct_legacy_assets(
+    game_dir: &Path,
+    assets_dir: &Path,
+    index_id: &str,
+    index: &AssetIndexJson,
+) -> Result<usize, String> {
+    let layout = detect_layout(index_id, index);
+    if layout == AssetLayout::Objects {
+        return Ok(0);
+    }
+
+    let target_dir = game_assets_dir(game_dir, assets_dir, index_id, layout);
+    let objects_dir = assets_dir.join("objects");
+    let mut copied = 0;
+
+    for (name, object) in &index.objects {
+        if object.hash.len() < 2 {
+            continue;
+        }
+        let source = objects_dir.join(&object.hash[0..2]).join(&object.hash);
+        let target = target_dir.join(name);
+
+        // Asset names come from a downloaded file; never write outside the target dir
+        if !target.starts_with(&target_dir)
+            || Path::new(name)
+                .components()
+                .any(|c| matches!(c, std::path::Component::ParentDir))
+        {
+            continue;
+        }
+
+        if let Ok(meta) = fs::metadata(&target) {
+            if meta.len() == object.size {
+                continue;
+            }
+        }
+
+        if !source.exists() {
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::copy(&source, &target)
+            .map_err(|e| format!("Failed to copy asset {}: {}", name, e))?;
+        copied += 1;
+    }
+
+    Ok(copied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(map_to_resources: bool, is_virtual: bool) -> AssetIndexJson {
+        AssetIndexJson {
+            objects: HashMap::new(),
+            map_to_resources,
+            is_virtual,
+        }
+    }
+
+    #[test]
+    fn test_detect_layout() {
+        assert_eq!(detect_layout("1.20", &index(false, false)), AssetLayout::Objects);
+        assert_eq!(detect_layout("legacy", &index(false, false)), AssetLayout::Virtual);
+        assert_eq!(detect_layout("custom", &index(false, true)), AssetLayout::Virtual);
+        assert_eq!(detect_layout("pre-1.6", &index(false, false)), AssetLayout::Resources);
+        assert_eq!(detect_layout("custom", &index(true, true)), AssetLayout::Resources);
+    }
+
+    #[test]
+    fn test_parse_legacy_index() {
+        let json = r#"{"virtual": true, "objects": {"sounds/a.ogg": {"hash": "abcd", "size": 4}}}"#;
+        let parsed: AssetIndexJson = serde_json::from_str(json).unwrap();
+        assert!(parsed.is_virtual);
+        assert!(!parsed.map_to_resources);
+        assert_eq!(parsed.objects["sounds/a.ogg"].size, 4);
+    }
+}
@@ -1,14 +1,80 @@
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
-// Helper to create a client with a custom User-Agent
-// This is critical because Microsoft's WAF often blocks requests without a valid UA
-fn get_client() -> reqwest::Client {
-    reqwest::Client::builder()
-        .user_agent("DropOut/1.0 (Linux)")
-        .build()
-        .unwrap_or_else(|_| get_client())
+use crate::core::http;
+
+/// Why a step of the Microsoft login failed. Timeouts are kept apart so the
+/// UI can tell a slow or unreachable server from a rejected login.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthError {
+    /// A login server did not answer within the configured timeout
+    Timeout(String),
+    /// XSTS refused the Xbox token
+    Xsts(XstsError),
+    /// The account has no Java Edition profile yet, as is the case for new
+    /// Game Pass accounts
+    NoProfile,
+    Failed(String),
+}
+
+/// Where an account without a profile can create one
+pub const CREATE_PROFILE_URL: &str = "https://www.minecraft.net/msaprofile/mygames/editprofile";
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Timeout(host) => write!(f, "{} did not respond in time", host),
+            Self::Xsts(e) => e.fmt(f),
+            Self::NoProfile => write!(
+                f,
+                "This account has no Minecraft profile yet. Create one at minecraft.net"
+            ),
+            Self::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+impl From<reqwest::Error> for AuthError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            let host = e
+                .url()
+                .and_then(|url| url.host_str())
+                .unwrap_or("The server");
+            Self::Timeout(host.to_string())
+        } else {
+            Self::Failed(e.to_string())
+        }
+    }
+}
+
+impl From<XstsError> for AuthError {
+    fn from(e: XstsError) -> Self {
+        Self::Xsts(e)
+    }
+}
+
+impl From<String> for AuthError {
+    fn from(message: String) -> Self {
+        Self::Failed(message)
+    }
+}
+
+impl From<&str> for AuthError {
+    fn from(message: &str) -> Self {
+        Self::Failed(message.to_string())
+    }
+}
+
+impl From<AuthError> for String {
+    fn from(e: AuthError) -> Self {
+        e.to_string()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,26 +124,96 @@ pub struct MicrosoftAccount {
 
 pub struct AccountState {
     pub active_account: Mutex<Option<Account>>,
+    /// A Microsoft login waiting for its Minecraft profile to be created
+    pub pending_profile: Mutex<Option<PendingProfile>>,
 }
 
 impl AccountState {
     pub fn new() -> Self {
         Self {
             active_account: Mutex::new(None),
+            pending_profile: Mutex::new(None),
         }
     }
 }
 
+/// Tokens of a login whose account has no Minecraft profile yet, kept until
+/// `create_profile` gives it one.
+#[derive(Debug, Clone)]
+pub struct PendingProfile {
+    pub mc_access_token: String,
+    pub ms_refresh_token: Option<String>,
+    pub expires_at: i64,
+}
+
+impl PendingProfile {
+    /// The account once `profile` exists.
+    pub fn into_account(self, profile: MinecraftProfile) -> (Account, Option<String>) {
+        let account = Account::Microsoft(MicrosoftAccount {
+            username: profile.name,
+            uuid: profile.id,
+            access_token: self.mc_access_token,
+            refresh_token: self.ms_refresh_token.clone(),
+            expires_at: self.expires_at,
+        });
+        (account, self.ms_refresh_token)
+    }
+}
+
 pub fn generate_offline_uuid(username: &str) -> String {
     let namespace = Uuid::NAMESPACE_OID;
     Uuid::new_v3(&namespace, username.as_bytes()).to_string()
 }
 
+/// Check a name against Minecraft's rules: 3 to 16 letters, digits or
+/// underscores. Servers kick players with other names.
+pub fn validate_username(username: &str) -> Result<(), String> {
+    if !(3..=16).contains(&username.len()) {
+        return Err("Username must be 3 to 16 characters long".to_string());
+    }
+    if !username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return Err("Username may only contain letters, digits and underscores".to_string());
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct NameLookup {
+    id: String,
+    name: String,
+}
+
+/// Name and dashed UUID of the Minecraft account called `username`, or
+/// `None` if there is no such account.
+pub async fn lookup_online_profile(username: &str) -> Result<Option<(String, String)>, String> {
+    let url = format!(
+        "https://api.mojang.com/users/profiles/minecraft/{}",
+        username
+    );
+    let resp = crate::core::http::get(&url)
+        .await
+        .map_err(|e| format!("Failed to look up {}: {}", username, e))?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND
+        || resp.status() == reqwest::StatusCode::NO_CONTENT
+    {
+        return Ok(None);
+    }
+    if !resp.status().is_success() {
+        return Err(format!("Failed to look up {}: {}", username, resp.status()));
+    }
+    let lookup: NameLookup = resp.json().await.map_err(|e| e.to_string())?;
+    let uuid = Uuid::parse_str(&lookup.id).map_err(|e| format!("Invalid UUID: {}", e))?;
+    Ok(Some((lookup.name, uuid.hyphenated().to_string())))
+}
+
 // const CLIENT_ID: &str = "fe165602-5410-4441-92f7-326e10a7cb82";
 const CLIENT_ID: &str = "c36a9fb6-4f2a-41ff-90bd-ae7cc92031eb"; // ATLauncher's Client ID
 const SCOPE: &str = "XboxLive.SignIn XboxLive.offline_access";
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeviceCodeResponse {
     pub user_code: String,
     pub device_code: String,
@@ -101,8 +237,7 @@ pub struct TokenError {
 }
 
 /// Refresh Microsoft OAuth token using refresh_token
-pub async fn refresh_microsoft_token(refresh_token: &str) -> Result<TokenResponse, String> {
-    let client = get_client();
+pub async fn refresh_microsoft_token(refresh_token: &str) -> Result<TokenResponse, AuthError> {
     let url = "https://login.microsoftonline.com/consumers/oauth2/v2.0/token";
 
     let params = [
@@ -112,15 +247,15 @@ pub async fn refresh_microsoft_token(refresh_token: &str) -> Result<TokenRespons
         ("scope", SCOPE),
     ];
 
-    let resp = client
-        .post(url)
-        .header("Content-Type", "application/x-www-form-urlencoded")
-        .body(serde_urlencoded::to_string(params).map_err(|e| e.to_string())?)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+    let resp = http::send(
+        http::client()
+            .post(url)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(serde_urlencoded::to_string(params).map_err(|e| e.to_string())?),
+    )
+    .await?;
 
-    let text = resp.text().await.map_err(|e| e.to_string())?;
+    let text = resp.text().await?;
 
     if let Ok(token_resp) = serde_json::from_str::<TokenResponse>(&text) {
         println!("[Auth] Token refreshed successfully!");
@@ -129,10 +264,10 @@ pub async fn refresh_microsoft_token(refresh_token: &str) -> Result<TokenRespons
 
     if let Ok(err_resp) = serde_json::from_str::<TokenError>(&text) {
         println!("[Auth] Token refresh error: {}", err_resp.error);
-        return Err(format!("Token refresh failed: {}", err_resp.error));
+        return Err(format!("Token refresh failed: {}", err_resp.error).into());
     }
 
-    Err(format!("Unknown refresh response: {}", text))
+    Err(format!("Unknown refresh response: {}", text).into())
 }
 
 /// Check if a Microsoft account token is expired or about to expire
@@ -149,7 +284,7 @@ pub fn is_token_expired(expires_at: i64) -> bool {
 /// Full refresh flow: refresh MS token -> Xbox -> XSTS -> Minecraft
 pub async fn refresh_full_auth(
     ms_refresh_token: &str,
-) -> Result<(MicrosoftAccount, String), String> {
+) -> Result<(MicrosoftAccount, String), AuthError> {
     println!("[Auth] Starting full token refresh...");
 
     // 1. Refresh Microsoft token
@@ -216,27 +351,23 @@ pub struct MinecraftProfile {
 }
 
 // 1. Start Device Flow
-pub async fn start_device_flow() -> Result<DeviceCodeResponse, String> {
-    let client = get_client();
+pub async fn start_device_flow() -> Result<DeviceCodeResponse, AuthError> {
     let url = "https://login.microsoftonline.com/consumers/oauth2/v2.0/devicecode";
 
     let params = [("client_id", CLIENT_ID), ("scope", SCOPE)];
 
-    let resp = client
-        .post(url)
-        .header("Content-Type", "application/x-www-form-urlencoded")
-        .body(serde_urlencoded::to_string(params).map_err(|e| e.to_string())?)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+    let resp = http::send(
+        http::client()
+            .post(url)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(serde_urlencoded::to_string(params).map_err(|e| e.to_string())?),
+    )
+    .await?;
 
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_else(|_| "No body".to_string());
-        return Err(format!(
-            "Device code request failed: {} - Body: {}",
-            status, text
-        ));
+        return Err(format!("Device code request failed: {} - Body: {}", status, text).into());
     }
 
     let body = resp
@@ -248,8 +379,7 @@ pub async fn start_device_flow() -> Result<DeviceCodeResponse, String> {
 
 // 2. Poll for Token (Simplified: User calls this repeatedly or we loop inside a command)
 // We'll implement a function that tries ONCE, consuming the device_code.
-pub async fn exchange_code_for_token(device_code: &str) -> Result<TokenResponse, String> {
-    let client = get_client();
+pub async fn exchange_code_for_token(device_code: &str) -> Result<TokenResponse, AuthError> {
     let url = "https://login.microsoftonline.com/consumers/oauth2/v2.0/token";
 
     let params = [
@@ -258,16 +388,16 @@ pub async fn exchange_code_for_token(device_code: &str) -> Result<TokenResponse,
         ("device_code", device_code),
     ];
 
-    let resp = client
-        .post(url)
-        .header("Content-Type", "application/x-www-form-urlencoded")
-        .body(serde_urlencoded::to_string(params).map_err(|e| e.to_string())?)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+    let resp = http::send(
+        http::client()
+            .post(url)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(serde_urlencoded::to_string(params).map_err(|e| e.to_string())?),
+    )
+    .await?;
 
     // Check application level error (e.g. "authorization_pending")
-    let text = resp.text().await.map_err(|e| e.to_string())?;
+    let text = resp.text().await?;
 
     // Try parse success
     if let Ok(token_resp) = serde_json::from_str::<TokenResponse>(&text) {
@@ -280,17 +410,88 @@ pub async fn exchange_code_for_token(device_code: &str) -> Result<TokenResponse,
         if err_resp.error != "authorization_pending" {
             println!("[Auth] Polling error: {}", err_resp.error);
         }
-        return Err(err_resp.error); // "authorization_pending", "expired_token", "access_denied"
+        return Err(err_resp.error.into()); // "authorization_pending", "expired_token", "access_denied"
     }
 
     println!("[Auth] Unknown response body: {}", text);
-    Err(format!("Unknown response: {}", text))
+    Err(format!("Unknown response: {}", text).into())
+}
+
+/// Bumped by every new device login and by `cancel_device_login`; a poll
+/// loop stops once it no longer matches the value it started with.
+static LOGIN_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Start a new device login, superseding any running one. Returns the
+/// generation to pass to `poll_device_token`.
+pub fn begin_device_login() -> u64 {
+    LOGIN_GENERATION.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+/// Stop the running device login, if any.
+pub fn cancel_device_login() {
+    LOGIN_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Poll the token endpoint at the interval the server asked for until the
+/// user signs in or the code expires. `on_pending` gets the seconds left
+/// after each unanswered poll. Returns `None` if the login was cancelled.
+pub async fn poll_device_token(
+    device: &DeviceCodeResponse,
+    generation: u64,
+    mut on_pending: impl FnMut(u64),
+) -> Result<Option<TokenResponse>, AuthError> {
+    let cancelled = || LOGIN_GENERATION.load(Ordering::SeqCst) != generation;
+    let mut interval = Duration::from_secs(device.interval.max(1));
+    let deadline = Instant::now() + Duration::from_secs(device.expires_in);
+    loop {
+        tokio::time::sleep(interval).await;
+        if cancelled() {
+            return Ok(None);
+        }
+        if Instant::now() >= deadline {
+            return Err("expired_token".into());
+        }
+        match exchange_code_for_token(&device.device_code).await {
+            Ok(_) if cancelled() => return Ok(None),
+            Ok(token) => return Ok(Some(token)),
+            Err(AuthError::Failed(e)) if e == "authorization_pending" => {
+                on_pending(deadline.saturating_duration_since(Instant::now()).as_secs());
+            }
+            // RFC 8628: back off by five seconds
+            Err(AuthError::Failed(e)) if e == "slow_down" => interval += Duration::from_secs(5),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Sent after each poll the user has not yet answered.
+#[derive(Debug, Clone, Serialize)]
+pub struct MsLoginPending {
+    pub seconds_remaining: u64,
+}
+
+impl crate::core::events::LauncherEvent for MsLoginPending {
+    const NAME: &'static str = "ms-login-pending";
+}
+
+/// End of a device login: the new account and whether it was already
+/// stored, or why it failed. Neither is set if the account needs a profile
+/// first, see `create_profile`.
+#[derive(Debug, Serialize)]
+pub struct MsLoginComplete {
+    pub account: Option<Account>,
+    pub change: Option<crate::core::account_storage::AccountChange>,
+    pub needs_profile: bool,
+    pub error: Option<crate::core::error::LauncherError>,
+}
+
+impl crate::core::events::LauncherEvent for MsLoginComplete {
+    const NAME: &'static str = "ms-login-complete";
 }
 
 // 3. Authenticate with Xbox Live
-pub async fn method_xbox_live(ms_access_token: &str) -> Result<(String, String), String> {
+pub async fn method_xbox_live(ms_access_token: &str) -> Result<(String, String), AuthError> {
     println!("[Auth] Starting Xbox Live auth...");
-    let client = get_client();
     let url = "https://user.auth.xboxlive.com/user/authenticate";
 
     let payload = serde_json::json!({
@@ -303,23 +504,23 @@ pub async fn method_xbox_live(ms_access_token: &str) -> Result<(String, String),
         "TokenType": "JWT"
     });
 
-    let resp = client
-        .post(url)
-        .json(&payload)
-        .header("Content-Type", "application/json")
-        .header("Accept", "application/json")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+    let resp = http::send(
+        http::client()
+            .post(url)
+            .json(&payload)
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json"),
+    )
+    .await?;
 
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
         println!("[Auth] Xbox Live auth failed: {} - {}", status, text);
-        return Err(format!("Xbox Live auth failed: {} - {}", status, text));
+        return Err(format!("Xbox Live auth failed: {} - {}", status, text).into());
     }
 
-    let xbl_resp: XboxLiveResponse = resp.json().await.map_err(|e| e.to_string())?;
+    let xbl_resp: XboxLiveResponse = resp.json().await?;
     println!("[Auth] Xbox Live auth success!");
 
     // Extract UHS (User Hash)
@@ -335,10 +536,93 @@ pub async fn method_xbox_live(ms_access_token: &str) -> Result<(String, String),
     Ok((xbl_resp.token, uhs))
 }
 
+/// Why XSTS refused a token. The known `XErr` codes are problems with the
+/// Microsoft account that the user has to fix on Xbox or Microsoft sites.
+#[derive(Debug, Clone, PartialEq)]
+pub enum XstsError {
+    /// 2148916227
+    Banned,
+    /// 2148916233: the account has never signed in to Xbox
+    NoXboxProfile,
+    /// 2148916234
+    TermsNotAccepted,
+    /// 2148916235
+    RegionUnavailable,
+    /// 2148916236 and 2148916237, required in South Korea
+    AdultVerificationRequired,
+    /// 2148916238: child accounts must be added to a family by an adult
+    Underage,
+    Failed(String),
+}
+
+impl XstsError {
+    /// Parse an error response, using its `XErr` code when it is a known one.
+    pub fn from_response(status: reqwest::StatusCode, body: &str) -> Self {
+        let code = serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .and_then(|v| v["XErr"].as_u64());
+        match code {
+            Some(2148916227) => Self::Banned,
+            Some(2148916233) => Self::NoXboxProfile,
+            Some(2148916234) => Self::TermsNotAccepted,
+            Some(2148916235) => Self::RegionUnavailable,
+            Some(2148916236) | Some(2148916237) => Self::AdultVerificationRequired,
+            Some(2148916238) => Self::Underage,
+            _ => Self::Failed(format!("XSTS auth failed: {} - {}", status, body)),
+        }
+    }
+
+    /// Page where the user can resolve the problem.
+    pub fn help_url(&self) -> Option<&'static str> {
+        match self {
+            Self::Banned => Some("https://enforcement.xbox.com/"),
+            Self::NoXboxProfile | Self::TermsNotAccepted => Some("https://www.xbox.com/"),
+            Self::AdultVerificationRequired => Some("https://account.xbox.com/"),
+            Self::Underage => Some("https://account.microsoft.com/family/"),
+            Self::RegionUnavailable | Self::Failed(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for XstsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Banned => write!(f, "This account is banned from Xbox Live"),
+            Self::NoXboxProfile => write!(
+                f,
+                "This Microsoft account has no Xbox profile. Sign in at xbox.com once to create one"
+            ),
+            Self::TermsNotAccepted => write!(
+                f,
+                "The Xbox terms of use have not been accepted. Sign in at xbox.com to accept them"
+            ),
+            Self::RegionUnavailable => {
+                write!(f, "Xbox Live is not available in this account's country")
+            }
+            Self::AdultVerificationRequired => write!(
+                f,
+                "This account needs adult verification on the Xbox website before it can play"
+            ),
+            Self::Underage => write!(
+                f,
+                "This is a child account. An adult must add it to a Microsoft family first"
+            ),
+            Self::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for XstsError {}
+
+impl From<XstsError> for String {
+    fn from(e: XstsError) -> Self {
+        e.to_string()
+    }
+}
+
 // 4. Authenticate with XSTS
-pub async fn method_xsts(xbl_token: &str) -> Result<String, String> {
+pub async fn method_xsts(xbl_token: &str) -> Result<String, AuthError> {
     println!("[Auth] Starting XSTS auth...");
-    let client = get_client();
     let url = "https://xsts.auth.xboxlive.com/xsts/authorize";
 
     let payload = serde_json::json!({
@@ -350,31 +634,24 @@ pub async fn method_xsts(xbl_token: &str) -> Result<String, String> {
         "TokenType": "JWT"
     });
 
-    let resp = client
-        .post(url)
-        .json(&payload)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+    let resp = http::send(http::client().post(url).json(&payload)).await?;
 
     if !resp.status().is_success() {
-        // Should handle specific errors like "Account not verified", "Age restriction"
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
         println!("[Auth] XSTS auth failed: {} - {}", status, text);
-        return Err(format!("XSTS auth failed: {} - {}", status, text));
+        return Err(XstsError::from_response(status, &text).into());
     }
 
-    let xsts_resp: XboxLiveResponse = resp.json().await.map_err(|e| e.to_string())?;
+    let xsts_resp: XboxLiveResponse = resp.json().await?;
     println!("[Auth] XSTS auth success!");
     Ok(xsts_resp.token)
 }
 
 // 5. Authenticate with Minecraft
 // Using the newer /launcher/login endpoint which is what modern launchers use
-pub async fn login_minecraft(xsts_token: &str, uhs: &str) -> Result<String, String> {
+pub async fn login_minecraft(xsts_token: &str, uhs: &str) -> Result<String, AuthError> {
     println!("[Auth] Starting Minecraft auth...");
-    let client = get_client();
     let url = "https://api.minecraftservices.com/launcher/login";
 
     let payload = serde_json::json!({
@@ -382,49 +659,104 @@ pub async fn login_minecraft(xsts_token: &str, uhs: &str) -> Result<String, Stri
         "platform": "PC_LAUNCHER"
     });
 
-    let resp = client
-        .post(url)
-        .json(&payload)
-        .header("Content-Type", "application/json")
-        .header("Accept", "application/json")
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+    let resp = http::send(
+        http::client()
+            .post(url)
+            .json(&payload)
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json"),
+    )
+    .await?;
 
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_else(|_| "No body".to_string());
         println!("[Auth] Minecraft auth failed: {} - {}", status, text);
-        return Err(format!(
-            "Minecraft auth failed: {} - Body: {}",
-            status, text
-        ));
+        return Err(format!("Minecraft auth failed: {} - Body: {}", status, text).into());
     }
 
-    let mc_resp: MinecraftAuthResponse = resp.json().await.map_err(|e| e.to_string())?;
+    let mc_resp: MinecraftAuthResponse = resp.json().await?;
     println!("[Auth] Minecraft auth success!");
     Ok(mc_resp.access_token)
 }
 
 // 6. Get Profile
-pub async fn fetch_profile(mc_access_token: &str) -> Result<MinecraftProfile, String> {
-    let client = get_client();
+pub async fn fetch_profile(mc_access_token: &str) -> Result<MinecraftProfile, AuthError> {
     let url = "https://api.minecraftservices.com/minecraft/profile";
 
-    let resp = client
-        .get(url)
-        .bearer_auth(mc_access_token)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+    let resp = http::send(http::client().get(url).bearer_auth(mc_access_token)).await?;
+
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(AuthError::NoProfile);
+    }
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        return Err(format!("Profile fetch failed: {} - {}", status, text).into());
+    }
+
+    let profile: MinecraftProfile = resp.json().await?;
+    Ok(profile)
+}
+
+/// Whether a name can be given to a new profile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum NameAvailability {
+    Available,
+    Duplicate,
+    NotAllowed,
+}
+
+#[derive(Debug, Deserialize)]
+struct NameAvailabilityResponse {
+    status: NameAvailability,
+}
+
+pub async fn check_name_availability(
+    mc_access_token: &str,
+    name: &str,
+) -> Result<NameAvailability, AuthError> {
+    let url = format!(
+        "https://api.minecraftservices.com/minecraft/profile/name/{}/available",
+        name
+    );
+    let resp = http::send(http::client().get(&url).bearer_auth(mc_access_token)).await?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        return Err(format!("Name check failed: {} - {}", status, text).into());
+    }
+
+    let body: NameAvailabilityResponse = resp.json().await?;
+    Ok(body.status)
+}
+
+/// Create the Java Edition profile of an account that has none, e.g. a new
+/// Game Pass account.
+pub async fn create_profile(
+    mc_access_token: &str,
+    name: &str,
+) -> Result<MinecraftProfile, AuthError> {
+    let url = "https://api.minecraftservices.com/minecraft/profile";
+    let body = serde_json::json!({ "profileName": name });
+
+    let resp = http::send(
+        http::client()
+            .post(url)
+            .bearer_auth(mc_access_token)
+            .json(&body),
+    )
+    .await?;
 
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
-        return Err(format!("Profile fetch failed: {} - {}", status, text));
+        return Err(format!("Profile creation failed: {} - {}", status, text).into());
     }
 
-    let profile: MinecraftProfile = resp.json().await.map_err(|e| e.to_string())?;
+    let profile: MinecraftProfile = resp.json().await?;
     Ok(profile)
 }
 
@@ -441,24 +773,49 @@ pub struct EntitlementsResponse {
     pub keyId: Option<String>,
 }
 
-pub async fn check_ownership(mc_access_token: &str) -> Result<bool, String> {
-    let client = get_client();
+/// Whether a Microsoft account can play Java Edition, and what is missing
+/// if it cannot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AccountEntitlements {
+    pub owns_game: bool,
+    pub has_profile: bool,
+    /// Set when the game is owned but the profile still has to be created
+    pub create_profile_url: Option<&'static str>,
+}
+
+impl AccountEntitlements {
+    fn new(owns_game: bool, has_profile: bool) -> Self {
+        Self {
+            owns_game,
+            has_profile,
+            create_profile_url: (owns_game && !has_profile).then_some(CREATE_PROFILE_URL),
+        }
+    }
+}
+
+/// Ownership and profile status of the account `mc_access_token` belongs to.
+pub async fn check_entitlements(mc_access_token: &str) -> Result<AccountEntitlements, AuthError> {
+    let owns_game = check_ownership(mc_access_token).await?;
+    let has_profile = match fetch_profile(mc_access_token).await {
+        Ok(_) => true,
+        Err(AuthError::NoProfile) => false,
+        Err(e) => return Err(e),
+    };
+    Ok(AccountEntitlements::new(owns_game, has_profile))
+}
+
+pub async fn check_ownership(mc_access_token: &str) -> Result<bool, AuthError> {
     let url = "https://api.minecraftservices.com/entitlements/mcstore";
 
-    let resp = client
-        .get(url)
-        .bearer_auth(mc_access_token)
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
+    let resp = http::send(http::client().get(url).bearer_auth(mc_access_token)).await?;
 
     if !resp.status().is_success() {
         let status = resp.status();
         let text = resp.text().await.unwrap_or_default();
-        return Err(format!("Entitlement check failed: {} - {}", status, text));
+        return Err(format!("Entitlement check failed: {} - {}", status, text).into());
     }
 
-    let body: EntitlementsResponse = resp.json().await.map_err(|e| e.to_string())?;
+    let body: EntitlementsResponse = resp.json().await?;
     // We look for "product_minecraft" or "game_minecraft"
     let owns_game = body
         .items
@@ -466,3 +823,39 @@ pub async fn check_ownership(mc_access_token: &str) -> Result<bool, String> {
         .any(|e| e.name == "product_minecraft" || e.name == "game_minecraft");
     Ok(owns_game)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xsts_error_codes() {
+        let status = reqwest::StatusCode::UNAUTHORIZED;
+        let body = r#"{"Identity":"0","XErr":2148916238,"Message":""}"#;
+        let error = XstsError::from_response(status, body);
+        assert_eq!(error, XstsError::Underage);
+        assert!(error.help_url().is_some());
+        assert!(matches!(
+            XstsError::from_response(status, "not json"),
+            XstsError::Failed(_)
+        ));
+    }
+
+    #[test]
+    fn test_entitlements_profile_hint() {
+        let game_pass = AccountEntitlements::new(true, false);
+        assert_eq!(game_pass.create_profile_url, Some(CREATE_PROFILE_URL));
+        assert_eq!(AccountEntitlements::new(true, true).create_profile_url, None);
+        assert_eq!(AccountEntitlements::new(false, false).create_profile_url, None);
+    }
+
+    #[test]
+    fn test_validate_username() {
+        assert!(validate_username("Steve").is_ok());
+        assert!(validate_username("a_b_C_123456789z").is_ok());
+        assert!(validate_username("ab").is_err());
+        assert!(validate_username("a_b_C_123456789zz").is_err());
+        assert!(validate_username("no spaces").is_err());
+        assert!(validate_username("Stéve").is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! Player heads for the account switcher.
+//!
+//! The face and hat layer of a player's skin are composited into a PNG and
+//! kept under `cache/avatars/<uuid>.png`, so heads still show offline. A
+//! cached head is refreshed once it is older than `AVATAR_TTL`; if that
+//! fails, the old one is used.
+
+use base64::Engine;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Edge length of rendered heads
+pub const AVATAR_SIZE: u32 = 64;
+const AVATAR_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const PROFILE_URL: &str = "https://sessionserver.mojang.com/session/minecraft/profile/";
+
+#[derive(Debug, Deserialize)]
+struct Profile {
+    #[serde(default)]
+    properties: Vec<ProfileProperty>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProfileProperty {
+    name: String,
+    /// Base64 encoded JSON
+    value: String,
+}
+
+/// Skin URL in a profile's `textures` property.
+fn skin_url(profile: &Profile) -> Option<String> {
+    let textures = profile.properties.iter().find(|p| p.name == "textures")?;
+    let json = base64::engine::general_purpose::STANDARD
+        .decode(&textures.value)
+        .ok()?;
+    let value: serde_json::Value = serde_json::from_slice(&json).ok()?;
+    value["textures"]["SKIN"]["url"]
+        .as_str()
+        .map(|url| url.replacen("http://", "https://", 1))
+}
+
+/// The face with the hat layer drawn over it, scaled to `AVATAR_SIZE`.
+/// Works for both 64x64 and legacy 64x32 skins.
+pub fn render_head(skin: &image::DynamicImage) -> Result<image::RgbaImage, String> {
+    if skin.width() < 64 || skin.height() < 16 {
+        return Err(format!(
+            "Unexpected skin size {}x{}",
+            skin.width(),
+            skin.height()
+        ));
+    }
+    let mut head = skin.crop_imm(8, 8, 8, 8).to_rgba8();
+    let hat = skin.crop_imm(40, 8, 8, 8).to_rgba8();
+    image::imageops::overlay(&mut head, &hat, 0, 0);
+    Ok(image::imageops::resize(
+        &head,
+        AVATAR_SIZE,
+        AVATAR_SIZE,
+        image::imageops::FilterType::Nearest,
+    ))
+}
+
+/// Download `uuid`'s skin and render its head. `None` if the player has no
+/// skin, as with offline accounts.
+async fn fetch_head(uuid: &str) -> Result<Option<image::RgbaImage>, String> {
+    let resp = crate::core::http::get(&format!("{}{}", PROFILE_URL, uuid))
+        .await
+        .map_err(|e| format!("Failed to fetch profile: {}", e))?;
+    // The session server answers 204 for unknown players
+    if resp.status() == reqwest::StatusCode::NO_CONTENT
+        || resp.status() == reqwest::StatusCode::NOT_FOUND
+    {
+        return Ok(None);
+    }
+    if !resp.status().is_success() {
+        return Err(format!("Failed to fetch profile: {}", resp.status()));
+    }
+    let profile: Profile = resp.json().await.map_err(|e| e.to_string())?;
+    let Some(url) = skin_url(&profile) else {
+        return Ok(None);
+    };
+
+    let bytes = crate::core::http::get(&url)
+        .await
+        .map_err(|e| format!("Failed to download skin: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to download skin: {}", e))?
+        .bytes()
+        .await
+        .map_err(|e| e.to_string())?;
+    let skin = image::load_from_memory(&bytes).map_err(|e| format!("Invalid skin: {}", e))?;
+    render_head(&skin).map(Some)
+}
+
+/// `uuid` without dashes and the file its head is cached in.
+fn cache_path(cache_dir: &Path, uuid: &str) -> Result<(String, PathBuf), String> {
+    let simple = uuid.replace('-', "");
+    if simple.len() != 32 || !simple.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid UUID: {}", uuid));
+    }
+    let path = cache_dir.join(format!("{}.png", simple.to_ascii_lowercase()));
+    Ok((simple, path))
+}
+
+/// Drop the cached head of `uuid`, e.g. after its skin or name changed.
+pub fn invalidate(cache_dir: &Path, uuid: &str) {
+    if let Ok((_, path)) = cache_path(cache_dir, uuid) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Head of `uuid` as a `data:image/png;base64,...` URL, cached in
+/// `cache_dir`. `None` if the player has no skin.
+pub async fn get_avatar(cache_dir: &Path, uuid: &str) -> Result<Option<String>, String> {
+    let (simple, path) = cache_path(cache_dir, uuid)?;
+
+    let fresh = std::fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age < AVATAR_TTL);
+    if !fresh {
+        match fetch_head(&simple).await {
+            Ok(Some(head)) => {
+                std::fs::create_dir_all(cache_dir).map_err(|e| e.to_string())?;
+                head.save_with_format(&path, image::ImageFormat::Png)
+                    .map_err(|e| format!("Failed to save avatar: {}", e))?;
+            }
+            Ok(None) => {
+                let _ = std::fs::remove_file(&path);
+                return Ok(None);
+            }
+            // Offline: fall back to the cached head, if any
+            Err(e) if !path.exists() => return Err(e),
+            Err(_) => {}
+        }
+    }
+
+    let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read avatar: {}", e))?;
+    Ok(Some(format!(
+        "data:image/png;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skin_url() {
+        let textures =
+            r#"{"textures":{"SKIN":{"url":"http://textures.minecraft.net/texture/abc"}}}"#;
+        let profile = Profile {
+            properties: vec![ProfileProperty {
+                name: "textures".to_string(),
+                value: base64::engine::general_purpose::STANDARD.encode(textures),
+            }],
+        };
+        assert_eq!(
+            skin_url(&profile).as_deref(),
+            Some("https://textures.minecraft.net/texture/abc")
+        );
+        assert_eq!(skin_url(&Profile { properties: vec![] }), None);
+    }
+
+    #[test]
+    fn test_render_head_draws_hat_over_face() {
+        let mut skin = image::RgbaImage::new(64, 32);
+        for y in 8..16 {
+            for x in 8..16 {
+                skin.put_pixel(x, y, image::Rgba([255, 0, 0, 255]));
+            }
+        }
+        // Hat covers only the face's top-left pixel
+        skin.put_pixel(40, 8, image::Rgba([0, 0, 255, 255]));
+
+        let head = render_head(&image::DynamicImage::ImageRgba8(skin)).unwrap();
+        assert_eq!(head.dimensions(), (AVATAR_SIZE, AVATAR_SIZE));
+        assert_eq!(head.get_pixel(0, 0), &image::Rgba([0, 0, 255, 255]));
+        assert_eq!(
+            head.get_pixel(AVATAR_SIZE - 1, AVATAR_SIZE - 1),
+            &image::Rgba([255, 0, 0, 255])
+        );
+    }
+}
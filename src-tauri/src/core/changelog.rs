@@ -0,0 +1,243 @@
+//! Per-version changelogs.
+//!
+//! Resolves a version id to its Minecraft version and mod loader, then
+//! collects Mojang's patch note plus the loader's own changelog. HTML is
+//! sanitized here; Markdown and plain text are returned as-is and must be
+//! rendered without raw HTML by the UI.
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::http_cache;
+use crate::core::news;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangelogFormat {
+    Html,
+    Markdown,
+    Text,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangelogSection {
+    /// "minecraft", "fabric", "quilt", "forge" or "neoforge"
+    pub source: String,
+    pub title: String,
+    pub format: ChangelogFormat,
+    pub content: String,
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionChangelog {
+    pub version_id: String,
+    pub minecraft_version: Option<String>,
+    pub sections: Vec<ChangelogSection>,
+}
+
+/// A version id split into its Minecraft version and loader.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParsedVersionId {
+    pub minecraft_version: Option<String>,
+    /// (loader, loader version)
+    pub loader: Option<(&'static str, String)>,
+}
+
+/// Split ids produced by the installers (`fabric-loader-<l>-<mc>`,
+/// `quilt-loader-<l>-<mc>`, `<mc>-forge-<l>`, `neoforge-<l>`).
+pub fn parse_version_id(version_id: &str) -> ParsedVersionId {
+    for (prefix, loader) in [("fabric-loader-", "fabric"), ("quilt-loader-", "quilt")] {
+        if let Some(rest) = version_id.strip_prefix(prefix) {
+            if let Some((loader_version, mc)) = rest.split_once('-') {
+                return ParsedVersionId {
+                    minecraft_version: Some(mc.to_string()),
+                    loader: Some((loader, loader_version.to_string())),
+                };
+            }
+        }
+    }
+    if let Some((mc, forge)) = version_id.split_once("-forge-") {
+        return ParsedVersionId {
+            minecraft_version: Some(mc.to_string()),
+            loader: Some(("forge", forge.to_string())),
+        };
+    }
+    if let Some(neoforge) = version_id.strip_prefix("neoforge-") {
+        return ParsedVersionId {
+            minecraft_version: neoforge_minecraft_version(neoforge),
+            loader: Some(("neoforge", neoforge.to_string())),
+        };
+    }
+    ParsedVersionId {
+        minecraft_version: Some(version_id.to_string()),
+        loader: None,
+    }
+}
+
+/// NeoForge versions encode the Minecraft version: `20.4.80` is for `1.20.4`.
+fn neoforge_minecraft_version(neoforge: &str) -> Option<String> {
+    let mut parts = neoforge.split('.');
+    let major = parts.next()?.parse::<u32>().ok()?;
+    let minor = parts.next()?.parse::<u32>().ok()?;
+    Some(if minor == 0 {
+        format!("1.{}", major)
+    } else {
+        format!("1.{}.{}", major, minor)
+    })
+}
+
+#[derive(Deserialize)]
+struct PatchNoteContent {
+    #[serde(default)]
+    body: String,
+}
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    #[serde(default)]
+    body: Option<String>,
+    html_url: String,
+}
+
+/// Collect changelogs for `version_id`. Sources that are unavailable are skipped.
+pub async fn fetch_changelog(version_id: &str) -> Result<VersionChangelog, String> {
+    let parsed = parse_version_id(version_id);
+    let mut sections = Vec::new();
+
+    if let Some(mc) = &parsed.minecraft_version {
+        match minecraft_section(mc).await {
+            Ok(Some(section)) => sections.push(section),
+            Ok(None) => {}
+            Err(e) => println!("[Changelog] Patch notes for {} unavailable: {}", mc, e),
+        }
+    }
+
+    if let Some((loader, loader_version)) = &parsed.loader {
+        let mc = parsed.minecraft_version.as_deref().unwrap_or_default();
+        match loader_section(loader, loader_version, mc).await {
+            Ok(section) => sections.push(section),
+            Err(e) => println!("[Changelog] {} changelog unavailable: {}", loader, e),
+        }
+    }
+
+    if sections.is_empty() {
+        return Err(format!("No changelog found for {}", version_id));
+    }
+
+    Ok(VersionChangelog {
+        version_id: version_id.to_string(),
+        minecraft_version: parsed.minecraft_version,
+        sections,
+    })
+}
+
+async fn minecraft_section(minecraft_version: &str) -> Result<Option<ChangelogSection>, String> {
+    let notes = news::fetch_patch_notes().await?;
+    let Some(note) = notes.into_iter().find(|n| n.version == minecraft_version) else {
+        return Ok(None);
+    };
+    let content: PatchNoteContent =
+        http_cache::get_json(&note.content_url, http_cache::TTL_DAY).await?;
+
+    Ok(Some(ChangelogSection {
+        source: "minecraft".to_string(),
+        title: note.title,
+        format: ChangelogFormat::Html,
+        content: ammonia::clean(&content.body),
+        url: None,
+    }))
+}
+
+async fn loader_section(
+    loader: &str,
+    loader_version: &str,
+    minecraft_version: &str,
+) -> Result<ChangelogSection, String> {
+    match loader {
+        "fabric" | "quilt" => {
+            let (repo, name) = if loader == "fabric" {
+                ("FabricMC/fabric-loader", "Fabric Loader")
+            } else {
+                ("QuiltMC/quilt-loader", "Quilt Loader")
+            };
+            let url = format!(
+                "https://api.github.com/repos/{}/releases/tags/{}",
+                repo, loader_version
+            );
+            let release: GithubRelease = http_cache::get_json(&url, http_cache::TTL_DAY).await?;
+            Ok(ChangelogSection {
+                source: loader.to_string(),
+                title: format!("{} {}", name, loader_version),
+                format: ChangelogFormat::Markdown,
+                content: release.body.unwrap_or_default(),
+                url: Some(release.html_url),
+            })
+        }
+        "forge" => {
+            let full = format!("{}-{}", minecraft_version, loader_version);
+            let url = format!(
+                "https://maven.minecraftforge.net/net/minecraftforge/forge/{0}/forge-{0}-changelog.txt",
+                full
+            );
+            Ok(ChangelogSection {
+                source: "forge".to_string(),
+                title: format!("Forge {}", loader_version),
+                format: ChangelogFormat::Text,
+                content: http_cache::get_text(&url, http_cache::TTL_DAY).await?,
+                url: Some(url),
+            })
+        }
+        "neoforge" => {
+            let url = format!(
+                "https://maven.neoforged.net/releases/net/neoforged/neoforge/{0}/neoforge-{0}-changelog.txt",
+                loader_version
+            );
+            Ok(ChangelogSection {
+                source: "neoforge".to_string(),
+                title: format!("NeoForge {}", loader_version),
+                format: ChangelogFormat::Text,
+                content: http_cache::get_text(&url, http_cache::TTL_DAY).await?,
+                url: Some(url),
+            })
+        }
+        other => Err(format!("Unknown loader {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version_id() {
+        assert_eq!(
+            parse_version_id("fabric-loader-0.15.7-1.20.4"),
+            ParsedVersionId {
+                minecraft_version: Some("1.20.4".to_string()),
+                loader: Some(("fabric", "0.15.7".to_string())),
+            }
+        );
+        assert_eq!(
+            parse_version_id("1.20.1-forge-47.2.0"),
+            ParsedVersionId {
+                minecraft_version: Some("1.20.1".to_string()),
+                loader: Some(("forge", "47.2.0".to_string())),
+            }
+        );
+        assert_eq!(
+            parse_version_id("neoforge-20.4.80-beta").minecraft_version,
+            Some("1.20.4".to_string())
+        );
+        assert_eq!(
+            parse_version_id("neoforge-21.0.10").minecraft_version,
+            Some("1.21".to_string())
+        );
+        assert_eq!(parse_version_id("24w03a").loader, None);
+    }
+
+    #[test]
+    fn test_sanitize_patch_note() {
+        let html = ammonia::clean("<p onclick=\"x()\">Hi</p><script>alert(1)</script>");
+        assert_eq!(html, "<p>Hi</p>");
+    }
+}
@@ -0,0 +1,664 @@
+//! Version removal, garbage collection and disk usage reporting.
+//!
+//! Libraries and asset objects are shared between versions, so deleting a
+//! version directory leaves them behind. `collect_garbage` finds files under
+//! `libraries/` and `assets/objects/` that no installed version references,
+//! mod store entries no instance uses and patched client jars no instance's
+//! jar mods produce. Libraries generated by the Forge
+//! installer count as referenced through the installer profile kept with the
+//! version.
+//! `prune_assets` only looks at assets, but also drops the asset indexes and
+//! `assets/virtual/` copies of versions that are gone.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::core::forge;
+use crate::core::game_version::{DownloadArtifact, GameVersion};
+use crate::core::library_override::{self, LibraryOverride};
+use crate::core::manifest;
+
+/// Result of a garbage collection pass.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct GarbageReport {
+    /// Unreferenced files, relative to the game directory
+    pub files: Vec<String>,
+    pub reclaimable_bytes: u64,
+    /// `false` for a dry run
+    pub deleted: bool,
+}
+
+/// Result of `prune_assets`.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct AssetPruneReport {
+    /// Asset indexes used by installed versions
+    pub kept_indexes: Vec<String>,
+    /// Asset indexes no installed version uses
+    pub removed_indexes: Vec<String>,
+    pub removed_objects: usize,
+    /// Objects, index files and virtual copies together
+    pub reclaimable_bytes: u64,
+    /// `false` for a dry run
+    pub deleted: bool,
+}
+
+/// Delete an installed version directory.
+///
+/// Fails if another installed version inherits from it, since that version
+/// would no longer be launchable.
+pub async fn delete_version(game_dir: &Path, version_id: &str) -> Result<(), String> {
+    if version_id.is_empty()
+        || version_id.contains(['/', '\\'])
+        || version_id == "."
+        || version_id == ".."
+    {
+        return Err(format!("Invalid version id: {}", version_id));
+    }
+
+    let version_dir = game_dir.join("versions").join(version_id);
+    if !version_dir.exists() {
+        return Err(format!("Version {} is not installed", version_id));
+    }
+
+    let installed = manifest::list_local_versions(game_dir)
+        .await
+        .map_err(|e| e.to_string())?;
+    for other in installed.iter().filter(|id| id.as_str() != version_id) {
+        if let Ok(v) = manifest::load_local_version(game_dir, other).await {
+            if v.inherits_from.as_deref() == Some(version_id) {
+                return Err(format!(
+                    "Version {} is required by {}; delete that version first",
+                    version_id, other
+                ));
+            }
+        }
+    }
+
+    tokio::fs::remove_dir_all(&version_dir)
+        .await
+        .map_err(|e| format!("Failed to delete {}: {}", version_dir.display(), e))
+}
+
+/// Library file paths a version may reference, for every platform.
+pub fn referenced_library_paths(version: &GameVersion, libraries_dir: &Path) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    for lib in &version.libraries {
+        if let Some(jar) = crate::core::maven::library_jar(lib, libraries_dir, None) {
+            paths.push(jar.path);
+        }
+        if let Some(downloads) = &lib.downloads {
+            // Keep natives for all platforms: cheap, and avoids surprises on shared dirs
+            if let Some(classifiers) = downloads.classifiers.as_ref().and_then(|c| c.as_object()) {
+                for value in classifiers.values() {
+                    if let Ok(native) = serde_json::from_value::<DownloadArtifact>(value.clone()) {
+                        if let Some(path) = native.path {
+                            paths.push(libraries_dir.join(path));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    paths
+}
+
+/// Folders the Forge installer writes to, kept whole for versions installed
+/// before their installer profile was saved.
+fn installer_output_dirs(version: &GameVersion, libraries_dir: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = version
+        .libraries
+        .iter()
+        .filter(|lib| {
+            lib.name.starts_with("net.minecraftforge:forge:")
+                || lib.name.starts_with("net.neoforged:")
+        })
+        .filter_map(|lib| crate::core::maven::get_library_path(&lib.name, libraries_dir))
+        .filter_map(|path| path.parent().map(Path::to_path_buf))
+        .collect();
+    if !dirs.is_empty() {
+        // Deobfuscated and patched client jars
+        dirs.push(libraries_dir.join("net").join("minecraft"));
+    }
+    dirs
+}
+
+/// Find (and optionally delete) library and asset files not referenced by any installed version.
+///
+/// Fails if a version, its parent or its asset index cannot be read, since
+/// the files it uses would otherwise count as garbage.
+///
+/// # Arguments
+/// * `game_dir` - The .minecraft directory path
+/// * `overrides` - Library overrides of all instances, whose replacement
+///   libraries are kept too
+/// * `mods` - Mod files of all instances, whose mod store entries are kept
+/// * `patched_jars` - Cached client jars with the current jar mods of all
+///   instances, see `jar_mods::cached_jar`
+/// * `dry_run` - Only report what would be deleted
+pub async fn collect_garbage(
+    game_dir: &Path,
+    overrides: &[LibraryOverride],
+    mods: &[PathBuf],
+    patched_jars: &[PathBuf],
+    dry_run: bool,
+) -> Result<GarbageReport, String> {
+    let libraries_dir = game_dir.join("libraries");
+    let assets_dir = game_dir.join("assets");
+    let objects_dir = assets_dir.join("objects");
+
+    let mut referenced: HashSet<PathBuf> = HashSet::new();
+    let mut kept_dirs = Vec::new();
+
+    let installed = manifest::list_local_versions(game_dir)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    for id in &installed {
+        let mut version = manifest::load_local_version(game_dir, id)
+            .await
+            .map_err(|e| format!("Cannot tell which files {} uses: {}", id, e))?;
+        while let Some(parent_id) = version.inherits_from.clone() {
+            let parent = manifest::load_local_version(game_dir, &parent_id)
+                .await
+                .map_err(|_| {
+                    format!(
+                        "Cannot tell which files {} uses: {} is not installed",
+                        id, parent_id
+                    )
+                })?;
+            version = crate::core::version_merge::merge_versions(version, parent);
+        }
+
+        referenced.extend(referenced_library_paths(&version, &libraries_dir));
+        let profile_path = game_dir
+            .join("versions")
+            .join(id)
+            .join(forge::INSTALL_PROFILE);
+        let profile = tokio::fs::read_to_string(&profile_path)
+            .await
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok());
+        match profile {
+            Some(profile) => referenced.extend(forge::installer_outputs(&profile, &libraries_dir)),
+            None => kept_dirs.extend(installer_output_dirs(&version, &libraries_dir)),
+        }
+        // One at a time, since instances may override the same library
+        for library_override in overrides {
+            let mut overridden = version.clone();
+            library_override::apply(&mut overridden, std::slice::from_ref(library_override));
+            referenced.extend(referenced_library_paths(&overridden, &libraries_dir));
+        }
+
+        if let Some(index) = &version.asset_index {
+            let index_path = assets_dir
+                .join("indexes")
+                .join(format!("{}.json", index.id));
+            let parsed: crate::core::assets::AssetIndexJson =
+                tokio::fs::read_to_string(&index_path)
+                    .await
+                    .ok()
+                    .and_then(|content| serde_json::from_str(&content).ok())
+                    .ok_or_else(|| {
+                        format!(
+                            "Cannot tell which files {} uses: asset index {} is unreadable",
+                            id, index.id
+                        )
+                    })?;
+            for object in parsed.objects.values() {
+                if object.hash.len() >= 2 {
+                    referenced.insert(objects_dir.join(&object.hash[0..2]).join(&object.hash));
+                }
+            }
+        }
+    }
+
+    // Cached `.sha1` files of kept libraries
+    let sidecars: Vec<PathBuf> = referenced
+        .iter()
+        .map(|path| crate::core::maven::sha1_sidecar(path))
+        .collect();
+    referenced.extend(sidecars);
+
+    let mut orphans = Vec::new();
+    collect_unreferenced(&libraries_dir, &referenced, &mut orphans);
+    collect_unreferenced(&objects_dir, &referenced, &mut orphans);
+    let patched_jars: HashSet<PathBuf> = patched_jars.iter().cloned().collect();
+    let jar_mod_dirs: Vec<PathBuf> = std::fs::read_dir(game_dir.join("versions"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path().join(crate::core::jar_mods::JAR_MODS_DIR))
+        .collect();
+    for dir in &jar_mod_dirs {
+        collect_unreferenced(dir, &patched_jars, &mut orphans);
+    }
+    orphans.retain(|(path, _)| !kept_dirs.iter().any(|dir| path.starts_with(dir)));
+    let store_dir = game_dir.join(crate::core::mod_store::STORE_DIR);
+    orphans.extend(crate::core::mod_store::unused(&store_dir, mods));
+
+    let mut report = GarbageReport {
+        deleted: !dry_run,
+        ..Default::default()
+    };
+    for (path, size) in &orphans {
+        report.reclaimable_bytes += size;
+        report.files.push(
+            path.strip_prefix(game_dir)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .to_string(),
+        );
+    }
+
+    if !dry_run {
+        for (path, _) in &orphans {
+            if let Err(e) = std::fs::remove_file(path) {
+                eprintln!("[GC] Failed to delete {}: {}", path.display(), e);
+            }
+        }
+        remove_empty_dirs(&libraries_dir);
+        remove_empty_dirs(&objects_dir);
+        remove_empty_dirs(&store_dir);
+        for dir in &jar_mod_dirs {
+            // Fails harmlessly if the directory is not empty
+            let _ = std::fs::remove_dir(dir);
+        }
+    }
+
+    Ok(report)
+}
+
+/// Ids of the asset indexes installed versions use. Fails if a version's
+/// index cannot be told from local files, since its assets would be pruned.
+pub async fn referenced_asset_indexes(game_dir: &Path) -> Result<HashSet<String>, String> {
+    let installed = manifest::list_local_versions(game_dir)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut ids = HashSet::new();
+    for id in &installed {
+        let mut version = manifest::load_local_version(game_dir, id)
+            .await
+            .map_err(|e| format!("Failed to read version {}: {}", id, e))?;
+        while let Some(parent_id) = version.inherits_from.clone() {
+            let parent = manifest::load_local_version(game_dir, &parent_id)
+                .await
+                .map_err(|_| {
+                    format!(
+                        "Cannot tell which assets {} uses: {} is not installed",
+                        id, parent_id
+                    )
+                })?;
+            version = crate::core::version_merge::merge_versions(version, parent);
+        }
+        if let Some(index) = version.asset_index {
+            ids.insert(index.id);
+        }
+    }
+    Ok(ids)
+}
+
+/// Delete asset objects, index files and virtual asset copies that no index
+/// in `kept` uses, or only report them for a dry run.
+pub fn prune_assets_in(
+    assets_dir: &Path,
+    kept: &HashSet<String>,
+    dry_run: bool,
+) -> Result<AssetPruneReport, String> {
+    let indexes_dir = assets_dir.join("indexes");
+    let objects_dir = assets_dir.join("objects");
+    let mut report = AssetPruneReport {
+        deleted: !dry_run,
+        ..Default::default()
+    };
+    let mut referenced: HashSet<PathBuf> = HashSet::new();
+    let mut removed_paths = Vec::new();
+
+    let index_files = std::fs::read_dir(&indexes_dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_else(|_| Vec::new());
+    for path in index_files {
+        let Some(id) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".json"))
+            .map(str::to_string)
+        else {
+            continue;
+        };
+        if !kept.contains(&id) {
+            report.reclaimable_bytes += std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            removed_paths.push(path);
+            report.removed_indexes.push(id);
+            continue;
+        }
+        // An unreadable kept index could list any object, so nothing is pruned
+        let index: crate::core::assets::AssetIndexJson = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .ok_or_else(|| format!("Failed to read asset index {}", id))?;
+        for object in index.objects.values() {
+            if object.hash.len() >= 2 {
+                referenced.insert(objects_dir.join(&object.hash[0..2]).join(&object.hash));
+            }
+        }
+        report.kept_indexes.push(id);
+    }
+
+    let virtual_dirs = std::fs::read_dir(assets_dir.join("virtual"))
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_else(|_| Vec::new());
+    for path in virtual_dirs {
+        let id = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+        if id.is_some_and(|id| !kept.contains(&id)) {
+            report.reclaimable_bytes += crate::utils::file_utils::dir_size(&path);
+            removed_paths.push(path);
+        }
+    }
+
+    let mut orphans = Vec::new();
+    collect_unreferenced(&objects_dir, &referenced, &mut orphans);
+    report.removed_objects = orphans.len();
+    for (path, size) in orphans {
+        report.reclaimable_bytes += size;
+        removed_paths.push(path);
+    }
+    report.kept_indexes.sort();
+    report.removed_indexes.sort();
+
+    if !dry_run {
+        for path in &removed_paths {
+            let result = if path.is_dir() {
+                std::fs::remove_dir_all(path)
+            } else {
+                std::fs::remove_file(path)
+            };
+            if let Err(e) = result {
+                eprintln!("[GC] Failed to delete {}: {}", path.display(), e);
+            }
+        }
+        remove_empty_dirs(&objects_dir);
+    }
+    Ok(report)
+}
+
+/// Prune assets of versions no longer installed in `game_dir`.
+pub async fn prune_assets(game_dir: &Path, dry_run: bool) -> Result<AssetPruneReport, String> {
+    let kept = referenced_asset_indexes(game_dir).await?;
+    let assets_dir = game_dir.join("assets");
+    tokio::task::spawn_blocking(move || prune_assets_in(&assets_dir, &kept, dry_run))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Disk usage of the launcher data directory, per category, in bytes.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct DiskUsage {
+    pub versions: u64,
+    pub libraries: u64,
+    pub assets: u64,
+    pub instances: u64,
+    pub java_runtimes: u64,
+    pub caches: u64,
+    /// Everything else (config files, logs, ...)
+    pub other: u64,
+    pub total: u64,
+}
+
+/// Compute per-category disk usage of the launcher data directory.
+///
+/// Each top-level entry is walked on its own blocking task, so large trees
+/// like `assets/objects` are sized concurrently.
+pub async fn compute_disk_usage(app_dir: &Path) -> DiskUsage {
+    let mut usage = DiskUsage::default();
+
+    let Ok(entries) = std::fs::read_dir(app_dir) else {
+        return usage;
+    };
+
+    let mut handles = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = entry.path();
+        handles.push(tokio::spawn(async move {
+            (name, parallel_dir_size(path).await)
+        }));
+    }
+
+    for handle in handles {
+        let Ok((name, size)) = handle.await else {
+            continue;
+        };
+        match name.as_str() {
+            "versions" => usage.versions += size,
+            "libraries" => usage.libraries += size,
+            "assets" => usage.assets += size,
+            "instances" => usage.instances += size,
+            "java" => usage.java_runtimes += size,
+            "cache" | "java_catalog_cache.json" => usage.caches += size,
+            _ => usage.other += size,
+        }
+        usage.total += size;
+    }
+
+    usage
+}
+
+/// Size a directory by walking each of its children on a blocking task.
+async fn parallel_dir_size(path: PathBuf) -> u64 {
+    if !path.is_dir() {
+        return crate::utils::file_utils::dir_size(&path);
+    }
+    let Ok(entries) = std::fs::read_dir(&path) else {
+        return 0;
+    };
+
+    let handles: Vec<_> = entries
+        .flatten()
+        .map(|entry| {
+            let child = entry.path();
+            tokio::task::spawn_blocking(move || crate::utils::file_utils::dir_size(&child))
+        })
+        .collect();
+
+    let mut total = 0;
+    for handle in handles {
+        total += handle.await.unwrap_or(0);
+    }
+    total
+}
+
+/// Recursively collect files under `dir` that are not in `referenced`.
+fn collect_unreferenced(dir: &Path, referenced: &HashSet<PathBuf>, out: &mut Vec<(PathBuf, u64)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            collect_unreferenced(&path, referenced, out);
+        } else if !referenced.contains(&path) {
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            out.push((path, size));
+        }
+    }
+}
+
+/// Remove empty directories below `dir` (but not `dir` itself).
+fn remove_empty_dirs(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            remove_empty_dirs(&path);
+            // Fails harmlessly if the directory is not empty
+            let _ = std::fs::remove_dir(&path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_referenced_library_paths() {
+        let version: GameVersion = serde_json::from_value(serde_json::json!({
+            "id": "test",
+            "mainClass": "Main",
+            "libraries": [
+                {
+                    "name": "org.lwjgl:lwjgl:3.3.1",
+                    "downloads": {
+                        "artifact": { "path": "org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar", "url": "https://example.com/a.jar" },
+                        "classifiers": {
+                            "natives-linux": { "path": "org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-linux.jar", "url": "https://example.com/b.jar" }
+                        }
+                    }
+                },
+                { "name": "net.fabricmc:fabric-loader:0.15.6" }
+            ]
+        }))
+        .unwrap();
+
+        let libs = Path::new("/libs");
+        let paths = referenced_library_paths(&version, libs);
+        assert_eq!(paths.len(), 3);
+        assert!(paths.contains(&libs.join("org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-linux.jar")));
+    }
+
+    #[tokio::test]
+    async fn test_collect_garbage_keeps_forge_outputs() {
+        let dir = std::env::temp_dir().join(format!("dropout-gc-{}", uuid::Uuid::new_v4()));
+        let write = |path: &str, content: &str| {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(
+            "versions/1.20.1/1.20.1.json",
+            r#"{"id": "1.20.1", "mainClass": "Main", "libraries": []}"#,
+        );
+        write(
+            "versions/1.20.1-forge-47.2.0/1.20.1-forge-47.2.0.json",
+            r#"{"id": "1.20.1-forge-47.2.0", "inheritsFrom": "1.20.1", "mainClass": "Main",
+                "libraries": [{"name": "net.minecraftforge:fmlloader:1.20.1-47.2.0"}]}"#,
+        );
+        write(
+            "versions/1.20.1-forge-47.2.0/install_profile.json",
+            r#"{
+                "data": {
+                    "MC_SRG": {"client": "[net.minecraft:client:1.20.1-mcp:srg]",
+                               "server": "[net.minecraft:server:1.20.1-mcp:srg]"},
+                    "PATCHED": {"client": "[net.minecraftforge:forge:1.20.1-47.2.0:client]",
+                                "server": "[net.minecraftforge:forge:1.20.1-47.2.0:server]"},
+                    "BINPATCH": {"client": "/data/client.lzma", "server": "/data/server.lzma"}
+                },
+                "processors": [
+                    {"sides": ["client"], "outputs": {"{MC_SRG}": "'abc'"}},
+                    {"outputs": {"{PATCHED}": "'def'"}},
+                    {"sides": ["server"],
+                     "outputs": {"[net.minecraft:server:1.20.1:extra]": "'ghi'"}}
+                ]
+            }"#,
+        );
+        let srg = "net/minecraft/client/1.20.1-mcp/client-1.20.1-mcp-srg.jar";
+        let patched = "net/minecraftforge/forge/1.20.1-47.2.0/forge-1.20.1-47.2.0-client.jar";
+        let loader = "net/minecraftforge/fmlloader/1.20.1-47.2.0/fmlloader-1.20.1-47.2.0.jar";
+        let orphan = "net/minecraft/server/1.20.1/server-1.20.1-extra.jar";
+        for path in [srg, patched, loader, orphan] {
+            write(&format!("libraries/{}", path), "x");
+        }
+        write("versions/1.20.1/jarmods/current.jar", "x");
+        write("versions/1.20.1/jarmods/old.jar", "x");
+
+        let current = dir.join("versions/1.20.1/jarmods/current.jar");
+        let report = collect_garbage(&dir, &[], &[], &[current], true)
+            .await
+            .unwrap();
+        let files: Vec<PathBuf> = report.files.iter().map(PathBuf::from).collect();
+        assert_eq!(
+            files,
+            [
+                Path::new("libraries").join(orphan),
+                Path::new("versions/1.20.1/jarmods/old.jar").to_path_buf(),
+            ]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_collect_garbage_refuses_broken_versions() {
+        let dir = std::env::temp_dir().join(format!("dropout-gc-{}", uuid::Uuid::new_v4()));
+        let write = |path: &str, content: &str| {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write("libraries/org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar", "x");
+        write("versions/1.20.1/1.20.1.json", "{ truncated");
+        assert!(collect_garbage(&dir, &[], &[], &[], false).await.is_err());
+
+        // A missing parent or asset index hides the version's files as well
+        write(
+            "versions/1.20.1/1.20.1.json",
+            r#"{"id": "1.20.1", "inheritsFrom": "1.19.4", "mainClass": "Main", "libraries": []}"#,
+        );
+        assert!(collect_garbage(&dir, &[], &[], &[], false).await.is_err());
+        write(
+            "versions/1.20.1/1.20.1.json",
+            r#"{"id": "1.20.1", "mainClass": "Main", "libraries": [],
+                "assetIndex": {"id": "5", "sha1": "", "size": 0, "totalSize": 0, "url": ""}}"#,
+        );
+        assert!(collect_garbage(&dir, &[], &[], &[], false).await.is_err());
+
+        assert!(dir
+            .join("libraries/org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1.jar")
+            .exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_prune_assets_in() {
+        let dir = std::env::temp_dir().join(format!("dropout-assets-{}", uuid::Uuid::new_v4()));
+        let write = |path: &str, content: &str| {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        let index = |hash: &str| {
+            format!(
+                r#"{{"objects": {{"a": {{"hash": "{}", "size": 1}}}}}}"#,
+                hash
+            )
+        };
+        write("indexes/12.json", &index("aa11"));
+        write("indexes/legacy.json", &index("bb22"));
+        write("objects/aa/aa11", "x");
+        write("objects/bb/bb22", "y");
+        write("virtual/legacy/a", "y");
+
+        let kept = HashSet::from(["12".to_string()]);
+        let report = prune_assets_in(&dir, &kept, true).unwrap();
+        assert_eq!(report.kept_indexes, ["12"]);
+        assert_eq!(report.removed_indexes, ["legacy"]);
+        assert_eq!(report.removed_objects, 1);
+        assert!(dir.join("objects/bb/bb22").exists());
+
+        prune_assets_in(&dir, &kept, false).unwrap();
+        assert!(dir.join("objects/aa/aa11").exists());
+        assert!(!dir.join("objects/bb").exists());
+        assert!(!dir.join("indexes/legacy.json").exists());
+        assert!(!dir.join("virtual/legacy").exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
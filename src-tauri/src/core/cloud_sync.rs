@@ -0,0 +1,129 @@
+//! Detection of game folders inside cloud-synced directories.
+//!
+//! Sync clients lock files while uploading them and replace files changed on
+//! two machines, which corrupts worlds and makes launches fail with files in
+//! use. `detect` recognises the folders of OneDrive, Dropbox, iCloud Drive
+//! and Google Drive by name and, for OneDrive, by the variables its client
+//! sets on Windows.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::core::events::LauncherEvent;
+
+const ONEDRIVE_VARIABLES: [&str; 3] = ["OneDrive", "OneDriveConsumer", "OneDriveCommercial"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncService {
+    OneDrive,
+    Dropbox,
+    ICloud,
+    GoogleDrive,
+}
+
+impl SyncService {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::OneDrive => "OneDrive",
+            Self::Dropbox => "Dropbox",
+            Self::ICloud => "iCloud Drive",
+            Self::GoogleDrive => "Google Drive",
+        }
+    }
+}
+
+/// A launcher folder inside a synced directory; `cloud-sync-warning` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct CloudSyncWarning {
+    pub path: String,
+    pub service: SyncService,
+    pub message: String,
+}
+
+impl LauncherEvent for CloudSyncWarning {
+    const NAME: &'static str = "cloud-sync-warning";
+}
+
+/// Warning for `path` if it lies in a synced directory.
+pub fn check(path: &Path) -> Option<CloudSyncWarning> {
+    let service = detect(path)?;
+    Some(CloudSyncWarning {
+        path: path.to_string_lossy().to_string(),
+        service,
+        message: format!(
+            "The game folder {} is synced by {}, which locks and replaces files while \
+             syncing and can corrupt worlds. Move it out of the synced folder or exclude \
+             it from syncing.",
+            path.display(),
+            service.name()
+        ),
+    })
+}
+
+/// Service syncing `path`, if any.
+pub fn detect(path: &Path) -> Option<SyncService> {
+    let onedrive_roots: Vec<PathBuf> = ONEDRIVE_VARIABLES
+        .iter()
+        .filter_map(std::env::var_os)
+        .filter(|root| !root.is_empty())
+        .map(PathBuf::from)
+        .collect();
+    detect_in(path, &onedrive_roots)
+}
+
+fn detect_in(path: &Path, onedrive_roots: &[PathBuf]) -> Option<SyncService> {
+    if onedrive_roots.iter().any(|root| path.starts_with(root)) {
+        return Some(SyncService::OneDrive);
+    }
+    path.components()
+        .find_map(|component| service_for_folder(component.as_os_str().to_str()?))
+}
+
+/// Service owning a folder named `name`, including the per-account folders
+/// like `OneDrive - Contoso` and macOS's `CloudStorage/GoogleDrive-<email>`.
+fn service_for_folder(name: &str) -> Option<SyncService> {
+    let name = name.to_lowercase();
+    if name.starts_with("onedrive") {
+        Some(SyncService::OneDrive)
+    } else if name.starts_with("dropbox") {
+        Some(SyncService::Dropbox)
+    } else if matches!(
+        name.as_str(),
+        "mobile documents" | "icloud drive" | "iclouddrive"
+    ) {
+        Some(SyncService::ICloud)
+    } else if name.starts_with("googledrive") || name == "google drive" || name == "my drive" {
+        Some(SyncService::GoogleDrive)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_in() {
+        let roots = [PathBuf::from("/home/me/Business")];
+        let detect = |path: &str| detect_in(Path::new(path), &roots);
+        assert_eq!(
+            detect("/home/me/Business/DropOut"),
+            Some(SyncService::OneDrive)
+        );
+        assert_eq!(
+            detect("/Users/me/Library/Mobile Documents/com~apple~CloudDocs/DropOut"),
+            Some(SyncService::ICloud)
+        );
+        assert_eq!(
+            detect("/Users/me/Library/CloudStorage/GoogleDrive-me@example.com/DropOut"),
+            Some(SyncService::GoogleDrive)
+        );
+        assert_eq!(
+            detect("/home/me/Dropbox (Personal)/mc"),
+            Some(SyncService::Dropbox)
+        );
+        assert_eq!(detect("/home/me/.local/share/com.dropout.launcher"), None);
+    }
+}
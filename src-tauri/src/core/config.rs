@@ -1,47 +1,412 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use tauri::{AppHandle, Manager};
 
+use crate::core::display::DisplayBackend;
+use crate::core::gpu::GpuPreference;
+use crate::core::instance::Instance;
+use crate::utils::file_utils;
+
+/// Schema version written to `config.json`. Bump it together with a new
+/// entry in `MIGRATIONS` whenever a field is renamed or changes meaning.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// `MIGRATIONS[n]` upgrades a version `n` config to version `n + 1`.
+const MIGRATIONS: [fn(&mut serde_json::Value); CONFIG_VERSION as usize] = [migrate_v0_to_v1];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct LauncherConfig {
+    /// Schema version, see `CONFIG_VERSION`
+    pub config_version: u32,
     pub min_memory: u32, // in MB
     pub max_memory: u32, // in MB
+    pub jvm_preset: JvmPreset,
+    /// Wayland or X11 for the game window on Linux
+    pub display_backend: DisplayBackend,
+    /// GPU the game runs on, for laptops with integrated and discrete graphics
+    pub gpu_preference: GpuPreference,
+    /// Write a GC log to analyse after the game exits, see `core::gc_log`
+    pub gc_logging: bool,
     pub java_path: String,
     pub width: u32,
     pub height: u32,
     pub download_threads: u32, // concurrent download threads (1-128)
+    pub max_connections_per_host: u32,
+    /// How files already on disk are checked before launching
+    pub verification_mode: VerificationMode,
+    pub proxy: ProxyConfig,
+    pub user_agent: Option<String>, // None = "DropOut/<version> (<os>)"
+    /// Seconds to wait for a server to accept a connection
+    pub connect_timeout_secs: u64,
+    /// Seconds to wait for an API response; file downloads are not limited
+    pub request_timeout_secs: u64,
     pub custom_background_path: Option<String>,
     pub enable_gpu_acceleration: bool,
     pub enable_visual_effects: bool,
     pub active_effect: String,
     pub theme: String,
-    pub log_upload_service: String, // "paste.rs" or "pastebin.com"
+    pub log_upload_service: LogUploadService,
     pub pastebin_api_key: Option<String>,
+    /// Also hide the player's name and UUID in uploaded logs
+    pub redact_account_in_logs: bool,
+    pub visible_version_types: Vec<String>, // "release", "snapshot", "old_beta", "old_alpha"
+    /// Tray icon with a menu to launch instances
+    pub show_tray_icon: bool,
+    /// Days deleted items stay in the trash; 0 keeps them until emptied
+    pub trash_retention_days: u32,
+    /// When to ask before large downloads, see `core::metered`
+    pub metered_policy: crate::core::metered::MeteredPolicy,
+    /// Downloads up to this size never ask
+    pub metered_prompt_mb: u32,
+    /// Keep mods once and link them into instances, see `core::mod_store`
+    pub mod_store: bool,
+    /// Needed to update CurseForge modpacks
+    pub curseforge_api_key: Option<String>,
+    /// Least severe launcher log lines shown, see `core::logging`
+    pub log_level: LogLevel,
+    /// Log HTTP requests, full launch arguments and rule decisions
+    pub debug_mode: bool,
+    pub prefetch: PrefetchConfig,
+    /// Settings given to new instances
+    pub instance_template: InstanceTemplate,
 }
 
 impl Default for LauncherConfig {
     fn default() -> Self {
         Self {
+            config_version: CONFIG_VERSION,
             min_memory: 1024,
             max_memory: 2048,
+            jvm_preset: JvmPreset::default(),
+            display_backend: DisplayBackend::default(),
+            gpu_preference: GpuPreference::default(),
+            gc_logging: false,
             java_path: "java".to_string(),
             width: 854,
             height: 480,
             download_threads: 32,
+            max_connections_per_host: 16,
+            verification_mode: VerificationMode::default(),
+            proxy: ProxyConfig::default(),
+            user_agent: None,
+            connect_timeout_secs: 15,
+            request_timeout_secs: 30,
             custom_background_path: None,
             enable_gpu_acceleration: false,
             enable_visual_effects: true,
             active_effect: "constellation".to_string(),
             theme: "dark".to_string(),
-            log_upload_service: "paste.rs".to_string(),
+            log_upload_service: LogUploadService::default(),
             pastebin_api_key: None,
+            redact_account_in_logs: false,
+            visible_version_types: crate::core::manifest::VERSION_TYPES
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+            show_tray_icon: true,
+            trash_retention_days: 30,
+            metered_policy: Default::default(),
+            metered_prompt_mb: 200,
+            mod_store: false,
+            curseforge_api_key: None,
+            log_level: LogLevel::default(),
+            debug_mode: false,
+            prefetch: PrefetchConfig::default(),
+            instance_template: InstanceTemplate::default(),
+        }
+    }
+}
+
+/// Paste service used by `upload_to_pastebin`, stored by its host name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum LogUploadService {
+    #[default]
+    #[serde(rename = "paste.rs")]
+    PasteRs,
+    /// Needs `pastebin_api_key`
+    #[serde(rename = "pastebin.com")]
+    Pastebin,
+    /// Minecraft log host; strips IP addresses and access tokens on upload
+    #[serde(rename = "mclo.gs")]
+    McLogs,
+}
+
+/// How `download_files` checks files that are already on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum VerificationMode {
+    /// Hash every file, catching corruption on disk
+    #[default]
+    Full,
+    /// Compare sizes only; files of unknown size are hashed
+    Fast,
+    /// Keep files the file index verified earlier and that are unchanged
+    /// since; others get the `Fast` check
+    #[serde(alias = "none")]
+    Index,
+}
+
+/// Garbage collector options added after the memory settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum JvmPreset {
+    /// Java's defaults
+    #[default]
+    Default,
+    /// G1 tuned for short pauses, for large modpacks
+    Performance,
+    /// Serial collector and a small code cache, for low-end machines
+    LowMemory,
+}
+
+impl JvmPreset {
+    pub fn args(self) -> &'static [&'static str] {
+        match self {
+            Self::Default => &[],
+            Self::Performance => &[
+                "-XX:+UseG1GC",
+                "-XX:+ParallelRefProcEnabled",
+                "-XX:MaxGCPauseMillis=200",
+                "-XX:+UnlockExperimentalVMOptions",
+                "-XX:+DisableExplicitGC",
+                "-XX:G1NewSizePercent=30",
+                "-XX:G1MaxNewSizePercent=40",
+                "-XX:G1HeapRegionSize=8M",
+                "-XX:G1ReservePercent=20",
+                "-XX:InitiatingHeapOccupancyPercent=15",
+            ],
+            Self::LowMemory => &["-XX:+UseSerialGC", "-XX:ReservedCodeCacheSize=64m"],
+        }
+    }
+}
+
+/// Memory and JVM settings an instance can override. Unset fields inherit
+/// the launcher setting.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JvmOverrides {
+    pub min_memory: Option<u32>,
+    pub max_memory: Option<u32>,
+    pub preset: Option<JvmPreset>,
+    pub display_backend: Option<DisplayBackend>,
+    pub gpu_preference: Option<GpuPreference>,
+    pub gc_logging: Option<bool>,
+}
+
+/// Defaults applied to instances by `create_instance`, or later with
+/// `apply_instance_template`, see `core::instance_template`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstanceTemplate {
+    pub jvm: JvmOverrides,
+    /// `key:value` lines merged into the instance's options.txt
+    pub options_txt: String,
+    /// Resource pack files copied into the instance and enabled
+    pub resource_packs: Vec<String>,
+}
+
+/// Severity of a launcher log line, most severe first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyKind {
+    #[default]
+    Http,
+    Socks5,
+}
+
+/// Background downloads of updates, see `core::prefetch`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrefetchConfig {
+    pub enabled: bool,
+    /// Hours between checks for updates
+    pub interval_hours: u32,
+    /// Download cap in KiB/s, 0 for none
+    pub max_kib_per_second: u32,
+}
+
+impl Default for PrefetchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_hours: 6,
+            max_kib_per_second: 1024,
         }
     }
 }
 
+impl PrefetchConfig {
+    /// The cap in bytes per second.
+    pub fn bandwidth_limit(&self) -> Option<u64> {
+        (self.max_kib_per_second > 0).then(|| self.max_kib_per_second as u64 * 1024)
+    }
+}
+
+/// Proxy used for all launcher HTTP traffic and, optionally, the game itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ProxyConfig {
+    pub enabled: bool,
+    pub kind: ProxyKind,
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Also pass the proxy to the game via JVM system properties
+    pub apply_to_game: bool,
+}
+
+impl ProxyConfig {
+    /// Proxy URL for reqwest, or `None` if disabled or incomplete.
+    pub fn url(&self) -> Option<String> {
+        if !self.enabled || self.host.is_empty() || self.port == 0 {
+            return None;
+        }
+        let scheme = match self.kind {
+            ProxyKind::Http => "http",
+            // socks5h resolves hostnames through the proxy
+            ProxyKind::Socks5 => "socks5h",
+        };
+        Some(format!("{}://{}:{}", scheme, self.host, self.port))
+    }
+
+    /// JVM system properties routing the game's traffic through the proxy.
+    ///
+    /// Java ignores credentials in these properties, so authenticated
+    /// proxies only work for the launcher itself.
+    pub fn jvm_args(&self) -> Vec<String> {
+        if !self.apply_to_game || self.url().is_none() {
+            return Vec::new();
+        }
+        match self.kind {
+            ProxyKind::Http => vec![
+                format!("-Dhttp.proxyHost={}", self.host),
+                format!("-Dhttp.proxyPort={}", self.port),
+                format!("-Dhttps.proxyHost={}", self.host),
+                format!("-Dhttps.proxyPort={}", self.port),
+            ],
+            ProxyKind::Socks5 => vec![
+                format!("-DsocksProxyHost={}", self.host),
+                format!("-DsocksProxyPort={}", self.port),
+            ],
+        }
+    }
+}
+
+/// Network settings an instance can override, e.g. to reach a private
+/// Maven repository through a different proxy. Unset fields inherit the
+/// launcher setting.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkOverrides {
+    pub download_threads: Option<u32>,
+    pub max_connections_per_host: Option<u32>,
+    pub proxy: Option<ProxyConfig>,
+    pub user_agent: Option<String>,
+    /// Repository for loader libraries whose version JSON names none,
+    /// used instead of the guessed default
+    pub maven_repository: Option<String>,
+}
+
+/// `LauncherConfig` with an instance's `NetworkOverrides` applied.
+///
+/// Derefs to the merged `LauncherConfig`, so code reading settings works
+/// the same with or without an instance.
+#[derive(Debug, Clone, Default)]
+pub struct EffectiveConfig {
+    pub settings: LauncherConfig,
+    pub maven_repository: Option<String>,
+    /// Download cap in bytes per second, set for background downloads
+    pub bandwidth_limit: Option<u64>,
+}
+
+impl EffectiveConfig {
+    pub fn resolve(config: &LauncherConfig, overrides: Option<&NetworkOverrides>) -> Self {
+        let mut settings = config.clone();
+        let Some(overrides) = overrides else {
+            return Self {
+                settings,
+                maven_repository: None,
+                bandwidth_limit: None,
+            };
+        };
+        if let Some(threads) = overrides.download_threads {
+            settings.download_threads = threads.clamp(1, 128);
+        }
+        if let Some(per_host) = overrides.max_connections_per_host {
+            settings.max_connections_per_host = per_host.max(1);
+        }
+        if let Some(proxy) = &overrides.proxy {
+            settings.proxy = proxy.clone();
+        }
+        if let Some(user_agent) = &overrides.user_agent {
+            settings.user_agent = Some(user_agent.clone());
+        }
+        Self {
+            settings,
+            maven_repository: overrides
+                .maven_repository
+                .as_ref()
+                .filter(|url| !url.trim().is_empty())
+                .map(|url| {
+                    if url.ends_with('/') {
+                        url.clone()
+                    } else {
+                        format!("{}/", url)
+                    }
+                }),
+            bandwidth_limit: None,
+        }
+    }
+}
+
+impl EffectiveConfig {
+    /// Apply an instance's memory and JVM overrides.
+    pub fn with_jvm(mut self, overrides: &JvmOverrides) -> Self {
+        if let Some(max_memory) = overrides.max_memory {
+            self.settings.max_memory = max_memory;
+        }
+        if let Some(min_memory) = overrides.min_memory {
+            self.settings.min_memory = min_memory;
+        }
+        self.settings.min_memory = self.settings.min_memory.min(self.settings.max_memory);
+        if let Some(preset) = overrides.preset {
+            self.settings.jvm_preset = preset;
+        }
+        if let Some(backend) = overrides.display_backend {
+            self.settings.display_backend = backend;
+        }
+        if let Some(gpu) = overrides.gpu_preference {
+            self.settings.gpu_preference = gpu;
+        }
+        if let Some(gc_logging) = overrides.gc_logging {
+            self.settings.gc_logging = gc_logging;
+        }
+        self
+    }
+}
+
+impl std::ops::Deref for EffectiveConfig {
+    type Target = LauncherConfig;
+
+    fn deref(&self) -> &LauncherConfig {
+        &self.settings
+    }
+}
+
 pub struct ConfigState {
     pub config: Mutex<LauncherConfig>,
     pub file_path: PathBuf,
@@ -50,11 +415,13 @@ pub struct ConfigState {
 impl ConfigState {
     pub fn new(app_handle: &AppHandle) -> Self {
         let app_dir = app_handle.path().app_data_dir().unwrap();
-        let config_path = app_dir.join("config.json");
+        Self::load(app_dir.join("config.json"))
+    }
 
+    /// Settings from `config_path`, or the defaults if it does not exist.
+    pub fn load(config_path: PathBuf) -> Self {
         let config = if config_path.exists() {
-            let content = fs::read_to_string(&config_path).unwrap_or_default();
-            serde_json::from_str(&content).unwrap_or_default()
+            load_config(&config_path)
         } else {
             LauncherConfig::default()
         };
@@ -65,11 +432,177 @@ impl ConfigState {
         }
     }
 
+    /// Current settings merged with an instance's overrides.
+    pub fn effective(&self, overrides: Option<&NetworkOverrides>) -> EffectiveConfig {
+        EffectiveConfig::resolve(&self.config.lock().unwrap(), overrides)
+    }
+
+    /// Settings for launching `instance`, with all of its overrides.
+    pub fn for_instance(&self, instance: &Instance) -> EffectiveConfig {
+        self.effective(Some(&instance.network)).with_jvm(&instance.jvm)
+    }
+
     pub fn save(&self) -> Result<(), String> {
         let config = self.config.lock().unwrap();
-        let content = serde_json::to_string_pretty(&*config).map_err(|e| e.to_string())?;
-        fs::create_dir_all(self.file_path.parent().unwrap()).map_err(|e| e.to_string())?;
-        fs::write(&self.file_path, content).map_err(|e| e.to_string())?;
-        Ok(())
+        file_utils::write_json_atomic(&self.file_path, &*config).map_err(|e| e.to_string())
+    }
+}
+
+/// Version 0 files predate `config_version`. Settings the UI could store out
+/// of range are brought back in, and a null Java path falls back to the default.
+fn migrate_v0_to_v1(config: &mut serde_json::Value) {
+    if let Some(threads) = config["download_threads"].as_u64() {
+        config["download_threads"] = threads.clamp(1, 128).into();
+    }
+    if config["java_path"].as_str().is_none_or(str::is_empty) {
+        if let Some(object) = config.as_object_mut() {
+            object.remove("java_path");
+        }
+    }
+}
+
+/// Bring a parsed config up to `CONFIG_VERSION`. Returns the version it had.
+fn migrate(config: &mut serde_json::Value) -> u32 {
+    let from = config["config_version"].as_u64().unwrap_or(0) as u32;
+    for migration in MIGRATIONS.iter().skip(from as usize) {
+        migration(config);
+    }
+    if from < CONFIG_VERSION {
+        config["config_version"] = CONFIG_VERSION.into();
+    }
+    from
+}
+
+/// Deserialize a config, dropping fields with invalid values instead of
+/// discarding the whole file. Returns the names of dropped fields.
+fn deserialize_lenient(mut config: serde_json::Value) -> (LauncherConfig, Vec<String>) {
+    if let Ok(parsed) = serde_json::from_value(config.clone()) {
+        return (parsed, Vec::new());
+    }
+    let mut dropped = Vec::new();
+    if let Some(object) = config.as_object_mut() {
+        object.retain(|key, value| {
+            let single = serde_json::json!({ key.as_str(): value.clone() });
+            let valid = serde_json::from_value::<LauncherConfig>(single).is_ok();
+            if !valid {
+                dropped.push(key.clone());
+            }
+            valid
+        });
+    }
+    let parsed = serde_json::from_value(config).unwrap_or_default();
+    (parsed, dropped)
+}
+
+/// Copy `path` next to itself with `suffix` appended before rewriting it.
+fn backup_file(path: &Path, suffix: &str) -> Result<PathBuf, String> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    let backup = path.with_file_name(name);
+    fs::copy(path, &backup).map_err(|e| format!("Failed to back up config: {}", e))?;
+    Ok(backup)
+}
+
+/// Load `config.json`, migrating older schemas. The original file is backed
+/// up before a migrated or repaired config is written back; files from a
+/// newer DropOut are loaded as-is and left untouched.
+fn load_config(path: &Path) -> LauncherConfig {
+    let mut value: serde_json::Value = match file_utils::read_json(path) {
+        Ok(value) => value,
+        Err(e) => {
+            println!("[Config] config.json is not valid JSON, using defaults: {}", e);
+            let _ = backup_file(path, ".invalid.bak");
+            return LauncherConfig::default();
+        }
+    };
+
+    let from = migrate(&mut value);
+    let (config, dropped) = deserialize_lenient(value);
+    if from > CONFIG_VERSION {
+        println!(
+            "[Config] config.json is from a newer version (schema {}), not rewriting it",
+            from
+        );
+        return config;
+    }
+    if !dropped.is_empty() {
+        println!("[Config] Reset invalid settings: {}", dropped.join(", "));
+    }
+    if from < CONFIG_VERSION || !dropped.is_empty() {
+        let suffix = if dropped.is_empty() {
+            format!(".v{}.bak", from)
+        } else {
+            ".invalid.bak".to_string()
+        };
+        let written = backup_file(path, &suffix).and_then(|_| {
+            file_utils::write_json_atomic(path, &config).map_err(|e| e.to_string())
+        });
+        if let Err(e) = written {
+            println!("[Config] Failed to save migrated config: {}", e);
+        }
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_v0() {
+        let mut value = serde_json::json!({
+            "max_memory": 4096,
+            "download_threads": 500,
+            "java_path": null,
+        });
+        assert_eq!(migrate(&mut value), 0);
+        let (config, dropped) = deserialize_lenient(value);
+        assert!(dropped.is_empty());
+        assert_eq!(config.config_version, CONFIG_VERSION);
+        assert_eq!(config.max_memory, 4096);
+        assert_eq!(config.download_threads, 128);
+        assert_eq!(config.java_path, "java");
+    }
+
+    #[test]
+    fn test_effective_config() {
+        let base = LauncherConfig::default();
+        let overrides = NetworkOverrides {
+            download_threads: Some(4),
+            maven_repository: Some("https://maven.example.com/releases".to_string()),
+            ..Default::default()
+        };
+        let effective = EffectiveConfig::resolve(&base, Some(&overrides));
+        assert_eq!(effective.download_threads, 4);
+        assert_eq!(effective.max_connections_per_host, base.max_connections_per_host);
+        assert_eq!(
+            effective.maven_repository.as_deref(),
+            Some("https://maven.example.com/releases/")
+        );
+        assert!(EffectiveConfig::resolve(&base, None).maven_repository.is_none());
+    }
+
+    #[test]
+    fn test_invalid_fields_are_dropped() {
+        let value = serde_json::json!({
+            "config_version": CONFIG_VERSION,
+            "max_memory": 6144,
+            "width": "wide",
+            "log_upload_service": "hastebin",
+        });
+        let (config, mut dropped) = deserialize_lenient(value);
+        dropped.sort();
+        assert_eq!(dropped, vec!["log_upload_service", "width"]);
+        assert_eq!(config.max_memory, 6144);
+        assert_eq!(config.width, 854);
+        assert_eq!(config.log_upload_service, LogUploadService::PasteRs);
+
+        let value = serde_json::json!({
+            "log_upload_service": "pastebin.com",
+            "verification_mode": "fast",
+        });
+        let (config, _) = deserialize_lenient(value);
+        assert_eq!(config.log_upload_service, LogUploadService::Pastebin);
+        assert_eq!(config.verification_mode, VerificationMode::Fast);
     }
 }
@@ -0,0 +1,103 @@
+//! Separate console windows for running games.
+//!
+//! `open` creates a window showing one launch session's output. While it is
+//! open, `SessionOutput` sends that session's `game-output` events only to
+//! it, so the main window's log is not flooded when several instances run at
+//! once. Closing the window sends the output to every window again.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+
+use crate::core::events::{EventSink, EventTarget, LauncherEvent};
+
+/// Managed state mapping session ids to the label of their console window.
+pub struct ConsoleWindows {
+    labels: Mutex<HashMap<String, String>>,
+}
+
+impl ConsoleWindows {
+    pub fn new() -> Self {
+        Self {
+            labels: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn label(&self, session_id: &str) -> Option<String> {
+        self.labels.lock().unwrap().get(session_id).cloned()
+    }
+}
+
+/// Open, or focus, the console window of launch session `session_id`.
+/// Returns the window's label.
+pub fn open(app: &AppHandle, session_id: &str, title: &str) -> Result<String, String> {
+    if session_id.is_empty()
+        || !session_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return Err(format!("Invalid session id: {}", session_id));
+    }
+    let label = format!("console-{}", session_id);
+    if let Some(window) = app.get_webview_window(&label) {
+        let _ = window.unminimize();
+        window.set_focus().map_err(|e| e.to_string())?;
+        return Ok(label);
+    }
+
+    let url = WebviewUrl::App(format!("index.html?console={}", session_id).into());
+    let window = WebviewWindowBuilder::new(app, &label, url)
+        .title(format!("{} - Console", title))
+        .inner_size(900.0, 600.0)
+        .min_inner_size(480.0, 320.0)
+        .build()
+        .map_err(|e| format!("Failed to open console window: {}", e))?;
+
+    let state = app.state::<ConsoleWindows>();
+    state
+        .labels
+        .lock()
+        .unwrap()
+        .insert(session_id.to_string(), label.clone());
+    let app = app.clone();
+    let session_id = session_id.to_string();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Destroyed = event {
+            app.state::<ConsoleWindows>()
+                .labels
+                .lock()
+                .unwrap()
+                .remove(&session_id);
+        }
+    });
+    Ok(label)
+}
+
+/// Emits to the session's console window if one is open, otherwise like
+/// the wrapped sink.
+#[derive(Clone)]
+pub struct SessionOutput {
+    events: EventSink,
+}
+
+impl SessionOutput {
+    pub fn new(events: EventSink) -> Self {
+        Self { events }
+    }
+}
+
+impl EventTarget for SessionOutput {
+    fn emit_event<E: LauncherEvent>(&self, event: E) {
+        let label = self.events.scope().session_id.as_deref().and_then(|id| {
+            self.events
+                .window()
+                .app_handle()
+                .state::<ConsoleWindows>()
+                .label(id)
+        });
+        match label {
+            Some(label) => self.events.emit_event_to(&label, event),
+            None => self.events.emit_event(event),
+        }
+    }
+}
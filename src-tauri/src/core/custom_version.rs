@@ -0,0 +1,253 @@
+//! Custom versions imported from a version JSON and, optionally, a jar.
+//!
+//! `import` checks the JSON against the `GameVersion` schema, gives it a
+//! free id if its own is taken and copies it to `versions/<id>/`, so it
+//! launches like any installed version. An imported jar becomes the
+//! version's client jar: the JSON points `jar` at it and records its hash
+//! under `downloads.client` without a URL, which `client_jar_task` uses to
+//! keep the launcher from replacing it with the vanilla jar.
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::downloader::{compute_sha1, DownloadTask};
+use crate::core::game_version::{DownloadArtifact, GameVersion};
+use crate::core::manifest::LoaderType;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CustomVersionImport {
+    /// Id the version was installed as
+    pub id: String,
+    /// Id in the JSON, when it was taken and the version was renamed
+    pub renamed_from: Option<String>,
+    pub loader: LoaderType,
+    pub inherits_from: Option<String>,
+    pub custom_jar: bool,
+    /// Problems that do not stop the import
+    pub warnings: Vec<String>,
+}
+
+/// The JSON and jar of a version folder `dir`, named after the folder.
+pub fn folder_files(dir: &Path) -> Result<(PathBuf, Option<PathBuf>), String> {
+    let name = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid path: {}", dir.display()))?;
+    let json = dir.join(format!("{}.json", name));
+    if !json.is_file() {
+        return Err(format!("{} has no {}.json", dir.display(), name));
+    }
+    let jar = dir.join(format!("{}.jar", name));
+    Ok((json, jar.is_file().then_some(jar)))
+}
+
+fn valid_id(id: &str) -> bool {
+    !id.trim().is_empty()
+        && id != "."
+        && id != ".."
+        && !id.chars().any(|c| {
+            c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|')
+        })
+}
+
+/// Check a version JSON; returns the parsed version and any warnings.
+pub fn validate(
+    raw: &serde_json::Value,
+    has_jar: bool,
+    versions_dir: &Path,
+) -> Result<(GameVersion, Vec<String>), String> {
+    let version: GameVersion = serde_json::from_value(raw.clone())
+        .map_err(|e| format!("Not a valid version JSON: {}", e))?;
+    if !valid_id(&version.id) {
+        return Err(format!("Invalid version id: {:?}", version.id));
+    }
+    if version.main_class.trim().is_empty() {
+        return Err("The version JSON has no mainClass".to_string());
+    }
+    if let Some(lib) = version
+        .libraries
+        .iter()
+        .find(|lib| lib.name.split(':').count() < 3)
+    {
+        return Err(format!("Invalid library name: {}", lib.name));
+    }
+
+    let mut warnings = Vec::new();
+    match &version.inherits_from {
+        Some(parent) => {
+            if !versions_dir
+                .join(parent)
+                .join(format!("{}.json", parent))
+                .exists()
+            {
+                warnings.push(format!(
+                    "{} is not installed; it is downloaded on the first launch",
+                    parent
+                ));
+            }
+        }
+        None => {
+            if version.asset_index.is_none() {
+                return Err("The version JSON has no assetIndex and inherits from nothing".into());
+            }
+            if version.downloads.is_none() && !has_jar {
+                return Err("The version has no client jar; import its jar too".to_string());
+            }
+        }
+    }
+    if let Some(jar) = &version.jar {
+        if !has_jar && !versions_dir.join(jar).join(format!("{}.jar", jar)).exists() {
+            warnings.push(format!("The client jar of {} is not installed", jar));
+        }
+    }
+    Ok((version, warnings))
+}
+
+/// `id`, or `id-2`, `id-3`, ... if a version of that id is installed.
+pub fn unique_id(versions_dir: &Path, id: &str) -> String {
+    let mut candidate = id.to_string();
+    let mut n = 2;
+    while versions_dir.join(&candidate).exists() {
+        candidate = format!("{}-{}", id, n);
+        n += 1;
+    }
+    candidate
+}
+
+/// Install the version JSON at `json_path`, and `jar_path` as its client
+/// jar, into `data_dir`.
+pub fn import(
+    data_dir: &Path,
+    json_path: &Path,
+    jar_path: Option<&Path>,
+) -> Result<CustomVersionImport, String> {
+    let content = fs::read_to_string(json_path)
+        .map_err(|e| format!("Failed to read {}: {}", json_path.display(), e))?;
+    let mut raw: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| format!("Not a valid version JSON: {}", e))?;
+    let versions_dir = data_dir.join("versions");
+    let (version, warnings) = validate(&raw, jar_path.is_some(), &versions_dir)?;
+
+    let jar = match jar_path {
+        Some(path) => {
+            let data =
+                fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            if zip::ZipArchive::new(std::io::Cursor::new(&data)).is_err() {
+                return Err(format!("{} is not a jar", path.display()));
+            }
+            Some(data)
+        }
+        None => None,
+    };
+
+    let id = unique_id(&versions_dir, &version.id);
+    raw["id"] = serde_json::Value::String(id.clone());
+    if let Some(data) = &jar {
+        raw["jar"] = serde_json::Value::String(id.clone());
+        raw["downloads"]["client"] = serde_json::json!({
+            "sha1": compute_sha1(data),
+            "size": data.len(),
+            "url": "",
+        });
+    }
+
+    let version_dir = versions_dir.join(&id);
+    fs::create_dir_all(&version_dir).map_err(|e| e.to_string())?;
+    let written = (|| {
+        if let Some(data) = &jar {
+            fs::write(version_dir.join(format!("{}.jar", id)), data)?;
+        }
+        let content = serde_json::to_string_pretty(&raw)?;
+        fs::write(version_dir.join(format!("{}.json", id)), content)
+    })();
+    if let Err(e) = written {
+        let _ = fs::remove_dir_all(&version_dir);
+        return Err(format!("Failed to install {}: {}", id, e));
+    }
+
+    Ok(CustomVersionImport {
+        renamed_from: (id != version.id).then(|| version.id.clone()),
+        id,
+        loader: LoaderType::detect(&version),
+        inherits_from: version.inherits_from,
+        custom_jar: jar.is_some(),
+        warnings,
+    })
+}
+
+/// Download task for a version's client jar. Imported jars have no URL and
+/// are never downloaded, only required to be present.
+pub fn client_jar_task(
+    artifact: &DownloadArtifact,
+    path: &Path,
+) -> Result<Option<DownloadTask>, String> {
+    if artifact.url.is_empty() {
+        if !path.exists() {
+            return Err(format!(
+                "The custom client jar {} is missing; import the version again",
+                path.display()
+            ));
+        }
+        return Ok(None);
+    }
+    Ok(Some(DownloadTask {
+        url: artifact.url.clone(),
+        path: path.to_path_buf(),
+        sha1: artifact.sha1.clone(),
+        sha256: None,
+        size: artifact.size,
+        mirrors: crate::core::multi_source::mojang_mirrors(&artifact.url),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import() {
+        let dir = std::env::temp_dir().join(format!("dropout-custom-{}", uuid::Uuid::new_v4()));
+        let data_dir = dir.join("data");
+        fs::create_dir_all(data_dir.join("versions").join("patched")).unwrap();
+
+        let json_path = dir.join("patched.json");
+        fs::write(
+            &json_path,
+            r#"{"id": "patched", "inheritsFrom": "1.20.4", "mainClass": "Main",
+                "libraries": [{"name": "com.example:patch:1.0"}], "custom": true}"#,
+        )
+        .unwrap();
+        let jar_path = dir.join("patched.jar");
+        let mut jar = zip::ZipWriter::new(fs::File::create(&jar_path).unwrap());
+        jar.start_file("Main.class", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        jar.finish().unwrap();
+
+        let report = import(&data_dir, &json_path, Some(&jar_path)).unwrap();
+        assert_eq!(report.id, "patched-2");
+        assert_eq!(report.renamed_from.as_deref(), Some("patched"));
+        assert!(report.custom_jar);
+        assert_eq!(report.warnings.len(), 1);
+
+        let installed = data_dir.join("versions").join("patched-2");
+        let raw: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(installed.join("patched-2.json")).unwrap())
+                .unwrap();
+        assert_eq!(raw["custom"], true);
+        let version: GameVersion = serde_json::from_value(raw).unwrap();
+        assert_eq!(version.client_jar_version(), "patched-2");
+        let client = &version.downloads.unwrap().client;
+        assert!(client_jar_task(client, &installed.join("patched-2.jar"))
+            .unwrap()
+            .is_none());
+
+        fs::write(
+            &json_path,
+            r#"{"id": "../x", "mainClass": "Main", "libraries": []}"#,
+        )
+        .unwrap();
+        assert!(import(&data_dir, &json_path, None).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,145 @@
+//! `dropout://` links from websites.
+//!
+//! Supported links:
+//! - `dropout://install/modrinth/<project id or slug>`
+//! - `dropout://join/<host>[:<port>]`
+//!
+//! A link never acts by itself: it is parsed and validated here and handed to
+//! the UI as a `deep-link-received` event, which asks the user to confirm.
+//! A link that started the launcher is kept until the UI collects it with
+//! `take_pending`, since no window is listening yet at that point.
+
+use serde::Serialize;
+use std::sync::Mutex;
+
+use crate::core::events::LauncherEvent;
+
+pub const SCHEME: &str = "dropout";
+const MAX_PROJECT_LEN: usize = 64;
+const MAX_HOST_LEN: usize = 253;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum DeepLink {
+    InstallModrinth { project: String },
+    Join { host: String, port: Option<u16> },
+}
+
+/// A link opened while the launcher is running.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeepLinkReceived {
+    pub link: DeepLink,
+}
+
+impl LauncherEvent for DeepLinkReceived {
+    const NAME: &'static str = "deep-link-received";
+}
+
+pub fn parse(url: &str) -> Result<DeepLink, String> {
+    let invalid = || format!("Unsupported link: {}", url);
+    let (scheme, rest) = url.split_once("://").ok_or_else(invalid)?;
+    if !scheme.eq_ignore_ascii_case(SCHEME) {
+        return Err(invalid());
+    }
+    let path = rest.split(['?', '#']).next().unwrap_or_default();
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+
+    match segments.as_slice() {
+        ["install", "modrinth", project] if valid_project(project) => {
+            Ok(DeepLink::InstallModrinth {
+                project: project.to_string(),
+            })
+        }
+        ["join", server] => parse_server(server).ok_or_else(invalid),
+        _ => Err(invalid()),
+    }
+}
+
+/// Modrinth project ids and slugs.
+fn valid_project(project: &str) -> bool {
+    !project.is_empty()
+        && project.len() <= MAX_PROJECT_LEN
+        && project
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// `host` or `host:port`, with a DNS name or IPv4 address as host.
+fn parse_server(server: &str) -> Option<DeepLink> {
+    let (host, port) = match server.rsplit_once(':') {
+        Some((host, port)) => (host, Some(port.parse::<u16>().ok().filter(|&p| p > 0)?)),
+        None => (server, None),
+    };
+    let valid_host = !host.is_empty()
+        && host.len() <= MAX_HOST_LEN
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    valid_host.then(|| DeepLink::Join {
+        host: host.to_ascii_lowercase(),
+        port,
+    })
+}
+
+/// The link the launcher was started with, until the UI takes it.
+#[derive(Default)]
+pub struct DeepLinkState {
+    pending: Mutex<Option<DeepLink>>,
+}
+
+impl DeepLinkState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_pending(&self, link: DeepLink) {
+        *self.pending.lock().unwrap() = Some(link);
+    }
+
+    pub fn take_pending(&self) -> Option<DeepLink> {
+        self.pending.lock().unwrap().take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_links() {
+        assert_eq!(
+            parse("dropout://install/modrinth/fabric-api/"),
+            Ok(DeepLink::InstallModrinth {
+                project: "fabric-api".to_string()
+            })
+        );
+        assert_eq!(
+            parse("DropOut://join/Play.Example.com:25566?ref=site"),
+            Ok(DeepLink::Join {
+                host: "play.example.com".to_string(),
+                port: Some(25566)
+            })
+        );
+        assert_eq!(
+            parse("dropout://join/127.0.0.1"),
+            Ok(DeepLink::Join {
+                host: "127.0.0.1".to_string(),
+                port: None
+            })
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_links() {
+        assert!(parse("https://install/modrinth/sodium").is_err());
+        assert!(parse("dropout://install/curseforge/sodium").is_err());
+        assert!(parse("dropout://install/modrinth/../../etc").is_err());
+        assert!(parse("dropout://install/modrinth/a%20b").is_err());
+        assert!(parse("dropout://join/example.com:0").is_err());
+        assert!(parse("dropout://join/example.com:99999").is_err());
+        assert!(parse("dropout://join/exa mple.com").is_err());
+        assert!(parse("dropout://join/").is_err());
+    }
+}
@@ -0,0 +1,517 @@
+//! System information for bug reports.
+//!
+//! `collect` gathers what usually matters when the game fails to start or
+//! render: OS and kernel, CPU, memory, GPUs and, on Linux, the display server
+//! and driver-related environment. `log_header` formats it for the top of an
+//! uploaded log and `DiagnosticBundle` packs it with logs and settings into a
+//! zip. Collection may run a short external probe for GPUs, so call it from a
+//! blocking thread.
+
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::core::config::LauncherConfig;
+use crate::core::redact::Redactor;
+use crate::core::setup::{self, SystemInfo};
+
+/// Largest part of a log or crash report put in a bundle; longer files keep
+/// their end.
+const MAX_BUNDLED_FILE: u64 = 4 * 1024 * 1024;
+const REDACTED: &str = "<redacted>";
+
+/// Environment variables that change which GPU or GL driver the game uses.
+#[cfg(target_os = "linux")]
+const GRAPHICS_VARIABLES: &[&str] = &[
+    "DRI_PRIME",
+    "__NV_PRIME_RENDER_OFFLOAD",
+    "__GLX_VENDOR_LIBRARY_NAME",
+    "MESA_LOADER_DRIVER_OVERRIDE",
+    "LIBGL_ALWAYS_SOFTWARE",
+];
+
+/// Where the launcher keeps its files.
+#[derive(Debug, Clone, Serialize)]
+pub struct LauncherPaths {
+    /// Root holding `versions/`, `libraries/` and `assets/`
+    pub data_dir: PathBuf,
+    pub config_file: PathBuf,
+    pub instances_dir: PathBuf,
+}
+
+impl LauncherPaths {
+    pub fn new(data_dir: &Path, config_file: &Path) -> Self {
+        Self {
+            data_dir: data_dir.to_path_buf(),
+            config_file: config_file.to_path_buf(),
+            instances_dir: data_dir.join("instances"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemDiagnostics {
+    pub launcher_version: String,
+    #[serde(flatten)]
+    pub system: SystemInfo,
+    /// e.g. "Linux (Arch Linux rolling)" or "Windows 11 (26100)"
+    pub os_version: Option<String>,
+    pub kernel_version: Option<String>,
+    pub cpu_model: Option<String>,
+    pub available_memory_mb: u64,
+    pub gpus: Vec<String>,
+    /// "wayland" or "x11" on Linux
+    pub display_server: Option<String>,
+    /// Driver details and environment overrides worth knowing about
+    pub graphics_hints: Vec<String>,
+    pub paths: LauncherPaths,
+}
+
+impl SystemDiagnostics {
+    /// Header for uploaded logs. Paths are left out since they usually
+    /// contain the user's account name.
+    pub fn log_header(&self) -> String {
+        let mut lines = vec![format!(
+            "DropOut {} on {} {}",
+            self.launcher_version,
+            self.os_version.as_deref().unwrap_or(&self.system.os),
+            self.system.arch
+        )];
+        if let Some(kernel) = &self.kernel_version {
+            lines.push(format!("Kernel: {}", kernel));
+        }
+        lines.push(format!(
+            "CPU: {} ({} threads)",
+            self.cpu_model.as_deref().unwrap_or("unknown"),
+            self.system.cpu_count
+        ));
+        lines.push(format!(
+            "Memory: {} MB total, {} MB available",
+            self.system.total_memory_mb, self.available_memory_mb
+        ));
+        if self.gpus.is_empty() {
+            lines.push("GPU: unknown".to_string());
+        }
+        for gpu in &self.gpus {
+            lines.push(format!("GPU: {}", gpu));
+        }
+        if let Some(display) = &self.display_server {
+            lines.push(format!("Display: {}", display));
+        }
+        for hint in &self.graphics_hints {
+            lines.push(format!("Graphics: {}", hint));
+        }
+
+        let mut header = String::new();
+        for line in lines {
+            header.push_str("# ");
+            header.push_str(&line);
+            header.push('\n');
+        }
+        header
+    }
+}
+
+pub fn collect(paths: LauncherPaths) -> SystemDiagnostics {
+    let mut system = sysinfo::System::new();
+    system.refresh_memory();
+    system.refresh_cpu_list(sysinfo::CpuRefreshKind::nothing());
+    let cpu_model = system
+        .cpus()
+        .first()
+        .map(|cpu| cpu.brand().trim().to_string())
+        .filter(|brand| !brand.is_empty());
+
+    SystemDiagnostics {
+        launcher_version: env!("CARGO_PKG_VERSION").to_string(),
+        system: setup::detect_system(),
+        os_version: sysinfo::System::long_os_version(),
+        kernel_version: sysinfo::System::kernel_version(),
+        cpu_model,
+        available_memory_mb: system.available_memory() / 1024 / 1024,
+        gpus: probe_gpus(),
+        display_server: display_server(),
+        graphics_hints: graphics_hints(),
+        paths,
+    }
+}
+
+/// Files collected for a bug report, as archive names and contents.
+pub struct DiagnosticBundle {
+    files: Vec<(String, String)>,
+}
+
+impl DiagnosticBundle {
+    /// A bundle starting with `system.txt`.
+    pub fn new(diagnostics: &SystemDiagnostics) -> Self {
+        Self {
+            files: vec![("system.txt".to_string(), diagnostics.log_header())],
+        }
+    }
+
+    pub fn add(&mut self, name: &str, contents: String) {
+        self.files.push((name.to_string(), contents));
+    }
+
+    /// Add the end of a text file, if it exists.
+    pub fn add_file(&mut self, name: &str, path: &Path) {
+        if let Some(contents) = read_tail(path, MAX_BUNDLED_FILE) {
+            self.add(name, contents);
+        }
+    }
+
+    /// Add an instance's metadata, `latest.log` and newest crash report.
+    pub fn add_instance_files(&mut self, game_dir: &Path) {
+        self.add_file("instance/instance.json", &game_dir.join("instance.json"));
+        self.add_file(
+            "instance/latest.log",
+            &game_dir.join("logs").join("latest.log"),
+        );
+        if let Some(report) = newest_file(&game_dir.join("crash-reports")) {
+            let name = report.file_name().unwrap_or_default().to_string_lossy();
+            self.add_file(&format!("instance/crash-reports/{}", name), &report);
+        }
+    }
+
+    /// Scrub every file, see `redact::Redactor`.
+    pub fn redact(&mut self, redactor: &Redactor) {
+        for (_, contents) in &mut self.files {
+            *contents = redactor.redact(contents);
+        }
+    }
+
+    pub fn write_zip(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create dir: {}", e))?;
+        }
+        let file = std::fs::File::create(path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let mut writer = zip::ZipWriter::new(file);
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        for (name, contents) in &self.files {
+            writer
+                .start_file(name.as_str(), options)
+                .map_err(|e| format!("Failed to write {}: {}", name, e))?;
+            writer
+                .write_all(contents.as_bytes())
+                .map_err(|e| format!("Failed to write {}: {}", name, e))?;
+        }
+        writer
+            .finish()
+            .map_err(|e| format!("Failed to finish {}: {}", path.display(), e))?;
+        Ok(())
+    }
+
+    /// All files as one text for paste services, within about `max_len`
+    /// bytes. Files over their share of the budget keep their end, where
+    /// errors usually are.
+    pub fn to_text(&self, max_len: usize) -> String {
+        let budget = max_len / self.files.len().max(1);
+        let mut text = String::new();
+        for (name, contents) in &self.files {
+            let banner = format!("===== {} =====\n", name);
+            let kept = tail(contents, budget.saturating_sub(banner.len() + 1));
+            text.push_str(&banner);
+            text.push_str(kept);
+            if !kept.ends_with('\n') {
+                text.push('\n');
+            }
+        }
+        text
+    }
+}
+
+/// Settings as JSON with the API keys and proxy password removed.
+pub fn redacted_config(config: &LauncherConfig) -> String {
+    let mut config = config.clone();
+    let redact = |value: &mut Option<String>| {
+        if value.is_some() {
+            *value = Some(REDACTED.to_string());
+        }
+    };
+    redact(&mut config.pastebin_api_key);
+    redact(&mut config.curseforge_api_key);
+    redact(&mut config.proxy.password);
+    serde_json::to_string_pretty(&config).unwrap_or_default()
+}
+
+/// The last `max_len` bytes of `s`, moved forward to a character boundary.
+fn tail(s: &str, max_len: usize) -> &str {
+    if s.len() <= max_len {
+        return s;
+    }
+    let mut start = s.len() - max_len;
+    while !s.is_char_boundary(start) {
+        start += 1;
+    }
+    &s[start..]
+}
+
+fn read_tail(path: &Path, max_len: u64) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    if len > max_len {
+        file.seek(SeekFrom::Start(len - max_len)).ok()?;
+    }
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn newest_file(dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max()
+        .map(|(_, path)| path)
+}
+
+/// GPUs from `/sys/class/drm`, named by vendor and PCI id with the kernel
+/// driver bound to them, e.g. "NVIDIA [10de:2484] (nvidia)".
+#[cfg(target_os = "linux")]
+fn probe_gpus() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir("/sys/class/drm") else {
+        return Vec::new();
+    };
+    let mut gpus = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        // Connectors such as card0-HDMI-A-1 share the card's device
+        if !name.starts_with("card") || name.contains('-') {
+            continue;
+        }
+        let device = entry.path().join("device");
+        let read = |file: &str| {
+            std::fs::read_to_string(device.join(file))
+                .ok()
+                .map(|s| s.trim().trim_start_matches("0x").to_string())
+        };
+        let (Some(vendor_id), Some(device_id)) = (read("vendor"), read("device")) else {
+            continue;
+        };
+        let slot = std::fs::canonicalize(&device).unwrap_or(device.clone());
+        if !seen.insert(slot) {
+            continue;
+        }
+        let driver = std::fs::read_link(device.join("driver"))
+            .ok()
+            .and_then(|link| link.file_name().map(|n| n.to_string_lossy().to_string()));
+        let mut gpu = format!("{} [{}:{}]", pci_vendor(&vendor_id), vendor_id, device_id);
+        if let Some(driver) = driver {
+            gpu.push_str(&format!(" ({})", driver));
+        }
+        gpus.push(gpu);
+    }
+    gpus.sort();
+    gpus
+}
+
+#[cfg(target_os = "windows")]
+fn probe_gpus() -> Vec<String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+    const GPU_QUERY: &str = "Get-CimInstance Win32_VideoController | \
+        ForEach-Object { \"$($_.Name) (driver $($_.DriverVersion))\" }";
+
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-Command", GPU_QUERY])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output();
+    match output {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn probe_gpus() -> Vec<String> {
+    match std::process::Command::new("system_profiler")
+        .arg("SPDisplaysDataType")
+        .output()
+    {
+        Ok(output) if output.status.success() => {
+            parse_system_profiler(&String::from_utf8_lossy(&output.stdout))
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+fn probe_gpus() -> Vec<String> {
+    Vec::new()
+}
+
+/// Chipset models from `system_profiler SPDisplaysDataType`.
+#[cfg(any(target_os = "macos", test))]
+fn parse_system_profiler(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("Chipset Model:"))
+        .map(|model| model.trim().to_string())
+        .collect()
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn pci_vendor(vendor_id: &str) -> &'static str {
+    match vendor_id.to_ascii_lowercase().as_str() {
+        "10de" => "NVIDIA",
+        "1002" => "AMD",
+        "8086" => "Intel",
+        "1af4" => "Virtio",
+        "15ad" => "VMware",
+        _ => "Unknown vendor",
+    }
+}
+
+/// "wayland" or "x11" for the current Linux session
+#[cfg(target_os = "linux")]
+pub fn display_server() -> Option<String> {
+    let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+    session_display_server(
+        var("XDG_SESSION_TYPE").as_deref(),
+        var("WAYLAND_DISPLAY").is_some(),
+        var("DISPLAY").is_some(),
+    )
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn display_server() -> Option<String> {
+    None
+}
+
+/// The session type if it names a display server, otherwise guessed from
+/// which display variables are set.
+#[cfg(any(target_os = "linux", test))]
+fn session_display_server(
+    session_type: Option<&str>,
+    wayland_display: bool,
+    x_display: bool,
+) -> Option<String> {
+    match session_type {
+        Some(kind @ ("wayland" | "x11")) => Some(kind.to_string()),
+        _ if wayland_display => Some("wayland".to_string()),
+        _ if x_display => Some("x11".to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn graphics_hints() -> Vec<String> {
+    let mut hints = Vec::new();
+    if let Ok(version) = std::fs::read_to_string("/proc/driver/nvidia/version") {
+        if let Some(line) = version.lines().next() {
+            hints.push(line.trim().to_string());
+        }
+    }
+    if display_server().as_deref() == Some("wayland") {
+        let xwayland = std::env::var_os("DISPLAY").is_some();
+        hints.push(format!(
+            "Wayland session, XWayland {}",
+            if xwayland {
+                "available"
+            } else {
+                "not available"
+            }
+        ));
+    }
+    for name in GRAPHICS_VARIABLES {
+        if let Ok(value) = std::env::var(name) {
+            hints.push(format!("{}={}", name, value));
+        }
+    }
+    hints
+}
+
+#[cfg(not(target_os = "linux"))]
+fn graphics_hints() -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_display_server() {
+        assert_eq!(
+            session_display_server(Some("wayland"), false, true).as_deref(),
+            Some("wayland")
+        );
+        assert_eq!(
+            session_display_server(Some("tty"), false, true).as_deref(),
+            Some("x11")
+        );
+        assert_eq!(
+            session_display_server(None, true, true).as_deref(),
+            Some("wayland")
+        );
+        assert_eq!(session_display_server(None, false, false), None);
+    }
+
+    #[test]
+    fn test_parse_system_profiler() {
+        let output = "Graphics/Displays:\n\n    Apple M2:\n\n      Chipset Model: Apple M2\n      Type: GPU\n";
+        assert_eq!(parse_system_profiler(output), vec!["Apple M2"]);
+        assert_eq!(pci_vendor("10DE"), "NVIDIA");
+    }
+
+    #[test]
+    fn test_bundle_text_keeps_file_ends() {
+        let mut bundle = DiagnosticBundle { files: Vec::new() };
+        bundle.add("a.log", format!("{}END", "x".repeat(1000)));
+        bundle.add("b.txt", "short".to_string());
+        let text = bundle.to_text(200);
+        assert!(text.len() <= 200);
+        assert!(text.starts_with("===== a.log =====\n"));
+        assert!(text.contains("xEND\n===== b.txt =====\nshort\n"));
+        assert_eq!(tail("héllo", 4), "llo");
+    }
+
+    #[test]
+    fn test_redacted_config() {
+        let mut config = LauncherConfig {
+            pastebin_api_key: Some("key123".to_string()),
+            ..Default::default()
+        };
+        config.proxy.password = Some("hunter2".to_string());
+        let json = redacted_config(&config);
+        assert!(!json.contains("key123"));
+        assert!(!json.contains("hunter2"));
+        assert!(json.contains(REDACTED));
+    }
+
+    #[test]
+    fn test_log_header_omits_paths() {
+        let diagnostics = SystemDiagnostics {
+            launcher_version: "1.0.0".to_string(),
+            system: SystemInfo {
+                total_memory_mb: 16000,
+                cpu_count: 8,
+                os: "linux".to_string(),
+                arch: "x86_64".to_string(),
+            },
+            os_version: None,
+            kernel_version: Some("6.9.1".to_string()),
+            cpu_model: None,
+            available_memory_mb: 8000,
+            gpus: vec!["AMD [1002:73df] (amdgpu)".to_string()],
+            display_server: Some("wayland".to_string()),
+            graphics_hints: Vec::new(),
+            paths: LauncherPaths::new(Path::new("/home/alice/.dropout"), Path::new("/c.json")),
+        };
+        let header = diagnostics.log_header();
+        assert!(header.starts_with("# DropOut 1.0.0 on linux x86_64\n"));
+        assert!(header.contains("# GPU: AMD [1002:73df] (amdgpu)\n"));
+        assert!(header.contains("# CPU: unknown (8 threads)\n"));
+        assert!(!header.contains("alice"));
+    }
+}
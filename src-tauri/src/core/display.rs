@@ -0,0 +1,153 @@
+//! Wayland and X11 handling for the game window on Linux.
+//!
+//! The GLFW bundled with LWJGL talks to X11, which on a Wayland session
+//! means XWayland. `DisplayBackend::Wayland` runs the game natively through
+//! a system GLFW built with Wayland support, `Xwayland` hides the Wayland
+//! socket so everything falls back to X11, and `Auto` only goes native when
+//! there is no XWayland to fall back to. Java's own windows, like crash
+//! dialogs and installers, need `_JAVA_AWT_WM_NONREPARENTING` on Wayland
+//! compositors. Other platforms and X11 sessions are left alone.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisplayBackend {
+    #[default]
+    Auto,
+    Wayland,
+    Xwayland,
+}
+
+/// JVM arguments and environment changes for the game process.
+#[derive(Debug, Default, PartialEq)]
+pub struct DisplaySetup {
+    pub jvm_args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub env_remove: Vec<String>,
+    /// Problems worth a line in the launch log
+    pub notes: Vec<String>,
+}
+
+impl DisplaySetup {
+    pub fn extend(&mut self, other: DisplaySetup) {
+        self.jvm_args.extend(other.jvm_args);
+        self.env.extend(other.env);
+        self.env_remove.extend(other.env_remove);
+        self.notes.extend(other.notes);
+    }
+
+    pub fn apply(&self, command: &mut tokio::process::Command) {
+        for name in &self.env_remove {
+            command.env_remove(name);
+        }
+        command.envs(self.env.iter().map(|(name, value)| (name, value)));
+    }
+}
+
+/// Setup for running the game with `backend` in the current session.
+pub fn setup(backend: DisplayBackend) -> DisplaySetup {
+    if !cfg!(target_os = "linux") {
+        return DisplaySetup::default();
+    }
+    let session = crate::core::diagnostics::display_server();
+    let xwayland = std::env::var_os("DISPLAY").is_some_and(|display| !display.is_empty());
+    plan(backend, session.as_deref(), xwayland, find_system_glfw())
+}
+
+fn plan(
+    backend: DisplayBackend,
+    session: Option<&str>,
+    xwayland: bool,
+    system_glfw: Option<PathBuf>,
+) -> DisplaySetup {
+    let mut setup = DisplaySetup::default();
+    if session != Some("wayland") {
+        return setup;
+    }
+    let mut set = |name: &str, value: &str| setup.env.push((name.to_string(), value.to_string()));
+    set("_JAVA_AWT_WM_NONREPARENTING", "1");
+
+    let native = match backend {
+        DisplayBackend::Wayland => true,
+        DisplayBackend::Xwayland => false,
+        DisplayBackend::Auto => !xwayland,
+    };
+    if native {
+        set("GDK_BACKEND", "wayland");
+        match system_glfw {
+            Some(path) => setup
+                .jvm_args
+                .push(format!("-Dorg.lwjgl.glfw.libname={}", path.display())),
+            None => setup.notes.push(
+                "No system GLFW found; install GLFW with Wayland support to run natively"
+                    .to_string(),
+            ),
+        }
+    } else if xwayland {
+        if backend == DisplayBackend::Xwayland {
+            set("GDK_BACKEND", "x11");
+            setup.env_remove.push("WAYLAND_DISPLAY".to_string());
+        }
+    } else {
+        setup
+            .notes
+            .push("XWayland is not running; the game may fail to open a window".to_string());
+    }
+    setup
+}
+
+/// A GLFW shared library installed by the system's package manager.
+fn find_system_glfw() -> Option<PathBuf> {
+    const LIB_DIRS: &[&str] = &[
+        "/usr/lib",
+        "/usr/lib64",
+        "/usr/local/lib",
+        "/usr/lib/x86_64-linux-gnu",
+        "/usr/lib/aarch64-linux-gnu",
+    ];
+    LIB_DIRS
+        .iter()
+        .flat_map(|dir| ["libglfw.so.3", "libglfw.so"].map(|name| PathBuf::from(dir).join(name)))
+        .find(|path| path.exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan() {
+        let glfw = Some(PathBuf::from("/usr/lib/libglfw.so.3"));
+        assert_eq!(
+            plan(DisplayBackend::Wayland, Some("x11"), true, glfw.clone()),
+            DisplaySetup::default()
+        );
+
+        let native = plan(DisplayBackend::Wayland, Some("wayland"), true, glfw.clone());
+        assert_eq!(
+            native.jvm_args,
+            ["-Dorg.lwjgl.glfw.libname=/usr/lib/libglfw.so.3"]
+        );
+        assert!(native
+            .env
+            .contains(&("GDK_BACKEND".to_string(), "wayland".to_string())));
+
+        let forced = plan(
+            DisplayBackend::Xwayland,
+            Some("wayland"),
+            true,
+            glfw.clone(),
+        );
+        assert!(forced.jvm_args.is_empty());
+        assert_eq!(forced.env_remove, ["WAYLAND_DISPLAY"]);
+
+        // Without XWayland, Auto has to go native
+        let auto = plan(DisplayBackend::Auto, Some("wayland"), false, None);
+        assert!(auto
+            .env
+            .contains(&("GDK_BACKEND".to_string(), "wayland".to_string())));
+        assert_eq!(auto.notes.len(), 1);
+    }
+}
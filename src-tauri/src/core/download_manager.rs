@@ -0,0 +1,452 @@
+//! Global download scheduling.
+//!
+//! Every download (game files, Java runtimes) takes a permit from the shared
+//! `DownloadManager` before opening a connection. Permits are handed out by
+//! priority, so a client jar or library queued after thousands of assets still
+//! starts next, and per host, so a single CDN never gets more than
+//! `max_per_host` connections. The manager also tracks destination paths
+//! being written, letting a second request for the same file wait for the
+//! first one instead of downloading it twice.
+//!
+//! Small files such as asset objects take only a fraction of a slot. They
+//! are mostly round trips, and over HTTP/2 they share one connection, so
+//! running many at once costs little and shortens launches considerably.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{oneshot, watch};
+
+/// Download priority; lower variants are scheduled first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadPriority {
+    /// Client jars, asset indexes, logging configs
+    Critical,
+    Library,
+    /// Java runtimes
+    Runtime,
+    Asset,
+}
+
+impl DownloadPriority {
+    /// Classify a game file by where it is stored.
+    pub fn classify(path: &Path) -> Self {
+        let mut in_assets = false;
+        for component in path.components() {
+            if let Component::Normal(name) = component {
+                match name.to_str() {
+                    Some("libraries") => return DownloadPriority::Library,
+                    Some("assets") => in_assets = true,
+                    Some("objects") if in_assets => return DownloadPriority::Asset,
+                    _ => {}
+                }
+            }
+        }
+        DownloadPriority::Critical
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueueStatus {
+    Queued,
+    Active,
+}
+
+/// A download known to the manager.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueEntry {
+    pub url: String,
+    pub file_name: String,
+    pub priority: DownloadPriority,
+    pub status: QueueStatus,
+}
+
+/// Snapshot of the queue for the UI.
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadQueueInfo {
+    pub active: Vec<QueueEntry>,
+    /// The next queued downloads, in scheduling order (at most `QUEUE_PREVIEW_LEN`)
+    pub queued: Vec<QueueEntry>,
+    pub total_queued: usize,
+    pub max_concurrent: usize,
+    pub max_per_host: usize,
+}
+
+const QUEUE_PREVIEW_LEN: usize = 100;
+
+/// Files up to this size count as small, see `SMALL_FILES_PER_SLOT`
+pub const SMALL_FILE_SIZE: u64 = 256 * 1024;
+/// How many small files share one download slot
+pub const SMALL_FILES_PER_SLOT: usize = 4;
+
+/// Share of a slot, in units of one small file.
+fn weight_of(size: Option<u64>) -> usize {
+    match size {
+        Some(size) if size <= SMALL_FILE_SIZE => 1,
+        _ => SMALL_FILES_PER_SLOT,
+    }
+}
+
+struct Waiter {
+    id: u64,
+    host: String,
+    weight: usize,
+    tx: oneshot::Sender<DownloadPermit>,
+}
+
+struct Inner {
+    max_concurrent: usize,
+    max_per_host: usize,
+    /// Weight of running downloads, see `weight_of`
+    active: usize,
+    host_active: HashMap<String, usize>,
+    /// Keyed by (priority, arrival order)
+    waiting: BTreeMap<(DownloadPriority, u64), Waiter>,
+    entries: HashMap<u64, QueueEntry>,
+    next_id: u64,
+    /// Destination paths currently being written
+    in_flight: HashMap<PathBuf, watch::Receiver<bool>>,
+}
+
+/// Managed state scheduling all downloads.
+#[derive(Clone)]
+pub struct DownloadManager {
+    inner: Arc<Mutex<Inner>>,
+}
+
+/// Held while a download is running; dropping it frees the slot.
+pub struct DownloadPermit {
+    manager: DownloadManager,
+    id: u64,
+    host: String,
+    weight: usize,
+}
+
+impl Drop for DownloadPermit {
+    fn drop(&mut self) {
+        let rejected = {
+            let mut inner = self.manager.inner.lock().unwrap();
+            inner.active = inner.active.saturating_sub(self.weight);
+            if let Some(count) = inner.host_active.get_mut(&self.host) {
+                *count = count.saturating_sub(self.weight);
+            }
+            inner.entries.remove(&self.id);
+            self.manager.dispatch(&mut inner)
+        };
+        // Permits for waiters that gave up are released outside the lock
+        drop(rejected);
+    }
+}
+
+/// Result of claiming a destination path.
+pub enum FileClaim {
+    /// The caller downloads the file; dropping the guard wakes waiters
+    Owner(FileGuard),
+    /// Another download is writing this file; wait on the receiver
+    InProgress(watch::Receiver<bool>),
+}
+
+pub struct FileGuard {
+    manager: DownloadManager,
+    path: PathBuf,
+    tx: watch::Sender<bool>,
+}
+
+impl Drop for FileGuard {
+    fn drop(&mut self) {
+        self.manager
+            .inner
+            .lock()
+            .unwrap()
+            .in_flight
+            .remove(&self.path);
+        let _ = self.tx.send(true);
+    }
+}
+
+impl Default for DownloadManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DownloadManager {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                max_concurrent: 32,
+                max_per_host: 16,
+                active: 0,
+                host_active: HashMap::new(),
+                waiting: BTreeMap::new(),
+                entries: HashMap::new(),
+                next_id: 0,
+                in_flight: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Update the global and per-host connection limits.
+    pub fn configure(&self, max_concurrent: usize, max_per_host: usize) {
+        let rejected = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.max_concurrent = max_concurrent.max(1);
+            inner.max_per_host = max_per_host.max(1);
+            self.dispatch(&mut inner)
+        };
+        drop(rejected);
+    }
+
+    /// Wait for a download slot. `size` is the expected file size, if known;
+    /// small files take only part of a slot.
+    pub async fn acquire(
+        &self,
+        url: &str,
+        file_name: &str,
+        priority: DownloadPriority,
+        size: Option<u64>,
+    ) -> DownloadPermit {
+        let host = host_of(url);
+        let weight = weight_of(size);
+        let (rx, rejected) = {
+            let mut inner = self.inner.lock().unwrap();
+            let id = inner.next_id;
+            inner.next_id += 1;
+            inner.entries.insert(
+                id,
+                QueueEntry {
+                    url: url.to_string(),
+                    file_name: file_name.to_string(),
+                    priority,
+                    status: QueueStatus::Queued,
+                },
+            );
+
+            let (tx, rx) = oneshot::channel();
+            inner.waiting.insert(
+                (priority, id),
+                Waiter {
+                    id,
+                    host,
+                    weight,
+                    tx,
+                },
+            );
+            // Starts this download right away if it is next and fits
+            (rx, self.dispatch(&mut inner))
+        };
+        drop(rejected);
+
+        // The sender is only dropped together with the manager
+        rx.await.expect("download manager dropped")
+    }
+
+    /// Hand free slots to the highest-priority waiters whose host is under its cap.
+    ///
+    /// A waiter that does not fit in the free slots blocks those behind it.
+    /// Must be called with the lock held. Returns permits whose waiter went away;
+    /// the caller drops them after releasing the lock.
+    fn dispatch(&self, inner: &mut Inner) -> Vec<DownloadPermit> {
+        let mut rejected = Vec::new();
+        loop {
+            let key = inner
+                .waiting
+                .iter()
+                .find(|(_, w)| inner.host_fits(&w.host, w.weight))
+                .map(|(key, w)| (*key, w.weight));
+            let Some((key, weight)) = key else {
+                break;
+            };
+            if inner.active + weight > inner.max_concurrent * SMALL_FILES_PER_SLOT {
+                break;
+            }
+            let waiter = inner.waiting.remove(&key).unwrap();
+
+            inner.active += weight;
+            *inner.host_active.entry(waiter.host.clone()).or_insert(0) += weight;
+            if let Some(entry) = inner.entries.get_mut(&waiter.id) {
+                entry.status = QueueStatus::Active;
+            }
+            let permit = DownloadPermit {
+                manager: self.clone(),
+                id: waiter.id,
+                host: waiter.host,
+                weight,
+            };
+            if let Err(permit) = waiter.tx.send(permit) {
+                rejected.push(permit);
+            }
+        }
+        rejected
+    }
+
+    /// Claim a destination path so concurrent requests for it are not downloaded twice.
+    pub fn claim_file(&self, path: &Path) -> FileClaim {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(rx) = inner.in_flight.get(path) {
+            return FileClaim::InProgress(rx.clone());
+        }
+        let (tx, rx) = watch::channel(false);
+        inner.in_flight.insert(path.to_path_buf(), rx);
+        FileClaim::Owner(FileGuard {
+            manager: self.clone(),
+            path: path.to_path_buf(),
+            tx,
+        })
+    }
+
+    /// No download is running or waiting.
+    pub fn is_idle(&self) -> bool {
+        self.inner.lock().unwrap().entries.is_empty()
+    }
+
+    /// Current queue state.
+    pub fn snapshot(&self) -> DownloadQueueInfo {
+        let inner = self.inner.lock().unwrap();
+        let mut active: Vec<QueueEntry> = inner
+            .entries
+            .values()
+            .filter(|e| e.status == QueueStatus::Active)
+            .cloned()
+            .collect();
+        active.sort_by_key(|e| e.priority);
+        let queued = inner
+            .waiting
+            .values()
+            .take(QUEUE_PREVIEW_LEN)
+            .filter_map(|w| inner.entries.get(&w.id).cloned())
+            .collect();
+
+        DownloadQueueInfo {
+            active,
+            queued,
+            total_queued: inner.waiting.len(),
+            max_concurrent: inner.max_concurrent,
+            max_per_host: inner.max_per_host,
+        }
+    }
+}
+
+impl Inner {
+    fn host_fits(&self, host: &str, weight: usize) -> bool {
+        let host_active = self.host_active.get(host).copied().unwrap_or(0);
+        host_active + weight <= self.max_per_host * SMALL_FILES_PER_SLOT
+    }
+}
+
+fn host_of(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_string()))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let game_dir = Path::new("/data");
+        assert_eq!(
+            DownloadPriority::classify(&game_dir.join("versions/1.20.4/1.20.4.jar")),
+            DownloadPriority::Critical
+        );
+        assert_eq!(
+            DownloadPriority::classify(&game_dir.join("libraries/org/lwjgl/lwjgl.jar")),
+            DownloadPriority::Library
+        );
+        assert_eq!(
+            DownloadPriority::classify(&game_dir.join("assets/indexes/12.json")),
+            DownloadPriority::Critical
+        );
+        assert_eq!(
+            DownloadPriority::classify(&game_dir.join("assets/objects/ab/abcdef")),
+            DownloadPriority::Asset
+        );
+    }
+
+    #[tokio::test]
+    async fn test_priority_order() {
+        let manager = DownloadManager::new();
+        manager.configure(1, 1);
+
+        let first = manager
+            .acquire("https://a.example/1", "1", DownloadPriority::Asset, None)
+            .await;
+
+        let asset = {
+            let m = manager.clone();
+            tokio::spawn(async move {
+                m.acquire("https://a.example/2", "2", DownloadPriority::Asset, None)
+                    .await
+            })
+        };
+        tokio::task::yield_now().await;
+        let library = {
+            let m = manager.clone();
+            tokio::spawn(async move {
+                m.acquire("https://a.example/3", "3", DownloadPriority::Library, None)
+                    .await
+            })
+        };
+        tokio::task::yield_now().await;
+
+        assert_eq!(manager.snapshot().total_queued, 2);
+        assert_eq!(
+            manager.snapshot().queued[0].priority,
+            DownloadPriority::Library
+        );
+
+        drop(first);
+        let library_permit = library.await.unwrap();
+        assert_eq!(manager.snapshot().active[0].file_name, "3");
+        drop(library_permit);
+        drop(asset.await.unwrap());
+        assert_eq!(manager.snapshot().total_queued, 0);
+    }
+
+    #[tokio::test]
+    async fn test_small_files_share_a_slot() {
+        let manager = DownloadManager::new();
+        manager.configure(1, 1);
+
+        let mut small = Vec::new();
+        for i in 0..SMALL_FILES_PER_SLOT {
+            let url = format!("https://a.example/{}", i);
+            small.push(
+                manager
+                    .acquire(&url, "small", DownloadPriority::Asset, Some(1024))
+                    .await,
+            );
+        }
+        assert_eq!(manager.snapshot().active.len(), SMALL_FILES_PER_SLOT);
+
+        let large = {
+            let m = manager.clone();
+            tokio::spawn(async move {
+                m.acquire(
+                    "https://a.example/large",
+                    "large",
+                    DownloadPriority::Asset,
+                    None,
+                )
+                .await
+            })
+        };
+        tokio::task::yield_now().await;
+        assert_eq!(manager.snapshot().total_queued, 1);
+
+        // The large file needs the whole slot
+        small.pop();
+        tokio::task::yield_now().await;
+        assert_eq!(manager.snapshot().total_queued, 1);
+        small.clear();
+        let large = large.await.unwrap();
+        assert_eq!(manager.snapshot().active[0].file_name, "large");
+        drop(large);
+    }
+}
@@ -0,0 +1,181 @@
+//! Download statistics.
+//!
+//! Every `download_files` run (an install or the checks before a launch) and
+//! every Java runtime download is appended to `download_stats.json` with the
+//! bytes it fetched and the files it could skip because they were already
+//! valid, so users on metered connections can see what launches cost. Only the newest `MAX_RUNS` runs
+//! are kept; aggregates are computed on demand.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const MAX_RUNS: usize = 500;
+/// Runs listed in `DownloadStats::recent`
+const RECENT_RUNS: usize = 20;
+const MONTH_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// One `download_files` run or Java runtime download.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadRun {
+    pub instance_id: Option<String>,
+    /// Launch or install session the run belonged to
+    pub session_id: Option<String>,
+    /// Unix timestamp (seconds)
+    pub finished_at: u64,
+    pub duration_ms: u64,
+    /// Files fetched from the network
+    pub downloaded_files: usize,
+    pub downloaded_bytes: u64,
+    /// Files already present and valid
+    pub skipped_files: usize,
+    pub skipped_bytes: u64,
+    pub failed_files: usize,
+}
+
+impl DownloadRun {
+    pub fn average_speed_bps(&self) -> u64 {
+        (self.downloaded_bytes * 1000)
+            .checked_div(self.duration_ms)
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct DownloadStatsFile {
+    runs: Vec<DownloadRun>,
+}
+
+/// A run with its derived figures, for the UI.
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadRunSummary {
+    #[serde(flatten)]
+    pub run: DownloadRun,
+    pub average_speed_bps: u64,
+}
+
+/// Totals over the recorded runs.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DownloadStats {
+    pub runs: usize,
+    pub downloaded_bytes: u64,
+    pub downloaded_files: usize,
+    pub skipped_files: usize,
+    pub failed_files: usize,
+    pub last_30_days_bytes: u64,
+    /// Share of files that were already valid, 0 to 1
+    pub cache_hit_rate: f64,
+    /// Over the time spent in runs that downloaded anything
+    pub average_speed_bps: u64,
+    /// Newest first
+    pub recent: Vec<DownloadRunSummary>,
+}
+
+/// Managed state holding recorded runs.
+pub struct DownloadStatsState {
+    runs: Mutex<Vec<DownloadRun>>,
+    path: PathBuf,
+}
+
+impl DownloadStatsState {
+    pub fn new(app_data_dir: &Path) -> Self {
+        let path = app_data_dir.join("download_stats.json");
+        let file: DownloadStatsFile = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            runs: Mutex::new(file.runs),
+            path,
+        }
+    }
+
+    /// Append a run and persist.
+    pub fn record(&self, run: DownloadRun) -> Result<(), String> {
+        let mut runs = self.runs.lock().unwrap();
+        runs.push(run);
+        let excess = runs.len().saturating_sub(MAX_RUNS);
+        runs.drain(..excess);
+        let content = serde_json::to_string_pretty(&DownloadStatsFile { runs: runs.clone() })
+            .map_err(|e| e.to_string())?;
+        fs::write(&self.path, content).map_err(|e| e.to_string())
+    }
+
+    pub fn stats(&self, now: u64) -> DownloadStats {
+        compute_stats(&self.runs.lock().unwrap(), now)
+    }
+}
+
+pub fn compute_stats(runs: &[DownloadRun], now: u64) -> DownloadStats {
+    let mut stats = DownloadStats {
+        runs: runs.len(),
+        ..Default::default()
+    };
+    let month_start = now.saturating_sub(MONTH_SECS);
+    let mut download_ms = 0;
+    for run in runs {
+        stats.downloaded_bytes += run.downloaded_bytes;
+        stats.downloaded_files += run.downloaded_files;
+        stats.skipped_files += run.skipped_files;
+        stats.failed_files += run.failed_files;
+        if run.finished_at > month_start {
+            stats.last_30_days_bytes += run.downloaded_bytes;
+        }
+        if run.downloaded_bytes > 0 {
+            download_ms += run.duration_ms;
+        }
+    }
+    let checked = stats.downloaded_files + stats.skipped_files;
+    if checked > 0 {
+        stats.cache_hit_rate = stats.skipped_files as f64 / checked as f64;
+    }
+    stats.average_speed_bps = (stats.downloaded_bytes * 1000)
+        .checked_div(download_ms)
+        .unwrap_or(0);
+    stats.recent = runs
+        .iter()
+        .rev()
+        .take(RECENT_RUNS)
+        .map(|run| DownloadRunSummary {
+            average_speed_bps: run.average_speed_bps(),
+            run: run.clone(),
+        })
+        .collect();
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_stats() {
+        let runs = vec![
+            DownloadRun {
+                finished_at: 100,
+                duration_ms: 2000,
+                downloaded_files: 3,
+                downloaded_bytes: 4_000_000,
+                skipped_files: 1,
+                ..Default::default()
+            },
+            DownloadRun {
+                finished_at: 5_000_000,
+                duration_ms: 50,
+                skipped_files: 4,
+                skipped_bytes: 4_000_000,
+                ..Default::default()
+            },
+        ];
+        let stats = compute_stats(&runs, 5_000_100);
+        assert_eq!(stats.downloaded_bytes, 4_000_000);
+        assert_eq!(stats.last_30_days_bytes, 0);
+        assert_eq!(stats.cache_hit_rate, 5.0 / 8.0);
+        assert_eq!(stats.average_speed_bps, 2_000_000);
+        assert_eq!(stats.recent[0].run.finished_at, 5_000_000);
+        assert_eq!(stats.recent[1].average_speed_bps, 2_000_000);
+    }
+}
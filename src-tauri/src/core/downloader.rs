@@ -1,13 +1,23 @@
-use futures::StreamExt;
+use futures::{FutureExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use sha1::Digest as Sha1Digest;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
-use std::sync::Arc;
-use tauri::{AppHandle, Emitter, Manager, Window};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::io::{AsyncSeekExt, AsyncWriteExt};
 use tokio::sync::Semaphore;
 
+use crate::core::config::VerificationMode;
+use crate::core::download_manager::{
+    DownloadManager, DownloadPriority, FileClaim, SMALL_FILES_PER_SLOT,
+};
+use crate::core::events::{DownloadCompleted, DownloadStarted, EventSink, EventTarget};
+use crate::core::file_index::FileIndexState;
+use crate::core::multi_source::{self, MultiSourceError};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DownloadTask {
     pub url: String,
@@ -16,6 +26,13 @@ pub struct DownloadTask {
     pub sha1: Option<String>,
     #[serde(default)]
     pub sha256: Option<String>,
+    /// Expected size in bytes, used by the `Fast` and `Index` verification modes
+    #[serde(default)]
+    pub size: Option<u64>,
+    /// Other URLs serving the same file. Large files are fetched from all of
+    /// them at once, see `multi_source`.
+    #[serde(default)]
+    pub mirrors: Vec<String>,
 }
 
 /// Metadata for resumable downloads stored in .part.meta file
@@ -54,6 +71,8 @@ pub struct JavaDownloadProgress {
 /// Pending download task for queue persistence
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PendingJavaDownload {
+    #[serde(default)]
+    pub vendor: crate::core::java::JavaVendor,
     pub major_version: u32,
     pub image_type: String,
     pub download_url: String,
@@ -64,10 +83,106 @@ pub struct PendingJavaDownload {
     pub created_at: u64,
 }
 
+/// What a `download_files` run was for, deciding how it is resumed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingKind {
+    /// Files a launch or install needs; downloading them again is enough
+    #[default]
+    GameFiles,
+    /// New files of a modpack update, which also removes old files and
+    /// writes overrides after the download; an interrupted one is rolled back
+    PackUpdate,
+}
+
+/// Game files of a `download_files` run that were still missing when it
+/// started; removed once the run ends, so a leftover entry means the
+/// launcher was closed mid-download.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingFileDownload {
+    pub id: String,
+    #[serde(default)]
+    pub kind: PendingKind,
+    pub instance_id: Option<String>,
+    pub tasks: Vec<DownloadTask>,
+    pub created_at: u64,
+    /// Instance backup taken before a `PackUpdate`, restored when it is rolled back
+    #[serde(default)]
+    pub backup: Option<PathBuf>,
+}
+
+/// A `PendingFileDownload` without its task list, for the UI.
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingFilesSummary {
+    pub id: String,
+    pub kind: PendingKind,
+    pub instance_id: Option<String>,
+    pub file_count: usize,
+    /// Known sizes only
+    pub total_bytes: u64,
+    pub created_at: u64,
+}
+
+impl PendingFileDownload {
+    pub fn summary(&self) -> PendingFilesSummary {
+        PendingFilesSummary {
+            id: self.id.clone(),
+            kind: self.kind,
+            instance_id: self.instance_id.clone(),
+            file_count: self.tasks.len(),
+            total_bytes: self.tasks.iter().filter_map(|task| task.size).sum(),
+            created_at: self.created_at,
+        }
+    }
+}
+
 /// Download queue for persistence
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct DownloadQueue {
+    #[serde(default)]
     pub pending_downloads: Vec<PendingJavaDownload>,
+    /// Interrupted game file downloads
+    #[serde(default)]
+    pub pending_files: Vec<PendingFileDownload>,
+}
+
+/// Serializes read-modify-write cycles of `download_queue.json`
+static QUEUE_LOCK: Mutex<()> = Mutex::new(());
+/// Ids of `pending_files` entries whose run is still going in this process
+static RUNNING_FILE_DOWNLOADS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Interrupted downloads of every kind.
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingDownloads {
+    pub java: Vec<PendingJavaDownload>,
+    pub files: Vec<PendingFilesSummary>,
+}
+
+impl PendingDownloads {
+    pub fn load(app_handle: &AppHandle) -> Self {
+        let queue = DownloadQueue::load(app_handle);
+        let running = RUNNING_FILE_DOWNLOADS.lock().unwrap();
+        Self {
+            java: queue.pending_downloads,
+            files: queue
+                .pending_files
+                .iter()
+                .filter(|pending| !running.contains(&pending.id))
+                .map(PendingFileDownload::summary)
+                .collect(),
+        }
+    }
+}
+
+/// Result of `resume_pending_downloads`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResumeReport {
+    pub java: Vec<crate::core::java::JavaInstallation>,
+    /// Game files checked or downloaded
+    pub files: usize,
+    /// Interrupted modpack updates whose partial files were removed
+    pub reverted_pack_updates: usize,
+    pub errors: Vec<String>,
 }
 
 impl DownloadQueue {
@@ -115,6 +230,32 @@ impl DownloadQueue {
             !(d.major_version == major_version && d.image_type == image_type)
         });
     }
+
+    /// Load, change and save the queue while no other caller does.
+    pub fn update(app_handle: &AppHandle, f: impl FnOnce(&mut Self)) -> Result<(), String> {
+        let _guard = QUEUE_LOCK.lock().unwrap();
+        let mut queue = Self::load(app_handle);
+        f(&mut queue);
+        queue.save(app_handle)
+    }
+
+    /// Remove and return interrupted game file downloads, leaving those
+    /// still running.
+    pub fn take_pending_files(app_handle: &AppHandle) -> Vec<PendingFileDownload> {
+        let mut taken = Vec::new();
+        let result = Self::update(app_handle, |queue| {
+            let running = RUNNING_FILE_DOWNLOADS.lock().unwrap();
+            let (keep, take) = std::mem::take(&mut queue.pending_files)
+                .into_iter()
+                .partition(|pending| running.contains(&pending.id));
+            queue.pending_files = keep;
+            taken = take;
+        });
+        if let Err(e) = result {
+            println!("[Download] Failed to update download queue: {}", e);
+        }
+        taken
+    }
 }
 
 /// Global cancel flag for Java downloads
@@ -146,15 +287,59 @@ fn get_segment_count(file_size: u64) -> usize {
     }
 }
 
-/// Download a large file with resume support and progress events
+/// Download a large file with resume support and progress events.
+///
+/// `urls` are mirrors of the same file; segments are spread over those that
+/// support range requests. Without any, the file is fetched in one stream.
+/// `bandwidth_limit` caps all segments together, in bytes per second.
 pub async fn download_with_resume(
     app_handle: &AppHandle,
-    url: &str,
+    urls: &[String],
+    dest_path: &PathBuf,
+    checksum: Option<&str>,
+    total_size: u64,
+    bandwidth_limit: Option<u64>,
+) -> Result<(), String> {
+    let started = std::time::Instant::now();
+    let fetched = Arc::new(AtomicU64::new(0));
+    let result = resume_download(
+        app_handle,
+        urls,
+        dest_path,
+        checksum,
+        total_size,
+        bandwidth_limit,
+        fetched.clone(),
+    )
+    .await;
+    let failed = result.is_err() as usize;
+    record_download_run(
+        app_handle,
+        crate::core::download_stats::DownloadRun {
+            finished_at: unix_now(),
+            duration_ms: started.elapsed().as_millis() as u64,
+            downloaded_files: 1 - failed,
+            downloaded_bytes: fetched.load(Ordering::Relaxed),
+            failed_files: failed,
+            ..Default::default()
+        },
+    );
+    result
+}
+
+/// `download_with_resume` without the statistics; `fetched` counts the bytes
+/// received from the network in this call.
+async fn resume_download(
+    app_handle: &AppHandle,
+    urls: &[String],
     dest_path: &PathBuf,
     checksum: Option<&str>,
     total_size: u64,
+    bandwidth_limit: Option<u64>,
+    fetched: Arc<AtomicU64>,
 ) -> Result<(), String> {
     reset_java_download_cancel();
+    let url = urls.first().ok_or("No download URL")?.as_str();
 
     let part_path = dest_path.with_extension(
         dest_path
@@ -196,8 +381,27 @@ pub async fn download_with_resume(
         .map_err(|e| format!("Failed to open part file: {}", e))?;
 
     let file = Arc::new(tokio::sync::Mutex::new(file));
-    let client = reqwest::Client::new();
+    let client = crate::core::http::client();
+    let mut sources = Vec::new();
+    for source in urls {
+        if multi_source::supports_ranges(&client, source).await {
+            sources.push(source.clone());
+        }
+    }
+    if sources.is_empty() {
+        sources.push(url.to_string());
+        if metadata.segments.len() > 1 {
+            metadata.segments = vec![DownloadSegment {
+                start: 0,
+                end: total_size.saturating_sub(1),
+                downloaded: 0,
+                completed: false,
+            }];
+            metadata.downloaded_bytes = 0;
+        }
+    }
     let progress = Arc::new(AtomicU64::new(metadata.downloaded_bytes));
+    let limiter = bandwidth_limit.map(|limit| Arc::new(RateLimiter::new(limit)));
     let start_time = std::time::Instant::now();
     let last_progress_bytes = Arc::new(AtomicU64::new(metadata.downloaded_bytes));
 
@@ -212,9 +416,10 @@ pub async fn download_with_resume(
         }
 
         let client = client.clone();
-        let url = url.to_string();
+        let url = sources[idx % sources.len()].clone();
         let file = file.clone();
         let progress = progress.clone();
+        let fetched = fetched.clone();
         let semaphore = semaphore.clone();
         let segment_start = segment.start + segment.downloaded;
         let segment_end = segment.end;
@@ -223,9 +428,15 @@ pub async fn download_with_resume(
         let total_size = total_size;
         let last_progress_bytes = last_progress_bytes.clone();
         let start_time = start_time.clone();
+        let limiter = limiter.clone();
+
+        let manager = app_handle.state::<DownloadManager>().inner().clone();
 
         let handle = tokio::spawn(async move {
             let _permit = semaphore.acquire().await.unwrap();
+            let _slot = manager
+                .acquire(&url, &file_name, DownloadPriority::Runtime, None)
+                .await;
 
             if is_java_download_cancelled() {
                 return Err("Download cancelled".to_string());
@@ -269,6 +480,10 @@ pub async fn download_with_resume(
                 }
 
                 current_pos += chunk_len;
+                if let Some(limiter) = &limiter {
+                    limiter.consume(chunk_len).await;
+                }
+                fetched.fetch_add(chunk_len, Ordering::Relaxed);
                 let total_downloaded = progress.fetch_add(chunk_len, Ordering::Relaxed) + chunk_len;
 
                 // Emit progress event (throttled)
@@ -421,6 +636,13 @@ pub struct ProgressEvent {
     pub completed_files: usize,
     pub total_files: usize,
     pub total_downloaded_bytes: u64,
+    /// Expected size of all files; files of unknown size count as 0
+    pub total_bytes: u64,
+    /// Moving average of the network throughput; files that were already
+    /// valid do not count
+    pub speed_bps: u64,
+    /// Estimated time left, 0 while the speed is unknown
+    pub eta_seconds: u64,
 }
 
 /// calculate SHA256 hash of data
@@ -454,134 +676,600 @@ struct ProgressSnapshot {
     completed_files: usize,
     total_files: usize,
     total_downloaded_bytes: u64,
+    total_bytes: u64,
+    speed_bps: u64,
+    eta_seconds: u64,
+}
+
+/// Minimum time between throughput samples
+const THROUGHPUT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Network throughput as a moving average over `THROUGHPUT_INTERVAL` samples.
+struct Throughput {
+    last_sample: Instant,
+    last_bytes: u64,
+    bytes_per_second: f64,
+}
+
+impl Throughput {
+    fn new(now: Instant) -> Self {
+        Self {
+            last_sample: now,
+            last_bytes: 0,
+            bytes_per_second: 0.0,
+        }
+    }
+
+    /// Fold in the byte count at `now`, at most once per `THROUGHPUT_INTERVAL`.
+    fn sample(&mut self, network_bytes: u64, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_sample);
+        if elapsed < THROUGHPUT_INTERVAL {
+            return;
+        }
+        let rate = network_bytes.saturating_sub(self.last_bytes) as f64 / elapsed.as_secs_f64();
+        self.bytes_per_second = if self.bytes_per_second == 0.0 {
+            rate
+        } else {
+            self.bytes_per_second * 0.7 + rate * 0.3
+        };
+        self.last_sample = now;
+        self.last_bytes = network_bytes;
+    }
+}
+
+/// Seconds to fetch the rest of `total_bytes` at `speed_bps`; 0 if unknown.
+fn eta_seconds(total_bytes: u64, downloaded_bytes: u64, speed_bps: u64) -> u64 {
+    if speed_bps == 0 {
+        return 0;
+    }
+    total_bytes
+        .saturating_sub(downloaded_bytes)
+        .div_ceil(speed_bps)
+}
+
+/// Caps the combined rate of the downloads sharing it.
+pub struct RateLimiter {
+    bytes_per_second: u64,
+    started: Instant,
+    bytes: AtomicU64,
+}
+
+impl RateLimiter {
+    pub fn new(bytes_per_second: u64) -> Self {
+        Self {
+            bytes_per_second: bytes_per_second.max(1),
+            started: Instant::now(),
+            bytes: AtomicU64::new(0),
+        }
+    }
+
+    /// Count `len` received bytes, waiting while the average rate is above the cap.
+    pub async fn consume(&self, len: u64) {
+        let total = self.bytes.fetch_add(len, Ordering::Relaxed) + len;
+        let wait = throttle_delay(total, self.bytes_per_second, self.started.elapsed());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Time to wait so that `bytes` took at least as long as `bytes_per_second` allows.
+fn throttle_delay(bytes: u64, bytes_per_second: u64, elapsed: Duration) -> Duration {
+    let due = Duration::from_secs_f64(bytes as f64 / bytes_per_second as f64);
+    due.saturating_sub(elapsed)
 }
 
 /// Centralized progress tracking with atomic counters
 struct GlobalProgress {
     completed_files: AtomicUsize,
     total_downloaded_bytes: AtomicU64,
+    /// Bytes actually fetched, for the throughput
+    network_bytes: AtomicU64,
+    /// Files already valid, for the download statistics
+    skipped_files: AtomicUsize,
+    skipped_bytes: AtomicU64,
+    throughput: Mutex<Throughput>,
     total_files: usize,
+    total_bytes: u64,
 }
 
 impl GlobalProgress {
-    fn new(total_files: usize) -> Self {
+    fn new(total_files: usize, total_bytes: u64) -> Self {
         Self {
             completed_files: AtomicUsize::new(0),
             total_downloaded_bytes: AtomicU64::new(0),
+            network_bytes: AtomicU64::new(0),
+            skipped_files: AtomicUsize::new(0),
+            skipped_bytes: AtomicU64::new(0),
+            throughput: Mutex::new(Throughput::new(Instant::now())),
             total_files,
+            total_bytes,
         }
     }
 
-    /// Get current progress snapshot without modification
-    fn snapshot(&self) -> ProgressSnapshot {
+    fn snapshot_with(
+        &self,
+        completed_files: usize,
+        total_downloaded_bytes: u64,
+    ) -> ProgressSnapshot {
+        let network_bytes = self.network_bytes.load(Ordering::Relaxed);
+        let speed_bps = {
+            let mut throughput = self.throughput.lock().unwrap();
+            throughput.sample(network_bytes, Instant::now());
+            throughput.bytes_per_second as u64
+        };
         ProgressSnapshot {
-            completed_files: self.completed_files.load(Ordering::Relaxed),
+            completed_files,
             total_files: self.total_files,
-            total_downloaded_bytes: self.total_downloaded_bytes.load(Ordering::Relaxed),
+            total_downloaded_bytes,
+            total_bytes: self.total_bytes,
+            speed_bps,
+            eta_seconds: eta_seconds(self.total_bytes, total_downloaded_bytes, speed_bps),
         }
     }
 
+    /// Get current progress snapshot without modification
+    fn snapshot(&self) -> ProgressSnapshot {
+        self.snapshot_with(
+            self.completed_files.load(Ordering::Relaxed),
+            self.total_downloaded_bytes.load(Ordering::Relaxed),
+        )
+    }
+
     /// Increment completed files counter and return updated snapshot
     fn inc_completed(&self) -> ProgressSnapshot {
         let completed = self.completed_files.fetch_add(1, Ordering::Relaxed) + 1;
-        ProgressSnapshot {
-            completed_files: completed,
-            total_files: self.total_files,
-            total_downloaded_bytes: self.total_downloaded_bytes.load(Ordering::Relaxed),
-        }
+        self.snapshot_with(
+            completed,
+            self.total_downloaded_bytes.load(Ordering::Relaxed),
+        )
     }
 
-    /// Add downloaded bytes and return updated snapshot
+    /// Add bytes of a file that was already valid and return updated snapshot
     fn add_bytes(&self, delta: u64) -> ProgressSnapshot {
         let total_bytes = self
             .total_downloaded_bytes
             .fetch_add(delta, Ordering::Relaxed)
             + delta;
-        ProgressSnapshot {
-            completed_files: self.completed_files.load(Ordering::Relaxed),
-            total_files: self.total_files,
-            total_downloaded_bytes: total_bytes,
-        }
+        self.snapshot_with(self.completed_files.load(Ordering::Relaxed), total_bytes)
+    }
+
+    /// Add bytes received from the network and return updated snapshot
+    fn add_network_bytes(&self, delta: u64) -> ProgressSnapshot {
+        self.network_bytes.fetch_add(delta, Ordering::Relaxed);
+        self.add_bytes(delta)
+    }
+
+    /// Take back the bytes of a failed attempt, which will be downloaded again.
+    /// They stay in `network_bytes`, which counts traffic.
+    fn discard_bytes(&self, delta: u64) {
+        let _ = self.total_downloaded_bytes.fetch_update(
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+            |total| Some(total.saturating_sub(delta)),
+        );
     }
 }
 
 /// Emit a progress event to the frontend
 fn emit_progress(
-    window: &Window,
+    events: &EventSink,
     file_name: &str,
     status: &str,
     downloaded: u64,
     total: u64,
     snapshot: &ProgressSnapshot,
 ) {
-    let _ = window.emit(
-        "download-progress",
-        ProgressEvent {
-            file: file_name.to_string(),
-            downloaded,
-            total,
-            status: status.into(),
-            completed_files: snapshot.completed_files,
-            total_files: snapshot.total_files,
-            total_downloaded_bytes: snapshot.total_downloaded_bytes,
-        },
-    );
+    events.emit_event(ProgressEvent {
+        file: file_name.to_string(),
+        downloaded,
+        total,
+        status: status.into(),
+        completed_files: snapshot.completed_files,
+        total_files: snapshot.total_files,
+        total_downloaded_bytes: snapshot.total_downloaded_bytes,
+        total_bytes: snapshot.total_bytes,
+        speed_bps: snapshot.speed_bps,
+        eta_seconds: snapshot.eta_seconds,
+    });
+}
+
+/// Number of attempts for a file whose checksum does not match after download
+const MAX_DOWNLOAD_ATTEMPTS: usize = 3;
+
+/// Incremental hasher for the checksum a task expects (SHA256 preferred).
+enum StreamHasher {
+    Sha1(sha1::Sha1),
+    Sha256(sha2::Sha256),
+    None,
+}
+
+impl StreamHasher {
+    fn for_task(task: &DownloadTask) -> Self {
+        if task.sha256.is_some() {
+            StreamHasher::Sha256(sha2::Sha256::new())
+        } else if task.sha1.is_some() {
+            StreamHasher::Sha1(sha1::Sha1::new())
+        } else {
+            StreamHasher::None
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            StreamHasher::Sha1(h) => h.update(data),
+            StreamHasher::Sha256(h) => h.update(data),
+            StreamHasher::None => {}
+        }
+    }
+
+    fn finalize(self) -> Option<String> {
+        match self {
+            StreamHasher::Sha1(h) => Some(hex::encode(h.finalize())),
+            StreamHasher::Sha256(h) => Some(hex::encode(h.finalize())),
+            StreamHasher::None => None,
+        }
+    }
+}
+
+/// The checksum a task expects, SHA256 preferred.
+fn expected_hash(task: &DownloadTask) -> Option<&str> {
+    task.sha256.as_deref().or(task.sha1.as_deref())
+}
+
+/// Fetch a large file from its URL and mirrors in parallel segments.
+///
+/// # Returns
+/// The verified hash, if the task had one.
+async fn download_multi_source(
+    client: &reqwest::Client,
+    events: &EventSink,
+    task: &DownloadTask,
+    file_name: &str,
+    progress: &GlobalProgress,
+    size: u64,
+) -> Result<Option<String>, MultiSourceError> {
+    let sources: Vec<String> = std::iter::once(task.url.clone())
+        .chain(task.mirrors.iter().cloned())
+        .collect();
+    let downloaded = AtomicU64::new(0);
+    let result = async {
+        multi_source::download(client, &sources, &task.path, size, |len| {
+            let done = downloaded.fetch_add(len, Ordering::Relaxed) + len;
+            let snapshot = progress.add_network_bytes(len);
+            emit_progress(events, file_name, "Downloading", done, size, &snapshot);
+        })
+        .await?;
+
+        let data = tokio::fs::read(&task.path)
+            .await
+            .map_err(|e| MultiSourceError::Failed(format!("Read error: {}", e)))?;
+        let mut hasher = StreamHasher::for_task(task);
+        hasher.update(&data);
+        let actual = hasher.finalize();
+        match (expected_hash(task), actual.as_deref()) {
+            (Some(expected), Some(actual)) if !actual.eq_ignore_ascii_case(expected) => {
+                let _ = tokio::fs::remove_file(&task.path).await;
+                Err(MultiSourceError::Failed(format!(
+                    "Checksum mismatch for {} (expected {}, got {})",
+                    file_name, expected, actual
+                )))
+            }
+            _ => Ok(actual),
+        }
+    }
+    .await;
+    if result.is_err() {
+        // The file is fetched again from scratch
+        progress.discard_bytes(downloaded.load(Ordering::Relaxed));
+    }
+    result
+}
+
+/// Whether an existing file can be kept, decided without reading it, or
+/// `None` if `mode` requires hashing it.
+fn check_without_hashing(
+    mode: VerificationMode,
+    known_good: bool,
+    expected_size: Option<u64>,
+    actual_size: Option<u64>,
+) -> Option<bool> {
+    let size_matches = expected_size.map(|size| actual_size == Some(size));
+    match mode {
+        VerificationMode::Full => None,
+        VerificationMode::Fast => size_matches,
+        VerificationMode::Index if known_good => Some(true),
+        VerificationMode::Index => size_matches,
+    }
+}
+
+/// Download a single file, hashing it as it streams.
+///
+/// Files of at least `multi_source::MIN_SIZE` are fetched in parallel
+/// segments first, falling back to a single stream if that fails. With a
+/// `limiter`, every file is fetched in one stream.
+/// A checksum mismatch deletes the file and retries, up to `MAX_DOWNLOAD_ATTEMPTS`.
+///
+/// # Returns
+/// The verified hash, if the task had one.
+async fn download_verified(
+    client: &reqwest::Client,
+    events: &EventSink,
+    task: &DownloadTask,
+    file_name: &str,
+    progress: &GlobalProgress,
+    limiter: Option<&RateLimiter>,
+) -> Result<Option<String>, String> {
+    let expected = expected_hash(task);
+    let mut last_error = String::new();
+    // Replace rather than truncate: the file may be hard-linked into the mod store
+    let _ = tokio::fs::remove_file(&task.path).await;
+
+    // Parallel segments would defeat a bandwidth cap
+    let multi_source_size = task
+        .size
+        .filter(|size| *size >= multi_source::MIN_SIZE && limiter.is_none());
+    if let Some(size) = multi_source_size {
+        match download_multi_source(client, events, task, file_name, progress, size).await {
+            Ok(hash) => return Ok(hash),
+            Err(MultiSourceError::RangesUnsupported) => {}
+            Err(e) => println!("[Download] {}, retrying in one stream", e),
+        }
+    }
+
+    for attempt in 1..=MAX_DOWNLOAD_ATTEMPTS {
+        let mut resp = match client.get(&task.url).send().await {
+            Ok(resp) => resp,
+            Err(e) => {
+                last_error = format!("Request error: {}", e);
+                continue;
+            }
+        };
+        let total_size = resp.content_length().unwrap_or(0);
+        let mut file = match tokio::fs::File::create(&task.path).await {
+            Ok(f) => f,
+            Err(e) => return Err(format!("Create file error: {}", e)),
+        };
+
+        let mut hasher = StreamHasher::for_task(task);
+        let mut downloaded: u64 = 0;
+        let mut stream_error = None;
+        loop {
+            match resp.chunk().await {
+                Ok(Some(chunk)) => {
+                    if let Err(e) = file.write_all(&chunk).await {
+                        return Err(format!("Write error: {}", e));
+                    }
+                    hasher.update(&chunk);
+                    downloaded += chunk.len() as u64;
+                    if let Some(limiter) = limiter {
+                        limiter.consume(chunk.len() as u64).await;
+                    }
+                    let snapshot = progress.add_network_bytes(chunk.len() as u64);
+                    emit_progress(
+                        events,
+                        file_name,
+                        "Downloading",
+                        downloaded,
+                        total_size,
+                        &snapshot,
+                    );
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    stream_error = Some(format!("Download error: {}", e));
+                    break;
+                }
+            }
+        }
+        file.flush()
+            .await
+            .map_err(|e| format!("Write error: {}", e))?;
+        drop(file);
+
+        if let Some(e) = stream_error {
+            progress.discard_bytes(downloaded);
+            last_error = e;
+            continue;
+        }
+
+        let actual = hasher.finalize();
+        match (expected, actual.as_deref()) {
+            (Some(expected), Some(actual)) if !actual.eq_ignore_ascii_case(expected) => {
+                last_error = format!(
+                    "Checksum mismatch for {} (expected {}, got {})",
+                    file_name, expected, actual
+                );
+                println!("[Download] {} (attempt {})", last_error, attempt);
+                progress.discard_bytes(downloaded);
+                let _ = tokio::fs::remove_file(&task.path).await;
+            }
+            _ => return Ok(actual),
+        }
+    }
+
+    Err(last_error)
 }
 
+/// Download `tasks` with the given settings, which may carry an instance's
+/// network overrides.
 pub async fn download_files(
-    window: Window,
+    events: EventSink,
+    tasks: Vec<DownloadTask>,
+    config: &crate::core::config::EffectiveConfig,
+) -> Result<(), String> {
+    download_files_as(PendingKind::GameFiles, None, events, tasks, config).await
+}
+
+/// `download_files`, recording an interruption as `kind` together with the
+/// instance `backup` to restore if it is rolled back.
+pub async fn download_files_as(
+    kind: PendingKind,
+    backup: Option<&Path>,
+    events: EventSink,
     tasks: Vec<DownloadTask>,
-    max_concurrent: usize,
+    config: &crate::core::config::EffectiveConfig,
 ) -> Result<(), String> {
+    let client = crate::core::http::client_for(config)?;
     // Clamp max_concurrent to a valid range (1-128) to prevent edge cases
-    let max_concurrent = max_concurrent.clamp(1, 128);
+    let max_concurrent = (config.download_threads as usize).clamp(1, 128);
+
+    // Remember missing files until the run ends, to resume after a restart
+    let app_handle = events.window().app_handle().clone();
+    let missing: Vec<DownloadTask> = tasks
+        .iter()
+        .filter(|task| !task.path.exists())
+        .cloned()
+        .collect();
+    let pending_id = (!missing.is_empty()).then(|| uuid::Uuid::new_v4().to_string());
+    if let Some(id) = &pending_id {
+        RUNNING_FILE_DOWNLOADS.lock().unwrap().push(id.clone());
+        let pending = PendingFileDownload {
+            id: id.clone(),
+            kind,
+            instance_id: events.scope().instance_id.clone(),
+            tasks: missing,
+            created_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            backup: backup.map(Path::to_path_buf),
+        };
+        let result = DownloadQueue::update(&app_handle, |queue| queue.pending_files.push(pending));
+        if let Err(e) = result {
+            println!("[Download] Failed to update download queue: {}", e);
+        }
+    }
+
+    let manager = events.window().state::<DownloadManager>().inner().clone();
+    let max_per_host = config.max_connections_per_host as usize;
+    manager.configure(max_concurrent, max_per_host);
+
+    // Identical URLs are fetched once and copied to the other destinations
+    let mut seen_urls: HashMap<String, PathBuf> = HashMap::new();
+    let mut copies: Vec<(PathBuf, DownloadTask)> = Vec::new();
+    let mut tasks: Vec<DownloadTask> = tasks
+        .into_iter()
+        .filter(|task| match seen_urls.get(&task.url) {
+            Some(first) => {
+                if *first != task.path {
+                    copies.push((first.clone(), task.clone()));
+                }
+                false
+            }
+            None => {
+                seen_urls.insert(task.url.clone(), task.path.clone());
+                true
+            }
+        })
+        .collect();
+    // Client jar and libraries first, assets last
+    tasks.sort_by_key(|task| DownloadPriority::classify(&task.path));
+
+    let limiter = config.bandwidth_limit.map(|limit| Arc::new(RateLimiter::new(limit)));
+    let total_bytes = tasks.iter().filter_map(|task| task.size).sum();
+    let progress = Arc::new(GlobalProgress::new(tasks.len(), total_bytes));
+    let started = Instant::now();
+    let task_count = tasks.len();
 
-    let client = reqwest::Client::new();
-    let semaphore = Arc::new(Semaphore::new(max_concurrent));
-    let progress = Arc::new(GlobalProgress::new(tasks.len()));
+    let index = app_handle.try_state::<FileIndexState>();
+    let verification_mode = config.verification_mode;
 
     // Notify start (total files)
-    let _ = window.emit("download-start", tasks.len());
+    events.emit_event(DownloadStarted {
+        total_files: tasks.len(),
+    });
 
     let tasks_stream = futures::stream::iter(tasks).map(|task| {
         let client = client.clone();
-        let window = window.clone();
-        let semaphore = semaphore.clone();
+        let events = events.clone();
+        let manager = manager.clone();
         let progress = progress.clone();
+        let index = index.clone();
+        let limiter = limiter.clone();
+        let path = task.path.clone();
 
         async move {
-            let _permit = semaphore.acquire().await.unwrap();
             let file_name = task.path.file_name().unwrap().to_string_lossy().to_string();
 
+            // Another download (e.g. a concurrent install) may already be writing this file;
+            // wait for it, then verify below as usual
+            let _file_guard = loop {
+                match manager.claim_file(&task.path) {
+                    FileClaim::Owner(guard) => break guard,
+                    FileClaim::InProgress(mut rx) => {
+                        let _ = rx.changed().await;
+                    }
+                }
+            };
+            let _permit = manager
+                .acquire(
+                    &task.url,
+                    &file_name,
+                    DownloadPriority::classify(&task.path),
+                    task.size,
+                )
+                .await;
+
             // 1. Check if file exists and verify checksum
             if task.path.exists() {
-                emit_progress(&window, &file_name, "Verifying", 0, 0, &progress.snapshot());
-
-                if task.sha256.is_some() || task.sha1.is_some() {
-                    if let Ok(data) = tokio::fs::read(&task.path).await {
-                        if verify_checksum(
-                            &data,
-                            task.sha256.as_deref(),
-                            task.sha1.as_deref(),
-                        ) {
-                            // Already valid, skip download
-                            let skipped_size = tokio::fs::metadata(&task.path)
-                                .await
-                                .map(|m| m.len())
-                                .unwrap_or(0);
-                            if skipped_size > 0 {
-                                let _ = progress.add_bytes(skipped_size);
+                if let Some(expected) = expected_hash(&task) {
+                    // Files verified in an earlier run and unchanged since need no re-read
+                    let known_good = verification_mode == VerificationMode::Index
+                        && index.as_ref().is_some_and(|i| {
+                            i.index.lock().unwrap().is_verified(&task.path, expected)
+                        });
+                    let actual_size = match task.size {
+                        Some(_) => tokio::fs::metadata(&task.path).await.ok().map(|m| m.len()),
+                        None => None,
+                    };
+                    // Decided without reading the file, if the mode allows it
+                    let trusted = check_without_hashing(
+                        verification_mode,
+                        known_good,
+                        task.size,
+                        actual_size,
+                    );
+                    let valid = match trusted {
+                        Some(valid) => valid,
+                        None => {
+                            let snapshot = progress.snapshot();
+                            emit_progress(&events, &file_name, "Verifying", 0, 0, &snapshot);
+                            let valid = match tokio::fs::read(&task.path).await {
+                                Ok(data) => verify_checksum(
+                                    &data,
+                                    task.sha256.as_deref(),
+                                    task.sha1.as_deref(),
+                                ),
+                                Err(_) => false,
+                            };
+                            if let (true, Some(index)) = (valid, &index) {
+                                index.index.lock().unwrap().record(&task.path, expected);
                             }
-                            emit_progress(
-                                &window,
-                                &file_name,
-                                "Skipped",
-                                0,
-                                0,
-                                &progress.inc_completed(),
-                            );
-                            return Ok(());
+                            valid
+                        }
+                    };
+
+                    if valid {
+                        // Already valid, skip download
+                        let skipped_size = tokio::fs::metadata(&task.path)
+                            .await
+                            .map(|m| m.len())
+                            .unwrap_or(0);
+                        if skipped_size > 0 {
+                            let _ = progress.add_bytes(skipped_size);
                         }
+                        progress.skipped_files.fetch_add(1, Ordering::Relaxed);
+                        progress.skipped_bytes.fetch_add(skipped_size, Ordering::Relaxed);
+                        emit_progress(
+                            &events,
+                            &file_name,
+                            "Skipped",
+                            0,
+                            0,
+                            &progress.inc_completed(),
+                        );
+                        return Ok(());
                     }
                 }
             }
@@ -591,42 +1279,22 @@ pub async fn download_files(
                 let _ = tokio::fs::create_dir_all(parent).await;
             }
 
-            match client.get(&task.url).send().await {
-                Ok(mut resp) => {
-                    let total_size = resp.content_length().unwrap_or(0);
-                    let mut file = match tokio::fs::File::create(&task.path).await {
-                        Ok(f) => f,
-                        Err(e) => return Err(format!("Create file error: {}", e)),
-                    };
-
-                    let mut downloaded: u64 = 0;
-                    loop {
-                        match resp.chunk().await {
-                            Ok(Some(chunk)) => {
-                                if let Err(e) = file.write_all(&chunk).await {
-                                    return Err(format!("Write error: {}", e));
-                                }
-                                downloaded += chunk.len() as u64;
-                                let snapshot = progress.add_bytes(chunk.len() as u64);
-                                emit_progress(
-                                    &window,
-                                    &file_name,
-                                    "Downloading",
-                                    downloaded,
-                                    total_size,
-                                    &snapshot,
-                                );
-                            }
-                            Ok(None) => break,
-                            Err(e) => return Err(format!("Download error: {}", e)),
-                        }
+            let limiter = limiter.as_deref();
+            match download_verified(&client, &events, &task, &file_name, &progress, limiter).await {
+                Ok(Some(hash)) => {
+                    if let Some(index) = &index {
+                        index.index.lock().unwrap().record(&task.path, &hash);
                     }
                 }
-                Err(e) => return Err(format!("Request error: {}", e)),
+                Ok(None) => {}
+                Err(e) => {
+                    emit_progress(&events, &file_name, "Error", 0, 0, &progress.snapshot());
+                    return Err(e);
+                }
             }
 
             emit_progress(
-                &window,
+                &events,
                 &file_name,
                 "Finished",
                 0,
@@ -635,14 +1303,208 @@ pub async fn download_files(
             );
             Ok(())
         }
+        .map(move |result| result.map_err(|e| (path, e)))
     });
 
     // Buffer unordered to run concurrently
-    tasks_stream
-        .buffer_unordered(max_concurrent)
-        .collect::<Vec<Result<(), String>>>()
+    let results = tasks_stream
+        .buffer_unordered(max_concurrent * SMALL_FILES_PER_SLOT)
+        .collect::<Vec<Result<(), (PathBuf, String)>>>()
         .await;
+    let (failed, mut errors): (HashSet<PathBuf>, Vec<String>) =
+        results.into_iter().filter_map(|r| r.err()).unzip();
+    let failed_files = errors.len();
+
+    for (src, dst) in &copies {
+        // The source's failure is already reported
+        if failed.contains(src) || is_same_download(src, dst).await {
+            continue;
+        }
+        if let Some(parent) = dst.path.parent() {
+            let _ = tokio::fs::create_dir_all(parent).await;
+        }
+        // Replace rather than truncate, like `download_verified`
+        let _ = tokio::fs::remove_file(&dst.path).await;
+        if let Err(e) = tokio::fs::copy(src, &dst.path).await {
+            errors.push(format!(
+                "Failed to copy {} to {}: {}",
+                src.display(),
+                dst.path.display(),
+                e
+            ));
+        }
+    }
+
+    if let Some(index) = &index {
+        if let Err(e) = index.save() {
+            println!("[Download] Failed to save file index: {}", e);
+        }
+    }
+
+    events.emit_event(DownloadCompleted {});
+    if let Some(id) = &pending_id {
+        let result = DownloadQueue::update(&app_handle, |queue| {
+            queue.pending_files.retain(|pending| &pending.id != id)
+        });
+        RUNNING_FILE_DOWNLOADS.lock().unwrap().retain(|running| running != id);
+        if let Err(e) = result {
+            println!("[Download] Failed to update download queue: {}", e);
+        }
+    }
 
-    let _ = window.emit("download-complete", ());
+    if task_count > 0 {
+        let skipped_files = progress.skipped_files.load(Ordering::Relaxed);
+        let run = crate::core::download_stats::DownloadRun {
+            instance_id: events.scope().instance_id.clone(),
+            session_id: events.scope().session_id.clone(),
+            finished_at: unix_now(),
+            duration_ms: started.elapsed().as_millis() as u64,
+            downloaded_files: task_count.saturating_sub(skipped_files + failed_files),
+            downloaded_bytes: progress.network_bytes.load(Ordering::Relaxed),
+            skipped_files,
+            skipped_bytes: progress.skipped_bytes.load(Ordering::Relaxed),
+            failed_files,
+        };
+        record_download_run(&app_handle, run);
+    }
+    if let Some(first) = errors.first() {
+        return Err(format!("{} file(s) failed to download: {}", errors.len(), first));
+    }
     Ok(())
 }
+
+/// Whether `dst` already holds the file downloaded to `src`.
+async fn is_same_download(src: &Path, dst: &DownloadTask) -> bool {
+    let (Ok(src_meta), Ok(dst_meta)) = (
+        tokio::fs::metadata(src).await,
+        tokio::fs::metadata(&dst.path).await,
+    ) else {
+        return false;
+    };
+    if src_meta.len() != dst_meta.len() {
+        return false;
+    }
+    if expected_hash(dst).is_none() {
+        return true;
+    }
+    tokio::fs::read(&dst.path)
+        .await
+        .is_ok_and(|data| verify_checksum(&data, dst.sha256.as_deref(), dst.sha1.as_deref()))
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn record_download_run(app_handle: &AppHandle, run: crate::core::download_stats::DownloadRun) {
+    let Some(stats) = app_handle.try_state::<crate::core::download_stats::DownloadStatsState>()
+    else {
+        return;
+    };
+    if let Err(e) = stats.record(run) {
+        println!("[Download] Failed to record download statistics: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_hasher_matches_one_shot_hash() {
+        let task = DownloadTask {
+            url: String::new(),
+            path: PathBuf::from("a.jar"),
+            sha1: Some(compute_sha1(b"hello world")),
+            sha256: None,
+            size: None,
+            mirrors: Vec::new(),
+        };
+        let mut hasher = StreamHasher::for_task(&task);
+        hasher.update(b"hello ");
+        hasher.update(b"world");
+        assert_eq!(hasher.finalize().as_deref(), expected_hash(&task));
+    }
+
+    #[test]
+    fn test_throughput_samples_at_interval() {
+        let start = Instant::now();
+        let mut throughput = Throughput::new(start);
+        throughput.sample(1_000_000, start + Duration::from_millis(100));
+        assert_eq!(throughput.bytes_per_second, 0.0);
+
+        throughput.sample(1_000_000, start + Duration::from_secs(1));
+        assert_eq!(throughput.bytes_per_second, 1_000_000.0);
+        throughput.sample(1_000_000, start + Duration::from_secs(2));
+        assert!((throughput.bytes_per_second - 700_000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_pending_kind_defaults_to_game_files() {
+        let pending: PendingFileDownload = serde_json::from_str(
+            r#"{"id": "a", "instance_id": null, "tasks": [], "created_at": 0}"#,
+        )
+        .unwrap();
+        assert_eq!(pending.kind, PendingKind::GameFiles);
+        assert!(pending.backup.is_none());
+        let json = serde_json::to_value(PendingFileDownload {
+            kind: PendingKind::PackUpdate,
+            ..pending
+        })
+        .unwrap();
+        assert_eq!(json["kind"], "pack_update");
+    }
+
+    #[test]
+    fn test_eta_seconds() {
+        assert_eq!(eta_seconds(10_000, 4_000, 0), 0);
+        assert_eq!(eta_seconds(10_000, 4_000, 1_000), 6);
+        assert_eq!(eta_seconds(10_000, 4_500, 1_000), 6);
+        assert_eq!(eta_seconds(10_000, 12_000, 1_000), 0);
+    }
+
+    #[test]
+    fn test_throttle_delay() {
+        let second = Duration::from_secs(1);
+        assert_eq!(throttle_delay(2_000, 1_000, second), second);
+        assert_eq!(throttle_delay(1_000, 1_000, second), Duration::ZERO);
+        assert_eq!(throttle_delay(500, 1_000, second), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_full_verification_always_hashes() {
+        let check = |known_good, actual| {
+            check_without_hashing(VerificationMode::Full, known_good, Some(4), actual)
+        };
+        assert_eq!(check(true, Some(4)), None);
+        assert_eq!(check(false, Some(4)), None);
+        assert_eq!(check(false, Some(0)), None);
+    }
+
+    #[test]
+    fn test_fast_verification_compares_sizes() {
+        let check = |expected, actual| {
+            check_without_hashing(VerificationMode::Fast, false, expected, actual)
+        };
+        assert_eq!(check(Some(4), Some(4)), Some(true));
+        assert_eq!(check(Some(4), Some(0)), Some(false));
+        assert_eq!(check(Some(4), None), Some(false));
+        assert_eq!(check(None, Some(4)), None);
+    }
+
+    #[test]
+    fn test_index_verification_trusts_unchanged_files() {
+        let check = |known_good, expected, actual| {
+            check_without_hashing(VerificationMode::Index, known_good, expected, actual)
+        };
+        assert_eq!(check(true, Some(4), Some(4)), Some(true));
+        assert_eq!(check(true, None, None), Some(true));
+        // Files the index has not seen still get a size check
+        assert_eq!(check(false, Some(4), Some(0)), Some(false));
+        assert_eq!(check(false, Some(4), Some(4)), Some(true));
+        assert_eq!(check(false, None, Some(4)), None);
+    }
+}
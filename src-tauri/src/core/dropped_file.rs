@@ -0,0 +1,251 @@
+//! Files dropped onto an instance.
+//!
+//! `detect` looks inside a dropped file to tell what it is, and `install`
+//! puts it where the game expects it: mods into `mods/`, resource packs into
+//! `resourcepacks/` and worlds, as folders or zips, into `saves/`. Modpacks
+//! are only detected, since they become an instance of their own.
+
+use serde::Serialize;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::core::events::EventSink;
+use crate::core::instance::ModpackPlatform;
+use crate::core::manifest::LoaderType;
+
+/// Mod metadata files and the loader they belong to, most specific first
+const MOD_METADATA: [(&str, LoaderType); 5] = [
+    ("fabric.mod.json", LoaderType::Fabric),
+    ("quilt.mod.json", LoaderType::Quilt),
+    ("META-INF/neoforge.mods.toml", LoaderType::NeoForge),
+    ("META-INF/mods.toml", LoaderType::Forge),
+    ("mcmod.info", LoaderType::Forge),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DroppedFile {
+    Mod { loader: LoaderType },
+    Modpack { platform: ModpackPlatform },
+    ResourcePack,
+    World { name: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DropImportReport {
+    pub detected: DroppedFile,
+    /// Where it was installed, `None` for modpacks
+    pub installed_to: Option<PathBuf>,
+    /// A file or world of the same name was replaced; replaced worlds are
+    /// kept as a backup
+    pub replaced: bool,
+}
+
+fn file_name(path: &Path) -> Result<String, String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid path: {}", path.display()))
+}
+
+/// What `path` is, judged by its contents rather than only its extension.
+pub fn detect(path: &Path) -> Result<DroppedFile, String> {
+    let name = file_name(path)?;
+    let unknown = || format!("{} is not a mod, modpack, resource pack or world", name);
+    if path.is_dir() {
+        if path.join("level.dat").is_file() {
+            return Ok(DroppedFile::World { name });
+        }
+        return Err(unknown());
+    }
+
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", name, e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|_| unknown())?;
+    let entries: Vec<String> = archive.file_names().map(str::to_string).collect();
+    // CurseForge packs only differ from other zips by their manifest
+    let curseforge = archive
+        .by_name("manifest.json")
+        .ok()
+        .and_then(|mut manifest| {
+            let mut content = String::new();
+            manifest.read_to_string(&mut content).ok()?;
+            serde_json::from_str::<serde_json::Value>(&content).ok()
+        })
+        .is_some_and(|manifest| manifest["manifestType"] == "minecraftModpack");
+    classify(&name, &entries, curseforge).ok_or_else(unknown)
+}
+
+fn classify(file_name: &str, entries: &[String], curseforge: bool) -> Option<DroppedFile> {
+    let has = |name: &str| entries.iter().any(|entry| entry == name);
+    let extension = Path::new(file_name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    if extension == "jar" {
+        return MOD_METADATA
+            .into_iter()
+            .find(|(metadata, _)| has(metadata))
+            .map(|(_, loader)| DroppedFile::Mod { loader });
+    }
+    if extension == "mrpack" || has("modrinth.index.json") {
+        return Some(DroppedFile::Modpack {
+            platform: ModpackPlatform::Modrinth,
+        });
+    }
+    if curseforge {
+        return Some(DroppedFile::Modpack {
+            platform: ModpackPlatform::CurseForge,
+        });
+    }
+    if has("pack.mcmeta") {
+        return Some(DroppedFile::ResourcePack);
+    }
+    if has("level.dat") {
+        let stem = Path::new(file_name).file_stem()?.to_string_lossy();
+        return Some(DroppedFile::World {
+            name: stem.to_string(),
+        });
+    }
+    // A world zipped together with its folder
+    entries.iter().find_map(|entry| {
+        let folder = entry.strip_suffix("/level.dat")?;
+        (!folder.is_empty() && !folder.contains('/')).then(|| DroppedFile::World {
+            name: folder.to_string(),
+        })
+    })
+}
+
+/// Install `path`, detected as `detected`, into the instance at `instance_dir`.
+pub async fn install(
+    events: &EventSink,
+    path: &Path,
+    detected: DroppedFile,
+    instance_dir: &Path,
+) -> Result<DropImportReport, String> {
+    let (installed_to, replaced) = match &detected {
+        DroppedFile::Mod { .. } => copy_into(path, &instance_dir.join("mods"))?,
+        DroppedFile::ResourcePack => copy_into(path, &instance_dir.join("resourcepacks"))?,
+        DroppedFile::World { name } => {
+            crate::core::worlds::validate_world_name(name)?;
+            let report = install_world(events, path, name, instance_dir).await?;
+            (report.destination, report.backup.is_some())
+        }
+        DroppedFile::Modpack { .. } => {
+            return Ok(DropImportReport {
+                detected,
+                installed_to: None,
+                replaced: false,
+            })
+        }
+    };
+    Ok(DropImportReport {
+        detected,
+        installed_to: Some(installed_to),
+        replaced,
+    })
+}
+
+/// Copy `path` into `dir` under its own name.
+fn copy_into(path: &Path, dir: &Path) -> Result<(PathBuf, bool), String> {
+    let dst = dir.join(file_name(path)?);
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let replaced = dst.exists();
+    // Writing through a hard link would change the mod store's copy
+    let _ = fs::remove_file(&dst);
+    fs::copy(path, &dst).map_err(|e| format!("Failed to copy {}: {}", path.display(), e))?;
+    Ok((dst, replaced))
+}
+
+async fn install_world(
+    events: &EventSink,
+    path: &Path,
+    name: &str,
+    instance_dir: &Path,
+) -> Result<crate::core::worlds::WorldSyncReport, String> {
+    let dst = instance_dir.join("saves").join(name);
+    fs::create_dir_all(instance_dir.join("saves")).map_err(|e| e.to_string())?;
+    if path.is_dir() {
+        return crate::core::worlds::copy_world(path, &dst);
+    }
+
+    let scratch = instance_dir.join(".drop-import");
+    if scratch.exists() {
+        fs::remove_dir_all(&scratch).map_err(|e| e.to_string())?;
+    }
+    let result = async {
+        crate::core::extract::extract(
+            events.clone(),
+            path.to_path_buf(),
+            scratch.clone(),
+            crate::utils::zip::SymlinkPolicy::Skip,
+            None,
+        )
+        .await?;
+        let nested = scratch.join(name);
+        let world = if nested.join("level.dat").is_file() {
+            &nested
+        } else {
+            &scratch
+        };
+        crate::core::worlds::copy_world(world, &dst)
+    }
+    .await;
+    let _ = fs::remove_dir_all(&scratch);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify(
+                "sodium.jar",
+                &entries(&["fabric.mod.json", "a.class"]),
+                false
+            ),
+            Some(DroppedFile::Mod {
+                loader: LoaderType::Fabric
+            })
+        );
+        assert_eq!(classify("library.jar", &entries(&["a.class"]), false), None);
+        assert_eq!(
+            classify("pack.zip", &entries(&["manifest.json", "overrides/"]), true),
+            Some(DroppedFile::Modpack {
+                platform: ModpackPlatform::CurseForge
+            })
+        );
+        assert_eq!(
+            classify("Faithful.zip", &entries(&["pack.mcmeta", "assets/"]), false),
+            Some(DroppedFile::ResourcePack)
+        );
+        assert_eq!(
+            classify(
+                "backup.zip",
+                &entries(&["level.dat", "region/r.0.0.mca"]),
+                false
+            ),
+            Some(DroppedFile::World {
+                name: "backup".to_string()
+            })
+        );
+        assert_eq!(
+            classify(
+                "x.zip",
+                &entries(&["Skyblock/", "Skyblock/level.dat"]),
+                false
+            ),
+            Some(DroppedFile::World {
+                name: "Skyblock".to_string()
+            })
+        );
+        assert_eq!(classify("x.zip", &entries(&["a/b/level.dat"]), false), None);
+    }
+}
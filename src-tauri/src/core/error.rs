@@ -0,0 +1,277 @@
+//! Errors returned to the frontend.
+//!
+//! Core modules mostly report `String` errors; commands convert them into
+//! `LauncherError`, which serializes as `{ code, message, context }` so the
+//! UI can branch on `code` instead of matching message text. Plain strings
+//! become `Other` unless a more specific variant is chosen at the call site.
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, thiserror::Error)]
+pub enum LauncherError {
+    #[error("Network error: {0}")]
+    Network(String),
+    #[error("Authentication failed: {0}")]
+    Auth(String),
+    /// A server did not answer within the configured timeout
+    #[error("Timed out: {0}")]
+    Timeout(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// Invalid or damaged data (JSON, archives, checksums)
+    #[error("Corrupt data: {0}")]
+    Corrupt(String),
+    #[error("Cancelled")]
+    Cancelled,
+    /// The target is locked by another operation
+    #[error("{0}")]
+    Busy(String),
+    /// Another operation holds the instance's lock
+    #[error("Instance is busy: {operation}")]
+    InstanceBusy {
+        instance_id: String,
+        operation: String,
+    },
+    #[error("No suitable Java found: {0}")]
+    JavaMissing(String),
+    /// Bad input from the UI
+    #[error("{0}")]
+    Invalid(String),
+    /// The Microsoft account cannot play until the user acts on it
+    #[error("{0}")]
+    AccountRestricted(crate::core::auth::XstsError),
+    /// The account owns the game but has no Java Edition profile yet
+    #[error("This account has no Minecraft profile yet. Create one at minecraft.net")]
+    ProfileMissing,
+    /// A large download on a metered connection waits for the user's consent,
+    /// see `core::metered`
+    #[error("{what} needs to download about {size_mb} MB")]
+    MeteredDownload { what: String, size_mb: u64 },
+    #[error("{0}")]
+    Other(String),
+    /// An error with a note on what was being done
+    #[error("{context}: {source}")]
+    Context {
+        context: String,
+        #[source]
+        source: Box<LauncherError>,
+    },
+}
+
+impl LauncherError {
+    /// Stable identifier for the UI, e.g. "network" or "java_missing".
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Network(_) => "network",
+            Self::Auth(_) => "auth",
+            Self::Timeout(_) => "timeout",
+            Self::Io(_) => "io",
+            Self::Corrupt(_) => "corrupt",
+            Self::Cancelled => "cancelled",
+            Self::Busy(_) => "busy",
+            Self::InstanceBusy { .. } => "instance_busy",
+            Self::JavaMissing(_) => "java_missing",
+            Self::Invalid(_) => "invalid",
+            Self::AccountRestricted(_) => "account_restricted",
+            Self::ProfileMissing => "profile_missing",
+            Self::MeteredDownload { .. } => "metered_download",
+            Self::Other(_) => "other",
+            Self::Context { source, .. } => source.code(),
+        }
+    }
+
+    /// Innermost error, without context.
+    fn root(&self) -> &Self {
+        match self {
+            Self::Context { source, .. } => source.root(),
+            _ => self,
+        }
+    }
+
+    /// Context notes, outermost first.
+    fn contexts(&self) -> Vec<&str> {
+        let mut contexts = Vec::new();
+        let mut current = self;
+        while let Self::Context { context, source } = current {
+            contexts.push(context.as_str());
+            current = source;
+        }
+        contexts
+    }
+
+    pub fn context(self, context: impl Into<String>) -> Self {
+        Self::Context {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+}
+
+impl Serialize for LauncherError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let help_url = match self.root() {
+            Self::AccountRestricted(e) => e.help_url(),
+            Self::ProfileMissing => Some(crate::core::auth::CREATE_PROFILE_URL),
+            _ => None,
+        };
+        let mut state = serializer.serialize_struct("LauncherError", 4)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.root().to_string())?;
+        state.serialize_field("context", &self.contexts())?;
+        state.serialize_field("help_url", &help_url)?;
+        state.end()
+    }
+}
+
+impl From<String> for LauncherError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl From<&str> for LauncherError {
+    fn from(message: &str) -> Self {
+        Self::Other(message.to_string())
+    }
+}
+
+impl From<reqwest::Error> for LauncherError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Self::Timeout(e.to_string())
+        } else {
+            Self::Network(e.to_string())
+        }
+    }
+}
+
+impl From<serde_json::Error> for LauncherError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Corrupt(e.to_string())
+    }
+}
+
+impl From<zip::result::ZipError> for LauncherError {
+    fn from(e: zip::result::ZipError) -> Self {
+        Self::Corrupt(e.to_string())
+    }
+}
+
+impl From<tauri::Error> for LauncherError {
+    fn from(e: tauri::Error) -> Self {
+        Self::Other(e.to_string())
+    }
+}
+
+impl From<tokio::task::JoinError> for LauncherError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        if e.is_cancelled() {
+            Self::Cancelled
+        } else {
+            Self::Other(e.to_string())
+        }
+    }
+}
+
+impl From<Box<dyn std::error::Error + Send + Sync>> for LauncherError {
+    fn from(e: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        Self::Other(e.to_string())
+    }
+}
+
+impl From<crate::core::forge::ForgeInstallError> for LauncherError {
+    fn from(e: crate::core::forge::ForgeInstallError) -> Self {
+        use crate::core::forge::ForgeInstallError;
+        match e {
+            ForgeInstallError::JavaNotFound(_) | ForgeInstallError::JavaTooOld { .. } => {
+                Self::JavaMissing(e.to_string())
+            }
+            ForgeInstallError::MavenUnreachable(_) => Self::Network(e.to_string()),
+            ForgeInstallError::Cancelled => Self::Cancelled,
+            ForgeInstallError::Failed(message) => Self::Other(message),
+        }
+    }
+}
+
+impl From<crate::core::natives::NativesError> for LauncherError {
+    fn from(e: crate::core::natives::NativesError) -> Self {
+        use crate::core::natives::NativesError;
+        match e {
+            NativesError::MissingJar(_) | NativesError::InvalidJar { .. } => {
+                Self::Corrupt(e.to_string())
+            }
+            NativesError::Io { .. } | NativesError::Task(_) => Self::Other(e.to_string()),
+        }
+    }
+}
+
+impl From<crate::core::auth::AuthError> for LauncherError {
+    fn from(e: crate::core::auth::AuthError) -> Self {
+        use crate::core::auth::AuthError;
+        match e {
+            AuthError::Timeout(_) => Self::Timeout(e.to_string()),
+            AuthError::Xsts(e) => e.into(),
+            AuthError::NoProfile => Self::ProfileMissing,
+            AuthError::Failed(message) => Self::Auth(message),
+        }
+    }
+}
+
+impl From<crate::core::auth::XstsError> for LauncherError {
+    fn from(e: crate::core::auth::XstsError) -> Self {
+        match e {
+            crate::core::auth::XstsError::Failed(message) => Self::Auth(message),
+            restricted => Self::AccountRestricted(restricted),
+        }
+    }
+}
+
+/// Attach context to any error convertible into `LauncherError`.
+pub trait ResultExt<T> {
+    fn context(self, context: impl Into<String>) -> Result<T, LauncherError>;
+}
+
+impl<T, E: Into<LauncherError>> ResultExt<T> for Result<T, E> {
+    fn context(self, context: impl Into<String>) -> Result<T, LauncherError> {
+        self.map_err(|e| e.into().context(context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize_with_context() {
+        let error: Result<(), LauncherError> = Err(LauncherError::Network("timed out".to_string()));
+        let error = error
+            .context("Fetching version manifest")
+            .context("Installing 1.20.4")
+            .unwrap_err();
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "code": "network",
+                "message": "Network error: timed out",
+                "context": ["Installing 1.20.4", "Fetching version manifest"],
+                "help_url": null,
+            })
+        );
+        assert_eq!(LauncherError::from("boom").code(), "other");
+
+        let error = LauncherError::from(crate::core::auth::XstsError::Underage);
+        let value = serde_json::to_value(&error).unwrap();
+        assert_eq!(value["code"], "account_restricted");
+        assert_eq!(value["help_url"], "https://account.microsoft.com/family/");
+
+        let error = LauncherError::from(crate::core::auth::AuthError::Timeout(
+            "login.microsoftonline.com".to_string(),
+        ));
+        assert_eq!(error.code(), "timeout");
+        assert_eq!(
+            error.to_string(),
+            "Timed out: login.microsoftonline.com did not respond in time"
+        );
+    }
+}
@@ -0,0 +1,305 @@
+//! Typed events sent to the UI.
+//!
+//! Each payload type names its event once, via `LauncherEvent::NAME`, and is
+//! emitted with an `EventScope` flattened into it. A launch or install
+//! carries an `EventSink` holding its scope, so the UI can tell apart logs
+//! and progress of operations running at the same time. Events emitted
+//! straight on a `Window` have an empty scope.
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Window};
+
+/// Which operation an event belongs to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EventScope {
+    pub instance_id: Option<String>,
+    /// Id of a single launch or install run
+    pub session_id: Option<String>,
+}
+
+/// A payload with a fixed event name.
+pub trait LauncherEvent: Serialize {
+    const NAME: &'static str;
+}
+
+#[derive(Serialize)]
+struct Scoped<'a, E: Serialize> {
+    #[serde(flatten)]
+    scope: &'a EventScope,
+    #[serde(flatten)]
+    event: &'a E,
+}
+
+// Emitting requires `Clone`; the fields are references, so no `E: Clone`
+impl<E: Serialize> Clone for Scoped<'_, E> {
+    fn clone(&self) -> Self {
+        Self {
+            scope: self.scope,
+            event: self.event,
+        }
+    }
+}
+
+/// Something events can be emitted on.
+pub trait EventTarget {
+    fn emit_event<E: LauncherEvent>(&self, event: E);
+}
+
+impl EventTarget for Window {
+    fn emit_event<E: LauncherEvent>(&self, event: E) {
+        let scope = EventScope::default();
+        let _ = self.emit(
+            E::NAME,
+            Scoped {
+                scope: &scope,
+                event: &event,
+            },
+        );
+    }
+}
+
+/// Emits to every window.
+impl EventTarget for AppHandle {
+    fn emit_event<E: LauncherEvent>(&self, event: E) {
+        let scope = EventScope::default();
+        let _ = self.emit(
+            E::NAME,
+            Scoped {
+                scope: &scope,
+                event: &event,
+            },
+        );
+    }
+}
+
+/// A window paired with the scope of the operation emitting on it.
+#[derive(Clone)]
+pub struct EventSink {
+    window: Window,
+    scope: EventScope,
+}
+
+impl EventSink {
+    pub fn new(window: Window, scope: EventScope) -> Self {
+        Self { window, scope }
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    pub fn scope(&self) -> &EventScope {
+        &self.scope
+    }
+
+    /// Emit only to the webview window labelled `label`.
+    pub fn emit_event_to<E: LauncherEvent>(&self, label: &str, event: E) {
+        let _ = self.window.emit_to(
+            label,
+            E::NAME,
+            Scoped {
+                scope: &self.scope,
+                event: &event,
+            },
+        );
+    }
+}
+
+impl EventTarget for EventSink {
+    fn emit_event<E: LauncherEvent>(&self, event: E) {
+        let _ = self.window.emit(
+            E::NAME,
+            Scoped {
+                scope: &self.scope,
+                event: &event,
+            },
+        );
+    }
+}
+
+/// An app handle paired with a scope, for events that belong to an
+/// operation but not to a window.
+#[derive(Clone)]
+pub struct AppEventSink {
+    app: AppHandle,
+    scope: EventScope,
+}
+
+impl AppEventSink {
+    pub fn new(app: AppHandle, scope: EventScope) -> Self {
+        Self { app, scope }
+    }
+}
+
+impl EventTarget for AppEventSink {
+    fn emit_event<E: LauncherEvent>(&self, event: E) {
+        let _ = self.app.emit(
+            E::NAME,
+            Scoped {
+                scope: &self.scope,
+                event: &event,
+            },
+        );
+    }
+}
+
+/// Drops every event, for work done without a UI such as CLI commands.
+#[derive(Clone, Copy)]
+pub struct NoEvents;
+
+impl EventTarget for NoEvents {
+    fn emit_event<E: LauncherEvent>(&self, _event: E) {}
+}
+
+/// A launcher log line; see `emit_log!`.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEvent {
+    pub level: crate::core::config::LogLevel,
+    pub message: String,
+}
+
+impl LauncherEvent for LogEvent {
+    const NAME: &'static str = "launcher-log";
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// A line printed by the game.
+#[derive(Debug, Clone, Serialize)]
+pub struct GameOutput {
+    pub stream: OutputStream,
+    pub line: String,
+}
+
+impl LauncherEvent for GameOutput {
+    const NAME: &'static str = "game-output";
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GameExited {
+    /// Exit code, -1 if killed by a signal
+    pub code: i32,
+}
+
+impl LauncherEvent for GameExited {
+    const NAME: &'static str = "game-exited";
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadStarted {
+    pub total_files: usize,
+}
+
+impl LauncherEvent for DownloadStarted {
+    const NAME: &'static str = "download-start";
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadCompleted {}
+
+impl LauncherEvent for DownloadCompleted {
+    const NAME: &'static str = "download-complete";
+}
+
+impl LauncherEvent for crate::core::downloader::ProgressEvent {
+    const NAME: &'static str = "download-progress";
+}
+
+/// A line printed by the Forge installer.
+#[derive(Debug, Clone, Serialize)]
+pub struct ForgeInstallOutput {
+    pub line: String,
+}
+
+impl LauncherEvent for ForgeInstallOutput {
+    const NAME: &'static str = "forge-install-output";
+}
+
+/// A line printed by a dedicated server.
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerOutput {
+    pub server_id: String,
+    pub line: String,
+}
+
+impl LauncherEvent for ServerOutput {
+    const NAME: &'static str = "server-output";
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerExited {
+    pub server_id: String,
+    /// Exit code, -1 if unknown
+    pub code: i32,
+}
+
+impl LauncherEvent for ServerExited {
+    const NAME: &'static str = "server-exited";
+}
+
+/// Arguments of a second launcher process, which exits after handing them
+/// to the running one. `dropout://` links are not included; they arrive as
+/// `deep-link-received` events.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArgumentsForwarded {
+    pub args: Vec<String>,
+    pub cwd: String,
+}
+
+impl LauncherEvent for ArgumentsForwarded {
+    const NAME: &'static str = "arguments-forwarded";
+}
+
+/// Emit a `launcher-log` event.
+pub fn log(target: &impl EventTarget, message: impl Into<String>) {
+    log_at(target, crate::core::config::LogLevel::Info, message);
+}
+
+/// Emit a `launcher-log` event of `level`, unless `core::logging` hides it.
+pub fn log_at(
+    target: &impl EventTarget,
+    level: crate::core::config::LogLevel,
+    message: impl Into<String>,
+) {
+    if crate::core::logging::enabled(level) {
+        target.emit_event(LogEvent {
+            level,
+            message: message.into(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_is_flattened() {
+        let scope = EventScope {
+            instance_id: Some("abc".to_string()),
+            session_id: None,
+        };
+        let event = GameOutput {
+            stream: OutputStream::Stderr,
+            line: "Crash".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(Scoped {
+                scope: &scope,
+                event: &event,
+            })
+            .unwrap(),
+            serde_json::json!({
+                "instance_id": "abc",
+                "session_id": null,
+                "stream": "stderr",
+                "line": "Crash",
+            })
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! Archive extraction off the async runtime.
+//!
+//! Extraction runs on a blocking thread and reports back over a channel,
+//! which is forwarded as `extract-progress` events.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::core::events::{EventTarget, LauncherEvent};
+use crate::utils::zip::{self, ExtractOptions, ExtractProgress, SymlinkPolicy};
+
+/// Cancel flag for extractions started without their own
+static EXTRACT_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Minimum time between two progress events
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Cancel running extractions that use the shared flag
+pub fn cancel_extraction() {
+    EXTRACT_CANCELLED.store(true, Ordering::SeqCst);
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExtractProgressEvent {
+    /// File name of the archive
+    pub archive: String,
+    pub entries_done: usize,
+    pub entries_total: Option<usize>,
+    pub bytes_done: u64,
+    pub bytes_total: Option<u64>,
+}
+
+impl LauncherEvent for ExtractProgressEvent {
+    const NAME: &'static str = "extract-progress";
+}
+
+fn is_tar_gz(name: &str) -> bool {
+    name.ends_with(".tar.gz") || name.ends_with(".tgz")
+}
+
+/// Extract a zip or tar.gz `archive` into `dest`, emitting progress on
+/// `target`. Returns the top-level directory of tar.gz archives.
+///
+/// Cancelled through `cancel`, or through `cancel_extraction` when `None`.
+pub async fn extract<T: EventTarget + Send + 'static>(
+    target: T,
+    archive: PathBuf,
+    dest: PathBuf,
+    symlinks: SymlinkPolicy,
+    cancel: Option<&'static AtomicBool>,
+) -> Result<Option<String>, String> {
+    let cancel = cancel.unwrap_or_else(|| {
+        EXTRACT_CANCELLED.store(false, Ordering::SeqCst);
+        &EXTRACT_CANCELLED
+    });
+    let name = archive
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<ExtractProgress>();
+    let archive_name = name.clone();
+    let forward = tokio::spawn(async move {
+        while let Some(progress) = rx.recv().await {
+            target.emit_event(ExtractProgressEvent {
+                archive: archive_name.clone(),
+                entries_done: progress.entries_done,
+                entries_total: progress.entries_total,
+                bytes_done: progress.bytes_done,
+                bytes_total: progress.bytes_total,
+            });
+        }
+    });
+
+    let result = tokio::task::spawn_blocking(move || {
+        let mut last_sent: Option<Instant> = None;
+        let options = ExtractOptions {
+            symlinks,
+            progress: Some(Box::new(move |progress: &ExtractProgress| {
+                let finished = progress.entries_total == Some(progress.entries_done);
+                if finished || last_sent.is_none_or(|t| t.elapsed() >= PROGRESS_INTERVAL) {
+                    last_sent = Some(Instant::now());
+                    let _ = tx.send(*progress);
+                }
+            })),
+            cancel: Some(cancel),
+            ..Default::default()
+        };
+        if is_tar_gz(&name) {
+            zip::extract_tar_gz(&archive, &dest, options).map(Some)
+        } else {
+            zip::extract_zip(&archive, &dest, options).map(|_| None)
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // The sender is dropped with the options, which ends forwarding
+    let _ = forward.await;
+    result
+}
@@ -113,10 +113,8 @@ pub struct InstalledFabricVersion {
 pub async fn fetch_supported_game_versions(
 ) -> Result<Vec<FabricGameVersion>, Box<dyn Error + Send + Sync>> {
     let url = format!("{}/versions/game", FABRIC_META_URL);
-    let resp = reqwest::get(&url)
-        .await?
-        .json::<Vec<FabricGameVersion>>()
-        .await?;
+    let resp: Vec<FabricGameVersion> =
+        crate::core::http_cache::get_json(&url, crate::core::http_cache::TTL_HOUR).await?;
     Ok(resp)
 }
 
@@ -127,10 +125,8 @@ pub async fn fetch_supported_game_versions(
 pub async fn fetch_loader_versions(
 ) -> Result<Vec<FabricLoaderVersion>, Box<dyn Error + Send + Sync>> {
     let url = format!("{}/versions/loader", FABRIC_META_URL);
-    let resp = reqwest::get(&url)
-        .await?
-        .json::<Vec<FabricLoaderVersion>>()
-        .await?;
+    let resp: Vec<FabricLoaderVersion> =
+        crate::core::http_cache::get_json(&url, crate::core::http_cache::TTL_SHORT).await?;
     Ok(resp)
 }
 
@@ -145,10 +141,8 @@ pub async fn fetch_loaders_for_game_version(
     game_version: &str,
 ) -> Result<Vec<FabricLoaderEntry>, Box<dyn Error + Send + Sync>> {
     let url = format!("{}/versions/loader/{}", FABRIC_META_URL, game_version);
-    let resp = reqwest::get(&url)
-        .await?
-        .json::<Vec<FabricLoaderEntry>>()
-        .await?;
+    let resp: Vec<FabricLoaderEntry> =
+        crate::core::http_cache::get_json(&url, crate::core::http_cache::TTL_SHORT).await?;
     Ok(resp)
 }
 
@@ -168,10 +162,8 @@ pub async fn fetch_version_profile(
         "{}/versions/loader/{}/{}/profile/json",
         FABRIC_META_URL, game_version, loader_version
     );
-    let resp = reqwest::get(&url)
-        .await?
-        .json::<serde_json::Value>()
-        .await?;
+    let resp: serde_json::Value =
+        crate::core::http_cache::get_json(&url, crate::core::http_cache::TTL_DAY).await?;
     Ok(resp)
 }
 
@@ -0,0 +1,143 @@
+//! Persistent index of verified file hashes (`file_index.json`).
+//!
+//! Hashing every asset on each launch means re-reading hundreds of megabytes.
+//! The index maps a path to the size, mtime and hash it had when it was last
+//! verified; as long as size and mtime are unchanged the file is trusted
+//! without reading it. Entries whose file changed are simply re-verified and
+//! overwritten on the next pass, so the index rebuilds itself lazily.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// A file whose hash was verified, valid while size and mtime are unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileEntry {
+    size: u64,
+    mtime_ms: u64,
+    hash: String,
+}
+
+/// Path -> (size, mtime, hash) map.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FileIndex {
+    entries: HashMap<String, FileEntry>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+fn file_stamp(path: &Path) -> Option<(u64, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    let mtime_ms = meta
+        .modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_millis() as u64;
+    Some((meta.len(), mtime_ms))
+}
+
+impl FileIndex {
+    /// Whether `path` was verified against `expected` and has not changed since.
+    pub fn is_verified(&self, path: &Path, expected: &str) -> bool {
+        let Some(entry) = self.entries.get(path.to_string_lossy().as_ref()) else {
+            return false;
+        };
+        entry.hash.eq_ignore_ascii_case(expected)
+            && file_stamp(path) == Some((entry.size, entry.mtime_ms))
+    }
+
+    /// Record that `path` currently has hash `hash`.
+    pub fn record(&mut self, path: &Path, hash: &str) {
+        if let Some((size, mtime_ms)) = file_stamp(path) {
+            self.entries.insert(
+                path.to_string_lossy().to_string(),
+                FileEntry {
+                    size,
+                    mtime_ms,
+                    hash: hash.to_string(),
+                },
+            );
+            self.dirty = true;
+        }
+    }
+
+    /// Drop entries whose file no longer exists.
+    ///
+    /// # Returns
+    /// The number of removed entries.
+    pub fn prune(&mut self) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|path, _| Path::new(path).exists());
+        let removed = before - self.entries.len();
+        if removed > 0 {
+            self.dirty = true;
+        }
+        removed
+    }
+}
+
+/// Managed state wrapping the index and its file.
+pub struct FileIndexState {
+    pub index: Mutex<FileIndex>,
+    path: PathBuf,
+}
+
+impl FileIndexState {
+    pub fn new(app_data_dir: &Path) -> Self {
+        let path = app_data_dir.join("file_index.json");
+        let index = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            index: Mutex::new(index),
+            path,
+        }
+    }
+
+    /// Write the index if it changed since the last save.
+    ///
+    /// Writes to a temporary file first so a crash never leaves a truncated index.
+    pub fn save(&self) -> Result<(), String> {
+        let mut index = self.index.lock().unwrap();
+        if !index.dirty {
+            return Ok(());
+        }
+        let content = serde_json::to_string(&*index).map_err(|e| e.to_string())?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, content).map_err(|e| e.to_string())?;
+        fs::rename(&tmp_path, &self.path).map_err(|e| e.to_string())?;
+        index.dirty = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_detects_changes() {
+        let path = std::env::temp_dir().join(format!("dropout-index-{}", uuid::Uuid::new_v4()));
+        fs::write(&path, b"data").unwrap();
+
+        let mut index = FileIndex::default();
+        index.record(&path, "abc");
+        assert!(index.is_verified(&path, "abc"));
+        assert!(!index.is_verified(&path, "def"));
+
+        fs::write(&path, b"changed data").unwrap();
+        let still_verified = index.is_verified(&path, "abc");
+        fs::remove_file(&path).unwrap();
+        assert!(!still_verified);
+
+        assert_eq!(index.prune(), 1);
+        assert!(index.entries.is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+//! Launcher folders the UI can open in the system file manager.
+//!
+//! The UI only names an instance and a subfolder; the path is built here and,
+//! after symlinks are resolved, must still lie inside the managed directory
+//! it was built from. Missing folders are created, so "Open mods folder"
+//! works before the first mod is installed.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// `subdir` of `root`, created if missing. `None` is `root` itself.
+pub fn managed_dir(root: &Path, subdir: Option<&str>) -> Result<PathBuf, String> {
+    let path = match subdir {
+        Some(subdir) => {
+            let relative = Path::new(subdir);
+            let normal = relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)));
+            if subdir.is_empty() || !normal {
+                return Err(format!("Invalid folder: {}", subdir));
+            }
+            root.join(relative)
+        }
+        None => root.to_path_buf(),
+    };
+    fs::create_dir_all(root).map_err(|e| format!("Failed to create {}: {}", root.display(), e))?;
+    let root = root.canonicalize().map_err(|e| e.to_string())?;
+
+    // A symlinked subfolder may point anywhere; checked before anything is
+    // created through it, and again once the folder exists
+    let outside = || format!("{} is outside the launcher folders", path.display());
+    if !crate::utils::zip::resolve(&path).is_some_and(|target| target.starts_with(&root)) {
+        return Err(outside());
+    }
+    fs::create_dir_all(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let resolved = path.canonicalize().map_err(|e| e.to_string())?;
+    if !resolved.starts_with(&root) || !resolved.is_dir() {
+        return Err(outside());
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_managed_dir() {
+        let root =
+            std::env::temp_dir().join(format!("dropout-folders-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+
+        let mods = managed_dir(&root, Some("mods"));
+        let escaped = managed_dir(&root, Some("../elsewhere"));
+        let absolute = managed_dir(&root, Some("/etc"));
+        let own = managed_dir(&root, None);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(mods.unwrap().ends_with("mods"));
+        assert!(escaped.is_err());
+        assert!(absolute.is_err());
+        assert!(own.is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_managed_dir_does_not_create_through_links() {
+        let dir =
+            std::env::temp_dir().join(format!("dropout-folders-test-{}", uuid::Uuid::new_v4()));
+        let (root, outside) = (dir.join("root"), dir.join("outside"));
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+
+        let linked = managed_dir(&root, Some("link/new"));
+        let created = outside.join("new").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(linked.is_err());
+        assert!(!created);
+    }
+}
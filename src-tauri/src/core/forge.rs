@@ -8,12 +8,25 @@
 //! This implementation fetches the installer manifest to get the correct library list.
 
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::error::Error;
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+use crate::core::config::EffectiveConfig;
+use crate::core::downloader::{download_files, DownloadTask};
+use crate::core::events::EventSink;
 
 const FORGE_PROMOTIONS_URL: &str =
     "https://files.minecraftforge.net/net/minecraftforge/forge/promotions_slim.json";
 const FORGE_MAVEN_URL: &str = "https://maven.minecraftforge.net/";
+/// Installer profile kept next to modern Forge version JSONs, so garbage
+/// collection knows which libraries the installer's processors produced
+pub const INSTALL_PROFILE: &str = "install_profile.json";
 
 /// Represents a Forge version entry.
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -112,10 +125,11 @@ pub async fn fetch_supported_game_versions() -> Result<Vec<String>, Box<dyn Erro
 
 /// Fetch Forge promotions data.
 async fn fetch_promotions() -> Result<ForgePromotions, Box<dyn Error + Send + Sync>> {
-    let resp = reqwest::get(FORGE_PROMOTIONS_URL)
-        .await?
-        .json::<ForgePromotions>()
-        .await?;
+    let resp: ForgePromotions = crate::core::http_cache::get_json(
+        FORGE_PROMOTIONS_URL,
+        crate::core::http_cache::TTL_HOUR,
+    )
+    .await?;
     Ok(resp)
 }
 
@@ -177,37 +191,158 @@ pub fn generate_version_id(game_version: &str, forge_version: &str) -> String {
     format!("{}-forge-{}", game_version, forge_version)
 }
 
-/// Fetch the Forge installer manifest to get the library list
-async fn fetch_forge_installer_manifest(
+/// What a Forge build is distributed as.
+enum ForgeDistribution {
+    /// 1.13+ (and late 1.12.2 builds): `version.json` plus processors run by the installer
+    Modern {
+        manifest: ForgeInstallerManifest,
+        install_profile: Option<serde_json::Value>,
+    },
+    /// 1.5.2 to 1.12.2: `install_profile.json` embedding the version and a universal jar
+    Legacy {
+        version_info: serde_json::Value,
+        library: String,
+        universal: Vec<u8>,
+    },
+    /// 1.5.1 and older: a zip to merge into the client jar
+    JarMod(Vec<u8>),
+}
+
+/// Legacy installer profile (`install_profile.json` with `versionInfo`)
+#[derive(Debug, Deserialize)]
+struct LegacyInstallProfile {
+    install: LegacyInstallInfo,
+    #[serde(rename = "versionInfo")]
+    version_info: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyInstallInfo {
+    /// Maven coordinate of the universal jar
+    path: String,
+    /// Name of the universal jar inside the installer
+    #[serde(rename = "filePath")]
+    file_path: String,
+}
+
+/// Download the installer, falling back to the universal/client zips of very old builds
+async fn fetch_forge_distribution(
     game_version: &str,
     forge_version: &str,
-) -> Result<ForgeInstallerManifest, Box<dyn Error + Send + Sync>> {
+) -> Result<ForgeDistribution, Box<dyn Error + Send + Sync>> {
     let forge_full = format!("{}-{}", game_version, forge_version);
-    
-    // Download the installer JAR to extract version.json
-    let installer_url = format!(
-        "{}net/minecraftforge/forge/{}/forge-{}-installer.jar",
-        FORGE_MAVEN_URL, forge_full, forge_full
+
+    if let Some(bytes) = download_forge_artifact(&forge_full, "installer.jar").await? {
+        return read_installer(&bytes);
+    }
+    for suffix in ["universal.zip", "client.zip"] {
+        if let Some(bytes) = download_forge_artifact(&forge_full, suffix).await? {
+            return Ok(ForgeDistribution::JarMod(bytes));
+        }
+    }
+    Err(format!("No Forge installer or universal package found for {}", forge_full).into())
+}
+
+/// Download `forge-<full>-<suffix>` from the Forge maven; `None` if it does not exist.
+async fn download_forge_artifact(
+    forge_full: &str,
+    suffix: &str,
+) -> Result<Option<Vec<u8>>, Box<dyn Error + Send + Sync>> {
+    let url = format!(
+        "{}net/minecraftforge/forge/{}/forge-{}-{}",
+        FORGE_MAVEN_URL, forge_full, forge_full, suffix
     );
-    
-    println!("Fetching Forge installer from: {}", installer_url);
-    
-    let response = reqwest::get(&installer_url).await?;
+    println!("Fetching Forge artifact from: {}", url);
+
+    let response = crate::core::http::client().get(&url).send().await?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
     if !response.status().is_success() {
-        return Err(format!("Failed to download Forge installer: {}", response.status()).into());
+        return Err(format!("Failed to download {}: {}", url, response.status()).into());
     }
-    
-    let bytes = response.bytes().await?;
-    
-    // Extract version.json from the JAR (which is a ZIP file)
-    let cursor = std::io::Cursor::new(bytes.as_ref());
-    let mut archive = zip::ZipArchive::new(cursor)?;
-    
-    // Look for version.json in the archive
-    let version_json = archive.by_name("version.json")?;
-    let manifest: ForgeInstallerManifest = serde_json::from_reader(version_json)?;
-    
-    Ok(manifest)
+    Ok(Some(response.bytes().await?.to_vec()))
+}
+
+/// Read the version information out of an installer JAR (a ZIP file)
+fn read_installer(bytes: &[u8]) -> Result<ForgeDistribution, Box<dyn Error + Send + Sync>> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))?;
+
+    let manifest = match archive.by_name("version.json") {
+        Ok(version_json) => Some(serde_json::from_reader(version_json)?),
+        Err(_) => None,
+    };
+    if let Some(manifest) = manifest {
+        let install_profile = archive
+            .by_name("install_profile.json")
+            .ok()
+            .and_then(|profile| serde_json::from_reader(profile).ok());
+        return Ok(ForgeDistribution::Modern {
+            manifest,
+            install_profile,
+        });
+    }
+
+    let profile: LegacyInstallProfile =
+        serde_json::from_reader(archive.by_name("install_profile.json")?)?;
+    let mut universal = Vec::new();
+    archive
+        .by_name(&profile.install.file_path)?
+        .read_to_end(&mut universal)?;
+    Ok(ForgeDistribution::Legacy {
+        version_info: profile.version_info,
+        library: profile.install.path,
+        universal,
+    })
+}
+
+/// Libraries the client side of an installer profile produces or reads:
+/// its `data` entries and the outputs of its processors.
+pub fn installer_outputs(profile: &serde_json::Value, libraries_dir: &Path) -> Vec<PathBuf> {
+    let data = &profile["data"];
+    // `{KEY}` refers to a data entry, `[coordinate]` to a library
+    let resolve = |value: &str| {
+        let value = match value.strip_prefix('{').and_then(|v| v.strip_suffix('}')) {
+            Some(key) => data[key]["client"].as_str()?,
+            None => value,
+        };
+        let coordinate = value.strip_prefix('[')?.strip_suffix(']')?;
+        crate::core::maven::get_library_path(coordinate, libraries_dir)
+    };
+
+    let mut paths: Vec<PathBuf> = data
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(_, entry)| entry["client"].as_str())
+        .filter_map(resolve)
+        .collect();
+    for processor in profile["processors"].as_array().into_iter().flatten() {
+        let client = processor["sides"]
+            .as_array()
+            .is_none_or(|sides| sides.iter().any(|side| side == "client"));
+        if client {
+            let outputs = processor["outputs"].as_object().into_iter().flatten();
+            paths.extend(outputs.filter_map(|(key, _)| resolve(key)));
+        }
+    }
+    paths
+}
+
+/// Whether this build's installer supports the headless `--installClient` mode.
+///
+/// Legacy installers only have a GUI; everything they do is handled by `install_forge`.
+pub fn has_headless_installer(game_version: &str, forge_version: &str) -> bool {
+    if is_modern_forge(game_version) {
+        return true;
+    }
+    // 1.12.2 switched to the new installer at 14.23.5.2851
+    game_version == "1.12.2"
+        && forge_version
+            .rsplit('.')
+            .next()
+            .and_then(|build| build.parse::<u32>().ok())
+            .is_some_and(|build| build >= 2851)
 }
 
 /// Install Forge for a specific Minecraft version.
@@ -229,12 +364,35 @@ pub async fn install_forge(
     forge_version: &str,
 ) -> Result<InstalledForgeVersion, Box<dyn Error + Send + Sync>> {
     let version_id = generate_version_id(game_version, forge_version);
+    let libraries_dir = game_dir.join("libraries");
 
-    // Fetch the installer manifest to get the complete version.json
-    let manifest = fetch_forge_installer_manifest(game_version, forge_version).await?;
-
-    // Create version JSON from the manifest
-    let version_json = create_forge_version_json_from_manifest(game_version, forge_version, &manifest)?;
+    let mut profile = None;
+    let version_json = match fetch_forge_distribution(game_version, forge_version).await? {
+        // Create version JSON from the installer manifest
+        ForgeDistribution::Modern {
+            manifest,
+            install_profile,
+        } => {
+            profile = install_profile;
+            create_forge_version_json_from_manifest(game_version, forge_version, &manifest)?
+        }
+        ForgeDistribution::Legacy {
+            version_info,
+            library,
+            universal,
+        } => {
+            let lib_path = crate::core::maven::get_library_path(&library, &libraries_dir)
+                .ok_or_else(|| format!("Invalid Forge library name: {}", library))?;
+            if let Some(parent) = lib_path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            tokio::fs::write(&lib_path, universal).await?;
+            create_legacy_version_json(game_version, forge_version, version_info)
+        }
+        ForgeDistribution::JarMod(universal) => {
+            install_jar_mod(game_dir, game_version, forge_version, &universal)?
+        }
+    };
 
     // Create the version directory
     let version_dir = game_dir.join("versions").join(&version_id);
@@ -244,6 +402,10 @@ pub async fn install_forge(
     let json_path = version_dir.join(format!("{}.json", version_id));
     let json_content = serde_json::to_string_pretty(&version_json)?;
     tokio::fs::write(&json_path, json_content).await?;
+    if let Some(profile) = profile {
+        let profile_content = serde_json::to_string_pretty(&profile)?;
+        tokio::fs::write(version_dir.join(INSTALL_PROFILE), profile_content).await?;
+    }
 
     Ok(InstalledForgeVersion {
         id: version_id,
@@ -257,6 +419,8 @@ pub async fn install_forge(
 /// This runs the Forge installer in headless mode to properly patch the client.
 ///
 /// # Arguments
+/// * `events` - Where the installer download reports progress
+/// * `config` - Download settings
 /// * `game_dir` - The .minecraft directory path
 /// * `game_version` - The Minecraft version
 /// * `forge_version` - The Forge version
@@ -265,52 +429,448 @@ pub async fn install_forge(
 /// # Returns
 /// Result indicating success or failure
 pub async fn run_forge_installer(
-    game_dir: &PathBuf,
+    events: &EventSink,
+    config: &EffectiveConfig,
+    game_dir: &Path,
     game_version: &str,
     forge_version: &str,
-    java_path: &PathBuf,
+    java_path: &Path,
 ) -> Result<(), Box<dyn Error + Send + Sync>> {
-    // Download the installer JAR
+    run_forge_installer_with_output(
+        events,
+        config,
+        game_dir,
+        game_version,
+        forge_version,
+        java_path,
+        |_| {},
+    )
+    .await?;
+    Ok(())
+}
+
+/// Global cancel flag for the running Forge installer
+static INSTALL_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Cancel the running Forge installer
+pub fn cancel_forge_install() {
+    INSTALL_CANCELLED.store(true, Ordering::SeqCst);
+}
+
+/// Errors raised by the Forge installer, classified from its output.
+#[derive(Debug)]
+pub enum ForgeInstallError {
+    /// The Java executable does not exist
+    JavaNotFound(PathBuf),
+    /// The installer or its processors need a newer Java
+    JavaTooOld { required: u32 },
+    /// The installer or Forge maven could not be reached
+    MavenUnreachable(String),
+    Cancelled,
+    Failed(String),
+}
+
+impl std::fmt::Display for ForgeInstallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ForgeInstallError::JavaNotFound(path) => {
+                write!(f, "Java not found at {}", path.display())
+            }
+            ForgeInstallError::JavaTooOld { required } => {
+                write!(f, "The Forge installer requires Java {} or newer", required)
+            }
+            ForgeInstallError::MavenUnreachable(detail) => {
+                write!(f, "Could not reach the Forge maven (are you offline?): {}", detail)
+            }
+            ForgeInstallError::Cancelled => write!(f, "Forge installation cancelled"),
+            ForgeInstallError::Failed(output) => write!(f, "Forge installer failed:\n{}", output),
+        }
+    }
+}
+
+impl Error for ForgeInstallError {}
+
+impl From<ForgeInstallError> for String {
+    fn from(e: ForgeInstallError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Minimum Java major version needed to run the installer's processors.
+pub fn required_java_major(game_version: &str) -> u32 {
+    let mut parts = game_version.split('.').skip(1);
+    let minor: u32 = parts.next().and_then(|p| p.parse().ok()).unwrap_or(0);
+    let patch: u32 = parts.next().and_then(|p| p.parse().ok()).unwrap_or(0);
+    match (minor, patch) {
+        (21.., _) | (20, 5..) => 21,
+        (18.., _) => 17,
+        (17, _) => 16,
+        _ => 8,
+    }
+}
+
+/// Lines of installer output kept for error reporting
+const OUTPUT_TAIL_LEN: usize = 40;
+
+/// Run the Forge installer, passing each line of its output to `on_output`.
+///
+/// The installer is downloaded into a temporary directory under
+/// `game_dir/cache`, which also receives its log file and is removed when the
+/// installer exits, fails or is cancelled with [`cancel_forge_install`].
+pub async fn run_forge_installer_with_output<F>(
+    events: &EventSink,
+    config: &EffectiveConfig,
+    game_dir: &Path,
+    game_version: &str,
+    forge_version: &str,
+    java_path: &Path,
+    on_output: F,
+) -> Result<(), ForgeInstallError>
+where
+    F: Fn(&str) + Send + Sync,
+{
+    run_installer(
+        events,
+        config,
+        InstallTarget::Client,
+        game_dir,
+        game_version,
+        forge_version,
+        java_path,
+        &on_output,
+    )
+    .await
+}
+
+/// Install a Forge dedicated server into `server_dir` with the installer's
+/// `--installServer` mode, passing each line of its output to `on_output`.
+pub async fn run_forge_server_installer<F>(
+    events: &EventSink,
+    config: &EffectiveConfig,
+    server_dir: &Path,
+    game_version: &str,
+    forge_version: &str,
+    java_path: &Path,
+    on_output: F,
+) -> Result<(), ForgeInstallError>
+where
+    F: Fn(&str) + Send + Sync,
+{
+    run_installer(
+        events,
+        config,
+        InstallTarget::Server,
+        server_dir,
+        game_version,
+        forge_version,
+        java_path,
+        &on_output,
+    )
+    .await
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum InstallTarget {
+    Client,
+    Server,
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_installer(
+    events: &EventSink,
+    config: &EffectiveConfig,
+    target: InstallTarget,
+    target_dir: &Path,
+    game_version: &str,
+    forge_version: &str,
+    java_path: &Path,
+    on_output: &(dyn Fn(&str) + Send + Sync),
+) -> Result<(), ForgeInstallError> {
+    INSTALL_CANCELLED.store(false, Ordering::SeqCst);
+
+    let temp_dir = target_dir
+        .join("cache")
+        .join(format!("forge-installer-{}", uuid::Uuid::new_v4()));
+    let result = run_installer_in(
+        events,
+        config,
+        target,
+        &temp_dir,
+        target_dir,
+        game_version,
+        forge_version,
+        java_path,
+        on_output,
+    )
+    .await;
+    let _ = tokio::fs::remove_dir_all(&temp_dir).await;
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_installer_in(
+    events: &EventSink,
+    config: &EffectiveConfig,
+    target: InstallTarget,
+    temp_dir: &Path,
+    game_dir: &Path,
+    game_version: &str,
+    forge_version: &str,
+    java_path: &Path,
+    on_output: &(dyn Fn(&str) + Send + Sync),
+) -> Result<(), ForgeInstallError> {
+    let failed = |e: std::io::Error| ForgeInstallError::Failed(e.to_string());
+    tokio::fs::create_dir_all(temp_dir).await.map_err(failed)?;
+
+    // The client installer refuses to run without a launcher profile file
+    let profiles_path = game_dir.join("launcher_profiles.json");
+    if target == InstallTarget::Client && !profiles_path.exists() {
+        tokio::fs::write(&profiles_path, r#"{"profiles":{}}"#)
+            .await
+            .map_err(failed)?;
+    }
+
     let installer_url = format!(
         "{}net/minecraftforge/forge/{}-{}/forge-{}-{}-installer.jar",
         FORGE_MAVEN_URL, game_version, forge_version, game_version, forge_version
     );
-    
-    let installer_path = game_dir.join("forge-installer.jar");
-    
-    // Download installer
-    let client = reqwest::Client::new();
-    let response = client.get(&installer_url).send().await?;
-    
-    if !response.status().is_success() {
-        return Err(format!("Failed to download Forge installer: {}", response.status()).into());
+    on_output(&format!("Downloading {}", installer_url));
+    let installer_path = temp_dir.join("forge-installer.jar");
+    let task = DownloadTask {
+        // The Forge maven publishes a .sha1 next to each artifact
+        sha1: crate::core::maven::fetch_sha1(&installer_url).await,
+        url: installer_url,
+        path: installer_path.clone(),
+        sha256: None,
+        size: None,
+        mirrors: Vec::new(),
+    };
+    download_files(events.clone(), vec![task], config)
+        .await
+        .map_err(ForgeInstallError::MavenUnreachable)?;
+
+    if INSTALL_CANCELLED.load(Ordering::SeqCst) {
+        return Err(ForgeInstallError::Cancelled);
     }
-    
-    let bytes = response.bytes().await?;
-    tokio::fs::write(&installer_path, &bytes).await?;
-    
-    // Run the installer in headless mode
-    // The installer accepts --installClient <path> to install to a specific directory
-    let output = tokio::process::Command::new(java_path)
+
+    // The installer accepts --installClient/--installServer <path> to install to a
+    // specific directory; legacy server installers ignore the path and use the working directory
+    let (mode, working_dir) = match target {
+        InstallTarget::Client => ("--installClient", temp_dir),
+        InstallTarget::Server => ("--installServer", game_dir),
+    };
+    let mut child = tokio::process::Command::new(java_path)
         .arg("-jar")
         .arg(&installer_path)
-        .arg("--installClient")
+        .arg(mode)
         .arg(game_dir)
-        .output()
-        .await?;
-    
-    // Clean up installer
-    let _ = tokio::fs::remove_file(&installer_path).await;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
+        .current_dir(working_dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                ForgeInstallError::JavaNotFound(java_path.to_path_buf())
+            }
+            _ => failed(e),
+        })?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    if let Some(stdout) = child.stdout.take() {
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let _ = tx.send(line);
+            }
+        });
+    }
+    if let Some(stderr) = child.stderr.take() {
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let _ = tx.send(line);
+            }
+        });
+    }
+    drop(tx);
+
+    let mut tail: VecDeque<String> = VecDeque::with_capacity(OUTPUT_TAIL_LEN);
+    loop {
+        if INSTALL_CANCELLED.load(Ordering::SeqCst) {
+            let _ = child.kill().await;
+            return Err(ForgeInstallError::Cancelled);
+        }
+        match tokio::time::timeout(Duration::from_millis(200), rx.recv()).await {
+            Ok(Some(line)) => {
+                on_output(&line);
+                if tail.len() == OUTPUT_TAIL_LEN {
+                    tail.pop_front();
+                }
+                tail.push_back(line);
+            }
+            // Both streams closed
+            Ok(None) => break,
+            Err(_) => {}
+        }
+    }
+
+    let status = child.wait().await.map_err(failed)?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(classify_failure(tail.make_contiguous(), game_version))
+    }
+}
+
+/// Map installer output to a specific error.
+fn classify_failure(output: &[String], game_version: &str) -> ForgeInstallError {
+    if output
+        .iter()
+        .any(|l| l.contains("UnsupportedClassVersionError"))
+    {
+        return ForgeInstallError::JavaTooOld {
+            required: required_java_major(game_version),
+        };
+    }
+    const NETWORK_MARKERS: [&str; 5] = [
+        "UnknownHostException",
+        "ConnectException",
+        "SocketTimeoutException",
+        "Connection reset",
+        "Failed to download",
+    ];
+    if let Some(line) = output
+        .iter()
+        .find(|l| NETWORK_MARKERS.iter().any(|m| l.contains(m)))
+    {
+        return ForgeInstallError::MavenUnreachable(line.trim().to_string());
+    }
+    ForgeInstallError::Failed(output.join("\n"))
+}
+
+/// Turn a legacy `versionInfo` into a version inheriting from vanilla.
+fn create_legacy_version_json(
+    game_version: &str,
+    forge_version: &str,
+    mut version_info: serde_json::Value,
+) -> serde_json::Value {
+    let version_id = generate_version_id(game_version, forge_version);
+    version_info["id"] = serde_json::Value::String(version_id);
+    version_info["inheritsFrom"] = serde_json::Value::String(game_version.to_string());
+    if let Some(obj) = version_info.as_object_mut() {
+        obj.remove("jar");
+    }
+
+    if let Some(libraries) = version_info["libraries"].as_array_mut() {
+        // Server-only libraries are marked `clientreq: false`
+        libraries.retain(|lib| lib["clientreq"].as_bool() != Some(false));
+        for lib in libraries.iter_mut() {
+            let Some(obj) = lib.as_object_mut() else {
+                continue;
+            };
+            obj.remove("clientreq");
+            obj.remove("serverreq");
+            obj.remove("checksums");
+            // The old maven host only redirects, and over plain HTTP
+            let legacy_host = obj
+                .get("url")
+                .and_then(|u| u.as_str())
+                .is_some_and(|u| u.contains("files.minecraftforge.net"));
+            if legacy_host {
+                obj.insert(
+                    "url".to_string(),
+                    serde_json::Value::String(FORGE_MAVEN_URL.to_string()),
+                );
+            }
+        }
+    }
+
+    version_info
+}
+
+/// Install a pre-1.5.2 Forge by merging it into a copy of the client jar.
+///
+/// The merged jar (Forge classes over vanilla ones, without Mojang's signature
+/// files) is stored as a library ahead of the vanilla jar on the classpath.
+fn install_jar_mod(
+    game_dir: &Path,
+    game_version: &str,
+    forge_version: &str,
+    universal: &[u8],
+) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>> {
+    let parent_dir = game_dir.join("versions").join(game_version);
+    let client_jar = parent_dir.join(format!("{}.jar", game_version));
+    let parent_json = parent_dir.join(format!("{}.json", game_version));
+    if !client_jar.exists() || !parent_json.exists() {
         return Err(format!(
-            "Forge installer failed:\nstdout: {}\nstderr: {}",
-            stdout, stderr
-        ).into());
+            "Minecraft {} must be installed before installing Forge {}",
+            game_version, forge_version
+        )
+        .into());
     }
-    
+    let parent: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&parent_json)?)?;
+
+    let library = format!(
+        "net.minecraftforge:minecraftforge-merged:{}-{}",
+        game_version, forge_version
+    );
+    let lib_path = crate::core::maven::get_library_path(&library, &game_dir.join("libraries"))
+        .ok_or("Invalid merged library name")?;
+    if let Some(dir) = lib_path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    merge_jar_mod(
+        std::fs::File::open(&client_jar)?,
+        std::io::Cursor::new(universal),
+        std::fs::File::create(&lib_path)?,
+    )?;
+
+    Ok(serde_json::json!({
+        "id": generate_version_id(game_version, forge_version),
+        "inheritsFrom": game_version,
+        "type": "release",
+        "mainClass": parent["mainClass"].as_str().unwrap_or("net.minecraft.client.Minecraft"),
+        "libraries": [{ "name": library }]
+    }))
+}
+
+/// Write `mod_zip` over `client` into `output`, dropping `META-INF`.
+fn merge_jar_mod<C, M, W>(
+    client: C,
+    mod_zip: M,
+    output: W,
+) -> Result<(), Box<dyn Error + Send + Sync>>
+where
+    C: std::io::Read + std::io::Seek,
+    M: std::io::Read + std::io::Seek,
+    W: std::io::Write + std::io::Seek,
+{
+    let mut writer = zip::ZipWriter::new(output);
+    let mut written = std::collections::HashSet::new();
+
+    let mut mod_archive = zip::ZipArchive::new(mod_zip)?;
+    for i in 0..mod_archive.len() {
+        let entry = mod_archive.by_index_raw(i)?;
+        if written.insert(entry.name().to_string()) {
+            writer.raw_copy_file(entry)?;
+        }
+    }
+
+    let mut client_archive = zip::ZipArchive::new(client)?;
+    for i in 0..client_archive.len() {
+        let entry = client_archive.by_index_raw(i)?;
+        if entry.name().starts_with("META-INF/") {
+            continue;
+        }
+        if written.insert(entry.name().to_string()) {
+            writer.raw_copy_file(entry)?;
+        }
+    }
+
+    writer.finish()?;
     Ok(())
 }
 
@@ -442,7 +1002,7 @@ fn create_forge_version_json(
 }
 
 /// Check if the Minecraft version uses modern Forge (1.13+).
-fn is_modern_forge(game_version: &str) -> bool {
+pub fn is_modern_forge(game_version: &str) -> bool {
     let parts: Vec<&str> = game_version.split('.').collect();
     if parts.len() >= 2 {
         if let (Ok(major), Ok(minor)) = (parts[0].parse::<u32>(), parts[1].parse::<u32>()) {
@@ -521,4 +1081,105 @@ mod tests {
         assert!(is_modern_forge("1.20.4"));
         assert!(is_modern_forge("1.21"));
     }
+
+    #[test]
+    fn test_has_headless_installer() {
+        assert!(has_headless_installer("1.20.4", "49.0.38"));
+        assert!(has_headless_installer("1.12.2", "14.23.5.2860"));
+        assert!(!has_headless_installer("1.12.2", "14.23.5.2847"));
+        assert!(!has_headless_installer("1.7.10", "10.13.4.1614-1.7.10"));
+    }
+
+    #[test]
+    fn test_legacy_version_json() {
+        let version_info = serde_json::json!({
+            "id": "1.7.10-Forge10.13.4.1614-1.7.10",
+            "jar": "1.7.10",
+            "mainClass": "net.minecraft.launchwrapper.Launch",
+            "libraries": [
+                {
+                    "name": "net.minecraftforge:forge:1.7.10-10.13.4.1614-1.7.10",
+                    "url": "http://files.minecraftforge.net/maven/"
+                },
+                {
+                    "name": "org.scala-lang:scala-library:2.11.1",
+                    "url": "http://files.minecraftforge.net/maven/",
+                    "checksums": ["abc"],
+                    "serverreq": true,
+                    "clientreq": true
+                },
+                { "name": "lzma:lzma:0.0.1", "serverreq": true, "clientreq": false }
+            ]
+        });
+        let json = create_legacy_version_json("1.7.10", "10.13.4.1614-1.7.10", version_info);
+        assert_eq!(json["id"], "1.7.10-forge-10.13.4.1614-1.7.10");
+        assert_eq!(json["inheritsFrom"], "1.7.10");
+        assert!(json.get("jar").is_none());
+        let libraries = json["libraries"].as_array().unwrap();
+        assert_eq!(libraries.len(), 2);
+        assert_eq!(libraries[1]["url"], FORGE_MAVEN_URL);
+        assert!(libraries[1].get("checksums").is_none());
+    }
+
+    #[test]
+    fn test_merge_jar_mod() {
+        use std::io::Write;
+
+        let zip_with = |entries: &[(&str, &str)]| {
+            let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+            for (name, content) in entries {
+                writer
+                    .start_file(*name, zip::write::SimpleFileOptions::default())
+                    .unwrap();
+                writer.write_all(content.as_bytes()).unwrap();
+            }
+            writer.finish().unwrap()
+        };
+        let client = zip_with(&[
+            ("META-INF/MOJANG_C.SF", "sig"),
+            ("a.class", "vanilla"),
+            ("b.class", "vanilla"),
+        ]);
+        let forge = zip_with(&[("a.class", "forge"), ("forge.class", "forge")]);
+
+        let mut output = std::io::Cursor::new(Vec::new());
+        merge_jar_mod(client, forge, &mut output).unwrap();
+
+        let mut merged = zip::ZipArchive::new(output).unwrap();
+        assert_eq!(merged.len(), 3);
+        assert!(merged.by_name("META-INF/MOJANG_C.SF").is_err());
+        let mut content = String::new();
+        merged
+            .by_name("a.class")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "forge");
+    }
+
+    #[test]
+    fn test_required_java_major() {
+        assert_eq!(required_java_major("1.12.2"), 8);
+        assert_eq!(required_java_major("1.17.1"), 16);
+        assert_eq!(required_java_major("1.20.4"), 17);
+        assert_eq!(required_java_major("1.20.6"), 21);
+        assert_eq!(required_java_major("1.21"), 21);
+    }
+
+    #[test]
+    fn test_classify_failure() {
+        let output = vec![
+            "Downloading libraries".to_string(),
+            "java.net.UnknownHostException: maven.minecraftforge.net".to_string(),
+        ];
+        assert!(matches!(
+            classify_failure(&output, "1.20.4"),
+            ForgeInstallError::MavenUnreachable(_)
+        ));
+        let output = vec!["java.lang.UnsupportedClassVersionError: x".to_string()];
+        assert!(matches!(
+            classify_failure(&output, "1.20.4"),
+            ForgeInstallError::JavaTooOld { required: 17 }
+        ));
+    }
 }
@@ -0,0 +1,340 @@
+//! Recognising what the player is doing from the game's log output.
+//!
+//! The client logs through log4j's XMLLayout (the `client-1.12.xml` setup),
+//! so `LogReader` joins the lines of each `<log4j:Event>` back into one
+//! `LogEvent`; output that is not XML passes through as plain lines.
+//! `parse_message` turns the client's messages about connecting to a server,
+//! starting a singleplayer world, disconnecting, players joining or leaving
+//! and advancements into `GameActivity` events. Chat lines are only trusted
+//! when they look like system messages, so a player typing "Steve joined the
+//! game" does not count. `ServerTracker` adds up the time spent on each
+//! server for the playtime stats.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::core::events::LauncherEvent;
+
+/// Something that happened in the game; `game-activity` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GameActivity {
+    /// `host:port` of a multiplayer server
+    ServerConnecting {
+        address: String,
+    },
+    SingleplayerStarted,
+    Disconnected {
+        reason: Option<String>,
+    },
+    PlayerJoined {
+        player: String,
+    },
+    PlayerLeft {
+        player: String,
+    },
+    Advancement {
+        player: String,
+        title: String,
+    },
+}
+
+impl LauncherEvent for GameActivity {
+    const NAME: &'static str = "game-activity";
+}
+
+/// Chat phrases announcing an advancement
+const ADVANCEMENT_PHRASES: [&str; 3] = [
+    " has made the advancement [",
+    " has completed the challenge [",
+    " has reached the goal [",
+];
+
+/// A log record of the game.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogEvent {
+    pub logger: Option<String>,
+    /// Milliseconds since the Unix epoch
+    pub timestamp: Option<u64>,
+    pub level: Option<String>,
+    pub thread: Option<String>,
+    pub message: String,
+}
+
+/// Turns game output into `LogEvent`s, buffering XML events that span
+/// several lines.
+#[derive(Debug, Default)]
+pub struct LogReader {
+    pending: Option<String>,
+}
+
+impl LogReader {
+    /// Feed one line of output; returns the event it completes, if any.
+    pub fn push(&mut self, line: &str) -> Option<LogEvent> {
+        if let Some(pending) = self.pending.as_mut() {
+            pending.push('\n');
+            pending.push_str(line);
+        } else if line.trim_start().starts_with("<log4j:Event") {
+            self.pending = Some(line.to_string());
+        } else {
+            return Some(plain_event(line));
+        }
+        if !line.contains("</log4j:Event>") {
+            return None;
+        }
+        let xml = self.pending.take()?;
+        Some(xml_event(&xml).unwrap_or_else(|| plain_event(&xml)))
+    }
+}
+
+/// "[12:34:56] [Render thread/INFO]: message"
+fn plain_event(line: &str) -> LogEvent {
+    let Some((header, message)) = line.split_once("]: ") else {
+        return LogEvent {
+            message: line.to_string(),
+            ..Default::default()
+        };
+    };
+    let source = header.rsplit_once(" [").map(|(_, source)| source);
+    let (thread, level) = match source.and_then(|source| source.rsplit_once('/')) {
+        Some((thread, level)) => (Some(thread.to_string()), Some(level.to_string())),
+        None => (None, None),
+    };
+    LogEvent {
+        level,
+        thread,
+        message: message.to_string(),
+        ..Default::default()
+    }
+}
+
+/// `<log4j:Event logger=".." timestamp=".." level=".." thread="..">` with a
+/// `<log4j:Message>` holding the text as CDATA.
+fn xml_event(xml: &str) -> Option<LogEvent> {
+    let start = xml.find("<log4j:Event")? + "<log4j:Event".len();
+    let tag = &xml[start..start + xml[start..].find('>')?];
+    let body = xml.split_once("<log4j:Message>")?.1;
+    let body = body.split_once("</log4j:Message>")?.0;
+    Some(LogEvent {
+        logger: attribute(tag, "logger"),
+        timestamp: attribute(tag, "timestamp").and_then(|t| t.parse().ok()),
+        level: attribute(tag, "level"),
+        thread: attribute(tag, "thread"),
+        message: character_data(body),
+    })
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let rest = tag.split_once(&format!(" {}=\"", name))?.1;
+    Some(unescape(rest.split_once('"')?.0))
+}
+
+/// Text of an element body; log4j splits messages containing "]]>" over
+/// several CDATA sections.
+fn character_data(body: &str) -> String {
+    let mut text = String::new();
+    let mut rest = body;
+    while let Some((before, after)) = rest.split_once("<![CDATA[") {
+        text.push_str(&unescape(before.trim()));
+        let (data, after) = after.split_once("]]>").unwrap_or((after, ""));
+        text.push_str(data);
+        rest = after;
+    }
+    text.push_str(&unescape(rest.trim()));
+    text
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Activity in a log message, if any.
+pub fn parse_message(message: &str) -> Option<GameActivity> {
+    if let Some(rest) = message.strip_prefix("Connecting to ") {
+        let (host, port) = rest.rsplit_once(", ")?;
+        port.parse::<u16>().ok()?;
+        return Some(GameActivity::ServerConnecting {
+            address: format!("{}:{}", host, port),
+        });
+    }
+    if message.starts_with("Starting integrated minecraft server version") {
+        return Some(GameActivity::SingleplayerStarted);
+    }
+    if let Some(reason) = message.strip_prefix("Client disconnected with reason: ") {
+        return Some(GameActivity::Disconnected {
+            reason: Some(reason.to_string()),
+        });
+    }
+    if message.starts_with("Stopping singleplayer server as player logged out") {
+        return Some(GameActivity::Disconnected { reason: None });
+    }
+
+    // "[System] [CHAT] Steve joined the game"; player messages start with "<Steve>"
+    let chat = message.split_once("[CHAT] ")?.1;
+    if let Some(player) = chat.strip_suffix(" joined the game") {
+        return is_player_name(player).then(|| GameActivity::PlayerJoined {
+            player: player.to_string(),
+        });
+    }
+    if let Some(player) = chat.strip_suffix(" left the game") {
+        return is_player_name(player).then(|| GameActivity::PlayerLeft {
+            player: player.to_string(),
+        });
+    }
+    ADVANCEMENT_PHRASES.iter().find_map(|phrase| {
+        let (player, title) = chat.split_once(phrase)?;
+        let title = title.strip_suffix(']')?;
+        is_player_name(player).then(|| GameActivity::Advancement {
+            player: player.to_string(),
+            title: title.to_string(),
+        })
+    })
+}
+
+fn is_player_name(name: &str) -> bool {
+    (1..=16).contains(&name.len()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Seconds spent on each multiplayer server during a session.
+#[derive(Debug, Default)]
+pub struct ServerTracker {
+    current: Option<(String, u64)>,
+    seconds: BTreeMap<String, u64>,
+}
+
+impl ServerTracker {
+    pub fn observe(&mut self, activity: &GameActivity, now: u64) {
+        match activity {
+            GameActivity::ServerConnecting { address } => {
+                self.leave(now);
+                self.current = Some((address.clone(), now));
+            }
+            GameActivity::SingleplayerStarted | GameActivity::Disconnected { .. } => {
+                self.leave(now)
+            }
+            _ => {}
+        }
+    }
+
+    /// Time per server, counting the current one until `now`.
+    pub fn finish(mut self, now: u64) -> BTreeMap<String, u64> {
+        self.leave(now);
+        self.seconds
+    }
+
+    fn leave(&mut self, now: u64) {
+        if let Some((address, since)) = self.current.take() {
+            *self.seconds.entry(address).or_default() += now.saturating_sub(since);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Option<GameActivity> {
+        parse_message(&plain_event(line).message)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("[18:02:11] [Render thread/INFO]: Connecting to mc.example.com, 25565"),
+            Some(GameActivity::ServerConnecting {
+                address: "mc.example.com:25565".to_string()
+            })
+        );
+        assert_eq!(
+            parse("[18:05:40] [Render thread/INFO]: [System] [CHAT] Alex joined the game"),
+            Some(GameActivity::PlayerJoined {
+                player: "Alex".to_string()
+            })
+        );
+        assert_eq!(
+            parse("[System] [CHAT] Alex has made the advancement [Stone Age]"),
+            Some(GameActivity::Advancement {
+                player: "Alex".to_string(),
+                title: "Stone Age".to_string()
+            })
+        );
+        assert_eq!(
+            parse("[18:06:30] [Render thread/INFO]: [CHAT] <Bob> Alex joined the game"),
+            None
+        );
+
+        let mut tracker = ServerTracker::default();
+        tracker.observe(
+            &GameActivity::ServerConnecting {
+                address: "mc.example.com:25565".to_string(),
+            },
+            100,
+        );
+        tracker.observe(&GameActivity::Disconnected { reason: None }, 400);
+        assert_eq!(tracker.finish(500)["mc.example.com:25565"], 300);
+    }
+
+    #[test]
+    fn test_log_reader() {
+        // Output of the client-1.12.xml configuration
+        let output = r#"<log4j:Event logger="net.minecraft.client.Minecraft" timestamp="1700000000123" level="INFO" thread="Render thread">
+  <log4j:Message><![CDATA[Setting user: Alex]]></log4j:Message>
+</log4j:Event>
+
+<log4j:Event logger="net.minecraft.client.gui.screens.ConnectScreen" timestamp="1700000005000" level="INFO" thread="Server Connector #1">
+  <log4j:Message><![CDATA[Connecting to mc.example.com, 25565]]></log4j:Message>
+</log4j:Event>
+
+<log4j:Event logger="net.minecraft.client.gui.components.ChatComponent" timestamp="1700000009000" level="INFO" thread="Render thread">
+  <log4j:Message><![CDATA[[System] [CHAT] Alex has made the advancement [Stone Age]]]></log4j:Message>
+</log4j:Event>
+
+<log4j:Event logger="com.example.&quot;Mod&quot;" timestamp="1700000010000" level="WARN" thread="Worker-Main-2">
+  <log4j:Message><![CDATA[first line
+end of ]]]]><![CDATA[>]]></log4j:Message>
+</log4j:Event>
+
+[18:02:11] [main/INFO]: Plain line"#;
+        let mut reader = LogReader::default();
+        let events: Vec<LogEvent> = output
+            .lines()
+            .filter_map(|line| reader.push(line))
+            .collect();
+        let events: Vec<&LogEvent> = events.iter().filter(|e| !e.message.is_empty()).collect();
+        assert_eq!(events.len(), 5);
+
+        assert_eq!(
+            events[1],
+            &LogEvent {
+                logger: Some("net.minecraft.client.gui.screens.ConnectScreen".to_string()),
+                timestamp: Some(1700000005000),
+                level: Some("INFO".to_string()),
+                thread: Some("Server Connector #1".to_string()),
+                message: "Connecting to mc.example.com, 25565".to_string(),
+            }
+        );
+        assert_eq!(
+            parse_message(&events[1].message),
+            Some(GameActivity::ServerConnecting {
+                address: "mc.example.com:25565".to_string()
+            })
+        );
+        assert_eq!(
+            parse_message(&events[2].message),
+            Some(GameActivity::Advancement {
+                player: "Alex".to_string(),
+                title: "Stone Age".to_string()
+            })
+        );
+        assert_eq!(events[3].logger.as_deref(), Some("com.example.\"Mod\""));
+        assert_eq!(events[3].level.as_deref(), Some("WARN"));
+        assert_eq!(events[3].message, "first line\nend of ]]>");
+        assert_eq!(events[4].thread.as_deref(), Some("main"));
+        assert_eq!(events[4].level.as_deref(), Some("INFO"));
+        assert_eq!(events[4].message, "Plain line");
+    }
+}
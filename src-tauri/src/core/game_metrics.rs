@@ -0,0 +1,133 @@
+//! CPU and memory usage of running games.
+//!
+//! `monitor` samples the game process every `SAMPLE_INTERVAL` until it
+//! exits. Each sample is sent as a `game-metrics` event and kept in
+//! `MetricsState` under the launch session, so a session's history can be
+//! shown next to its log. Only the most recent sessions are kept, in memory.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::core::events::LauncherEvent;
+
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+/// Six hours of samples per session
+const MAX_SAMPLES: usize = 6 * 60 * 60 / 5;
+const MAX_SESSIONS: usize = 8;
+
+/// One sample of a game process; `game-metrics` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct GameMetrics {
+    pub timestamp: u64,
+    /// Percent of one CPU core, so 250 means two and a half cores are busy
+    pub cpu_percent: f32,
+    /// Resident memory of the game process
+    pub memory_bytes: u64,
+    /// Memory left for other programs, low when the system is swapping
+    pub system_available_bytes: u64,
+}
+
+impl LauncherEvent for GameMetrics {
+    const NAME: &'static str = "game-metrics";
+}
+
+/// Managed state holding the samples of recent sessions.
+pub struct MetricsState {
+    sessions: Mutex<VecDeque<(String, Vec<GameMetrics>)>>,
+}
+
+impl MetricsState {
+    pub fn new() -> Self {
+        Self {
+            sessions: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn record(&self, session_id: &str, sample: GameMetrics) {
+        let mut sessions = self.sessions.lock().unwrap();
+        let index = match sessions.iter().position(|(id, _)| id == session_id) {
+            Some(index) => index,
+            None => {
+                if sessions.len() == MAX_SESSIONS {
+                    sessions.pop_front();
+                }
+                sessions.push_back((session_id.to_string(), Vec::new()));
+                sessions.len() - 1
+            }
+        };
+        let samples = &mut sessions[index].1;
+        if samples.len() == MAX_SAMPLES {
+            samples.remove(0);
+        }
+        samples.push(sample);
+    }
+
+    /// Samples of `session_id`, oldest first.
+    pub fn get(&self, session_id: &str) -> Option<Vec<GameMetrics>> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .iter()
+            .find(|(id, _)| id == session_id)
+            .map(|(_, samples)| samples.clone())
+    }
+}
+
+/// Sample process `pid` until it exits, passing each sample to `on_sample`.
+pub async fn monitor(pid: u32, mut on_sample: impl FnMut(GameMetrics)) {
+    let pid = sysinfo::Pid::from_u32(pid);
+    let refresh = sysinfo::ProcessRefreshKind::nothing()
+        .with_cpu()
+        .with_memory();
+    let mut system = sysinfo::System::new();
+    let mut ticker = tokio::time::interval(SAMPLE_INTERVAL);
+    loop {
+        ticker.tick().await;
+        system.refresh_processes_specifics(sysinfo::ProcessesToUpdate::Some(&[pid]), true, refresh);
+        system.refresh_memory();
+        let Some(process) = system.process(pid) else {
+            break;
+        };
+        on_sample(GameMetrics {
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            cpu_percent: process.cpu_usage(),
+            memory_bytes: process.memory(),
+            system_available_bytes: system.available_memory(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: u64) -> GameMetrics {
+        GameMetrics {
+            timestamp,
+            cpu_percent: 0.0,
+            memory_bytes: 0,
+            system_available_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn test_state_keeps_recent_sessions() {
+        let state = MetricsState::new();
+        for session in 0..=MAX_SESSIONS {
+            state.record(&session.to_string(), sample(1));
+        }
+        state.record("1", sample(2));
+        assert!(state.get("0").is_none());
+        let timestamps: Vec<u64> = state
+            .get("1")
+            .unwrap()
+            .iter()
+            .map(|s| s.timestamp)
+            .collect();
+        assert_eq!(timestamps, [1, 2]);
+    }
+}
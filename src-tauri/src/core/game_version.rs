@@ -26,6 +26,25 @@ pub struct GameVersion {
     /// Release type (release, snapshot, old_beta, etc.)
     #[serde(rename = "type")]
     pub version_type: Option<String>,
+    /// Log4j configuration (Mojang ships patched configs here for 1.7-1.18.1)
+    pub logging: Option<Logging>,
+    /// Version whose client jar is used, when it is neither this version's
+    /// nor the one it inherits from (OptiFine, imported custom jars)
+    pub jar: Option<String>,
+    /// 0 for versions lacking the current player safety features
+    #[serde(rename = "complianceLevel")]
+    pub compliance_level: Option<u32>,
+}
+
+impl GameVersion {
+    /// Id of the `versions/<id>/<id>.jar` this version launches, read from the
+    /// version's own JSON before inheritance is merged.
+    pub fn client_jar_version(&self) -> &str {
+        self.jar
+            .as_deref()
+            .or(self.inherits_from.as_deref())
+            .unwrap_or(&self.id)
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -60,6 +79,8 @@ pub struct Library {
     pub natives: Option<serde_json::Value>,
     /// Maven repository URL for mod loader libraries
     pub url: Option<String>,
+    /// Hash of the jar, given by Fabric Meta for libraries without `downloads`
+    pub sha1: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -88,6 +109,28 @@ pub struct Arguments {
     pub jvm: Option<serde_json::Value>,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Logging {
+    pub client: Option<LoggingConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LoggingConfig {
+    /// JVM argument template, e.g. "-Dlog4j.configurationFile=${path}"
+    pub argument: String,
+    pub file: LoggingFile,
+    #[serde(rename = "type")]
+    pub log_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LoggingFile {
+    pub id: String,
+    pub sha1: Option<String>,
+    pub size: Option<u64>,
+    pub url: String,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct JavaVersion {
     pub component: String,
@@ -0,0 +1,199 @@
+//! Garbage collection logs for tuning memory settings.
+//!
+//! With `gc_logging` on, the game runs with `-Xlog:gc*` writing to
+//! `logs/gc.log` in its game directory (Java 9 and newer; Java 8 uses a
+//! different log format and is skipped). `analyze` reads the pause lines,
+//! like `GC(12) Pause Young (Normal) (G1 Evacuation Pause) 120M->45M(512M)
+//! 7.891ms`, and sums up pause times and how much heap stays in use after
+//! collections, which is what the maximum memory has to fit.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::core::events::LauncherEvent;
+
+/// Pauses this long are noticeable as stutters
+const LONG_PAUSE_MS: f64 = 100.0;
+
+/// Summary of a GC log; `gc-report` event after a game exits.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GcReport {
+    pub pauses: usize,
+    /// Full collections, which stop the game the longest
+    pub full_pauses: usize,
+    pub total_pause_ms: f64,
+    pub max_pause_ms: f64,
+    pub average_pause_ms: f64,
+    /// Heap in use before a collection, at most
+    pub peak_heap_mb: u64,
+    /// Heap still in use after a collection, at most; roughly the memory
+    /// the game really needs
+    pub max_live_heap_mb: u64,
+    /// Largest heap the JVM had reserved
+    pub heap_capacity_mb: u64,
+    /// Game run time covered by the log
+    pub uptime_secs: f64,
+    /// Suggestions for the memory settings
+    pub notes: Vec<String>,
+}
+
+impl LauncherEvent for GcReport {
+    const NAME: &'static str = "gc-report";
+}
+
+/// Where the GC log of a game running in `game_dir` goes.
+pub fn log_path(game_dir: &Path) -> PathBuf {
+    game_dir.join("logs").join("gc.log")
+}
+
+/// JVM arguments logging GCs to `log_path`, if `java_major` supports the
+/// unified logging syntax.
+pub fn jvm_args(java_major: u32, log_path: &Path) -> Option<Vec<String>> {
+    if java_major < 9 {
+        return None;
+    }
+    // Quoted because Windows paths contain ':', the option separator
+    Some(vec![format!(
+        "-Xlog:gc*:file=\"{}\":uptime,level,tags:filecount=0",
+        log_path.display()
+    )])
+}
+
+/// Summarise the GC log at `path` of a game that ran with `max_memory_mb`.
+pub fn analyze_file(path: &Path, max_memory_mb: u32) -> Result<GcReport, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(analyze(&content, max_memory_mb))
+}
+
+pub fn analyze(content: &str, max_memory_mb: u32) -> GcReport {
+    let mut report = GcReport::default();
+    for line in content.lines() {
+        if let Some(uptime) = line
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once("s]"))
+            .and_then(|(uptime, _)| uptime.parse::<f64>().ok())
+        {
+            report.uptime_secs = report.uptime_secs.max(uptime);
+        }
+        let Some(message) = line
+            .split_once(" GC(")
+            .and_then(|(_, rest)| rest.split_once(") "))
+            .map(|(_, message)| message)
+        else {
+            continue;
+        };
+        if !message.starts_with("Pause") {
+            continue;
+        }
+        // Start lines of a pause carry no duration
+        let Some(duration) = message
+            .rsplit(' ')
+            .next()
+            .and_then(|last| last.strip_suffix("ms"))
+            .and_then(|ms| ms.parse::<f64>().ok())
+        else {
+            continue;
+        };
+        report.pauses += 1;
+        if message.starts_with("Pause Full") {
+            report.full_pauses += 1;
+        }
+        report.total_pause_ms += duration;
+        report.max_pause_ms = report.max_pause_ms.max(duration);
+        if let Some((before, after, capacity)) = message.split(' ').find_map(heap_transition) {
+            report.peak_heap_mb = report.peak_heap_mb.max(before);
+            report.max_live_heap_mb = report.max_live_heap_mb.max(after);
+            report.heap_capacity_mb = report.heap_capacity_mb.max(capacity);
+        }
+    }
+    if report.pauses > 0 {
+        report.average_pause_ms = report.total_pause_ms / report.pauses as f64;
+    }
+    report.notes = notes(&report, max_memory_mb);
+    report
+}
+
+fn notes(report: &GcReport, max_memory_mb: u32) -> Vec<String> {
+    let mut notes = Vec::new();
+    if report.pauses == 0 {
+        notes.push("No collections were logged; play longer for useful numbers".to_string());
+        return notes;
+    }
+    let max_memory_mb = u64::from(max_memory_mb);
+    if report.full_pauses > 0 {
+        notes.push(format!(
+            "{} full collections ran because the heap was full; raise the maximum memory",
+            report.full_pauses
+        ));
+    }
+    if report.max_live_heap_mb * 100 > max_memory_mb * 85 {
+        notes.push(format!(
+            "Up to {} MB stayed in use after collections, close to the maximum of {} MB; raise it",
+            report.max_live_heap_mb, max_memory_mb
+        ));
+    } else if max_memory_mb > 2048 && report.max_live_heap_mb * 3 < max_memory_mb {
+        // Twice the live data leaves room to work with, in 512 MB steps
+        let suggested = (report.max_live_heap_mb * 2).div_ceil(512).max(4) * 512;
+        if suggested < max_memory_mb {
+            notes.push(format!(
+                "At most {} MB stayed in use after collections; a maximum of {} MB would be enough",
+                report.max_live_heap_mb, suggested
+            ));
+        }
+    }
+    if report.max_pause_ms > LONG_PAUSE_MS {
+        notes.push(format!(
+            "The longest pause took {:.0} ms, long enough to notice as a stutter",
+            report.max_pause_ms
+        ));
+    }
+    notes
+}
+
+/// Sizes in MB from a token like `120M->45M(512M)`.
+fn heap_transition(token: &str) -> Option<(u64, u64, u64)> {
+    let (before, rest) = token.split_once("->")?;
+    let (after, capacity) = rest.strip_suffix(')')?.split_once('(')?;
+    Some((size_mb(before)?, size_mb(after)?, size_mb(capacity)?))
+}
+
+fn size_mb(size: &str) -> Option<u64> {
+    let (number, unit) = size.split_at(size.len().checked_sub(1)?);
+    let number: u64 = number.parse().ok()?;
+    match unit {
+        "K" => Some(number / 1024),
+        "M" => Some(number),
+        "G" => Some(number * 1024),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze() {
+        let log = "\
+[0.010s][info][gc,init] Version: 21.0.4+7-LTS
+[5.120s][info][gc,start    ] GC(0) Pause Young (Normal) (G1 Evacuation Pause)
+[5.128s][info][gc          ] GC(0) Pause Young (Normal) (G1 Evacuation Pause) 120M->45M(512M) 8.000ms
+[9.000s][info][gc,phases   ] GC(1)   Evacuate Collection Set: 3.1ms
+[60.500s][info][gc          ] GC(2) Pause Full (G1 Compaction Pause) 1900M->1800M(2048M) 250.000ms
+";
+        let report = analyze(log, 2048);
+        assert_eq!(report.pauses, 2);
+        assert_eq!(report.full_pauses, 1);
+        assert_eq!(report.max_pause_ms, 250.0);
+        assert_eq!(report.average_pause_ms, 129.0);
+        assert_eq!(report.peak_heap_mb, 1900);
+        assert_eq!(report.max_live_heap_mb, 1800);
+        assert_eq!(report.heap_capacity_mb, 2048);
+        assert_eq!(report.uptime_secs, 60.5);
+        assert_eq!(report.notes.len(), 3);
+
+        assert_eq!(heap_transition("2G->512M(4G)"), Some((2048, 512, 4096)));
+        assert_eq!(jvm_args(8, Path::new("gc.log")), None);
+    }
+}
@@ -0,0 +1,145 @@
+//! GPU selection on hybrid-graphics machines.
+//!
+//! On Linux the game is offloaded through PRIME: Mesa drivers read
+//! `DRI_PRIME`, NVIDIA's proprietary driver `__NV_PRIME_RENDER_OFFLOAD` and
+//! `__GLX_VENDOR_LIBRARY_NAME`. Windows keeps the preference per executable
+//! under the DirectX `UserGpuPreferences` registry key, the same setting as
+//! Settings > Display > Graphics, so it is written for the Java executable
+//! before each launch. macOS switches GPUs on its own.
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::display::DisplaySetup;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GpuPreference {
+    /// Leave the choice to the system
+    #[default]
+    Default,
+    Integrated,
+    Discrete,
+}
+
+/// NVIDIA variables that send OpenGL and Vulkan to the discrete GPU
+const NVIDIA_OFFLOAD: [(&str, &str); 3] = [
+    ("__NV_PRIME_RENDER_OFFLOAD", "1"),
+    ("__GLX_VENDOR_LIBRARY_NAME", "nvidia"),
+    ("__VK_LAYER_NV_optimus", "NVIDIA_only"),
+];
+
+/// Environment changes, and on Windows the registry entry, that make the
+/// game started with `java_path` run on the preferred GPU.
+pub fn setup(preference: GpuPreference, java_path: &str) -> DisplaySetup {
+    if preference == GpuPreference::Default {
+        return DisplaySetup::default();
+    }
+    if cfg!(target_os = "linux") {
+        let nvidia = std::path::Path::new("/proc/driver/nvidia/version").exists();
+        return linux_plan(preference, nvidia);
+    }
+    let mut setup = DisplaySetup::default();
+    if cfg!(target_os = "windows") {
+        if let Err(e) = set_windows_preference(preference, java_path) {
+            setup
+                .notes
+                .push(format!("Could not set the GPU preference: {}", e));
+        }
+    }
+    setup
+}
+
+fn linux_plan(preference: GpuPreference, nvidia: bool) -> DisplaySetup {
+    let mut setup = DisplaySetup::default();
+    let owned = |(name, value): (&str, &str)| (name.to_string(), value.to_string());
+    match preference {
+        GpuPreference::Default => {}
+        GpuPreference::Discrete if nvidia => setup.env.extend(NVIDIA_OFFLOAD.map(owned)),
+        GpuPreference::Discrete => setup.env.push(owned(("DRI_PRIME", "1"))),
+        GpuPreference::Integrated => {
+            setup.env.push(owned(("DRI_PRIME", "0")));
+            setup
+                .env_remove
+                .extend(NVIDIA_OFFLOAD.map(|(name, _)| name.to_string()));
+        }
+    }
+    setup
+}
+
+/// Value of the `UserGpuPreferences` entry: 1 is power saving, 2 high
+/// performance.
+#[cfg(any(target_os = "windows", test))]
+fn windows_value(preference: GpuPreference) -> Option<&'static str> {
+    match preference {
+        GpuPreference::Default => None,
+        GpuPreference::Integrated => Some("GpuPreference=1;"),
+        GpuPreference::Discrete => Some("GpuPreference=2;"),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn set_windows_preference(preference: GpuPreference, java_path: &str) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let Some(value) = windows_value(preference) else {
+        return Ok(());
+    };
+    // Entries are keyed by the full path of the executable
+    let java = std::fs::canonicalize(java_path)
+        .map_err(|_| format!("{} is not a full path to Java", java_path))?;
+    let java = java.to_string_lossy();
+    let java = java.strip_prefix(r"\\?\").unwrap_or(&java);
+    let status = std::process::Command::new("reg")
+        .args([
+            "add",
+            r"HKCU\Software\Microsoft\DirectX\UserGpuPreferences",
+            "/v",
+            java,
+            "/t",
+            "REG_SZ",
+            "/d",
+            value,
+            "/f",
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .status()
+        .map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err(format!("reg exited with {}", status));
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn set_windows_preference(_preference: GpuPreference, _java_path: &str) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linux_plan() {
+        let mesa = linux_plan(GpuPreference::Discrete, false);
+        assert_eq!(mesa.env, [("DRI_PRIME".to_string(), "1".to_string())]);
+
+        let nvidia = linux_plan(GpuPreference::Discrete, true);
+        assert_eq!(nvidia.env.len(), 3);
+        assert!(nvidia.env_remove.is_empty());
+
+        let integrated = linux_plan(GpuPreference::Integrated, true);
+        assert_eq!(integrated.env, [("DRI_PRIME".to_string(), "0".to_string())]);
+        assert_eq!(integrated.env_remove.len(), 3);
+
+        assert_eq!(
+            linux_plan(GpuPreference::Default, true),
+            DisplaySetup::default()
+        );
+        assert_eq!(
+            windows_value(GpuPreference::Discrete),
+            Some("GpuPreference=2;")
+        );
+    }
+}
@@ -0,0 +1,244 @@
+//! Shared HTTP client.
+//!
+//! All launcher HTTP traffic goes through the client built here, so proxy,
+//! User-Agent and timeout settings from `LauncherConfig` apply everywhere
+//! (auth, downloads, meta APIs) and connections are pooled across modules.
+//! The client is built lazily and rebuilt when the settings change. An
+//! invalid proxy is an error rather than a reason to connect directly.
+//!
+//! HTTP/2 is negotiated where the server supports it, so the thousands of
+//! small asset requests of a fresh install share a few connections.
+
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::core::config::{LauncherConfig, ProxyConfig};
+
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Whole-request timeout for small API calls (not used for file downloads)
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_RETRIES: u32 = 3;
+
+#[derive(Clone, PartialEq)]
+struct HttpSettings {
+    proxy: Option<ProxyConfig>,
+    user_agent: Option<String>,
+    connect_timeout: Duration,
+    request_timeout: Duration,
+}
+
+static SETTINGS: RwLock<HttpSettings> = RwLock::new(HttpSettings {
+    proxy: None,
+    user_agent: None,
+    connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+    request_timeout: DEFAULT_REQUEST_TIMEOUT,
+});
+static CLIENT: RwLock<Option<reqwest::Client>> = RwLock::new(None);
+
+/// Default User-Agent, e.g. `DropOut/0.1.0 (linux)`.
+pub fn default_user_agent() -> String {
+    format!(
+        "DropOut/{} ({})",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS
+    )
+}
+
+impl HttpSettings {
+    fn from_config(config: &LauncherConfig) -> Self {
+        Self {
+            proxy: Some(config.proxy.clone()),
+            user_agent: config.user_agent.clone().filter(|ua| !ua.trim().is_empty()),
+            connect_timeout: Duration::from_secs(config.connect_timeout_secs.max(1)),
+            request_timeout: Duration::from_secs(config.request_timeout_secs.max(1)),
+        }
+    }
+}
+
+/// Apply proxy, User-Agent and timeout settings; the shared client is rebuilt on next use.
+pub fn configure(config: &LauncherConfig) {
+    *SETTINGS.write().unwrap() = HttpSettings::from_config(config);
+    *CLIENT.write().unwrap() = None;
+}
+
+/// A client builder with the configured proxy, User-Agent, timeouts and gzip applied.
+///
+/// Prefer `client()`; build a separate client only when it needs different settings.
+pub fn client_builder() -> Result<reqwest::ClientBuilder, String> {
+    builder_with(&SETTINGS.read().unwrap())
+}
+
+/// Check that `proxy` can be used, before saving it.
+pub fn check_proxy(proxy: &ProxyConfig) -> Result<(), String> {
+    proxy_for(proxy).map(drop)
+}
+
+fn proxy_for(proxy: &ProxyConfig) -> Result<Option<reqwest::Proxy>, String> {
+    let Some(url) = proxy.url() else {
+        return Ok(None);
+    };
+    let mut p = reqwest::Proxy::all(&url).map_err(|e| format!("Invalid proxy {}: {}", url, e))?;
+    if let Some(username) = &proxy.username {
+        p = p.basic_auth(username, proxy.password.as_deref().unwrap_or(""));
+    }
+    Ok(Some(p))
+}
+
+fn builder_with(settings: &HttpSettings) -> Result<reqwest::ClientBuilder, String> {
+    let mut builder = reqwest::Client::builder()
+        .user_agent(
+            settings
+                .user_agent
+                .clone()
+                .unwrap_or_else(default_user_agent),
+        )
+        .connect_timeout(settings.connect_timeout)
+        .tcp_keepalive(Duration::from_secs(60))
+        .http2_adaptive_window(true)
+        .gzip(true);
+
+    if let Some(proxy) = settings
+        .proxy
+        .as_ref()
+        .map(proxy_for)
+        .transpose()?
+        .flatten()
+    {
+        builder = builder.proxy(proxy);
+    }
+    Ok(builder)
+}
+
+/// The shared client. Cheap to call; clones share one connection pool.
+///
+/// `save_settings` refuses invalid proxies, so failing to build the client
+/// means a config file edited by hand; that is logged and the default client
+/// used.
+pub fn client() -> reqwest::Client {
+    if let Some(client) = CLIENT.read().unwrap().as_ref() {
+        return client.clone();
+    }
+    let mut cached = CLIENT.write().unwrap();
+    cached
+        .get_or_insert_with(|| {
+            client_builder()
+                .and_then(|builder| builder.build().map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| {
+                    eprintln!("[HTTP] Failed to build the HTTP client: {}", e);
+                    reqwest::Client::default()
+                })
+        })
+        .clone()
+}
+
+/// A client for `config`, which may carry an instance's proxy and User-Agent.
+/// Returns the shared client when the settings match the launcher's.
+pub fn client_for(config: &LauncherConfig) -> Result<reqwest::Client, String> {
+    let settings = HttpSettings::from_config(config);
+    if settings == *SETTINGS.read().unwrap() {
+        return Ok(client());
+    }
+    builder_with(&settings)?
+        .build()
+        .map_err(|e| format!("Failed to build the HTTP client: {}", e))
+}
+
+/// Configured whole-request timeout for API calls.
+pub fn request_timeout() -> Duration {
+    SETTINGS.read().unwrap().request_timeout
+}
+
+/// GET a small resource (JSON metadata), see `send`.
+pub async fn get(url: &str) -> Result<reqwest::Response, reqwest::Error> {
+    send(client().get(url)).await
+}
+
+/// Send an API request with the request timeout, retrying connection errors,
+/// timeouts and 5xx responses with a short backoff. Requests with a
+/// streaming body cannot be cloned and are sent only once.
+pub async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response, reqwest::Error> {
+    let request = request.timeout(request_timeout());
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let Some(this_attempt) = request.try_clone() else {
+            return request.send().await;
+        };
+        let (client, built) = this_attempt.build_split();
+        let built = built?;
+        crate::core::logging::debug_request(&built);
+        let started = std::time::Instant::now();
+        let result = client.execute(built).await;
+        if crate::core::logging::debug_mode() {
+            let outcome = match &result {
+                Ok(resp) => resp.status().to_string(),
+                Err(e) if e.is_timeout() => "timed out".to_string(),
+                Err(_) => "failed".to_string(),
+            };
+            crate::core::logging::debug(
+                "HTTP",
+                format!("-> {} in {} ms", outcome, started.elapsed().as_millis()),
+            );
+        }
+        let retryable = match &result {
+            Ok(resp) => resp.status().is_server_error(),
+            Err(e) => e.is_connect() || e.is_timeout() || e.is_request(),
+        };
+        if !retryable || attempt >= MAX_RETRIES {
+            return result;
+        }
+        tokio::time::sleep(Duration::from_millis(500 * 2u64.pow(attempt - 1))).await;
+    }
+}
+
+/// Whether a HEAD request for `url` succeeds.
+pub async fn exists(url: &str) -> bool {
+    client()
+        .head(url)
+        .timeout(request_timeout())
+        .send()
+        .await
+        .is_ok_and(|resp| resp.status().is_success())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::config::{ProxyConfig, ProxyKind};
+
+    #[test]
+    fn test_proxy_url_and_jvm_args() {
+        let mut proxy = ProxyConfig {
+            enabled: true,
+            kind: ProxyKind::Socks5,
+            host: "127.0.0.1".to_string(),
+            port: 1080,
+            ..Default::default()
+        };
+        assert_eq!(proxy.url().as_deref(), Some("socks5h://127.0.0.1:1080"));
+        assert!(proxy.jvm_args().is_empty());
+
+        proxy.apply_to_game = true;
+        assert_eq!(
+            proxy.jvm_args(),
+            vec!["-DsocksProxyHost=127.0.0.1", "-DsocksProxyPort=1080"]
+        );
+
+        proxy.enabled = false;
+        assert_eq!(proxy.url(), None);
+        assert!(proxy.jvm_args().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_proxy_is_an_error() {
+        let mut proxy = ProxyConfig {
+            enabled: true,
+            kind: ProxyKind::Http,
+            host: "proxy host".to_string(),
+            port: 8080,
+            ..Default::default()
+        };
+        assert!(super::check_proxy(&proxy).is_err());
+        proxy.host = "proxy.example.com".to_string();
+        assert!(super::check_proxy(&proxy).is_ok());
+    }
+}
@@ -0,0 +1,178 @@
+//! Conditional-request cache for metadata APIs.
+//!
+//! Responses from the version manifest, Fabric meta, Forge promotions and
+//! Adoptium are stored under `cache/http/` together with their `ETag` /
+//! `Last-Modified` headers. Within the TTL the stored body is returned without
+//! a request; after it, a conditional request revalidates the entry, so an
+//! unchanged resource costs a `304` instead of a full download. If the network
+//! is unavailable, a stale entry is served rather than failing.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// Version manifest and loader version lists change a few times a day at most
+pub const TTL_SHORT: Duration = Duration::from_secs(10 * 60);
+pub const TTL_HOUR: Duration = Duration::from_secs(60 * 60);
+/// Content that effectively never changes once published (e.g. loader profiles)
+pub const TTL_DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+static CACHE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Set the cache directory (`<app_data>/cache/http`). Until set, requests bypass the cache.
+pub fn set_cache_dir(dir: PathBuf) {
+    *CACHE_DIR.write().unwrap() = Some(dir);
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheMeta {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    fetched_at: u64,
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn entry_paths(url: &str) -> Option<(PathBuf, PathBuf)> {
+    let dir = CACHE_DIR.read().unwrap().clone()?;
+    let key = crate::core::downloader::compute_sha1(url.as_bytes());
+    Some((
+        dir.join(format!("{}.json", key)),
+        dir.join(format!("{}.body", key)),
+    ))
+}
+
+/// GET `url` as text, using the cache.
+pub async fn get_text(url: &str, ttl: Duration) -> Result<String, String> {
+    let Some((meta_path, body_path)) = entry_paths(url) else {
+        return fetch_plain(url).await;
+    };
+
+    let meta: Option<CacheMeta> = tokio::fs::read_to_string(&meta_path)
+        .await
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok());
+    let cached_body = match &meta {
+        Some(_) => tokio::fs::read_to_string(&body_path).await.ok(),
+        None => None,
+    };
+
+    if let (Some(meta), Some(body)) = (&meta, &cached_body) {
+        if now_secs().saturating_sub(meta.fetched_at) < ttl.as_secs() {
+            return Ok(body.clone());
+        }
+    }
+
+    let mut request = crate::core::http::client()
+        .get(url)
+        .timeout(Duration::from_secs(30));
+    if let (Some(meta), Some(_)) = (&meta, &cached_body) {
+        if let Some(etag) = &meta.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &meta.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            return match cached_body {
+                Some(body) => {
+                    println!("[Cache] {} unreachable, using stale copy: {}", url, e);
+                    Ok(body)
+                }
+                None => Err(format!("Request to {} failed: {}", url, e)),
+            };
+        }
+    };
+
+    if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+        if let (Some(mut meta), Some(body)) = (meta, cached_body.clone()) {
+            meta.fetched_at = now_secs();
+            write_meta(&meta_path, &meta).await;
+            return Ok(body);
+        }
+    }
+
+    if !response.status().is_success() {
+        return match cached_body {
+            Some(body) => Ok(body),
+            None => Err(format!("{} returned {}", url, response.status())),
+        };
+    }
+
+    let header = |name: reqwest::header::HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+    };
+    let new_meta = CacheMeta {
+        url: url.to_string(),
+        etag: header(reqwest::header::ETAG),
+        last_modified: header(reqwest::header::LAST_MODIFIED),
+        fetched_at: now_secs(),
+    };
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", url, e))?;
+
+    if let Some(parent) = body_path.parent() {
+        let _ = tokio::fs::create_dir_all(parent).await;
+    }
+    if tokio::fs::write(&body_path, &body).await.is_ok() {
+        write_meta(&meta_path, &new_meta).await;
+    }
+
+    Ok(body)
+}
+
+/// GET `url` and parse it as JSON, using the cache.
+pub async fn get_json<T: DeserializeOwned>(url: &str, ttl: Duration) -> Result<T, String> {
+    let body = get_text(url, ttl).await?;
+    serde_json::from_str(&body).map_err(|e| format!("Invalid response from {}: {}", url, e))
+}
+
+/// Remove all cached responses.
+pub async fn clear() -> Result<(), String> {
+    let dir = CACHE_DIR.read().unwrap().clone();
+    if let Some(dir) = dir {
+        if dir.exists() {
+            tokio::fs::remove_dir_all(&dir)
+                .await
+                .map_err(|e| format!("Failed to clear cache: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+async fn write_meta(path: &Path, meta: &CacheMeta) {
+    if let Ok(content) = serde_json::to_string(meta) {
+        let _ = tokio::fs::write(path, content).await;
+    }
+}
+
+async fn fetch_plain(url: &str) -> Result<String, String> {
+    let response = crate::core::http::get(url)
+        .await
+        .map_err(|e| format!("Request to {} failed: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned {}", url, response.status()));
+    }
+    response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", url, e))
+}
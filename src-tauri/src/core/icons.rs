@@ -0,0 +1,115 @@
+//! Instance icons.
+//!
+//! `Instance.icon` holds either `builtin:<name>` for one of the icons shipped
+//! with the UI, or the file name of a custom image stored in the instance
+//! directory. Custom images are re-encoded as a PNG that fits within
+//! `ICON_SIZE`, so arbitrary user files never reach the webview directly.
+
+use base64::Engine;
+use serde::Serialize;
+use std::path::Path;
+
+/// Edge length of stored custom icons
+pub const ICON_SIZE: u32 = 128;
+const CUSTOM_ICON_FILE: &str = "icon.png";
+const BUILTIN_PREFIX: &str = "builtin:";
+
+/// Icons bundled with the UI.
+pub const BUILTIN_ICONS: [&str; 10] = [
+    "grass",
+    "dirt",
+    "stone",
+    "crafting_table",
+    "furnace",
+    "tnt",
+    "creeper",
+    "diamond",
+    "ender_pearl",
+    "bookshelf",
+];
+
+/// An icon ready for the UI to render.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum InstanceIcon {
+    /// One of `BUILTIN_ICONS`; the UI owns the artwork
+    Builtin { name: String },
+    /// A custom image as a `data:image/png;base64,...` URL
+    Image { data_url: String },
+}
+
+/// `Instance.icon` value for a built-in icon.
+pub fn builtin_icon(name: &str) -> Result<String, String> {
+    if !BUILTIN_ICONS.contains(&name) {
+        return Err(format!("Unknown built-in icon: {}", name));
+    }
+    Ok(format!("{}{}", BUILTIN_PREFIX, name))
+}
+
+/// Decode `source`, scale it to fit `ICON_SIZE` and store it as the
+/// instance's custom icon. Returns the `Instance.icon` value.
+pub fn store_custom_icon(source: &Path, instance_dir: &Path) -> Result<String, String> {
+    let image = image::open(source)
+        .map_err(|e| format!("Failed to read image {}: {}", source.display(), e))?;
+    let image = if image.width() > ICON_SIZE || image.height() > ICON_SIZE {
+        image.resize(ICON_SIZE, ICON_SIZE, image::imageops::FilterType::Lanczos3)
+    } else {
+        image
+    };
+
+    std::fs::create_dir_all(instance_dir).map_err(|e| e.to_string())?;
+    image
+        .save_with_format(instance_dir.join(CUSTOM_ICON_FILE), image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to save icon: {}", e))?;
+    Ok(CUSTOM_ICON_FILE.to_string())
+}
+
+/// Resolve an `Instance.icon` value.
+pub fn load_icon(icon: &str, instance_dir: &Path) -> Result<InstanceIcon, String> {
+    if let Some(name) = icon.strip_prefix(BUILTIN_PREFIX) {
+        return Ok(InstanceIcon::Builtin {
+            name: name.to_string(),
+        });
+    }
+    // Only the file written by `store_custom_icon` is ever read
+    if icon != CUSTOM_ICON_FILE {
+        return Err(format!("Unsupported icon: {}", icon));
+    }
+    let bytes = std::fs::read(instance_dir.join(CUSTOM_ICON_FILE))
+        .map_err(|e| format!("Failed to read icon: {}", e))?;
+    Ok(InstanceIcon::Image {
+        data_url: format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(bytes)
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_icon() {
+        assert_eq!(builtin_icon("creeper").unwrap(), "builtin:creeper");
+        assert!(builtin_icon("../../etc/passwd").is_err());
+        assert!(matches!(
+            load_icon("builtin:creeper", Path::new("/nonexistent")),
+            Ok(InstanceIcon::Builtin { .. })
+        ));
+        assert!(load_icon("../secret.png", Path::new("/nonexistent")).is_err());
+    }
+
+    #[test]
+    fn test_store_custom_icon_scales_down() {
+        let dir = std::env::temp_dir().join(format!("dropout-icon-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("big.png");
+        image::RgbaImage::new(512, 256).save(&source).unwrap();
+
+        let icon = store_custom_icon(&source, &dir.join("instance")).unwrap();
+        let stored = image::open(dir.join("instance").join(&icon)).unwrap();
+        assert_eq!((stored.width(), stored.height()), (128, 64));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
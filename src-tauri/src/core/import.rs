@@ -0,0 +1,610 @@
+//! Importing game data from other launchers.
+//!
+//! - A standard `.minecraft` directory from the official launcher: versions,
+//!   libraries and assets are hard-linked (or copied) into DropOut's data
+//!   directory after checksum validation, and profiles from
+//!   `launcher_profiles.json` are reported so they can be turned into instances.
+//! - A single MultiMC / Prism Launcher (`instance.cfg` + `mmc-pack.json`) or
+//!   ATLauncher (`instance.json`) instance, as a folder or exported zip.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::downloader::compute_sha1;
+use crate::core::game_version::{DownloadArtifact, GameVersion};
+use crate::core::instance::Instance;
+use crate::core::manifest::LoaderType;
+use crate::utils::file_utils::{copy_dir_recursive, link_or_copy};
+
+/// Per-instance content copied from a profile's game directory.
+const USER_CONTENT_DIRS: [&str; 5] = ["saves", "mods", "config", "resourcepacks", "shaderpacks"];
+const USER_CONTENT_FILES: [&str; 2] = ["options.txt", "servers.dat"];
+
+/// A profile found in `launcher_profiles.json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectedProfile {
+    pub name: String,
+    /// May be `latest-release` / `latest-snapshot` for the built-in profiles
+    pub version_id: String,
+    pub game_dir: PathBuf,
+}
+
+/// Summary of a `.minecraft` import.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct VanillaImportReport {
+    pub versions: Vec<String>,
+    pub libraries_imported: usize,
+    pub assets_imported: usize,
+    /// Files skipped because their checksum did not match
+    pub corrupt_files: Vec<String>,
+    pub profiles: Vec<DetectedProfile>,
+    /// Instances created from the detected profiles
+    pub instances: Vec<Instance>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LauncherProfiles {
+    #[serde(default)]
+    profiles: HashMap<String, LauncherProfile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LauncherProfile {
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type", default)]
+    profile_type: String,
+    #[serde(rename = "lastVersionId")]
+    last_version_id: Option<String>,
+    #[serde(rename = "gameDir")]
+    game_dir: Option<String>,
+}
+
+/// Import versions, libraries and assets from a `.minecraft` directory.
+///
+/// Files already present in `game_dir` are left alone. Files with a known
+/// SHA1 (client jars, libraries listed in version JSONs, asset objects) are
+/// verified first and skipped if corrupt.
+///
+/// This does blocking I/O and should run on a blocking thread.
+pub fn import_vanilla_files(source: &Path, game_dir: &Path) -> Result<VanillaImportReport, String> {
+    if !source.join("versions").is_dir() {
+        return Err(format!(
+            "{} does not look like a .minecraft directory (no versions folder)",
+            source.display()
+        ));
+    }
+
+    let mut report = VanillaImportReport::default();
+    let mut library_hashes: HashMap<PathBuf, String> = HashMap::new();
+
+    // Versions
+    let entries = fs::read_dir(source.join("versions")).map_err(|e| e.to_string())?;
+    for entry in entries.flatten() {
+        let id = entry.file_name().to_string_lossy().to_string();
+        let src_dir = entry.path();
+        let json_path = src_dir.join(format!("{}.json", id));
+        let Ok(content) = fs::read_to_string(&json_path) else {
+            continue;
+        };
+        let Ok(version) = serde_json::from_str::<GameVersion>(&content) else {
+            continue;
+        };
+
+        collect_library_hashes(&version, &mut library_hashes);
+
+        let dest_dir = game_dir.join("versions").join(&id);
+        let dest_json = dest_dir.join(format!("{}.json", id));
+        if dest_json.exists() {
+            continue;
+        }
+
+        let src_jar = src_dir.join(format!("{}.jar", id));
+        if src_jar.exists() {
+            let expected = version
+                .downloads
+                .as_ref()
+                .and_then(|d| d.client.sha1.as_deref());
+            if file_matches(&src_jar, expected) {
+                link_or_copy(&src_jar, &dest_dir.join(format!("{}.jar", id)))
+                    .map_err(|e| format!("Failed to import {}: {}", src_jar.display(), e))?;
+            } else {
+                report
+                    .corrupt_files
+                    .push(src_jar.to_string_lossy().to_string());
+            }
+        }
+
+        fs::create_dir_all(&dest_dir).map_err(|e| e.to_string())?;
+        fs::copy(&json_path, &dest_json).map_err(|e| e.to_string())?;
+        report.versions.push(id);
+    }
+
+    // Libraries
+    let src_libraries = source.join("libraries");
+    let dest_libraries = game_dir.join("libraries");
+    for file in walk_files(&src_libraries) {
+        let Ok(rel) = file.strip_prefix(&src_libraries) else {
+            continue;
+        };
+        let dest = dest_libraries.join(rel);
+        if dest.exists() {
+            continue;
+        }
+        if !file_matches(&file, library_hashes.get(rel).map(|s| s.as_str())) {
+            report
+                .corrupt_files
+                .push(file.to_string_lossy().to_string());
+            continue;
+        }
+        link_or_copy(&file, &dest).map_err(|e| e.to_string())?;
+        report.libraries_imported += 1;
+    }
+
+    // Asset indexes
+    let src_indexes = source.join("assets").join("indexes");
+    let dest_indexes = game_dir.join("assets").join("indexes");
+    for file in walk_files(&src_indexes) {
+        if let Some(name) = file.file_name() {
+            let dest = dest_indexes.join(name);
+            if !dest.exists() {
+                link_or_copy(&file, &dest).map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    // Asset objects are named after their SHA1
+    let src_objects = source.join("assets").join("objects");
+    let dest_objects = game_dir.join("assets").join("objects");
+    for file in walk_files(&src_objects) {
+        let Ok(rel) = file.strip_prefix(&src_objects) else {
+            continue;
+        };
+        let dest = dest_objects.join(rel);
+        if dest.exists() {
+            continue;
+        }
+        let hash = file
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if !file_matches(&file, Some(&hash)) {
+            report
+                .corrupt_files
+                .push(file.to_string_lossy().to_string());
+            continue;
+        }
+        link_or_copy(&file, &dest).map_err(|e| e.to_string())?;
+        report.assets_imported += 1;
+    }
+
+    report.profiles = read_profiles(source);
+
+    Ok(report)
+}
+
+/// Read profiles from `launcher_profiles.json`. Missing or invalid files yield no profiles.
+pub fn read_profiles(source: &Path) -> Vec<DetectedProfile> {
+    let Ok(content) = fs::read_to_string(source.join("launcher_profiles.json")) else {
+        return Vec::new();
+    };
+    let Ok(parsed) = serde_json::from_str::<LauncherProfiles>(&content) else {
+        return Vec::new();
+    };
+
+    let mut profiles: Vec<DetectedProfile> = parsed
+        .profiles
+        .into_values()
+        .filter_map(|p| {
+            let version_id = match p.profile_type.as_str() {
+                "latest-release" => "latest-release".to_string(),
+                "latest-snapshot" => "latest-snapshot".to_string(),
+                _ => p.last_version_id?,
+            };
+            let name = if p.name.is_empty() {
+                match p.profile_type.as_str() {
+                    "latest-release" => "Latest Release".to_string(),
+                    "latest-snapshot" => "Latest Snapshot".to_string(),
+                    _ => version_id.clone(),
+                }
+            } else {
+                p.name
+            };
+            let game_dir = p
+                .game_dir
+                .map(PathBuf::from)
+                .unwrap_or_else(|| source.to_path_buf());
+            Some(DetectedProfile {
+                name,
+                version_id,
+                game_dir,
+            })
+        })
+        .collect();
+
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    profiles
+}
+
+/// Copy a profile's saves, mods, configs and options into an instance directory.
+pub fn copy_user_content(profile_game_dir: &Path, instance_dir: &Path) -> Result<(), String> {
+    for dir in USER_CONTENT_DIRS {
+        let src = profile_game_dir.join(dir);
+        if src.is_dir() {
+            copy_dir_recursive(&src, &instance_dir.join(dir))
+                .map_err(|e| format!("Failed to copy {}: {}", dir, e))?;
+        }
+    }
+    for file in USER_CONTENT_FILES {
+        let src = profile_game_dir.join(file);
+        if src.is_file() {
+            fs::create_dir_all(instance_dir).map_err(|e| e.to_string())?;
+            fs::copy(&src, instance_dir.join(file))
+                .map_err(|e| format!("Failed to copy {}: {}", file, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// An instance read from another launcher.
+#[derive(Debug, Clone, Serialize)]
+pub struct ForeignInstance {
+    pub name: String,
+    pub minecraft_version: String,
+    pub loader: LoaderType,
+    pub loader_version: Option<String>,
+    /// DropOut version id the instance should launch
+    pub version_id: String,
+    /// Directory holding saves, mods, configs, ...
+    pub game_dir: PathBuf,
+    /// Instance icon shipped with the instance or export, if any
+    pub icon_path: Option<PathBuf>,
+}
+
+/// Result of importing an instance from another launcher.
+#[derive(Debug, Clone, Serialize)]
+pub struct LauncherImportResult {
+    pub instance: Instance,
+    pub source: ForeignInstance,
+    /// Whether `version_id` is already installed; if not, the UI should offer to install it
+    pub version_installed: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct MmcPack {
+    #[serde(default)]
+    components: Vec<MmcComponent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MmcComponent {
+    uid: String,
+    version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AtLauncherInstance {
+    /// Minecraft version
+    id: String,
+    launcher: AtLauncherMeta,
+}
+
+#[derive(Debug, Deserialize)]
+struct AtLauncherMeta {
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "loaderVersion")]
+    loader_version: Option<AtLauncherLoader>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AtLauncherLoader {
+    #[serde(rename = "type")]
+    loader_type: String,
+    version: String,
+}
+
+/// Locate and read an instance from another launcher.
+///
+/// `dir` may be the instance folder itself or a folder wrapping it
+/// (exported zips usually contain a single top-level directory).
+pub fn read_foreign_instance(dir: &Path) -> Result<ForeignInstance, String> {
+    if let Some(instance) = try_read_foreign_instance(dir)? {
+        return Ok(instance);
+    }
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if entry.path().is_dir() {
+                if let Some(instance) = try_read_foreign_instance(&entry.path())? {
+                    return Ok(instance);
+                }
+            }
+        }
+    }
+    Err(format!(
+        "No MultiMC, Prism or ATLauncher instance found in {}",
+        dir.display()
+    ))
+}
+
+fn try_read_foreign_instance(dir: &Path) -> Result<Option<ForeignInstance>, String> {
+    if dir.join("mmc-pack.json").is_file() {
+        read_multimc_instance(dir).map(Some)
+    } else if dir.join("instance.json").is_file() {
+        read_atlauncher_instance(dir).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Read a MultiMC / Prism Launcher instance folder.
+fn read_multimc_instance(dir: &Path) -> Result<ForeignInstance, String> {
+    let pack_content = fs::read_to_string(dir.join("mmc-pack.json"))
+        .map_err(|e| format!("Failed to read mmc-pack.json: {}", e))?;
+    let pack: MmcPack =
+        serde_json::from_str(&pack_content).map_err(|e| format!("Invalid mmc-pack.json: {}", e))?;
+
+    let cfg = fs::read_to_string(dir.join("instance.cfg")).unwrap_or_default();
+    let mut cfg = parse_cfg(&cfg);
+    let name = cfg.remove("name").unwrap_or_else(|| dir_name(dir));
+    // Exports place a custom icon next to instance.cfg, named after its key
+    let icon_path = cfg
+        .remove("iconKey")
+        .and_then(|key| find_icon(dir, &[&key, "icon"]));
+
+    let mut minecraft_version = None;
+    let mut loader = LoaderType::Vanilla;
+    let mut loader_version = None;
+    for component in pack.components {
+        let detected = match component.uid.as_str() {
+            "net.minecraft" => {
+                minecraft_version = component.version;
+                continue;
+            }
+            "net.fabricmc.fabric-loader" => LoaderType::Fabric,
+            "org.quiltmc.quilt-loader" => LoaderType::Quilt,
+            "net.minecraftforge" => LoaderType::Forge,
+            "net.neoforged" => LoaderType::NeoForge,
+            _ => continue,
+        };
+        loader = detected;
+        loader_version = component.version;
+    }
+    let minecraft_version =
+        minecraft_version.ok_or("mmc-pack.json has no net.minecraft component")?;
+
+    // Prism uses `minecraft/`, older MultiMC instances `.minecraft/`
+    let game_dir = [".minecraft", "minecraft"]
+        .iter()
+        .map(|d| dir.join(d))
+        .find(|d| d.is_dir())
+        .unwrap_or_else(|| dir.join(".minecraft"));
+
+    Ok(ForeignInstance {
+        version_id: loader_version_id(&minecraft_version, loader, loader_version.as_deref()),
+        name,
+        minecraft_version,
+        loader,
+        loader_version,
+        game_dir,
+        icon_path,
+    })
+}
+
+/// Read an ATLauncher instance folder.
+fn read_atlauncher_instance(dir: &Path) -> Result<ForeignInstance, String> {
+    let content = fs::read_to_string(dir.join("instance.json"))
+        .map_err(|e| format!("Failed to read instance.json: {}", e))?;
+    let parsed: AtLauncherInstance = serde_json::from_str(&content)
+        .map_err(|e| format!("Not an ATLauncher instance.json: {}", e))?;
+
+    let (loader, loader_version) = match parsed.launcher.loader_version {
+        Some(l) => {
+            let loader = match l.loader_type.to_lowercase().as_str() {
+                "fabric" => LoaderType::Fabric,
+                "quilt" => LoaderType::Quilt,
+                "forge" => LoaderType::Forge,
+                "neoforge" => LoaderType::NeoForge,
+                other => return Err(format!("Unsupported ATLauncher loader: {}", other)),
+            };
+            (loader, Some(l.version))
+        }
+        None => (LoaderType::Vanilla, None),
+    };
+
+    let name = if parsed.launcher.name.is_empty() {
+        dir_name(dir)
+    } else {
+        parsed.launcher.name
+    };
+
+    Ok(ForeignInstance {
+        version_id: loader_version_id(&parsed.id, loader, loader_version.as_deref()),
+        name,
+        minecraft_version: parsed.id,
+        loader,
+        loader_version,
+        game_dir: dir.to_path_buf(),
+        icon_path: find_icon(dir, &["instance", "icon"]),
+    })
+}
+
+/// Find `<stem>.png` / `.jpg` / `.jpeg` in `dir`, trying stems in order.
+fn find_icon(dir: &Path, stems: &[&str]) -> Option<PathBuf> {
+    stems.iter().find_map(|stem| {
+        ["png", "jpg", "jpeg"]
+            .iter()
+            .map(|ext| dir.join(format!("{}.{}", stem, ext)))
+            .find(|p| p.is_file())
+    })
+}
+
+/// DropOut version id for a game version and loader.
+pub fn loader_version_id(
+    minecraft_version: &str,
+    loader: LoaderType,
+    loader_version: Option<&str>,
+) -> String {
+    let Some(loader_version) = loader_version else {
+        return minecraft_version.to_string();
+    };
+    match loader {
+        LoaderType::Vanilla => minecraft_version.to_string(),
+        LoaderType::Fabric => {
+            crate::core::fabric::generate_version_id(minecraft_version, loader_version)
+        }
+        LoaderType::Quilt => format!("quilt-loader-{}-{}", loader_version, minecraft_version),
+        LoaderType::Forge => {
+            crate::core::forge::generate_version_id(minecraft_version, loader_version)
+        }
+        LoaderType::NeoForge => format!("neoforge-{}", loader_version),
+    }
+}
+
+/// Parse a MultiMC `instance.cfg` (INI-style `key=value` lines).
+fn parse_cfg(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .filter(|l| !l.starts_with('[') && !l.starts_with('#'))
+        .filter_map(|l| l.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect()
+}
+
+fn dir_name(dir: &Path) -> String {
+    dir.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Imported instance".to_string())
+}
+
+/// Record the expected SHA1 of every library artifact a version lists.
+fn collect_library_hashes(version: &GameVersion, hashes: &mut HashMap<PathBuf, String>) {
+    for lib in &version.libraries {
+        let Some(downloads) = &lib.downloads else {
+            continue;
+        };
+        let mut artifacts: Vec<DownloadArtifact> = downloads.artifact.iter().cloned().collect();
+        if let Some(classifiers) = downloads.classifiers.as_ref().and_then(|c| c.as_object()) {
+            artifacts.extend(
+                classifiers
+                    .values()
+                    .filter_map(|v| serde_json::from_value::<DownloadArtifact>(v.clone()).ok()),
+            );
+        }
+        for artifact in artifacts {
+            if let (Some(path), Some(sha1)) = (artifact.path, artifact.sha1) {
+                hashes.insert(PathBuf::from(path), sha1);
+            }
+        }
+    }
+}
+
+/// Check a file against an expected SHA1; files without a known hash pass.
+fn file_matches(path: &Path, expected_sha1: Option<&str>) -> bool {
+    match expected_sha1 {
+        Some(expected) => fs::read(path)
+            .map(|data| compute_sha1(&data).eq_ignore_ascii_case(expected))
+            .unwrap_or(false),
+        None => true,
+    }
+}
+
+/// All regular files below `dir`.
+fn walk_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return files;
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            files.extend(walk_files(&entry.path()));
+        } else if file_type.is_file() {
+            files.push(entry.path());
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_profiles() {
+        let dir =
+            std::env::temp_dir().join(format!("dropout-import-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("launcher_profiles.json"),
+            r#"{"profiles": {
+                "a": {"name": "", "type": "latest-release", "lastVersionId": "latest-release"},
+                "b": {"name": "Modded", "type": "custom", "lastVersionId": "1.20.4-forge-49.0.38", "gameDir": "/tmp/modded"},
+                "c": {"name": "Broken", "type": "custom"}
+            }}"#,
+        )
+        .unwrap();
+
+        let profiles = read_profiles(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].name, "Latest Release");
+        assert_eq!(profiles[0].version_id, "latest-release");
+        assert_eq!(profiles[0].game_dir, dir);
+        assert_eq!(profiles[1].version_id, "1.20.4-forge-49.0.38");
+        assert_eq!(profiles[1].game_dir, PathBuf::from("/tmp/modded"));
+    }
+
+    #[test]
+    fn test_read_multimc_instance() {
+        let root = std::env::temp_dir().join(format!("dropout-mmc-test-{}", uuid::Uuid::new_v4()));
+        let dir = root.join("My Pack");
+        fs::create_dir_all(dir.join("minecraft")).unwrap();
+        fs::write(
+            dir.join("instance.cfg"),
+            "[General]\nInstanceType=OneSix\nname=My Pack\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("mmc-pack.json"),
+            r#"{"formatVersion": 1, "components": [
+                {"uid": "org.lwjgl3", "version": "3.3.1"},
+                {"uid": "net.minecraft", "version": "1.20.1"},
+                {"uid": "net.fabricmc.intermediary", "version": "1.20.1"},
+                {"uid": "net.fabricmc.fabric-loader", "version": "0.15.6"}
+            ]}"#,
+        )
+        .unwrap();
+
+        // Exported zips wrap the instance in a top-level folder
+        let instance = read_foreign_instance(&root).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(instance.name, "My Pack");
+        assert_eq!(instance.minecraft_version, "1.20.1");
+        assert_eq!(instance.loader, LoaderType::Fabric);
+        assert_eq!(instance.version_id, "fabric-loader-0.15.6-1.20.1");
+        assert_eq!(instance.game_dir, dir.join("minecraft"));
+    }
+
+    #[test]
+    fn test_loader_version_id() {
+        assert_eq!(
+            loader_version_id("1.20.1", LoaderType::Vanilla, None),
+            "1.20.1"
+        );
+        assert_eq!(
+            loader_version_id("1.20.1", LoaderType::Forge, Some("47.2.0")),
+            "1.20.1-forge-47.2.0"
+        );
+        assert_eq!(
+            loader_version_id("1.20.4", LoaderType::NeoForge, Some("20.4.80")),
+            "neoforge-20.4.80"
+        );
+    }
+}
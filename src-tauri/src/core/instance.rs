@@ -0,0 +1,654 @@
+//! Game instances.
+//!
+//! An instance is a separate game directory (saves, mods, configs, options)
+//! bound to a version. By default versions, libraries and assets stay shared
+//! in the launcher data directory; an isolated instance keeps its own copies
+//! under its directory, so it can be patched or moved independently.
+//!
+//! Layout:
+//! - `instances.json` - the `InstanceIndex` (display order, active instance)
+//! - `instances/<id>/instance.json` - the `Instance` metadata
+//! - `instances/<id>/` - the instance's game directory
+//! - `archive/<id>.zip` - the files of an archived instance
+//! - `instances/<id>/jarmods/` - jar mods merged into the client jar
+//! - `instances/<id>/previous-version/` - copy of the version JSON used before
+//!   the last version change, see `InstanceManager::roll_back_version`
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::core::config::{JvmOverrides, NetworkOverrides};
+use crate::core::error::LauncherError;
+use crate::core::game_version::GameVersion;
+use crate::core::instance_archive::ArchiveInfo;
+use crate::core::instance_backup::BackupReason;
+use crate::core::instance_lock::InstanceLocks;
+use crate::core::jar_mods::JarMod;
+use crate::core::library_override::LibraryOverride;
+use crate::core::manifest::LoaderType;
+use crate::utils::file_utils;
+
+/// Folder in an instance holding the JSON of its previous version
+const PREVIOUS_VERSION_DIR: &str = "previous-version";
+
+/// A game instance.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Instance {
+    pub id: String,
+    pub name: String,
+    /// Version to launch (vanilla or mod loader version id)
+    pub version_id: String,
+    /// Version used before the last version change, for rollbacks
+    pub previous_version_id: Option<String>,
+    /// Icon path or built-in icon name
+    pub icon: Option<String>,
+    pub created_at: u64,
+    pub last_played: Option<u64>,
+    /// Keep versions, libraries and assets inside the instance directory
+    pub isolated: bool,
+    /// Group the instance is shown under, e.g. "Modpacks"
+    pub group: Option<String>,
+    /// Freeform tags used for filtering
+    pub tags: Vec<String>,
+    /// Short description shown in the instance list
+    pub description: Option<String>,
+    /// Free-form user notes
+    pub notes: Option<String>,
+    /// Modpack the instance was created from
+    pub modpack: Option<ModpackSource>,
+    /// Download and proxy settings overriding the launcher's
+    pub network: NetworkOverrides,
+    /// Memory and JVM settings overriding the launcher's
+    pub jvm: JvmOverrides,
+    /// Mods merged into the client jar, in merge order, see `jar_mods`
+    pub jar_mods: Vec<JarMod>,
+    /// Library versions replacing those of the version JSON
+    pub library_overrides: Vec<LibraryOverride>,
+    /// UUID of the stored account that always launches this instance,
+    /// whichever account is active
+    pub account_id: Option<String>,
+    /// Set while the instance's files are packed away, see `instance_archive`
+    pub archived: Option<ArchiveInfo>,
+}
+
+/// Platform a modpack was installed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModpackPlatform {
+    Modrinth,
+    CurseForge,
+}
+
+/// Origin of a modpack instance, used to look up updates.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModpackSource {
+    pub platform: ModpackPlatform,
+    /// Modrinth project id or CurseForge project id
+    pub project_id: String,
+    /// Modrinth version id or CurseForge file id of the installed release
+    pub version_id: String,
+    #[serde(default)]
+    pub project_name: Option<String>,
+    /// Human-readable version, e.g. "1.4.2"
+    #[serde(default)]
+    pub version_name: Option<String>,
+}
+
+/// Criteria for filtering instances; unset fields match everything.
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+pub struct InstanceFilter {
+    pub group: Option<String>,
+    /// Instances must have every one of these tags (case-insensitive)
+    pub tags: Vec<String>,
+    /// Case-insensitive substring of the name or version
+    pub query: Option<String>,
+}
+
+impl InstanceFilter {
+    pub fn matches(&self, instance: &Instance) -> bool {
+        if let Some(group) = &self.group {
+            if instance.group.as_deref() != Some(group.as_str()) {
+                return false;
+            }
+        }
+        let has_tags = self.tags.iter().all(|wanted| {
+            instance
+                .tags
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(wanted))
+        });
+        if !has_tags {
+            return false;
+        }
+        match &self.query {
+            Some(query) => {
+                let query = query.to_lowercase();
+                instance.name.to_lowercase().contains(&query)
+                    || instance.version_id.to_lowercase().contains(&query)
+            }
+            None => true,
+        }
+    }
+}
+
+/// Index of all instances, stored in `instances.json`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct InstanceIndex {
+    /// Instance ids in display order
+    pub instances: Vec<String>,
+    pub active_instance_id: Option<String>,
+    /// Group names in display order
+    pub groups: Vec<String>,
+}
+
+/// Managed state for creating, loading and deleting instances.
+pub struct InstanceManager {
+    pub index: Mutex<InstanceIndex>,
+    pub instances_dir: PathBuf,
+    /// Zips of archived instances
+    pub archive_dir: PathBuf,
+    index_path: PathBuf,
+    /// Held by operations on an instance's files
+    pub locks: InstanceLocks,
+    /// Called after the index or an instance is saved
+    on_change: Mutex<Option<Box<dyn Fn() + Send + Sync>>>,
+}
+
+impl InstanceManager {
+    pub fn new(app_data_dir: &Path) -> Self {
+        let index_path = app_data_dir.join("instances.json");
+        let index = file_utils::read_json(&index_path).unwrap_or_default();
+
+        Self {
+            index: Mutex::new(index),
+            instances_dir: app_data_dir.join("instances"),
+            archive_dir: app_data_dir.join("archive"),
+            index_path,
+            locks: InstanceLocks::new(),
+            on_change: Mutex::new(None),
+        }
+    }
+
+    /// Call `f` whenever instances are created, deleted, reordered or saved.
+    /// It may run while the index is locked, so it must not lock it itself.
+    pub fn set_on_change(&self, f: impl Fn() + Send + Sync + 'static) {
+        *self.on_change.lock().unwrap() = Some(Box::new(f));
+    }
+
+    fn notify_change(&self) {
+        if let Some(f) = self.on_change.lock().unwrap().as_ref() {
+            f();
+        }
+    }
+
+    /// Game directory of an instance.
+    pub fn instance_dir(&self, id: &str) -> PathBuf {
+        self.instances_dir.join(id)
+    }
+
+    /// Lock the index after reloading it from disk, so changes made by
+    /// another process (CLI commands run alongside the GUI) are not
+    /// overwritten by the next save.
+    pub fn lock_index(&self) -> MutexGuard<'_, InstanceIndex> {
+        let mut index = self.index.lock().unwrap();
+        if let Ok(current) = file_utils::read_json(&self.index_path) {
+            *index = current;
+        }
+        index
+    }
+
+    fn save_index(&self, index: &InstanceIndex) -> Result<(), String> {
+        file_utils::write_json_atomic(&self.index_path, index).map_err(|e| e.to_string())?;
+        self.notify_change();
+        Ok(())
+    }
+
+    /// Load an instance by id.
+    pub fn get(&self, id: &str) -> Result<Instance, String> {
+        validate_id(id)?;
+        let path = self.instance_dir(id).join("instance.json");
+        file_utils::read_json(&path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => format!("Instance {} not found", id),
+            _ => format!("Corrupt instance {}: {}", id, e),
+        })
+    }
+
+    /// All instances in display order. Instances that fail to load are skipped.
+    pub fn list(&self) -> Vec<Instance> {
+        let ids = self.lock_index().instances.clone();
+        ids.iter().filter_map(|id| self.get(id).ok()).collect()
+    }
+
+    /// Persist an instance's metadata.
+    pub fn save(&self, instance: &Instance) -> Result<(), String> {
+        validate_id(&instance.id)?;
+        let path = self.instance_dir(&instance.id).join("instance.json");
+        file_utils::write_json_atomic(&path, instance).map_err(|e| e.to_string())?;
+        self.notify_change();
+        Ok(())
+    }
+
+    /// Create a new instance and add it to the index.
+    pub fn create(&self, name: &str, version_id: &str) -> Result<Instance, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Instance name cannot be empty".to_string());
+        }
+
+        let instance = Instance {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            version_id: version_id.to_string(),
+            created_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            ..Default::default()
+        };
+        self.save(&instance)?;
+
+        let mut index = self.lock_index();
+        index.instances.push(instance.id.clone());
+        if index.active_instance_id.is_none() {
+            index.active_instance_id = Some(instance.id.clone());
+        }
+        self.save_index(&index)?;
+
+        Ok(instance)
+    }
+
+    /// Drop `id` from the index once its files are gone.
+    pub(crate) fn remove_from_index(&self, id: &str) -> Result<(), String> {
+        let mut index = self.lock_index();
+        index.instances.retain(|i| i != id);
+        if index.active_instance_id.as_deref() == Some(id) {
+            index.active_instance_id = index.instances.first().cloned();
+        }
+        self.save_index(&index)
+    }
+
+    /// Add an instance whose files were put back, e.g. from the trash, at
+    /// the end of the list.
+    pub(crate) fn restore_to_index(&self, id: &str) -> Result<(), String> {
+        let mut index = self.lock_index();
+        if !index.instances.iter().any(|i| i == id) {
+            index.instances.push(id.to_string());
+        }
+        if index.active_instance_id.is_none() {
+            index.active_instance_id = Some(id.to_string());
+        }
+        self.save_index(&index)
+    }
+
+    /// Reorder instances; `ids` must contain every instance exactly once.
+    pub fn reorder(&self, ids: &[String]) -> Result<(), String> {
+        let mut index = self.lock_index();
+        let mut current = index.instances.clone();
+        let mut requested = ids.to_vec();
+        current.sort();
+        requested.sort();
+        if current != requested {
+            return Err("Instance order must list every instance exactly once".to_string());
+        }
+        index.instances = ids.to_vec();
+        self.save_index(&index)
+    }
+
+    /// Move an instance into a group (created if new), or out of any group.
+    pub fn set_group(&self, id: &str, group: Option<&str>) -> Result<Instance, String> {
+        let mut instance = self.get(id)?;
+        let group = group.map(str::trim).filter(|g| !g.is_empty());
+        instance.group = group.map(|g| g.to_string());
+        self.save(&instance)?;
+
+        if let Some(group) = group {
+            let mut index = self.lock_index();
+            if !index.groups.iter().any(|g| g == group) {
+                index.groups.push(group.to_string());
+                self.save_index(&index)?;
+            }
+        }
+        Ok(instance)
+    }
+
+    /// Replace an instance's tags (trimmed, without case-insensitive duplicates).
+    pub fn set_tags(&self, id: &str, tags: &[String]) -> Result<Instance, String> {
+        let mut instance = self.get(id)?;
+        instance.tags.clear();
+        for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+            if !instance.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                instance.tags.push(tag.to_string());
+            }
+        }
+        self.save(&instance)?;
+        Ok(instance)
+    }
+
+    /// Set the display order of groups. Groups not listed are dropped from the
+    /// order but instances keep their group name.
+    pub fn set_group_order(&self, groups: &[String]) -> Result<(), String> {
+        let mut index = self.lock_index();
+        index.groups = groups.to_vec();
+        self.save_index(&index)
+    }
+
+    /// Switch `instance` to `version_id` without saving it. The version it
+    /// leaves becomes `previous_version_id`, and its JSON is copied into the
+    /// instance so a rollback works even after that version is deleted.
+    pub fn change_version(
+        &self,
+        instance: &mut Instance,
+        app_data_dir: &Path,
+        version_id: &str,
+    ) -> Result<(), String> {
+        if instance.version_id == version_id {
+            return Ok(());
+        }
+        let old = std::mem::replace(&mut instance.version_id, version_id.to_string());
+        let instance_dir = self.instance_dir(&instance.id);
+        let versions_dir = LaunchPaths::for_instance(app_data_dir, &instance_dir, instance)
+            .data_dir
+            .join("versions");
+        let json = versions_dir.join(&old).join(format!("{}.json", old));
+        let backup_dir = instance_dir.join(PREVIOUS_VERSION_DIR);
+        if backup_dir.exists() {
+            fs::remove_dir_all(&backup_dir).map_err(|e| e.to_string())?;
+        }
+        if json.is_file() {
+            fs::create_dir_all(&backup_dir).map_err(|e| e.to_string())?;
+            fs::copy(&json, backup_dir.join(format!("{}.json", old)))
+                .map_err(|e| format!("Failed to keep version {}: {}", old, e))?;
+        }
+        instance.previous_version_id = Some(old);
+        Ok(())
+    }
+
+    /// Return an instance to the mod loader version it used before its last
+    /// version change, restoring that version's JSON if it was deleted. The
+    /// current version becomes the previous one, so a rollback can be undone.
+    pub fn roll_back_version(
+        &self,
+        id: &str,
+        app_data_dir: &Path,
+    ) -> Result<Instance, LauncherError> {
+        let _lock = self.locks.write(id, "rolling back its loader")?;
+        let mut instance = self.get(id)?;
+        let previous = instance.previous_version_id.clone().ok_or_else(|| {
+            LauncherError::Invalid(format!("{} has no previous version", instance.name))
+        })?;
+        let instance_dir = self.instance_dir(id);
+        let version_dir = LaunchPaths::for_instance(app_data_dir, &instance_dir, &instance)
+            .data_dir
+            .join("versions")
+            .join(&previous);
+        let json = version_dir.join(format!("{}.json", previous));
+        if !json.is_file() {
+            let backup = instance_dir
+                .join(PREVIOUS_VERSION_DIR)
+                .join(format!("{}.json", previous));
+            if !backup.is_file() {
+                return Err(format!("Version {} is no longer available", previous).into());
+            }
+            fs::create_dir_all(&version_dir).map_err(|e| e.to_string())?;
+            fs::copy(&backup, &json).map_err(|e| format!("Failed to restore {}: {}", previous, e))?;
+        }
+
+        let version: GameVersion = file_utils::read_json(&json)
+            .map_err(|e| format!("Corrupt version {}: {}", previous, e))?;
+        if LoaderType::detect(&version) == LoaderType::Vanilla {
+            return Err(LauncherError::Invalid(format!(
+                "{} is not a mod loader version",
+                previous
+            )));
+        }
+
+        crate::core::instance_backup::snapshot(&instance_dir, BackupReason::LoaderChange)?;
+        self.change_version(&mut instance, app_data_dir, &previous)?;
+        self.save(&instance)?;
+        Ok(instance)
+    }
+
+    /// Set the active instance.
+    pub fn set_active(&self, id: &str) -> Result<(), String> {
+        let mut index = self.lock_index();
+        if !index.instances.iter().any(|i| i == id) {
+            return Err(format!("Instance {} not found", id));
+        }
+        index.active_instance_id = Some(id.to_string());
+        self.save_index(&index)
+    }
+}
+
+/// Directories used to launch a game.
+#[derive(Debug, Clone)]
+pub struct LaunchPaths {
+    /// Root holding `versions/`, `libraries/` and `assets/`
+    pub data_dir: PathBuf,
+    /// Working directory of the game (`${game_directory}`)
+    pub game_dir: PathBuf,
+}
+
+impl LaunchPaths {
+    /// Launch without an instance: everything lives in the launcher data directory.
+    pub fn shared(app_data_dir: &Path) -> Self {
+        Self {
+            data_dir: app_data_dir.to_path_buf(),
+            game_dir: app_data_dir.to_path_buf(),
+        }
+    }
+
+    /// Paths for launching an instance, honouring its isolation flag.
+    pub fn for_instance(app_data_dir: &Path, instance_dir: &Path, instance: &Instance) -> Self {
+        Self {
+            data_dir: if instance.isolated {
+                instance_dir.to_path_buf()
+            } else {
+                app_data_dir.to_path_buf()
+            },
+            game_dir: instance_dir.to_path_buf(),
+        }
+    }
+}
+
+/// Copy a version JSON and its parents from the shared data directory into an
+/// isolated instance, unless the instance already has them.
+///
+/// Libraries and assets are not copied; the launch pipeline downloads missing
+/// files into the instance. Versions not installed in the shared directory are
+/// left to be fetched from Mojang.
+pub fn seed_isolated_version(
+    shared_dir: &Path,
+    instance_dir: &Path,
+    version_id: &str,
+) -> Result<(), String> {
+    let mut next = Some(version_id.to_string());
+    while let Some(id) = next.take() {
+        let rel = Path::new("versions").join(&id).join(format!("{}.json", id));
+        let dest = instance_dir.join(&rel);
+        if !dest.exists() {
+            let src = shared_dir.join(&rel);
+            if !src.exists() {
+                break;
+            }
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            fs::copy(&src, &dest).map_err(|e| format!("Failed to copy {}: {}", id, e))?;
+        }
+
+        let content = fs::read_to_string(&dest).map_err(|e| e.to_string())?;
+        let json: serde_json::Value = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        next = json
+            .get("inheritsFrom")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+    }
+    Ok(())
+}
+
+/// Reject ids that could escape the instances directory.
+fn validate_id(id: &str) -> Result<(), String> {
+    if id.is_empty() || id.contains(['/', '\\']) || id == "." || id == ".." {
+        return Err(format!("Invalid instance id: {}", id));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_id() {
+        assert!(validate_id("5f1c3a2e-0000-4000-8000-000000000000").is_ok());
+        assert!(validate_id("").is_err());
+        assert!(validate_id("..").is_err());
+        assert!(validate_id("a/b").is_err());
+    }
+
+    #[test]
+    fn test_instance_defaults_for_missing_fields() {
+        let instance: Instance =
+            serde_json::from_str(r#"{"id": "a", "name": "A", "version_id": "1.20.4"}"#).unwrap();
+        assert_eq!(instance.icon, None);
+        assert_eq!(instance.last_played, None);
+        assert!(!instance.isolated);
+        assert!(instance.tags.is_empty());
+        assert!(instance.modpack.is_none());
+    }
+
+    #[test]
+    fn test_modpack_source_roundtrip() {
+        let instance: Instance = serde_json::from_str(
+            r#"{"id": "a", "name": "A", "version_id": "1.20.1",
+                "modpack": {"platform": "modrinth", "project_id": "1KVo5zza", "version_id": "x9yz"}}"#,
+        )
+        .unwrap();
+        let modpack = instance.modpack.unwrap();
+        assert_eq!(modpack.platform, ModpackPlatform::Modrinth);
+        assert_eq!(modpack.version_name, None);
+    }
+
+    #[test]
+    fn test_instance_filter() {
+        let instance = Instance {
+            name: "Create Above and Beyond".to_string(),
+            version_id: "1.16.5-forge-36.2.39".to_string(),
+            group: Some("Modpacks".to_string()),
+            tags: vec!["Tech".to_string(), "multiplayer".to_string()],
+            ..Default::default()
+        };
+
+        assert!(InstanceFilter::default().matches(&instance));
+        assert!(InstanceFilter {
+            group: Some("Modpacks".to_string()),
+            tags: vec!["tech".to_string()],
+            query: Some("create".to_string()),
+        }
+        .matches(&instance));
+        assert!(!InstanceFilter {
+            group: Some("Testing".to_string()),
+            ..Default::default()
+        }
+        .matches(&instance));
+        assert!(!InstanceFilter {
+            tags: vec!["tech".to_string(), "magic".to_string()],
+            ..Default::default()
+        }
+        .matches(&instance));
+    }
+
+    #[test]
+    fn test_damaged_instance_falls_back_to_backup() {
+        let dir =
+            std::env::temp_dir().join(format!("dropout-instance-test-{}", uuid::Uuid::new_v4()));
+        let manager = InstanceManager::new(&dir);
+        let mut instance = manager.create("Survival", "1.20.4").unwrap();
+        instance.name = "Creative".to_string();
+        manager.save(&instance).unwrap();
+
+        let path = manager.instance_dir(&instance.id).join("instance.json");
+        fs::write(&path, "{\"id\": ").unwrap();
+        let loaded = manager.get(&instance.id);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.unwrap().name, "Survival");
+    }
+
+    #[test]
+    fn test_index_keeps_changes_of_other_processes() {
+        let dir =
+            std::env::temp_dir().join(format!("dropout-instance-test-{}", uuid::Uuid::new_v4()));
+        let gui = InstanceManager::new(&dir);
+        let first = gui.create("Survival", "1.20.4").unwrap();
+        // Loaded like the CLI does, while the GUI keeps running
+        let cli = InstanceManager::new(&dir);
+        let imported = cli.create("Imported", "1.20.1").unwrap();
+        gui.set_active(&imported.id).unwrap();
+        let second = gui.create("Creative", "1.20.4").unwrap();
+        let ids: Vec<String> = InstanceManager::new(&dir)
+            .list()
+            .into_iter()
+            .map(|instance| instance.id)
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(ids, [first.id, imported.id, second.id]);
+    }
+
+    #[test]
+    fn test_roll_back_restores_deleted_loader_version() {
+        let dir =
+            std::env::temp_dir().join(format!("dropout-instance-test-{}", uuid::Uuid::new_v4()));
+        let manager = InstanceManager::new(&dir);
+        let old = "fabric-loader-0.15.6-1.20.4";
+        let old_dir = dir.join("versions").join(old);
+        fs::create_dir_all(&old_dir).unwrap();
+        fs::write(
+            old_dir.join(format!("{}.json", old)),
+            format!(
+                r#"{{"id": "{}", "inheritsFrom": "1.20.4", "mainClass": "Knot", "libraries": []}}"#,
+                old
+            ),
+        )
+        .unwrap();
+        let mut instance = manager.create("Modded", old).unwrap();
+        manager
+            .change_version(&mut instance, &dir, "fabric-loader-0.16.0-1.20.4")
+            .unwrap();
+        manager.save(&instance).unwrap();
+        fs::remove_dir_all(&old_dir).unwrap();
+
+        let rolled_back = manager.roll_back_version(&instance.id, &dir);
+        let restored = old_dir.join(format!("{}.json", old)).is_file();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let rolled_back = rolled_back.unwrap();
+        assert_eq!(rolled_back.version_id, old);
+        assert_eq!(
+            rolled_back.previous_version_id.as_deref(),
+            Some("fabric-loader-0.16.0-1.20.4")
+        );
+        assert!(restored);
+    }
+
+    #[test]
+    fn test_launch_paths() {
+        let app_dir = Path::new("/data");
+        let instance_dir = Path::new("/data/instances/a");
+        let mut instance = Instance::default();
+
+        let shared = LaunchPaths::for_instance(app_dir, instance_dir, &instance);
+        assert_eq!(shared.data_dir, app_dir);
+        assert_eq!(shared.game_dir, instance_dir);
+
+        instance.isolated = true;
+        let isolated = LaunchPaths::for_instance(app_dir, instance_dir, &instance);
+        assert_eq!(isolated.data_dir, instance_dir);
+    }
+}
@@ -0,0 +1,140 @@
+//! Archiving instances that are not played any more.
+//!
+//! `archive` packs an instance's directory into `archive/<id>.zip` and
+//! deletes everything in it except `instance.json`, which is marked as
+//! archived so the instance keeps its place, group and tags in the list.
+//! `unarchive` extracts the zip again; metadata edited while archived, like
+//! the name or group, wins over the copy in the zip. Archived instances
+//! cannot be launched.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::core::error::LauncherError;
+use crate::core::instance::{Instance, InstanceManager};
+
+/// Where and when an archived instance's files were packed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveInfo {
+    pub archived_at: u64,
+    /// Bytes the instance's directory took before archiving
+    pub original_size: u64,
+    pub archive_size: u64,
+}
+
+/// Zip holding the files of the archived instance `id`.
+pub fn archive_path(manager: &InstanceManager, id: &str) -> PathBuf {
+    manager.archive_dir.join(format!("{}.zip", id))
+}
+
+/// Pack instance `id` into its zip and free its directory.
+pub fn archive(manager: &InstanceManager, id: &str) -> Result<Instance, LauncherError> {
+    let _lock = manager.locks.write(id, "archiving")?;
+    let mut instance = manager.get(id)?;
+    if instance.archived.is_some() {
+        return Err(LauncherError::Invalid(format!(
+            "{} is already archived",
+            instance.name
+        )));
+    }
+
+    let dir = manager.instance_dir(id);
+    let zip_path = archive_path(manager, id);
+    // A crash while zipping must not leave a zip that looks complete
+    let partial = zip_path.with_extension("zip.partial");
+    crate::utils::zip::create_zip(&dir, &partial, &[])?;
+    fs::rename(&partial, &zip_path).map_err(|e| format!("Failed to save archive: {}", e))?;
+
+    instance.archived = Some(ArchiveInfo {
+        archived_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        original_size: crate::utils::file_utils::dir_size(&dir),
+        archive_size: fs::metadata(&zip_path).map(|m| m.len()).unwrap_or(0),
+    });
+    manager.save(&instance)?;
+
+    let entries = fs::read_dir(&dir).map_err(|e| e.to_string())?;
+    for entry in entries.flatten() {
+        if entry.file_name() == "instance.json" {
+            continue;
+        }
+        let path = entry.path();
+        let removed = if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        removed.map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+    }
+    Ok(instance)
+}
+
+/// Restore the files of archived instance `id` and delete its zip.
+pub fn unarchive(manager: &InstanceManager, id: &str) -> Result<Instance, LauncherError> {
+    let _lock = manager.locks.write(id, "unarchiving")?;
+    let mut instance = manager.get(id)?;
+    let Some(info) = instance.archived.take() else {
+        return Err(LauncherError::Invalid(format!(
+            "{} is not archived",
+            instance.name
+        )));
+    };
+
+    let zip_path = archive_path(manager, id);
+    if !zip_path.is_file() {
+        return Err(LauncherError::Invalid(format!(
+            "The archive of {} is missing: {}",
+            instance.name,
+            zip_path.display()
+        )));
+    }
+    crate::utils::zip::extract_zip(
+        &zip_path,
+        &manager.instance_dir(id),
+        crate::utils::zip::ExtractOptions {
+            max_total_size: info
+                .original_size
+                .max(crate::utils::zip::DEFAULT_MAX_EXTRACTED_SIZE),
+            ..Default::default()
+        },
+    )?;
+
+    manager.save(&instance)?;
+    fs::remove_file(&zip_path).map_err(|e| format!("Failed to remove archive: {}", e))?;
+    Ok(instance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_round_trip() {
+        let root = std::env::temp_dir().join(format!("dropout-archive-{}", uuid::Uuid::new_v4()));
+        let manager = InstanceManager::new(&root);
+        let instance = manager.create("Old pack", "1.20.1").unwrap();
+        let dir = manager.instance_dir(&instance.id);
+        fs::create_dir_all(dir.join("saves/World")).unwrap();
+        fs::write(dir.join("saves/World/level.dat"), b"level").unwrap();
+
+        let archived = archive(&manager, &instance.id).unwrap();
+        assert!(archived.archived.is_some());
+        assert!(archive_path(&manager, &instance.id).is_file());
+        assert!(!dir.join("saves").exists());
+        assert_eq!(manager.list().len(), 1);
+
+        manager.set_group(&instance.id, Some("Retired")).unwrap();
+        let restored = unarchive(&manager, &instance.id).unwrap();
+        assert!(restored.archived.is_none());
+        assert_eq!(restored.group.as_deref(), Some("Retired"));
+        assert_eq!(
+            fs::read(dir.join("saves/World/level.dat")).unwrap(),
+            b"level"
+        );
+        assert!(!archive_path(&manager, &instance.id).exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
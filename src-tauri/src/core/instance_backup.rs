@@ -0,0 +1,224 @@
+//! Automatic instance backups.
+//!
+//! Before a loader version change the instance's `config/` and `mods/` are
+//! zipped into `backups/<reason>-<timestamp>.zip`; modpack updates write a
+//! fuller backup under the same naming. Restoring a backup replaces every
+//! top-level folder and file it contains, so mods added since are removed.
+//! The backup is extracted next to them first and swapped in afterwards, so
+//! a failed restore leaves the instance as it was. Only the newest
+//! `KEEP_SNAPSHOTS` snapshots per reason are kept.
+
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const BACKUP_DIR: &str = "backups";
+/// Folders saved by `snapshot`
+const SNAPSHOT_DIRS: [&str; 2] = ["config", "mods"];
+const KEEP_SNAPSHOTS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupReason {
+    PackUpdate,
+    LoaderChange,
+}
+
+impl BackupReason {
+    const ALL: [BackupReason; 2] = [Self::PackUpdate, Self::LoaderChange];
+
+    fn prefix(self) -> &'static str {
+        match self {
+            Self::PackUpdate => "pack-update",
+            Self::LoaderChange => "loader-change",
+        }
+    }
+}
+
+/// A backup zip in an instance's `backups/` folder.
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceBackup {
+    pub file_name: String,
+    pub reason: BackupReason,
+    pub created_at: u64,
+    pub size: u64,
+}
+
+/// Path for a new backup of the instance in `instance_dir`.
+pub fn new_backup_path(instance_dir: &Path, reason: BackupReason) -> PathBuf {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    instance_dir
+        .join(BACKUP_DIR)
+        .join(format!("{}-{}.zip", reason.prefix(), timestamp))
+}
+
+/// Zip the instance's `config/` and `mods/`; `None` if it has neither.
+pub fn snapshot(instance_dir: &Path, reason: BackupReason) -> Result<Option<PathBuf>, String> {
+    if !SNAPSHOT_DIRS
+        .iter()
+        .any(|dir| instance_dir.join(dir).is_dir())
+    {
+        return Ok(None);
+    }
+    let exclude: Vec<String> = fs::read_dir(instance_dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| !SNAPSHOT_DIRS.contains(&name.as_str()))
+        .collect();
+    let exclude: Vec<&str> = exclude.iter().map(String::as_str).collect();
+    let path = new_backup_path(instance_dir, reason);
+    crate::utils::zip::create_zip(instance_dir, &path, &exclude)?;
+
+    for old in list(instance_dir)
+        .into_iter()
+        .filter(|backup| backup.reason == reason)
+        .skip(KEEP_SNAPSHOTS)
+    {
+        let _ = fs::remove_file(instance_dir.join(BACKUP_DIR).join(old.file_name));
+    }
+    Ok(Some(path))
+}
+
+/// Backups of the instance in `instance_dir`, newest first.
+pub fn list(instance_dir: &Path) -> Vec<InstanceBackup> {
+    let Ok(entries) = fs::read_dir(instance_dir.join(BACKUP_DIR)) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<InstanceBackup> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_str()?.to_string();
+            let (reason, created_at) = parse_name(&file_name)?;
+            Some(InstanceBackup {
+                file_name,
+                reason,
+                created_at,
+                size: entry.metadata().ok()?.len(),
+            })
+        })
+        .collect();
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+    backups
+}
+
+/// Restore backup `file_name` of the instance in `instance_dir`.
+pub fn restore(instance_dir: &Path, file_name: &str) -> Result<InstanceBackup, String> {
+    let backup = list(instance_dir)
+        .into_iter()
+        .find(|backup| backup.file_name == file_name)
+        .ok_or_else(|| format!("Backup {} not found", file_name))?;
+    let zip_path = instance_dir.join(BACKUP_DIR).join(file_name);
+
+    let staging = instance_dir.join(format!(".restore-{}", uuid::Uuid::new_v4()));
+    let result =
+        crate::utils::zip::extract_zip(&zip_path, &staging.join("new"), Default::default())
+            .map_err(|e| format!("Corrupt backup: {}", e))
+            .and_then(|_| {
+                swap_in(instance_dir, &staging)
+                    .map_err(|e| format!("Failed to restore {}: {}", file_name, e))
+            });
+    let _ = fs::remove_dir_all(&staging);
+    result.map(|_| backup)
+}
+
+/// Move the entries of `staging/new` into `instance_dir`, parking the ones
+/// they replace in `staging/old`. Moves are undone if one fails.
+fn swap_in(instance_dir: &Path, staging: &Path) -> io::Result<()> {
+    let old_dir = staging.join("old");
+    fs::create_dir_all(&old_dir)?;
+    let mut moved: Vec<(PathBuf, Option<PathBuf>)> = Vec::new();
+    let result = (|| {
+        for entry in fs::read_dir(staging.join("new"))? {
+            let entry = entry?;
+            let name = entry.file_name();
+            if name == BACKUP_DIR {
+                continue;
+            }
+            let target = instance_dir.join(&name);
+            let old = fs::symlink_metadata(&target)
+                .is_ok()
+                .then(|| old_dir.join(&name));
+            if let Some(old) = &old {
+                fs::rename(&target, old)?;
+            }
+            moved.push((target.clone(), old));
+            fs::rename(entry.path(), &target)?;
+        }
+        Ok(())
+    })();
+    if result.is_err() {
+        for (target, old) in moved.into_iter().rev() {
+            let _ = remove_path(&target);
+            if let Some(old) = old {
+                let _ = fs::rename(old, &target);
+            }
+        }
+    }
+    result
+}
+
+fn remove_path(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+fn parse_name(file_name: &str) -> Option<(BackupReason, u64)> {
+    let stem = file_name.strip_suffix(".zip")?;
+    let (prefix, timestamp) = stem.rsplit_once('-')?;
+    let reason = BackupReason::ALL
+        .into_iter()
+        .find(|reason| reason.prefix() == prefix)?;
+    Some((reason, timestamp.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let dir = std::env::temp_dir().join(format!("dropout-backup-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("mods")).unwrap();
+        fs::create_dir_all(dir.join("saves")).unwrap();
+        fs::write(dir.join("mods/sodium.jar"), b"old").unwrap();
+        fs::write(dir.join("options.txt"), b"fov:1.0").unwrap();
+
+        let path = snapshot(&dir, BackupReason::LoaderChange).unwrap().unwrap();
+        let file_name = path.file_name().unwrap().to_str().unwrap();
+        let backups = list(&dir);
+        assert_eq!(backups.len(), 1);
+        assert_eq!(backups[0].reason, BackupReason::LoaderChange);
+
+        fs::write(dir.join("mods/sodium.jar"), b"new").unwrap();
+        fs::write(dir.join("mods/added.jar"), b"added").unwrap();
+        restore(&dir, file_name).unwrap();
+        assert_eq!(fs::read(dir.join("mods/sodium.jar")).unwrap(), b"old");
+        assert!(!dir.join("mods/added.jar").exists());
+        assert!(dir.join("options.txt").exists());
+        // Nothing is left of the staging folder
+        assert!(fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .all(|e| !e.file_name().to_string_lossy().starts_with(".restore-")));
+
+        // A damaged backup leaves the instance untouched
+        fs::write(dir.join(BACKUP_DIR).join("pack-update-1.zip"), b"not a zip").unwrap();
+        assert!(restore(&dir, "pack-update-1.zip").is_err());
+        assert_eq!(fs::read(dir.join("mods/sodium.jar")).unwrap(), b"old");
+
+        assert_eq!(
+            parse_name("pack-update-1700000000.zip"),
+            Some((BackupReason::PackUpdate, 1700000000))
+        );
+        assert_eq!(parse_name("notes.zip"), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
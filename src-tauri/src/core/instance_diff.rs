@@ -0,0 +1,311 @@
+//! Differences between two instances.
+//!
+//! `diff` compares the versions and mod loaders the instances launch, their
+//! mods by the id and version in each jar's metadata, and the files under
+//! `config/`. For text configs the lines only one side has are listed, which
+//! is usually enough to spot the changed option.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use crate::core::game_version::GameVersion;
+use crate::core::instance::{Instance, LaunchPaths};
+use crate::core::manifest::LoaderType;
+
+/// Lines listed per side of a differing config file
+const MAX_LINES: usize = 100;
+/// Larger config files are only compared as a whole
+const MAX_TEXT_SIZE: u64 = 1024 * 1024;
+
+/// A setting with a different value in each instance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Mismatch {
+    pub a: String,
+    pub b: String,
+}
+
+/// A mod jar in one instance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModEntry {
+    pub file_name: String,
+    pub version: Option<String>,
+}
+
+/// A mod missing from one instance or installed in different versions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ModDifference {
+    /// Mod id, or the file name for jars without metadata
+    pub id: String,
+    pub a: Option<ModEntry>,
+    pub b: Option<ModEntry>,
+}
+
+/// A file under `config/` that is missing from one instance or differs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileDifference {
+    /// Path relative to `config/`
+    pub path: String,
+    pub in_a: bool,
+    pub in_b: bool,
+    /// Lines only the first instance's file has
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceDiff {
+    pub version: Option<Mismatch>,
+    pub loader: Option<Mismatch>,
+    pub mods: Vec<ModDifference>,
+    pub config_files: Vec<FileDifference>,
+}
+
+/// Compare instance `a` in `a_dir` with instance `b` in `b_dir`.
+pub fn diff(
+    app_data_dir: &Path,
+    (a, a_dir): (&Instance, &Path),
+    (b, b_dir): (&Instance, &Path),
+) -> InstanceDiff {
+    let mismatch = |a: String, b: String| (a != b).then_some(Mismatch { a, b });
+    let loader = |instance: &Instance, dir: &Path| {
+        loader_name(
+            &LaunchPaths::for_instance(app_data_dir, dir, instance).data_dir,
+            instance,
+        )
+    };
+    InstanceDiff {
+        version: mismatch(a.version_id.clone(), b.version_id.clone()),
+        loader: mismatch(loader(a, a_dir), loader(b, b_dir)),
+        mods: diff_mods(
+            &read_mods(&a_dir.join("mods")),
+            &read_mods(&b_dir.join("mods")),
+        ),
+        config_files: diff_configs(&a_dir.join("config"), &b_dir.join("config")),
+    }
+}
+
+fn loader_name(data_dir: &Path, instance: &Instance) -> String {
+    let id = &instance.version_id;
+    let json = data_dir
+        .join("versions")
+        .join(id)
+        .join(format!("{}.json", id));
+    match crate::utils::file_utils::read_json::<GameVersion>(&json) {
+        Ok(version) => serde_json::to_value(LoaderType::detect(&version))
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default(),
+        Err(_) => "unknown".to_string(),
+    }
+}
+
+/// Mods in `mods_dir` by id.
+fn read_mods(mods_dir: &Path) -> BTreeMap<String, ModEntry> {
+    let Ok(entries) = fs::read_dir(mods_dir) else {
+        return BTreeMap::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_str()?.to_string();
+            if !file_name.to_ascii_lowercase().ends_with(".jar") {
+                return None;
+            }
+            let (id, version) =
+                mod_metadata(&entry.path()).unwrap_or_else(|| (file_name.clone(), None));
+            Some((id, ModEntry { file_name, version }))
+        })
+        .collect()
+}
+
+fn diff_mods(a: &BTreeMap<String, ModEntry>, b: &BTreeMap<String, ModEntry>) -> Vec<ModDifference> {
+    let ids: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    ids.into_iter()
+        .filter_map(|id| {
+            let (a, b) = (a.get(id), b.get(id));
+            let same = match (a, b) {
+                (Some(a), Some(b)) if a.version.is_some() => a.version == b.version,
+                (Some(a), Some(b)) => a == b,
+                _ => false,
+            };
+            (!same).then(|| ModDifference {
+                id: id.clone(),
+                a: a.cloned(),
+                b: b.cloned(),
+            })
+        })
+        .collect()
+}
+
+/// Id and version from a mod jar's Fabric, Quilt, Forge or NeoForge metadata.
+fn mod_metadata(jar: &Path) -> Option<(String, Option<String>)> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(jar).ok()?).ok()?;
+    let mut read = |name: &str| -> Option<String> {
+        let mut content = String::new();
+        archive
+            .by_name(name)
+            .ok()?
+            .read_to_string(&mut content)
+            .ok()?;
+        Some(content)
+    };
+    let text = |value: &serde_json::Value| value.as_str().map(str::to_string);
+
+    if let Some(json) = read("fabric.mod.json") {
+        let json: serde_json::Value = serde_json::from_str(&json).ok()?;
+        return Some((text(&json["id"])?, text(&json["version"])));
+    }
+    if let Some(json) = read("quilt.mod.json") {
+        let json: serde_json::Value = serde_json::from_str(&json).ok()?;
+        let loader = &json["quilt_loader"];
+        return Some((text(&loader["id"])?, text(&loader["version"])));
+    }
+    let toml = read("META-INF/neoforge.mods.toml").or_else(|| read("META-INF/mods.toml"));
+    if let Some(toml) = toml {
+        let toml: toml::Value = toml::from_str(&toml).ok()?;
+        let first = toml.get("mods")?.as_array()?.first()?;
+        let id = first.get("modId")?.as_str()?.to_string();
+        let version = match first.get("version").and_then(toml::Value::as_str) {
+            // Filled in from the manifest when the mod is loaded
+            Some("${file.jarVersion}") | None => read("META-INF/MANIFEST.MF").and_then(|m| {
+                m.lines()
+                    .find_map(|line| line.strip_prefix("Implementation-Version:"))
+                    .map(|version| version.trim().to_string())
+            }),
+            Some(version) => Some(version.to_string()),
+        };
+        return Some((id, version));
+    }
+    let info: serde_json::Value = serde_json::from_str(&read("mcmod.info")?).ok()?;
+    let first = info.get(0).or_else(|| info["modList"].get(0))?;
+    Some((text(&first["modid"])?, text(&first["version"])))
+}
+
+fn diff_configs(a: &Path, b: &Path) -> Vec<FileDifference> {
+    let (a_files, b_files) = (list_files(a), list_files(b));
+    let paths: BTreeSet<&String> = a_files.iter().chain(&b_files).collect();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let (in_a, in_b) = (a_files.contains(path), b_files.contains(path));
+            let mut difference = FileDifference {
+                path: path.clone(),
+                in_a,
+                in_b,
+                only_in_a: Vec::new(),
+                only_in_b: Vec::new(),
+            };
+            if in_a && in_b {
+                let (a_data, b_data) = (fs::read(a.join(path)).ok()?, fs::read(b.join(path)).ok()?);
+                if a_data == b_data {
+                    return None;
+                }
+                if let (Ok(a_text), Ok(b_text)) =
+                    (std::str::from_utf8(&a_data), std::str::from_utf8(&b_data))
+                {
+                    if a_data.len() as u64 <= MAX_TEXT_SIZE && b_data.len() as u64 <= MAX_TEXT_SIZE
+                    {
+                        (difference.only_in_a, difference.only_in_b) =
+                            line_differences(a_text, b_text);
+                    }
+                }
+            }
+            Some(difference)
+        })
+        .collect()
+}
+
+/// Non-blank lines only in `a` and only in `b`, in file order.
+fn line_differences(a: &str, b: &str) -> (Vec<String>, Vec<String>) {
+    let lines = |text: &str| -> Vec<String> {
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let (a, b) = (lines(a), lines(b));
+    let only = |from: &[String], other: &[String]| -> Vec<String> {
+        let other: BTreeSet<&String> = other.iter().collect();
+        from.iter()
+            .filter(|line| !other.contains(line))
+            .take(MAX_LINES)
+            .cloned()
+            .collect()
+    };
+    (only(&a, &b), only(&b, &a))
+}
+
+/// Files below `root`, relative and with forward slashes.
+fn list_files(root: &Path) -> BTreeSet<String> {
+    let mut files = BTreeSet::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => dirs.push(path),
+                Ok(file_type) if file_type.is_file() => {
+                    if let Ok(relative) = path.strip_prefix(root) {
+                        files.insert(relative.to_string_lossy().replace('\\', "/"));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_mod(mods_dir: &Path, file_name: &str, id: &str, version: &str) {
+        let source = mods_dir.join(format!("{}-src", file_name));
+        fs::create_dir_all(&source).unwrap();
+        let metadata = format!(r#"{{"id": "{}", "version": "{}"}}"#, id, version);
+        fs::write(source.join("fabric.mod.json"), metadata).unwrap();
+        crate::utils::zip::create_zip(&source, &mods_dir.join(file_name), &[]).unwrap();
+        fs::remove_dir_all(&source).unwrap();
+    }
+
+    #[test]
+    fn test_diff_mods_and_configs() {
+        let root = std::env::temp_dir().join(format!("dropout-diff-{}", uuid::Uuid::new_v4()));
+        let (a, b) = (root.join("a"), root.join("b"));
+        for dir in [&a, &b] {
+            fs::create_dir_all(dir.join("mods")).unwrap();
+            fs::create_dir_all(dir.join("config")).unwrap();
+        }
+        write_mod(&a.join("mods"), "sodium.jar", "sodium", "0.5.8");
+        write_mod(&b.join("mods"), "sodium-new.jar", "sodium", "0.5.11");
+        write_mod(&a.join("mods"), "lithium.jar", "lithium", "0.12.1");
+        write_mod(&b.join("mods"), "lithium-copy.jar", "lithium", "0.12.1");
+        write_mod(&b.join("mods"), "iris.jar", "iris", "1.7.0");
+        fs::write(a.join("config/sodium.properties"), "fog=true\nchunks=12\n").unwrap();
+        fs::write(b.join("config/sodium.properties"), "fog=true\nchunks=16\n").unwrap();
+        fs::write(a.join("config/same.toml"), "x = 1").unwrap();
+        fs::write(b.join("config/same.toml"), "x = 1").unwrap();
+
+        let mods = diff_mods(&read_mods(&a.join("mods")), &read_mods(&b.join("mods")));
+        let ids: Vec<&str> = mods.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, ["iris", "sodium"]);
+        assert_eq!(
+            mods[1].b.as_ref().unwrap().version.as_deref(),
+            Some("0.5.11")
+        );
+
+        let configs = diff_configs(&a.join("config"), &b.join("config"));
+        assert_eq!(configs.len(), 1);
+        assert_eq!(configs[0].only_in_a, ["chunks=12"]);
+        assert_eq!(configs[0].only_in_b, ["chunks=16"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
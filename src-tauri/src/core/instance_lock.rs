@@ -0,0 +1,142 @@
+//! Advisory read/write locks on instances.
+//!
+//! Operations that only read an instance's files, including running the
+//! game, share a read lock; those that rewrite or remove them, such as
+//! modpack updates, world syncs and deletion, take it exclusively. Locks are
+//! never waited for: a conflicting operation fails at once with
+//! `LauncherError::InstanceBusy`, naming what holds the instance.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::core::error::LauncherError;
+
+#[derive(Default)]
+struct Holders {
+    /// Operation of each read guard, keyed by guard id
+    readers: Vec<(u64, &'static str)>,
+    writer: Option<&'static str>,
+}
+
+#[derive(Default)]
+struct Registry {
+    instances: HashMap<String, Holders>,
+    next_id: u64,
+}
+
+#[derive(Default)]
+pub struct InstanceLocks {
+    registry: Arc<Mutex<Registry>>,
+}
+
+impl InstanceLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Share `instance_id` with other readers. `operation` describes what the
+    /// lock is held for, e.g. "running", and is shown to blocked callers.
+    pub fn read(
+        &self,
+        instance_id: &str,
+        operation: &'static str,
+    ) -> Result<InstanceGuard, LauncherError> {
+        let mut registry = self.registry.lock().unwrap();
+        let id = registry.next_id;
+        let holders = registry
+            .instances
+            .entry(instance_id.to_string())
+            .or_default();
+        if let Some(writer) = holders.writer {
+            return Err(busy(instance_id, writer));
+        }
+        holders.readers.push((id, operation));
+        registry.next_id += 1;
+        Ok(self.guard(instance_id, Some(id)))
+    }
+
+    /// Hold `instance_id` exclusively.
+    pub fn write(
+        &self,
+        instance_id: &str,
+        operation: &'static str,
+    ) -> Result<InstanceGuard, LauncherError> {
+        let mut registry = self.registry.lock().unwrap();
+        let holders = registry
+            .instances
+            .entry(instance_id.to_string())
+            .or_default();
+        if let Some(holder) = holders
+            .writer
+            .or_else(|| holders.readers.first().map(|(_, operation)| *operation))
+        {
+            return Err(busy(instance_id, holder));
+        }
+        holders.writer = Some(operation);
+        Ok(self.guard(instance_id, None))
+    }
+
+    fn guard(&self, instance_id: &str, reader: Option<u64>) -> InstanceGuard {
+        InstanceGuard {
+            registry: self.registry.clone(),
+            instance_id: instance_id.to_string(),
+            reader,
+        }
+    }
+}
+
+fn busy(instance_id: &str, operation: &str) -> LauncherError {
+    LauncherError::InstanceBusy {
+        instance_id: instance_id.to_string(),
+        operation: operation.to_string(),
+    }
+}
+
+/// Releases its lock when dropped.
+pub struct InstanceGuard {
+    registry: Arc<Mutex<Registry>>,
+    instance_id: String,
+    /// Guard id for read locks, `None` for the write lock
+    reader: Option<u64>,
+}
+
+impl Drop for InstanceGuard {
+    fn drop(&mut self) {
+        let mut registry = self.registry.lock().unwrap();
+        let Some(holders) = registry.instances.get_mut(&self.instance_id) else {
+            return;
+        };
+        match self.reader {
+            Some(id) => holders.readers.retain(|(reader, _)| *reader != id),
+            None => holders.writer = None,
+        }
+        if holders.readers.is_empty() && holders.writer.is_none() {
+            registry.instances.remove(&self.instance_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_readers_share_and_writers_exclude() {
+        let locks = InstanceLocks::new();
+        let running = locks.read("a", "running").unwrap();
+        let exporting = locks.read("a", "exporting").unwrap();
+        assert!(locks.write("b", "deleting").is_ok());
+
+        let err = locks.write("a", "deleting").err().unwrap();
+        assert_eq!(err.code(), "instance_busy");
+        assert_eq!(err.to_string(), "Instance is busy: running");
+
+        drop(running);
+        assert!(locks.write("a", "deleting").is_err());
+        drop(exporting);
+        let deleting = locks.write("a", "deleting").unwrap();
+        assert!(locks.read("a", "running").is_err());
+        drop(deleting);
+        assert!(locks.registry.lock().unwrap().instances.is_empty());
+    }
+}
@@ -0,0 +1,142 @@
+//! The new instance template from the settings.
+//!
+//! `apply` sets the memory and JVM overrides the template has, keeping the
+//! instance's other overrides, merges its options.txt lines into the
+//! instance's `options.txt` and copies its resource packs into
+//! `resourcepacks/`, enabling them. Applying it again replaces the overrides
+//! and options it sets but keeps everything else, so settings changed in game
+//! survive.
+
+use std::fs;
+use std::path::Path;
+
+use crate::core::config::{InstanceTemplate, JvmOverrides};
+use crate::core::instance::Instance;
+
+/// Apply `template` to `instance`, whose game directory is `instance_dir`.
+/// The instance itself is not saved.
+pub fn apply(
+    template: &InstanceTemplate,
+    instance: &mut Instance,
+    instance_dir: &Path,
+) -> Result<(), String> {
+    merge_jvm(&mut instance.jvm, &template.jvm);
+
+    let packs_dir = instance_dir.join("resourcepacks");
+    let mut packs = Vec::new();
+    for pack in &template.resource_packs {
+        let source = Path::new(pack);
+        let name = source
+            .file_name()
+            .ok_or_else(|| format!("Invalid resource pack: {}", pack))?
+            .to_string_lossy()
+            .to_string();
+        fs::create_dir_all(&packs_dir).map_err(|e| e.to_string())?;
+        fs::copy(source, packs_dir.join(&name))
+            .map_err(|e| format!("Failed to copy resource pack {}: {}", pack, e))?;
+        packs.push(name);
+    }
+
+    if template.options_txt.trim().is_empty() && packs.is_empty() {
+        return Ok(());
+    }
+    let options_path = instance_dir.join("options.txt");
+    let existing = fs::read_to_string(&options_path).unwrap_or_default();
+    let merged = merge_options(&existing, &template.options_txt, &packs);
+    fs::write(&options_path, merged).map_err(|e| format!("Failed to write options.txt: {}", e))
+}
+
+/// Set the overrides that `template` has on `jvm`.
+fn merge_jvm(jvm: &mut JvmOverrides, template: &JvmOverrides) {
+    jvm.min_memory = template.min_memory.or(jvm.min_memory);
+    jvm.max_memory = template.max_memory.or(jvm.max_memory);
+    jvm.preset = template.preset.or(jvm.preset);
+    jvm.display_backend = template.display_backend.or(jvm.display_backend);
+    jvm.gpu_preference = template.gpu_preference.or(jvm.gpu_preference);
+    jvm.gc_logging = template.gc_logging.or(jvm.gc_logging);
+}
+
+/// `existing` options.txt with the `key:value` lines of `snippet` set and
+/// `packs` added to the enabled resource packs.
+fn merge_options(existing: &str, snippet: &str, packs: &[String]) -> String {
+    let mut lines: Vec<String> = existing.lines().map(str::to_string).collect();
+    for (key, value) in snippet
+        .lines()
+        .filter_map(|line| line.trim().split_once(':'))
+    {
+        set_option(&mut lines, key.trim(), value.trim());
+    }
+
+    if !packs.is_empty() {
+        let mut enabled: Vec<String> = lines
+            .iter()
+            .find_map(|line| line.strip_prefix("resourcePacks:"))
+            .and_then(|value| serde_json::from_str(value).ok())
+            .unwrap_or_else(|| vec!["vanilla".to_string()]);
+        for pack in packs {
+            let entry = format!("file/{}", pack);
+            if !enabled.contains(&entry) {
+                enabled.push(entry);
+            }
+        }
+        let value = serde_json::to_string(&enabled).unwrap_or_default();
+        set_option(&mut lines, "resourcePacks", &value);
+    }
+
+    let mut merged = lines.join("\n");
+    merged.push('\n');
+    merged
+}
+
+fn set_option(lines: &mut Vec<String>, key: &str, value: &str) {
+    let line = format!("{}:{}", key, value);
+    match lines
+        .iter_mut()
+        .find(|l| l.split_once(':').is_some_and(|(k, _)| k == key))
+    {
+        Some(existing) => *existing = line,
+        None => lines.push(line),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_jvm_keeps_unset_overrides() {
+        let mut jvm = JvmOverrides {
+            min_memory: Some(512),
+            max_memory: Some(2048),
+            gc_logging: Some(true),
+            ..Default::default()
+        };
+        let template = JvmOverrides {
+            max_memory: Some(4096),
+            ..Default::default()
+        };
+        merge_jvm(&mut jvm, &template);
+        assert_eq!(jvm.min_memory, Some(512));
+        assert_eq!(jvm.max_memory, Some(4096));
+        assert_eq!(jvm.gc_logging, Some(true));
+    }
+
+    #[test]
+    fn test_merge_options() {
+        let existing = "version:3465\nrenderDistance:8\nresourcePacks:[\"vanilla\"]\n";
+        let merged = merge_options(
+            existing,
+            "renderDistance: 12\nguiScale:2\nnot an option",
+            &["Faithful.zip".to_string()],
+        );
+        assert_eq!(
+            merged,
+            "version:3465\nrenderDistance:12\nresourcePacks:[\"vanilla\",\"file/Faithful.zip\"]\n\
+             guiScale:2\n"
+        );
+        assert_eq!(
+            merge_options("", "", &["a.zip".to_string()]),
+            "resourcePacks:[\"vanilla\",\"file/a.zip\"]\n"
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Live updates of the active instance's content folders.
+//!
+//! Files dropped into `mods`, `resourcepacks` or `saves` from a file manager
+//! would otherwise only show up after the UI reloads. Each folder is watched
+//! without recursion, so a running game writing inside a world does not
+//! flood the UI; the instance directory itself is watched too, so folders
+//! created later are noticed. Bursts of events, such as a copy of many mods,
+//! are coalesced into one `instance-content-changed` event per folder.
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex, Weak};
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::core::events::{AppEventSink, EventScope, EventTarget, LauncherEvent};
+
+/// Quiet time after the last change before an event is emitted
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentFolder {
+    Mods,
+    Resourcepacks,
+    Saves,
+}
+
+impl ContentFolder {
+    const ALL: [ContentFolder; 3] = [Self::Mods, Self::Resourcepacks, Self::Saves];
+
+    fn dir_name(self) -> &'static str {
+        match self {
+            Self::Mods => "mods",
+            Self::Resourcepacks => "resourcepacks",
+            Self::Saves => "saves",
+        }
+    }
+
+    /// Folder a changed path belongs to: a direct child of it, or the folder
+    /// itself being created or removed.
+    fn of(instance_dir: &Path, path: &Path) -> Option<Self> {
+        let relative = path.strip_prefix(instance_dir).ok()?;
+        let top = relative.components().next()?.as_os_str();
+        Self::ALL
+            .into_iter()
+            .find(|folder| top == folder.dir_name())
+    }
+}
+
+/// Files in a content folder of the instance in the event's scope changed.
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceContentChanged {
+    pub folder: ContentFolder,
+}
+
+impl LauncherEvent for InstanceContentChanged {
+    const NAME: &'static str = "instance-content-changed";
+}
+
+struct Watch {
+    instance_id: String,
+    /// Stops watching when dropped, which also ends the debounce thread
+    _watcher: Arc<Mutex<RecommendedWatcher>>,
+}
+
+pub struct InstanceWatcher {
+    app: AppHandle,
+    current: Mutex<Option<Watch>>,
+}
+
+impl InstanceWatcher {
+    pub fn new(app: AppHandle) -> Self {
+        Self {
+            app,
+            current: Mutex::new(None),
+        }
+    }
+
+    /// Watch `instance_dir` instead of the previously watched instance.
+    pub fn watch(&self, instance_id: &str, instance_dir: &Path) -> Result<(), String> {
+        let mut current = self.current.lock().unwrap();
+        *current = None;
+
+        let (tx, rx) = mpsc::channel();
+        let dir = instance_dir.to_path_buf();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                for path in &event.paths {
+                    if let Some(folder) = ContentFolder::of(&dir, path) {
+                        let _ = tx.send(folder);
+                    }
+                }
+            }
+        })
+        .map_err(|e| format!("Failed to watch instance: {}", e))?;
+
+        watcher
+            .watch(instance_dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch instance: {}", e))?;
+        for folder in ContentFolder::ALL {
+            let path = instance_dir.join(folder.dir_name());
+            // Missing folders are picked up through the instance directory
+            if path.is_dir() {
+                watcher
+                    .watch(&path, RecursiveMode::NonRecursive)
+                    .map_err(|e| format!("Failed to watch {}: {}", path.display(), e))?;
+            }
+        }
+
+        let sink = AppEventSink::new(
+            self.app.clone(),
+            EventScope {
+                instance_id: Some(instance_id.to_string()),
+                session_id: None,
+            },
+        );
+        let watcher = Arc::new(Mutex::new(watcher));
+        let weak = Arc::downgrade(&watcher);
+        let watched = instance_dir.to_path_buf();
+        std::thread::spawn(move || debounce(rx, &sink, &weak, &watched));
+
+        *current = Some(Watch {
+            instance_id: instance_id.to_string(),
+            _watcher: watcher,
+        });
+        Ok(())
+    }
+
+    /// Stop watching `instance_id`, e.g. before deleting it.
+    pub fn unwatch(&self, instance_id: &str) {
+        let mut current = self.current.lock().unwrap();
+        if current
+            .as_ref()
+            .is_some_and(|watch| watch.instance_id == instance_id)
+        {
+            *current = None;
+        }
+    }
+}
+
+/// Emit one event per folder once changes stop for `DEBOUNCE`. Returns when
+/// the watcher is dropped.
+fn debounce(
+    rx: mpsc::Receiver<ContentFolder>,
+    sink: &AppEventSink,
+    watcher: &Weak<Mutex<RecommendedWatcher>>,
+    instance_dir: &Path,
+) {
+    while let Ok(first) = rx.recv() {
+        let mut changed = HashSet::from([first]);
+        loop {
+            match rx.recv_timeout(DEBOUNCE) {
+                Ok(folder) => {
+                    changed.insert(folder);
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        let Some(watcher) = watcher.upgrade() else {
+            return;
+        };
+        for folder in changed {
+            // A folder created after `watch` has no watch of its own yet
+            let path = instance_dir.join(folder.dir_name());
+            if path.is_dir() {
+                let _ = watcher
+                    .lock()
+                    .unwrap()
+                    .watch(&path, RecursiveMode::NonRecursive);
+            }
+            sink.emit_event(InstanceContentChanged { folder });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_folder_of_path() {
+        let dir = Path::new("/data/instances/a");
+        assert_eq!(
+            ContentFolder::of(dir, &dir.join("mods").join("sodium.jar")),
+            Some(ContentFolder::Mods)
+        );
+        assert_eq!(
+            ContentFolder::of(dir, &dir.join("saves")),
+            Some(ContentFolder::Saves)
+        );
+        assert_eq!(ContentFolder::of(dir, &dir.join("options.txt")), None);
+        assert_eq!(
+            ContentFolder::of(Path::new("/elsewhere"), &dir.join("mods")),
+            None
+        );
+    }
+}
@@ -0,0 +1,233 @@
+//! Jar mods for legacy versions.
+//!
+//! Before mod loaders, mods were installed by copying their classes into
+//! `minecraft.jar` and deleting `META-INF` so the signature check passes.
+//! An instance keeps its jar mods in `jarmods/` and lists them in
+//! `Instance::jar_mods`; later entries override earlier ones, and all of
+//! them override the client jar. `client_jar` builds the patched jar once
+//! per set of inputs and caches it under `versions/<id>/jarmods/`, named by
+//! the hash of the client jar and the mods in order. Garbage collection keeps
+//! the cached jars whose inputs an instance still has, see `cached_jar`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::downloader::compute_sha1;
+use crate::core::instance::Instance;
+
+/// Folder in an instance holding its jar mods
+pub const JAR_MODS_DIR: &str = "jarmods";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JarMod {
+    /// File name in the instance's `jarmods/`
+    pub file: String,
+    pub enabled: bool,
+}
+
+fn read_jar(path: &Path) -> Result<Vec<u8>, String> {
+    let data = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if zip::ZipArchive::new(std::io::Cursor::new(&data)).is_err() {
+        return Err(format!("{} is not a jar or zip", path.display()));
+    }
+    Ok(data)
+}
+
+/// Copy `source` into the instance and append it to its jar mods. A jar mod
+/// of the same name is replaced and keeps its place.
+pub fn add(instance_dir: &Path, instance: &mut Instance, source: &Path) -> Result<(), String> {
+    let file = source
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid path: {}", source.display()))?;
+    let data = read_jar(source)?;
+    let dir = instance_dir.join(JAR_MODS_DIR);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    fs::write(dir.join(&file), data).map_err(|e| e.to_string())?;
+    if !instance.jar_mods.iter().any(|m| m.file == file) {
+        instance.jar_mods.push(JarMod {
+            file,
+            enabled: true,
+        });
+    }
+    Ok(())
+}
+
+/// Delete a jar mod's file and drop it from the list.
+pub fn remove(instance_dir: &Path, instance: &mut Instance, file: &str) -> Result<(), String> {
+    let index = instance
+        .jar_mods
+        .iter()
+        .position(|m| m.file == file)
+        .ok_or_else(|| format!("No jar mod named {}", file))?;
+    instance.jar_mods.remove(index);
+    match fs::remove_file(instance_dir.join(JAR_MODS_DIR).join(file)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Replace the order and enabled flags; `jar_mods` must list the same files.
+pub fn set(instance: &mut Instance, jar_mods: Vec<JarMod>) -> Result<(), String> {
+    let current: HashSet<&str> = instance.jar_mods.iter().map(|m| m.file.as_str()).collect();
+    let given: HashSet<&str> = jar_mods.iter().map(|m| m.file.as_str()).collect();
+    if current != given || given.len() != jar_mods.len() {
+        return Err("The list must contain each jar mod once".to_string());
+    }
+    instance.jar_mods = jar_mods;
+    Ok(())
+}
+
+/// Enabled jar mods of `instance`, in merge order.
+pub fn enabled_paths(instance_dir: &Path, instance: &Instance) -> Vec<PathBuf> {
+    instance
+        .jar_mods
+        .iter()
+        .filter(|m| m.enabled)
+        .map(|m| instance_dir.join(JAR_MODS_DIR).join(&m.file))
+        .collect()
+}
+
+/// The jar to put on the classpath: `client_jar` itself without enabled jar
+/// mods, otherwise the patched copy.
+pub fn client_jar(
+    instance_dir: &Path,
+    instance: &Instance,
+    client_jar: &Path,
+) -> Result<PathBuf, String> {
+    let mods = enabled_paths(instance_dir, instance);
+    if mods.is_empty() {
+        return Ok(client_jar.to_path_buf());
+    }
+    patched_jar(&cache_dir(client_jar)?, client_jar, &mods)
+}
+
+fn cache_dir(client_jar: &Path) -> Result<PathBuf, String> {
+    client_jar
+        .parent()
+        .map(|dir| dir.join(JAR_MODS_DIR))
+        .ok_or_else(|| "Invalid client jar path".to_string())
+}
+
+/// Where the patched copy of `client_jar` with `mods` is cached, whether or
+/// not it was built yet.
+pub fn cached_jar(client_jar: &Path, mods: &[PathBuf]) -> Result<PathBuf, String> {
+    cached_path(&cache_dir(client_jar)?, client_jar, mods)
+}
+
+fn cached_path(cache_dir: &Path, client_jar: &Path, mods: &[PathBuf]) -> Result<PathBuf, String> {
+    let mut key = compute_sha1(&read_jar(client_jar)?);
+    for path in mods {
+        key.push('\n');
+        key.push_str(&compute_sha1(&read_jar(path)?));
+    }
+    Ok(cache_dir.join(format!("{}.jar", compute_sha1(key.as_bytes()))))
+}
+
+/// Merge `mods` into `client_jar`, reusing an earlier result for the same
+/// inputs.
+pub fn patched_jar(
+    cache_dir: &Path,
+    client_jar: &Path,
+    mods: &[PathBuf],
+) -> Result<PathBuf, String> {
+    let target = cached_path(cache_dir, client_jar, mods)?;
+    if target.exists() {
+        return Ok(target);
+    }
+
+    fs::create_dir_all(cache_dir).map_err(|e| e.to_string())?;
+    let temp = target.with_extension("jar.part");
+    merge(&temp, client_jar, mods)
+        .and_then(|_| fs::rename(&temp, &target).map_err(|e| e.to_string()))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&temp);
+        })?;
+    Ok(target)
+}
+
+fn merge(target: &Path, client_jar: &Path, mods: &[PathBuf]) -> Result<(), String> {
+    let file = fs::File::create(target).map_err(|e| e.to_string())?;
+    let mut writer = zip::ZipWriter::new(file);
+    let mut written = HashSet::new();
+    // The last mod wins, so sources are read from highest priority down
+    for source in mods.iter().rev().map(PathBuf::as_path).chain([client_jar]) {
+        let file = fs::File::open(source).map_err(|e| e.to_string())?;
+        let mut archive = zip::ZipArchive::new(file)
+            .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+        for i in 0..archive.len() {
+            let entry = archive.by_index_raw(i).map_err(|e| e.to_string())?;
+            let name = entry.name().to_string();
+            let signature = name
+                .get(..9)
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case("META-INF/"));
+            if signature || !written.insert(name) {
+                continue;
+            }
+            writer.raw_copy_file(entry).map_err(|e| e.to_string())?;
+        }
+    }
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    fn write_jar(path: &Path, entries: &[(&str, &str)]) {
+        let mut jar = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        for (name, content) in entries {
+            jar.start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            jar.write_all(content.as_bytes()).unwrap();
+        }
+        jar.finish().unwrap();
+    }
+
+    #[test]
+    fn test_patched_jar() {
+        let dir = std::env::temp_dir().join(format!("dropout-jarmods-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let client = dir.join("b1.7.3.jar");
+        write_jar(
+            &client,
+            &[
+                ("META-INF/MOJANG_C.SF", "sig"),
+                ("a.class", "vanilla"),
+                ("b.class", "vanilla"),
+            ],
+        );
+        let first = dir.join("first.zip");
+        write_jar(&first, &[("a.class", "first"), ("c.class", "first")]);
+        let second = dir.join("second.zip");
+        write_jar(&second, &[("a.class", "second")]);
+
+        let cache = dir.join("cache");
+        let mods = [first, second];
+        let patched = patched_jar(&cache, &client, &mods).unwrap();
+        assert_eq!(patched_jar(&cache, &client, &mods).unwrap(), patched);
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(&patched).unwrap()).unwrap();
+        let mut read = |name: &str| {
+            let mut content = String::new();
+            archive
+                .by_name(name)
+                .ok()?
+                .read_to_string(&mut content)
+                .unwrap();
+            Some(content)
+        };
+        assert_eq!(read("a.class").as_deref(), Some("second"));
+        assert_eq!(read("b.class").as_deref(), Some("vanilla"));
+        assert_eq!(read("c.class").as_deref(), Some("first"));
+        assert_eq!(read("META-INF/MOJANG_C.SF"), None);
+
+        let reversed = [mods[1].clone(), mods[0].clone()];
+        assert_ne!(patched_jar(&cache, &client, &reversed).unwrap(), patched);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
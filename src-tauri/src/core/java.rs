@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use tauri::AppHandle;
 use tauri::Emitter;
@@ -9,13 +10,46 @@ use crate::core::downloader::{self, JavaDownloadProgress, DownloadQueue, Pending
 use crate::utils::zip;
 
 const ADOPTIUM_API_BASE: &str = "https://api.adoptium.net/v3";
+const ZULU_API_BASE: &str = "https://api.azul.com/metadata/v1/zulu/packages";
+const GRAALVM_RELEASES_URL: &str =
+    "https://api.github.com/repos/graalvm/graalvm-ce-builds/releases?per_page=50";
 const CACHE_DURATION_SECS: u64 = 24 * 60 * 60; // 24 hours
+const PROVENANCE_FILE: &str = "dropout-java.json";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JavaInstallation {
     pub path: String,
     pub version: String,
     pub is_64bit: bool,
+    /// Set for runtimes installed by DropOut; `None` for system Java
+    #[serde(default)]
+    pub managed: Option<JavaProvenance>,
+}
+
+/// Where a runtime installed by DropOut came from, kept as
+/// `dropout-java.json` in its `<vendor>-<major>-<type>` folder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JavaProvenance {
+    pub vendor: String,
+    pub major_version: u32,
+    pub image_type: ImageType,
+    /// Release the runtime was extracted from, e.g. `jdk-21.0.4+7`
+    pub release_name: String,
+    /// Unix time of the installation
+    pub installed_at: u64,
+    pub source_url: String,
+    pub archive_checksum: Option<String>,
+}
+
+impl JavaProvenance {
+    pub fn load(runtime_dir: &Path) -> Option<Self> {
+        crate::utils::file_utils::read_json(&runtime_dir.join(PROVENANCE_FILE)).ok()
+    }
+
+    fn save(&self, runtime_dir: &Path) -> Result<(), String> {
+        crate::utils::file_utils::write_json_atomic(&runtime_dir.join(PROVENANCE_FILE), self)
+            .map_err(|e| e.to_string())
+    }
 }
 
 /// Java image type: JRE or JDK
@@ -41,6 +75,93 @@ impl std::fmt::Display for ImageType {
     }
 }
 
+/// Distribution a runtime is downloaded from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JavaVendor {
+    #[default]
+    Adoptium,
+    Zulu,
+    Graalvm,
+}
+
+impl JavaVendor {
+    pub const ALL: [JavaVendor; 3] = [Self::Adoptium, Self::Zulu, Self::Graalvm];
+
+    /// Prefix of the folders its runtimes are installed to
+    fn dir_prefix(self) -> &'static str {
+        match self {
+            Self::Adoptium => "temurin",
+            Self::Zulu => "zulu",
+            Self::Graalvm => "graalvm",
+        }
+    }
+
+    /// Folder under the Java directory a runtime is installed to, e.g.
+    /// `temurin-21-jre`
+    pub fn runtime_dir_name(self, major_version: u32, image_type: ImageType) -> String {
+        format!("{}-{}-{}", self.dir_prefix(), major_version, image_type)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Adoptium => Adoptium.vendor(),
+            Self::Zulu => Zulu.vendor(),
+            Self::Graalvm => GraalVm.vendor(),
+        }
+    }
+
+    pub async fn fetch_release(
+        self,
+        major_version: u32,
+        image_type: ImageType,
+    ) -> Result<JavaDownloadInfo, String> {
+        match self {
+            Self::Adoptium => Adoptium.fetch_release(major_version, image_type).await,
+            Self::Zulu => Zulu.fetch_release(major_version, image_type).await,
+            Self::Graalvm => GraalVm.fetch_release(major_version, image_type).await,
+        }
+    }
+}
+
+/// Vendor, major version and image type of a runtime folder named by
+/// `JavaVendor::runtime_dir_name`.
+pub fn parse_runtime_dir(name: &str) -> Option<(JavaVendor, u32, ImageType)> {
+    let (prefix, rest) = name.split_once('-')?;
+    let vendor = JavaVendor::ALL
+        .into_iter()
+        .find(|vendor| vendor.dir_prefix() == prefix)?;
+    let (major, image_type) = rest.split_once('-')?;
+    let image_type = match image_type {
+        "jre" => ImageType::Jre,
+        "jdk" => ImageType::Jdk,
+        _ => return None,
+    };
+    Some((vendor, major.parse().ok()?, image_type))
+}
+
+/// A source of Java runtime archives.
+pub trait JavaProvider {
+    /// Vendor recorded in the provenance of installed runtimes
+    fn vendor(&self) -> &'static str;
+
+    /// The newest release of `major_version` for the current platform
+    fn fetch_release(
+        &self,
+        major_version: u32,
+        image_type: ImageType,
+    ) -> impl Future<Output = Result<JavaDownloadInfo, String>> + Send;
+}
+
+/// Eclipse Temurin builds from the Adoptium API
+pub struct Adoptium;
+
+/// Azul Zulu builds from Azul's metadata API
+pub struct Zulu;
+
+/// GraalVM Community Edition builds from its GitHub releases
+pub struct GraalVm;
+
 /// Java release information for UI display
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JavaReleaseInfo {
@@ -127,6 +248,8 @@ pub struct JavaDownloadInfo {
     pub version: String,
     pub release_name: String,
     pub download_url: String,
+    /// Other URLs serving the same archive
+    pub mirrors: Vec<String>,
     pub file_name: String,
     pub file_size: u64,
     pub checksum: Option<String>,
@@ -250,7 +373,7 @@ pub async fn fetch_java_catalog(app_handle: &AppHandle, force_refresh: bool) ->
 
     let os = get_adoptium_os();
     let arch = get_adoptium_arch();
-    let client = reqwest::Client::new();
+    let client = crate::core::http::client();
 
     // 1. Fetch available releases
     let releases_url = format!("{}/info/available_releases", ADOPTIUM_API_BASE);
@@ -355,80 +478,243 @@ pub async fn fetch_java_catalog(app_handle: &AppHandle, force_refresh: bool) ->
     Ok(catalog)
 }
 
-/// Get Adoptium API download info for a specific Java version and image type
-///
-/// # Arguments
-/// * `major_version` - Java major version (e.g., 8, 11, 17)
-/// * `image_type` - JRE or JDK
-///
-/// # Returns
-/// * `Ok(JavaDownloadInfo)` - Download information
-/// * `Err(String)` - Error message
-pub async fn fetch_java_release(
-    major_version: u32,
-    image_type: ImageType,
-) -> Result<JavaDownloadInfo, String> {
-    let os = get_adoptium_os();
-    let arch = get_adoptium_arch();
+impl JavaProvider for Adoptium {
+    fn vendor(&self) -> &'static str {
+        "Eclipse Adoptium"
+    }
 
-    let url = format!(
-        "{}/assets/latest/{}/hotspot?os={}&architecture={}&image_type={}",
-        ADOPTIUM_API_BASE, major_version, os, arch, image_type
-    );
+    async fn fetch_release(
+        &self,
+        major_version: u32,
+        image_type: ImageType,
+    ) -> Result<JavaDownloadInfo, String> {
+        let os = get_adoptium_os();
+        let arch = get_adoptium_arch();
+
+        let url = format!(
+            "{}/assets/latest/{}/hotspot?os={}&architecture={}&image_type={}",
+            ADOPTIUM_API_BASE, major_version, os, arch, image_type
+        );
+
+        let client = crate::core::http::client();
+        let response = client
+            .get(&url)
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| format!("Network request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!(
+                "Adoptium API returned error: {} - The version/platform might be unavailable",
+                response.status()
+            ));
+        }
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .header("Accept", "application/json")
-        .send()
-        .await
-        .map_err(|e| format!("Network request failed: {}", e))?;
+        let assets: Vec<AdoptiumAsset> = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse API response: {}", e))?;
+
+        let asset = assets
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("Java {} {} download not found", major_version, image_type))?;
+
+        // The binary endpoint redirects to the same archive
+        let binary_url = format!(
+            "{}/binary/version/{}/{}/{}/{}/hotspot/normal/eclipse",
+            ADOPTIUM_API_BASE,
+            asset.release_name.replace('+', "%2B"),
+            os,
+            arch,
+            image_type
+        );
+
+        Ok(JavaDownloadInfo {
+            version: asset.version.semver.clone(),
+            release_name: asset.release_name,
+            download_url: asset.binary.package.link,
+            mirrors: vec![binary_url],
+            file_name: asset.binary.package.name,
+            file_size: asset.binary.package.size,
+            checksum: asset.binary.package.checksum,
+            image_type: asset.binary.image_type,
+        })
+    }
+}
 
-    if !response.status().is_success() {
-        return Err(format!(
-            "Adoptium API returned error: {} - The version/platform might be unavailable",
-            response.status()
-        ));
+#[derive(Debug, Deserialize)]
+struct ZuluPackage {
+    package_uuid: String,
+    name: String,
+    java_version: Vec<u32>,
+    download_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ZuluPackageDetails {
+    sha256_hash: Option<String>,
+    size: u64,
+}
+
+impl JavaProvider for Zulu {
+    fn vendor(&self) -> &'static str {
+        "Azul Zulu"
     }
 
-    let assets: Vec<AdoptiumAsset> = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse API response: {}", e))?;
+    async fn fetch_release(
+        &self,
+        major_version: u32,
+        image_type: ImageType,
+    ) -> Result<JavaDownloadInfo, String> {
+        let (os, archive_type) = match get_adoptium_os() {
+            "alpine-linux" => ("linux-musl", "tar.gz"),
+            "mac" => ("macos", "tar.gz"),
+            "windows" => ("windows", "zip"),
+            _ => ("linux-glibc", "tar.gz"),
+        };
+        let url = format!(
+            "{}/?java_version={}&os={}&arch={}&archive_type={}&java_package_type={}\
+             &javafx_bundled=false&release_status=ga&availability_types=CA&latest=true\
+             &page=1&page_size=1",
+            ZULU_API_BASE,
+            major_version,
+            os,
+            get_adoptium_arch(),
+            archive_type,
+            image_type
+        );
+        let packages: Vec<ZuluPackage> =
+            crate::core::http_cache::get_json(&url, crate::core::http_cache::TTL_HOUR).await?;
+        let package = packages.into_iter().next().ok_or_else(|| {
+            format!("Zulu {} {} is not available for this platform", major_version, image_type)
+        })?;
+        let details: ZuluPackageDetails = crate::core::http_cache::get_json(
+            &format!("{}/{}", ZULU_API_BASE, package.package_uuid),
+            crate::core::http_cache::TTL_DAY,
+        )
+        .await?;
+
+        let version: Vec<String> = package.java_version.iter().map(u32::to_string).collect();
+        Ok(JavaDownloadInfo {
+            version: version.join("."),
+            // The archive holds a folder named like the file
+            release_name: archive_stem(&package.name).to_string(),
+            download_url: package.download_url,
+            mirrors: Vec::new(),
+            file_name: package.name,
+            file_size: details.size,
+            checksum: details.sha256_hash,
+            image_type: image_type.to_string(),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    prerelease: bool,
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubAsset {
+    name: String,
+    size: u64,
+    browser_download_url: String,
+}
+
+impl JavaProvider for GraalVm {
+    fn vendor(&self) -> &'static str {
+        "GraalVM Community"
+    }
+
+    async fn fetch_release(
+        &self,
+        major_version: u32,
+        image_type: ImageType,
+    ) -> Result<JavaDownloadInfo, String> {
+        if image_type == ImageType::Jre {
+            return Err("GraalVM is only available as a JDK".to_string());
+        }
+        let (os, extension) = match get_adoptium_os() {
+            "mac" => ("macos", "tar.gz"),
+            "windows" => ("windows", "zip"),
+            _ => ("linux", "tar.gz"),
+        };
+        // e.g. graalvm-community-jdk-21.0.2_linux-x64_bin.tar.gz
+        let suffix = format!("_{}-{}_bin.{}", os, get_adoptium_arch(), extension);
+
+        let releases: Vec<GithubRelease> = crate::core::http_cache::get_json(
+            GRAALVM_RELEASES_URL,
+            crate::core::http_cache::TTL_HOUR,
+        )
+        .await?;
+        let (release, asset) = releases
+            .iter()
+            .filter(|release| {
+                !release.prerelease && graalvm_tag_major(&release.tag_name) == Some(major_version)
+            })
+            .find_map(|release| {
+                let asset = release.assets.iter().find(|asset| {
+                    asset.name.starts_with("graalvm-community-") && asset.name.ends_with(&suffix)
+                })?;
+                Some((release, asset))
+            })
+            .ok_or_else(|| {
+                format!("GraalVM for Java {} is not available for this platform", major_version)
+            })?;
+
+        let checksum_name = format!("{}.sha256", asset.name);
+        let checksum = match release.assets.iter().find(|a| a.name == checksum_name) {
+            Some(sums) => crate::core::http_cache::get_text(
+                &sums.browser_download_url,
+                crate::core::http_cache::TTL_DAY,
+            )
+            .await
+            .ok()
+            .and_then(|text| text.split_whitespace().next().map(str::to_string)),
+            None => None,
+        };
 
-    let asset = assets
+        Ok(JavaDownloadInfo {
+            version: release.tag_name.trim_start_matches("jdk-").to_string(),
+            release_name: release.tag_name.clone(),
+            download_url: asset.browser_download_url.clone(),
+            mirrors: Vec::new(),
+            file_name: asset.name.clone(),
+            file_size: asset.size,
+            checksum,
+            image_type: image_type.to_string(),
+        })
+    }
+}
+
+/// Java major version of a GraalVM release tag like `jdk-21.0.2`.
+fn graalvm_tag_major(tag: &str) -> Option<u32> {
+    tag.strip_prefix("jdk-")?.split(['.', '+']).next()?.parse().ok()
+}
+
+/// File name without its archive extension
+fn archive_stem(file_name: &str) -> &str {
+    [".tar.gz", ".tgz", ".zip"]
         .into_iter()
-        .next()
-        .ok_or_else(|| format!("Java {} {} download not found", major_version, image_type))?;
-
-    Ok(JavaDownloadInfo {
-        version: asset.version.semver.clone(),
-        release_name: asset.release_name,
-        download_url: asset.binary.package.link,
-        file_name: asset.binary.package.name,
-        file_size: asset.binary.package.size,
-        checksum: asset.binary.package.checksum,
-        image_type: asset.binary.image_type,
-    })
+        .find_map(|extension| file_name.strip_suffix(extension))
+        .unwrap_or(file_name)
 }
 
 /// Fetch available Java versions from Adoptium API
 pub async fn fetch_available_versions() -> Result<Vec<u32>, String> {
     let url = format!("{}/info/available_releases", ADOPTIUM_API_BASE);
 
-    let response = reqwest::get(url)
-        .await
-        .map_err(|e| format!("Network request failed: {}", e))?;
-
     #[derive(Deserialize)]
     struct AvailableReleases {
         available_releases: Vec<u32>,
     }
 
-    let releases: AvailableReleases = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    let releases: AvailableReleases =
+        crate::core::http_cache::get_json(&url, crate::core::http_cache::TTL_DAY).await?;
 
     Ok(releases.available_releases)
 }
@@ -437,6 +723,7 @@ pub async fn fetch_available_versions() -> Result<Vec<u32>, String> {
 ///
 /// # Arguments
 /// * `app_handle` - Tauri app handle for accessing app directories
+/// * `vendor` - Distribution to download
 /// * `major_version` - Java major version (e.g., 8, 11, 17)
 /// * `image_type` - JRE or JDK
 /// * `custom_path` - Optional custom installation path
@@ -445,24 +732,25 @@ pub async fn fetch_available_versions() -> Result<Vec<u32>, String> {
 /// * `Ok(JavaInstallation)` - Information about the successfully installed Java
 pub async fn download_and_install_java(
     app_handle: &AppHandle,
+    vendor: JavaVendor,
     major_version: u32,
     image_type: ImageType,
     custom_path: Option<PathBuf>,
 ) -> Result<JavaInstallation, String> {
     // 1. Fetch download information
-    let info = fetch_java_release(major_version, image_type).await?;
+    let info = vendor.fetch_release(major_version, image_type).await?;
     let file_name = info.file_name.clone();
 
     // 2. Prepare installation directory
     let install_base = custom_path.unwrap_or_else(|| get_java_install_dir(app_handle));
-    let version_dir = install_base.join(format!("temurin-{}-{}", major_version, image_type));
+    let version_dir = install_base.join(vendor.runtime_dir_name(major_version, image_type));
 
     std::fs::create_dir_all(&install_base)
         .map_err(|e| format!("Failed to create installation directory: {}", e))?;
 
     // 3. Add to download queue for persistence
-    let mut queue = DownloadQueue::load(app_handle);
-    queue.add(PendingJavaDownload {
+    let pending = PendingJavaDownload {
+        vendor,
         major_version,
         image_type: image_type.to_string(),
         download_url: info.download_url.clone(),
@@ -474,8 +762,8 @@ pub async fn download_and_install_java(
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs(),
-    });
-    queue.save(app_handle)?;
+    };
+    DownloadQueue::update(app_handle, |queue| queue.add(pending))?;
 
     // 4. Download the archive with resume support
     let archive_path = install_base.join(&info.file_name);
@@ -495,12 +783,16 @@ pub async fn download_and_install_java(
 
     if need_download {
         // Use resumable download
+        let urls: Vec<String> = std::iter::once(info.download_url.clone())
+            .chain(info.mirrors.iter().cloned())
+            .collect();
         downloader::download_with_resume(
             app_handle,
-            &info.download_url,
+            &urls,
             &archive_path,
             info.checksum.as_deref(),
             info.file_size,
+            None,
         )
         .await?;
     }
@@ -519,55 +811,91 @@ pub async fn download_and_install_java(
         },
     );
 
-    // 6. Extract
-    // If the target directory exists, remove it first
-    if version_dir.exists() {
-        std::fs::remove_dir_all(&version_dir)
-            .map_err(|e| format!("Failed to remove old version directory: {}", e))?;
+    // 6. Extract next to the current runtime, which stays usable until the
+    // new one is complete
+    let staging_dir = install_base.join(format!(
+        "{}.staging",
+        vendor.runtime_dir_name(major_version, image_type)
+    ));
+    if staging_dir.exists() {
+        std::fs::remove_dir_all(&staging_dir)
+            .map_err(|e| format!("Failed to remove old staging directory: {}", e))?;
     }
 
-    std::fs::create_dir_all(&version_dir)
+    std::fs::create_dir_all(&staging_dir)
         .map_err(|e| format!("Failed to create version directory: {}", e))?;
 
-    let top_level_dir = if info.file_name.ends_with(".tar.gz") || info.file_name.ends_with(".tgz") {
-        zip::extract_tar_gz(&archive_path, &version_dir)?
-    } else if info.file_name.ends_with(".zip") {
-        zip::extract_zip(&archive_path, &version_dir)?;
-        // Find the top-level directory inside the extracted folder
-        find_top_level_dir(&version_dir)?
-    } else {
+    if !(info.file_name.ends_with(".tar.gz")
+        || info.file_name.ends_with(".tgz")
+        || info.file_name.ends_with(".zip"))
+    {
         return Err(format!("Unsupported archive format: {}", info.file_name));
+    }
+    // Cancelling the download also cancels its extraction
+    let extracted = crate::core::extract::extract(
+        app_handle.clone(),
+        archive_path.clone(),
+        staging_dir.clone(),
+        zip::SymlinkPolicy::Contained,
+        Some(&downloader::JAVA_DOWNLOAD_CANCELLED),
+    )
+    .await?;
+    // Zip archives: find the top-level directory inside the extracted folder
+    let top_level_dir = match extracted {
+        Some(dir) => dir,
+        None => find_top_level_dir(&staging_dir)?,
     };
 
     // 7. Clean up downloaded archive
     let _ = std::fs::remove_file(&archive_path);
 
-    // 8. Locate java executable
-    // macOS has a different structure: jdk-xxx/Contents/Home/bin/java
-    // Linux/Windows: jdk-xxx/bin/java
-    let java_home = version_dir.join(&top_level_dir);
-    let java_bin = if cfg!(target_os = "macos") {
-        java_home.join("Contents").join("Home").join("bin").join("java")
-    } else if cfg!(windows) {
-        java_home.join("bin").join("java.exe")
-    } else {
-        java_home.join("bin").join("java")
-    };
-
-    if !java_bin.exists() {
+    // 8. Locate java executable, then swap the new runtime in
+    if !java_executable(&staging_dir.join(&top_level_dir)).exists() {
+        let _ = std::fs::remove_dir_all(&staging_dir);
         return Err(format!(
-            "Installation completed but Java executable not found: {}",
-            java_bin.display()
+            "Installation completed but Java executable not found in {}",
+            info.file_name
         ));
     }
+    replace_dir(&staging_dir, &version_dir)?;
+    let java_bin = java_executable(&version_dir.join(&top_level_dir));
 
     // 9. Verify installation
-    let installation = check_java_installation(&java_bin)
+    let mut installation = check_java_installation(&java_bin)
         .ok_or_else(|| "Failed to verify Java installation".to_string())?;
+    let provenance = JavaProvenance {
+        vendor: vendor.name().to_string(),
+        major_version,
+        image_type,
+        release_name: info.release_name.clone(),
+        installed_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        source_url: info.download_url.clone(),
+        archive_checksum: info.checksum.clone(),
+    };
+    provenance.save(&version_dir)?;
+    installation.managed = Some(provenance);
+
+    // Record the files so damage can be detected later
+    if let Some(checksum) = info.checksum.clone() {
+        let runtime_dir = version_dir.clone();
+        let recorded = tokio::task::spawn_blocking(move || {
+            crate::core::java_integrity::write_manifest(&runtime_dir, &checksum)
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result);
+        if let Err(e) = recorded {
+            println!("[Java] Failed to record the installed files: {}", e);
+        }
+    }
 
     // 10. Remove from download queue
-    queue.remove(major_version, &image_type.to_string());
-    queue.save(app_handle)?;
+    DownloadQueue::update(app_handle, |queue| {
+        queue.remove(major_version, &image_type.to_string())
+    })?;
 
     // 11. Emit completed status
     let _ = app_handle.emit(
@@ -586,6 +914,80 @@ pub async fn download_and_install_java(
     Ok(installation)
 }
 
+/// A managed runtime compared with the newest release of its major version.
+#[derive(Debug, Clone, Serialize)]
+pub struct JavaUpdate {
+    pub vendor: JavaVendor,
+    pub major_version: u32,
+    pub image_type: ImageType,
+    /// Installed release, e.g. `jdk-21.0.4+7`
+    pub installed: String,
+    /// Newest release, e.g. `jdk-21.0.5+11`
+    pub latest: String,
+    pub update_available: bool,
+}
+
+/// Compare the runtimes DropOut installed against the latest release of
+/// each major version from their vendor.
+pub async fn check_java_updates(app_handle: &AppHandle) -> Result<Vec<JavaUpdate>, String> {
+    let java_dir = get_java_install_dir(app_handle);
+    let mut updates = Vec::new();
+    for runtime in crate::core::prefetch::managed_runtimes(&java_dir) {
+        let info = runtime
+            .vendor
+            .fetch_release(runtime.major_version, runtime.image_type)
+            .await?;
+        updates.push(JavaUpdate {
+            vendor: runtime.vendor,
+            major_version: runtime.major_version,
+            image_type: runtime.image_type,
+            update_available: !runtime.is_release(&info.release_name),
+            installed: runtime.release_name.unwrap_or(runtime.release_dir),
+            latest: info.release_name,
+        });
+    }
+    updates.sort_by_key(|update| {
+        (update.major_version, update.image_type == ImageType::Jdk, update.vendor.dir_prefix())
+    });
+    Ok(updates)
+}
+
+/// The `java` executable of the runtime extracted to `java_home`.
+/// macOS has a different structure: jdk-xxx/Contents/Home/bin/java
+fn java_executable(java_home: &Path) -> PathBuf {
+    if cfg!(target_os = "macos") {
+        java_home.join("Contents").join("Home").join("bin").join("java")
+    } else if cfg!(windows) {
+        java_home.join("bin").join("java.exe")
+    } else {
+        java_home.join("bin").join("java")
+    }
+}
+
+/// Replace `target` with `staging`, keeping `target` if that fails, e.g.
+/// because a game still runs from it on Windows.
+fn replace_dir(staging: &Path, target: &Path) -> Result<(), String> {
+    let mut backup = target.as_os_str().to_owned();
+    backup.push(".old");
+    let backup = PathBuf::from(backup);
+    if backup.exists() {
+        std::fs::remove_dir_all(&backup)
+            .map_err(|e| format!("Failed to remove {}: {}", backup.display(), e))?;
+    }
+    if target.exists() {
+        std::fs::rename(target, &backup).map_err(|e| {
+            let _ = std::fs::remove_dir_all(staging);
+            format!("Failed to replace {}, is it in use? {}", target.display(), e)
+        })?;
+    }
+    if let Err(e) = std::fs::rename(staging, target) {
+        let _ = std::fs::rename(&backup, target);
+        return Err(format!("Failed to move the new runtime in place: {}", e));
+    }
+    let _ = std::fs::remove_dir_all(&backup);
+    Ok(())
+}
+
 /// Find the top-level directory inside the extracted folder
 fn find_top_level_dir(extract_dir: &PathBuf) -> Result<String, String> {
     let entries: Vec<_> = std::fs::read_dir(extract_dir)
@@ -785,7 +1187,7 @@ fn get_java_candidates() -> Vec<PathBuf> {
 }
 
 /// Check a specific Java installation and get its version info
-fn check_java_installation(path: &PathBuf) -> Option<JavaInstallation> {
+pub fn check_java_installation(path: &PathBuf) -> Option<JavaInstallation> {
     let output = Command::new(path).arg("-version").output().ok()?;
 
     // Java outputs version info to stderr
@@ -799,6 +1201,7 @@ fn check_java_installation(path: &PathBuf) -> Option<JavaInstallation> {
         path: path.to_string_lossy().to_string(),
         version,
         is_64bit,
+        managed: None,
     })
 }
 
@@ -818,7 +1221,7 @@ fn parse_version_string(output: &str) -> Option<String> {
 }
 
 /// Parse version for comparison (returns major version number)
-fn parse_java_version(version: &str) -> u32 {
+pub fn parse_java_version(version: &str) -> u32 {
     // Handle both old format (1.8.0_xxx) and new format (11.0.x, 17.0.x)
     let parts: Vec<&str> = version.split('.').collect();
     if let Some(first) = parts.first() {
@@ -850,6 +1253,29 @@ pub fn get_recommended_java(required_major_version: Option<u64>) -> Option<JavaI
     }
 }
 
+/// Pick a Java for running an installer or game that needs at least `required_major`.
+///
+/// `preferred` (usually the configured Java path) wins if it is new enough;
+/// otherwise the oldest matching installation is used, since the newest Java
+/// can be too new for old tooling.
+pub fn find_suitable_java(
+    app_handle: &AppHandle,
+    required_major: u32,
+    preferred: &str,
+) -> Option<String> {
+    let mut suitable: Vec<(u32, String)> = detect_all_java_installations(app_handle)
+        .into_iter()
+        .map(|java| (parse_java_version(&java.version), java.path))
+        .filter(|(major, _)| *major >= required_major)
+        .collect();
+    suitable.sort_by_key(|(major, _)| *major);
+    suitable
+        .iter()
+        .find(|(_, path)| path == preferred)
+        .or(suitable.first())
+        .map(|(_, path)| path.clone())
+}
+
 /// Detect all installed Java versions (including system installations and DropOut downloads)
 pub fn detect_all_java_installations(app_handle: &AppHandle) -> Vec<JavaInstallation> {
     let mut installations = detect_java_installations();
@@ -864,10 +1290,11 @@ pub fn detect_all_java_installations(app_handle: &AppHandle) -> Vec<JavaInstalla
                     // Find the java executable in this directory
                     let java_bin = find_java_executable(&path);
                     if let Some(java_path) = java_bin {
-                        if let Some(java) = check_java_installation(&java_path) {
-                            if !installations.iter().any(|j| j.path == java.path) {
-                                installations.push(java);
-                            }
+                        if let Some(mut java) = check_java_installation(&java_path) {
+                            java.managed = JavaProvenance::load(&path);
+                            // Managed runtimes may also be on the PATH
+                            installations.retain(|j| j.path != java.path);
+                            installations.push(java);
                         }
                     }
                 }
@@ -945,6 +1372,7 @@ pub async fn resume_pending_downloads(app_handle: &AppHandle) -> Result<Vec<Java
         // Try to resume the download
         match download_and_install_java(
             app_handle,
+            pending.vendor,
             pending.major_version,
             image_type,
             Some(PathBuf::from(&pending.install_path)),
@@ -979,7 +1407,28 @@ pub fn get_pending_downloads(app_handle: &AppHandle) -> Vec<PendingJavaDownload>
 
 /// Clear a specific pending download
 pub fn clear_pending_download(app_handle: &AppHandle, major_version: u32, image_type: &str) -> Result<(), String> {
-    let mut queue = DownloadQueue::load(app_handle);
-    queue.remove(major_version, image_type);
-    queue.save(app_handle)
+    DownloadQueue::update(app_handle, |queue| queue.remove(major_version, image_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_dir_names() {
+        for vendor in JavaVendor::ALL {
+            let name = vendor.runtime_dir_name(21, ImageType::Jdk);
+            assert_eq!(parse_runtime_dir(&name), Some((vendor, 21, ImageType::Jdk)));
+        }
+        assert_eq!(parse_runtime_dir("temurin-21-jre.staging"), None);
+        assert_eq!(parse_runtime_dir("corretto-21-jre"), None);
+
+        assert_eq!(graalvm_tag_major("jdk-21.0.2"), Some(21));
+        assert_eq!(graalvm_tag_major("jdk-24"), Some(24));
+        assert_eq!(graalvm_tag_major("vm-22.3.1"), None);
+        assert_eq!(
+            archive_stem("zulu21.36.17-ca-jre21.0.4-linux_x64.tar.gz"),
+            "zulu21.36.17-ca-jre21.0.4-linux_x64"
+        );
+    }
 }
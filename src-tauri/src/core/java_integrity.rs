@@ -0,0 +1,211 @@
+//! Integrity checks for Java runtimes.
+//!
+//! Runtimes the launcher installs get a manifest next to the extracted
+//! release, `<vendor>-<major>-<jre|jdk>/.dropout-manifest.json`, holding the
+//! checksum of the archive they came from and the SHA1 of every file. A
+//! verification checks the layout of the Java home, runs `java -version`
+//! and, if there is a manifest, compares the files against it, which catches
+//! runtimes damaged by antivirus software or disk errors.
+
+use serde::{Deserialize, Serialize};
+use sha1::Digest;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::core::java::{ImageType, JavaVendor};
+
+const MANIFEST_FILE: &str = ".dropout-manifest.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RuntimeManifest {
+    /// Checksum of the downloaded archive, as published by Adoptium
+    archive_checksum: String,
+    /// SHA1 of each file, by path relative to the manifest's folder
+    files: BTreeMap<String, String>,
+}
+
+/// Outcome of `verify`.
+#[derive(Debug, Clone, Serialize)]
+pub struct JavaVerification {
+    pub path: String,
+    pub valid: bool,
+    /// Version reported by `java -version`
+    pub version: Option<String>,
+    pub problems: Vec<String>,
+    /// Whether the files were compared against an install manifest
+    pub manifest_checked: bool,
+    /// Whether the runtime was installed again because it was damaged
+    pub reinstalled: bool,
+}
+
+/// A runtime installed by the launcher into `java_dir`: its
+/// `<vendor>-<major>-<type>` folder, vendor, major version and image type.
+/// JDKs elsewhere are never managed, whatever their folder is called.
+pub fn managed_runtime(
+    java_dir: &Path,
+    java_path: &Path,
+) -> Option<(PathBuf, JavaVendor, u32, ImageType)> {
+    let name = java_path.strip_prefix(java_dir).ok()?.components().next()?;
+    let name = name.as_os_str().to_str()?;
+    let (vendor, major, image_type) = crate::core::java::parse_runtime_dir(name)?;
+    Some((java_dir.join(name), vendor, major, image_type))
+}
+
+/// Record the files of a freshly extracted runtime in `runtime_dir`.
+pub fn write_manifest(runtime_dir: &Path, archive_checksum: &str) -> Result<(), String> {
+    let manifest = RuntimeManifest {
+        archive_checksum: archive_checksum.to_string(),
+        files: hash_files(runtime_dir)?,
+    };
+    crate::utils::file_utils::write_json_atomic(&runtime_dir.join(MANIFEST_FILE), &manifest)
+        .map_err(|e| e.to_string())
+}
+
+/// Check the runtime whose `java` executable is `java_path`; runtimes
+/// installed into `java_dir` are also compared against their manifest.
+pub fn verify(java_dir: &Path, java_path: &Path) -> JavaVerification {
+    let mut problems = Vec::new();
+    let version = if java_path.is_file() {
+        let installation = crate::core::java::check_java_installation(&java_path.to_path_buf());
+        if installation.is_none() {
+            problems.push("`java -version` failed".to_string());
+        }
+        installation.map(|java| java.version)
+    } else {
+        problems.push(format!("{} does not exist", java_path.display()));
+        None
+    };
+
+    // <home>/bin/java, where `java` may be a link like /usr/bin/java
+    let resolved = fs::canonicalize(java_path).unwrap_or_else(|_| java_path.to_path_buf());
+    if let Some(home) = resolved.parent().and_then(Path::parent) {
+        for required in ["lib", "release"] {
+            if !home.join(required).exists() {
+                problems.push(format!("{} is missing", home.join(required).display()));
+            }
+        }
+    }
+
+    let manifest = managed_runtime(java_dir, java_path).and_then(|(dir, ..)| {
+        let manifest: RuntimeManifest =
+            crate::utils::file_utils::read_json(&dir.join(MANIFEST_FILE)).ok()?;
+        Some((dir, manifest))
+    });
+    let manifest_checked = manifest.is_some();
+    if let Some((dir, manifest)) = manifest {
+        match hash_files(&dir) {
+            Ok(actual) => problems.extend(compare(&manifest.files, &actual)),
+            Err(e) => problems.push(e),
+        }
+    }
+
+    JavaVerification {
+        path: java_path.to_string_lossy().to_string(),
+        valid: problems.is_empty(),
+        version,
+        problems,
+        manifest_checked,
+        reinstalled: false,
+    }
+}
+
+/// Differences between the recorded and actual file hashes. Files added
+/// since, e.g. by a JVM writing a cache, are not a problem.
+fn compare(expected: &BTreeMap<String, String>, actual: &BTreeMap<String, String>) -> Vec<String> {
+    expected
+        .iter()
+        .filter_map(|(path, hash)| match actual.get(path) {
+            None => Some(format!("{} is missing", path)),
+            Some(actual) if actual != hash => Some(format!("{} is damaged", path)),
+            Some(_) => None,
+        })
+        .collect()
+}
+
+/// SHA1 of every regular file below `root`, except the manifest.
+fn hash_files(root: &Path) -> Result<BTreeMap<String, String>, String> {
+    let mut files = BTreeMap::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let entries =
+            fs::read_dir(&dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                dirs.push(path);
+            } else if file_type.is_file() && path != root.join(MANIFEST_FILE) {
+                let relative = path
+                    .strip_prefix(root)
+                    .map_err(|e| e.to_string())?
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                files.insert(relative, hash_file(&path)?);
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn hash_file(path: &Path) -> Result<String, String> {
+    let mut file =
+        fs::File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut hasher = sha1::Sha1::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_detects_damage() {
+        let root = std::env::temp_dir().join(format!("dropout-java-{}", uuid::Uuid::new_v4()));
+        let runtime = root.join("temurin-21-jre");
+        let bin = runtime.join("jdk-21.0.4+7-jre").join("bin");
+        fs::create_dir_all(&bin).unwrap();
+        fs::write(bin.join("java"), b"java").unwrap();
+        fs::write(bin.join("keytool"), b"keytool").unwrap();
+        write_manifest(&runtime, "abc").unwrap();
+
+        let manifest: RuntimeManifest =
+            crate::utils::file_utils::read_json(&runtime.join(MANIFEST_FILE)).unwrap();
+        assert_eq!(manifest.files.len(), 2);
+        assert!(compare(&manifest.files, &hash_files(&runtime).unwrap()).is_empty());
+
+        fs::write(bin.join("java"), b"damaged").unwrap();
+        fs::remove_file(bin.join("keytool")).unwrap();
+        fs::write(bin.join("new"), b"new").unwrap();
+        let problems = compare(&manifest.files, &hash_files(&runtime).unwrap());
+        assert_eq!(
+            problems,
+            [
+                "jdk-21.0.4+7-jre/bin/java is damaged",
+                "jdk-21.0.4+7-jre/bin/keytool is missing"
+            ]
+        );
+
+        assert_eq!(
+            managed_runtime(&root, &bin.join("java")),
+            Some((runtime, JavaVendor::Adoptium, 21, ImageType::Jre))
+        );
+        // A system JDK in a folder with the same name is not the launcher's
+        let elsewhere = root.parent().unwrap().join("java");
+        assert_eq!(managed_runtime(&elsewhere, &bin.join("java")), None);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,166 @@
+//! Recommended memory and JVM flags for an instance.
+//!
+//! `suggest` sizes the heap by how heavily the instance is modded, keeps at
+//! least half of the computer's memory free and picks a `JvmPreset`: the
+//! Aikar-style G1 flags of `Performance` for large packs, `LowMemory` when
+//! there is little to spare. Nothing is applied; the suggestion maps onto
+//! the instance's `JvmOverrides`.
+
+use serde::Serialize;
+use std::path::Path;
+
+use crate::core::config::JvmPreset;
+use crate::core::launch::MAX_32BIT_HEAP_MB;
+
+/// Mod counts from which an instance counts as a large pack
+const LARGE_PACK_MODS: usize = 100;
+const HUGE_PACK_MODS: usize = 200;
+/// Computers with at most this much memory get the low memory preset
+const LOW_MEMORY_SYSTEM_MB: u64 = 4096;
+
+/// What `suggest` looks at.
+#[derive(Debug, Clone, Copy)]
+pub struct TuningInput {
+    pub mod_count: usize,
+    pub total_memory_mb: u64,
+    /// 0 when the Java could not be run
+    pub java_major: u32,
+    pub java_64bit: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JvmSuggestion {
+    pub min_memory: u32,
+    pub max_memory: u32,
+    pub preset: JvmPreset,
+    /// Flags the preset adds
+    pub args: Vec<String>,
+    pub mod_count: usize,
+    pub total_memory_mb: u64,
+    pub java_major: u32,
+    /// Why each value was chosen
+    pub reasons: Vec<String>,
+}
+
+/// Enabled mod jars in `mods_dir`.
+pub fn count_mods(mods_dir: &Path) -> usize {
+    std::fs::read_dir(mods_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| {
+                    entry
+                        .file_name()
+                        .to_string_lossy()
+                        .to_ascii_lowercase()
+                        .ends_with(".jar")
+                })
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+pub fn suggest(input: TuningInput) -> JvmSuggestion {
+    let mut reasons = Vec::new();
+    let (wanted, kind) = match input.mod_count {
+        0 => (2048, "a vanilla instance".to_string()),
+        n if n < LARGE_PACK_MODS => (4096, format!("{} mods", n)),
+        n if n < HUGE_PACK_MODS => (6144, format!("a large pack of {} mods", n)),
+        n => (8192, format!("a very large pack of {} mods", n)),
+    };
+    reasons.push(format!("{} MB suits {}", wanted, kind));
+
+    let mut max_memory: u32 = wanted;
+    if input.total_memory_mb > 0 {
+        // Half of the memory in 512 MB steps, and never below 1 GB
+        let half = (input.total_memory_mb / 2 / 512 * 512).max(1024);
+        let half = u32::try_from(half).unwrap_or(u32::MAX);
+        if half < max_memory {
+            max_memory = half;
+            reasons.push(format!(
+                "Lowered to {} MB to leave half of the {} MB of memory to the system",
+                max_memory, input.total_memory_mb
+            ));
+        }
+    }
+    if !input.java_64bit && max_memory > MAX_32BIT_HEAP_MB {
+        max_memory = MAX_32BIT_HEAP_MB;
+        reasons.push(format!(
+            "Lowered to {} MB because the selected Java is 32-bit",
+            max_memory
+        ));
+    }
+
+    let low_memory = input.total_memory_mb > 0 && input.total_memory_mb <= LOW_MEMORY_SYSTEM_MB;
+    let preset = if low_memory {
+        reasons.push("The low memory preset keeps overhead small on this computer".to_string());
+        JvmPreset::LowMemory
+    } else if input.mod_count >= LARGE_PACK_MODS || max_memory >= 6144 {
+        reasons.push("G1 tuned for short pauses keeps large packs from stuttering".to_string());
+        JvmPreset::Performance
+    } else {
+        JvmPreset::Default
+    };
+    if input.java_major == 0 {
+        reasons.push("The selected Java could not be checked".to_string());
+    } else if input.java_major < 17 && preset == JvmPreset::Performance {
+        reasons.push(format!(
+            "Java {} uses these G1 flags too, but Java 17 or newer collects faster",
+            input.java_major
+        ));
+    }
+
+    // A fixed heap saves resizing pauses, worth it when the pack needs most of it
+    let min_memory = if preset == JvmPreset::Performance {
+        reasons.push("Minimum equals maximum so the heap is never resized".to_string());
+        max_memory
+    } else {
+        max_memory.min(1024)
+    };
+
+    JvmSuggestion {
+        min_memory,
+        max_memory,
+        preset,
+        args: preset.args().iter().map(|arg| arg.to_string()).collect(),
+        mod_count: input.mod_count,
+        total_memory_mb: input.total_memory_mb,
+        java_major: input.java_major,
+        reasons,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(mod_count: usize, total_memory_mb: u64) -> TuningInput {
+        TuningInput {
+            mod_count,
+            total_memory_mb,
+            java_major: 21,
+            java_64bit: true,
+        }
+    }
+
+    #[test]
+    fn test_suggest() {
+        let vanilla = suggest(input(0, 16384));
+        assert_eq!((vanilla.min_memory, vanilla.max_memory), (1024, 2048));
+        assert_eq!(vanilla.preset, JvmPreset::Default);
+
+        let pack = suggest(input(250, 32768));
+        assert_eq!((pack.min_memory, pack.max_memory), (8192, 8192));
+        assert_eq!(pack.preset, JvmPreset::Performance);
+
+        let small = suggest(input(40, 4096));
+        assert_eq!(small.max_memory, 2048);
+        assert_eq!(small.preset, JvmPreset::LowMemory);
+
+        let java32 = suggest(TuningInput {
+            java_64bit: false,
+            ..input(40, 16384)
+        });
+        assert_eq!(java32.max_memory, MAX_32BIT_HEAP_MB);
+    }
+}
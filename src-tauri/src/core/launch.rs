@@ -0,0 +1,538 @@
+//! Launch command construction and locks against concurrent launches.
+//!
+//! `build_arguments` turns an installed version into the Java command line;
+//! `start_game` runs it and `export_launch_script` writes it to a script.
+//! `check_memory` runs first, since a heap the JVM cannot reserve makes the
+//! game crash before it writes a log.
+//!
+//! Preparing a launch writes version files and extracts natives under the
+//! version directory, so only one launch or install may prepare a given
+//! version at a time. An instance stays locked until its game exits, since
+//! two copies would share saves and options. Background work such as
+//! prefetching locks versions with `lock_version_background` and gives them
+//! up when a launch or install asks for the same version.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{watch, Notify};
+
+use crate::core::config::{LauncherConfig, LogLevel};
+use crate::core::error::LauncherError;
+use crate::core::game_version::{GameVersion, Rule};
+use crate::core::java::JavaInstallation;
+
+/// Largest heap a 32-bit JVM reliably reserves, in MB
+pub const MAX_32BIT_HEAP_MB: u32 = 1500;
+/// Below this the game tends to run out of memory while loading, in MB
+const LOW_HEAP_MB: u32 = 512;
+
+#[derive(Default)]
+pub struct LaunchLocks {
+    /// Held keys, with a channel asking background holders to give way
+    held: Arc<Mutex<HashMap<String, Option<watch::Sender<bool>>>>>,
+    released: Arc<Notify>,
+}
+
+impl LaunchLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lock the version `version_id` stored under `data_dir`, waiting for
+    /// background work on it to stop.
+    pub async fn lock_version(
+        &self,
+        data_dir: &Path,
+        version_id: &str,
+    ) -> Result<LaunchGuard, LauncherError> {
+        let key = version_key(data_dir, version_id);
+        loop {
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+            match self.held.lock().unwrap().get(&key) {
+                None => {}
+                Some(Some(give_way)) => {
+                    let _ = give_way.send(true);
+                }
+                Some(None) => {
+                    return Err(LauncherError::Busy(format!(
+                        "Version {} is already being prepared",
+                        version_id
+                    )))
+                }
+            }
+            if let Some(guard) = self.acquire(key.clone(), None) {
+                return Ok(guard);
+            }
+            released.await;
+        }
+    }
+
+    /// Lock a version for background work, which has to stop and drop the
+    /// guard once the receiver turns `true`. `None` if the version is busy.
+    pub fn lock_version_background(
+        &self,
+        data_dir: &Path,
+        version_id: &str,
+    ) -> Option<(LaunchGuard, watch::Receiver<bool>)> {
+        let (give_way, wanted) = watch::channel(false);
+        let guard = self.acquire(version_key(data_dir, version_id), Some(give_way))?;
+        Some((guard, wanted))
+    }
+
+    pub fn lock_instance(&self, instance_id: &str) -> Result<LaunchGuard, LauncherError> {
+        self.acquire(format!("instance:{}", instance_id), None)
+            .ok_or_else(|| {
+                LauncherError::Busy(format!(
+                    "Instance {} is already launching or running",
+                    instance_id
+                ))
+            })
+    }
+
+    /// Nothing is being launched, installed or played.
+    pub fn is_idle(&self) -> bool {
+        self.held.lock().unwrap().is_empty()
+    }
+
+    fn acquire(&self, key: String, give_way: Option<watch::Sender<bool>>) -> Option<LaunchGuard> {
+        let mut held = self.held.lock().unwrap();
+        if held.contains_key(&key) {
+            return None;
+        }
+        held.insert(key.clone(), give_way);
+        Some(LaunchGuard {
+            held: self.held.clone(),
+            released: self.released.clone(),
+            key,
+        })
+    }
+}
+
+fn version_key(data_dir: &Path, version_id: &str) -> String {
+    let dir = data_dir.join("versions").join(version_id);
+    format!("version:{}", dir.display())
+}
+
+/// Launcher log line for the end of the game process.
+pub fn exit_message(result: &std::io::Result<std::process::ExitStatus>) -> (LogLevel, String) {
+    match result {
+        Ok(status) => {
+            let level = if status.success() {
+                LogLevel::Info
+            } else {
+                LogLevel::Warn
+            };
+            (
+                level,
+                format!("Game process exited with status: {}", status),
+            )
+        }
+        Err(e) => (
+            LogLevel::Error,
+            format!("Error waiting for game process: {}", e),
+        ),
+    }
+}
+
+/// Releases its lock when dropped.
+pub struct LaunchGuard {
+    held: Arc<Mutex<HashMap<String, Option<watch::Sender<bool>>>>>,
+    released: Arc<Notify>,
+    key: String,
+}
+
+impl Drop for LaunchGuard {
+    fn drop(&mut self) {
+        self.held.lock().unwrap().remove(&self.key);
+        self.released.notify_waiters();
+    }
+}
+
+/// Player identity passed to the game.
+pub struct LaunchAuth {
+    pub username: String,
+    pub uuid: String,
+    pub access_token: String,
+}
+
+/// Resolved inputs of `build_arguments`.
+pub struct LaunchSpec<'a> {
+    pub version: &'a GameVersion,
+    pub version_id: &'a str,
+    /// Vanilla version providing the client jar
+    pub minecraft_version: &'a str,
+    /// Client jar on the classpath, patched if the instance has jar mods
+    pub client_jar: &'a Path,
+    /// Root holding `versions/`, `libraries/` and `assets/`
+    pub data_dir: &'a Path,
+    pub game_dir: &'a Path,
+    /// Assets directory as seen by the game, see `assets::game_assets_dir`
+    pub game_assets_dir: &'a Path,
+    pub config: &'a LauncherConfig,
+    /// Log4j configuration argument from `log4j::logging_setup`
+    pub logging_argument: Option<&'a str>,
+    pub auth: &'a LaunchAuth,
+}
+
+pub fn natives_dir(data_dir: &Path, version_id: &str) -> PathBuf {
+    data_dir.join("versions").join(version_id).join("natives")
+}
+
+pub fn client_jar_path(data_dir: &Path, minecraft_version: &str) -> PathBuf {
+    data_dir
+        .join("versions")
+        .join(minecraft_version)
+        .join(format!("{}.jar", minecraft_version))
+}
+
+/// Classpath entries: allowed libraries followed by the client jar.
+pub fn classpath(spec: &LaunchSpec) -> Vec<PathBuf> {
+    let libraries_dir = spec.data_dir.join("libraries");
+    let mut entries = Vec::new();
+    for lib in &spec.version.libraries {
+        if crate::core::rules::is_library_allowed(&lib.rules) {
+            if let Some(jar) = crate::core::maven::library_jar(lib, &libraries_dir, None) {
+                entries.push(jar.path);
+            }
+        }
+    }
+    entries.push(spec.client_jar.to_path_buf());
+    entries
+}
+
+/// Arguments after the Java executable: JVM options, main class and game
+/// arguments.
+pub fn build_arguments(spec: &LaunchSpec) -> Vec<String> {
+    let version = spec.version;
+    let config = spec.config;
+    let cp_separator = if cfg!(target_os = "windows") {
+        ";"
+    } else {
+        ":"
+    };
+    let classpath = classpath(spec)
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join(cp_separator);
+
+    let mut args = Vec::new();
+    let natives_path = natives_dir(spec.data_dir, spec.version_id)
+        .to_string_lossy()
+        .to_string();
+
+    // JVM arguments from version.json, for full compatibility
+    if let Some(args_obj) = &version.arguments {
+        if let Some(jvm_args) = &args_obj.jvm {
+            parse_jvm_arguments(jvm_args, &mut args, &natives_path, &classpath);
+        }
+    }
+
+    // Add memory settings (these override any defaults)
+    args.push(format!("-Xmx{}M", config.max_memory));
+    args.push(format!("-Xms{}M", config.min_memory));
+    args.extend(config.jvm_preset.args().iter().map(|arg| arg.to_string()));
+
+    // Route the game's own traffic (skins, servers list, realms) through the proxy
+    args.extend(config.proxy.jvm_args());
+
+    // Log4j mitigation: patched config from the version JSON, or lookups disabled
+    if let Some(argument) = spec.logging_argument {
+        args.push(argument.to_string());
+    } else if crate::core::log4j::needs_format_msg_no_lookups(spec.minecraft_version) {
+        args.push("-Dlog4j2.formatMsgNoLookups=true".to_string());
+    }
+
+    // Ensure natives path is set if not already in jvm args
+    if !args.iter().any(|a| a.contains("-Djava.library.path")) {
+        args.push(format!("-Djava.library.path={}", natives_path));
+    }
+
+    // Ensure classpath is set if not already
+    if !args.iter().any(|a| a == "-cp" || a == "-classpath") {
+        args.push("-cp".to_string());
+        args.push(classpath.clone());
+    }
+
+    args.push(version.main_class.clone());
+
+    let assets_dir = spec.data_dir.join("assets");
+    let asset_index_id = version
+        .asset_index
+        .as_ref()
+        .map(|index| index.id.clone())
+        .unwrap_or_default();
+    let mut replacements = HashMap::new();
+    replacements.insert("${auth_player_name}", spec.auth.username.clone());
+    replacements.insert("${version_name}", spec.version_id.to_string());
+    replacements.insert(
+        "${game_directory}",
+        spec.game_dir.to_string_lossy().to_string(),
+    );
+    replacements.insert("${assets_root}", assets_dir.to_string_lossy().to_string());
+    replacements.insert("${assets_index_name}", asset_index_id);
+    replacements.insert(
+        "${game_assets}",
+        spec.game_assets_dir.to_string_lossy().to_string(),
+    );
+    replacements.insert("${auth_uuid}", spec.auth.uuid.clone());
+    replacements.insert("${auth_access_token}", spec.auth.access_token.clone());
+    replacements.insert("${user_type}", "mojang".to_string());
+    replacements.insert("${version_type}", "release".to_string());
+    replacements.insert("${user_properties}", "{}".to_string()); // Correctly pass empty JSON object for user properties
+
+    if let Some(minecraft_arguments) = &version.minecraft_arguments {
+        // Legacy string
+        for part in minecraft_arguments.split_whitespace() {
+            let mut arg = part.to_string();
+            for (key, val) in &replacements {
+                arg = arg.replace(key, val);
+            }
+            args.push(arg);
+        }
+    } else if let Some(game_args) = version.arguments.as_ref().and_then(|a| a.game.as_ref()) {
+        // Can be array of strings or objects
+        for item in game_args.as_array().into_iter().flatten() {
+            if let Some(s) = item.as_str() {
+                let mut arg = s.to_string();
+                for (key, val) in &replacements {
+                    arg = arg.replace(key, val);
+                }
+                args.push(arg);
+            } else if let Some(obj) = item.as_object() {
+                // Rules that fail to parse allow the argument
+                let allow = match obj.get("rules") {
+                    Some(rules_val) => {
+                        match serde_json::from_value::<Vec<Rule>>(rules_val.clone()) {
+                            Ok(rules) => crate::core::rules::is_library_allowed(&Some(rules)),
+                            Err(_) => true,
+                        }
+                    }
+                    None => true,
+                };
+                if !allow {
+                    continue;
+                }
+                for s in argument_values(obj.get("value")) {
+                    let mut arg = s.to_string();
+                    for (key, replacement) in &replacements {
+                        arg = arg.replace(key, replacement);
+                    }
+                    // Skip arguments with unresolved placeholders
+                    if !has_unresolved_placeholder(&arg) {
+                        args.push(arg);
+                    }
+                }
+            }
+        }
+    }
+
+    args
+}
+
+/// String values of a conditional argument, which is a string or an array.
+fn argument_values(value: Option<&serde_json::Value>) -> Vec<&str> {
+    match value {
+        Some(serde_json::Value::String(s)) => vec![s.as_str()],
+        Some(serde_json::Value::Array(arr)) => arr.iter().filter_map(|v| v.as_str()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Parse JVM arguments from version.json
+fn parse_jvm_arguments(
+    jvm_args: &serde_json::Value,
+    args: &mut Vec<String>,
+    natives_path: &str,
+    classpath: &str,
+) {
+    let mut replacements = HashMap::new();
+    replacements.insert("${natives_directory}", natives_path.to_string());
+    replacements.insert("${classpath}", classpath.to_string());
+    replacements.insert("${launcher_name}", "DropOut".to_string());
+    replacements.insert("${launcher_version}", env!("CARGO_PKG_VERSION").to_string());
+
+    for item in jvm_args.as_array().into_iter().flatten() {
+        let values = if let Some(s) = item.as_str() {
+            // Simple string argument
+            vec![s]
+        } else if let Some(obj) = item.as_object() {
+            // Conditional argument with rules
+            let allow = match obj.get("rules") {
+                Some(rules_val) => match serde_json::from_value::<Vec<Rule>>(rules_val.clone()) {
+                    Ok(rules) => crate::core::rules::is_library_allowed(&Some(rules)),
+                    Err(_) => false,
+                },
+                None => true,
+            };
+            if !allow {
+                continue;
+            }
+            argument_values(obj.get("value"))
+        } else {
+            continue;
+        };
+
+        for s in values {
+            let mut arg = s.to_string();
+            for (key, val) in &replacements {
+                arg = arg.replace(key, val);
+            }
+            // Skip memory args as we set them explicitly
+            if !arg.starts_with("-Xmx") && !arg.starts_with("-Xms") {
+                args.push(arg);
+            }
+        }
+    }
+}
+
+/// Check if a string contains unresolved placeholders in the form ${...}
+///
+/// After the replacement phase, if a string still contains ${...}, it means
+/// that placeholder variable was not found in the replacements map and is
+/// therefore unresolved. We should skip adding such arguments to avoid
+/// passing malformed arguments to the game launcher.
+fn has_unresolved_placeholder(s: &str) -> bool {
+    // A `${` with or without a closing brace is unresolved or malformed
+    s.contains("${")
+}
+
+/// Check the memory settings against the configured Java and the
+/// machine's RAM. Returns warnings to log, or an error if the game would
+/// crash at start. A Java that does not run is left to fail at launch.
+pub fn check_memory(config: &LauncherConfig) -> Result<Vec<String>, LauncherError> {
+    let java_path = PathBuf::from(&config.java_path);
+    let Some(java) = crate::core::java::check_java_installation(&java_path) else {
+        return Ok(Vec::new());
+    };
+    let total_memory_mb = crate::core::setup::detect_system().total_memory_mb;
+    let (errors, warnings) = memory_problems(config, &java, total_memory_mb);
+    if !errors.is_empty() {
+        return Err(LauncherError::Invalid(errors.join(" ")));
+    }
+    Ok(warnings)
+}
+
+/// Errors and warnings for running `config`'s heap on `java`.
+fn memory_problems(
+    config: &LauncherConfig,
+    java: &JavaInstallation,
+    total_memory_mb: u64,
+) -> (Vec<String>, Vec<String>) {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    if !java.is_64bit && config.max_memory > MAX_32BIT_HEAP_MB {
+        errors.push(format!(
+            "Java {} at {} is 32-bit and cannot use {} MB of memory. \
+             Select a 64-bit Java or set the maximum memory to {} MB or less.",
+            java.version, java.path, config.max_memory, MAX_32BIT_HEAP_MB
+        ));
+    }
+    if config.max_memory < LOW_HEAP_MB {
+        warnings.push(format!(
+            "A maximum memory of {} MB is likely too little for the game to load",
+            config.max_memory
+        ));
+    }
+    if total_memory_mb > 0 && config.max_memory as u64 > total_memory_mb {
+        warnings.push(format!(
+            "The maximum memory of {} MB is more than the {} MB this computer has",
+            config.max_memory, total_memory_mb
+        ));
+    }
+    (errors, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_problems() {
+        let mut java = JavaInstallation {
+            path: "java".to_string(),
+            version: "1.8.0_51".to_string(),
+            is_64bit: true,
+            managed: None,
+        };
+        let mut config = LauncherConfig {
+            max_memory: 4096,
+            ..LauncherConfig::default()
+        };
+        assert_eq!(memory_problems(&config, &java, 16384), (vec![], vec![]));
+        assert_eq!(memory_problems(&config, &java, 2048).1.len(), 1);
+
+        java.is_64bit = false;
+        assert_eq!(memory_problems(&config, &java, 16384).0.len(), 1);
+        config.max_memory = MAX_32BIT_HEAP_MB;
+        assert_eq!(memory_problems(&config, &java, 16384), (vec![], vec![]));
+    }
+
+    #[tokio::test]
+    async fn test_lock_released_on_drop() {
+        let locks = LaunchLocks::new();
+        let dir = Path::new("/data");
+        let guard = locks.lock_version(dir, "1.20.4").await.unwrap();
+        assert!(locks.lock_version(dir, "1.20.4").await.is_err());
+        assert!(locks
+            .lock_version(Path::new("/other"), "1.20.4")
+            .await
+            .is_ok());
+        assert!(locks.lock_instance("1.20.4").is_ok());
+        drop(guard);
+        assert!(locks.lock_version(dir, "1.20.4").await.is_ok());
+    }
+
+    #[test]
+    fn test_exit_failure_logs_error() {
+        struct Recorder(Mutex<Vec<serde_json::Value>>);
+        impl crate::core::events::EventTarget for Recorder {
+            fn emit_event<E: crate::core::events::LauncherEvent>(&self, event: E) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(serde_json::to_value(&event).unwrap());
+            }
+        }
+
+        let recorder = Recorder(Mutex::new(Vec::new()));
+        let (level, message) = exit_message(&Err(std::io::Error::other("no child process")));
+        crate::core::events::log_at(&recorder, level, message);
+        let logged = recorder.0.lock().unwrap();
+        assert_eq!(logged[0]["level"], "error");
+        assert_eq!(
+            logged[0]["message"],
+            "Error waiting for game process: no child process"
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            let crashed = std::process::ExitStatus::from_raw(1 << 8);
+            assert_eq!(exit_message(&Ok(crashed)).0, LogLevel::Warn);
+            let clean = std::process::ExitStatus::from_raw(0);
+            assert_eq!(exit_message(&Ok(clean)).0, LogLevel::Info);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_background_lock_gives_way() {
+        let locks = Arc::new(LaunchLocks::new());
+        let dir = Path::new("/data");
+        let (guard, mut wanted) = locks.lock_version_background(dir, "1.20.4").unwrap();
+        assert!(locks.lock_version_background(dir, "1.20.4").is_none());
+
+        let background = tokio::spawn(async move {
+            wanted.wait_for(|wanted| *wanted).await.unwrap();
+            drop(guard);
+        });
+        let launch = locks.lock_version(dir, "1.20.4").await.unwrap();
+        background.await.unwrap();
+        assert!(locks.lock_version_background(dir, "1.20.4").is_none());
+        drop(launch);
+        assert!(locks.is_idle());
+    }
+}
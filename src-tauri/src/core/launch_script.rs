@@ -0,0 +1,236 @@
+//! Standalone launch scripts.
+//!
+//! Renders the command built by `launch::build_arguments` as a shell or batch
+//! script, for running an instance without the launcher. cmd.exe cuts lines
+//! at 8191 characters, so batch scripts keep the classpath off the command
+//! line: in an argument file for Java 9 and newer, in `CLASSPATH` otherwise.
+
+use std::path::{Path, PathBuf};
+
+/// Variable the script reads the access token from when it is not embedded.
+pub const TOKEN_VARIABLE: &str = "DROPOUT_ACCESS_TOKEN";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptKind {
+    Shell,
+    Batch,
+}
+
+impl ScriptKind {
+    /// Batch for `.bat`/`.cmd` paths, shell otherwise.
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("bat") || ext.eq_ignore_ascii_case("cmd") => {
+                Self::Batch
+            }
+            _ => Self::Shell,
+        }
+    }
+}
+
+/// First Java version reading arguments from `@file`s
+pub const ARGFILE_MIN_JAVA: u32 = 9;
+
+/// A rendered script and the argument file it reads, if any.
+pub struct Script {
+    pub content: String,
+    /// Contents for the `argfile` passed to `render`
+    pub argfile: Option<String>,
+}
+
+/// Argument file written next to `script`.
+pub fn argfile_path(script: &Path) -> PathBuf {
+    script.with_extension("args")
+}
+
+/// Render a script running `java` with `args` from `game_dir`.
+///
+/// Arguments equal to `hidden_token` are replaced by a reference to
+/// `TOKEN_VARIABLE`, which must then be set before running the script.
+/// Without it, any token in `args` ends up in the file. A batch script
+/// passes the classpath through `argfile` when given, which needs
+/// `ARGFILE_MIN_JAVA`, and through `CLASSPATH` otherwise.
+pub fn render(
+    kind: ScriptKind,
+    java: &str,
+    args: &[String],
+    game_dir: &Path,
+    hidden_token: Option<&str>,
+    argfile: Option<&Path>,
+) -> Script {
+    let game_dir = game_dir.to_string_lossy();
+    let mut argfile_content = None;
+    let embed_token = hidden_token.is_none();
+    let token_arg = |arg: &String| hidden_token == Some(arg.as_str());
+    let mut out = String::new();
+    match kind {
+        ScriptKind::Shell => {
+            out.push_str("#!/bin/sh\n# Generated by DropOut\n");
+            if embed_token {
+                out.push_str("# WARNING: contains your access token, do not share this file\n");
+            } else {
+                out.push_str(&format!(
+                    ": \"${{{0}:?Set {0} to your access token}}\"\n",
+                    TOKEN_VARIABLE
+                ));
+            }
+            out.push_str(&format!("cd {} || exit 1\n", shell_quote(&game_dir)));
+            out.push_str(&format!("exec {}", shell_quote(java)));
+            for arg in args {
+                out.push_str(" \\\n  ");
+                if token_arg(arg) {
+                    out.push_str(&format!("\"${}\"", TOKEN_VARIABLE));
+                } else {
+                    out.push_str(&shell_quote(arg));
+                }
+            }
+        }
+        ScriptKind::Batch => {
+            let mut args = args.to_vec();
+            let classpath = args
+                .iter()
+                .position(|a| a == "-cp" || a == "-classpath")
+                .filter(|i| i + 1 < args.len())
+                .map(|i| args.drain(i..i + 2).nth(1).unwrap_or_default());
+            out.push_str("@echo off\r\nrem Generated by DropOut\r\n");
+            if embed_token {
+                out.push_str("rem WARNING: contains your access token, do not share this file\r\n");
+            } else {
+                out.push_str(&format!(
+                    "if \"%{0}%\"==\"\" (\r\n  echo Set {0} to your access token\r\n  exit /b 1\r\n)\r\n",
+                    TOKEN_VARIABLE
+                ));
+            }
+            out.push_str(&format!(
+                "cd /d {} || exit /b 1\r\n",
+                batch_quote(&game_dir)
+            ));
+            match (classpath, argfile) {
+                (Some(classpath), Some(argfile)) => {
+                    argfile_content = Some(format!("-cp {}\r\n", argfile_quote(&classpath)));
+                    let reference = format!("@{}", argfile.to_string_lossy());
+                    args.insert(0, reference);
+                }
+                (Some(classpath), None) => {
+                    out.push_str(&format!(
+                        "set \"CLASSPATH={}\"\r\n",
+                        classpath.replace('%', "%%")
+                    ));
+                }
+                (None, _) => {}
+            }
+            out.push_str(&batch_quote(java));
+            for arg in &args {
+                out.push_str(" ^\r\n  ");
+                if token_arg(arg) {
+                    out.push_str(&format!("\"%{}%\"", TOKEN_VARIABLE));
+                } else {
+                    out.push_str(&batch_quote(arg));
+                }
+            }
+            out.push('\r');
+        }
+    }
+    out.push('\n');
+    Script {
+        content: out,
+        argfile: argfile_content,
+    }
+}
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Quote for cmd.exe; `%` is doubled so it is not expanded.
+fn batch_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('%', "%%").replace('"', "\"\""))
+}
+
+/// Quote for a Java argument file, where `\` escapes inside quotes.
+fn argfile_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_shell_hides_token() {
+        let args = vec![
+            "-Xmx2048M".to_string(),
+            "--accessToken".to_string(),
+            "secret".to_string(),
+            "--username".to_string(),
+            "it's me".to_string(),
+        ];
+        let script = render(
+            ScriptKind::Shell,
+            "/usr/bin/java",
+            &args,
+            Path::new("/games/mc"),
+            Some("secret"),
+            None,
+        )
+        .content;
+        assert!(!script.contains("secret"));
+        assert!(script.contains("\"$DROPOUT_ACCESS_TOKEN\""));
+        assert!(script.contains("cd '/games/mc' || exit 1"));
+        assert!(script.contains("'it'\\''s me'"));
+
+        let script = render(
+            ScriptKind::Shell,
+            "/usr/bin/java",
+            &args,
+            Path::new("/games/mc"),
+            None,
+            None,
+        )
+        .content;
+        assert!(script.contains("'secret'"));
+        assert!(script.contains("WARNING"));
+    }
+
+    #[test]
+    fn test_render_batch_keeps_classpath_off_the_command_line() {
+        let classpath = format!("{};C:\\mc\\client.jar", "C:\\mc\\lib.jar;".repeat(500));
+        let args = vec![
+            "-Xmx2G".to_string(),
+            "-cp".to_string(),
+            classpath.clone(),
+            "net.minecraft.client.main.Main".to_string(),
+        ];
+        let game_dir = Path::new("C:\\mc");
+        let argfile = Path::new("C:\\scripts\\run.args");
+        let script = render(
+            ScriptKind::Batch,
+            "java.exe",
+            &args,
+            game_dir,
+            None,
+            Some(argfile),
+        );
+        assert!(script.content.lines().all(|line| line.len() < 8191));
+        assert!(script.content.contains("\"@C:\\scripts\\run.args\""));
+        let content = script.argfile.unwrap();
+        assert!(content.starts_with("-cp \"C:\\\\mc\\\\lib.jar;"));
+
+        let script = render(ScriptKind::Batch, "java.exe", &args, game_dir, None, None);
+        assert!(script.argfile.is_none());
+        assert!(script
+            .content
+            .contains(&format!("set \"CLASSPATH={}\"", classpath)));
+        assert!(!script.content.contains("-cp"));
+    }
+
+    #[test]
+    fn test_script_kind_for_path() {
+        assert_eq!(
+            ScriptKind::for_path(Path::new("run.BAT")),
+            ScriptKind::Batch
+        );
+        assert_eq!(ScriptKind::for_path(Path::new("run.sh")), ScriptKind::Shell);
+        assert_eq!(ScriptKind::for_path(Path::new("run")), ScriptKind::Shell);
+    }
+}
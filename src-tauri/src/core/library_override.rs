@@ -0,0 +1,173 @@
+//! Per-instance library overrides.
+//!
+//! An override swaps the version of a library the version JSON asks for,
+//! e.g. a newer LWJGL with ARM fixes or a pinned Mixin, or removes the
+//! library. `apply` rewrites the resolved version's library list before
+//! launching, so download tasks and the classpath both use the replacement.
+//! Replaced libraries lose their `downloads` entry and are fetched by their
+//! Maven coordinate like mod loader libraries, see `maven::library_jar`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::game_version::GameVersion;
+use crate::core::maven::MavenCoordinate;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryOverride {
+    /// `group:artifact` of the libraries to change, or
+    /// `group:artifact:classifier` for one classifier only
+    pub library: String,
+    /// Version to use instead; `None` removes the library
+    pub version: Option<String>,
+    /// Maven repository hosting the new version
+    #[serde(default)]
+    pub url: Option<String>,
+    /// SHA-1 of the new jar; fetched from the repository if unset
+    #[serde(default)]
+    pub sha1: Option<String>,
+}
+
+impl LibraryOverride {
+    fn matches(&self, coord: &MavenCoordinate) -> bool {
+        let mut parts = self.library.split(':');
+        parts.next() == Some(coord.group.as_str())
+            && parts.next() == Some(coord.artifact.as_str())
+            && match parts.next() {
+                Some(classifier) => coord.classifier.as_deref() == Some(classifier),
+                None => true,
+            }
+    }
+}
+
+/// Check overrides before they are saved.
+pub fn validate(overrides: &[LibraryOverride]) -> Result<(), String> {
+    for o in overrides {
+        let parts: Vec<&str> = o.library.split(':').collect();
+        if !(2..=3).contains(&parts.len()) || parts.iter().any(|part| part.trim().is_empty()) {
+            return Err(format!(
+                "Invalid library {:?}; expected group:artifact[:classifier]",
+                o.library
+            ));
+        }
+        if let Some(version) = &o.version {
+            if version.trim().is_empty() || version.contains([':', '/', '\\', '@']) {
+                return Err(format!("Invalid version {:?} for {}", version, o.library));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Apply `overrides` to `version`'s libraries. Returns a line per change and
+/// per override that matched nothing.
+pub fn apply(version: &mut GameVersion, overrides: &[LibraryOverride]) -> Vec<String> {
+    let mut notes = Vec::new();
+    let mut used = vec![false; overrides.len()];
+    version.libraries.retain_mut(|lib| {
+        let Some(coord) = MavenCoordinate::parse(&lib.name) else {
+            return true;
+        };
+        let Some(i) = overrides.iter().position(|o| o.matches(&coord)) else {
+            return true;
+        };
+        used[i] = true;
+        let o = &overrides[i];
+        let Some(version) = &o.version else {
+            notes.push(format!("Removed library {}", lib.name));
+            return false;
+        };
+        if lib.natives.is_some() {
+            // Old-style native classifiers only exist for the original version
+            notes.push(format!(
+                "Kept {}: libraries with native classifiers cannot be overridden",
+                lib.name
+            ));
+            return true;
+        }
+        let mut name = format!("{}:{}:{}", coord.group, coord.artifact, version);
+        if let Some(classifier) = &coord.classifier {
+            name = format!("{}:{}", name, classifier);
+        }
+        if coord.extension != "jar" {
+            name = format!("{}@{}", name, coord.extension);
+        }
+        notes.push(format!("Replaced library {} with {}", lib.name, name));
+        lib.name = name;
+        lib.downloads = None;
+        lib.url = o.url.clone().or(lib.url.take());
+        lib.sha1 = o.sha1.clone();
+        true
+    });
+    for (o, used) in overrides.iter().zip(used) {
+        if !used {
+            notes.push(format!("Library override {} matched no library", o.library));
+        }
+    }
+    notes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let mut version: GameVersion = serde_json::from_value(serde_json::json!({
+            "id": "1.20.4",
+            "mainClass": "Main",
+            "libraries": [
+                {
+                    "name": "org.lwjgl:lwjgl:3.3.1",
+                    "downloads": { "artifact": { "url": "https://example.com/lwjgl.jar" } }
+                },
+                { "name": "org.lwjgl:lwjgl:3.3.1:natives-linux-arm64" },
+                { "name": "net.fabricmc:sponge-mixin:0.12.5" },
+                { "name": "com.example:unwanted:1.0" }
+            ],
+        }))
+        .unwrap();
+        let overrides = vec![
+            LibraryOverride {
+                library: "org.lwjgl:lwjgl".to_string(),
+                version: Some("3.3.3".to_string()),
+                url: None,
+                sha1: None,
+            },
+            LibraryOverride {
+                library: "com.example:unwanted".to_string(),
+                version: None,
+                url: None,
+                sha1: None,
+            },
+            LibraryOverride {
+                library: "org.ow2.asm:asm".to_string(),
+                version: Some("9.7".to_string()),
+                url: None,
+                sha1: None,
+            },
+        ];
+        assert!(validate(&overrides).is_ok());
+
+        let notes = apply(&mut version, &overrides);
+        let names: Vec<&str> = version.libraries.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "org.lwjgl:lwjgl:3.3.3",
+                "org.lwjgl:lwjgl:3.3.3:natives-linux-arm64",
+                "net.fabricmc:sponge-mixin:0.12.5",
+            ]
+        );
+        assert!(version.libraries[0].downloads.is_none());
+        assert_eq!(notes.len(), 4);
+        assert!(notes[3].contains("org.ow2.asm:asm"));
+
+        let invalid = LibraryOverride {
+            library: "org.lwjgl".to_string(),
+            version: Some("3.3.3".to_string()),
+            url: None,
+            sha1: None,
+        };
+        assert!(validate(&[invalid]).is_err());
+    }
+}
@@ -0,0 +1,182 @@
+//! LiteLoader installation.
+//!
+//! LiteLoader (Minecraft 1.5.2 to 1.12.2) is no longer served by a live
+//! API, so like OptiFine it is installed from an installer jar supplied by
+//! the user. The installer's `install_profile.json` embeds the version JSON
+//! (`versionInfo`) and names the LiteLoader jar inside it; the jar is stored
+//! as a library and the version JSON written as a version inheriting from
+//! vanilla, launched through LaunchWrapper with the LiteLoader tweaker.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::core::maven::MavenCoordinate;
+
+const TWEAK_CLASS: &str = "com.mumfrey.liteloader.launch.LiteLoaderTweaker";
+
+#[derive(Debug, Deserialize)]
+struct InstallProfile {
+    install: InstallInfo,
+    #[serde(rename = "versionInfo")]
+    version_info: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct InstallInfo {
+    /// Maven coordinate of the LiteLoader jar
+    path: String,
+    /// Name of the LiteLoader jar inside the installer
+    #[serde(rename = "filePath")]
+    file_path: String,
+    /// Minecraft version the release is for
+    minecraft: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct InstalledLiteLoaderVersion {
+    pub id: String,
+    pub minecraft_version: String,
+    pub path: PathBuf,
+}
+
+/// Minecraft version a LiteLoader installer is for.
+pub fn inspect_installer(installer: &Path) -> Result<String, String> {
+    Ok(read_profile(installer)?.install.minecraft)
+}
+
+fn read_profile(installer: &Path) -> Result<InstallProfile, String> {
+    let file = fs::File::open(installer).map_err(|e| format!("{}: {}", installer.display(), e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("{}: {}", installer.display(), e))?;
+    let entry = archive
+        .by_name("install_profile.json")
+        .map_err(|_| format!("{} is not a LiteLoader installer", installer.display()))?;
+    let profile: InstallProfile = serde_json::from_reader(entry)
+        .map_err(|e| format!("Invalid LiteLoader install profile: {}", e))?;
+    if !profile.install.path.starts_with("com.mumfrey:liteloader:") {
+        return Err(format!(
+            "{} is not a LiteLoader installer",
+            installer.display()
+        ));
+    }
+    Ok(profile)
+}
+
+/// Install LiteLoader from `installer` into `game_dir`.
+///
+/// The vanilla version must already be installed, since legacy versions
+/// replace rather than extend their parent's `minecraftArguments`.
+pub fn install_liteloader(
+    game_dir: &Path,
+    installer: &Path,
+) -> Result<InstalledLiteLoaderVersion, String> {
+    let profile = read_profile(installer)?;
+    let mc = &profile.install.minecraft;
+    let parent_json = game_dir
+        .join("versions")
+        .join(mc)
+        .join(format!("{}.json", mc));
+    let parent: serde_json::Value = fs::read_to_string(&parent_json)
+        .map_err(|_| {
+            format!(
+                "Minecraft {} must be installed before adding LiteLoader",
+                mc
+            )
+        })
+        .and_then(|c| serde_json::from_str(&c).map_err(|e| e.to_string()))?;
+
+    let coord = MavenCoordinate::parse(&profile.install.path)
+        .ok_or_else(|| format!("Invalid LiteLoader library: {}", profile.install.path))?;
+    let lib_path = coord.to_local_path(&game_dir.join("libraries"));
+    let file = fs::File::open(installer).map_err(|e| e.to_string())?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
+    let mut jar = Vec::new();
+    archive
+        .by_name(&profile.install.file_path)
+        .map_err(|_| format!("Installer is missing {}", profile.install.file_path))?
+        .read_to_end(&mut jar)
+        .map_err(|e| e.to_string())?;
+    if let Some(parent) = lib_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::write(&lib_path, jar).map_err(|e| e.to_string())?;
+
+    let version_json = create_version_json(profile.version_info, mc, &parent)?;
+    let version_id = version_json["id"]
+        .as_str()
+        .ok_or("LiteLoader install profile has no version id")?
+        .to_string();
+    let version_dir = game_dir.join("versions").join(&version_id);
+    fs::create_dir_all(&version_dir).map_err(|e| e.to_string())?;
+    let json_path = version_dir.join(format!("{}.json", version_id));
+    let content = serde_json::to_string_pretty(&version_json).map_err(|e| e.to_string())?;
+    fs::write(&json_path, content).map_err(|e| e.to_string())?;
+
+    Ok(InstalledLiteLoaderVersion {
+        id: version_id,
+        minecraft_version: mc.clone(),
+        path: json_path,
+    })
+}
+
+/// The installer's version JSON, inheriting from `minecraft_version` and
+/// passing the tweaker. Profiles that leave out the game arguments get a copy
+/// of the parent's.
+fn create_version_json(
+    mut json: serde_json::Value,
+    minecraft_version: &str,
+    parent: &serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let object = json
+        .as_object_mut()
+        .ok_or("LiteLoader install profile has no version")?;
+    object.insert("inheritsFrom".to_string(), minecraft_version.into());
+    object.insert("jar".to_string(), minecraft_version.into());
+    let args = object
+        .get("minecraftArguments")
+        .and_then(|a| a.as_str())
+        .or_else(|| parent["minecraftArguments"].as_str())
+        .unwrap_or_default()
+        .to_string();
+    let args = if args.contains(TWEAK_CLASS) {
+        args
+    } else {
+        format!("{} --tweakClass {}", args, TWEAK_CLASS)
+            .trim_start()
+            .to_string()
+    };
+    object.insert("minecraftArguments".to_string(), args.into());
+    Ok(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_json_adds_tweaker() {
+        let version_info = serde_json::json!({
+            "id": "1.12.2-LiteLoader1.12.2",
+            "mainClass": "net.minecraft.launchwrapper.Launch",
+            "libraries": [{ "name": "com.mumfrey:liteloader:1.12.2-SNAPSHOT" }],
+        });
+        let parent = serde_json::json!({ "minecraftArguments": "--username ${auth_player_name}" });
+        let json = create_version_json(version_info, "1.12.2", &parent).unwrap();
+        assert_eq!(json["inheritsFrom"], "1.12.2");
+        assert_eq!(
+            json["minecraftArguments"],
+            "--username ${auth_player_name} --tweakClass \
+             com.mumfrey.liteloader.launch.LiteLoaderTweaker"
+        );
+
+        // Arguments already carrying the tweaker are kept as they are
+        let json = create_version_json(json.clone(), "1.12.2", &parent).unwrap();
+        assert_eq!(
+            json["minecraftArguments"],
+            "--username ${auth_player_name} --tweakClass \
+             com.mumfrey.liteloader.launch.LiteLoaderTweaker"
+        );
+    }
+}
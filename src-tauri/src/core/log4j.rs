@@ -0,0 +1,74 @@
+//! Log4j (CVE-2021-44228) mitigation for older Minecraft versions.
+//!
+//! Mojang patched the version JSONs of 1.7 through 1.18.1 to ship a safe
+//! log4j XML configuration under `logging.client`, which the official launcher
+//! downloads and passes via `-Dlog4j.configurationFile`. For the log4j 2.10+
+//! versions (1.17 - 1.18.1) `-Dlog4j2.formatMsgNoLookups=true` is used as a
+//! fallback when a version JSON lacks the logging block (e.g. stale local copies).
+
+use std::path::{Path, PathBuf};
+
+use crate::core::downloader::DownloadTask;
+use crate::core::game_version::GameVersion;
+
+/// Resolved logging configuration for a launch.
+pub struct LoggingSetup {
+    /// Download task for the XML config file
+    pub task: DownloadTask,
+    /// JVM argument with `${path}` already substituted
+    pub jvm_argument: String,
+}
+
+/// Build the logging config download task and JVM argument for a version.
+///
+/// # Arguments
+/// * `assets_dir` - The `assets` directory; configs are stored in `assets/log_configs/`
+/// * `version` - The resolved version JSON
+///
+/// # Returns
+/// `None` if the version does not declare a client logging config.
+pub fn logging_setup(assets_dir: &Path, version: &GameVersion) -> Option<LoggingSetup> {
+    let config = version.logging.as_ref()?.client.as_ref()?;
+    let path = config_path(assets_dir, &config.file.id);
+
+    Some(LoggingSetup {
+        task: DownloadTask {
+            url: config.file.url.clone(),
+            path: path.clone(),
+            sha1: config.file.sha1.clone(),
+            sha256: None,
+            size: config.file.size,
+            mirrors: Vec::new(),
+        },
+        jvm_argument: config
+            .argument
+            .replace("${path}", &path.to_string_lossy()),
+    })
+}
+
+/// Local path of a logging config file.
+pub fn config_path(assets_dir: &Path, file_id: &str) -> PathBuf {
+    assets_dir.join("log_configs").join(file_id)
+}
+
+/// Check whether `-Dlog4j2.formatMsgNoLookups=true` is effective for a version.
+///
+/// Only log4j 2.10+ honours the property, which Minecraft bundles from 1.17
+/// up to 1.18.1 (1.18.2 and later ship a fixed log4j).
+pub fn needs_format_msg_no_lookups(minecraft_version: &str) -> bool {
+    matches!(minecraft_version, "1.17" | "1.17.1" | "1.18" | "1.18.1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_needs_format_msg_no_lookups() {
+        assert!(needs_format_msg_no_lookups("1.17"));
+        assert!(needs_format_msg_no_lookups("1.18.1"));
+        assert!(!needs_format_msg_no_lookups("1.18.2"));
+        assert!(!needs_format_msg_no_lookups("1.16.5"));
+        assert!(!needs_format_msg_no_lookups("1.20.4"));
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::error::Error;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::core::game_version::GameVersion;
 
@@ -25,14 +25,58 @@ pub struct Version {
     pub time: String,
     #[serde(rename = "releaseTime")]
     pub release_time: String,
+    /// SHA-1 of the version JSON at `url`
+    #[serde(default)]
+    pub sha1: Option<String>,
+    /// 1 if the version supports the current player safety features
+    #[serde(rename = "complianceLevel", default)]
+    pub compliance_level: Option<u32>,
+}
+
+/// Shown for versions with compliance level 0, as in the vanilla launcher.
+pub fn compliance_warning(compliance_level: Option<u32>) -> Option<&'static str> {
+    (compliance_level == Some(0)).then_some(
+        "This version does not support the latest player safety features, such as \
+         reporting chat messages. Play it only with people you trust.",
+    )
+}
+
+/// Fail unless `data`, the JSON of version `id`, has the manifest's hash.
+fn verify_version_json(id: &str, data: &[u8], sha1: Option<&str>) -> Result<(), String> {
+    let Some(expected) = sha1 else {
+        return Ok(());
+    };
+    let actual = crate::core::downloader::compute_sha1(data);
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(format!(
+            "Version JSON of {} does not match the manifest (expected SHA-1 {}, got {})",
+            id, expected, actual
+        ));
+    }
+    Ok(())
 }
 
 pub async fn fetch_version_manifest() -> Result<VersionManifest, Box<dyn Error + Send + Sync>> {
     let url = "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
-    let resp = reqwest::get(url).await?.json::<VersionManifest>().await?;
+    let resp: VersionManifest =
+        crate::core::http_cache::get_json(url, crate::core::http_cache::TTL_SHORT).await?;
     Ok(resp)
 }
 
+/// All release types known to the version manifest.
+pub const VERSION_TYPES: [&str; 4] = ["release", "snapshot", "old_beta", "old_alpha"];
+
+impl VersionManifest {
+    /// Keep only versions whose type is in `types` (e.g. "release", "snapshot").
+    ///
+    /// The `latest` entry is left untouched so callers can still mark the
+    /// recommended release/snapshot even when snapshots are hidden.
+    pub fn retain_types(&mut self, types: &[String]) {
+        self.versions
+            .retain(|v| types.iter().any(|t| t == &v.type_));
+    }
+}
+
 /// Load a version JSON from the local versions directory.
 ///
 /// This is used for loading both vanilla and modded versions that have been
@@ -45,7 +89,7 @@ pub async fn fetch_version_manifest() -> Result<VersionManifest, Box<dyn Error +
 /// # Returns
 /// The parsed `GameVersion` if found, or an error if not found.
 pub async fn load_local_version(
-    game_dir: &PathBuf,
+    game_dir: &Path,
     version_id: &str,
 ) -> Result<GameVersion, Box<dyn Error + Send + Sync>> {
     let json_path = game_dir
@@ -81,13 +125,13 @@ pub async fn fetch_vanilla_version(
         .find(|v| v.id == version_id)
         .ok_or_else(|| format!("Version {} not found in manifest", version_id))?;
 
-    // Fetch the actual version JSON
-    let resp = reqwest::get(&version_entry.url)
+    // Fetch the actual version JSON, checked against the manifest
+    let data = crate::core::http::get(&version_entry.url)
         .await?
-        .json::<GameVersion>()
+        .bytes()
         .await?;
-
-    Ok(resp)
+    verify_version_json(version_id, &data, version_entry.sha1.as_deref())?;
+    Ok(serde_json::from_slice(&data)?)
 }
 
 /// Load a version, checking local first, then fetching from remote if needed.
@@ -159,7 +203,7 @@ pub async fn save_local_version(
 /// # Returns
 /// A list of version IDs found in the versions directory.
 pub async fn list_local_versions(
-    game_dir: &PathBuf,
+    game_dir: &Path,
 ) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
     let versions_dir = game_dir.join("versions");
     let mut versions = Vec::new();
@@ -181,3 +225,218 @@ pub async fn list_local_versions(
 
     Ok(versions)
 }
+
+/// Mod loader a locally installed version is based on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoaderType {
+    Vanilla,
+    Fabric,
+    Quilt,
+    Forge,
+    NeoForge,
+}
+
+impl LoaderType {
+    /// Detect the loader from the version id and its library list.
+    pub fn detect(version: &GameVersion) -> Self {
+        let has_lib = |prefix: &str| version.libraries.iter().any(|l| l.name.starts_with(prefix));
+
+        if version.id.starts_with("quilt-loader-") || has_lib("org.quiltmc:quilt-loader") {
+            LoaderType::Quilt
+        } else if version.id.starts_with("fabric-loader-") || has_lib("net.fabricmc:fabric-loader") {
+            LoaderType::Fabric
+        } else if version.id.contains("neoforge") || has_lib("net.neoforged") {
+            LoaderType::NeoForge
+        } else if version.id.contains("-forge")
+            || has_lib("net.minecraftforge:forge")
+            || has_lib("net.minecraftforge:fmlloader")
+        {
+            LoaderType::Forge
+        } else {
+            LoaderType::Vanilla
+        }
+    }
+}
+
+/// Details about a locally installed version.
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalVersionInfo {
+    pub id: String,
+    pub loader: LoaderType,
+    pub inherits_from: Option<String>,
+    /// Release type from the version JSON (release, snapshot, ...)
+    pub version_type: Option<String>,
+    /// Size of `versions/<id>/` in bytes
+    pub size_on_disk: u64,
+    /// Whether the (parent) client jar is present
+    pub client_jar_present: bool,
+    /// Whether the asset index and every asset object it lists are present
+    pub assets_complete: bool,
+}
+
+/// Inspect a locally installed version without touching the network.
+///
+/// # Arguments
+/// * `game_dir` - The .minecraft directory path
+/// * `version_id` - The version ID to inspect
+pub async fn inspect_local_version(
+    game_dir: &Path,
+    version_id: &str,
+) -> Result<LocalVersionInfo, Box<dyn Error + Send + Sync>> {
+    let version = load_local_version(game_dir, version_id).await?;
+    let loader = LoaderType::detect(&version);
+    let inherits_from = version.inherits_from.clone();
+    let version_type = version.version_type.clone();
+    let jar_version = version.client_jar_version().to_string();
+
+    // Resolve the inheritance chain from local files only
+    let mut resolved = version;
+    let mut chain_complete = true;
+    while let Some(parent_id) = resolved.inherits_from.clone() {
+        match load_local_version(game_dir, &parent_id).await {
+            Ok(parent) => resolved = crate::core::version_merge::merge_versions(resolved, parent),
+            Err(_) => {
+                chain_complete = false;
+                break;
+            }
+        }
+    }
+
+    let game_dir = game_dir.to_path_buf();
+    let version_dir = game_dir.join("versions").join(version_id);
+    let index_id = resolved
+        .asset_index
+        .map(|index| index.id)
+        .filter(|_| chain_complete);
+    // Stats every asset object and walks the version folder
+    let (client_jar_present, assets_complete, size_on_disk) =
+        tokio::task::spawn_blocking(move || {
+            let client_jar_present = game_dir
+                .join("versions")
+                .join(&jar_version)
+                .join(format!("{}.jar", jar_version))
+                .exists();
+            let assets_complete = index_id.is_some_and(|id| assets_present(&game_dir, &id));
+            let size_on_disk = crate::utils::file_utils::dir_size(&version_dir);
+            (client_jar_present, assets_complete, size_on_disk)
+        })
+        .await?;
+
+    Ok(LocalVersionInfo {
+        id: version_id.to_string(),
+        loader,
+        inherits_from,
+        version_type,
+        size_on_disk,
+        client_jar_present,
+        assets_complete,
+    })
+}
+
+/// Check that an asset index and all objects it references exist locally.
+fn assets_present(game_dir: &Path, index_id: &str) -> bool {
+    let assets_dir = game_dir.join("assets");
+    let index_path = assets_dir.join("indexes").join(format!("{}.json", index_id));
+    let Ok(content) = std::fs::read_to_string(&index_path) else {
+        return false;
+    };
+    let Ok(index) = serde_json::from_str::<crate::core::assets::AssetIndexJson>(&content) else {
+        return false;
+    };
+
+    let objects_dir = assets_dir.join("objects");
+    index.objects.values().all(|object| {
+        object.hash.len() >= 2
+            && objects_dir
+                .join(&object.hash[0..2])
+                .join(&object.hash)
+                .exists()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(id: &str, type_: &str) -> Version {
+        Version {
+            id: id.to_string(),
+            type_: type_.to_string(),
+            url: String::new(),
+            time: String::new(),
+            release_time: String::new(),
+            sha1: None,
+            compliance_level: None,
+        }
+    }
+
+    fn local_version(id: &str, libraries: &[&str]) -> GameVersion {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "mainClass": "Main",
+            "libraries": libraries.iter().map(|name| serde_json::json!({ "name": name })).collect::<Vec<_>>(),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_detect_loader() {
+        assert_eq!(LoaderType::detect(&local_version("1.20.4", &[])), LoaderType::Vanilla);
+        assert_eq!(
+            LoaderType::detect(&local_version("fabric-loader-0.15.6-1.20.4", &[])),
+            LoaderType::Fabric
+        );
+        assert_eq!(
+            LoaderType::detect(&local_version("my-pack", &["org.quiltmc:quilt-loader:0.23.0"])),
+            LoaderType::Quilt
+        );
+        assert_eq!(
+            LoaderType::detect(&local_version("1.20.4-forge-49.0.38", &[])),
+            LoaderType::Forge
+        );
+    }
+
+    #[test]
+    fn test_retain_types() {
+        let mut manifest = VersionManifest {
+            latest: Latest {
+                release: "1.20.4".to_string(),
+                snapshot: "24w03a".to_string(),
+            },
+            versions: vec![
+                version("24w03a", "snapshot"),
+                version("1.20.4", "release"),
+                version("b1.7.3", "old_beta"),
+            ],
+        };
+
+        manifest.retain_types(&["release".to_string(), "old_beta".to_string()]);
+
+        let ids: Vec<&str> = manifest.versions.iter().map(|v| v.id.as_str()).collect();
+        assert_eq!(ids, vec!["1.20.4", "b1.7.3"]);
+        assert_eq!(manifest.latest.snapshot, "24w03a");
+    }
+
+    #[test]
+    fn test_manifest_v2_fields() {
+        let entry: Version = serde_json::from_value(serde_json::json!({
+            "id": "1.12.2",
+            "type": "release",
+            "url": "https://piston-meta.mojang.com/v1/packages/0f2/1.12.2.json",
+            "time": "2024-01-01T00:00:00+00:00",
+            "releaseTime": "2017-09-18T08:39:46+00:00",
+            "sha1": "a9f2a5af7f5d0fd2e3d7f8e2ba2e2a5e0bd5f1c4",
+            "complianceLevel": 0
+        }))
+        .unwrap();
+        assert!(compliance_warning(entry.compliance_level).is_some());
+        assert!(compliance_warning(Some(1)).is_none());
+
+        let data = br#"{"id": "1.12.2"}"#;
+        let sha1 = crate::core::downloader::compute_sha1(data);
+        assert!(verify_version_json("1.12.2", data, Some(&sha1.to_uppercase())).is_ok());
+        assert!(verify_version_json("1.12.2", data, entry.sha1.as_deref()).is_err());
+        assert!(verify_version_json("1.12.2", data, None).is_ok());
+    }
+}
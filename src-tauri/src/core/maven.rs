@@ -5,12 +5,19 @@
 //! This module provides utilities to parse these coordinates and construct
 //! download URLs for various Maven repositories.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use futures::StreamExt;
+
+use crate::core::downloader::DownloadTask;
+use crate::core::game_version::Library;
 
 /// Known Maven repository URLs for mod loaders
 pub const MAVEN_CENTRAL: &str = "https://repo1.maven.org/maven2/";
 pub const FABRIC_MAVEN: &str = "https://maven.fabricmc.net/";
 pub const FORGE_MAVEN: &str = "https://maven.minecraftforge.net/";
+pub const NEOFORGE_MAVEN: &str = "https://maven.neoforged.net/releases/";
+pub const QUILT_MAVEN: &str = "https://maven.quiltmc.org/repository/release/";
 pub const MOJANG_LIBRARIES: &str = "https://libraries.minecraft.net/";
 
 /// Represents a parsed Maven coordinate.
@@ -88,16 +95,22 @@ impl MavenCoordinate {
     /// assert_eq!(coord.to_path(), "net/fabricmc/fabric-loader/0.14.21/fabric-loader-0.14.21.jar");
     /// ```
     pub fn to_path(&self) -> String {
+        self.path_with_file_version(&self.version)
+    }
+
+    /// Like `to_path`, with `file_version` in the file name. Snapshots are
+    /// stored under `1.0-SNAPSHOT/` as e.g. `artifact-1.0-20240101.120000-3.jar`.
+    fn path_with_file_version(&self, file_version: &str) -> String {
         let group_path = self.group.replace('.', "/");
         let filename = match &self.classifier {
             Some(classifier) => {
                 format!(
                     "{}-{}-{}.{}",
-                    self.artifact, self.version, classifier, self.extension
+                    self.artifact, file_version, classifier, self.extension
                 )
             }
             None => {
-                format!("{}-{}.{}", self.artifact, self.version, self.extension)
+                format!("{}-{}.{}", self.artifact, file_version, self.extension)
             }
         };
 
@@ -107,6 +120,21 @@ impl MavenCoordinate {
         )
     }
 
+    pub fn is_snapshot(&self) -> bool {
+        self.version.ends_with("-SNAPSHOT")
+    }
+
+    /// URL of the `maven-metadata.xml` describing this version's snapshots.
+    pub fn metadata_url(&self, base_url: &str) -> String {
+        format!(
+            "{}/{}/{}/{}/maven-metadata.xml",
+            base_url.trim_end_matches('/'),
+            self.group.replace('.', "/"),
+            self.artifact,
+            self.version
+        )
+    }
+
     /// Get the local file path for storing this artifact.
     ///
     /// # Arguments
@@ -114,7 +142,7 @@ impl MavenCoordinate {
     ///
     /// # Returns
     /// The full path where the library should be stored
-    pub fn to_local_path(&self, libraries_dir: &PathBuf) -> PathBuf {
+    pub fn to_local_path(&self, libraries_dir: &Path) -> PathBuf {
         let rel_path = self.to_path();
         libraries_dir.join(rel_path.replace('/', std::path::MAIN_SEPARATOR_STR))
     }
@@ -132,6 +160,41 @@ impl MavenCoordinate {
     }
 }
 
+/// Repositories that may host `coord`, most likely first: `preferred`, the
+/// repository of the mod loader owning the group, Mojang's, Maven Central.
+pub fn candidate_repositories<'a>(
+    coord: &MavenCoordinate,
+    preferred: Option<&'a str>,
+) -> Vec<&'a str> {
+    let group = coord.group.as_str();
+    let guessed = if group.starts_with("net.fabricmc") {
+        Some(FABRIC_MAVEN)
+    } else if group.starts_with("org.quiltmc") {
+        Some(QUILT_MAVEN)
+    } else if group.starts_with("net.neoforged") {
+        Some(NEOFORGE_MAVEN)
+    } else if group.starts_with("net.minecraftforge") || group.starts_with("cpw.mods") {
+        Some(FORGE_MAVEN)
+    } else {
+        None
+    };
+
+    let mut repositories: Vec<&str> = Vec::new();
+    let candidates = [
+        preferred,
+        guessed,
+        Some(MOJANG_LIBRARIES),
+        Some(MAVEN_CENTRAL),
+    ];
+    for repository in candidates.into_iter().flatten() {
+        let base = repository.trim_end_matches('/');
+        if !repositories.iter().any(|r| r.trim_end_matches('/') == base) {
+            repositories.push(repository);
+        }
+    }
+    repositories
+}
+
 /// Resolve the download URL for a library.
 ///
 /// This function handles both:
@@ -144,7 +207,7 @@ impl MavenCoordinate {
 /// * `maven_url` - A custom Maven repository URL from the library JSON
 ///
 /// # Returns
-/// The resolved download URL
+/// The resolved download URL, in the first of `candidate_repositories`
 pub fn resolve_library_url(
     name: &str,
     explicit_url: Option<&str>,
@@ -155,23 +218,8 @@ pub fn resolve_library_url(
         return Some(url.to_string());
     }
 
-    // Parse the Maven coordinate
     let coord = MavenCoordinate::parse(name)?;
-
-    // Determine the base Maven URL
-    let base_url = maven_url.unwrap_or_else(|| {
-        // Guess the repository based on group
-        if coord.group.starts_with("net.fabricmc") {
-            FABRIC_MAVEN
-        } else if coord.group.starts_with("net.minecraftforge")
-            || coord.group.starts_with("cpw.mods")
-        {
-            FORGE_MAVEN
-        } else {
-            MOJANG_LIBRARIES
-        }
-    });
-
+    let base_url = candidate_repositories(&coord, maven_url)[0];
     Some(coord.to_url(base_url))
 }
 
@@ -183,15 +231,318 @@ pub fn resolve_library_url(
 ///
 /// # Returns
 /// The path where the library should be stored
-pub fn get_library_path(name: &str, libraries_dir: &PathBuf) -> Option<PathBuf> {
+pub fn get_library_path(name: &str, libraries_dir: &Path) -> Option<PathBuf> {
     let coord = MavenCoordinate::parse(name)?;
     Some(coord.to_local_path(libraries_dir))
 }
 
+/// The main jar of a library.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibraryJar {
+    pub path: PathBuf,
+    /// Download URL, if one is known
+    pub url: Option<String>,
+    pub sha1: Option<String>,
+    pub size: Option<u64>,
+    /// Coordinate of jars resolved from Maven, see `locate_jars`
+    pub coordinate: Option<MavenCoordinate>,
+    /// Repositories to try, most likely first
+    pub repositories: Vec<String>,
+}
+
+/// Resolve the main jar of `lib`.
+///
+/// Used for both downloading and the classpath, so everything downloaded is
+/// also loaded. An explicit `downloads.artifact` wins, falling back to the
+/// Maven coordinate for its path; libraries with only native classifiers
+/// have no main jar. Without `downloads` (mod loader libraries) the jar is
+/// fetched from `lib.url`, then `repository`, then a repository guessed from
+/// the group.
+pub fn library_jar(
+    lib: &Library,
+    libraries_dir: &Path,
+    repository: Option<&str>,
+) -> Option<LibraryJar> {
+    match &lib.downloads {
+        Some(downloads) => match &downloads.artifact {
+            Some(artifact) => Some(LibraryJar {
+                path: match &artifact.path {
+                    Some(path) => libraries_dir.join(path),
+                    None => get_library_path(&lib.name, libraries_dir)?,
+                },
+                url: Some(artifact.url.clone()),
+                sha1: artifact.sha1.clone(),
+                size: artifact.size,
+                coordinate: None,
+                repositories: Vec::new(),
+            }),
+            None if downloads.classifiers.is_some() => None,
+            None => maven_jar(lib, libraries_dir, repository),
+        },
+        None => maven_jar(lib, libraries_dir, repository),
+    }
+}
+
+fn maven_jar(lib: &Library, libraries_dir: &Path, repository: Option<&str>) -> Option<LibraryJar> {
+    let coord = MavenCoordinate::parse(&lib.name)?;
+    let preferred = lib.url.as_deref().or(repository);
+    let repositories: Vec<String> = candidate_repositories(&coord, preferred)
+        .into_iter()
+        .map(String::from)
+        .collect();
+    Some(LibraryJar {
+        path: coord.to_local_path(libraries_dir),
+        url: resolve_library_url(&lib.name, None, preferred),
+        // Often missing; see `fill_missing_sha1`
+        sha1: lib.sha1.clone(),
+        size: None,
+        coordinate: Some(coord),
+        repositories,
+    })
+}
+
+/// For Maven jars not downloaded yet, switch `url` to the first repository
+/// that has the jar, resolving snapshot versions to their timestamped file.
+/// Jars found nowhere keep `url`, so the download reports the error.
+pub async fn locate_jars(jars: &mut [LibraryJar]) {
+    let pending: Vec<(usize, MavenCoordinate, Vec<String>)> = jars
+        .iter()
+        .enumerate()
+        .filter(|(_, jar)| !jar.path.exists())
+        .filter_map(|(i, jar)| {
+            let coord = jar.coordinate.clone()?;
+            (jar.repositories.len() > 1 || coord.is_snapshot())
+                .then(|| (i, coord, jar.repositories.clone()))
+        })
+        .collect();
+    let found: Vec<(usize, Option<String>)> = futures::stream::iter(pending)
+        .map(|(i, coord, repositories)| async move {
+            for base in repositories {
+                let url = if coord.is_snapshot() {
+                    resolve_snapshot_url(&coord, &base).await
+                } else {
+                    coord.to_url(&base)
+                };
+                if crate::core::http::exists(&url).await {
+                    return (i, Some(url));
+                }
+            }
+            (i, None)
+        })
+        .buffer_unordered(8)
+        .collect()
+        .await;
+    for (i, url) in found {
+        if url.is_some() {
+            jars[i].url = url;
+        }
+    }
+}
+
+/// URL of a snapshot artifact in `base_url`.
+///
+/// Repositories usually store only timestamped snapshot files, named in the
+/// version's `maven-metadata.xml`. Falls back to the plain `-SNAPSHOT` name
+/// when the metadata is missing.
+pub async fn resolve_snapshot_url(coord: &MavenCoordinate, base_url: &str) -> String {
+    let metadata = match crate::core::http::get(&coord.metadata_url(base_url)).await {
+        Ok(response) if response.status().is_success() => response.text().await.ok(),
+        _ => None,
+    };
+    let file_version = metadata.and_then(|xml| snapshot_file_version(coord, &xml));
+    match file_version {
+        Some(version) => format!(
+            "{}/{}",
+            base_url.trim_end_matches('/'),
+            coord.path_with_file_version(&version)
+        ),
+        None => coord.to_url(base_url),
+    }
+}
+
+/// Timestamped version of `coord`'s file from snapshot metadata.
+///
+/// Prefers the `<snapshotVersion>` entry for the exact classifier and
+/// extension, then the `<snapshot>` timestamp and build number.
+fn snapshot_file_version(coord: &MavenCoordinate, xml: &str) -> Option<String> {
+    for entry in xml_elements(xml, "snapshotVersion") {
+        let classifier = xml_elements(entry, "classifier").next();
+        if classifier == coord.classifier.as_deref()
+            && xml_elements(entry, "extension").next() == Some(coord.extension.as_str())
+        {
+            if let Some(value) = xml_elements(entry, "value").next() {
+                return Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let snapshot = xml_elements(xml, "snapshot").next()?;
+    if xml_elements(snapshot, "localCopy").next() == Some("true") {
+        return None;
+    }
+    let timestamp = xml_elements(snapshot, "timestamp").next()?.trim();
+    let build = xml_elements(snapshot, "buildNumber").next()?.trim();
+    let base = coord.version.strip_suffix("-SNAPSHOT")?;
+    Some(format!("{}-{}-{}", base, timestamp, build))
+}
+
+/// Contents of each `<tag>...</tag>` in `xml`. Enough for Maven metadata,
+/// which has no attributes, CDATA or nested elements of the same name.
+fn xml_elements<'a>(xml: &'a str, tag: &str) -> impl Iterator<Item = &'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut rest = xml;
+    std::iter::from_fn(move || {
+        let start = rest.find(&open)? + open.len();
+        let end = start + rest[start..].find(&close)?;
+        let content = &rest[start..end];
+        rest = &rest[end + close.len()..];
+        Some(content)
+    })
+}
+
+/// Fetch the `.sha1` file Maven repositories publish next to `url`.
+pub async fn fetch_sha1(url: &str) -> Option<String> {
+    let response = crate::core::http::get(&format!("{}.sha1", url))
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    parse_sha1_file(&response.text().await.ok()?)
+}
+
+/// The hash from a `.sha1` file, which may be followed by a file name.
+fn parse_sha1_file(content: &str) -> Option<String> {
+    let hash = content.split_whitespace().next()?.to_ascii_lowercase();
+    (hash.len() == 40 && hash.bytes().all(|b| b.is_ascii_hexdigit())).then_some(hash)
+}
+
+/// Where a fetched `.sha1` is kept next to the artifact at `path`, as in a
+/// local Maven repository.
+pub fn sha1_sidecar(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".sha1");
+    PathBuf::from(name)
+}
+
+/// Look up `.sha1` files for tasks without any hash, i.e. Maven libraries
+/// whose JSON gave none, so corrupt downloads are caught. Tasks whose
+/// repository publishes no hash stay unverified.
+///
+/// Fetched hashes are cached in `sha1_sidecar` files, so only artifacts that
+/// are neither downloaded nor cached yet cost a request.
+pub async fn fill_missing_sha1(tasks: &mut [DownloadTask]) {
+    let mut missing = Vec::new();
+    for (i, task) in tasks.iter_mut().enumerate() {
+        if task.sha1.is_some() || task.sha256.is_some() {
+            continue;
+        }
+        let sidecar = sha1_sidecar(&task.path);
+        if let Ok(content) = tokio::fs::read_to_string(&sidecar).await {
+            task.sha1 = parse_sha1_file(&content);
+        } else if !task.path.exists() {
+            missing.push((i, task.url.clone(), sidecar));
+        }
+    }
+    let hashes: Vec<(usize, Option<String>)> = futures::stream::iter(missing)
+        .map(|(i, url, sidecar)| async move {
+            let hash = fetch_sha1(&url).await;
+            if let Some(hash) = &hash {
+                if let Some(parent) = sidecar.parent() {
+                    let _ = tokio::fs::create_dir_all(parent).await;
+                }
+                let _ = tokio::fs::write(&sidecar, hash).await;
+            }
+            (i, hash)
+        })
+        .buffer_unordered(8)
+        .collect()
+        .await;
+    for (i, hash) in hashes {
+        tasks[i].sha1 = hash;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn library(json: serde_json::Value) -> Library {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_parse_sha1_file() {
+        let hash = "2fd4e1c67a2d28fced849ee1bb76e7391b93eb12";
+        assert_eq!(parse_sha1_file(hash).as_deref(), Some(hash));
+        assert_eq!(
+            parse_sha1_file(&format!("{}  asm-9.6.jar\n", hash.to_uppercase())).as_deref(),
+            Some(hash)
+        );
+        assert_eq!(parse_sha1_file("<html>Not Found</html>"), None);
+    }
+
+    #[tokio::test]
+    async fn test_fill_missing_sha1_uses_local_files() {
+        let dir = std::env::temp_dir().join(format!("dropout-maven-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let hash = "2fd4e1c67a2d28fced849ee1bb76e7391b93eb12";
+        let task = |name: &str| DownloadTask {
+            // Unreachable, so any request would leave the hash empty
+            url: format!("http://127.0.0.1:9/{}", name),
+            path: dir.join(name),
+            sha1: None,
+            sha256: None,
+            size: None,
+            mirrors: Vec::new(),
+        };
+        std::fs::write(dir.join("cached.jar"), "jar").unwrap();
+        std::fs::write(sha1_sidecar(&dir.join("cached.jar")), hash).unwrap();
+        std::fs::write(dir.join("present.jar"), "jar").unwrap();
+
+        let mut tasks = vec![task("cached.jar"), task("present.jar")];
+        fill_missing_sha1(&mut tasks).await;
+        assert_eq!(tasks[0].sha1.as_deref(), Some(hash));
+        assert_eq!(tasks[1].sha1, None);
+        assert!(!sha1_sidecar(&dir.join("present.jar")).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_library_jar() {
+        let dir = Path::new("/libs");
+        // Artifact without a path is located by its coordinate
+        let lib = library(serde_json::json!({
+            "name": "net.fabricmc:intermediary:1.20.4",
+            "downloads": { "artifact": { "url": "https://maven.fabricmc.net/x.jar" } }
+        }));
+        let jar = library_jar(&lib, dir, None).unwrap();
+        assert_eq!(
+            jar.path,
+            dir.join("net/fabricmc/intermediary/1.20.4/intermediary-1.20.4.jar")
+        );
+        assert_eq!(jar.url.as_deref(), Some("https://maven.fabricmc.net/x.jar"));
+
+        let lib = library(serde_json::json!({
+            "name": "org.ow2.asm:asm:9.6",
+            "url": "https://maven.fabricmc.net/"
+        }));
+        let jar = library_jar(&lib, dir, Some("https://mirror.example/")).unwrap();
+        assert_eq!(jar.path, dir.join("org/ow2/asm/asm/9.6/asm-9.6.jar"));
+        assert_eq!(
+            jar.url.as_deref(),
+            Some("https://maven.fabricmc.net/org/ow2/asm/asm/9.6/asm-9.6.jar")
+        );
+
+        // Natives-only entries have no main jar
+        let lib = library(serde_json::json!({
+            "name": "org.lwjgl.lwjgl:lwjgl-platform:2.9.4",
+            "downloads": { "classifiers": {} }
+        }));
+        assert_eq!(library_jar(&lib, dir, None), None);
+    }
+
     #[test]
     fn test_parse_simple_coordinate() {
         let coord = MavenCoordinate::parse("net.fabricmc:fabric-loader:0.14.21").unwrap();
@@ -245,6 +596,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_snapshot_file_version() {
+        let xml = r#"<metadata>
+  <groupId>org.example</groupId>
+  <artifactId>lib</artifactId>
+  <version>1.2-SNAPSHOT</version>
+  <versioning>
+    <snapshot>
+      <timestamp>20240101.120000</timestamp>
+      <buildNumber>3</buildNumber>
+    </snapshot>
+    <snapshotVersions>
+      <snapshotVersion>
+        <classifier>sources</classifier>
+        <extension>jar</extension>
+        <value>1.2-20240101.115500-2</value>
+      </snapshotVersion>
+      <snapshotVersion>
+        <extension>jar</extension>
+        <value>1.2-20240101.120000-3</value>
+      </snapshotVersion>
+    </snapshotVersions>
+  </versioning>
+</metadata>"#;
+        let coord = MavenCoordinate::parse("org.example:lib:1.2-SNAPSHOT").unwrap();
+        assert!(coord.is_snapshot());
+        assert_eq!(
+            snapshot_file_version(&coord, xml).as_deref(),
+            Some("1.2-20240101.120000-3")
+        );
+        assert_eq!(
+            coord.path_with_file_version("1.2-20240101.120000-3"),
+            "org/example/lib/1.2-SNAPSHOT/lib-1.2-20240101.120000-3.jar"
+        );
+
+        let sources = MavenCoordinate::parse("org.example:lib:1.2-SNAPSHOT:sources").unwrap();
+        assert_eq!(
+            snapshot_file_version(&sources, xml).as_deref(),
+            Some("1.2-20240101.115500-2")
+        );
+
+        // Older metadata only has the <snapshot> element
+        let xml = "<snapshot><timestamp>20230505.101010</timestamp>\
+                   <buildNumber>7</buildNumber></snapshot>";
+        assert_eq!(
+            snapshot_file_version(&coord, xml).as_deref(),
+            Some("1.2-20230505.101010-7")
+        );
+    }
+
     #[test]
     fn test_resolve_library_url_explicit() {
         let url = resolve_library_url(
@@ -260,4 +661,28 @@ mod tests {
         let url = resolve_library_url("net.fabricmc:fabric-loader:0.14.21", None, None);
         assert!(url.unwrap().starts_with(FABRIC_MAVEN));
     }
+
+    #[test]
+    fn test_candidate_repositories() {
+        let coord = MavenCoordinate::parse("net.neoforged:neoforge:20.4.80").unwrap();
+        assert_eq!(
+            candidate_repositories(&coord, Some("https://maven.neoforged.net/releases")),
+            vec![
+                "https://maven.neoforged.net/releases",
+                MOJANG_LIBRARIES,
+                MAVEN_CENTRAL
+            ]
+        );
+        let coord = MavenCoordinate::parse("org.ow2.asm:asm:9.6").unwrap();
+        assert_eq!(
+            candidate_repositories(&coord, None),
+            vec![MOJANG_LIBRARIES, MAVEN_CENTRAL]
+        );
+    }
+
+    #[test]
+    fn test_candidate_repositories_fabric() {
+        let coord = MavenCoordinate::parse("net.fabricmc:fabric-loader:0.14.21").unwrap();
+        assert_eq!(candidate_repositories(&coord, None)[0], FABRIC_MAVEN);
+    }
 }
@@ -0,0 +1,158 @@
+//! Metered connection awareness.
+//!
+//! Large downloads (game files, Java runtimes, modpack updates) check
+//! `check` first. Depending on `MeteredPolicy` it fails with
+//! `LauncherError::MeteredDownload` when more than `metered_prompt_mb` would
+//! be fetched, and the UI asks the user before retrying the command with
+//! `allow_metered`. Whether the connection is metered comes from
+//! NetworkManager on Linux and the connection cost of the internet profile
+//! on Windows; elsewhere it is unknown and treated as unmetered. Background
+//! prefetching stops on metered connections regardless of size.
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::config::LauncherConfig;
+use crate::core::downloader::DownloadTask;
+use crate::core::error::LauncherError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionCost {
+    Unmetered,
+    Metered,
+    Unknown,
+}
+
+/// When to ask before a large download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MeteredPolicy {
+    Never,
+    #[default]
+    WhenMetered,
+    Always,
+}
+
+/// Cost of the current internet connection, as the OS reports it.
+pub fn detect() -> ConnectionCost {
+    probe()
+}
+
+#[cfg(target_os = "linux")]
+fn probe() -> ConnectionCost {
+    // NMMetered of the primary connection, e.g. "u 4"
+    let output = std::process::Command::new("busctl")
+        .args([
+            "--system",
+            "get-property",
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            "org.freedesktop.NetworkManager",
+            "Metered",
+        ])
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_nm_metered(&String::from_utf8_lossy(&output.stdout))
+        }
+        _ => ConnectionCost::Unknown,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn probe() -> ConnectionCost {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+    const COST_QUERY: &str = "[Windows.Networking.Connectivity.NetworkInformation,\
+        Windows.Networking.Connectivity,ContentType=WindowsRuntime]::\
+        GetInternetConnectionProfile().GetConnectionCost().NetworkCostType";
+
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-Command", COST_QUERY])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            match String::from_utf8_lossy(&output.stdout).trim() {
+                "Unrestricted" => ConnectionCost::Unmetered,
+                "Fixed" | "Variable" => ConnectionCost::Metered,
+                _ => ConnectionCost::Unknown,
+            }
+        }
+        _ => ConnectionCost::Unknown,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn probe() -> ConnectionCost {
+    ConnectionCost::Unknown
+}
+
+/// `busctl` output for NetworkManager's `Metered` property; "guess" values
+/// count as what they guess.
+#[cfg(any(target_os = "linux", test))]
+fn parse_nm_metered(output: &str) -> ConnectionCost {
+    match output.trim().strip_prefix("u ") {
+        Some("1") | Some("3") => ConnectionCost::Metered,
+        Some("2") | Some("4") => ConnectionCost::Unmetered,
+        _ => ConnectionCost::Unknown,
+    }
+}
+
+/// Bytes of `tasks` not on disk yet; files of unknown size count as empty.
+pub fn missing_bytes(tasks: &[DownloadTask]) -> u64 {
+    tasks
+        .iter()
+        .filter(|task| !task.path.exists())
+        .filter_map(|task| task.size)
+        .sum()
+}
+
+/// Whether prefetching in the background should wait for another connection.
+pub fn avoid_background_downloads(config: &LauncherConfig) -> bool {
+    config.metered_policy != MeteredPolicy::Never && detect() == ConnectionCost::Metered
+}
+
+/// Fail with `MeteredDownload` if downloading `bytes` for `what` needs the
+/// user's consent first.
+pub fn check(config: &LauncherConfig, bytes: u64, what: &str) -> Result<(), LauncherError> {
+    let size_mb = bytes / 1024 / 1024;
+    if size_mb <= u64::from(config.metered_prompt_mb) {
+        return Ok(());
+    }
+    let ask = match config.metered_policy {
+        MeteredPolicy::Never => false,
+        MeteredPolicy::WhenMetered => detect() == ConnectionCost::Metered,
+        MeteredPolicy::Always => true,
+    };
+    if ask {
+        return Err(LauncherError::MeteredDownload {
+            what: what.to_string(),
+            size_mb,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        assert_eq!(parse_nm_metered("u 3\n"), ConnectionCost::Metered);
+        assert_eq!(parse_nm_metered("u 4\n"), ConnectionCost::Unmetered);
+        assert_eq!(parse_nm_metered("u 0\n"), ConnectionCost::Unknown);
+
+        let mut config = LauncherConfig {
+            metered_policy: MeteredPolicy::Always,
+            metered_prompt_mb: 100,
+            ..Default::default()
+        };
+        assert!(check(&config, 50 * 1024 * 1024, "Assets").is_ok());
+        let error = check(&config, 300 * 1024 * 1024, "Assets").unwrap_err();
+        assert_eq!(error.code(), "metered_download");
+        config.metered_policy = MeteredPolicy::Never;
+        assert!(check(&config, 300 * 1024 * 1024, "Assets").is_ok());
+    }
+}
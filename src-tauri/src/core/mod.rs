@@ -1,12 +1,68 @@
 pub mod account_storage;
+pub mod assets;
 pub mod auth;
+pub mod avatar;
+pub mod changelog;
+pub mod cleanup;
+pub mod cloud_sync;
 pub mod config;
+pub mod console_window;
+pub mod custom_version;
+pub mod deeplink;
+pub mod diagnostics;
+pub mod display;
+pub mod download_manager;
+pub mod download_stats;
+pub mod dropped_file;
 pub mod downloader;
+pub mod error;
+pub mod events;
+pub mod extract;
 pub mod fabric;
+pub mod file_index;
+pub mod folders;
 pub mod forge;
+pub mod game_log;
+pub mod game_metrics;
 pub mod game_version;
+pub mod gc_log;
+pub mod gpu;
+pub mod http;
+pub mod http_cache;
+pub mod icons;
+pub mod import;
+pub mod instance;
+pub mod instance_archive;
+pub mod instance_backup;
+pub mod instance_diff;
+pub mod instance_lock;
+pub mod instance_template;
+pub mod instance_watcher;
+pub mod jar_mods;
 pub mod java;
+pub mod java_integrity;
+pub mod jvm_tuning;
+pub mod launch;
+pub mod launch_script;
+pub mod library_override;
+pub mod liteloader;
+pub mod log4j;
+pub mod logging;
 pub mod manifest;
 pub mod maven;
+pub mod metered;
+pub mod mod_store;
+pub mod modpack;
+pub mod multi_source;
+pub mod natives;
+pub mod news;
+pub mod optifine;
+pub mod prefetch;
+pub mod redact;
 pub mod rules;
+pub mod server;
+pub mod setup;
+pub mod stats;
+pub mod trash;
 pub mod version_merge;
+pub mod worlds;
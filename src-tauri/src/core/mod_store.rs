@@ -0,0 +1,304 @@
+//! Content-addressed storage for instance mods.
+//!
+//! With `LauncherConfig::mod_store` on, mod files are kept once in
+//! `mod_store/`, named by their SHA-1 like asset objects, and each instance's
+//! `mods/` holds a reflink (copy-on-write clone) or hard link of the stored
+//! file, so a pack installed twice takes the space of one. Filesystems that
+//! support neither get plain copies. A hard-linked mod shares its contents
+//! with the store, so writers must replace mod files rather than truncate
+//! them; `download_verified` deletes the old file first. Entries no instance
+//! uses any more are removed by `cleanup::collect_garbage`.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::core::downloader::compute_sha1;
+
+/// Folder in the data directory holding stored mods
+pub const STORE_DIR: &str = "mod_store";
+
+/// How a file was put in place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Placement {
+    Reflink,
+    HardLink,
+    Copy,
+}
+
+/// Result of `store_files`.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct DedupeReport {
+    pub files: usize,
+    pub reflinked: usize,
+    pub hard_linked: usize,
+    /// Files left as separate copies, since the filesystem cannot share them
+    pub copied: usize,
+    /// Size of the files now sharing their data with the store
+    pub shared_bytes: u64,
+    pub errors: Vec<String>,
+}
+
+impl DedupeReport {
+    fn count(&mut self, placement: Placement, size: u64) {
+        match placement {
+            Placement::Reflink => self.reflinked += 1,
+            Placement::HardLink => self.hard_linked += 1,
+            Placement::Copy => {
+                self.copied += 1;
+                return;
+            }
+        }
+        self.shared_bytes += size;
+    }
+
+    pub fn merge(&mut self, other: DedupeReport) {
+        self.files += other.files;
+        self.reflinked += other.reflinked;
+        self.hard_linked += other.hard_linked;
+        self.copied += other.copied;
+        self.shared_bytes += other.shared_bytes;
+        self.errors.extend(other.errors);
+    }
+}
+
+/// Path of the stored file with hash `sha1`.
+pub fn stored_path(store_dir: &Path, sha1: &str) -> PathBuf {
+    store_dir.join(sha1.get(..2).unwrap_or("00")).join(sha1)
+}
+
+/// Mod jars in `dir` and its subfolders, disabled ones included.
+pub fn mod_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return files;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let name = entry.file_name().to_string_lossy().to_ascii_lowercase();
+        if file_type.is_dir() {
+            files.extend(mod_files(&path));
+        } else if file_type.is_file() && (name.ends_with(".jar") || name.ends_with(".jar.disabled"))
+        {
+            files.push(path);
+        }
+    }
+    files
+}
+
+/// Shares files with reflinks or hard links, remembering whether reflinks
+/// work so an unsupported filesystem is only probed once.
+struct Linker {
+    reflinks: bool,
+}
+
+impl Linker {
+    fn new() -> Self {
+        Self { reflinks: true }
+    }
+
+    /// Make `dst`, which must not exist, share `src`'s data.
+    fn share(&mut self, src: &Path, dst: &Path) -> Option<Placement> {
+        if self.reflinks {
+            if reflink(src, dst) {
+                return Some(Placement::Reflink);
+            }
+            let _ = fs::remove_file(dst);
+            self.reflinks = false;
+        }
+        fs::hard_link(src, dst).ok().map(|_| Placement::HardLink)
+    }
+
+    /// Replace `dst` with a shared copy of `src`, falling back to a plain
+    /// copy unless `copy` is false. Returns `None` if nothing changed.
+    fn place(&mut self, src: &Path, dst: &Path, copy: bool) -> io::Result<Option<Placement>> {
+        let mut name = dst.file_name().unwrap_or_default().to_os_string();
+        name.push(".link");
+        let temp = dst.with_file_name(name);
+        let _ = fs::remove_file(&temp);
+        let placement = match self.share(src, &temp) {
+            Some(placement) => placement,
+            None if copy => fs::copy(src, &temp).map(|_| Placement::Copy)?,
+            None => return Ok(None),
+        };
+        fs::rename(&temp, dst).inspect_err(|_| {
+            let _ = fs::remove_file(&temp);
+        })?;
+        Ok(Some(placement))
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn reflink(src: &Path, dst: &Path) -> bool {
+    #[cfg(target_os = "linux")]
+    let flag = "--reflink=always";
+    #[cfg(target_os = "macos")]
+    let flag = "-c";
+    std::process::Command::new("cp")
+        .arg(flag)
+        .arg(src)
+        .arg(dst)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn reflink(_src: &Path, _dst: &Path) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_file(_a: &Path, _b: &Path) -> bool {
+    false
+}
+
+/// Put `files` into the store and make each share its stored copy.
+pub fn store_files(store_dir: &Path, files: &[PathBuf]) -> DedupeReport {
+    let mut report = DedupeReport::default();
+    let mut linker = Linker::new();
+    for path in files {
+        report.files += 1;
+        match store_file(&mut linker, store_dir, path) {
+            Ok((placement, size)) => report.count(placement, size),
+            Err(e) => report.errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+    report
+}
+
+fn store_file(linker: &mut Linker, store_dir: &Path, path: &Path) -> io::Result<(Placement, u64)> {
+    let data = fs::read(path)?;
+    let size = data.len() as u64;
+    let stored = stored_path(store_dir, &compute_sha1(&data));
+    if same_file(path, &stored) {
+        return Ok((Placement::HardLink, size));
+    }
+    // A damaged entry is replaced by this file
+    if fs::metadata(&stored).map(|m| m.len()).ok() != Some(size) {
+        fs::create_dir_all(stored.parent().unwrap_or(store_dir))?;
+        let placement = linker
+            .place(path, &stored, true)?
+            .unwrap_or(Placement::Copy);
+        return Ok((placement, size));
+    }
+    let placement = linker
+        .place(&stored, path, false)?
+        .unwrap_or(Placement::Copy);
+    Ok((placement, size))
+}
+
+/// Put the stored file with hash `sha1` at `dst`, if the store has it.
+///
+/// An entry whose contents no longer match `sha1` is removed and treated as
+/// missing, so the caller downloads the file instead.
+pub fn restore(store_dir: &Path, sha1: &str, dst: &Path) -> io::Result<Option<Placement>> {
+    let stored = stored_path(store_dir, sha1);
+    let Ok(data) = fs::read(&stored) else {
+        return Ok(None);
+    };
+    if !compute_sha1(&data).eq_ignore_ascii_case(sha1) {
+        let _ = fs::remove_file(&stored);
+        return Ok(None);
+    }
+    if let Some(parent) = dst.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Linker::new().place(&stored, dst, true)
+}
+
+/// Stored files whose hash none of `files` has, with their sizes.
+pub fn unused(store_dir: &Path, files: &[PathBuf]) -> Vec<(PathBuf, u64)> {
+    if !store_dir.is_dir() {
+        return Vec::new();
+    }
+    let used: HashSet<PathBuf> = files
+        .iter()
+        .filter_map(|path| fs::read(path).ok())
+        .map(|data| stored_path(store_dir, &compute_sha1(&data)))
+        .collect();
+    let mut unused = Vec::new();
+    for dir in fs::read_dir(store_dir).into_iter().flatten().flatten() {
+        for entry in fs::read_dir(dir.path()).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if !used.contains(&path) {
+                unused.push((path, entry.metadata().map(|m| m.len()).unwrap_or(0)));
+            }
+        }
+    }
+    unused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_files() {
+        let dir = std::env::temp_dir().join(format!("dropout-modstore-{}", uuid::Uuid::new_v4()));
+        let store = dir.join(STORE_DIR);
+        let (a, b) = (dir.join("a/mods"), dir.join("b/mods"));
+        fs::create_dir_all(&a).unwrap();
+        fs::create_dir_all(b.join("1.20.1")).unwrap();
+        fs::write(a.join("sodium.jar"), "sodium").unwrap();
+        fs::write(b.join("1.20.1/sodium-copy.jar"), "sodium").unwrap();
+        fs::write(b.join("lithium.jar.disabled"), "lithium").unwrap();
+        fs::write(b.join("notes.txt"), "not a mod").unwrap();
+
+        let mut files = mod_files(&a);
+        files.extend(mod_files(&b));
+        assert_eq!(files.len(), 3);
+        let report = store_files(&store, &files);
+        assert_eq!(report.files, 3);
+        assert!(report.errors.is_empty());
+        assert_eq!(
+            report.reflinked + report.hard_linked + report.copied,
+            report.files
+        );
+        assert_eq!(
+            fs::read_to_string(b.join("1.20.1/sodium-copy.jar")).unwrap(),
+            "sodium"
+        );
+        let sodium = stored_path(&store, &compute_sha1(b"sodium"));
+        assert_eq!(fs::read_to_string(&sodium).unwrap(), "sodium");
+
+        let restored = dir.join("c/mods/sodium.jar");
+        assert!(restore(&store, &compute_sha1(b"sodium"), &restored)
+            .unwrap()
+            .is_some());
+        assert_eq!(fs::read_to_string(&restored).unwrap(), "sodium");
+        assert!(restore(&store, &compute_sha1(b"iris"), &restored)
+            .unwrap()
+            .is_none());
+
+        // A damaged entry is dropped rather than restored
+        fs::remove_file(&sodium).unwrap();
+        fs::write(&sodium, "sodiun").unwrap();
+        assert!(restore(&store, &compute_sha1(b"sodium"), &restored)
+            .unwrap()
+            .is_none());
+        assert!(!sodium.exists());
+
+        fs::remove_file(b.join("lithium.jar.disabled")).unwrap();
+        let unused = unused(&store, &mod_files(&b));
+        assert_eq!(unused.len(), 1);
+        assert_eq!(unused[0].0, stored_path(&store, &compute_sha1(b"lithium")));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
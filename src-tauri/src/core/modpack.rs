@@ -0,0 +1,982 @@
+//! Updating modpack instances in place.
+//!
+//! An update downloads the archive (`.mrpack`, or the CurseForge zip and its
+//! `manifest.json`) of both the installed and the target release and diffs
+//! their file lists, so only changed files are fetched and only files the pack
+//! itself shipped are removed. Override files (configs, options) are only
+//! replaced when the user has not edited them. The instance is zipped into
+//! `backups/` before anything is touched. With the mod store enabled, files it
+//! already holds are linked instead of downloaded. The CurseForge API needs the
+//! user's `curseforge_api_key`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::core::config::EffectiveConfig;
+use crate::core::downloader::{compute_sha1, download_files_as, DownloadTask, PendingKind};
+use crate::core::error::LauncherError;
+use crate::core::events::EventSink;
+use crate::core::instance::{ModpackPlatform, ModpackSource};
+use crate::core::instance_backup::{self, BackupReason};
+use crate::core::manifest::LoaderType;
+use crate::core::mod_store;
+
+const MODRINTH_API_URL: &str = "https://api.modrinth.com/v2";
+const CURSEFORGE_API_URL: &str = "https://api.curseforge.com/v1";
+const INDEX_FILE: &str = "modrinth.index.json";
+const CURSEFORGE_MANIFEST: &str = "manifest.json";
+/// CurseForge project classes installed outside `mods/`
+const CF_CLASS_RESOURCE_PACKS: u64 = 12;
+const CF_CLASS_SHADER_PACKS: u64 = 6552;
+const OVERRIDE_DIRS: [&str; 2] = ["overrides", "client-overrides"];
+/// Instance entries left out of pre-update backups
+const BACKUP_EXCLUDES: [&str; 9] = [
+    instance_backup::BACKUP_DIR,
+    "saves",
+    "logs",
+    "crash-reports",
+    "screenshots",
+    "versions",
+    "libraries",
+    "assets",
+    ".pack-update",
+];
+
+/// A release of a Modrinth project.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackVersion {
+    pub id: String,
+    pub version_number: String,
+    pub name: String,
+    #[serde(default)]
+    pub changelog: Option<String>,
+    pub date_published: String,
+    pub version_type: String,
+    #[serde(default, skip_serializing)]
+    files: Vec<PackVersionFile>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PackVersionFile {
+    url: String,
+    filename: String,
+    #[serde(default)]
+    primary: bool,
+    #[serde(default)]
+    hashes: HashMap<String, String>,
+    #[serde(default)]
+    size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PackUpdateInfo {
+    pub current_version_id: String,
+    pub latest: Option<PackVersion>,
+    pub update_available: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PackUpdateReport {
+    pub from_version: String,
+    pub to_version: String,
+    /// Version id the instance now launches
+    pub version_id: String,
+    pub downloaded: usize,
+    /// Files linked from the mod store instead of downloaded
+    pub from_store: usize,
+    pub removed: usize,
+    /// Files the user changed, left as they were
+    pub kept: Vec<String>,
+    pub backup: PathBuf,
+}
+
+/// `modrinth.index.json`
+#[derive(Debug, Clone, Deserialize)]
+pub struct MrpackIndex {
+    pub files: Vec<MrpackFile>,
+    #[serde(default)]
+    pub dependencies: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MrpackFile {
+    pub path: String,
+    #[serde(default)]
+    pub hashes: HashMap<String, String>,
+    #[serde(default)]
+    pub env: Option<MrpackEnv>,
+    pub downloads: Vec<String>,
+    #[serde(rename = "fileSize", default)]
+    pub file_size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MrpackEnv {
+    pub client: String,
+}
+
+impl MrpackFile {
+    fn sha1(&self) -> Option<&str> {
+        self.hashes.get("sha1").map(String::as_str)
+    }
+
+    fn for_client(&self) -> bool {
+        self.env.as_ref().is_none_or(|e| e.client != "unsupported")
+    }
+}
+
+impl MrpackIndex {
+    /// Game version, loader and loader version from `dependencies`.
+    pub fn game_version(&self) -> Result<(String, LoaderType, Option<String>), String> {
+        let minecraft = self
+            .dependencies
+            .get("minecraft")
+            .ok_or("Pack does not declare a Minecraft version")?;
+        let loader = [
+            ("fabric-loader", LoaderType::Fabric),
+            ("quilt-loader", LoaderType::Quilt),
+            ("neoforge", LoaderType::NeoForge),
+            ("forge", LoaderType::Forge),
+        ]
+        .into_iter()
+        .find_map(|(key, loader)| self.dependencies.get(key).map(|v| (loader, v.clone())));
+        Ok(match loader {
+            Some((loader, version)) => (minecraft.clone(), loader, Some(version)),
+            None => (minecraft.clone(), LoaderType::Vanilla, None),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CfResponse<T> {
+    data: T,
+}
+
+/// A file of a CurseForge project: a pack release or a mod in it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CfFile {
+    id: u64,
+    mod_id: u64,
+    display_name: String,
+    file_name: String,
+    /// 1 release, 2 beta, 3 alpha
+    release_type: u8,
+    file_date: String,
+    /// Missing when the author disabled third-party downloads
+    #[serde(default)]
+    download_url: Option<String>,
+    #[serde(default)]
+    hashes: Vec<CfHash>,
+    #[serde(default)]
+    file_length: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CfHash {
+    value: String,
+    /// 1 SHA-1, 2 MD5
+    algo: u8,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CfMod {
+    id: u64,
+    #[serde(default)]
+    class_id: Option<u64>,
+}
+
+impl CfFile {
+    fn sha1(&self) -> Option<&str> {
+        self.hashes
+            .iter()
+            .find(|h| h.algo == 1)
+            .map(|h| h.value.as_str())
+    }
+
+    fn download_url(&self) -> Result<&str, String> {
+        self.download_url.as_deref().ok_or_else(|| {
+            format!(
+                "{} can only be downloaded from the CurseForge website",
+                self.file_name
+            )
+        })
+    }
+
+    fn pack_version(&self) -> PackVersion {
+        PackVersion {
+            id: self.id.to_string(),
+            version_number: self.display_name.clone(),
+            name: self.display_name.clone(),
+            changelog: None,
+            date_published: self.file_date.clone(),
+            version_type: match self.release_type {
+                1 => "release",
+                2 => "beta",
+                _ => "alpha",
+            }
+            .to_string(),
+            files: Vec::new(),
+        }
+    }
+}
+
+/// `manifest.json` of a CurseForge pack.
+#[derive(Debug, Clone, Deserialize)]
+struct CfManifest {
+    minecraft: CfMinecraft,
+    #[serde(default)]
+    files: Vec<CfManifestFile>,
+    #[serde(default = "default_overrides")]
+    overrides: String,
+}
+
+fn default_overrides() -> String {
+    "overrides".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CfMinecraft {
+    version: String,
+    #[serde(rename = "modLoaders", default)]
+    mod_loaders: Vec<CfModLoader>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CfModLoader {
+    /// e.g. "forge-47.2.0"
+    id: String,
+    #[serde(default)]
+    primary: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct CfManifestFile {
+    #[serde(rename = "fileID")]
+    file_id: u64,
+    #[serde(default = "default_required")]
+    required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+impl CfManifest {
+    /// Game version, loader and loader version from the primary mod loader.
+    fn game_version(&self) -> (String, LoaderType, Option<String>) {
+        let minecraft = self.minecraft.version.clone();
+        let loader = self
+            .minecraft
+            .mod_loaders
+            .iter()
+            .find(|l| l.primary)
+            .or(self.minecraft.mod_loaders.first())
+            .and_then(|l| {
+                let (name, version) = l.id.split_once('-')?;
+                let loader = match name {
+                    "forge" => LoaderType::Forge,
+                    "neoforge" => LoaderType::NeoForge,
+                    "fabric" => LoaderType::Fabric,
+                    "quilt" => LoaderType::Quilt,
+                    _ => return None,
+                };
+                Some((loader, version.to_string()))
+            });
+        match loader {
+            Some((loader, version)) => (minecraft, loader, Some(version)),
+            None => (minecraft, LoaderType::Vanilla, None),
+        }
+    }
+}
+
+/// Changes between two releases of a pack.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct UpdatePlan {
+    /// Indexed files that are new or changed
+    pub download: Vec<String>,
+    /// Files the old release shipped that the new one drops
+    pub remove: Vec<String>,
+    /// Override files to copy from the new release
+    pub write_overrides: Vec<String>,
+    /// Files the user changed, left untouched
+    pub kept: Vec<String>,
+}
+
+/// Diff two releases against the instance. `old` and `new` map relative
+/// paths to SHA-1 hashes of everything each release ships (indexed files and
+/// overrides); `local` hashes a file in the instance, if it exists.
+pub fn plan_update(
+    old: &HashMap<String, String>,
+    new_indexed: &HashMap<String, String>,
+    new_overrides: &HashMap<String, String>,
+    local: impl Fn(&str) -> Option<String>,
+) -> UpdatePlan {
+    let mut plan = UpdatePlan::default();
+
+    for (path, hash) in new_indexed {
+        if old.get(path) != Some(hash) || local(path).as_ref() != Some(hash) {
+            plan.download.push(path.clone());
+        }
+    }
+
+    for (path, hash) in new_overrides {
+        match local(path) {
+            None => plan.write_overrides.push(path.clone()),
+            Some(current) if &current == hash => {}
+            Some(current) if old.get(path) == Some(&current) => {
+                plan.write_overrides.push(path.clone())
+            }
+            Some(_) => plan.kept.push(path.clone()),
+        }
+    }
+
+    for (path, hash) in old {
+        if new_indexed.contains_key(path) || new_overrides.contains_key(path) {
+            continue;
+        }
+        match local(path) {
+            Some(current) if &current == hash => plan.remove.push(path.clone()),
+            Some(_) => plan.kept.push(path.clone()),
+            None => {}
+        }
+    }
+
+    plan.download.sort();
+    plan.remove.sort();
+    plan.write_overrides.sort();
+    plan.kept.sort();
+    plan
+}
+
+/// Reject absolute paths and `..` in pack-supplied paths.
+fn safe_relative_path(path: &str) -> Result<&Path, String> {
+    let relative = Path::new(path);
+    if path.is_empty()
+        || !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+    {
+        return Err(format!("Pack contains an unsafe path: {}", path));
+    }
+    Ok(relative)
+}
+
+fn curseforge_key(config: &EffectiveConfig) -> Result<&str, String> {
+    config
+        .settings
+        .curseforge_api_key
+        .as_deref()
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| "Updating CurseForge modpacks needs a CurseForge API key".to_string())
+}
+
+async fn read_json<T: serde::de::DeserializeOwned>(
+    url: &str,
+    response: Result<reqwest::Response, reqwest::Error>,
+) -> Result<T, String> {
+    let response = response.map_err(|e| format!("Request to {} failed: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned {}", url, response.status()));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid response from {}: {}", url, e))
+}
+
+async fn fetch_json<T: serde::de::DeserializeOwned>(url: &str) -> Result<T, String> {
+    read_json(url, crate::core::http::get(url).await).await
+}
+
+/// `data` of a CurseForge API response; POSTed if `body` is given.
+async fn curseforge_json<T: serde::de::DeserializeOwned>(
+    key: &str,
+    path: &str,
+    body: Option<serde_json::Value>,
+) -> Result<T, String> {
+    let url = format!("{}{}", CURSEFORGE_API_URL, path);
+    let client = crate::core::http::client();
+    let request = match body {
+        Some(body) => client.post(&url).json(&body),
+        None => client.get(&url),
+    };
+    let response = crate::core::http::send(request.header("x-api-key", key)).await;
+    read_json::<CfResponse<T>>(&url, response)
+        .await
+        .map(|r| r.data)
+}
+
+/// Releases of the pack, newest first.
+async fn list_versions(
+    source: &ModpackSource,
+    config: &EffectiveConfig,
+) -> Result<Vec<PackVersion>, String> {
+    match source.platform {
+        ModpackPlatform::Modrinth => {
+            fetch_json(&format!(
+                "{}/project/{}/version",
+                MODRINTH_API_URL, source.project_id
+            ))
+            .await
+        }
+        ModpackPlatform::CurseForge => {
+            let path = format!("/mods/{}/files", source.project_id);
+            let files: Vec<CfFile> = curseforge_json(curseforge_key(config)?, &path, None).await?;
+            let mut versions: Vec<PackVersion> = files.iter().map(CfFile::pack_version).collect();
+            versions.sort_by(|a, b| b.date_published.cmp(&a.date_published));
+            Ok(versions)
+        }
+    }
+}
+
+/// Look for a newer release of the pack an instance was installed from.
+pub async fn check_update(
+    source: &ModpackSource,
+    config: &EffectiveConfig,
+) -> Result<PackUpdateInfo, String> {
+    let versions = list_versions(source, config).await?;
+    // Prefer releases over betas
+    let latest = versions
+        .iter()
+        .find(|v| v.version_type == "release")
+        .or(versions.first())
+        .cloned();
+    Ok(PackUpdateInfo {
+        current_version_id: source.version_id.clone(),
+        update_available: latest.as_ref().is_some_and(|v| v.id != source.version_id),
+        latest,
+    })
+}
+
+/// A file of a release that is downloaded rather than shipped in the archive.
+struct PackFile {
+    path: String,
+    sha1: String,
+    url: String,
+    size: Option<u64>,
+}
+
+/// A downloaded and extracted pack archive.
+struct ExtractedPack {
+    version_name: String,
+    files: Vec<PackFile>,
+    /// Relative path -> extracted file, `client-overrides` winning
+    overrides: HashMap<String, PathBuf>,
+    override_hashes: HashMap<String, String>,
+    game_version: (String, LoaderType, Option<String>),
+}
+
+impl ExtractedPack {
+    fn indexed_hashes(&self) -> Result<HashMap<String, String>, String> {
+        let mut hashes = HashMap::new();
+        for file in &self.files {
+            safe_relative_path(&file.path)?;
+            hashes.insert(file.path.clone(), file.sha1.clone());
+        }
+        Ok(hashes)
+    }
+}
+
+async fn fetch_pack(
+    events: &EventSink,
+    source: &ModpackSource,
+    version_id: &str,
+    work_dir: &Path,
+    config: &EffectiveConfig,
+) -> Result<ExtractedPack, String> {
+    let (task, version_name) = match source.platform {
+        ModpackPlatform::Modrinth => {
+            let version: PackVersion =
+                fetch_json(&format!("{}/version/{}", MODRINTH_API_URL, version_id)).await?;
+            let file = version
+                .files
+                .iter()
+                .find(|f| f.primary)
+                .or(version.files.first())
+                .filter(|f| f.filename.ends_with(".mrpack"))
+                .ok_or_else(|| format!("Version {} has no .mrpack file", version.version_number))?;
+            let task = DownloadTask {
+                url: file.url.clone(),
+                path: work_dir.join(format!("{}.mrpack", version_id)),
+                sha1: file.hashes.get("sha1").cloned(),
+                sha256: None,
+                size: file.size,
+                mirrors: Vec::new(),
+            };
+            (task, version.version_number)
+        }
+        ModpackPlatform::CurseForge => {
+            let path = format!("/mods/{}/files/{}", source.project_id, version_id);
+            let file: CfFile = curseforge_json(curseforge_key(config)?, &path, None).await?;
+            let task = DownloadTask {
+                url: file.download_url()?.to_string(),
+                path: work_dir.join(format!("{}.zip", version_id)),
+                sha1: file.sha1().map(str::to_string),
+                sha256: None,
+                size: file.file_length,
+                mirrors: Vec::new(),
+            };
+            (task, file.display_name)
+        }
+    };
+
+    let archive = task.path.clone();
+    // An interrupted download is rolled back on resume rather than finished
+    download_files_as(
+        PendingKind::PackUpdate,
+        None,
+        events.clone(),
+        vec![task],
+        config,
+    )
+    .await?;
+    let dir = work_dir.join(version_id);
+    crate::core::extract::extract(
+        events.clone(),
+        archive,
+        dir.clone(),
+        crate::utils::zip::SymlinkPolicy::Skip,
+        None,
+    )
+    .await?;
+
+    let (files, override_dirs, game_version) = match source.platform {
+        ModpackPlatform::Modrinth => {
+            let index: MrpackIndex = read_manifest(&dir, INDEX_FILE).await?;
+            let files = index
+                .files
+                .iter()
+                .filter(|f| f.for_client())
+                .map(|f| {
+                    Ok(PackFile {
+                        path: f.path.clone(),
+                        sha1: f
+                            .sha1()
+                            .ok_or_else(|| format!("{} has no SHA-1 hash", f.path))?
+                            .to_string(),
+                        url: f
+                            .downloads
+                            .first()
+                            .ok_or_else(|| format!("{} has no download URL", f.path))?
+                            .clone(),
+                        size: f.file_size,
+                    })
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            let dirs = OVERRIDE_DIRS.iter().map(|d| d.to_string()).collect();
+            (files, dirs, index.game_version()?)
+        }
+        ModpackPlatform::CurseForge => {
+            let manifest: CfManifest = read_manifest(&dir, CURSEFORGE_MANIFEST).await?;
+            let files = curseforge_files(curseforge_key(config)?, &manifest).await?;
+            let game_version = manifest.game_version();
+            (files, vec![manifest.overrides], game_version)
+        }
+    };
+
+    let (overrides, override_hashes) = tokio::task::spawn_blocking(move || {
+        let mut overrides = HashMap::new();
+        for name in &override_dirs {
+            collect_files(&dir.join(name), &dir.join(name), &mut overrides);
+        }
+        let hashes = overrides
+            .iter()
+            .map(|(path, file)| {
+                let data = fs::read(file).map_err(|e| e.to_string())?;
+                Ok((path.clone(), compute_sha1(&data)))
+            })
+            .collect::<Result<HashMap<_, _>, String>>()?;
+        Ok::<_, String>((overrides, hashes))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    Ok(ExtractedPack {
+        version_name,
+        files,
+        overrides,
+        override_hashes,
+        game_version,
+    })
+}
+
+async fn read_manifest<T: serde::de::DeserializeOwned>(
+    dir: &Path,
+    name: &str,
+) -> Result<T, String> {
+    let content = tokio::fs::read_to_string(dir.join(name))
+        .await
+        .map_err(|e| format!("Pack has no {}: {}", name, e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid {}: {}", name, e))
+}
+
+/// Download locations of the mods a CurseForge manifest lists, placed by
+/// project class (mods, resource packs or shader packs).
+async fn curseforge_files(key: &str, manifest: &CfManifest) -> Result<Vec<PackFile>, String> {
+    let file_ids: Vec<u64> = manifest
+        .files
+        .iter()
+        .filter(|f| f.required)
+        .map(|f| f.file_id)
+        .collect();
+    if file_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let body = serde_json::json!({ "fileIds": file_ids });
+    let files: Vec<CfFile> = curseforge_json(key, "/mods/files", Some(body)).await?;
+    let mod_ids: Vec<u64> = files.iter().map(|f| f.mod_id).collect();
+    let body = serde_json::json!({ "modIds": mod_ids });
+    let mods: Vec<CfMod> = curseforge_json(key, "/mods", Some(body)).await?;
+    let classes: HashMap<u64, u64> = mods
+        .iter()
+        .filter_map(|m| Some((m.id, m.class_id?)))
+        .collect();
+
+    files
+        .iter()
+        .map(|f| {
+            let dir = match classes.get(&f.mod_id) {
+                Some(&CF_CLASS_RESOURCE_PACKS) => "resourcepacks",
+                Some(&CF_CLASS_SHADER_PACKS) => "shaderpacks",
+                _ => "mods",
+            };
+            Ok(PackFile {
+                path: format!("{}/{}", dir, f.file_name),
+                sha1: f
+                    .sha1()
+                    .ok_or_else(|| format!("{} has no SHA-1 hash", f.file_name))?
+                    .to_string(),
+                url: f.download_url()?.to_string(),
+                size: f.file_length,
+            })
+        })
+        .collect()
+}
+
+fn collect_files(root: &Path, dir: &Path, out: &mut HashMap<String, PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(root, &path, out);
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            out.insert(relative.to_string_lossy().replace('\\', "/"), path);
+        }
+    }
+}
+
+/// Update a Modrinth or CurseForge pack instance to `target_version_id`.
+///
+/// Returns the report; the caller records the new version on the instance.
+/// The new loader version may still need installing before launch. Unless
+/// `allow_metered` is set, large updates may need the user's consent first,
+/// see `core::metered`. `mod_store` is the store directory if it is enabled.
+pub async fn update_pack(
+    events: &EventSink,
+    instance_dir: &Path,
+    source: &ModpackSource,
+    target_version_id: &str,
+    config: &EffectiveConfig,
+    allow_metered: bool,
+    mod_store: Option<&Path>,
+) -> Result<PackUpdateReport, LauncherError> {
+    if target_version_id == source.version_id {
+        return Err(LauncherError::Invalid(
+            "The instance already has this version".to_string(),
+        ));
+    }
+
+    let work_dir = instance_dir.join(".pack-update");
+    if work_dir.exists() {
+        fs::remove_dir_all(&work_dir).map_err(|e| e.to_string())?;
+    }
+    let result = apply_update(
+        events,
+        instance_dir,
+        &work_dir,
+        source,
+        target_version_id,
+        config,
+        allow_metered,
+        mod_store,
+    )
+    .await;
+    let _ = fs::remove_dir_all(&work_dir);
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn apply_update(
+    events: &EventSink,
+    instance_dir: &Path,
+    work_dir: &Path,
+    source: &ModpackSource,
+    target_version_id: &str,
+    config: &EffectiveConfig,
+    allow_metered: bool,
+    mod_store: Option<&Path>,
+) -> Result<PackUpdateReport, LauncherError> {
+    let (old, new) = tokio::try_join!(
+        fetch_pack(events, source, &source.version_id, work_dir, config),
+        fetch_pack(events, source, target_version_id, work_dir, config)
+    )?;
+
+    let mut old_hashes = old.override_hashes.clone();
+    old_hashes.extend(old.indexed_hashes()?);
+    let new_indexed = new.indexed_hashes()?;
+    let new_overrides = new.override_hashes.clone();
+    for path in new_overrides.keys() {
+        safe_relative_path(path)?;
+    }
+
+    // Hashes every file the releases touch
+    let root = instance_dir.to_path_buf();
+    let plan = tokio::task::spawn_blocking(move || {
+        plan_update(&old_hashes, &new_indexed, &new_overrides, |path| {
+            fs::read(root.join(path))
+                .ok()
+                .map(|data| compute_sha1(&data))
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let stored =
+        |f: &PackFile| mod_store.is_some_and(|dir| mod_store::stored_path(dir, &f.sha1).is_file());
+    if !allow_metered {
+        let bytes = new
+            .files
+            .iter()
+            .filter(|f| plan.download.contains(&f.path) && !stored(f))
+            .filter_map(|f| f.size)
+            .sum();
+        crate::core::metered::check(config, bytes, "The modpack update")?;
+    }
+
+    let backup = instance_backup::new_backup_path(instance_dir, BackupReason::PackUpdate);
+    let (root, zip) = (instance_dir.to_path_buf(), backup.clone());
+    tokio::task::spawn_blocking(move || {
+        crate::utils::zip::create_zip(&root, &zip, &BACKUP_EXCLUDES)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let mut tasks = Vec::new();
+    let mut stored_tasks = Vec::new();
+    for f in new.files.iter().filter(|f| plan.download.contains(&f.path)) {
+        let task = DownloadTask {
+            url: f.url.clone(),
+            path: instance_dir.join(safe_relative_path(&f.path)?),
+            sha1: Some(f.sha1.clone()),
+            sha256: None,
+            size: f.size,
+            mirrors: Vec::new(),
+        };
+        if stored(f) {
+            stored_tasks.push(task);
+        } else {
+            tasks.push(task);
+        }
+    }
+    let mut from_store = 0;
+    if let Some(store_dir) = mod_store.filter(|_| !stored_tasks.is_empty()) {
+        // Entries that vanished or fail their hash check are downloaded instead
+        let store_dir = store_dir.to_path_buf();
+        let (restored, missing) = tokio::task::spawn_blocking(move || {
+            let mut missing = Vec::new();
+            let mut restored = 0;
+            for task in stored_tasks {
+                let sha1 = task.sha1.as_deref().unwrap_or_default();
+                let placed = mod_store::restore(&store_dir, sha1, &task.path)
+                    .map_err(|e| format!("Failed to link {}: {}", task.path.display(), e))?;
+                match placed {
+                    Some(_) => restored += 1,
+                    None => missing.push(task),
+                }
+            }
+            Ok::<_, String>((restored, missing))
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+        from_store = restored;
+        tasks.extend(missing);
+    }
+    let downloaded: Vec<PathBuf> = tasks.iter().map(|task| task.path.clone()).collect();
+    // An interrupted download is rolled back on resume rather than finished
+    download_files_as(
+        PendingKind::PackUpdate,
+        Some(&backup),
+        events.clone(),
+        tasks,
+        config,
+    )
+    .await?;
+    if let Some(store_dir) = mod_store {
+        let mods_dir = instance_dir.join("mods");
+        let mods: Vec<PathBuf> = downloaded
+            .into_iter()
+            .filter(|path| path.starts_with(&mods_dir))
+            .collect();
+        let store_dir = store_dir.to_path_buf();
+        let report = tokio::task::spawn_blocking(move || mod_store::store_files(&store_dir, &mods))
+            .await
+            .map_err(|e| e.to_string())?;
+        for error in report.errors {
+            println!("[ModStore] {}", error);
+        }
+    }
+
+    for path in &plan.write_overrides {
+        let dst = instance_dir.join(safe_relative_path(path)?);
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        // Writing through a hard link would change the mod store's copy
+        let _ = fs::remove_file(&dst);
+        fs::copy(&new.overrides[path], &dst)
+            .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    }
+
+    for path in &plan.remove {
+        let target = instance_dir.join(safe_relative_path(path)?);
+        fs::remove_file(&target).map_err(|e| format!("Failed to remove {}: {}", path, e))?;
+    }
+
+    let (minecraft, loader, loader_version) = new.game_version;
+    Ok(PackUpdateReport {
+        from_version: old.version_name,
+        to_version: new.version_name,
+        version_id: crate::core::import::loader_version_id(
+            &minecraft,
+            loader,
+            loader_version.as_deref(),
+        ),
+        downloaded: plan.download.len() - from_store,
+        from_store,
+        removed: plan.remove.len(),
+        kept: plan.kept,
+        backup,
+    })
+}
+
+/// `source` pointing at the release it was updated to.
+pub fn updated_source(
+    source: &ModpackSource,
+    version_id: &str,
+    version_name: &str,
+) -> ModpackSource {
+    ModpackSource {
+        version_id: version_id.to_string(),
+        version_name: Some(version_name.to_string()),
+        ..source.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hashes(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(p, h)| (p.to_string(), h.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_update() {
+        let old = hashes(&[
+            ("mods/a.jar", "a1"),
+            ("mods/b.jar", "b1"),
+            ("mods/dropped.jar", "d1"),
+            ("config/edited.toml", "e1"),
+            ("config/pristine.toml", "p1"),
+        ]);
+        let new_indexed = hashes(&[("mods/a.jar", "a1"), ("mods/b.jar", "b2")]);
+        let new_overrides = hashes(&[
+            ("config/edited.toml", "e2"),
+            ("config/pristine.toml", "p2"),
+            ("options.txt", "o1"),
+        ]);
+        let local = hashes(&[
+            ("mods/a.jar", "a1"),
+            ("mods/b.jar", "b1"),
+            ("mods/dropped.jar", "d1"),
+            ("config/edited.toml", "user"),
+            ("config/pristine.toml", "p1"),
+        ]);
+
+        let plan = plan_update(&old, &new_indexed, &new_overrides, |p| {
+            local.get(p).cloned()
+        });
+
+        assert_eq!(plan.download, vec!["mods/b.jar"]);
+        assert_eq!(plan.remove, vec!["mods/dropped.jar"]);
+        assert_eq!(
+            plan.write_overrides,
+            vec!["config/pristine.toml", "options.txt"]
+        );
+        assert_eq!(plan.kept, vec!["config/edited.toml"]);
+    }
+
+    #[test]
+    fn test_curseforge_manifest() {
+        let manifest: CfManifest = serde_json::from_str(
+            r#"{
+                "minecraft": {
+                    "version": "1.20.1",
+                    "modLoaders": [{ "id": "forge-47.2.0", "primary": true }]
+                },
+                "manifestType": "minecraftModpack",
+                "name": "Example Pack",
+                "version": "1.4.2",
+                "files": [
+                    { "projectID": 238222, "fileID": 4712345, "required": true },
+                    { "projectID": 306612, "fileID": 4798765, "required": false }
+                ],
+                "overrides": "overrides"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            manifest.game_version(),
+            (
+                "1.20.1".to_string(),
+                LoaderType::Forge,
+                Some("47.2.0".to_string())
+            )
+        );
+        assert!(!manifest.files[1].required);
+
+        let file: CfFile = serde_json::from_value(serde_json::json!({
+            "id": 4712345,
+            "modId": 238222,
+            "displayName": "Example Pack 1.4.2",
+            "fileName": "example-1.4.2.zip",
+            "releaseType": 2,
+            "fileDate": "2024-01-02T03:04:05Z",
+            "downloadUrl": null,
+            "hashes": [{ "value": "abc", "algo": 2 }, { "value": "def", "algo": 1 }],
+            "fileLength": 1024
+        }))
+        .unwrap();
+        assert_eq!(file.sha1(), Some("def"));
+        assert!(file.download_url().is_err());
+        let version = file.pack_version();
+        assert_eq!(version.id, "4712345");
+        assert_eq!(version.version_type, "beta");
+    }
+
+    #[test]
+    fn test_safe_relative_path() {
+        assert!(safe_relative_path("mods/sodium.jar").is_ok());
+        assert!(safe_relative_path("../outside.jar").is_err());
+        assert!(safe_relative_path("/etc/passwd").is_err());
+    }
+}
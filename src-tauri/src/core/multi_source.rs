@@ -0,0 +1,303 @@
+//! Parallel download of one large file from several mirrors.
+//!
+//! The file is split into fixed-size segments that the sources take from a
+//! shared queue, so a fast mirror ends up serving more of the file than a
+//! throttled one. A source that fails is dropped and the rest of its segment
+//! goes back to the queue, where a source that is still running picks it up.
+//! Sources that ignore `Range` are never used; when
+//! none honours it the caller falls back to a single stream.
+
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Mutex;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::Notify;
+
+/// Files smaller than this are downloaded in one stream
+pub const MIN_SIZE: u64 = 8 * 1024 * 1024;
+const SEGMENT_SIZE: u64 = 2 * 1024 * 1024;
+/// Segments fetched at once from each source
+const CONNECTIONS_PER_SOURCE: usize = 2;
+
+/// Official hosts serving the same Mojang objects under the same path.
+const MOJANG_HOSTS: [&str; 2] = [
+    "https://piston-data.mojang.com/",
+    "https://launcher.mojang.com/",
+];
+
+#[derive(Debug, PartialEq)]
+pub enum MultiSourceError {
+    /// No source answered a range request with partial content
+    RangesUnsupported,
+    Failed(String),
+}
+
+impl std::fmt::Display for MultiSourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RangesUnsupported => f.write_str("No source supports range requests"),
+            Self::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Other Mojang hosts serving `url`, e.g. `launcher.mojang.com` for a
+/// `piston-data.mojang.com` client jar.
+pub fn mojang_mirrors(url: &str) -> Vec<String> {
+    let Some(path) = MOJANG_HOSTS.iter().find_map(|host| url.strip_prefix(host)) else {
+        return Vec::new();
+    };
+    MOJANG_HOSTS
+        .iter()
+        .filter(|host| !url.starts_with(*host))
+        .map(|host| format!("{}{}", host, path))
+        .collect()
+}
+
+/// Segments waiting for a source and the number being fetched. Both change
+/// under one lock, so a worker that finds nothing pending knows whether a
+/// failing segment may still come back.
+struct SegmentQueue {
+    pending: Mutex<(VecDeque<(u64, u64)>, usize)>,
+    changed: Notify,
+}
+
+impl SegmentQueue {
+    fn new(size: u64) -> Self {
+        Self {
+            pending: Mutex::new((segments(size), 0)),
+            changed: Notify::new(),
+        }
+    }
+
+    /// The next segment, waiting while others are in flight. `None` once
+    /// every segment is done.
+    async fn next(&self) -> Option<(u64, u64)> {
+        loop {
+            // Registered before checking, so a `finish` in between is not missed
+            let changed = self.changed.notified();
+            {
+                let mut pending = self.pending.lock().unwrap();
+                if let Some(segment) = pending.0.pop_front() {
+                    pending.1 += 1;
+                    return Some(segment);
+                }
+                if pending.1 == 0 {
+                    return None;
+                }
+            }
+            changed.await;
+        }
+    }
+
+    /// Mark a segment taken with `next` as done, queueing `rest` if part of it
+    /// is still missing.
+    fn finish(&self, rest: Option<(u64, u64)>) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.1 -= 1;
+        pending.0.extend(rest);
+        drop(pending);
+        self.changed.notify_waiters();
+    }
+
+    fn is_done(&self) -> bool {
+        let pending = self.pending.lock().unwrap();
+        pending.0.is_empty() && pending.1 == 0
+    }
+}
+
+/// Inclusive byte ranges of at most `SEGMENT_SIZE` covering `size` bytes.
+fn segments(size: u64) -> VecDeque<(u64, u64)> {
+    (0..size.div_ceil(SEGMENT_SIZE))
+        .map(|i| {
+            let start = i * SEGMENT_SIZE;
+            (start, (start + SEGMENT_SIZE).min(size) - 1)
+        })
+        .collect()
+}
+
+/// Whether `url` answers a range request with 206 Partial Content.
+pub async fn supports_ranges(client: &reqwest::Client, url: &str) -> bool {
+    client
+        .get(url)
+        .header(reqwest::header::RANGE, "bytes=0-0")
+        .send()
+        .await
+        .is_ok_and(|resp| resp.status() == reqwest::StatusCode::PARTIAL_CONTENT)
+}
+
+/// Download `size` bytes to `path` from all `sources` that support ranges.
+/// `on_progress` gets the length of every chunk written.
+pub async fn download(
+    client: &reqwest::Client,
+    sources: &[String],
+    path: &Path,
+    size: u64,
+    on_progress: impl Fn(u64) + Sync,
+) -> Result<(), MultiSourceError> {
+    let probes = sources
+        .iter()
+        .map(|url| async move { supports_ranges(client, url).await.then_some(url.as_str()) });
+    let usable: Vec<&str> = futures::future::join_all(probes)
+        .await
+        .into_iter()
+        .flatten()
+        .collect();
+    if usable.is_empty() {
+        return Err(MultiSourceError::RangesUnsupported);
+    }
+
+    let file = tokio::fs::File::create(path)
+        .await
+        .map_err(|e| MultiSourceError::Failed(format!("Create file error: {}", e)))?;
+    file.set_len(size)
+        .await
+        .map_err(|e| MultiSourceError::Failed(format!("Write error: {}", e)))?;
+    drop(file);
+
+    let queue = SegmentQueue::new(size);
+    let workers = usable
+        .iter()
+        .flat_map(|url| std::iter::repeat_n(*url, CONNECTIONS_PER_SOURCE))
+        .map(|url| worker(client, url, path, &queue, &on_progress));
+    let errors: Vec<String> = futures::future::join_all(workers)
+        .await
+        .into_iter()
+        .filter_map(Result::err)
+        .collect();
+
+    if queue.is_done() {
+        return Ok(());
+    }
+    Err(MultiSourceError::Failed(
+        errors
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| "Download incomplete".to_string()),
+    ))
+}
+
+/// Fetch segments from `url` until the queue is empty or a request fails.
+async fn worker(
+    client: &reqwest::Client,
+    url: &str,
+    path: &Path,
+    queue: &SegmentQueue,
+    on_progress: &(impl Fn(u64) + Sync),
+) -> Result<(), String> {
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .await
+        .map_err(|e| format!("Open file error: {}", e))?;
+    while let Some((start, end)) = queue.next().await {
+        let mut written = 0;
+        let result = fetch_segment(
+            client,
+            url,
+            &mut file,
+            start,
+            end,
+            &mut written,
+            on_progress,
+        );
+        if let Err(e) = result.await {
+            // Hand the rest to the other sources
+            queue.finish((start + written <= end).then_some((start + written, end)));
+            return Err(format!("{}: {}", url, e));
+        }
+        queue.finish(None);
+    }
+    Ok(())
+}
+
+async fn fetch_segment(
+    client: &reqwest::Client,
+    url: &str,
+    file: &mut tokio::fs::File,
+    start: u64,
+    end: u64,
+    written: &mut u64,
+    on_progress: &(impl Fn(u64) + Sync),
+) -> Result<(), String> {
+    let mut resp = client
+        .get(url)
+        .header(reqwest::header::RANGE, format!("bytes={}-{}", start, end))
+        .send()
+        .await
+        .map_err(|e| format!("Request error: {}", e))?;
+    if resp.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+        return Err(format!("Unexpected status {}", resp.status()));
+    }
+    file.seek(std::io::SeekFrom::Start(start))
+        .await
+        .map_err(|e| format!("Seek error: {}", e))?;
+    while let Some(chunk) = resp
+        .chunk()
+        .await
+        .map_err(|e| format!("Download error: {}", e))?
+    {
+        // Never write past the segment, whatever the server sends
+        let len = (chunk.len() as u64).min(end + 1 - start - *written);
+        file.write_all(&chunk[..len as usize])
+            .await
+            .map_err(|e| format!("Write error: {}", e))?;
+        *written += len;
+        on_progress(len);
+    }
+    file.flush()
+        .await
+        .map_err(|e| format!("Write error: {}", e))?;
+    if start + *written <= end {
+        return Err("Connection closed early".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segments_cover_file() {
+        let ranges: Vec<_> = segments(2 * SEGMENT_SIZE + 10).into_iter().collect();
+        assert_eq!(
+            ranges,
+            vec![
+                (0, SEGMENT_SIZE - 1),
+                (SEGMENT_SIZE, 2 * SEGMENT_SIZE - 1),
+                (2 * SEGMENT_SIZE, 2 * SEGMENT_SIZE + 9),
+            ]
+        );
+        assert!(segments(0).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_segment_waits_for_other_workers() {
+        let queue = SegmentQueue::new(SEGMENT_SIZE + 10);
+        let first = queue.next().await.unwrap();
+        let second = queue.next().await.unwrap();
+        assert_eq!(second, (SEGMENT_SIZE, SEGMENT_SIZE + 9));
+
+        // Nothing is pending, but the first segment may still fail
+        let waiting = queue.next();
+        tokio::pin!(waiting);
+        assert!(futures::poll!(waiting.as_mut()).is_pending());
+        queue.finish(Some((first.0 + 5, first.1)));
+        assert_eq!(waiting.await, Some((5, SEGMENT_SIZE - 1)));
+
+        queue.finish(None);
+        queue.finish(None);
+        assert!(queue.is_done());
+        assert_eq!(queue.next().await, None);
+    }
+
+    #[test]
+    fn test_mojang_mirrors() {
+        assert_eq!(
+            mojang_mirrors("https://piston-data.mojang.com/v1/objects/abc/client.jar"),
+            vec!["https://launcher.mojang.com/v1/objects/abc/client.jar"]
+        );
+        assert!(mojang_mirrors("https://example.com/client.jar").is_empty());
+    }
+}
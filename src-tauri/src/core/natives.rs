@@ -0,0 +1,215 @@
+//! Native library extraction.
+//!
+//! Native jars are extracted into `versions/<id>/natives`. A fingerprint of
+//! the jar set (SHA1 over every jar's path and contents) is stored next to the
+//! extracted files, so launches with an unchanged set skip extraction entirely.
+//! Jars are extracted concurrently into a staging directory that replaces the
+//! old one only after every jar succeeded, so a failed launch never leaves a
+//! half-populated natives directory behind.
+
+use sha1::Digest;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const FINGERPRINT_FILE: &str = ".natives-sha1";
+
+/// Errors raised while preparing natives.
+#[derive(Debug)]
+pub enum NativesError {
+    /// A native jar listed by the version is not on disk
+    MissingJar(PathBuf),
+    /// A native jar could not be read as a zip archive
+    InvalidJar { jar: PathBuf, message: String },
+    /// Filesystem error on a specific path
+    Io { path: PathBuf, source: io::Error },
+    /// An extraction task panicked or was cancelled
+    Task(String),
+}
+
+impl fmt::Display for NativesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NativesError::MissingJar(jar) => {
+                write!(f, "Native library {} is missing", jar.display())
+            }
+            NativesError::InvalidJar { jar, message } => {
+                write!(
+                    f,
+                    "Native library {} is corrupt: {}",
+                    jar.display(),
+                    message
+                )
+            }
+            NativesError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            NativesError::Task(message) => write!(f, "Native extraction failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for NativesError {}
+
+impl From<NativesError> for String {
+    fn from(e: NativesError) -> Self {
+        e.to_string()
+    }
+}
+
+fn io_err(path: &Path) -> impl FnOnce(io::Error) -> NativesError + '_ {
+    move |source| NativesError::Io {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+/// Make sure `natives_dir` contains the extracted contents of `jars`.
+///
+/// # Returns
+/// `true` if the jars were extracted, `false` if the existing directory was up to date.
+pub async fn prepare_natives(jars: &[PathBuf], natives_dir: &Path) -> Result<bool, NativesError> {
+    let jars = jars.to_vec();
+    let fingerprint = {
+        let jars = jars.clone();
+        tokio::task::spawn_blocking(move || fingerprint(&jars))
+            .await
+            .map_err(|e| NativesError::Task(e.to_string()))??
+    };
+
+    let stamp_path = natives_dir.join(FINGERPRINT_FILE);
+    if let Ok(existing) = tokio::fs::read_to_string(&stamp_path).await {
+        if existing.trim() == fingerprint {
+            return Ok(false);
+        }
+    }
+
+    let staging_dir = natives_dir.with_extension("tmp");
+    if staging_dir.exists() {
+        tokio::fs::remove_dir_all(&staging_dir)
+            .await
+            .map_err(io_err(&staging_dir))?;
+    }
+    tokio::fs::create_dir_all(&staging_dir)
+        .await
+        .map_err(io_err(&staging_dir))?;
+
+    let handles: Vec<_> = jars
+        .into_iter()
+        .map(|jar| {
+            let dest = staging_dir.clone();
+            tokio::task::spawn_blocking(move || extract_native_jar(&jar, &dest))
+        })
+        .collect();
+
+    let mut result = Ok(());
+    for handle in handles {
+        let outcome = handle
+            .await
+            .map_err(|e| NativesError::Task(e.to_string()))
+            .and_then(|r| r);
+        if result.is_ok() {
+            result = outcome;
+        }
+    }
+    if let Err(e) = result {
+        let _ = tokio::fs::remove_dir_all(&staging_dir).await;
+        return Err(e);
+    }
+
+    tokio::fs::write(staging_dir.join(FINGERPRINT_FILE), &fingerprint)
+        .await
+        .map_err(io_err(&staging_dir))?;
+
+    if natives_dir.exists() {
+        tokio::fs::remove_dir_all(natives_dir)
+            .await
+            .map_err(io_err(natives_dir))?;
+    }
+    tokio::fs::rename(&staging_dir, natives_dir)
+        .await
+        .map_err(io_err(natives_dir))?;
+
+    Ok(true)
+}
+
+/// SHA1 over the sorted jar paths and their contents.
+fn fingerprint(jars: &[PathBuf]) -> Result<String, NativesError> {
+    let mut sorted: Vec<&PathBuf> = jars.iter().collect();
+    sorted.sort();
+
+    let mut hasher = sha1::Sha1::new();
+    for jar in sorted {
+        if !jar.exists() {
+            return Err(NativesError::MissingJar(jar.clone()));
+        }
+        let data = fs::read(jar).map_err(io_err(jar))?;
+        hasher.update(jar.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update(&data);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Extract a native jar, skipping `META-INF` and directory entries.
+fn extract_native_jar(jar: &Path, dest: &Path) -> Result<(), NativesError> {
+    let invalid = |message: String| NativesError::InvalidJar {
+        jar: jar.to_path_buf(),
+        message,
+    };
+
+    let file = fs::File::open(jar).map_err(io_err(jar))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| invalid(e.to_string()))?;
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| invalid(e.to_string()))?;
+        let Some(rel) = entry.enclosed_name() else {
+            continue;
+        };
+        if entry.is_dir() || rel.starts_with("META-INF") {
+            continue;
+        }
+
+        let out_path = dest.join(rel);
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent).map_err(io_err(parent))?;
+        }
+        let mut out = fs::File::create(&out_path).map_err(io_err(&out_path))?;
+        io::copy(&mut entry, &mut out).map_err(io_err(&out_path))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_missing_jar() {
+        let jar = PathBuf::from("/nonexistent/lwjgl-natives.jar");
+        assert!(matches!(
+            fingerprint(&[jar]),
+            Err(NativesError::MissingJar(_))
+        ));
+    }
+
+    #[test]
+    fn test_fingerprint_order_independent() {
+        let dir =
+            std::env::temp_dir().join(format!("dropout-natives-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let a = dir.join("a.jar");
+        let b = dir.join("b.jar");
+        fs::write(&a, b"a").unwrap();
+        fs::write(&b, b"b").unwrap();
+
+        let first = fingerprint(&[a.clone(), b.clone()]).unwrap();
+        let second = fingerprint(&[b.clone(), a.clone()]).unwrap();
+        fs::write(&b, b"changed").unwrap();
+        let third = fingerprint(&[a, b]).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(first, second);
+        assert_ne!(first, third);
+    }
+}
@@ -0,0 +1,203 @@
+//! Official Minecraft news and patch notes.
+//!
+//! Reads Mojang's launcher content API (the same feed the official launcher
+//! shows) and normalizes it into typed structs. Responses go through
+//! `http_cache`, so the home screen can poll freely.
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::http_cache;
+
+const CONTENT_BASE_URL: &str = "https://launchercontent.mojang.com";
+
+/// A news tile.
+#[derive(Debug, Clone, Serialize)]
+pub struct NewsItem {
+    pub id: String,
+    pub title: String,
+    pub category: String,
+    pub tag: Option<String>,
+    /// ISO date (`YYYY-MM-DD`)
+    pub date: String,
+    pub text: String,
+    pub image_url: Option<String>,
+    pub read_more_url: Option<String>,
+    /// Editions/pages the item targets, e.g. "Java Edition", "News page"
+    pub news_types: Vec<String>,
+}
+
+/// A Java Edition patch note entry.
+#[derive(Debug, Clone, Serialize)]
+pub struct PatchNote {
+    pub id: String,
+    pub title: String,
+    /// Minecraft version id, e.g. "1.20.4" or "24w03a"
+    pub version: String,
+    /// "release" or "snapshot"
+    pub version_type: String,
+    pub date: String,
+    pub short_text: String,
+    pub image_url: Option<String>,
+    /// URL of the full patch note JSON
+    pub content_url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MinecraftNews {
+    pub news: Vec<NewsItem>,
+    /// Newest first
+    pub patch_notes: Vec<PatchNote>,
+}
+
+#[derive(Deserialize)]
+struct RawImage {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct RawNewsFeed {
+    #[serde(default)]
+    entries: Vec<RawNewsEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawNewsEntry {
+    id: String,
+    title: String,
+    #[serde(default)]
+    category: String,
+    tag: Option<String>,
+    #[serde(default)]
+    date: String,
+    #[serde(default)]
+    text: String,
+    news_page_image: Option<RawImage>,
+    play_page_image: Option<RawImage>,
+    read_more_link: Option<String>,
+    #[serde(default)]
+    news_type: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RawPatchNotes {
+    #[serde(default)]
+    entries: Vec<RawPatchNote>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawPatchNote {
+    id: String,
+    title: String,
+    version: String,
+    #[serde(rename = "type", default)]
+    version_type: String,
+    #[serde(default)]
+    date: String,
+    #[serde(default)]
+    short_text: String,
+    image: Option<RawImage>,
+    content_path: String,
+}
+
+/// Make a content API path absolute.
+pub fn content_url(path: &str) -> String {
+    if path.starts_with("http://") || path.starts_with("https://") {
+        path.to_string()
+    } else if path.starts_with('/') {
+        format!("{}{}", CONTENT_BASE_URL, path)
+    } else {
+        format!("{}/v2/{}", CONTENT_BASE_URL, path)
+    }
+}
+
+/// Fetch news tiles and Java Edition patch notes.
+pub async fn fetch_news() -> Result<MinecraftNews, String> {
+    let news_url = format!("{}/v2/news.json", CONTENT_BASE_URL);
+    let (feed, notes) = tokio::join!(
+        http_cache::get_json::<RawNewsFeed>(&news_url, http_cache::TTL_HOUR),
+        fetch_patch_notes(),
+    );
+    let feed = feed?;
+
+    let news = feed
+        .entries
+        .into_iter()
+        .map(|e| NewsItem {
+            id: e.id,
+            title: e.title,
+            category: e.category,
+            tag: e.tag,
+            date: e.date,
+            text: e.text,
+            image_url: e
+                .news_page_image
+                .or(e.play_page_image)
+                .map(|i| content_url(&i.url)),
+            read_more_url: e.read_more_link,
+            news_types: e.news_type,
+        })
+        .collect();
+
+    Ok(MinecraftNews {
+        news,
+        patch_notes: notes?,
+    })
+}
+
+/// Fetch Java Edition patch notes, newest first.
+pub async fn fetch_patch_notes() -> Result<Vec<PatchNote>, String> {
+    let url = format!("{}/v2/javaPatchNotes.json", CONTENT_BASE_URL);
+    let raw: RawPatchNotes = http_cache::get_json(&url, http_cache::TTL_HOUR).await?;
+    let mut notes: Vec<PatchNote> = raw
+        .entries
+        .into_iter()
+        .map(|n| PatchNote {
+            id: n.id,
+            title: n.title,
+            version: n.version,
+            version_type: n.version_type,
+            date: n.date,
+            short_text: n.short_text,
+            image_url: n.image.map(|i| content_url(&i.url)),
+            content_url: content_url(&n.content_path),
+        })
+        .collect();
+    notes.sort_by(|a, b| b.date.cmp(&a.date));
+    Ok(notes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_url() {
+        assert_eq!(
+            content_url("/v2/images/a.jpg"),
+            "https://launchercontent.mojang.com/v2/images/a.jpg"
+        );
+        assert_eq!(
+            content_url("1.20.4.json"),
+            "https://launchercontent.mojang.com/v2/1.20.4.json"
+        );
+        assert_eq!(content_url("https://x.test/a"), "https://x.test/a");
+    }
+
+    #[test]
+    fn test_parse_patch_notes() {
+        let raw: RawPatchNotes = serde_json::from_str(
+            r#"{"version": 1, "entries": [{
+                "title": "Minecraft Java Edition 1.20.4", "version": "1.20.4", "type": "release",
+                "image": {"title": "x", "url": "/images/1.20.4.jpg"},
+                "contentPath": "1.20.4-a.json", "id": "1.20.4-a", "date": "2023-12-07T10:00:00Z",
+                "shortText": "A fix"
+            }]}"#,
+        )
+        .unwrap();
+        assert_eq!(raw.entries.len(), 1);
+        assert_eq!(raw.entries[0].version_type, "release");
+        assert_eq!(raw.entries[0].content_path, "1.20.4-a.json");
+    }
+}
@@ -0,0 +1,270 @@
+//! Standalone OptiFine installation.
+//!
+//! Takes an OptiFine installer jar supplied by the user (OptiFine cannot be
+//! downloaded automatically), patches it against the vanilla client jar with
+//! the installer's own headless `optifine.Patcher`, stores the result as a
+//! library and writes a version JSON inheriting from vanilla that launches
+//! through LaunchWrapper with the OptiFine tweaker.
+
+use serde::Serialize;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+const TWEAK_CLASS: &str = "optifine.OptiFineTweaker";
+const LAUNCH_WRAPPER: &str = "net.minecraft:launchwrapper:1.12";
+
+/// Version information parsed from an installer file name.
+#[derive(Debug, PartialEq, Eq)]
+pub struct OptiFineRelease {
+    pub minecraft_version: String,
+    /// e.g. "HD_U_I6" or "HD_U_I7_pre1"
+    pub edition: String,
+}
+
+impl OptiFineRelease {
+    /// Parse `OptiFine_<mc>_<edition>.jar` or `preview_OptiFine_<mc>_<edition>.jar`.
+    pub fn from_file_name(name: &str) -> Option<Self> {
+        let stem = name.strip_suffix(".jar")?;
+        let rest = stem
+            .strip_prefix("preview_")
+            .unwrap_or(stem)
+            .strip_prefix("OptiFine_")?;
+        let (minecraft_version, edition) = rest.split_once('_')?;
+        if minecraft_version.is_empty() || edition.is_empty() {
+            return None;
+        }
+        Some(Self {
+            minecraft_version: minecraft_version.to_string(),
+            edition: edition.to_string(),
+        })
+    }
+
+    pub fn version_id(&self) -> String {
+        format!("{}-OptiFine_{}", self.minecraft_version, self.edition)
+    }
+
+    fn library_version(&self) -> String {
+        format!("{}_{}", self.minecraft_version, self.edition)
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct InstalledOptiFineVersion {
+    pub id: String,
+    pub minecraft_version: String,
+    pub edition: String,
+    pub path: PathBuf,
+}
+
+/// Check an installer jar and return the release it contains.
+pub fn inspect_installer(installer: &Path) -> Result<OptiFineRelease, String> {
+    let file_name = installer
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or("Invalid installer path")?;
+    let release = OptiFineRelease::from_file_name(file_name).ok_or_else(|| {
+        format!(
+            "{} is not an OptiFine installer (expected OptiFine_<version>_<edition>.jar)",
+            file_name
+        )
+    })?;
+    let archive = open_jar(installer)?;
+    if !archive
+        .file_names()
+        .any(|n| n.starts_with("optifine/") || n.starts_with("net/optifine/"))
+    {
+        return Err(format!("{} does not contain OptiFine", file_name));
+    }
+    Ok(release)
+}
+
+/// Required Java major version of the vanilla version, from its JSON (8 if absent).
+pub fn required_java_major(game_dir: &Path, minecraft_version: &str) -> u32 {
+    let json_path = game_dir
+        .join("versions")
+        .join(minecraft_version)
+        .join(format!("{}.json", minecraft_version));
+    fs::read_to_string(json_path)
+        .ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+        .and_then(|v| v["javaVersion"]["majorVersion"].as_u64())
+        .unwrap_or(8) as u32
+}
+
+/// Install OptiFine from `installer` into `game_dir`.
+///
+/// The vanilla version must already be installed (its JSON and client jar are
+/// needed for patching).
+pub async fn install_optifine(
+    game_dir: &Path,
+    installer: &Path,
+    java_path: &Path,
+) -> Result<InstalledOptiFineVersion, String> {
+    let release = inspect_installer(installer)?;
+    let mc = &release.minecraft_version;
+
+    let parent_dir = game_dir.join("versions").join(mc);
+    let parent_json = parent_dir.join(format!("{}.json", mc));
+    let client_jar = parent_dir.join(format!("{}.jar", mc));
+    if !parent_json.exists() || !client_jar.exists() {
+        return Err(format!(
+            "Minecraft {} must be installed before adding OptiFine",
+            mc
+        ));
+    }
+
+    let libraries_dir = game_dir.join("libraries");
+    let lib_version = release.library_version();
+    let lib_path = libraries_dir
+        .join("optifine")
+        .join("OptiFine")
+        .join(&lib_version)
+        .join(format!("OptiFine-{}.jar", lib_version));
+    if let Some(parent) = lib_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    let mut archive = open_jar(installer)?;
+    if archive.by_name("optifine/Patcher.class").is_ok() {
+        // Produces a jar holding only the classes OptiFine changes
+        let output = tokio::process::Command::new(java_path)
+            .arg("-cp")
+            .arg(installer)
+            .arg("optifine.Patcher")
+            .arg(&client_jar)
+            .arg(installer)
+            .arg(&lib_path)
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run Java at {}: {}", java_path.display(), e))?;
+        if !output.status.success() {
+            let _ = fs::remove_file(&lib_path);
+            return Err(format!(
+                "OptiFine patcher failed:\n{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+    } else {
+        // Old releases are used unpatched as a library
+        fs::copy(installer, &lib_path).map_err(|e| e.to_string())?;
+    }
+
+    // Newer releases bundle their own LaunchWrapper fork, which works on modern Java
+    let launch_wrapper = match read_entry(&mut archive, "launchwrapper-of.txt") {
+        Some(version) => {
+            let version = String::from_utf8_lossy(&version).trim().to_string();
+            let jar_name = format!("launchwrapper-of-{}.jar", version);
+            let data = read_entry(&mut archive, &jar_name)
+                .ok_or_else(|| format!("Installer is missing {}", jar_name))?;
+            let path = libraries_dir
+                .join("optifine")
+                .join("launchwrapper-of")
+                .join(&version)
+                .join(&jar_name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            fs::write(&path, data).map_err(|e| e.to_string())?;
+            format!("optifine:launchwrapper-of:{}", version)
+        }
+        None => LAUNCH_WRAPPER.to_string(),
+    };
+
+    let parent: serde_json::Value = fs::read_to_string(&parent_json)
+        .map_err(|e| e.to_string())
+        .and_then(|c| serde_json::from_str(&c).map_err(|e| e.to_string()))?;
+    let version_json = create_version_json(&release, &launch_wrapper, &parent);
+
+    let version_id = release.version_id();
+    let version_dir = game_dir.join("versions").join(&version_id);
+    fs::create_dir_all(&version_dir).map_err(|e| e.to_string())?;
+    let json_path = version_dir.join(format!("{}.json", version_id));
+    let content = serde_json::to_string_pretty(&version_json).map_err(|e| e.to_string())?;
+    fs::write(&json_path, content).map_err(|e| e.to_string())?;
+
+    Ok(InstalledOptiFineVersion {
+        id: version_id,
+        minecraft_version: release.minecraft_version,
+        edition: release.edition,
+        path: json_path,
+    })
+}
+
+/// Build the inheriting version JSON. Legacy parents use `minecraftArguments`,
+/// which a child replaces rather than extends, so the tweaker is appended to a
+/// copy of the parent's string.
+fn create_version_json(
+    release: &OptiFineRelease,
+    launch_wrapper: &str,
+    parent: &serde_json::Value,
+) -> serde_json::Value {
+    let mut json = serde_json::json!({
+        "id": release.version_id(),
+        "inheritsFrom": release.minecraft_version,
+        "type": "release",
+        "mainClass": "net.minecraft.launchwrapper.Launch",
+        "libraries": [
+            { "name": format!("optifine:OptiFine:{}", release.library_version()) },
+            { "name": launch_wrapper },
+        ],
+    });
+    match parent["minecraftArguments"].as_str() {
+        Some(args) => {
+            json["minecraftArguments"] =
+                serde_json::Value::String(format!("{} --tweakClass {}", args, TWEAK_CLASS));
+        }
+        None => {
+            json["arguments"] = serde_json::json!({
+                "game": ["--tweakClass", TWEAK_CLASS],
+                "jvm": [],
+            });
+        }
+    }
+    json
+}
+
+fn open_jar(path: &Path) -> Result<zip::ZipArchive<fs::File>, String> {
+    let file = fs::File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    zip::ZipArchive::new(file).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn read_entry(archive: &mut zip::ZipArchive<fs::File>, name: &str) -> Option<Vec<u8>> {
+    let mut entry = archive.by_name(name).ok()?;
+    let mut data = Vec::new();
+    entry.read_to_end(&mut data).ok()?;
+    Some(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file_name() {
+        let release = OptiFineRelease::from_file_name("OptiFine_1.20.1_HD_U_I6.jar").unwrap();
+        assert_eq!(release.minecraft_version, "1.20.1");
+        assert_eq!(release.edition, "HD_U_I6");
+        assert_eq!(release.version_id(), "1.20.1-OptiFine_HD_U_I6");
+        assert_eq!(
+            OptiFineRelease::from_file_name("preview_OptiFine_1.20.4_HD_U_I7_pre3.jar")
+                .unwrap()
+                .edition,
+            "HD_U_I7_pre3"
+        );
+        assert!(OptiFineRelease::from_file_name("forge-installer.jar").is_none());
+    }
+
+    #[test]
+    fn test_legacy_arguments() {
+        let release = OptiFineRelease::from_file_name("OptiFine_1.8.9_HD_U_M5.jar").unwrap();
+        let parent = serde_json::json!({ "minecraftArguments": "--username ${auth_player_name}" });
+        let json = create_version_json(&release, LAUNCH_WRAPPER, &parent);
+        assert_eq!(
+            json["minecraftArguments"],
+            "--username ${auth_player_name} --tweakClass optifine.OptiFineTweaker"
+        );
+        assert!(json.get("arguments").is_none());
+    }
+}
@@ -0,0 +1,159 @@
+//! Background pre-downloading of updates.
+//!
+//! When enabled, the launcher checks every `interval_hours` for a new
+//! Minecraft release (and snapshot, if snapshots are shown), newer Fabric
+//! loaders for the Minecraft versions instances play on, and Temurin patch
+//! releases of the Java runtimes it manages. Their files are downloaded only
+//! while no game runs and nothing else downloads, under the configured
+//! bandwidth cap, so the next install or launch finds them in place.
+//!
+//! Nothing is switched over: instances keep their versions, and a Java patch
+//! waits as an archive next to the runtime it replaces until that runtime is
+//! reinstalled.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::core::java::{ImageType, JavaProvenance, JavaVendor};
+use crate::utils::file_utils;
+
+/// How often the job checks whether a run is due and the launcher is idle
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+const STATE_FILE: &str = "prefetch.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrefetchState {
+    /// Unix time of the last completed run
+    pub last_run: u64,
+}
+
+impl PrefetchState {
+    pub fn load(data_dir: &Path) -> Self {
+        file_utils::read_json(&data_dir.join(STATE_FILE)).unwrap_or_default()
+    }
+
+    pub fn save(&self, data_dir: &Path) -> Result<(), String> {
+        file_utils::write_json_atomic(&data_dir.join(STATE_FILE), self).map_err(|e| e.to_string())
+    }
+
+    pub fn is_due(&self, interval_hours: u32, now: u64) -> bool {
+        now.saturating_sub(self.last_run) >= interval_hours.max(1) as u64 * 3600
+    }
+}
+
+/// Minecraft version and loader version of a Fabric version id, e.g.
+/// `fabric-loader-0.15.6-1.20.4`.
+pub fn parse_fabric_version(version_id: &str) -> Option<(String, String)> {
+    let rest = version_id.strip_prefix("fabric-loader-")?;
+    let (loader, minecraft) = rest.split_once('-')?;
+    (!loader.is_empty() && !minecraft.is_empty())
+        .then(|| (minecraft.to_string(), loader.to_string()))
+}
+
+/// A Java runtime installed by the launcher under `java/`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManagedRuntime {
+    pub vendor: JavaVendor,
+    pub major_version: u32,
+    pub image_type: ImageType,
+    /// Folder the runtime was extracted to, named after its release
+    pub release_dir: String,
+    /// Release recorded at install, missing for runtimes installed before
+    /// provenance was kept
+    pub release_name: Option<String>,
+}
+
+impl ManagedRuntime {
+    /// Whether `release_name`, e.g. `jdk-21.0.4+7`, is the installed release.
+    /// Without a recorded release, the folder name is compared; JRE folders
+    /// carry a `-jre` suffix.
+    pub fn is_release(&self, release_name: &str) -> bool {
+        if let Some(installed) = &self.release_name {
+            return installed == release_name;
+        }
+        self.release_dir
+            .strip_prefix(release_name)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+    }
+}
+
+/// Runtimes in `java_dir`, which holds a `<vendor>-<major>-<jre|jdk>` folder
+/// per runtime.
+pub fn managed_runtimes(java_dir: &Path) -> Vec<ManagedRuntime> {
+    let Ok(entries) = std::fs::read_dir(java_dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let (vendor, major_version, image_type) = crate::core::java::parse_runtime_dir(&name)?;
+            // Extracted archives hold a single folder named after the release
+            let mut dirs = std::fs::read_dir(entry.path())
+                .ok()?
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_dir());
+            let release = dirs.next()?;
+            if dirs.next().is_some() {
+                return None;
+            }
+            Some(ManagedRuntime {
+                vendor,
+                major_version,
+                image_type,
+                release_dir: release.file_name().to_string_lossy().to_string(),
+                release_name: JavaProvenance::load(&entry.path()).map(|p| p.release_name),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fabric_version() {
+        assert_eq!(
+            parse_fabric_version("fabric-loader-0.15.6-1.20.4"),
+            Some(("1.20.4".to_string(), "0.15.6".to_string()))
+        );
+        assert_eq!(
+            parse_fabric_version("fabric-loader-0.16.0-24w14a"),
+            Some(("24w14a".to_string(), "0.16.0".to_string()))
+        );
+        assert_eq!(parse_fabric_version("1.20.4"), None);
+        assert_eq!(parse_fabric_version("fabric-loader-0.15.6"), None);
+    }
+
+    #[test]
+    fn test_runtime_release() {
+        let mut runtime = ManagedRuntime {
+            vendor: JavaVendor::Adoptium,
+            major_version: 21,
+            image_type: ImageType::Jre,
+            release_dir: "jdk-21.0.4+7-jre".to_string(),
+            release_name: None,
+        };
+        assert!(runtime.is_release("jdk-21.0.4+7"));
+        assert!(!runtime.is_release("jdk-21.0.5+11"));
+        assert!(!runtime.is_release("jdk-21.0.4+70"));
+
+        // GraalVM folders are not named after the release tag
+        runtime.release_dir = "graalvm-community-openjdk-21.0.2+13.1".to_string();
+        runtime.release_name = Some("jdk-21.0.2".to_string());
+        assert!(runtime.is_release("jdk-21.0.2"));
+        assert!(!runtime.is_release("jdk-21.0.3"));
+    }
+
+    #[test]
+    fn test_is_due() {
+        let state = PrefetchState { last_run: 10_000 };
+        assert!(!state.is_due(6, 10_000 + 3600));
+        assert!(state.is_due(6, 10_000 + 6 * 3600));
+        assert!(PrefetchState::default().is_due(6, 100_000));
+    }
+}
@@ -39,9 +39,20 @@ pub fn is_library_allowed(rules: &Option<Vec<Rule>>) -> bool {
     let mut allowed = false;
 
     for rule in rules {
-        if rule_matches(rule) {
+        let matches = rule_matches(rule);
+        if matches {
             allowed = rule.action == "allow";
         }
+        crate::core::logging::debug(
+            "Rules",
+            format!(
+                "{} os={:?} features={}: {}",
+                rule.action,
+                rule.os.as_ref().and_then(|os| os.name.as_deref()),
+                rule.features.is_some(),
+                if matches { "applies" } else { "does not apply" }
+            ),
+        );
     }
     allowed
 }
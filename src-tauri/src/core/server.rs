@@ -0,0 +1,387 @@
+//! Dedicated server instances.
+//!
+//! A server instance is a directory under `servers/<id>/` holding a vanilla,
+//! Fabric or Forge dedicated server. Running servers are tracked in memory
+//! with a channel feeding their stdin, so console commands can be sent from
+//! the UI.
+//!
+//! Layout:
+//! - `servers.json` - all `ServerInstance`s
+//! - `servers/<id>/` - the server's working directory
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+
+use crate::core::config::EffectiveConfig;
+use crate::core::downloader::{download_files, DownloadTask};
+use crate::core::events::EventSink;
+use crate::core::game_version::GameVersion;
+use crate::core::manifest::LoaderType;
+
+const FABRIC_META_URL: &str = "https://meta.fabricmc.net/v2";
+const SERVER_JAR: &str = "server.jar";
+
+/// A dedicated server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerInstance {
+    pub id: String,
+    pub name: String,
+    pub minecraft_version: String,
+    pub loader: LoaderType,
+    pub loader_version: Option<String>,
+    /// Maximum heap in MiB
+    pub memory_mb: u32,
+    pub port: u16,
+    /// Java major version the server needs
+    pub java_major: u32,
+    /// Java executable; picked automatically when unset
+    #[serde(default)]
+    pub java_path: Option<String>,
+    pub created_at: u64,
+    /// Not persisted; filled in when listing
+    #[serde(skip_deserializing, default)]
+    pub running: bool,
+}
+
+/// Parameters for creating a server.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewServer {
+    pub name: String,
+    pub minecraft_version: String,
+    pub loader: LoaderType,
+    pub loader_version: Option<String>,
+    pub memory_mb: u32,
+    pub port: u16,
+    /// The Minecraft EULA must be accepted before a server may run
+    pub accept_eula: bool,
+}
+
+/// Managed state for server instances and running server processes.
+pub struct ServerManager {
+    servers: Mutex<Vec<ServerInstance>>,
+    pub servers_dir: PathBuf,
+    index_path: PathBuf,
+    /// Stdin channels of running servers, by id
+    running: Mutex<HashMap<String, mpsc::UnboundedSender<String>>>,
+}
+
+impl ServerManager {
+    pub fn new(app_data_dir: &Path) -> Self {
+        let index_path = app_data_dir.join("servers.json");
+        let servers = fs::read_to_string(&index_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            servers: Mutex::new(servers),
+            servers_dir: app_data_dir.join("servers"),
+            index_path,
+            running: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn server_dir(&self, id: &str) -> PathBuf {
+        self.servers_dir.join(id)
+    }
+
+    fn save(&self, servers: &[ServerInstance]) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(servers).map_err(|e| e.to_string())?;
+        if let Some(parent) = self.index_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(&self.index_path, content).map_err(|e| e.to_string())
+    }
+
+    pub fn list(&self) -> Vec<ServerInstance> {
+        let running = self.running.lock().unwrap();
+        let mut servers = self.servers.lock().unwrap().clone();
+        for server in &mut servers {
+            server.running = running.contains_key(&server.id);
+        }
+        servers
+    }
+
+    pub fn get(&self, id: &str) -> Result<ServerInstance, String> {
+        self.list()
+            .into_iter()
+            .find(|s| s.id == id)
+            .ok_or_else(|| format!("Server {} not found", id))
+    }
+
+    /// Register a server whose files were installed into `server_dir(server.id)`.
+    pub fn add(&self, server: ServerInstance) -> Result<(), String> {
+        let mut servers = self.servers.lock().unwrap();
+        servers.push(server);
+        self.save(&servers)
+    }
+
+    /// Update a server's settings (memory, port, Java).
+    pub fn update(&self, server: &ServerInstance) -> Result<(), String> {
+        let mut servers = self.servers.lock().unwrap();
+        let existing = servers
+            .iter_mut()
+            .find(|s| s.id == server.id)
+            .ok_or_else(|| format!("Server {} not found", server.id))?;
+        existing.name = server.name.clone();
+        existing.memory_mb = server.memory_mb;
+        existing.java_path = server.java_path.clone();
+        if existing.port != server.port {
+            existing.port = server.port;
+            write_server_properties(
+                &self.server_dir(&server.id).join("server.properties"),
+                &[("server-port", server.port.to_string())],
+            )?;
+        }
+        self.save(&servers)
+    }
+
+    /// Delete a stopped server and its directory.
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        if self.is_running(id) {
+            return Err("Stop the server before deleting it".to_string());
+        }
+        let mut servers = self.servers.lock().unwrap();
+        if !servers.iter().any(|s| s.id == id) {
+            return Err(format!("Server {} not found", id));
+        }
+        let dir = self.server_dir(id);
+        if dir.exists() {
+            fs::remove_dir_all(&dir).map_err(|e| format!("Failed to delete server: {}", e))?;
+        }
+        servers.retain(|s| s.id != id);
+        self.save(&servers)
+    }
+
+    pub fn is_running(&self, id: &str) -> bool {
+        self.running.lock().unwrap().contains_key(id)
+    }
+
+    /// Track a started server; returns the receiver that feeds its stdin.
+    pub fn mark_running(&self, id: &str) -> Result<mpsc::UnboundedReceiver<String>, String> {
+        let mut running = self.running.lock().unwrap();
+        if running.contains_key(id) {
+            return Err("Server is already running".to_string());
+        }
+        let (tx, rx) = mpsc::unbounded_channel();
+        running.insert(id.to_string(), tx);
+        Ok(rx)
+    }
+
+    pub fn mark_stopped(&self, id: &str) {
+        self.running.lock().unwrap().remove(id);
+    }
+
+    /// Send a console command to a running server.
+    pub fn send_command(&self, id: &str, command: &str) -> Result<(), String> {
+        let running = self.running.lock().unwrap();
+        let tx = running
+            .get(id)
+            .ok_or_else(|| "Server is not running".to_string())?;
+        tx.send(command.trim().to_string())
+            .map_err(|_| "Server console is closed".to_string())
+    }
+}
+
+/// Set keys in a `server.properties` file, keeping every other line.
+pub fn write_server_properties(path: &Path, values: &[(&str, String)]) -> Result<(), String> {
+    let existing = fs::read_to_string(path).unwrap_or_default();
+    let mut remaining: Vec<&(&str, String)> = values.iter().collect();
+    let mut lines: Vec<String> = existing
+        .lines()
+        .map(|line| {
+            let key = line.split('=').next().unwrap_or_default().trim();
+            match remaining.iter().position(|(k, _)| *k == key) {
+                Some(i) if !line.starts_with('#') => {
+                    let (k, v) = remaining.remove(i);
+                    format!("{}={}", k, v)
+                }
+                _ => line.to_string(),
+            }
+        })
+        .collect();
+    lines.extend(remaining.iter().map(|(k, v)| format!("{}={}", k, v)));
+    fs::write(path, lines.join("\n") + "\n").map_err(|e| e.to_string())
+}
+
+/// Record acceptance of the Minecraft EULA in `eula.txt`.
+pub fn accept_eula(server_dir: &Path) -> Result<(), String> {
+    fs::write(
+        server_dir.join("eula.txt"),
+        "# Accepted in DropOut (https://aka.ms/MinecraftEULA)\neula=true\n",
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Download the vanilla server jar for `minecraft_version` into `server_dir`.
+/// Returns the Java major version the server needs.
+pub async fn install_vanilla_server(
+    events: &EventSink,
+    config: &EffectiveConfig,
+    server_dir: &Path,
+    minecraft_version: &str,
+) -> Result<u32, String> {
+    let version = crate::core::manifest::fetch_vanilla_version(minecraft_version)
+        .await
+        .map_err(|e| e.to_string())?;
+    write_server_jar(events, config, &version, &server_dir.join(SERVER_JAR)).await
+}
+
+/// Download the dedicated server jar of `version_id` to `path`, e.g. for a
+/// quick test server next to the client. Mod loader versions get the vanilla
+/// server of the Minecraft version they are based on. Returns the Java major
+/// version the server needs.
+pub async fn download_server_jar(
+    events: &EventSink,
+    config: &EffectiveConfig,
+    game_dir: &PathBuf,
+    version_id: &str,
+    path: &Path,
+) -> Result<u32, String> {
+    let version = crate::core::manifest::load_version(game_dir, version_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    write_server_jar(events, config, &version, path).await
+}
+
+/// Download the `downloads.server` artifact of `version` to `path`, checking
+/// its size and SHA-1.
+async fn write_server_jar(
+    events: &EventSink,
+    config: &EffectiveConfig,
+    version: &GameVersion,
+    path: &Path,
+) -> Result<u32, String> {
+    let server = version
+        .downloads
+        .as_ref()
+        .and_then(|d| d.server.clone())
+        .ok_or_else(|| format!("Minecraft {} has no dedicated server", version.id))?;
+
+    let task = DownloadTask {
+        url: server.url,
+        path: path.to_path_buf(),
+        sha1: server.sha1,
+        sha256: None,
+        size: server.size,
+        mirrors: Vec::new(),
+    };
+    download_files(events.clone(), vec![task], config).await?;
+
+    Ok(version
+        .java_version
+        .as_ref()
+        .map(|j| j.major_version as u32)
+        .unwrap_or(8))
+}
+
+#[derive(Deserialize)]
+struct FabricInstallerVersion {
+    version: String,
+    stable: bool,
+}
+
+/// Download the Fabric server launcher, which fetches the vanilla server on first start.
+pub async fn install_fabric_server(
+    events: &EventSink,
+    config: &EffectiveConfig,
+    server_dir: &Path,
+    minecraft_version: &str,
+    loader_version: &str,
+) -> Result<(), String> {
+    let installers: Vec<FabricInstallerVersion> = crate::core::http_cache::get_json(
+        &format!("{}/versions/installer", FABRIC_META_URL),
+        crate::core::http_cache::TTL_HOUR,
+    )
+    .await?;
+    let installer = installers
+        .iter()
+        .find(|i| i.stable)
+        .or(installers.first())
+        .ok_or("No Fabric installer versions available")?;
+
+    let url = format!(
+        "{}/versions/loader/{}/{}/{}/server/jar",
+        FABRIC_META_URL, minecraft_version, loader_version, installer.version
+    );
+    let path = server_dir.join(SERVER_JAR);
+    // Replaces the vanilla jar; without a hash an existing file would be kept
+    let _ = fs::remove_file(&path);
+    let task = DownloadTask {
+        url,
+        path,
+        sha1: None,
+        sha256: None,
+        size: None,
+        mirrors: Vec::new(),
+    };
+    download_files(events.clone(), vec![task], config).await
+}
+
+/// Arguments after the memory flags that start the server, relative to its directory.
+pub fn launch_args(server_dir: &Path, server: &ServerInstance) -> Result<Vec<String>, String> {
+    if server.loader != LoaderType::Forge {
+        return Ok(vec!["-jar".to_string(), SERVER_JAR.to_string()]);
+    }
+
+    let full = format!(
+        "{}-{}",
+        server.minecraft_version,
+        server.loader_version.as_deref().unwrap_or_default()
+    );
+    // 1.17+ installers write an argument file instead of an executable jar
+    let args_file = format!(
+        "libraries/net/minecraftforge/forge/{}/{}",
+        full,
+        if cfg!(windows) {
+            "win_args.txt"
+        } else {
+            "unix_args.txt"
+        }
+    );
+    if server_dir.join(&args_file).exists() {
+        return Ok(vec![format!("@{}", args_file)]);
+    }
+    for suffix in ["-shim.jar", ".jar", "-universal.jar"] {
+        let jar = format!("forge-{}{}", full, suffix);
+        if server_dir.join(&jar).exists() {
+            return Ok(vec!["-jar".to_string(), jar]);
+        }
+    }
+    Err(format!("No Forge server jar found for {}", full))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_server_properties() {
+        let dir = std::env::temp_dir().join(format!("dropout-server-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("server.properties");
+        fs::write(
+            &path,
+            "#Minecraft server properties\nserver-port=25565\npvp=true\n",
+        )
+        .unwrap();
+
+        write_server_properties(
+            &path,
+            &[
+                ("server-port", "25570".to_string()),
+                ("motd", "Hello".to_string()),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "#Minecraft server properties\nserver-port=25570\npvp=true\nmotd=Hello\n"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
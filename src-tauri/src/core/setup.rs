@@ -0,0 +1,216 @@
+//! First-launch setup.
+//!
+//! Each step (system detection, Java, settings, default instance) is usable
+//! on its own; `run_initial_setup` in `main.rs` chains them and reports
+//! progress through `setup-step` events.
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, Window};
+
+use crate::core::java::{self, JavaInstallation};
+
+/// Hardware relevant to the default settings.
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemInfo {
+    pub total_memory_mb: u64,
+    pub cpu_count: usize,
+    pub os: String,
+    pub arch: String,
+}
+
+pub fn detect_system() -> SystemInfo {
+    let mut system = sysinfo::System::new();
+    system.refresh_memory();
+    SystemInfo {
+        total_memory_mb: system.total_memory() / 1024 / 1024,
+        cpu_count: std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+    }
+}
+
+/// Heap sizes in MiB (min, max) for a machine with `total_mb` of RAM.
+///
+/// Leaves at least half of the memory to the system and caps the heap at
+/// 8 GiB, beyond which vanilla and most modpacks gain nothing.
+pub fn recommended_memory(total_mb: u64) -> (u32, u32) {
+    let max = match total_mb {
+        0..=4096 => 1024,
+        4097..=8192 => 2048,
+        8193..=16384 => 4096,
+        16385..=32768 => 6144,
+        _ => 8192,
+    };
+    (1024, max)
+}
+
+/// Defaults proposed to the user before anything is changed.
+#[derive(Debug, Clone, Serialize)]
+pub struct SetupProposal {
+    pub system: SystemInfo,
+    pub java_installations: Vec<JavaInstallation>,
+    /// Latest release, used for the default instance
+    pub latest_release: String,
+    /// Java major version the latest release needs
+    pub required_java_major: u32,
+    /// Installed Java suitable for the latest release
+    pub java_path: Option<String>,
+    pub min_memory: u32,
+    pub max_memory: u32,
+    pub download_threads: u32,
+}
+
+pub async fn propose_defaults(app_handle: &AppHandle) -> Result<SetupProposal, String> {
+    let system = detect_system();
+    let (min_memory, max_memory) = recommended_memory(system.total_memory_mb);
+
+    let manifest = crate::core::manifest::fetch_version_manifest()
+        .await
+        .map_err(|e| e.to_string())?;
+    let latest_release = manifest.latest.release.clone();
+    let required_java_major = crate::core::manifest::fetch_vanilla_version(&latest_release)
+        .await
+        .map_err(|e| e.to_string())?
+        .java_version
+        .map(|j| j.major_version as u32)
+        .unwrap_or(8);
+
+    Ok(SetupProposal {
+        java_installations: java::detect_all_java_installations(app_handle),
+        java_path: java::find_suitable_java(app_handle, required_java_major, ""),
+        latest_release,
+        required_java_major,
+        min_memory,
+        max_memory,
+        download_threads: (system.cpu_count as u32 * 4).clamp(8, 64),
+        system,
+    })
+}
+
+/// Choices confirmed by the user, usually an edited `SetupProposal`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetupOptions {
+    pub min_memory: u32,
+    pub max_memory: u32,
+    pub download_threads: u32,
+    /// Java to use; ignored when `download_java` is set
+    pub java_path: Option<String>,
+    /// Download Temurin for `required_java_major`
+    pub download_java: bool,
+    pub required_java_major: u32,
+    /// Create an instance for this version, if set
+    pub default_instance_version: Option<String>,
+    #[serde(default)]
+    pub default_instance_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SetupStep {
+    DetectJava,
+    DownloadJava,
+    ApplySettings,
+    CreateInstance,
+    Done,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepStatus {
+    Started,
+    Completed,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SetupStepEvent<'a> {
+    step: SetupStep,
+    status: StepStatus,
+    message: Option<&'a str>,
+}
+
+pub fn emit_step(window: &Window, step: SetupStep, status: StepStatus, message: Option<&str>) {
+    let _ = window.emit(
+        "setup-step",
+        SetupStepEvent {
+            step,
+            status,
+            message,
+        },
+    );
+}
+
+/// Result of `run_initial_setup`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SetupResult {
+    pub java_path: String,
+    pub instance: Option<crate::core::instance::Instance>,
+}
+
+/// Resolve the Java to configure, downloading Temurin if requested or if
+/// no installed Java is suitable.
+pub async fn resolve_java(window: &Window, options: &SetupOptions) -> Result<String, String> {
+    let app_handle = window.app_handle();
+    emit_step(window, SetupStep::DetectJava, StepStatus::Started, None);
+    let detected = match &options.java_path {
+        Some(path) if !options.download_java => Some(path.clone()),
+        _ if options.download_java => None,
+        _ => java::find_suitable_java(app_handle, options.required_java_major, ""),
+    };
+    if let Some(path) = detected {
+        emit_step(
+            window,
+            SetupStep::DetectJava,
+            StepStatus::Completed,
+            Some(&path),
+        );
+        emit_step(window, SetupStep::DownloadJava, StepStatus::Skipped, None);
+        return Ok(path);
+    }
+    emit_step(window, SetupStep::DetectJava, StepStatus::Completed, None);
+
+    emit_step(window, SetupStep::DownloadJava, StepStatus::Started, None);
+    match java::download_and_install_java(
+        app_handle,
+        java::JavaVendor::Adoptium,
+        options.required_java_major,
+        java::ImageType::Jre,
+        None,
+    )
+    .await
+    {
+        Ok(installation) => {
+            emit_step(
+                window,
+                SetupStep::DownloadJava,
+                StepStatus::Completed,
+                Some(&installation.path),
+            );
+            Ok(installation.path)
+        }
+        Err(e) => {
+            emit_step(
+                window,
+                SetupStep::DownloadJava,
+                StepStatus::Failed,
+                Some(&e),
+            );
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recommended_memory() {
+        assert_eq!(recommended_memory(4000), (1024, 1024));
+        assert_eq!(recommended_memory(16 * 1024), (1024, 4096));
+        assert_eq!(recommended_memory(128 * 1024), (1024, 8192));
+    }
+}
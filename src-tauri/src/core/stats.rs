@@ -0,0 +1,195 @@
+//! Playtime tracking.
+//!
+//! Every game session (process start to exit) is appended to `stats.json`;
+//! aggregates are computed on demand.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const WEEK_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// A finished game session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub instance_id: Option<String>,
+    pub version_id: String,
+    /// Unix timestamps (seconds)
+    pub started_at: u64,
+    pub ended_at: u64,
+    /// Seconds spent on each multiplayer server, by `host:port`
+    #[serde(default)]
+    pub servers: BTreeMap<String, u64>,
+}
+
+impl Session {
+    pub fn duration(&self) -> u64 {
+        self.ended_at.saturating_sub(self.started_at)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct StatsFile {
+    sessions: Vec<Session>,
+}
+
+/// Playtime for one instance (or one version, for launches without an instance).
+#[derive(Debug, Clone, Serialize)]
+pub struct InstancePlaytime {
+    pub instance_id: Option<String>,
+    pub version_id: String,
+    pub total_seconds: u64,
+    pub launches: usize,
+    pub last_played: u64,
+}
+
+/// Playtime on one multiplayer server.
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerPlaytime {
+    pub address: String,
+    pub total_seconds: u64,
+}
+
+/// Aggregated playtime statistics.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct PlaytimeStats {
+    pub total_seconds: u64,
+    pub last_7_days_seconds: u64,
+    pub launches: usize,
+    pub average_session_seconds: u64,
+    /// Sorted by total playtime, most played first
+    pub per_instance: Vec<InstancePlaytime>,
+    /// Sorted by total playtime, most played first
+    pub per_server: Vec<ServerPlaytime>,
+}
+
+/// Managed state holding recorded sessions.
+pub struct StatsState {
+    sessions: Mutex<Vec<Session>>,
+    path: PathBuf,
+}
+
+impl StatsState {
+    pub fn new(app_data_dir: &Path) -> Self {
+        let path = app_data_dir.join("stats.json");
+        let file: StatsFile = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            sessions: Mutex::new(file.sessions),
+            path,
+        }
+    }
+
+    /// Append a session and persist.
+    pub fn record(&self, session: Session) -> Result<(), String> {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.push(session);
+        let content = serde_json::to_string_pretty(&StatsFile {
+            sessions: sessions.clone(),
+        })
+        .map_err(|e| e.to_string())?;
+        fs::write(&self.path, content).map_err(|e| e.to_string())
+    }
+
+    pub fn stats(&self, now: u64) -> PlaytimeStats {
+        compute_stats(&self.sessions.lock().unwrap(), now)
+    }
+}
+
+/// Aggregate sessions; sessions partially inside the last 7 days count partially.
+pub fn compute_stats(sessions: &[Session], now: u64) -> PlaytimeStats {
+    let mut stats = PlaytimeStats::default();
+    let week_start = now.saturating_sub(WEEK_SECS);
+    let mut per_key: HashMap<String, InstancePlaytime> = HashMap::new();
+    let mut per_server: HashMap<String, u64> = HashMap::new();
+
+    for session in sessions {
+        let duration = session.duration();
+        stats.total_seconds += duration;
+        stats.launches += 1;
+        if session.ended_at > week_start {
+            stats.last_7_days_seconds += session.ended_at - session.started_at.max(week_start);
+        }
+
+        for (address, seconds) in &session.servers {
+            *per_server.entry(address.clone()).or_default() += seconds;
+        }
+
+        let key = session
+            .instance_id
+            .clone()
+            .unwrap_or_else(|| format!("version:{}", session.version_id));
+        let entry = per_key.entry(key).or_insert_with(|| InstancePlaytime {
+            instance_id: session.instance_id.clone(),
+            version_id: session.version_id.clone(),
+            total_seconds: 0,
+            launches: 0,
+            last_played: 0,
+        });
+        entry.total_seconds += duration;
+        entry.launches += 1;
+        if session.ended_at >= entry.last_played {
+            entry.last_played = session.ended_at;
+            entry.version_id = session.version_id.clone();
+        }
+    }
+
+    if stats.launches > 0 {
+        stats.average_session_seconds = stats.total_seconds / stats.launches as u64;
+    }
+    stats.per_instance = per_key.into_values().collect();
+    stats
+        .per_instance
+        .sort_by_key(|p| std::cmp::Reverse(p.total_seconds));
+    stats.per_server = per_server
+        .into_iter()
+        .map(|(address, total_seconds)| ServerPlaytime {
+            address,
+            total_seconds,
+        })
+        .collect();
+    stats
+        .per_server
+        .sort_by_key(|p| std::cmp::Reverse(p.total_seconds));
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(instance: Option<&str>, start: u64, end: u64) -> Session {
+        Session {
+            instance_id: instance.map(|s| s.to_string()),
+            version_id: "1.20.4".to_string(),
+            started_at: start,
+            ended_at: end,
+            servers: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_compute_stats() {
+        let now = 100 * 24 * 60 * 60;
+        let sessions = vec![
+            session(Some("a"), 0, 3600),
+            session(Some("a"), now - 600, now),
+            session(None, now - WEEK_SECS - 100, now - WEEK_SECS + 200),
+        ];
+        let stats = compute_stats(&sessions, now);
+
+        assert_eq!(stats.launches, 3);
+        assert_eq!(stats.total_seconds, 3600 + 600 + 300);
+        assert_eq!(stats.last_7_days_seconds, 600 + 200);
+        assert_eq!(stats.average_session_seconds, 1500);
+        assert_eq!(stats.per_instance.len(), 2);
+        assert_eq!(stats.per_instance[0].instance_id.as_deref(), Some("a"));
+        assert_eq!(stats.per_instance[0].launches, 2);
+        assert!(stats.per_server.is_empty());
+    }
+}
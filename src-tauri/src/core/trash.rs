@@ -0,0 +1,276 @@
+//! The launcher's trash.
+//!
+//! Deleted instances, worlds and mods are moved to `trash/<item id>/`
+//! instead of being removed, with a `trash.json` recording where they came
+//! from so `restore` can put them back. Items older than the retention set
+//! in the settings are removed at startup, and `empty` removes them all.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::error::LauncherError;
+use crate::core::instance::InstanceManager;
+use crate::utils::file_utils;
+
+const ITEM_FILE: &str = "trash.json";
+/// The moved file or folder inside an item
+const CONTENT: &str = "content";
+/// Zip of an archived instance, trashed along with it
+const ARCHIVE: &str = "archive.zip";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrashKind {
+    Instance,
+    World,
+    Mod,
+}
+
+/// A deleted item, as listed in the trash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashItem {
+    pub id: String,
+    pub kind: TrashKind,
+    /// Instance, world or file name
+    pub name: String,
+    /// Where the item is restored to
+    pub original_path: String,
+    /// The trashed instance, or the one the world or mod belonged to
+    pub instance_id: Option<String>,
+    pub deleted_at: u64,
+    pub size: u64,
+}
+
+/// Trash folder in the launcher's data directory.
+pub fn trash_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("trash")
+}
+
+/// Move `path` into the trash.
+pub fn move_to_trash(
+    trash_dir: &Path,
+    kind: TrashKind,
+    name: &str,
+    path: &Path,
+    instance_id: Option<&str>,
+) -> Result<TrashItem, String> {
+    if !path.exists() {
+        return Err(format!("{} does not exist", path.display()));
+    }
+    let item = TrashItem {
+        id: uuid::Uuid::new_v4().to_string(),
+        kind,
+        name: name.to_string(),
+        original_path: path.to_string_lossy().to_string(),
+        instance_id: instance_id.map(str::to_string),
+        deleted_at: now(),
+        size: file_utils::dir_size(path),
+    };
+    let item_dir = trash_dir.join(&item.id);
+    // The record comes first so moved files are never left unlisted
+    save_item(trash_dir, &item)?;
+    if let Err(e) = move_path(path, &item_dir.join(CONTENT)) {
+        let _ = fs::remove_dir_all(&item_dir);
+        return Err(format!("Failed to move {} to the trash: {}", name, e));
+    }
+    Ok(item)
+}
+
+/// Move instance `id`, including its archive, into the trash and remove it
+/// from the instance list.
+pub fn trash_instance(
+    instances: &InstanceManager,
+    trash_dir: &Path,
+    id: &str,
+) -> Result<TrashItem, LauncherError> {
+    let _lock = instances.locks.write(id, "deleting")?;
+    let instance = instances.get(id)?;
+    let mut item = move_to_trash(
+        trash_dir,
+        TrashKind::Instance,
+        &instance.name,
+        &instances.instance_dir(id),
+        Some(id),
+    )?;
+    let archive = crate::core::instance_archive::archive_path(instances, id);
+    if archive.exists() {
+        item.size += fs::metadata(&archive).map(|m| m.len()).unwrap_or(0);
+        move_path(&archive, &trash_dir.join(&item.id).join(ARCHIVE))
+            .map_err(|e| format!("Failed to move the archive to the trash: {}", e))?;
+        save_item(trash_dir, &item)?;
+    }
+    instances.remove_from_index(id)?;
+    Ok(item)
+}
+
+/// Items in the trash, most recently deleted first.
+pub fn list(trash_dir: &Path) -> Vec<TrashItem> {
+    let Ok(entries) = fs::read_dir(trash_dir) else {
+        return Vec::new();
+    };
+    let mut items: Vec<TrashItem> = entries
+        .flatten()
+        .filter_map(|entry| file_utils::read_json(&entry.path().join(ITEM_FILE)).ok())
+        .collect();
+    items.sort_by_key(|item| std::cmp::Reverse(item.deleted_at));
+    items
+}
+
+/// Put item `item_id` back where it was deleted from.
+pub fn restore(
+    trash_dir: &Path,
+    instances: &InstanceManager,
+    item_id: &str,
+) -> Result<TrashItem, LauncherError> {
+    let item_dir = item_dir(trash_dir, item_id)?;
+    let item: TrashItem = file_utils::read_json(&item_dir.join(ITEM_FILE))
+        .map_err(|_| LauncherError::Invalid(format!("{} is not in the trash", item_id)))?;
+    let target = PathBuf::from(&item.original_path);
+    if target.exists() {
+        return Err(LauncherError::Invalid(format!(
+            "{} already exists; move it away to restore {}",
+            target.display(),
+            item.name
+        )));
+    }
+
+    match (item.kind, item.instance_id.as_deref()) {
+        (TrashKind::Instance, Some(id)) => {
+            let _lock = instances.locks.write(id, "restoring")?;
+            move_path(&item_dir.join(CONTENT), &target)
+                .map_err(|e| format!("Failed to restore {}: {}", item.name, e))?;
+            let archive = item_dir.join(ARCHIVE);
+            if archive.exists() {
+                fs::create_dir_all(&instances.archive_dir).map_err(|e| e.to_string())?;
+                let restored = crate::core::instance_archive::archive_path(instances, id);
+                move_path(&archive, &restored)
+                    .map_err(|e| format!("Failed to restore the archive: {}", e))?;
+            }
+            instances.restore_to_index(id)?;
+        }
+        _ => {
+            let parent = target.parent().unwrap_or(&target);
+            if !parent.is_dir() {
+                return Err(LauncherError::Invalid(format!(
+                    "{} cannot be restored because {} no longer exists",
+                    item.name,
+                    parent.display()
+                )));
+            }
+            move_path(&item_dir.join(CONTENT), &target)
+                .map_err(|e| format!("Failed to restore {}: {}", item.name, e))?;
+        }
+    }
+    let _ = fs::remove_dir_all(&item_dir);
+    Ok(item)
+}
+
+/// Permanently delete everything in the trash; returns the bytes freed.
+pub fn empty(trash_dir: &Path) -> Result<u64, String> {
+    let mut freed = 0;
+    for item in list(trash_dir) {
+        fs::remove_dir_all(trash_dir.join(&item.id))
+            .map_err(|e| format!("Failed to delete {}: {}", item.name, e))?;
+        freed += item.size;
+    }
+    Ok(freed)
+}
+
+/// Delete items trashed more than `retention_days` ago; 0 keeps them until
+/// the trash is emptied. Returns the number of items deleted.
+pub fn purge_expired(trash_dir: &Path, retention_days: u32) -> usize {
+    if retention_days == 0 {
+        return 0;
+    }
+    let cutoff = now().saturating_sub(u64::from(retention_days) * 24 * 60 * 60);
+    list(trash_dir)
+        .into_iter()
+        .filter(|item| item.deleted_at < cutoff)
+        .filter(|item| fs::remove_dir_all(trash_dir.join(&item.id)).is_ok())
+        .count()
+}
+
+fn item_dir(trash_dir: &Path, item_id: &str) -> Result<PathBuf, LauncherError> {
+    uuid::Uuid::parse_str(item_id)
+        .map_err(|_| LauncherError::Invalid(format!("Invalid trash item: {}", item_id)))?;
+    Ok(trash_dir.join(item_id))
+}
+
+fn save_item(trash_dir: &Path, item: &TrashItem) -> Result<(), String> {
+    file_utils::write_json_atomic(&trash_dir.join(&item.id).join(ITEM_FILE), item)
+        .map_err(|e| e.to_string())
+}
+
+/// Rename `from` to `to`, copying when they are on different drives.
+fn move_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    let copied = if from.is_dir() {
+        file_utils::copy_dir_recursive(from, to)
+    } else {
+        fs::copy(from, to).map(|_| ())
+    };
+    if let Err(e) = copied {
+        let _ = if to.is_dir() {
+            fs::remove_dir_all(to)
+        } else {
+            fs::remove_file(to)
+        };
+        return Err(e);
+    }
+    if from.is_dir() {
+        fs::remove_dir_all(from)
+    } else {
+        fs::remove_file(from)
+    }
+}
+
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trash_and_restore_instance() {
+        let root = std::env::temp_dir().join(format!("dropout-trash-{}", uuid::Uuid::new_v4()));
+        let instances = InstanceManager::new(&root);
+        let trash = trash_dir(&root);
+        let instance = instances.create("Skyblock", "1.20.1").unwrap();
+        let dir = instances.instance_dir(&instance.id);
+        fs::write(dir.join("options.txt"), b"fov:1.0").unwrap();
+
+        let item = trash_instance(&instances, &trash, &instance.id).unwrap();
+        assert!(!dir.exists());
+        assert!(instances.list().is_empty());
+        assert_eq!(list(&trash).len(), 1);
+        assert_eq!(purge_expired(&trash, 30), 0);
+
+        restore(&trash, &instances, &item.id).unwrap();
+        assert_eq!(fs::read(dir.join("options.txt")).unwrap(), b"fov:1.0");
+        assert_eq!(instances.list().len(), 1);
+        assert!(list(&trash).is_empty());
+
+        let world = dir.join("saves").join("World");
+        fs::create_dir_all(&world).unwrap();
+        move_to_trash(
+            &trash,
+            TrashKind::World,
+            "World",
+            &world,
+            Some(&instance.id),
+        )
+        .unwrap();
+        assert!(!world.exists());
+        assert_eq!(empty(&trash).unwrap(), 0);
+        assert!(list(&trash).is_empty());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
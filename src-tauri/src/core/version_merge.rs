@@ -50,6 +50,10 @@ pub fn merge_versions(child: GameVersion, parent: GameVersion) -> GameVersion {
         assets: child.assets.or(parent.assets),
         // Use parent's version type if child doesn't specify
         version_type: child.version_type.or(parent.version_type),
+        // Use child's logging config if specified, otherwise parent's
+        logging: child.logging.or(parent.logging),
+        jar: child.jar.or(parent.jar),
+        compliance_level: child.compliance_level.or(parent.compliance_level),
     }
 }
 
@@ -147,6 +151,7 @@ mod tests {
             rules: None,
             natives: None,
             url: None,
+            sha1: None,
         }
     }
 
@@ -164,6 +169,9 @@ mod tests {
             inherits_from: Some("1.20.4".to_string()),
             assets: None,
             version_type: None,
+            logging: None,
+            jar: None,
+            compliance_level: None,
         };
 
         let parent = GameVersion {
@@ -186,6 +194,9 @@ mod tests {
             inherits_from: None,
             assets: None,
             version_type: Some("release".to_string()),
+            logging: None,
+            jar: None,
+            compliance_level: None,
         };
 
         let merged = merge_versions(child, parent);
@@ -222,6 +233,9 @@ mod tests {
             inherits_from: Some("1.20.4".to_string()),
             assets: None,
             version_type: None,
+            logging: None,
+            jar: None,
+            compliance_level: None,
         };
 
         let without_inheritance = GameVersion {
@@ -236,6 +250,9 @@ mod tests {
             inherits_from: None,
             assets: None,
             version_type: None,
+            logging: None,
+            jar: None,
+            compliance_level: None,
         };
 
         assert!(needs_inheritance_resolution(&with_inheritance));
@@ -0,0 +1,120 @@
+//! Copying worlds between client instances and server instances.
+//!
+//! A client keeps worlds in `saves/<name>/`; a dedicated server runs the
+//! single world named by `level-name` in `server.properties`. Copies are made
+//! into a staging directory first, and a world being replaced is renamed to a
+//! timestamped backup rather than deleted.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::utils::file_utils;
+
+/// Which way a world is copied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncDirection {
+    ToServer,
+    ToInstance,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorldSyncReport {
+    pub destination: PathBuf,
+    /// Where the replaced world was moved, if one existed
+    pub backup: Option<PathBuf>,
+}
+
+/// World directory a server runs (`level-name`, default `world`).
+pub fn server_world_dir(server_dir: &Path) -> PathBuf {
+    let level_name = fs::read_to_string(server_dir.join("server.properties"))
+        .ok()
+        .and_then(|content| {
+            content.lines().find_map(|line| {
+                line.strip_prefix("level-name=")
+                    .map(|v| v.trim().to_string())
+            })
+        })
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "world".to_string());
+    server_dir.join(level_name)
+}
+
+/// Validate a world folder name from the UI.
+pub fn validate_world_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Err(format!("Invalid world name: {}", name));
+    }
+    Ok(())
+}
+
+/// Replace `dst` with a copy of the world at `src`.
+pub fn copy_world(src: &Path, dst: &Path) -> Result<WorldSyncReport, String> {
+    if !src.join("level.dat").exists() {
+        return Err(format!("{} is not a Minecraft world", src.display()));
+    }
+
+    let staging = dst.with_extension("sync-tmp");
+    if staging.exists() {
+        fs::remove_dir_all(&staging).map_err(|e| e.to_string())?;
+    }
+    file_utils::copy_dir_recursive(src, &staging)
+        .map_err(|e| format!("Failed to copy world: {}", e))?;
+    // The lock belongs to the process that had the source world open
+    let _ = fs::remove_file(staging.join("session.lock"));
+
+    let backup = if dst.exists() {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let name = dst
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let backup = dst.with_file_name(format!("{}-backup-{}", name, timestamp));
+        fs::rename(dst, &backup).map_err(|e| format!("Failed to back up world: {}", e))?;
+        Some(backup)
+    } else {
+        None
+    };
+
+    fs::rename(&staging, dst).map_err(|e| format!("Failed to move world into place: {}", e))?;
+    Ok(WorldSyncReport {
+        destination: dst.to_path_buf(),
+        backup,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_world_backs_up_destination() {
+        let root = std::env::temp_dir().join(format!("dropout-worlds-{}", uuid::Uuid::new_v4()));
+        let src = root.join("saves").join("New World");
+        let dst = root.join("server").join("world");
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&dst).unwrap();
+        fs::write(src.join("level.dat"), "new").unwrap();
+        fs::write(src.join("session.lock"), "").unwrap();
+        fs::write(dst.join("level.dat"), "old").unwrap();
+
+        let report = copy_world(&src, &dst).unwrap();
+
+        assert_eq!(fs::read_to_string(dst.join("level.dat")).unwrap(), "new");
+        assert!(!dst.join("session.lock").exists());
+        let backup = report.backup.unwrap();
+        assert_eq!(fs::read_to_string(backup.join("level.dat")).unwrap(), "old");
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_validate_world_name() {
+        assert!(validate_world_name("New World").is_ok());
+        assert!(validate_world_name("../x").is_err());
+        assert!(validate_world_name("..").is_err());
+    }
+}
@@ -7,19 +7,30 @@ use tauri::{Emitter, Manager, State, Window}; // Added Emitter
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use serde::Serialize; // Added Serialize
+use crate::core::error::{LauncherError, ResultExt};
+use crate::core::events::{EventScope, EventSink, EventTarget, GameExited, GameOutput};
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 
-/// Helper macro to emit launcher log events
+/// Helper macro to emit launcher log events, at info level unless given
 macro_rules! emit_log {
-    ($window:expr, $msg:expr) => {
-        let _ = $window.emit("launcher-log", $msg);
-        println!("[Launcher] {}", $msg);
+    ($target:expr, $level:expr, $msg:expr) => {
+        let level = $level;
+        let message = $msg;
+        core::events::log_at(&$target, level, message.clone());
+        if core::logging::enabled(level) {
+            println!("[Launcher] {}", message);
+        }
+    };
+    ($target:expr, $msg:expr) => {
+        emit_log!($target, core::config::LogLevel::Info, $msg);
     };
 }
 
+mod cli;
 mod core;
+mod tray;
 mod utils;
 
 // Global storage for MS refresh token (not in Account struct to keep it separate)
@@ -41,95 +52,176 @@ impl MsRefreshTokenState {
     }
 }
 
-/// Check if a string contains unresolved placeholders in the form ${...}
-///
-/// After the replacement phase, if a string still contains ${...}, it means
-/// that placeholder variable was not found in the replacements map and is
-/// therefore unresolved. We should skip adding such arguments to avoid
-/// passing malformed arguments to the game launcher.
-fn has_unresolved_placeholder(s: &str) -> bool {
-    // Look for the opening sequence
-    if let Some(start_pos) = s.find("${") {
-        // Check if there's a closing brace after the opening sequence
-        if s[start_pos + 2..].find('}').is_some() {
-            // Found a complete ${...} pattern - this is an unresolved placeholder
-            return true;
-        }
-        // Found ${ but no closing } - also treat as unresolved/malformed
-        return true;
-    }
-    // No ${ found - the string is fully resolved
-    false
-}
-
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn start_game(
     window: Window,
     auth_state: State<'_, core::auth::AccountState>,
     config_state: State<'_, core::config::ConfigState>,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    launch_locks: State<'_, core::launch::LaunchLocks>,
     version_id: String,
-) -> Result<String, String> {
+    instance_id: Option<String>,
+    allow_metered: Option<bool>,
+) -> Result<String, LauncherError> {
+    // Every event of this launch carries its session id, which is returned
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let events = EventSink::new(
+        window.clone(),
+        EventScope {
+            instance_id: instance_id.clone(),
+            session_id: Some(session_id.clone()),
+        },
+    );
+    // Held until the game exits
+    let instance_lock = match &instance_id {
+        Some(id) => Some((
+            launch_locks.lock_instance(id)?,
+            instance_state.locks.read(id, "running")?,
+        )),
+        None => None,
+    };
     emit_log!(
-        window,
+        events,
         format!("Starting game launch for version: {}", version_id)
     );
 
-    // Check for active account
-    emit_log!(window, "Checking for active account...".to_string());
-    let account = auth_state
-        .active_account
-        .lock()
-        .unwrap()
-        .clone()
-        .ok_or("No active account found. Please login first.")?;
+    // Instances may be bound to an account; others use the active one
+    emit_log!(events, "Checking for active account...".to_string());
+    let bound_account = match &instance_id {
+        Some(id) => {
+            let instance = instance_state.get(id)?;
+            if instance.archived.is_some() {
+                return Err(LauncherError::Invalid(format!(
+                    "{} is archived; unarchive it before playing",
+                    instance.name
+                )));
+            }
+            instance.account_id
+        }
+        None => None,
+    };
+    let account = match bound_account {
+        Some(uuid) => {
+            let app_dir = window
+                .app_handle()
+                .path()
+                .app_data_dir()
+                .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+            let storage = core::account_storage::AccountStorage::new(app_dir.clone());
+            let (stored, ms_refresh) = storage.get_account(&uuid).ok_or(
+                "The account bound to this instance was removed. Bind another one or unbind it.",
+            )?;
+            usable_account(&window, &app_dir, stored, ms_refresh).await?
+        }
+        None => auth_state
+            .active_account
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or("No active account found. Please login first.")?,
+    };
 
-    emit_log!(
-        window,
-        format!("Account found: {}", account.username())
-    );
+    emit_log!(events, format!("Account found: {}", account.username()));
 
-    let config = config_state.config.lock().unwrap().clone();
-    emit_log!(window, format!("Java path: {}", config.java_path));
+    // Instances may override download, proxy, memory and JVM settings
+    let config = match &instance_id {
+        Some(id) => config_state.for_instance(&instance_state.get(id)?),
+        None => config_state.effective(None),
+    };
+    emit_log!(events, format!("Java path: {}", config.java_path));
     emit_log!(
-        window,
+        events,
         format!("Memory: {}MB - {}MB", config.min_memory, config.max_memory)
     );
+    for warning in core::launch::check_memory(&config)? {
+        emit_log!(
+            events,
+            core::config::LogLevel::Warn,
+            format!("Warning: {}", warning)
+        );
+    }
 
     // Get App Data Directory (e.g., ~/.local/share/com.dropout.launcher or similar)
     // The identifier is set in tauri.conf.json.
     // If not accessible, use a specific logic.
     let app_handle = window.app_handle();
-    let game_dir = app_handle
+    let app_dir = app_handle
         .path()
         .app_data_dir()
         .map_err(|e| format!("Failed to get app data dir: {}", e))?;
 
+    // Instances run in their own directory; isolated ones also keep their own
+    // versions, libraries and assets (`data_dir`)
+    let launch_paths = match &instance_id {
+        Some(id) => {
+            let mut instance = instance_state.get(id)?;
+            let instance_dir = instance_state.instance_dir(id);
+            if instance.isolated {
+                core::instance::seed_isolated_version(&app_dir, &instance_dir, &version_id)?;
+            }
+            instance.last_played = Some(
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs(),
+            );
+            instance_state.save(&instance)?;
+            emit_log!(
+                events,
+                format!(
+                    "Instance: {}{}",
+                    instance.name,
+                    if instance.isolated { " (isolated)" } else { "" }
+                )
+            );
+            core::instance::LaunchPaths::for_instance(&app_dir, &instance_dir, &instance)
+        }
+        None => core::instance::LaunchPaths::shared(&app_dir),
+    };
+    let data_dir = launch_paths.data_dir;
+    let game_dir = launch_paths.game_dir;
+    // Held until the game is started
+    let version_lock = launch_locks.lock_version(&data_dir, &version_id).await?;
+
     // Ensure game directory exists
-    tokio::fs::create_dir_all(&game_dir)
-        .await
-        .map_err(|e| e.to_string())?;
+    tokio::fs::create_dir_all(&game_dir).await?;
 
-    emit_log!(window, format!("Game directory: {:?}", game_dir));
+    emit_log!(events, format!("Game directory: {:?}", game_dir));
 
     // 1. Load version (supports both vanilla and modded versions with inheritance)
     emit_log!(
-        window,
+        events,
         format!("Loading version details for {}...", version_id)
     );
 
     // First, load the local version to get the original inheritsFrom value
     // (before merge clears it)
-    let original_inherits_from = match core::manifest::load_local_version(&game_dir, &version_id).await {
-        Ok(local_version) => local_version.inherits_from.clone(),
-        Err(_) => None,
-    };
+    let original_jar_version =
+        match core::manifest::load_local_version(&data_dir, &version_id).await {
+            Ok(local_version) => Some(local_version.client_jar_version().to_string()),
+            Err(_) => None,
+        };
 
-    let version_details = core::manifest::load_version(&game_dir, &version_id)
+    let mut version_details = core::manifest::load_version(&data_dir, &version_id)
         .await
-        .map_err(|e| e.to_string())?;
+        .context(format!("Loading version {}", version_id))?;
+    if let Some(warning) = core::manifest::compliance_warning(version_details.compliance_level) {
+        emit_log!(
+            events,
+            core::config::LogLevel::Warn,
+            format!("Warning: {}", warning)
+        );
+    }
+    if let Some(id) = &instance_id {
+        let overrides = instance_state.get(id)?.library_overrides;
+        for note in core::library_override::apply(&mut version_details, &overrides) {
+            emit_log!(events, note);
+        }
+    }
 
     emit_log!(
-        window,
+        events,
         format!(
             "Version details loaded: main class = {}",
             version_details.main_class
@@ -138,11 +230,10 @@ async fn start_game(
 
     // Determine the actual minecraft version for client.jar
     // (for modded versions, this is the parent vanilla version)
-    let minecraft_version = original_inherits_from
-        .unwrap_or_else(|| version_id.clone());
+    let minecraft_version = original_jar_version.unwrap_or_else(|| version_id.clone());
 
     // 2. Prepare download tasks
-    emit_log!(window, "Preparing download tasks...".to_string());
+    emit_log!(events, "Preparing download tasks...".to_string());
     let mut download_tasks = Vec::new();
 
     // --- Client Jar ---
@@ -151,44 +242,33 @@ async fn start_game(
         .downloads
         .as_ref()
         .ok_or("Version has no downloads information")?;
-    let client_jar = &downloads.client;
-    let mut client_path = game_dir.join("versions");
-    client_path.push(&minecraft_version);
-    client_path.push(format!("{}.jar", minecraft_version));
-
-    download_tasks.push(core::downloader::DownloadTask {
-        url: client_jar.url.clone(),
-        path: client_path.clone(),
-        sha1: client_jar.sha1.clone(),
-        sha256: None,
-    });
+    let client_path = core::launch::client_jar_path(&data_dir, &minecraft_version);
+    download_tasks.extend(core::custom_version::client_jar_task(
+        &downloads.client,
+        &client_path,
+    )?);
 
     // --- Libraries ---
     println!("Processing libraries...");
-    let libraries_dir = game_dir.join("libraries");
+    let libraries_dir = data_dir.join("libraries");
     let mut native_libs_paths = Vec::new(); // Store paths to native jars for extraction
+    let mut library_jars = Vec::new();
 
     for lib in &version_details.libraries {
-        if core::rules::is_library_allowed(&lib.rules) {
-            // 1. Standard Library - check for explicit downloads first
-            if let Some(downloads) = &lib.downloads {
-                if let Some(artifact) = &downloads.artifact {
-                    let path_str = artifact
-                        .path
-                        .clone()
-                        .unwrap_or_else(|| format!("{}.jar", lib.name));
-
-                    let mut lib_path = libraries_dir.clone();
-                    lib_path.push(path_str);
-
-                    download_tasks.push(core::downloader::DownloadTask {
-                        url: artifact.url.clone(),
-                        path: lib_path,
-                        sha1: artifact.sha1.clone(),
-                        sha256: None,
-                    });
-                }
+        let allowed = core::rules::is_library_allowed(&lib.rules);
+        if !allowed {
+            emit_log!(
+                events,
+                core::config::LogLevel::Debug,
+                format!("Skipping library {}: not allowed by its rules", lib.name)
+            );
+        }
+        if allowed {
+            // 1. Main jar, resolved the same way as for the classpath
+            let repository = config.maven_repository.as_deref();
+            library_jars.extend(core::maven::library_jar(lib, &libraries_dir, repository));
 
+            if let Some(downloads) = &lib.downloads {
                 // 2. Native Library (classifiers)
                 // e.g. "natives-linux": { ... }
                 if let Some(classifiers) = &downloads.classifiers {
@@ -220,35 +300,40 @@ async fn start_game(
                                 path: native_path.clone(),
                                 sha1: native_artifact.sha1,
                                 sha256: None,
+                                size: native_artifact.size,
+                                mirrors: Vec::new(),
                             });
 
                             native_libs_paths.push(native_path);
                         }
                     }
                 }
-            } else {
-                // 3. Library without explicit downloads (mod loader libraries)
-                // Use Maven coordinate resolution
-                if let Some(url) =
-                    core::maven::resolve_library_url(&lib.name, None, lib.url.as_deref())
-                {
-                    if let Some(lib_path) = core::maven::get_library_path(&lib.name, &libraries_dir)
-                    {
-                        download_tasks.push(core::downloader::DownloadTask {
-                            url,
-                            path: lib_path,
-                            sha1: None, // Maven libraries often don't have SHA1 in the JSON
-                            sha256: None,
-                        });
-                    }
-                }
             }
         }
     }
 
+    // Maven libraries may live in another repository than guessed
+    core::maven::locate_jars(&mut library_jars).await;
+    let mut library_tasks: Vec<_> = library_jars
+        .into_iter()
+        .filter_map(|jar| {
+            Some(core::downloader::DownloadTask {
+                url: jar.url?,
+                path: jar.path,
+                sha1: jar.sha1,
+                sha256: None,
+                size: jar.size,
+                mirrors: Vec::new(),
+            })
+        })
+        .collect();
+    // Maven libraries without a hash in their JSON
+    core::maven::fill_missing_sha1(&mut library_tasks).await;
+    download_tasks.extend(library_tasks);
+
     // --- Assets ---
     println!("Fetching asset index...");
-    let assets_dir = game_dir.join("assets");
+    let assets_dir = data_dir.join("assets");
     let objects_dir = assets_dir.join("objects");
     let indexes_dir = assets_dir.join("indexes");
 
@@ -267,47 +352,24 @@ async fn start_game(
     // So we must download it immediately (await) before processing objects.
 
     let asset_index_content: String = if asset_index_path.exists() {
-        tokio::fs::read_to_string(&asset_index_path)
-            .await
-            .map_err(|e| e.to_string())?
+        tokio::fs::read_to_string(&asset_index_path).await?
     } else {
         println!("Downloading asset index from {}", asset_index.url);
-        let content = reqwest::get(&asset_index.url)
-            .await
-            .map_err(|e| e.to_string())?
-            .text()
-            .await
-            .map_err(|e| e.to_string())?;
+        let content = core::http::get(&asset_index.url).await?.text().await?;
 
         // Save it for next time
-        tokio::fs::create_dir_all(&indexes_dir)
-            .await
-            .map_err(|e| e.to_string())?;
-        tokio::fs::write(&asset_index_path, &content)
-            .await
-            .map_err(|e| e.to_string())?;
+        tokio::fs::create_dir_all(&indexes_dir).await?;
+        tokio::fs::write(&asset_index_path, &content).await?;
         content
     };
 
-    #[derive(serde::Deserialize, Debug)]
-    struct AssetObject {
-        hash: String,
-        #[allow(dead_code)]
-        size: u64,
-    }
-
-    #[derive(serde::Deserialize, Debug)]
-    struct AssetIndexJson {
-        objects: std::collections::HashMap<String, AssetObject>,
-    }
-
-    let asset_index_parsed: AssetIndexJson =
-        serde_json::from_str(&asset_index_content).map_err(|e| e.to_string())?;
+    let asset_index_parsed: core::assets::AssetIndexJson =
+        serde_json::from_str(&asset_index_content)?;
 
     println!("Processing {} assets...", asset_index_parsed.objects.len());
 
-    for (_name, object) in asset_index_parsed.objects {
-        let hash = object.hash;
+    for object in asset_index_parsed.objects.values() {
+        let hash = object.hash.clone();
         let prefix = &hash[0..2];
         let path = objects_dir.join(prefix).join(&hash);
         let url = format!(
@@ -320,11 +382,19 @@ async fn start_game(
             path,
             sha1: Some(hash),
             sha256: None,
+            size: (object.size > 0).then_some(object.size),
+            mirrors: Vec::new(),
         });
     }
 
+    // --- Logging config (log4j mitigation) ---
+    let logging_setup = core::log4j::logging_setup(&assets_dir, &version_details);
+    if let Some(setup) = &logging_setup {
+        download_tasks.push(setup.task.clone());
+    }
+
     emit_log!(
-        window,
+        events,
         format!(
             "Total download tasks: {} (Client + Libraries + Assets)",
             download_tasks.len()
@@ -332,208 +402,140 @@ async fn start_game(
     );
 
     // 4. Start Download
+    if !allow_metered.unwrap_or(false) {
+        let bytes = core::metered::missing_bytes(&download_tasks);
+        core::metered::check(&config, bytes, &format!("Launching {}", version_id))?;
+    }
     emit_log!(
-        window,
+        events,
         format!(
             "Starting downloads with {} concurrent threads...",
             config.download_threads
         )
     );
-    core::downloader::download_files(
-        window.clone(),
-        download_tasks,
-        config.download_threads as usize,
-    )
-    .await
-    .map_err(|e| e.to_string())?;
-    emit_log!(window, "All downloads completed successfully".to_string());
-
-    // 5. Extract Natives
-    emit_log!(window, "Extracting native libraries...".to_string());
-    let natives_dir = game_dir.join("versions").join(&version_id).join("natives");
-
-    // Clean old natives if they exist to prevent conflicts
-    if natives_dir.exists() {
-        tokio::fs::remove_dir_all(&natives_dir)
-            .await
-            .map_err(|e| e.to_string())?;
-    }
-    tokio::fs::create_dir_all(&natives_dir)
-        .await
-        .map_err(|e| e.to_string())?;
+    core::downloader::download_files(events.clone(), download_tasks, &config).await?;
+    emit_log!(events, "All downloads completed successfully".to_string());
 
-    for path in native_libs_paths {
-        if path.exists() {
-            println!("Extracting native: {:?}", path);
-            utils::zip::extract_zip(&path, &natives_dir)?;
-        }
+    // Pre-1.7 versions read assets by name instead of from the object store
+    let asset_layout = core::assets::detect_layout(&asset_index.id, &asset_index_parsed);
+    if asset_layout != core::assets::AssetLayout::Objects {
+        emit_log!(
+            events,
+            format!(
+                "Reconstructing legacy assets ({:?} layout)...",
+                asset_layout
+            )
+        );
+        let copied = core::assets::reconstruct_legacy_assets(
+            &game_dir,
+            &assets_dir,
+            &asset_index.id,
+            &asset_index_parsed,
+        )?;
+        emit_log!(events, format!("Copied {} legacy asset files", copied));
     }
+    let game_assets_dir =
+        core::assets::game_assets_dir(&game_dir, &assets_dir, &asset_index.id, asset_layout);
 
-    // 6. Construct Classpath
-    let cp_separator = if cfg!(target_os = "windows") {
-        ";"
+    // 5. Extract Natives (skipped when the native jar set is unchanged)
+    let natives_dir = core::launch::natives_dir(&data_dir, &version_id);
+    if core::natives::prepare_natives(&native_libs_paths, &natives_dir).await? {
+        emit_log!(
+            events,
+            format!("Extracted {} native libraries", native_libs_paths.len())
+        );
     } else {
-        ":"
-    };
-    let mut classpath_entries = Vec::new();
-
-    // Add libraries
-    for lib in &version_details.libraries {
-        if core::rules::is_library_allowed(&lib.rules) {
-            if let Some(downloads) = &lib.downloads {
-                // Standard library with explicit downloads
-                if let Some(artifact) = &downloads.artifact {
-                    let path_str = artifact
-                        .path
-                        .clone()
-                        .unwrap_or_else(|| format!("{}.jar", lib.name));
-                    let lib_path = libraries_dir.join(path_str);
-                    classpath_entries.push(lib_path.to_string_lossy().to_string());
-                }
-            } else {
-                // Library without explicit downloads (mod loader libraries)
-                // Use Maven coordinate resolution
-                if let Some(lib_path) = core::maven::get_library_path(&lib.name, &libraries_dir) {
-                    classpath_entries.push(lib_path.to_string_lossy().to_string());
-                }
-            }
-        }
-    }
-    // Add client jar
-    classpath_entries.push(client_path.to_string_lossy().to_string());
-
-    let classpath = classpath_entries.join(cp_separator);
-
-    // 7. Prepare Arguments
-    let mut args = Vec::new();
-    let natives_path = natives_dir.to_string_lossy().to_string();
-
-    // 7a. JVM Arguments - Parse from version.json for full compatibility
-    // First add arguments from version.json if available
-    if let Some(args_obj) = &version_details.arguments {
-        if let Some(jvm_args) = &args_obj.jvm {
-            parse_jvm_arguments(jvm_args, &mut args, &natives_path, &classpath);
-        }
+        emit_log!(events, "Native libraries up to date".to_string());
     }
 
-    // Add memory settings (these override any defaults)
-    args.push(format!("-Xmx{}M", config.max_memory));
-    args.push(format!("-Xms{}M", config.min_memory));
-
-    // Ensure natives path is set if not already in jvm args
-    if !args.iter().any(|a| a.contains("-Djava.library.path")) {
-        args.push(format!("-Djava.library.path={}", natives_path));
-    }
-
-    // Ensure classpath is set if not already
-    if !args.iter().any(|a| a == "-cp" || a == "-classpath") {
-        args.push("-cp".to_string());
-        args.push(classpath.clone());
-    }
-
-    // 7b. Main Class
-    args.push(version_details.main_class.clone());
-
-    // 7c. Game Arguments
-    // Replacements map
-    let mut replacements = std::collections::HashMap::new();
-    replacements.insert("${auth_player_name}", account.username());
-    replacements.insert("${version_name}", version_id.clone());
-    replacements.insert("${game_directory}", game_dir.to_string_lossy().to_string());
-    replacements.insert("${assets_root}", assets_dir.to_string_lossy().to_string());
-    replacements.insert("${assets_index_name}", asset_index.id.clone());
-    replacements.insert("${auth_uuid}", account.uuid());
-    replacements.insert("${auth_access_token}", account.access_token());
-    replacements.insert("${user_type}", "mojang".to_string());
-    replacements.insert("${version_type}", "release".to_string());
-    replacements.insert("${user_properties}", "{}".to_string()); // Correctly pass empty JSON object for user properties
-
-    if let Some(minecraft_arguments) = &version_details.minecraft_arguments {
-        // Legacy string
-        for part in minecraft_arguments.split_whitespace() {
-            let mut arg = part.to_string();
-            for (key, val) in &replacements {
-                arg = arg.replace(key, val);
-            }
-            args.push(arg);
-        }
-    } else if let Some(args_obj) = &version_details.arguments {
-        if let Some(game_args) = &args_obj.game {
-            // Can be array of strings or objects
-            if let Some(list) = game_args.as_array() {
-                for item in list {
-                    if let Some(s) = item.as_str() {
-                        let mut arg = s.to_string();
-                        for (key, val) in &replacements {
-                            arg = arg.replace(key, val);
-                        }
-                        args.push(arg);
-                    } else if let Some(obj) = item.as_object() {
-                        // Check rules
-                        // Simplified: if it has "value", and rules pass.
-                        // For now, assuming rules pass if no "rules" field or simplistic check
-                        // Ideally we should implement a helper to check rules for args just like libs
-
-                        let allow = if let Some(rules_val) = obj.get("rules") {
-                            if let Ok(rules) = serde_json::from_value::<Vec<core::game_version::Rule>>(
-                                rules_val.clone(),
-                            ) {
-                                core::rules::is_library_allowed(&Some(rules))
-                            } else {
-                                true // Parse error, assume allow? or disallow.
-                            }
-                        } else {
-                            true
-                        };
-
-                        if allow {
-                            if let Some(val) = obj.get("value") {
-                                if let Some(s) = val.as_str() {
-                                    let mut arg = s.to_string();
-                                    for (key, replacement) in &replacements {
-                                        arg = arg.replace(key, replacement);
-                                    }
-                                    // Skip arguments with unresolved placeholders
-                                    if !has_unresolved_placeholder(&arg) {
-                                        args.push(arg);
-                                    }
-                                } else if let Some(arr) = val.as_array() {
-                                    for sub in arr {
-                                        if let Some(s) = sub.as_str() {
-                                            let mut arg = s.to_string();
-                                            for (key, replacement) in &replacements {
-                                                arg = arg.replace(key, replacement);
-                                            }
-                                            // Skip arguments with unresolved placeholders
-                                            if !has_unresolved_placeholder(&arg) {
-                                                args.push(arg);
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+    // Jar mods are merged into a copy of the client jar
+    let client_jar = match &instance_id {
+        Some(id) => {
+            let instance = instance_state.get(id)?;
+            let instance_dir = instance_state.instance_dir(id);
+            let enabled = core::jar_mods::enabled_paths(&instance_dir, &instance).len();
+            if enabled > 0 {
+                emit_log!(events, format!("Applying {} jar mods...", enabled));
             }
+            let client_path = client_path.clone();
+            tokio::task::spawn_blocking(move || {
+                core::jar_mods::client_jar(&instance_dir, &instance, &client_path)
+            })
+            .await??
         }
-    }
+        None => client_path.clone(),
+    };
+
+    // 6. Build the command line
+    let auth = core::launch::LaunchAuth {
+        username: account.username(),
+        uuid: account.uuid(),
+        access_token: account.access_token(),
+    };
+    let mut args = core::launch::build_arguments(&core::launch::LaunchSpec {
+        version: &version_details,
+        version_id: &version_id,
+        minecraft_version: &minecraft_version,
+        client_jar: &client_jar,
+        data_dir: &data_dir,
+        game_dir: &game_dir,
+        game_assets_dir: &game_assets_dir,
+        config: &config,
+        logging_argument: logging_setup.as_ref().map(|s| s.jvm_argument.as_str()),
+        auth: &auth,
+    });
 
     emit_log!(
-        window,
+        events,
         format!("Preparing to launch game with {} arguments...", args.len())
     );
     // Debug: Log arguments (only first few to avoid spam)
     if args.len() > 10 {
-        emit_log!(window, format!("First 10 args: {:?}", &args[..10]));
+        emit_log!(events, format!("First 10 args: {:?}", &args[..10]));
     }
 
     // Spawn the process
     emit_log!(
-        window,
+        events,
         format!("Starting Java process: {}", config.java_path)
     );
+    let mut display = core::display::setup(config.display_backend);
+    display.extend(core::gpu::setup(config.gpu_preference, &config.java_path));
+    for note in &display.notes {
+        emit_log!(events, format!("Display: {}", note));
+    }
+    args.splice(0..0, display.jvm_args.iter().cloned());
+    let gc_log = config.gc_logging.then(|| core::gc_log::log_path(&game_dir));
+    if let Some(path) = &gc_log {
+        let java_path = std::path::PathBuf::from(&config.java_path);
+        let java_major = core::java::check_java_installation(&java_path)
+            .map(|java| core::java::parse_java_version(&java.version))
+            .unwrap_or(0);
+        match core::gc_log::jvm_args(java_major, path) {
+            Some(gc_args) => {
+                let _ = std::fs::remove_file(path);
+                args.splice(0..0, gc_args);
+                emit_log!(events, format!("GC logging to {}", path.display()));
+            }
+            None => {
+                emit_log!(
+                    events,
+                    core::config::LogLevel::Warn,
+                    "GC logging needs Java 9 or newer".to_string()
+                );
+            }
+        }
+    }
+    if core::logging::debug_mode() {
+        let redactor = core::redact::Redactor::new().secret(&auth.access_token);
+        emit_log!(
+            events,
+            core::config::LogLevel::Debug,
+            redactor.redact(&format!("Launch command: {} {:?}", config.java_path, args))
+        );
+    }
     let mut command = Command::new(&config.java_path);
+    display.apply(&mut command);
     command.args(&args);
     command.current_dir(&game_dir); // Run in game directory
     command.stdout(Stdio::piped());
@@ -545,7 +547,7 @@ async fn start_game(
         const CREATE_NO_WINDOW: u32 = 0x08000000;
         command.creation_flags(CREATE_NO_WINDOW);
         emit_log!(
-            window,
+            events,
             "Applied CREATE_NO_WINDOW flag for Windows".to_string()
         );
     }
@@ -554,8 +556,22 @@ async fn start_game(
     let mut child = command
         .spawn()
         .map_err(|e| format!("Failed to launch java: {}", e))?;
+    let started_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
 
-    emit_log!(window, "Java process started successfully".to_string());
+    emit_log!(events, "Java process started successfully".to_string());
+    drop(version_lock);
+    if let Err(e) = core::account_storage::AccountStorage::new(app_dir.clone())
+        .mark_used(&account.uuid(), instance_id.as_deref())
+    {
+        emit_log!(
+            events,
+            core::config::LogLevel::Error,
+            format!("Failed to record account use: {}", e)
+        );
+    }
 
     let stdout = child
         .stdout
@@ -568,129 +584,289 @@ async fn start_game(
 
     // Emit launcher log that game is running
     emit_log!(
-        window,
+        events,
         "Game is now running, capturing output...".to_string()
     );
 
-    let window_rx = window.clone();
+    let events_out = events.clone();
+    let output_out = core::console_window::SessionOutput::new(events.clone());
+    let servers = std::sync::Arc::new(Mutex::new(core::game_log::ServerTracker::default()));
+    let servers_out = servers.clone();
     tokio::spawn(async move {
         let mut reader = BufReader::new(stdout).lines();
+        let mut log = core::game_log::LogReader::default();
         while let Ok(Some(line)) = reader.next_line().await {
-            let _ = window_rx.emit("game-stdout", line);
+            let event = log.push(&line);
+            if let Some(activity) = event
+                .as_ref()
+                .and_then(|event| core::game_log::parse_message(&event.message))
+            {
+                // Prefer the game's own timestamp over the time the line arrived
+                let now = event.and_then(|event| event.timestamp).map_or_else(
+                    || {
+                        std::time::SystemTime::now()
+                            .duration_since(std::time::UNIX_EPOCH)
+                            .unwrap()
+                            .as_secs()
+                    },
+                    |millis| millis / 1000,
+                );
+                servers_out.lock().unwrap().observe(&activity, now);
+                events_out.emit_event(activity);
+            }
+            output_out.emit_event(GameOutput {
+                stream: core::events::OutputStream::Stdout,
+                line,
+            });
         }
         // Emit log when stdout stream ends (game closing)
-        let _ = window_rx.emit("launcher-log", "Game stdout stream ended");
+        core::events::log(&events_out, "Game stdout stream ended");
     });
 
-    let window_rx_err = window.clone();
-    let window_exit = window.clone();
+    let events_err = events.clone();
+    let output_err = core::console_window::SessionOutput::new(events.clone());
+    let events_exit = events.clone();
     tokio::spawn(async move {
         let mut reader = BufReader::new(stderr).lines();
         while let Ok(Some(line)) = reader.next_line().await {
-            let _ = window_rx_err.emit("game-stderr", line);
+            output_err.emit_event(GameOutput {
+                stream: core::events::OutputStream::Stderr,
+                line,
+            });
         }
         // Emit log when stderr stream ends
-        let _ = window_rx_err.emit("launcher-log", "Game stderr stream ended");
+        core::events::log(&events_err, "Game stderr stream ended");
     });
 
-    // Monitor game process exit
+    if let Some(pid) = child.id() {
+        let app_metrics = window.app_handle().clone();
+        let events_metrics = events.clone();
+        let metrics_session = session_id.clone();
+        tokio::spawn(core::game_metrics::monitor(pid, move |sample| {
+            app_metrics
+                .state::<core::game_metrics::MetricsState>()
+                .record(&metrics_session, sample.clone());
+            events_metrics.emit_event(sample);
+        }));
+    }
+
+    // Monitor game process exit and record the session for playtime stats
+    let app_exit = window.app_handle().clone();
+    let session_version = version_id.clone();
+    let session_instance = instance_id.clone();
+    let max_memory = config.max_memory;
     tokio::spawn(async move {
-        match child.wait().await {
-            Ok(status) => {
-                let msg = format!("Game process exited with status: {}", status);
-                let _ = window_exit.emit("launcher-log", &msg);
-                let _ = window_exit.emit("game-exited", status.code().unwrap_or(-1));
-            }
-            Err(e) => {
-                let msg = format!("Error waiting for game process: {}", e);
-                let _ = window_exit.emit("launcher-log", &msg);
+        let result = child.wait().await;
+        drop(instance_lock);
+        let ended_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let session = core::stats::Session {
+            instance_id: session_instance,
+            version_id: session_version,
+            started_at,
+            ended_at,
+            servers: std::mem::take(&mut *servers.lock().unwrap()).finish(ended_at),
+        };
+        if let Err(e) = app_exit.state::<core::stats::StatsState>().record(session) {
+            println!("[Stats] Failed to record session: {}", e);
+        }
+        if let Some(path) = gc_log.filter(|path| path.exists()) {
+            match core::gc_log::analyze_file(&path, max_memory) {
+                Ok(report) => {
+                    core::events::log(
+                        &events_exit,
+                        format!(
+                            "GC: {} pauses, longest {:.0} ms, up to {} MB in use after collections",
+                            report.pauses, report.max_pause_ms, report.max_live_heap_mb
+                        ),
+                    );
+                    events_exit.emit_event(report);
+                }
+                Err(e) => core::events::log_at(
+                    &events_exit,
+                    core::config::LogLevel::Warn,
+                    format!("GC log: {}", e),
+                ),
             }
         }
+
+        let (level, msg) = core::launch::exit_message(&result);
+        core::events::log_at(&events_exit, level, msg);
+        if let Ok(status) = result {
+            events_exit.emit_event(GameExited {
+                code: status.code().unwrap_or(-1),
+            });
+        }
     });
 
-    Ok(format!("Launched Minecraft {} successfully!", version_id))
+    emit_log!(
+        events,
+        format!("Launched Minecraft {} successfully!", version_id)
+    );
+    Ok(session_id)
 }
 
-/// Parse JVM arguments from version.json
-fn parse_jvm_arguments(
-    jvm_args: &serde_json::Value,
-    args: &mut Vec<String>,
-    natives_path: &str,
-    classpath: &str,
-) {
-    let mut replacements = std::collections::HashMap::new();
-    replacements.insert("${natives_directory}", natives_path.to_string());
-    replacements.insert("${classpath}", classpath.to_string());
-    replacements.insert("${launcher_name}", "DropOut".to_string());
-    replacements.insert("${launcher_version}", env!("CARGO_PKG_VERSION").to_string());
-
-    if let Some(list) = jvm_args.as_array() {
-        for item in list {
-            if let Some(s) = item.as_str() {
-                // Simple string argument
-                let mut arg = s.to_string();
-                for (key, val) in &replacements {
-                    arg = arg.replace(key, val);
-                }
-                // Skip memory args as we set them explicitly
-                if !arg.starts_with("-Xmx") && !arg.starts_with("-Xms") {
-                    args.push(arg);
-                }
-            } else if let Some(obj) = item.as_object() {
-                // Conditional argument with rules
-                let allow = if let Some(rules_val) = obj.get("rules") {
-                    if let Ok(rules) =
-                        serde_json::from_value::<Vec<core::game_version::Rule>>(rules_val.clone())
-                    {
-                        core::rules::is_library_allowed(&Some(rules))
-                    } else {
-                        false
-                    }
-                } else {
-                    true
-                };
+/// Write a shell (`.sh`) or batch (`.bat`) script launching an installed
+/// instance without the launcher. Batch scripts for Java 9 and newer come with
+/// a `.args` file holding the classpath.
+///
+/// Unless `embed_token` is set, the script reads the access token of a
+/// Microsoft account from `DROPOUT_ACCESS_TOKEN` instead of containing it.
+#[tauri::command]
+async fn export_launch_script(
+    window: Window,
+    auth_state: State<'_, core::auth::AccountState>,
+    config_state: State<'_, core::config::ConfigState>,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+    path: String,
+    embed_token: bool,
+) -> Result<(), LauncherError> {
+    let account = auth_state
+        .active_account
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("No active account found. Please login first.")?;
+    let instance = instance_state.get(&instance_id)?;
+    let config = config_state.for_instance(&instance);
+    let app_dir = window
+        .app_handle()
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    let launch_paths = core::instance::LaunchPaths::for_instance(
+        &app_dir,
+        &instance_state.instance_dir(&instance_id),
+        &instance,
+    );
+    let auth = core::launch::LaunchAuth {
+        username: account.username(),
+        uuid: account.uuid(),
+        access_token: account.access_token(),
+    };
+    let args = installed_launch_arguments(&launch_paths, &instance, &config, &auth).await?;
 
-                if allow {
-                    if let Some(val) = obj.get("value") {
-                        if let Some(s) = val.as_str() {
-                            let mut arg = s.to_string();
-                            for (key, replacement) in &replacements {
-                                arg = arg.replace(key, replacement);
-                            }
-                            if !arg.starts_with("-Xmx") && !arg.starts_with("-Xms") {
-                                args.push(arg);
-                            }
-                        } else if let Some(arr) = val.as_array() {
-                            for sub in arr {
-                                if let Some(s) = sub.as_str() {
-                                    let mut arg = s.to_string();
-                                    for (key, replacement) in &replacements {
-                                        arg = arg.replace(key, replacement);
-                                    }
-                                    if !arg.starts_with("-Xmx") && !arg.starts_with("-Xms") {
-                                        args.push(arg);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    // Offline accounts have no real token to protect
+    let hidden_token = match &account {
+        core::auth::Account::Microsoft(_) if !embed_token => Some(auth.access_token.as_str()),
+        _ => None,
+    };
+    let path = std::path::PathBuf::from(path);
+    let java_major = core::java::check_java_installation(&config.java_path.clone().into())
+        .map_or(0, |java| core::java::parse_java_version(&java.version));
+    let argfile = core::launch_script::argfile_path(&path);
+    let script = core::launch_script::render(
+        core::launch_script::ScriptKind::for_path(&path),
+        &config.java_path,
+        &args,
+        &launch_paths.game_dir,
+        hidden_token,
+        (java_major >= core::launch_script::ARGFILE_MIN_JAVA).then_some(argfile.as_path()),
+    );
+    std::fs::write(&path, script.content)?;
+    if let Some(content) = script.argfile {
+        std::fs::write(&argfile, content)?;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
     }
+    Ok(())
 }
 
-#[tauri::command]
-async fn get_versions() -> Result<Vec<core::manifest::Version>, String> {
-    match core::manifest::fetch_version_manifest().await {
-        Ok(manifest) => Ok(manifest.versions),
-        Err(e) => Err(e.to_string()),
+/// Java arguments launching `instance` from files already on disk, without
+/// downloading or extracting anything. Used for launch scripts and the CLI.
+async fn installed_launch_arguments(
+    launch_paths: &core::instance::LaunchPaths,
+    instance: &core::instance::Instance,
+    config: &core::config::EffectiveConfig,
+    auth: &core::launch::LaunchAuth,
+) -> Result<Vec<String>, LauncherError> {
+    let data_dir = &launch_paths.data_dir;
+    let version_id = &instance.version_id;
+
+    let minecraft_version = core::manifest::load_local_version(data_dir, version_id)
+        .await
+        .context(format!("Loading version {}", version_id))?
+        .client_jar_version()
+        .to_string();
+    let mut version = core::manifest::load_version(data_dir, version_id)
+        .await
+        .context(format!("Loading version {}", version_id))?;
+    core::library_override::apply(&mut version, &instance.library_overrides);
+    let asset_index = version
+        .asset_index
+        .as_ref()
+        .ok_or("Version has no asset index information")?;
+    let assets_dir = data_dir.join("assets");
+    let index_path = assets_dir
+        .join("indexes")
+        .join(format!("{}.json", asset_index.id));
+    if !core::launch::client_jar_path(data_dir, &minecraft_version).exists() || !index_path.exists()
+    {
+        return Err(LauncherError::Invalid(format!(
+            "Version {} is not installed; launch the instance once first",
+            version_id
+        )));
     }
+    let index: core::assets::AssetIndexJson =
+        serde_json::from_str(&std::fs::read_to_string(&index_path)?)?;
+    let layout = core::assets::detect_layout(&asset_index.id, &index);
+    let game_assets_dir =
+        core::assets::game_assets_dir(&launch_paths.game_dir, &assets_dir, &asset_index.id, layout);
+    let logging_setup = core::log4j::logging_setup(&assets_dir, &version);
+    let client_jar = core::jar_mods::client_jar(
+        &launch_paths.game_dir,
+        instance,
+        &core::launch::client_jar_path(data_dir, &minecraft_version),
+    )?;
+
+    Ok(core::launch::build_arguments(&core::launch::LaunchSpec {
+        version: &version,
+        version_id,
+        minecraft_version: &minecraft_version,
+        client_jar: &client_jar,
+        data_dir,
+        game_dir: &launch_paths.game_dir,
+        game_assets_dir: &game_assets_dir,
+        config,
+        logging_argument: logging_setup.as_ref().map(|s| s.jvm_argument.as_str()),
+        auth,
+    }))
+}
+
+/// Get the version manifest, filtered by release type.
+///
+/// `types` overrides the `visible_version_types` setting when given.
+#[tauri::command]
+async fn get_versions(
+    config_state: State<'_, core::config::ConfigState>,
+    types: Option<Vec<String>>,
+) -> Result<core::manifest::VersionManifest, LauncherError> {
+    let types = types.unwrap_or_else(|| {
+        config_state
+            .config
+            .lock()
+            .unwrap()
+            .visible_version_types
+            .clone()
+    });
+
+    let mut manifest = core::manifest::fetch_version_manifest().await?;
+    manifest.retain_types(&types);
+    Ok(manifest)
 }
 
 /// Check if a version is installed (has client.jar)
 #[tauri::command]
-async fn check_version_installed(window: Window, version_id: String) -> Result<bool, String> {
+async fn check_version_installed(
+    window: Window,
+    version_id: String,
+) -> Result<bool, LauncherError> {
     let app_handle = window.app_handle();
     let game_dir = app_handle
         .path()
@@ -721,74 +897,105 @@ async fn check_version_installed(window: Window, version_id: String) -> Result<b
 async fn install_version(
     window: Window,
     config_state: State<'_, core::config::ConfigState>,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    launch_locks: State<'_, core::launch::LaunchLocks>,
     version_id: String,
-) -> Result<(), String> {
+    instance_id: Option<String>,
+    allow_metered: Option<bool>,
+) -> Result<(), LauncherError> {
+    let events = EventSink::new(
+        window.clone(),
+        EventScope {
+            instance_id: instance_id.clone(),
+            session_id: None,
+        },
+    );
     emit_log!(
-        window,
+        events,
         format!("Starting installation for version: {}", version_id)
     );
 
-    let config = config_state.config.lock().unwrap().clone();
+    // Installing for an instance uses its network overrides
+    let network = match &instance_id {
+        Some(id) => Some(instance_state.get(id)?.network),
+        None => None,
+    };
+    let config = config_state.effective(network.as_ref());
     let app_handle = window.app_handle();
     let game_dir = app_handle
         .path()
         .app_data_dir()
         .map_err(|e| format!("Failed to get app data dir: {}", e))?;
 
+    let _version_lock = launch_locks.lock_version(&game_dir, &version_id).await?;
+
     // Ensure game directory exists
-    tokio::fs::create_dir_all(&game_dir)
-        .await
-        .map_err(|e| e.to_string())?;
+    tokio::fs::create_dir_all(&game_dir).await?;
+
+    emit_log!(events, format!("Game directory: {:?}", game_dir));
+    download_version_files(
+        events.clone(),
+        &game_dir,
+        &version_id,
+        &config,
+        allow_metered.unwrap_or(false),
+    )
+    .await?;
 
-    emit_log!(window, format!("Game directory: {:?}", game_dir));
+    Ok(())
+}
 
+/// Download the client, libraries and assets of `version_id`, fetching the
+/// version JSON first if it is not installed yet. Unless `allow_metered` is
+/// set, large downloads may first need the user's consent, see `core::metered`.
+async fn download_version_files(
+    events: EventSink,
+    game_dir: &std::path::PathBuf,
+    version_id: &str,
+    config: &core::config::EffectiveConfig,
+    allow_metered: bool,
+) -> Result<(), LauncherError> {
     // Load version (supports both vanilla and modded versions with inheritance)
     emit_log!(
-        window,
+        events,
         format!("Loading version details for {}...", version_id)
     );
 
     // First, try to fetch the vanilla version from Mojang and save it locally
-    let version_details = match core::manifest::load_local_version(&game_dir, &version_id).await {
+    let version_details = match core::manifest::load_local_version(game_dir, version_id).await {
         Ok(v) => v,
         Err(_) => {
             // Not found locally, fetch from Mojang
-            emit_log!(window, format!("Fetching version {} from Mojang...", version_id));
-            let fetched = core::manifest::fetch_vanilla_version(&version_id)
-                .await
-                .map_err(|e| e.to_string())?;
-            
+            emit_log!(
+                events,
+                format!("Fetching version {} from Mojang...", version_id)
+            );
+            let fetched = core::manifest::fetch_vanilla_version(version_id).await?;
+
             // Save the version JSON locally
-            emit_log!(window, format!("Saving version JSON..."));
-            core::manifest::save_local_version(&game_dir, &fetched)
-                .await
-                .map_err(|e| e.to_string())?;
-            
+            emit_log!(events, format!("Saving version JSON..."));
+            core::manifest::save_local_version(game_dir, &fetched).await?;
+
             fetched
         }
     };
 
+    // Determine the actual minecraft version for client.jar
+    let minecraft_version = version_details.client_jar_version().to_string();
+
     // Now load the full version with inheritance resolved
-    let version_details = core::manifest::load_version(&game_dir, &version_id)
-        .await
-        .map_err(|e| e.to_string())?;
+    let version_details = core::manifest::load_version(game_dir, version_id).await?;
 
     emit_log!(
-        window,
+        events,
         format!(
             "Version details loaded: main class = {}",
             version_details.main_class
         )
     );
 
-    // Determine the actual minecraft version for client.jar
-    let minecraft_version = version_details
-        .inherits_from
-        .clone()
-        .unwrap_or_else(|| version_id.clone());
-
     // Prepare download tasks
-    emit_log!(window, "Preparing download tasks...".to_string());
+    emit_log!(events, "Preparing download tasks...".to_string());
     let mut download_tasks = Vec::new();
 
     // --- Client Jar ---
@@ -796,41 +1003,22 @@ async fn install_version(
         .downloads
         .as_ref()
         .ok_or("Version has no downloads information")?;
-    let client_jar = &downloads.client;
-    let mut client_path = game_dir.join("versions");
-    client_path.push(&minecraft_version);
-    client_path.push(format!("{}.jar", minecraft_version));
-
-    download_tasks.push(core::downloader::DownloadTask {
-        url: client_jar.url.clone(),
-        path: client_path.clone(),
-        sha1: client_jar.sha1.clone(),
-        sha256: None,
-    });
+    let client_path = core::launch::client_jar_path(game_dir, &minecraft_version);
+    download_tasks.extend(core::custom_version::client_jar_task(
+        &downloads.client,
+        &client_path,
+    )?);
 
     // --- Libraries ---
     let libraries_dir = game_dir.join("libraries");
+    let mut library_jars = Vec::new();
 
     for lib in &version_details.libraries {
         if core::rules::is_library_allowed(&lib.rules) {
-            if let Some(downloads) = &lib.downloads {
-                if let Some(artifact) = &downloads.artifact {
-                    let path_str = artifact
-                        .path
-                        .clone()
-                        .unwrap_or_else(|| format!("{}.jar", lib.name));
-
-                    let mut lib_path = libraries_dir.clone();
-                    lib_path.push(path_str);
-
-                    download_tasks.push(core::downloader::DownloadTask {
-                        url: artifact.url.clone(),
-                        path: lib_path,
-                        sha1: artifact.sha1.clone(),
-                        sha256: None,
-                    });
-                }
+            let repository = config.maven_repository.as_deref();
+            library_jars.extend(core::maven::library_jar(lib, &libraries_dir, repository));
 
+            if let Some(downloads) = &lib.downloads {
                 // Native Library (classifiers)
                 if let Some(classifiers) = &downloads.classifiers {
                     let os_key = if cfg!(target_os = "linux") {
@@ -858,29 +1046,35 @@ async fn install_version(
                                 path: native_path.clone(),
                                 sha1: native_artifact.sha1,
                                 sha256: None,
+                                size: native_artifact.size,
+                                mirrors: Vec::new(),
                             });
                         }
                     }
                 }
-            } else {
-                // Library without explicit downloads (mod loader libraries)
-                if let Some(url) =
-                    core::maven::resolve_library_url(&lib.name, None, lib.url.as_deref())
-                {
-                    if let Some(lib_path) = core::maven::get_library_path(&lib.name, &libraries_dir)
-                    {
-                        download_tasks.push(core::downloader::DownloadTask {
-                            url,
-                            path: lib_path,
-                            sha1: None,
-                            sha256: None,
-                        });
-                    }
-                }
             }
         }
     }
 
+    // Maven libraries may live in another repository than guessed
+    core::maven::locate_jars(&mut library_jars).await;
+    let mut library_tasks: Vec<_> = library_jars
+        .into_iter()
+        .filter_map(|jar| {
+            Some(core::downloader::DownloadTask {
+                url: jar.url?,
+                path: jar.path,
+                sha1: jar.sha1,
+                sha256: None,
+                size: jar.size,
+                mirrors: Vec::new(),
+            })
+        })
+        .collect();
+    // Maven libraries without a hash in their JSON
+    core::maven::fill_missing_sha1(&mut library_tasks).await;
+    download_tasks.extend(library_tasks);
+
     // --- Assets ---
     let assets_dir = game_dir.join("assets");
     let objects_dir = assets_dir.join("objects");
@@ -894,47 +1088,26 @@ async fn install_version(
     let asset_index_path = indexes_dir.join(format!("{}.json", asset_index.id));
 
     let asset_index_content: String = if asset_index_path.exists() {
-        tokio::fs::read_to_string(&asset_index_path)
-            .await
-            .map_err(|e| e.to_string())?
+        tokio::fs::read_to_string(&asset_index_path).await?
     } else {
-        emit_log!(window, format!("Downloading asset index..."));
-        let content = reqwest::get(&asset_index.url)
-            .await
-            .map_err(|e| e.to_string())?
-            .text()
-            .await
-            .map_err(|e| e.to_string())?;
+        emit_log!(events, format!("Downloading asset index..."));
+        let content = core::http::get(&asset_index.url).await?.text().await?;
 
-        tokio::fs::create_dir_all(&indexes_dir)
-            .await
-            .map_err(|e| e.to_string())?;
-        tokio::fs::write(&asset_index_path, &content)
-            .await
-            .map_err(|e| e.to_string())?;
+        tokio::fs::create_dir_all(&indexes_dir).await?;
+        tokio::fs::write(&asset_index_path, &content).await?;
         content
     };
 
-    #[derive(serde::Deserialize)]
-    struct AssetObject {
-        hash: String,
-    }
-
-    #[derive(serde::Deserialize)]
-    struct AssetIndexJson {
-        objects: std::collections::HashMap<String, AssetObject>,
-    }
-
-    let asset_index_parsed: AssetIndexJson =
-        serde_json::from_str(&asset_index_content).map_err(|e| e.to_string())?;
+    let asset_index_parsed: core::assets::AssetIndexJson =
+        serde_json::from_str(&asset_index_content)?;
 
     emit_log!(
-        window,
+        events,
         format!("Processing {} assets...", asset_index_parsed.objects.len())
     );
 
-    for (_name, object) in asset_index_parsed.objects {
-        let hash = object.hash;
+    for object in asset_index_parsed.objects.values() {
+        let hash = object.hash.clone();
         let prefix = &hash[0..2];
         let path = objects_dir.join(prefix).join(&hash);
         let url = format!(
@@ -947,11 +1120,17 @@ async fn install_version(
             path,
             sha1: Some(hash),
             sha256: None,
+            size: (object.size > 0).then_some(object.size),
+            mirrors: Vec::new(),
         });
     }
 
+    if let Some(setup) = core::log4j::logging_setup(&assets_dir, &version_details) {
+        download_tasks.push(setup.task);
+    }
+
     emit_log!(
-        window,
+        events,
         format!(
             "Total download tasks: {} (Client + Libraries + Assets)",
             download_tasks.len()
@@ -959,23 +1138,28 @@ async fn install_version(
     );
 
     // Start Download
+    if !allow_metered {
+        let bytes = core::metered::missing_bytes(&download_tasks);
+        core::metered::check(config, bytes, &format!("Installing {}", version_id))?;
+    }
     emit_log!(
-        window,
+        events,
         format!(
             "Starting downloads with {} concurrent threads...",
             config.download_threads
         )
     );
-    core::downloader::download_files(
-        window.clone(),
-        download_tasks,
-        config.download_threads as usize,
-    )
-    .await
-    .map_err(|e| e.to_string())?;
+    core::downloader::download_files(events.clone(), download_tasks, config).await?;
+
+    core::assets::reconstruct_legacy_assets(
+        game_dir,
+        &assets_dir,
+        &asset_index.id,
+        &asset_index_parsed,
+    )?;
 
     emit_log!(
-        window,
+        events,
         format!("Installation of {} completed successfully!", version_id)
     );
 
@@ -987,33 +1171,73 @@ async fn login_offline(
     window: Window,
     state: State<'_, core::auth::AccountState>,
     username: String,
-) -> Result<core::auth::Account, String> {
-    let uuid = core::auth::generate_offline_uuid(&username);
+    use_online_uuid: Option<bool>,
+) -> Result<core::account_storage::SavedAccount, LauncherError> {
+    let username = username.trim().to_string();
+    core::auth::validate_username(&username).map_err(LauncherError::Invalid)?;
+
+    // The premium account's UUID keeps skins and server data tied to it
+    let (username, uuid) = if use_online_uuid.unwrap_or(false) {
+        core::auth::lookup_online_profile(&username)
+            .await
+            .map_err(LauncherError::Network)?
+            .ok_or_else(|| {
+                LauncherError::Invalid(format!("No Minecraft account is named {}", username))
+            })?
+    } else {
+        let uuid = core::auth::generate_offline_uuid(&username);
+        (username, uuid)
+    };
     let account = core::auth::Account::Offline(core::auth::OfflineAccount { username, uuid });
 
     *state.active_account.lock().unwrap() = Some(account.clone());
 
     // Save to storage
     let app_handle = window.app_handle();
-    let app_dir = app_handle
-        .path()
-        .app_data_dir()
-        .map_err(|e| e.to_string())?;
+    let app_dir = app_handle.path().app_data_dir()?;
     let storage = core::account_storage::AccountStorage::new(app_dir);
-    storage.add_or_update_account(&account, None)?;
+    let change = storage.add_or_update_account(&account, None)?;
 
-    Ok(account)
+    Ok(core::account_storage::SavedAccount { account, change })
+}
+
+/// Stored accounts, most recently used first
+#[tauri::command]
+async fn list_accounts(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<core::account_storage::AccountSummary>, LauncherError> {
+    let app_dir = app_handle.path().app_data_dir()?;
+    Ok(core::account_storage::AccountStorage::new(app_dir).list())
 }
 
 #[tauri::command]
 async fn get_active_account(
     state: State<'_, core::auth::AccountState>,
-) -> Result<Option<core::auth::Account>, String> {
+) -> Result<Option<core::auth::Account>, LauncherError> {
     Ok(state.active_account.lock().unwrap().clone())
 }
 
+/// A player's head as a PNG data URL, or None if they have no skin.
+/// Heads are cached so the account switcher works offline.
+#[tauri::command]
+async fn get_account_avatar(
+    app_handle: tauri::AppHandle,
+    uuid: String,
+) -> Result<Option<String>, LauncherError> {
+    let cache_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?
+        .join("cache")
+        .join("avatars");
+    Ok(core::avatar::get_avatar(&cache_dir, &uuid).await?)
+}
+
 #[tauri::command]
-async fn logout(window: Window, state: State<'_, core::auth::AccountState>) -> Result<(), String> {
+async fn logout(
+    window: Window,
+    state: State<'_, core::auth::AccountState>,
+) -> Result<(), LauncherError> {
     // Get current account UUID before clearing
     let uuid = state
         .active_account
@@ -1027,10 +1251,7 @@ async fn logout(window: Window, state: State<'_, core::auth::AccountState>) -> R
     // Remove from storage
     if let Some(uuid) = uuid {
         let app_handle = window.app_handle();
-        let app_dir = app_handle
-            .path()
-            .app_data_dir()
-            .map_err(|e| e.to_string())?;
+        let app_dir = app_handle.path().app_data_dir()?;
         let storage = core::account_storage::AccountStorage::new(app_dir);
         storage.remove_account(&uuid)?;
     }
@@ -1041,45 +1262,92 @@ async fn logout(window: Window, state: State<'_, core::auth::AccountState>) -> R
 #[tauri::command]
 async fn get_settings(
     state: State<'_, core::config::ConfigState>,
-) -> Result<core::config::LauncherConfig, String> {
+) -> Result<core::config::LauncherConfig, LauncherError> {
     Ok(state.config.lock().unwrap().clone())
 }
 
 #[tauri::command]
 async fn save_settings(
+    app_handle: tauri::AppHandle,
     state: State<'_, core::config::ConfigState>,
     config: core::config::LauncherConfig,
-) -> Result<(), String> {
+) -> Result<(), LauncherError> {
+    core::http::check_proxy(&config.proxy).map_err(LauncherError::Invalid)?;
+    core::http::configure(&config);
+    core::logging::configure(&config);
+    tray::apply(&app_handle, config.show_tray_icon);
     *state.config.lock().unwrap() = config;
     state.save()?;
     Ok(())
 }
 
+/// Request a device code and poll for the user's sign-in in the background.
+/// Progress is reported through `ms-login-pending` and the outcome through
+/// `ms-login-complete`.
 #[tauri::command]
-async fn start_microsoft_login() -> Result<core::auth::DeviceCodeResponse, String> {
-    core::auth::start_device_flow().await
+async fn start_microsoft_login(
+    app_handle: tauri::AppHandle,
+) -> Result<core::auth::DeviceCodeResponse, LauncherError> {
+    let device = core::auth::start_device_flow().await?;
+    let generation = core::auth::begin_device_login();
+
+    let poll_device = device.clone();
+    tauri::async_runtime::spawn(async move {
+        let events = app_handle.clone();
+        let polled = core::auth::poll_device_token(&poll_device, generation, |seconds| {
+            events.emit_event(core::auth::MsLoginPending {
+                seconds_remaining: seconds,
+            });
+        })
+        .await;
+        let result = match polled {
+            Ok(Some(token_resp)) => finish_microsoft_login(&app_handle, token_resp).await,
+            // Cancelled or superseded by a newer login
+            Ok(None) => return,
+            Err(e) => Err(e.into()),
+        };
+        let (account, change, error) = match result {
+            Ok(Some(saved)) => (Some(saved.account), Some(saved.change), None),
+            Ok(None) => (None, None, None),
+            Err(e) => (None, None, Some(e)),
+        };
+        app_handle.emit_event(core::auth::MsLoginComplete {
+            needs_profile: account.is_none() && error.is_none(),
+            account,
+            change,
+            error,
+        });
+    });
+
+    Ok(device)
 }
 
 #[tauri::command]
-async fn complete_microsoft_login(
-    window: Window,
-    state: State<'_, core::auth::AccountState>,
-    ms_refresh_state: State<'_, MsRefreshTokenState>,
-    device_code: String,
-) -> Result<core::auth::Account, String> {
+async fn cancel_microsoft_login() -> Result<(), LauncherError> {
+    core::auth::cancel_device_login();
+    Ok(())
+}
+
+/// Exchange a Microsoft token for a Minecraft account and make it active.
+/// `None` if the account has no Minecraft profile yet; the login is then
+/// kept until `create_minecraft_profile` is called.
+async fn finish_microsoft_login(
+    app_handle: &tauri::AppHandle,
+    token_resp: core::auth::TokenResponse,
+) -> Result<Option<core::account_storage::SavedAccount>, LauncherError> {
     // Helper to emit auth progress
     let emit_progress = |step: &str| {
-        let _ = window.emit("auth-progress", step);
+        let _ = app_handle.emit("auth-progress", step);
     };
-
-    // 1. Poll (once) for token
-    emit_progress("Receiving token from Microsoft...");
-    let token_resp = core::auth::exchange_code_for_token(&device_code).await?;
     emit_progress("Token received successfully!");
 
     // Store MS refresh token
     let ms_refresh_token = token_resp.refresh_token.clone();
-    *ms_refresh_state.token.lock().unwrap() = ms_refresh_token.clone();
+    *app_handle
+        .state::<MsRefreshTokenState>()
+        .token
+        .lock()
+        .unwrap() = ms_refresh_token.clone();
 
     // 2. Xbox Live Auth
     emit_progress("Authenticating with Xbox Live...");
@@ -1096,37 +1364,162 @@ async fn complete_microsoft_login(
     let mc_token = core::auth::login_minecraft(&xsts_token, &uhs).await?;
     emit_progress("Minecraft authentication successful!");
 
-    // 5. Get Profile
-    emit_progress("Fetching Minecraft profile...");
-    let profile = core::auth::fetch_profile(&mc_token).await?;
-    emit_progress(&format!("Welcome, {}!", profile.name));
-
-    // 6. Create Account
-    let account = core::auth::Account::Microsoft(core::auth::MicrosoftAccount {
-        username: profile.name,
-        uuid: profile.id,
-        access_token: mc_token, // This is the MC Access Token
-        refresh_token: token_resp.refresh_token.clone(),
+    let pending = core::auth::PendingProfile {
+        mc_access_token: mc_token,
+        ms_refresh_token,
         expires_at: (std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs()
             + token_resp.expires_in) as i64,
-    });
+    };
 
-    // 7. Save to state
-    *state.active_account.lock().unwrap() = Some(account.clone());
+    // 5. Get Profile; new Game Pass accounts have to create one first
+    emit_progress("Fetching Minecraft profile...");
+    let profile = match core::auth::fetch_profile(&pending.mc_access_token).await {
+        Ok(profile) => profile,
+        Err(core::auth::AuthError::NoProfile) => {
+            emit_progress("This account has no Minecraft profile yet. Choose a name for it.");
+            *app_handle
+                .state::<core::auth::AccountState>()
+                .pending_profile
+                .lock()
+                .unwrap() = Some(pending);
+            return Ok(None);
+        }
+        Err(e) => return Err(e.into()),
+    };
+    emit_progress(&format!("Welcome, {}!", profile.name));
 
-    // 8. Save to storage
-    let app_handle = window.app_handle();
-    let app_dir = app_handle
-        .path()
-        .app_data_dir()
-        .map_err(|e| e.to_string())?;
+    save_microsoft_login(app_handle, pending, profile).map(Some)
+}
+
+/// Make a Microsoft login with a profile the active, stored account.
+fn save_microsoft_login(
+    app_handle: &tauri::AppHandle,
+    login: core::auth::PendingProfile,
+    profile: core::auth::MinecraftProfile,
+) -> Result<core::account_storage::SavedAccount, LauncherError> {
+    let (account, ms_refresh_token) = login.into_account(profile);
+
+    *app_handle
+        .state::<core::auth::AccountState>()
+        .active_account
+        .lock()
+        .unwrap() = Some(account.clone());
+
+    let app_dir = app_handle.path().app_data_dir()?;
     let storage = core::account_storage::AccountStorage::new(app_dir);
-    storage.add_or_update_account(&account, ms_refresh_token)?;
+    let change = storage.add_or_update_account(&account, ms_refresh_token)?;
 
-    Ok(account)
+    Ok(core::account_storage::SavedAccount { account, change })
+}
+
+/// Create the Minecraft profile of a login that has none, after checking
+/// that `name` is available, and finish the login.
+#[tauri::command]
+async fn create_minecraft_profile(
+    app_handle: tauri::AppHandle,
+    name: String,
+) -> Result<core::account_storage::SavedAccount, LauncherError> {
+    let name = name.trim().to_string();
+    core::auth::validate_username(&name).map_err(LauncherError::Invalid)?;
+    let pending = app_handle
+        .state::<core::auth::AccountState>()
+        .pending_profile
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| LauncherError::Invalid("No login is waiting for a profile".to_string()))?;
+
+    let problem = match core::auth::check_name_availability(&pending.mc_access_token, &name).await?
+    {
+        core::auth::NameAvailability::Available => None,
+        core::auth::NameAvailability::Duplicate => Some("is taken"),
+        core::auth::NameAvailability::NotAllowed => Some("is not allowed"),
+    };
+    if let Some(problem) = problem {
+        return Err(LauncherError::Invalid(format!(
+            "The name {} {}",
+            name, problem
+        )));
+    }
+    let profile = core::auth::create_profile(&pending.mc_access_token, &name).await?;
+    *app_handle
+        .state::<core::auth::AccountState>()
+        .pending_profile
+        .lock()
+        .unwrap() = None;
+    save_microsoft_login(&app_handle, pending, profile)
+}
+
+/// A stored account ready to launch with, its Microsoft token refreshed
+/// and saved if it has expired.
+async fn usable_account(
+    events: &impl EventTarget,
+    data_dir: &std::path::Path,
+    stored: core::account_storage::StoredAccount,
+    ms_refresh: Option<String>,
+) -> Result<core::auth::Account, LauncherError> {
+    let account = stored.to_account();
+    let core::auth::Account::Microsoft(microsoft) = &account else {
+        return Ok(account);
+    };
+    if !core::auth::is_token_expired(microsoft.expires_at) {
+        return Ok(account);
+    }
+
+    let ms_refresh = ms_refresh.ok_or("The login has expired; log in again in the launcher")?;
+    let (refreshed, new_ms_refresh) = core::auth::refresh_full_auth(&ms_refresh).await?;
+    let refreshed = core::auth::Account::Microsoft(refreshed);
+    let storage = core::account_storage::AccountStorage::new(data_dir.to_path_buf());
+    let update = storage.update_account(&account.uuid(), &refreshed, Some(new_ms_refresh))?;
+    if let Some(update) = update {
+        profile_updated(events, data_dir, update).await;
+    }
+    Ok(refreshed)
+}
+
+/// Fetch the heads of an account whose profile changed again and tell the UI.
+async fn profile_updated(
+    events: &impl EventTarget,
+    data_dir: &std::path::Path,
+    update: core::account_storage::AccountProfileUpdated,
+) {
+    let cache_dir = data_dir.join("cache").join("avatars");
+    core::avatar::invalidate(&cache_dir, &update.previous_uuid);
+    core::avatar::invalidate(&cache_dir, &update.uuid);
+    if let Err(e) = core::avatar::get_avatar(&cache_dir, &update.uuid).await {
+        println!(
+            "[Auth] Failed to fetch the head of {}: {}",
+            update.username, e
+        );
+    }
+    println!(
+        "[Auth] Profile {} is now {} ({})",
+        update.previous_username, update.username, update.uuid
+    );
+    events.emit_event(update);
+}
+
+/// Game ownership and profile status of the active Microsoft account
+#[tauri::command]
+async fn get_account_entitlements(
+    window: Window,
+) -> Result<core::auth::AccountEntitlements, LauncherError> {
+    let app_dir = window.app_handle().path().app_data_dir()?;
+    let storage = core::account_storage::AccountStorage::new(app_dir.clone());
+    let (stored, ms_refresh) = storage
+        .get_active_account()
+        .ok_or("No active account found")?;
+    let core::auth::Account::Microsoft(account) =
+        usable_account(&window, &app_dir, stored, ms_refresh).await?
+    else {
+        return Err(LauncherError::Invalid(
+            "Offline accounts have no entitlements".to_string(),
+        ));
+    };
+    Ok(core::auth::check_entitlements(&account.access_token).await?)
 }
 
 /// Refresh token for current Microsoft account
@@ -1135,16 +1528,13 @@ async fn refresh_account(
     window: Window,
     state: State<'_, core::auth::AccountState>,
     ms_refresh_state: State<'_, MsRefreshTokenState>,
-) -> Result<core::auth::Account, String> {
+) -> Result<core::auth::Account, LauncherError> {
     // Get stored MS refresh token
     let app_handle = window.app_handle();
-    let app_dir = app_handle
-        .path()
-        .app_data_dir()
-        .map_err(|e| e.to_string())?;
+    let app_dir = app_handle.path().app_data_dir()?;
     let storage = core::account_storage::AccountStorage::new(app_dir.clone());
 
-    let (_stored_account, ms_refresh) = storage
+    let (stored_account, ms_refresh) = storage
         .get_active_account()
         .ok_or("No active account found")?;
 
@@ -1159,14 +1549,19 @@ async fn refresh_account(
     *ms_refresh_state.token.lock().unwrap() = Some(new_ms_refresh.clone());
 
     // Update storage
-    storage.add_or_update_account(&account, Some(new_ms_refresh))?;
+    let update = storage.update_account(&stored_account.id(), &account, Some(new_ms_refresh))?;
+    if let Some(update) = update {
+        profile_updated(&window, &app_dir, update).await;
+    }
 
     Ok(account)
 }
 
 /// Detect Java installations on the system
 #[tauri::command]
-async fn detect_java(app_handle: tauri::AppHandle) -> Result<Vec<core::java::JavaInstallation>, String> {
+async fn detect_java(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<core::java::JavaInstallation>, LauncherError> {
     Ok(core::java::detect_all_java_installations(&app_handle))
 }
 
@@ -1174,64 +1569,326 @@ async fn detect_java(app_handle: tauri::AppHandle) -> Result<Vec<core::java::Jav
 #[tauri::command]
 async fn get_recommended_java(
     required_major_version: Option<u64>,
-) -> Result<Option<core::java::JavaInstallation>, String> {
+) -> Result<Option<core::java::JavaInstallation>, LauncherError> {
     Ok(core::java::get_recommended_java(required_major_version))
 }
 
-/// Get Adoptium Java download info
+/// Get Java download info, from Adoptium unless another vendor is given
 #[tauri::command]
 async fn fetch_adoptium_java(
     major_version: u32,
     image_type: String,
-) -> Result<core::java::JavaDownloadInfo, String> {
+    vendor: Option<core::java::JavaVendor>,
+) -> Result<core::java::JavaDownloadInfo, LauncherError> {
     let img_type = match image_type.to_lowercase().as_str() {
         "jdk" => core::java::ImageType::Jdk,
         _ => core::java::ImageType::Jre,
     };
-    core::java::fetch_java_release(major_version, img_type).await
+    let vendor = vendor.unwrap_or_default();
+    Ok(vendor.fetch_release(major_version, img_type).await?)
 }
 
-/// Download and install Adoptium Java
+/// Download and install Java, from Adoptium unless another vendor is given
 #[tauri::command]
 async fn download_adoptium_java(
     app_handle: tauri::AppHandle,
+    config_state: State<'_, core::config::ConfigState>,
     major_version: u32,
     image_type: String,
     custom_path: Option<String>,
-) -> Result<core::java::JavaInstallation, String> {
+    vendor: Option<core::java::JavaVendor>,
+    allow_metered: Option<bool>,
+) -> Result<core::java::JavaInstallation, LauncherError> {
     let img_type = match image_type.to_lowercase().as_str() {
         "jdk" => core::java::ImageType::Jdk,
         _ => core::java::ImageType::Jre,
     };
+    if !allow_metered.unwrap_or(false) {
+        let info = vendor
+            .unwrap_or_default()
+            .fetch_release(major_version, img_type)
+            .await?;
+        let config = config_state.config.lock().unwrap().clone();
+        let what = format!("Java {}", major_version);
+        core::metered::check(&config, info.file_size, &what)?;
+    }
     let path = custom_path.map(std::path::PathBuf::from);
-    core::java::download_and_install_java(&app_handle, major_version, img_type, path).await
+    Ok(core::java::download_and_install_java(
+        &app_handle,
+        vendor.unwrap_or_default(),
+        major_version,
+        img_type,
+        path,
+    )
+    .await?)
+}
+
+/// Managed Java runtimes and whether a newer build of each is available
+#[tauri::command]
+async fn check_java_updates(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<core::java::JavaUpdate>, LauncherError> {
+    Ok(core::java::check_java_updates(&app_handle).await?)
+}
+
+/// Install the newest build of the managed runtimes of `major_version`,
+/// optionally only the JRE or JDK. A Java path setting pointing into an
+/// updated runtime is moved to the new build.
+#[tauri::command]
+async fn update_java(
+    app_handle: tauri::AppHandle,
+    config_state: State<'_, core::config::ConfigState>,
+    major_version: u32,
+    image_type: Option<String>,
+) -> Result<Vec<core::java::JavaInstallation>, LauncherError> {
+    let java_dir = core::java::get_java_install_dir(&app_handle);
+    let mut installed = Vec::new();
+    for update in core::java::check_java_updates(&app_handle).await? {
+        let matches_type = image_type
+            .as_deref()
+            .is_none_or(|t| t.eq_ignore_ascii_case(&update.image_type.to_string()));
+        if update.major_version != major_version || !matches_type || !update.update_available {
+            continue;
+        }
+        let runtime_dir = java_dir.join(
+            update
+                .vendor
+                .runtime_dir_name(update.major_version, update.image_type),
+        );
+        let installation = core::java::download_and_install_java(
+            &app_handle,
+            update.vendor,
+            update.major_version,
+            update.image_type,
+            None,
+        )
+        .await?;
+
+        let mut config = config_state.config.lock().unwrap();
+        if std::path::Path::new(&config.java_path).starts_with(&runtime_dir) {
+            config.java_path = installation.path.clone();
+            drop(config);
+            config_state.save()?;
+        }
+        installed.push(installation);
+    }
+    if installed.is_empty() {
+        return Err(LauncherError::Invalid(format!(
+            "No managed Java {} runtime has an update",
+            major_version
+        )));
+    }
+    Ok(installed)
+}
+
+/// Check a Java runtime for damage. With `reinstall`, a damaged runtime the
+/// launcher installed is downloaded and installed again.
+#[tauri::command]
+async fn verify_java_installation(
+    app_handle: tauri::AppHandle,
+    path: String,
+    reinstall: Option<bool>,
+) -> Result<core::java_integrity::JavaVerification, LauncherError> {
+    let java_path = std::path::PathBuf::from(&path);
+    let java_dir = core::java::get_java_install_dir(&app_handle);
+    let (check_dir, check_path) = (java_dir.clone(), java_path.clone());
+    let mut verification =
+        tokio::task::spawn_blocking(move || core::java_integrity::verify(&check_dir, &check_path))
+            .await?;
+    if verification.valid || !reinstall.unwrap_or(false) {
+        return Ok(verification);
+    }
+
+    let (runtime_dir, vendor, major_version, image_type) =
+        core::java_integrity::managed_runtime(&java_dir, &java_path).ok_or_else(|| {
+            LauncherError::Invalid(format!(
+                "{} was not installed by the launcher and cannot be reinstalled",
+                path
+            ))
+        })?;
+    let install_base = runtime_dir.parent().map(std::path::Path::to_path_buf);
+    let installation = core::java::download_and_install_java(
+        &app_handle,
+        vendor,
+        major_version,
+        image_type,
+        install_base,
+    )
+    .await?;
+    let installed = std::path::PathBuf::from(&installation.path);
+    verification =
+        tokio::task::spawn_blocking(move || core::java_integrity::verify(&java_dir, &installed))
+            .await?;
+    verification.reinstalled = true;
+    Ok(verification)
+}
+
+/// Whether the first-launch setup has not been completed yet
+#[tauri::command]
+async fn needs_initial_setup(
+    config_state: State<'_, core::config::ConfigState>,
+) -> Result<bool, LauncherError> {
+    Ok(!config_state.file_path.exists())
+}
+
+/// OS, CPU, memory, GPUs and launcher paths, for Settings and bug reports
+#[tauri::command]
+async fn get_system_info(
+    app_handle: tauri::AppHandle,
+    config_state: State<'_, core::config::ConfigState>,
+) -> Result<core::diagnostics::SystemDiagnostics, LauncherError> {
+    collect_diagnostics(&app_handle, &config_state).await
+}
+
+/// Warning if the launcher's data folder is inside a cloud-synced folder
+#[tauri::command]
+async fn check_cloud_sync(
+    app_handle: tauri::AppHandle,
+) -> Result<Option<core::cloud_sync::CloudSyncWarning>, LauncherError> {
+    let data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(core::cloud_sync::check(&data_dir))
+}
+
+async fn collect_diagnostics(
+    app_handle: &tauri::AppHandle,
+    config_state: &core::config::ConfigState,
+) -> Result<core::diagnostics::SystemDiagnostics, LauncherError> {
+    let data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    let paths = core::diagnostics::LauncherPaths::new(&data_dir, &config_state.file_path);
+    tokio::task::spawn_blocking(move || core::diagnostics::collect(paths))
+        .await
+        .map_err(|e| LauncherError::Other(e.to_string()))
+}
+
+/// Propose settings for the first-launch setup without changing anything
+#[tauri::command]
+async fn propose_setup_defaults(
+    app_handle: tauri::AppHandle,
+) -> Result<core::setup::SetupProposal, LauncherError> {
+    Ok(core::setup::propose_defaults(&app_handle).await?)
+}
+
+/// Apply first-launch setup choices, emitting `setup-step` events per step
+#[tauri::command]
+async fn run_initial_setup(
+    window: Window,
+    config_state: State<'_, core::config::ConfigState>,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    options: core::setup::SetupOptions,
+) -> Result<core::setup::SetupResult, LauncherError> {
+    use core::setup::{emit_step, SetupStep, StepStatus};
+
+    let java_path = core::setup::resolve_java(&window, &options).await?;
+
+    emit_step(&window, SetupStep::ApplySettings, StepStatus::Started, None);
+    {
+        let mut config = config_state.config.lock().unwrap();
+        config.min_memory = options.min_memory.min(options.max_memory);
+        config.max_memory = options.max_memory;
+        config.download_threads = options.download_threads.clamp(1, 128);
+        config.java_path = java_path.clone();
+    }
+    if let Err(e) = config_state.save() {
+        emit_step(
+            &window,
+            SetupStep::ApplySettings,
+            StepStatus::Failed,
+            Some(&e),
+        );
+        return Err(e.into());
+    }
+    emit_step(
+        &window,
+        SetupStep::ApplySettings,
+        StepStatus::Completed,
+        None,
+    );
+
+    let instance = match &options.default_instance_version {
+        Some(version_id) => {
+            emit_step(
+                &window,
+                SetupStep::CreateInstance,
+                StepStatus::Started,
+                None,
+            );
+            let name = options
+                .default_instance_name
+                .clone()
+                .unwrap_or_else(|| format!("Minecraft {}", version_id));
+            match instance_state.create(&name, version_id) {
+                Ok(instance) => {
+                    instance_state.set_active(&instance.id)?;
+                    watch_active_instance(window.app_handle());
+                    emit_step(
+                        &window,
+                        SetupStep::CreateInstance,
+                        StepStatus::Completed,
+                        None,
+                    );
+                    Some(instance)
+                }
+                Err(e) => {
+                    emit_step(
+                        &window,
+                        SetupStep::CreateInstance,
+                        StepStatus::Failed,
+                        Some(&e),
+                    );
+                    return Err(e.into());
+                }
+            }
+        }
+        None => {
+            emit_step(
+                &window,
+                SetupStep::CreateInstance,
+                StepStatus::Skipped,
+                None,
+            );
+            None
+        }
+    };
+
+    emit_step(&window, SetupStep::Done, StepStatus::Completed, None);
+    emit_log!(window, "Initial setup complete".to_string());
+    Ok(core::setup::SetupResult {
+        java_path,
+        instance,
+    })
 }
 
 /// Get available Adoptium Java versions
 #[tauri::command]
-async fn fetch_available_java_versions() -> Result<Vec<u32>, String> {
-    core::java::fetch_available_versions().await
+async fn fetch_available_java_versions() -> Result<Vec<u32>, LauncherError> {
+    Ok(core::java::fetch_available_versions().await?)
 }
 
 /// Fetch Java catalog with platform availability (uses cache)
 #[tauri::command]
 async fn fetch_java_catalog(
     app_handle: tauri::AppHandle,
-) -> Result<core::java::JavaCatalog, String> {
-    core::java::fetch_java_catalog(&app_handle, false).await
+) -> Result<core::java::JavaCatalog, LauncherError> {
+    Ok(core::java::fetch_java_catalog(&app_handle, false).await?)
 }
 
 /// Refresh Java catalog (bypass cache)
 #[tauri::command]
 async fn refresh_java_catalog(
     app_handle: tauri::AppHandle,
-) -> Result<core::java::JavaCatalog, String> {
-    core::java::fetch_java_catalog(&app_handle, true).await
+) -> Result<core::java::JavaCatalog, LauncherError> {
+    Ok(core::java::fetch_java_catalog(&app_handle, true).await?)
 }
 
 /// Cancel current Java download
 #[tauri::command]
-async fn cancel_java_download() -> Result<(), String> {
+async fn cancel_java_download() -> Result<(), LauncherError> {
     core::java::cancel_current_download();
     Ok(())
 }
@@ -1240,7 +1897,7 @@ async fn cancel_java_download() -> Result<(), String> {
 #[tauri::command]
 async fn get_pending_java_downloads(
     app_handle: tauri::AppHandle,
-) -> Result<Vec<core::downloader::PendingJavaDownload>, String> {
+) -> Result<Vec<core::downloader::PendingJavaDownload>, LauncherError> {
     Ok(core::java::get_pending_downloads(&app_handle))
 }
 
@@ -1248,34 +1905,131 @@ async fn get_pending_java_downloads(
 #[tauri::command]
 async fn resume_java_downloads(
     app_handle: tauri::AppHandle,
-) -> Result<Vec<core::java::JavaInstallation>, String> {
-    core::java::resume_pending_downloads(&app_handle).await
+) -> Result<Vec<core::java::JavaInstallation>, LauncherError> {
+    Ok(core::java::resume_pending_downloads(&app_handle).await?)
+}
+
+/// Get interrupted downloads of every kind: Java runtimes and game files
+#[tauri::command]
+async fn get_pending_downloads(
+    app_handle: tauri::AppHandle,
+) -> Result<core::downloader::PendingDownloads, LauncherError> {
+    Ok(core::downloader::PendingDownloads::load(&app_handle))
+}
+
+/// Resume interrupted downloads of every kind
+#[tauri::command]
+async fn resume_pending_downloads(
+    window: Window,
+    config_state: State<'_, core::config::ConfigState>,
+    instance_state: State<'_, core::instance::InstanceManager>,
+) -> Result<core::downloader::ResumeReport, LauncherError> {
+    let app_handle = window.app_handle().clone();
+    let mut report = core::downloader::ResumeReport {
+        java: core::java::resume_pending_downloads(&app_handle).await?,
+        files: 0,
+        reverted_pack_updates: 0,
+        errors: Vec::new(),
+    };
+    for pending in core::downloader::DownloadQueue::take_pending_files(&app_handle) {
+        if pending.kind == core::downloader::PendingKind::PackUpdate {
+            // Files the update replaced come back from the backup taken before
+            // it; the files it added are not in the backup and go afterwards
+            if let Some(backup) = pending.backup.clone() {
+                let (Some(instance_dir), Some(file_name)) = (
+                    backup.parent().and_then(std::path::Path::parent),
+                    backup.file_name(),
+                ) else {
+                    continue;
+                };
+                // Nothing to roll back in a deleted instance
+                if !instance_dir.exists() {
+                    continue;
+                }
+                let (instance_dir, file_name) = (
+                    instance_dir.to_path_buf(),
+                    file_name.to_string_lossy().to_string(),
+                );
+                let restored = tokio::task::spawn_blocking(move || {
+                    core::instance_backup::restore(&instance_dir, &file_name)
+                })
+                .await?;
+                if let Err(e) = restored {
+                    report.errors.push(e);
+                    continue;
+                }
+            }
+            for task in &pending.tasks {
+                if let Err(e) = tokio::fs::remove_file(&task.path).await {
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        report
+                            .errors
+                            .push(format!("{}: {}", task.path.display(), e));
+                    }
+                }
+            }
+            report.reverted_pack_updates += 1;
+            continue;
+        }
+        // Files of deleted instances are not needed anymore
+        let network = match &pending.instance_id {
+            Some(id) => match instance_state.get(id) {
+                Ok(instance) => Some(instance.network),
+                Err(_) => continue,
+            },
+            None => None,
+        };
+        let config = config_state.effective(network.as_ref());
+        let events = EventSink::new(
+            window.clone(),
+            EventScope {
+                instance_id: pending.instance_id.clone(),
+                session_id: None,
+            },
+        );
+        let count = pending.tasks.len();
+        match core::downloader::download_files(events, pending.tasks, &config).await {
+            Ok(()) => report.files += count,
+            Err(e) => report.errors.push(e),
+        }
+    }
+    emit_log!(
+        window,
+        format!(
+            "Resumed {} Java download(s) and {} game file(s); reverted {} modpack update(s)",
+            report.java.len(),
+            report.files,
+            report.reverted_pack_updates
+        )
+    );
+    Ok(report)
 }
 
 /// Get Minecraft versions supported by Fabric
 #[tauri::command]
-async fn get_fabric_game_versions() -> Result<Vec<core::fabric::FabricGameVersion>, String> {
+async fn get_fabric_game_versions() -> Result<Vec<core::fabric::FabricGameVersion>, LauncherError> {
     core::fabric::fetch_supported_game_versions()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| LauncherError::Network(e.to_string()))
 }
 
 /// Get available Fabric loader versions
 #[tauri::command]
-async fn get_fabric_loader_versions() -> Result<Vec<core::fabric::FabricLoaderVersion>, String> {
+async fn get_fabric_loader_versions(
+) -> Result<Vec<core::fabric::FabricLoaderVersion>, LauncherError> {
     core::fabric::fetch_loader_versions()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| LauncherError::Network(e.to_string()))
 }
 
 /// Get Fabric loaders available for a specific Minecraft version
 #[tauri::command]
 async fn get_fabric_loaders_for_version(
     game_version: String,
-) -> Result<Vec<core::fabric::FabricLoaderEntry>, String> {
+) -> Result<Vec<core::fabric::FabricLoaderEntry>, LauncherError> {
     core::fabric::fetch_loaders_for_game_version(&game_version)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| LauncherError::Network(e.to_string()))
 }
 
 /// Install Fabric loader for a specific Minecraft version
@@ -1284,7 +2038,7 @@ async fn install_fabric(
     window: Window,
     game_version: String,
     loader_version: String,
-) -> Result<core::fabric::InstalledFabricVersion, String> {
+) -> Result<core::fabric::InstalledFabricVersion, LauncherError> {
     emit_log!(
         window,
         format!(
@@ -1299,9 +2053,7 @@ async fn install_fabric(
         .app_data_dir()
         .map_err(|e| format!("Failed to get app data dir: {}", e))?;
 
-    let result = core::fabric::install_fabric(&game_dir, &game_version, &loader_version)
-        .await
-        .map_err(|e| e.to_string())?;
+    let result = core::fabric::install_fabric(&game_dir, &game_version, &loader_version).await?;
 
     emit_log!(
         window,
@@ -1313,16 +2065,14 @@ async fn install_fabric(
 
 /// List installed Fabric versions
 #[tauri::command]
-async fn list_installed_fabric_versions(window: Window) -> Result<Vec<String>, String> {
+async fn list_installed_fabric_versions(window: Window) -> Result<Vec<String>, LauncherError> {
     let app_handle = window.app_handle();
     let game_dir = app_handle
         .path()
         .app_data_dir()
         .map_err(|e| format!("Failed to get app data dir: {}", e))?;
 
-    core::fabric::list_installed_fabric_versions(&game_dir)
-        .await
-        .map_err(|e| e.to_string())
+    Ok(core::fabric::list_installed_fabric_versions(&game_dir).await?)
 }
 
 /// Installed version info
@@ -1336,13 +2086,18 @@ struct InstalledVersion {
 /// List all installed versions from the data directory
 /// Simply lists all folders in the versions directory without validation
 #[tauri::command]
-async fn list_installed_versions(window: Window) -> Result<Vec<InstalledVersion>, String> {
+async fn list_installed_versions(window: Window) -> Result<Vec<InstalledVersion>, LauncherError> {
     let app_handle = window.app_handle();
     let game_dir = app_handle
         .path()
         .app_data_dir()
         .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    read_installed_versions(&game_dir).await
+}
 
+async fn read_installed_versions(
+    game_dir: &std::path::Path,
+) -> Result<Vec<InstalledVersion>, LauncherError> {
     let versions_dir = game_dir.join("versions");
     let mut installed = Vec::new();
 
@@ -1350,13 +2105,11 @@ async fn list_installed_versions(window: Window) -> Result<Vec<InstalledVersion>
         return Ok(installed);
     }
 
-    let mut entries = tokio::fs::read_dir(&versions_dir)
-        .await
-        .map_err(|e| e.to_string())?;
+    let mut entries = tokio::fs::read_dir(&versions_dir).await?;
 
-    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+    while let Some(entry) = entries.next_entry().await? {
         // Only include directories
-        if !entry.file_type().await.map_err(|e| e.to_string())?.is_dir() {
+        if !entry.file_type().await?.is_dir() {
             continue;
         }
 
@@ -1418,94 +2171,2016 @@ async fn list_installed_versions(window: Window) -> Result<Vec<InstalledVersion>
     Ok(installed)
 }
 
-/// Check if Fabric is installed for a specific version
+/// List installed versions with loader, size and completeness details
 #[tauri::command]
-async fn is_fabric_installed(
+async fn list_local_versions(
     window: Window,
-    game_version: String,
-    loader_version: String,
-) -> Result<bool, String> {
+) -> Result<Vec<core::manifest::LocalVersionInfo>, LauncherError> {
     let app_handle = window.app_handle();
     let game_dir = app_handle
         .path()
         .app_data_dir()
         .map_err(|e| format!("Failed to get app data dir: {}", e))?;
 
-    Ok(core::fabric::is_fabric_installed(
-        &game_dir,
-        &game_version,
-        &loader_version,
-    ))
-}
+    let ids = core::manifest::list_local_versions(&game_dir).await?;
 
-/// Get Minecraft versions supported by Forge
-#[tauri::command]
-async fn get_forge_game_versions() -> Result<Vec<String>, String> {
-    core::forge::fetch_supported_game_versions()
-        .await
-        .map_err(|e| e.to_string())
-}
+    let mut versions = Vec::new();
+    for id in ids {
+        match core::manifest::inspect_local_version(&game_dir, &id).await {
+            Ok(info) => versions.push(info),
+            Err(e) => println!("[Versions] Skipping {}: {}", id, e),
+        }
+    }
+    versions.sort_by(|a, b| b.id.cmp(&a.id));
 
-/// Get available Forge versions for a specific Minecraft version
-#[tauri::command]
-async fn get_forge_versions_for_game(
-    game_version: String,
-) -> Result<Vec<core::forge::ForgeVersion>, String> {
-    core::forge::fetch_forge_versions(&game_version)
-        .await
-        .map_err(|e| e.to_string())
+    Ok(versions)
 }
 
-/// Install Forge for a specific Minecraft version
+/// Import a custom version from its JSON, or a version folder, and
+/// optionally a client jar
 #[tauri::command]
-async fn install_forge(
+async fn import_custom_version(
     window: Window,
-    config_state: State<'_, core::config::ConfigState>,
-    game_version: String,
-    forge_version: String,
-) -> Result<core::forge::InstalledForgeVersion, String> {
-    emit_log!(
-        window,
-        format!(
-            "Installing Forge {} for Minecraft {}...",
-            forge_version, game_version
-        )
-    );
+    json_path: String,
+    jar_path: Option<String>,
+) -> Result<core::custom_version::CustomVersionImport, LauncherError> {
+    let app_handle = window.app_handle();
+    let game_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+
+    let mut json_path = std::path::PathBuf::from(json_path);
+    let mut jar_path = jar_path.map(std::path::PathBuf::from);
+    if json_path.is_dir() {
+        let (json, jar) =
+            core::custom_version::folder_files(&json_path).map_err(LauncherError::Invalid)?;
+        json_path = json;
+        jar_path = jar_path.or(jar);
+    }
+    let report = tokio::task::spawn_blocking(move || {
+        core::custom_version::import(&game_dir, &json_path, jar_path.as_deref())
+    })
+    .await?
+    .map_err(LauncherError::Invalid)?;
+
+    emit_log!(window, format!("Imported custom version {}", report.id));
+    for warning in &report.warnings {
+        emit_log!(window, core::config::LogLevel::Warn, warning.clone());
+    }
+    Ok(report)
+}
 
+/// Delete an installed version directory
+#[tauri::command]
+async fn delete_version(window: Window, version_id: String) -> Result<(), LauncherError> {
     let app_handle = window.app_handle();
     let game_dir = app_handle
         .path()
         .app_data_dir()
         .map_err(|e| format!("Failed to get app data dir: {}", e))?;
 
-    // Get Java path from config or detect
-    let config = config_state.config.lock().unwrap().clone();
-    let java_path_str = if !config.java_path.is_empty() && config.java_path != "java" {
-        config.java_path.clone()
+    core::cleanup::delete_version(&game_dir, &version_id).await?;
+    emit_log!(window, format!("Deleted version {}", version_id));
+    Ok(())
+}
+
+/// Find libraries and assets no installed version uses; delete them unless `dry_run`
+#[tauri::command]
+async fn garbage_collect(
+    window: Window,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    dry_run: bool,
+) -> Result<core::cleanup::GarbageReport, LauncherError> {
+    let app_handle = window.app_handle();
+    let game_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+
+    let instances = instance_state.list();
+    let mods: Vec<_> = instances
+        .iter()
+        .flat_map(|instance| {
+            core::mod_store::mod_files(&instance_state.instance_dir(&instance.id).join("mods"))
+        })
+        .collect();
+    // Isolated instances keep their versions, and patched jars, to themselves
+    let mut jar_mods = Vec::new();
+    for instance in instances.iter().filter(|instance| !instance.isolated) {
+        let instance_dir = instance_state.instance_dir(&instance.id);
+        let enabled = core::jar_mods::enabled_paths(&instance_dir, instance);
+        if enabled.is_empty() {
+            continue;
+        }
+        let version = core::manifest::load_local_version(&game_dir, &instance.version_id).await;
+        if let Ok(version) = version {
+            let client_jar = core::launch::client_jar_path(&game_dir, version.client_jar_version());
+            jar_mods.push((client_jar, enabled));
+        }
+    }
+    let patched_jars: Vec<_> = tokio::task::spawn_blocking(move || {
+        jar_mods
+            .iter()
+            .filter_map(|(client_jar, enabled)| {
+                core::jar_mods::cached_jar(client_jar, enabled).ok()
+            })
+            .collect()
+    })
+    .await?;
+    let overrides: Vec<_> = instances
+        .into_iter()
+        .flat_map(|instance| instance.library_overrides)
+        .collect();
+    let report =
+        core::cleanup::collect_garbage(&game_dir, &overrides, &mods, &patched_jars, dry_run)
+            .await?;
+    if !dry_run {
+        let index_state = app_handle.state::<core::file_index::FileIndexState>();
+        index_state.index.lock().unwrap().prune();
+        index_state.save()?;
+    }
+    emit_log!(
+        window,
+        format!(
+            "Garbage collection{}: {} files, {} bytes",
+            if dry_run { " (dry run)" } else { "" },
+            report.files.len(),
+            report.reclaimable_bytes
+        )
+    );
+    Ok(report)
+}
+
+/// Remove asset indexes and objects no installed version uses; only report them if `dry_run`
+#[tauri::command]
+async fn prune_assets(
+    window: Window,
+    dry_run: bool,
+) -> Result<core::cleanup::AssetPruneReport, LauncherError> {
+    let game_dir = window
+        .app_handle()
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+
+    let report = core::cleanup::prune_assets(&game_dir, dry_run).await?;
+    emit_log!(
+        window,
+        format!(
+            "Asset pruning{}: {} indexes, {} objects, {} bytes",
+            if dry_run { " (dry run)" } else { "" },
+            report.removed_indexes.len(),
+            report.removed_objects,
+            report.reclaimable_bytes
+        )
+    );
+    Ok(report)
+}
+
+/// Move the mods of all instances into the mod store, sharing identical files
+#[tauri::command]
+async fn dedupe_mods(
+    window: Window,
+    instance_state: State<'_, core::instance::InstanceManager>,
+) -> Result<core::mod_store::DedupeReport, LauncherError> {
+    let store_dir = window
+        .app_handle()
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?
+        .join(core::mod_store::STORE_DIR);
+
+    let mut report = core::mod_store::DedupeReport::default();
+    for instance in instance_state.list() {
+        let _lock = match instance_state
+            .locks
+            .write(&instance.id, "deduplicating its mods")
+        {
+            Ok(lock) => lock,
+            Err(e) => {
+                report.errors.push(format!("{}: {}", instance.name, e));
+                continue;
+            }
+        };
+        let mods_dir = instance_state.instance_dir(&instance.id).join("mods");
+        let store_dir = store_dir.clone();
+        let stored = tokio::task::spawn_blocking(move || {
+            core::mod_store::store_files(&store_dir, &core::mod_store::mod_files(&mods_dir))
+        })
+        .await?;
+        report.merge(stored);
+    }
+    emit_log!(
+        window,
+        format!(
+            "Deduplicated {} mods: {} reflinked, {} hard-linked, {} copies, {} bytes shared",
+            report.files, report.reflinked, report.hard_linked, report.copied, report.shared_bytes
+        )
+    );
+    for error in &report.errors {
+        emit_log!(window, core::config::LogLevel::Warn, error.clone());
+    }
+    Ok(report)
+}
+
+/// Drop cached metadata responses (version manifest, loader lists, Adoptium)
+#[tauri::command]
+async fn clear_meta_cache() -> Result<(), LauncherError> {
+    Ok(core::http_cache::clear().await?)
+}
+
+/// Get total, per-instance and last-7-days playtime
+#[tauri::command]
+async fn get_playtime_stats(
+    stats_state: State<'_, core::stats::StatsState>,
+) -> Result<core::stats::PlaytimeStats, LauncherError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    Ok(stats_state.stats(now))
+}
+
+/// Show a launch session's game output in its own window instead of the
+/// main one; returns the window label
+#[tauri::command]
+async fn open_console_window(
+    app_handle: tauri::AppHandle,
+    session_id: String,
+    title: Option<String>,
+) -> Result<String, LauncherError> {
+    let title = title.unwrap_or_else(|| "Minecraft".to_string());
+    Ok(core::console_window::open(
+        &app_handle,
+        &session_id,
+        &title,
+    )?)
+}
+
+/// Bytes and files fetched by installs and launches, and how many files
+/// were already valid
+#[tauri::command]
+async fn get_download_stats(
+    download_stats_state: State<'_, core::download_stats::DownloadStatsState>,
+) -> Result<core::download_stats::DownloadStats, LauncherError> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    Ok(download_stats_state.stats(now))
+}
+
+/// CPU and memory samples of a launch session, oldest first
+#[tauri::command]
+async fn get_game_metrics(
+    metrics_state: State<'_, core::game_metrics::MetricsState>,
+    session_id: String,
+) -> Result<Vec<core::game_metrics::GameMetrics>, LauncherError> {
+    metrics_state.get(&session_id).ok_or_else(|| {
+        LauncherError::Invalid(format!("No metrics recorded for session {}", session_id))
+    })
+}
+
+/// Analysis of the GC log from the last launch of an instance, or of the
+/// shared game directory without `instance_id`
+#[tauri::command]
+async fn get_gc_report(
+    window: Window,
+    config_state: State<'_, core::config::ConfigState>,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: Option<String>,
+) -> Result<core::gc_log::GcReport, LauncherError> {
+    let app_dir = window
+        .app_handle()
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    let (config, paths) = match &instance_id {
+        Some(id) => {
+            let instance = instance_state.get(id)?;
+            let instance_dir = instance_state.instance_dir(id);
+            (
+                config_state.for_instance(&instance),
+                core::instance::LaunchPaths::for_instance(&app_dir, &instance_dir, &instance),
+            )
+        }
+        None => (
+            config_state.effective(None),
+            core::instance::LaunchPaths::shared(&app_dir),
+        ),
+    };
+    let path = core::gc_log::log_path(&paths.game_dir);
+    if !path.exists() {
+        return Err(LauncherError::Invalid(
+            "No GC log yet; enable GC logging and launch the game".to_string(),
+        ));
+    }
+    Ok(core::gc_log::analyze_file(&path, config.max_memory)?)
+}
+
+/// List dedicated servers
+#[tauri::command]
+async fn list_servers(
+    server_state: State<'_, core::server::ServerManager>,
+) -> Result<Vec<core::server::ServerInstance>, LauncherError> {
+    Ok(server_state.list())
+}
+
+/// Save the dedicated server jar of a version, or of the Minecraft version a
+/// mod loader version is based on, to `path`
+#[tauri::command]
+async fn download_server_jar(
+    window: Window,
+    config_state: State<'_, core::config::ConfigState>,
+    version_id: String,
+    path: String,
+) -> Result<(), LauncherError> {
+    let game_dir = window
+        .app_handle()
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    emit_log!(
+        window,
+        format!("Downloading the server jar of {} to {}", version_id, path)
+    );
+    let events = EventSink::new(window.clone(), EventScope::default());
+    let config = config_state.effective(None);
+    let java_major = core::server::download_server_jar(
+        &events,
+        &config,
+        &game_dir,
+        &version_id,
+        std::path::Path::new(&path),
+    )
+    .await?;
+    emit_log!(
+        window,
+        format!(
+            "Saved the server jar of {} (needs Java {})",
+            version_id, java_major
+        )
+    );
+    Ok(())
+}
+
+/// Install a vanilla, Fabric or Forge dedicated server
+#[tauri::command]
+async fn create_server(
+    window: Window,
+    config_state: State<'_, core::config::ConfigState>,
+    server_state: State<'_, core::server::ServerManager>,
+    options: core::server::NewServer,
+) -> Result<core::server::ServerInstance, LauncherError> {
+    use core::manifest::LoaderType;
+
+    if !options.accept_eula {
+        return Err(LauncherError::Invalid(
+            "The Minecraft EULA must be accepted to run a server".to_string(),
+        ));
+    }
+    let name = options.name.trim();
+    if name.is_empty() {
+        return Err(LauncherError::Invalid(
+            "Server name cannot be empty".to_string(),
+        ));
+    }
+    let loader_version = match options.loader {
+        LoaderType::Vanilla => None,
+        LoaderType::Fabric | LoaderType::Forge => Some(
+            options
+                .loader_version
+                .clone()
+                .ok_or("A loader version is required")?,
+        ),
+        other => {
+            return Err(LauncherError::Invalid(format!(
+                "{:?} servers are not supported",
+                other
+            )))
+        }
+    };
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let server_dir = server_state.server_dir(&id);
+    std::fs::create_dir_all(&server_dir)?;
+    emit_log!(
+        window,
+        format!(
+            "Installing {:?} server for Minecraft {}...",
+            options.loader, options.minecraft_version
+        )
+    );
+
+    let events = EventSink::new(window.clone(), EventScope::default());
+    let config = config_state.effective(None);
+    let install = async {
+        // The vanilla jar also tells us which Java the server needs
+        let java_major = core::server::install_vanilla_server(
+            &events,
+            &config,
+            &server_dir,
+            &options.minecraft_version,
+        )
+        .await?;
+        match (options.loader, &loader_version) {
+            (LoaderType::Fabric, Some(loader)) => {
+                core::server::install_fabric_server(
+                    &events,
+                    &config,
+                    &server_dir,
+                    &options.minecraft_version,
+                    loader,
+                )
+                .await?
+            }
+            (LoaderType::Forge, Some(forge)) => {
+                let preferred = config_state.config.lock().unwrap().java_path.clone();
+                let required =
+                    java_major.max(core::forge::required_java_major(&options.minecraft_version));
+                let java =
+                    core::java::find_suitable_java(window.app_handle(), required, &preferred)
+                        .ok_or_else(|| {
+                            format!("Forge needs Java {} or newer, but none was found", required)
+                        })?;
+                let output_window = window.clone();
+                core::forge::run_forge_server_installer(
+                    &events,
+                    &config,
+                    &server_dir,
+                    &options.minecraft_version,
+                    forge,
+                    std::path::Path::new(&java),
+                    move |line| {
+                        output_window.emit_event(core::events::ForgeInstallOutput {
+                            line: line.to_string(),
+                        });
+                    },
+                )
+                .await?;
+            }
+            _ => {}
+        }
+
+        core::server::accept_eula(&server_dir)?;
+        core::server::write_server_properties(
+            &server_dir.join("server.properties"),
+            &[
+                ("server-port", options.port.to_string()),
+                ("motd", name.to_string()),
+            ],
+        )?;
+        Ok::<u32, String>(java_major)
+    };
+
+    let java_major = match install.await {
+        Ok(java_major) => java_major,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&server_dir);
+            return Err(e.into());
+        }
+    };
+
+    let server = core::server::ServerInstance {
+        id,
+        name: name.to_string(),
+        minecraft_version: options.minecraft_version,
+        loader: options.loader,
+        loader_version,
+        memory_mb: options.memory_mb.max(512),
+        port: options.port,
+        java_major,
+        java_path: None,
+        created_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        running: false,
+    };
+    server_state.add(server.clone())?;
+    emit_log!(window, format!("Server {} installed", server.name));
+    Ok(server)
+}
+
+/// Update a server's name, memory, port or Java
+#[tauri::command]
+async fn update_server(
+    server_state: State<'_, core::server::ServerManager>,
+    server: core::server::ServerInstance,
+) -> Result<(), LauncherError> {
+    Ok(server_state.update(&server)?)
+}
+
+/// Delete a stopped server and its files
+#[tauri::command]
+async fn delete_server(
+    server_state: State<'_, core::server::ServerManager>,
+    server_id: String,
+) -> Result<(), LauncherError> {
+    Ok(server_state.delete(&server_id)?)
+}
+
+/// Start a server, streaming its console as `server-output` events
+#[tauri::command]
+async fn start_server(
+    window: Window,
+    config_state: State<'_, core::config::ConfigState>,
+    server_state: State<'_, core::server::ServerManager>,
+    server_id: String,
+) -> Result<(), LauncherError> {
+    let server = server_state.get(&server_id)?;
+    if server.running {
+        return Err("Server is already running".into());
+    }
+    let server_dir = server_state.server_dir(&server.id);
+    let java_path = match &server.java_path {
+        Some(path) => path.clone(),
+        None => {
+            let preferred = config_state.config.lock().unwrap().java_path.clone();
+            core::java::find_suitable_java(window.app_handle(), server.java_major, &preferred)
+                .ok_or_else(|| format!("This server needs Java {} or newer", server.java_major))?
+        }
+    };
+
+    let mut command = Command::new(&java_path);
+    command
+        .arg(format!("-Xmx{}M", server.memory_mb))
+        .args(core::server::launch_args(&server_dir, &server)?)
+        .arg("nogui")
+        .current_dir(&server_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let mut stdin_rx = server_state.mark_running(&server.id)?;
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            server_state.mark_stopped(&server.id);
+            return Err(format!("Failed to start server: {}", e).into());
+        }
+    };
+    emit_log!(window, format!("Server {} started", server.name));
+
+    let mut stdin = child
+        .stdin
+        .take()
+        .expect("child did not have a handle to stdin");
+    tokio::spawn(async move {
+        use tokio::io::AsyncWriteExt;
+        while let Some(line) = stdin_rx.recv().await {
+            if stdin
+                .write_all(format!("{}\n", line).as_bytes())
+                .await
+                .is_err()
+            {
+                break;
+            }
+            let _ = stdin.flush().await;
+        }
+    });
+
+    type OutputStream = Box<dyn tokio::io::AsyncRead + Send + Unpin>;
+    let streams = [
+        child.stdout.take().map(|s| Box::new(s) as OutputStream),
+        child.stderr.take().map(|s| Box::new(s) as OutputStream),
+    ];
+    for stream in streams.into_iter().flatten() {
+        let window_out = window.clone();
+        let server_id = server.id.clone();
+        tokio::spawn(async move {
+            let mut reader = BufReader::new(stream).lines();
+            while let Ok(Some(line)) = reader.next_line().await {
+                window_out.emit_event(core::events::ServerOutput {
+                    server_id: server_id.clone(),
+                    line,
+                });
+            }
+        });
+    }
+
+    let app_exit = window.app_handle().clone();
+    let window_exit = window.clone();
+    let server_id = server.id.clone();
+    tokio::spawn(async move {
+        let code = match child.wait().await {
+            Ok(status) => status.code().unwrap_or(-1),
+            Err(_) => -1,
+        };
+        app_exit
+            .state::<core::server::ServerManager>()
+            .mark_stopped(&server_id);
+        window_exit.emit_event(core::events::ServerExited { server_id, code });
+    });
+
+    Ok(())
+}
+
+/// Send a console command to a running server
+#[tauri::command]
+async fn send_server_command(
+    server_state: State<'_, core::server::ServerManager>,
+    server_id: String,
+    command: String,
+) -> Result<(), LauncherError> {
+    Ok(server_state.send_command(&server_id, &command)?)
+}
+
+/// Stop a running server gracefully
+#[tauri::command]
+async fn stop_server(
+    server_state: State<'_, core::server::ServerManager>,
+    server_id: String,
+) -> Result<(), LauncherError> {
+    Ok(server_state.send_command(&server_id, "stop")?)
+}
+
+/// Copy a world between a client instance's `saves` and a server's world folder.
+/// Both sides must be stopped; a replaced world is kept as a backup.
+#[tauri::command]
+async fn sync_world(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    server_state: State<'_, core::server::ServerManager>,
+    instance_id: String,
+    server_id: String,
+    world_name: String,
+    direction: core::worlds::SyncDirection,
+) -> Result<core::worlds::WorldSyncReport, LauncherError> {
+    core::worlds::validate_world_name(&world_name)?;
+    let instance = instance_state.get(&instance_id)?;
+    let server = server_state.get(&server_id)?;
+    let _lock = instance_state.locks.write(&instance.id, "syncing worlds")?;
+    if server.running {
+        return Err(LauncherError::Invalid(format!(
+            "Stop {} before syncing worlds",
+            server.name
+        )));
+    }
+
+    let client_world = instance_state
+        .instance_dir(&instance.id)
+        .join("saves")
+        .join(&world_name);
+    let server_world = core::worlds::server_world_dir(&server_state.server_dir(&server.id));
+    let (src, dst) = match direction {
+        core::worlds::SyncDirection::ToServer => (client_world, server_world),
+        core::worlds::SyncDirection::ToInstance => (server_world, client_world),
+    };
+
+    Ok(tokio::task::spawn_blocking(move || core::worlds::copy_world(&src, &dst)).await??)
+}
+
+/// Get the active and queued downloads
+#[tauri::command]
+async fn get_download_queue(
+    manager: State<'_, core::download_manager::DownloadManager>,
+) -> Result<core::download_manager::DownloadQueueInfo, LauncherError> {
+    Ok(manager.snapshot())
+}
+
+/// Get per-category disk usage of the launcher data directory
+#[tauri::command]
+async fn get_disk_usage(window: Window) -> Result<core::cleanup::DiskUsage, LauncherError> {
+    let app_handle = window.app_handle();
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+
+    Ok(core::cleanup::compute_disk_usage(&app_dir).await)
+}
+
+/// List all instances in display order
+#[tauri::command]
+async fn list_instances(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    filter: Option<core::instance::InstanceFilter>,
+) -> Result<Vec<core::instance::Instance>, LauncherError> {
+    let instances = instance_state.list();
+    Ok(match filter {
+        Some(filter) => instances
+            .into_iter()
+            .filter(|i| filter.matches(i))
+            .collect(),
+        None => instances,
+    })
+}
+
+/// Get an instance's icon as a built-in name or a data URL
+#[tauri::command]
+async fn get_instance_icon(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+) -> Result<Option<core::icons::InstanceIcon>, LauncherError> {
+    let instance = instance_state.get(&instance_id)?;
+    match &instance.icon {
+        Some(icon) => Ok(
+            core::icons::load_icon(icon, &instance_state.instance_dir(&instance.id)).map(Some)?,
+        ),
+        None => Ok(None),
+    }
+}
+
+/// Set an instance's icon from a local image file
+#[tauri::command]
+async fn set_instance_icon_from_file(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+    path: String,
+) -> Result<core::icons::InstanceIcon, LauncherError> {
+    let mut instance = instance_state.get(&instance_id)?;
+    let instance_dir = instance_state.instance_dir(&instance.id);
+    let dir = instance_dir.clone();
+    let icon = tokio::task::spawn_blocking(move || {
+        core::icons::store_custom_icon(std::path::Path::new(&path), &dir)
+    })
+    .await??;
+    let loaded = core::icons::load_icon(&icon, &instance_dir)?;
+    instance.icon = Some(icon);
+    instance_state.save(&instance)?;
+    Ok(loaded)
+}
+
+/// Set an instance's icon to a built-in icon, or clear it with `None`
+#[tauri::command]
+async fn set_instance_icon_builtin(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+    name: Option<String>,
+) -> Result<(), LauncherError> {
+    let mut instance = instance_state.get(&instance_id)?;
+    instance.icon = match name {
+        Some(name) => Some(core::icons::builtin_icon(&name)?),
+        None => None,
+    };
+    Ok(instance_state.save(&instance)?)
+}
+
+/// Get the names of the built-in instance icons
+#[tauri::command]
+async fn list_builtin_icons() -> Result<Vec<String>, LauncherError> {
+    Ok(core::icons::BUILTIN_ICONS
+        .iter()
+        .map(|name| name.to_string())
+        .collect())
+}
+
+/// Set an instance's description and notes; empty strings clear them
+#[tauri::command]
+async fn set_instance_notes(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+    description: Option<String>,
+    notes: Option<String>,
+) -> Result<core::instance::Instance, LauncherError> {
+    let mut instance = instance_state.get(&instance_id)?;
+    instance.description = description.filter(|d| !d.trim().is_empty());
+    instance.notes = notes.filter(|n| !n.trim().is_empty());
+    instance_state.save(&instance)?;
+    Ok(instance)
+}
+
+/// Replace an instance's network overrides
+#[tauri::command]
+async fn set_instance_network(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+    network: core::config::NetworkOverrides,
+) -> Result<core::instance::Instance, LauncherError> {
+    let mut instance = instance_state.get(&instance_id)?;
+    instance.network = network;
+    instance_state.save(&instance)?;
+    Ok(instance)
+}
+
+/// Record (or clear) the modpack an instance was created from
+#[tauri::command]
+async fn set_instance_modpack(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+    modpack: Option<core::instance::ModpackSource>,
+) -> Result<core::instance::Instance, LauncherError> {
+    let mut instance = instance_state.get(&instance_id)?;
+    instance.modpack = modpack;
+    instance_state.save(&instance)?;
+    Ok(instance)
+}
+
+/// Check whether a newer release of an instance's modpack exists
+#[tauri::command]
+async fn check_pack_update(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    config_state: State<'_, core::config::ConfigState>,
+    instance_id: String,
+) -> Result<core::modpack::PackUpdateInfo, LauncherError> {
+    let instance = instance_state.get(&instance_id)?;
+    let source = instance
+        .modpack
+        .ok_or("Instance was not created from a modpack")?;
+    let config = config_state.effective(Some(&instance.network));
+    Ok(core::modpack::check_update(&source, &config).await?)
+}
+
+/// Update a modpack instance in place, to `version_id` or the latest release
+#[tauri::command]
+async fn update_pack(
+    window: Window,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    config_state: State<'_, core::config::ConfigState>,
+    instance_id: String,
+    version_id: Option<String>,
+    allow_metered: Option<bool>,
+) -> Result<core::modpack::PackUpdateReport, LauncherError> {
+    let mut instance = instance_state.get(&instance_id)?;
+    let source = instance
+        .modpack
+        .clone()
+        .ok_or("Instance was not created from a modpack")?;
+    let _lock = instance_state
+        .locks
+        .write(&instance_id, "updating its modpack")?;
+    let config = config_state.effective(Some(&instance.network));
+
+    let target = match version_id {
+        Some(id) => id,
+        None => {
+            let info = core::modpack::check_update(&source, &config).await?;
+            match info.latest {
+                Some(latest) if info.update_available => latest.id,
+                _ => {
+                    return Err(LauncherError::Invalid(
+                        "The modpack is already up to date".to_string(),
+                    ))
+                }
+            }
+        }
+    };
+
+    let app_data_dir = window
+        .app_handle()
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    let mod_store = config
+        .settings
+        .mod_store
+        .then(|| app_data_dir.join(core::mod_store::STORE_DIR));
+    emit_log!(window, format!("Updating modpack of {}...", instance.name));
+    let events = EventSink::new(
+        window.clone(),
+        EventScope {
+            instance_id: Some(instance_id.clone()),
+            session_id: None,
+        },
+    );
+    let report = core::modpack::update_pack(
+        &events,
+        &instance_state.instance_dir(&instance_id),
+        &source,
+        &target,
+        &config,
+        allow_metered.unwrap_or(false),
+        mod_store.as_deref(),
+    )
+    .await?;
+
+    instance_state.change_version(&mut instance, &app_data_dir, &report.version_id)?;
+    instance.modpack = Some(core::modpack::updated_source(
+        &source,
+        &target,
+        &report.to_version,
+    ));
+    instance_state.save(&instance)?;
+    emit_log!(
+        window,
+        format!(
+            "Updated {} from {} to {}; backup at {}",
+            instance.name,
+            report.from_version,
+            report.to_version,
+            report.backup.display()
+        )
+    );
+    Ok(report)
+}
+
+/// Set the display order of instances
+#[tauri::command]
+async fn reorder_instances(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_ids: Vec<String>,
+) -> Result<(), LauncherError> {
+    Ok(instance_state.reorder(&instance_ids)?)
+}
+
+/// Move an instance into a group, or out of any group with `None`
+#[tauri::command]
+async fn set_instance_group(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+    group: Option<String>,
+) -> Result<core::instance::Instance, LauncherError> {
+    Ok(instance_state.set_group(&instance_id, group.as_deref())?)
+}
+
+/// Replace an instance's tags
+#[tauri::command]
+async fn set_instance_tags(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+    tags: Vec<String>,
+) -> Result<core::instance::Instance, LauncherError> {
+    Ok(instance_state.set_tags(&instance_id, &tags)?)
+}
+
+/// Copy a jar mod into an instance and append it to its jar mods
+#[tauri::command]
+async fn add_jar_mod(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+    path: String,
+) -> Result<core::instance::Instance, LauncherError> {
+    let mut instance = instance_state.get(&instance_id)?;
+    let _lock = instance_state
+        .locks
+        .write(&instance.id, "adding a jar mod")?;
+    let instance_dir = instance_state.instance_dir(&instance.id);
+    core::jar_mods::add(&instance_dir, &mut instance, std::path::Path::new(&path))
+        .map_err(LauncherError::Invalid)?;
+    instance_state.save(&instance)?;
+    Ok(instance)
+}
+
+/// Delete a jar mod from an instance
+#[tauri::command]
+async fn remove_jar_mod(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+    file: String,
+) -> Result<core::instance::Instance, LauncherError> {
+    let mut instance = instance_state.get(&instance_id)?;
+    let _lock = instance_state
+        .locks
+        .write(&instance.id, "removing a jar mod")?;
+    let instance_dir = instance_state.instance_dir(&instance.id);
+    core::jar_mods::remove(&instance_dir, &mut instance, &file).map_err(LauncherError::Invalid)?;
+    instance_state.save(&instance)?;
+    Ok(instance)
+}
+
+/// Reorder an instance's jar mods or enable and disable them
+#[tauri::command]
+async fn set_jar_mods(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+    jar_mods: Vec<core::jar_mods::JarMod>,
+) -> Result<core::instance::Instance, LauncherError> {
+    let _lock = instance_state
+        .locks
+        .write(&instance_id, "changing jar mods")?;
+    let mut instance = instance_state.get(&instance_id)?;
+    core::jar_mods::set(&mut instance, jar_mods).map_err(LauncherError::Invalid)?;
+    instance_state.save(&instance)?;
+    Ok(instance)
+}
+
+/// Get group names in display order, including groups used by instances but not yet ordered
+#[tauri::command]
+async fn list_instance_groups(
+    instance_state: State<'_, core::instance::InstanceManager>,
+) -> Result<Vec<String>, LauncherError> {
+    let mut groups = instance_state.lock_index().groups.clone();
+    for instance in instance_state.list() {
+        if let Some(group) = instance.group {
+            if !groups.contains(&group) {
+                groups.push(group);
+            }
+        }
+    }
+    Ok(groups)
+}
+
+/// Set the display order of groups
+#[tauri::command]
+async fn set_instance_group_order(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    groups: Vec<String>,
+) -> Result<(), LauncherError> {
+    Ok(instance_state.set_group_order(&groups)?)
+}
+
+/// Get a single instance
+#[tauri::command]
+async fn get_instance(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+) -> Result<core::instance::Instance, LauncherError> {
+    Ok(instance_state.get(&instance_id)?)
+}
+
+/// Create a new instance for a version
+#[tauri::command]
+async fn create_instance(
+    config_state: State<'_, core::config::ConfigState>,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    name: String,
+    version_id: String,
+) -> Result<core::instance::Instance, LauncherError> {
+    let mut instance = instance_state.create(&name, &version_id)?;
+    let instance_dir = instance_state.instance_dir(&instance.id);
+    let template = config_state
+        .config
+        .lock()
+        .unwrap()
+        .instance_template
+        .clone();
+    let applied = core::instance_template::apply(&template, &mut instance, &instance_dir)
+        .and_then(|()| instance_state.save(&instance));
+    if let Err(e) = applied {
+        // Don't leave a half-made instance behind
+        let _ = std::fs::remove_dir_all(&instance_dir);
+        let _ = instance_state.remove_from_index(&instance.id);
+        return Err(e.into());
+    }
+    Ok(instance)
+}
+
+/// Apply the new instance template to an existing instance again
+#[tauri::command]
+async fn apply_instance_template(
+    config_state: State<'_, core::config::ConfigState>,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+) -> Result<core::instance::Instance, LauncherError> {
+    let _lock = instance_state
+        .locks
+        .write(&instance_id, "applying the template")?;
+    let mut instance = instance_state.get(&instance_id)?;
+    let template = config_state
+        .config
+        .lock()
+        .unwrap()
+        .instance_template
+        .clone();
+    core::instance_template::apply(
+        &template,
+        &mut instance,
+        &instance_state.instance_dir(&instance_id),
+    )?;
+    instance_state.save(&instance)?;
+    Ok(instance)
+}
+
+/// Save changes to an instance (name, version, icon, isolation)
+#[tauri::command]
+async fn update_instance(
+    app_handle: tauri::AppHandle,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    mut instance: core::instance::Instance,
+) -> Result<(), LauncherError> {
+    // Only existing instances can be updated
+    let current = instance_state.get(&instance.id)?;
+    // The previous version is only changed by switching versions
+    let version_id = std::mem::replace(&mut instance.version_id, current.version_id);
+    instance.previous_version_id = current.previous_version_id;
+    // Jar mods have their own commands, which keep the files in step
+    instance.jar_mods = current.jar_mods;
+    core::library_override::validate(&instance.library_overrides)
+        .map_err(LauncherError::Invalid)?;
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    if let Some(uuid) = &instance.account_id {
+        let storage = core::account_storage::AccountStorage::new(app_data_dir.clone());
+        if storage.get_account(uuid).is_none() {
+            return Err(LauncherError::Invalid(format!(
+                "Account {} is not stored",
+                uuid
+            )));
+        }
+    }
+    // Held until the new version is recorded
+    let _lock = if version_id != instance.version_id {
+        let lock = instance_state
+            .locks
+            .write(&instance.id, "changing its version")?;
+        core::instance_backup::snapshot(
+            &instance_state.instance_dir(&instance.id),
+            core::instance_backup::BackupReason::LoaderChange,
+        )?;
+        Some(lock)
+    } else {
+        None
+    };
+    instance_state.change_version(&mut instance, &app_data_dir, &version_id)?;
+    Ok(instance_state.save(&instance)?)
+}
+
+/// Mods, config files, version and loader that differ between two instances
+#[tauri::command]
+async fn diff_instances(
+    app_handle: tauri::AppHandle,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_a: String,
+    instance_b: String,
+) -> Result<core::instance_diff::InstanceDiff, LauncherError> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    let (a, b) = (
+        instance_state.get(&instance_a)?,
+        instance_state.get(&instance_b)?,
+    );
+    let (a_dir, b_dir) = (
+        instance_state.instance_dir(&a.id),
+        instance_state.instance_dir(&b.id),
+    );
+    Ok(tokio::task::spawn_blocking(move || {
+        core::instance_diff::diff(&app_data_dir, (&a, &a_dir), (&b, &b_dir))
+    })
+    .await?)
+}
+
+/// Recommended memory and JVM preset for an instance, from the computer's
+/// memory, the instance's Java and how many mods it has
+#[tauri::command]
+async fn suggest_jvm_settings(
+    config_state: State<'_, core::config::ConfigState>,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+) -> Result<core::jvm_tuning::JvmSuggestion, LauncherError> {
+    let instance = instance_state.get(&instance_id)?;
+    let config = config_state.for_instance(&instance);
+    let mods_dir = instance_state.instance_dir(&instance.id).join("mods");
+    Ok(tokio::task::spawn_blocking(move || {
+        let java =
+            core::java::check_java_installation(&std::path::PathBuf::from(&config.java_path));
+        core::jvm_tuning::suggest(core::jvm_tuning::TuningInput {
+            mod_count: core::jvm_tuning::count_mods(&mods_dir),
+            total_memory_mb: core::setup::detect_system().total_memory_mb,
+            java_major: java
+                .as_ref()
+                .map_or(0, |java| core::java::parse_java_version(&java.version)),
+            java_64bit: java.as_ref().is_none_or(|java| java.is_64bit),
+        })
+    })
+    .await?)
+}
+
+/// Automatic backups of an instance, newest first
+#[tauri::command]
+async fn list_instance_backups(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+) -> Result<Vec<core::instance_backup::InstanceBackup>, LauncherError> {
+    let instance = instance_state.get(&instance_id)?;
+    Ok(core::instance_backup::list(
+        &instance_state.instance_dir(&instance.id),
+    ))
+}
+
+/// Put an instance's folders back to how they were in a backup
+#[tauri::command]
+async fn restore_instance_backup(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+    file_name: String,
+) -> Result<core::instance_backup::InstanceBackup, LauncherError> {
+    let instance = instance_state.get(&instance_id)?;
+    let _lock = instance_state
+        .locks
+        .write(&instance.id, "restoring a backup")?;
+    Ok(core::instance_backup::restore(
+        &instance_state.instance_dir(&instance.id),
+        &file_name,
+    )?)
+}
+
+/// Switch an instance back to the mod loader version it used before its
+/// last version change
+#[tauri::command]
+async fn rollback_loader(
+    app_handle: tauri::AppHandle,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+) -> Result<core::instance::Instance, LauncherError> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    instance_state.roll_back_version(&instance_id, &app_data_dir)
+}
+
+/// Move an instance and its game directory to the trash
+#[tauri::command]
+async fn delete_instance(
+    window: Window,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    watcher_state: State<'_, core::instance_watcher::InstanceWatcher>,
+    instance_id: String,
+) -> Result<core::trash::TrashItem, LauncherError> {
+    let app_dir = window
+        .app_handle()
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    let trash_dir = core::trash::trash_dir(&app_dir);
+    // Windows cannot move a directory that is being watched
+    watcher_state.unwatch(&instance_id);
+    let result = core::trash::trash_instance(&instance_state, &trash_dir, &instance_id);
+    watch_active_instance(window.app_handle());
+    result
+}
+
+/// Deleted instances, worlds and mods that can still be restored
+#[tauri::command]
+async fn list_trash(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<core::trash::TrashItem>, LauncherError> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(core::trash::list(&core::trash::trash_dir(&app_dir)))
+}
+
+/// Put a deleted item back where it was
+#[tauri::command]
+async fn restore_deleted_item(
+    app_handle: tauri::AppHandle,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    item_id: String,
+) -> Result<core::trash::TrashItem, LauncherError> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    let item = core::trash::restore(&core::trash::trash_dir(&app_dir), &instance_state, &item_id)?;
+    watch_active_instance(&app_handle);
+    Ok(item)
+}
+
+/// Permanently delete everything in the trash; returns the bytes freed
+#[tauri::command]
+async fn empty_trash(app_handle: tauri::AppHandle) -> Result<u64, LauncherError> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    Ok(core::trash::empty(&core::trash::trash_dir(&app_dir))?)
+}
+
+/// Pack an instance into a zip under `archive/` to free its disk space
+#[tauri::command]
+async fn archive_instance(
+    window: Window,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    watcher_state: State<'_, core::instance_watcher::InstanceWatcher>,
+    instance_id: String,
+) -> Result<core::instance::Instance, LauncherError> {
+    watcher_state.unwatch(&instance_id);
+    let result = core::instance_archive::archive(&instance_state, &instance_id);
+    watch_active_instance(window.app_handle());
+    result
+}
+
+/// Restore the files of an archived instance
+#[tauri::command]
+async fn unarchive_instance(
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+) -> Result<core::instance::Instance, LauncherError> {
+    core::instance_archive::unarchive(&instance_state, &instance_id)
+}
+
+/// Set the instance selected in the UI
+#[tauri::command]
+async fn set_active_instance(
+    window: Window,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+) -> Result<(), LauncherError> {
+    instance_state.set_active(&instance_id)?;
+    watch_active_instance(window.app_handle());
+    Ok(())
+}
+
+/// Detect what a file dropped onto an instance is and install it there
+#[tauri::command]
+async fn import_dropped_file(
+    window: Window,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    config_state: State<'_, core::config::ConfigState>,
+    path: String,
+    instance_id: String,
+) -> Result<core::dropped_file::DropImportReport, LauncherError> {
+    let instance = instance_state.get(&instance_id)?;
+    let path = std::path::PathBuf::from(&path);
+    let detected = core::dropped_file::detect(&path).map_err(LauncherError::Invalid)?;
+    let _lock = instance_state
+        .locks
+        .write(&instance.id, "importing a file")?;
+
+    let events = EventSink::new(
+        window.clone(),
+        EventScope {
+            instance_id: Some(instance.id.clone()),
+            session_id: None,
+        },
+    );
+    let report = core::dropped_file::install(
+        &events,
+        &path,
+        detected,
+        &instance_state.instance_dir(&instance.id),
+    )
+    .await?;
+    let mod_store = config_state.config.lock().unwrap().mod_store;
+    if let (true, core::dropped_file::DroppedFile::Mod { .. }, Some(installed_to)) =
+        (mod_store, &report.detected, &report.installed_to)
+    {
+        let app_data_dir = window
+            .app_handle()
+            .path()
+            .app_data_dir()
+            .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+        let store_dir = app_data_dir.join(core::mod_store::STORE_DIR);
+        let installed_to = installed_to.clone();
+        let stored = tokio::task::spawn_blocking(move || {
+            core::mod_store::store_files(&store_dir, std::slice::from_ref(&installed_to))
+        })
+        .await?;
+        for error in stored.errors {
+            emit_log!(window, core::config::LogLevel::Warn, error);
+        }
+    }
+    if let Some(installed_to) = &report.installed_to {
+        emit_log!(
+            window,
+            format!("Imported {} into {}", installed_to.display(), instance.name)
+        );
+    }
+    Ok(report)
+}
+
+/// Handle `dropout://` links. A link that started the launcher waits for the
+/// UI to call `take_pending_deep_link`; later ones are sent as events.
+fn setup_deep_links(app: &tauri::AppHandle) {
+    use tauri_plugin_deep_link::DeepLinkExt;
+
+    // Installs without a registered scheme, such as a bare AppImage
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = app.deep_link().register_all() {
+        println!("[DeepLink] Failed to register the dropout:// scheme: {}", e);
+    }
+
+    let startup = app
+        .deep_link()
+        .get_current()
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    if let Some(url) = startup.last() {
+        match core::deeplink::parse(url.as_str()) {
+            Ok(link) => app
+                .state::<core::deeplink::DeepLinkState>()
+                .set_pending(link),
+            Err(e) => println!("[DeepLink] {}", e),
+        }
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            match core::deeplink::parse(url.as_str()) {
+                Ok(link) => handle.emit_event(core::deeplink::DeepLinkReceived { link }),
+                Err(e) => println!("[DeepLink] {}", e),
+            }
+        }
+    });
+}
+
+/// Bring the launcher window to the front.
+fn show_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Called in the running launcher when it is started again. Links in `argv`
+/// are passed to the deep link handler by the plugin itself.
+fn forward_to_running_instance(app: &tauri::AppHandle, argv: Vec<String>, cwd: String) {
+    show_main_window(app);
+    let args: Vec<String> = argv
+        .into_iter()
+        .skip(1)
+        .filter(|arg| {
+            let scheme = format!("{}:", core::deeplink::SCHEME);
+            !arg.to_ascii_lowercase().starts_with(&scheme)
+        })
+        .collect();
+    if !args.is_empty() {
+        app.emit_event(core::events::ArgumentsForwarded { args, cwd });
+    }
+}
+
+/// The `dropout://` link the launcher was started with, if the UI has not
+/// taken it yet
+#[tauri::command]
+async fn take_pending_deep_link(
+    deep_link_state: State<'_, core::deeplink::DeepLinkState>,
+) -> Result<Option<core::deeplink::DeepLink>, LauncherError> {
+    Ok(deep_link_state.take_pending())
+}
+
+/// Point the content folder watcher at the active instance.
+fn watch_active_instance(app: &tauri::AppHandle) {
+    let instances = app.state::<core::instance::InstanceManager>();
+    let active = instances.lock_index().active_instance_id.clone();
+    if let Some(id) = active {
+        let watcher = app.state::<core::instance_watcher::InstanceWatcher>();
+        if let Err(e) = watcher.watch(&id, &instances.instance_dir(&id)) {
+            println!("[Watcher] {}", e);
+        }
+    }
+}
+
+/// Newest stable Fabric loader for `minecraft_version`.
+async fn newest_fabric_loader(minecraft_version: &str) -> Result<String, LauncherError> {
+    let loaders = core::fabric::fetch_loaders_for_game_version(minecraft_version).await?;
+    loaders
+        .iter()
+        .find(|entry| entry.loader.stable)
+        .or(loaders.first())
+        .map(|entry| entry.loader.version.clone())
+        .ok_or_else(|| {
+            LauncherError::Invalid(format!(
+                "Fabric does not support Minecraft {}",
+                minecraft_version
+            ))
+        })
+}
+
+/// Pre-download updates in the background while the launcher is idle, see
+/// `core::prefetch`.
+fn start_prefetch_job(app: &tauri::AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(core::prefetch::CHECK_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let settings = app
+                .state::<core::config::ConfigState>()
+                .config
+                .lock()
+                .unwrap()
+                .prefetch
+                .clone();
+            if !settings.enabled || !launcher_idle(&app) {
+                continue;
+            }
+            let Ok(data_dir) = app.path().app_data_dir() else {
+                continue;
+            };
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            let mut state = core::prefetch::PrefetchState::load(&data_dir);
+            if !state.is_due(settings.interval_hours, now) {
+                continue;
+            }
+            // Waits for a connection that is not metered
+            let config = app
+                .state::<core::config::ConfigState>()
+                .config
+                .lock()
+                .unwrap()
+                .clone();
+            if core::metered::avoid_background_downloads(&config) {
+                continue;
+            }
+
+            match prefetch_updates(&app, &data_dir, &settings).await {
+                // Picked up again at the next idle check
+                Ok(false) => continue,
+                Ok(true) => println!("[Prefetch] Updates are downloaded"),
+                // Retried at the next scheduled run rather than every check
+                Err(e) => println!("[Prefetch] {}", e),
+            }
+            state.last_run = now;
+            if let Err(e) = state.save(&data_dir) {
+                println!("[Prefetch] Failed to save state: {}", e);
+            }
+        }
+    });
+}
+
+/// No game is launching or running and nothing is downloading.
+fn launcher_idle(app: &tauri::AppHandle) -> bool {
+    app.state::<core::launch::LaunchLocks>().is_idle()
+        && app
+            .state::<core::download_manager::DownloadManager>()
+            .is_idle()
+}
+
+/// Download new Minecraft versions, Fabric loaders and Java patches.
+/// Returns `false` if it stopped because the launcher became busy.
+async fn prefetch_updates(
+    app: &tauri::AppHandle,
+    data_dir: &std::path::PathBuf,
+    settings: &core::config::PrefetchConfig,
+) -> Result<bool, LauncherError> {
+    let Some(webview) = app.get_webview_window("main") else {
+        return Ok(true);
+    };
+    let events = EventSink::new(
+        webview.as_ref().window(),
+        EventScope {
+            instance_id: None,
+            session_id: Some(format!("prefetch-{}", uuid::Uuid::new_v4())),
+        },
+    );
+    let mut config = app.state::<core::config::ConfigState>().effective(None);
+    config.bandwidth_limit = settings.bandwidth_limit();
+
+    let manifest = core::manifest::fetch_version_manifest()
+        .await
+        .map_err(|e| LauncherError::Network(e.to_string()))?;
+    let mut versions = vec![manifest.latest.release];
+    if config.visible_version_types.iter().any(|t| t == "snapshot") {
+        versions.push(manifest.latest.snapshot);
+    }
+    versions.retain(|id| {
+        let json = data_dir
+            .join("versions")
+            .join(id)
+            .join(format!("{}.json", id));
+        !json.exists()
+    });
+
+    let mut fabric_games: Vec<String> = app
+        .state::<core::instance::InstanceManager>()
+        .list()
+        .iter()
+        .filter_map(|instance| core::prefetch::parse_fabric_version(&instance.version_id))
+        .map(|(minecraft_version, _)| minecraft_version)
+        .collect();
+    fabric_games.sort();
+    fabric_games.dedup();
+    for minecraft_version in fabric_games {
+        let loader = newest_fabric_loader(&minecraft_version).await?;
+        if !core::fabric::is_fabric_installed(data_dir, &minecraft_version, &loader) {
+            let installed =
+                core::fabric::install_fabric(data_dir, &minecraft_version, &loader).await?;
+            versions.push(installed.id);
+        }
+    }
+
+    let launch_locks = app.state::<core::launch::LaunchLocks>();
+    for version_id in versions {
+        if !launcher_idle(app) {
+            return Ok(false);
+        }
+        let Some((_version_lock, mut launch_wanted)) =
+            launch_locks.lock_version_background(data_dir, &version_id)
+        else {
+            return Ok(false);
+        };
+        emit_log!(events, format!("Pre-downloading {}", version_id));
+        let download = download_version_files(events.clone(), data_dir, &version_id, &config, true);
+        tokio::select! {
+            result = download => result?,
+            // A launch or install wants the version and downloads the rest itself
+            _ = launch_wanted.wait_for(|wanted| *wanted) => return Ok(false),
+        }
+    }
+
+    // Archives of newer patches, used when the runtime is reinstalled
+    let java_dir = core::java::get_java_install_dir(app);
+    for runtime in core::prefetch::managed_runtimes(&java_dir) {
+        if !launcher_idle(app) {
+            return Ok(false);
+        }
+        let info = runtime
+            .vendor
+            .fetch_release(runtime.major_version, runtime.image_type)
+            .await?;
+        let archive = java_dir.join(&info.file_name);
+        if runtime.is_release(&info.release_name) || archive.exists() {
+            continue;
+        }
+        emit_log!(events, format!("Pre-downloading Java {}", info.version));
+        let urls: Vec<String> = std::iter::once(info.download_url)
+            .chain(info.mirrors)
+            .collect();
+        core::downloader::download_with_resume(
+            app,
+            &urls,
+            &archive,
+            info.checksum.as_deref(),
+            info.file_size,
+            settings.bandwidth_limit(),
+        )
+        .await?;
+    }
+    Ok(true)
+}
+
+/// Import versions, libraries, assets and profiles from an existing .minecraft directory
+#[tauri::command]
+async fn import_vanilla_directory(
+    window: Window,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    path: String,
+) -> Result<core::import::VanillaImportReport, LauncherError> {
+    let app_handle = window.app_handle();
+    let game_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+
+    let source = std::path::PathBuf::from(&path);
+    emit_log!(window, format!("Importing from {}...", source.display()));
+
+    let import_dir = game_dir.clone();
+    let mut report = tokio::task::spawn_blocking(move || {
+        core::import::import_vanilla_files(&source, &import_dir)
+    })
+    .await??;
+
+    let level = if report.corrupt_files.is_empty() {
+        core::config::LogLevel::Info
+    } else {
+        core::config::LogLevel::Warn
+    };
+    emit_log!(
+        window,
+        level,
+        format!(
+            "Imported {} versions, {} libraries, {} assets ({} corrupt files skipped)",
+            report.versions.len(),
+            report.libraries_imported,
+            report.assets_imported,
+            report.corrupt_files.len()
+        )
+    );
+
+    // The built-in profiles follow the latest release/snapshot
+    let latest = if report
+        .profiles
+        .iter()
+        .any(|p| p.version_id.starts_with("latest-"))
+    {
+        core::manifest::fetch_version_manifest()
+            .await
+            .ok()
+            .map(|m| m.latest)
+    } else {
+        None
+    };
+
+    for profile in report.profiles.clone() {
+        let version_id = match profile.version_id.as_str() {
+            "latest-release" => latest.as_ref().map(|l| l.release.clone()),
+            "latest-snapshot" => latest.as_ref().map(|l| l.snapshot.clone()),
+            _ => Some(profile.version_id.clone()),
+        };
+        let Some(version_id) = version_id else {
+            emit_log!(
+                window,
+                core::config::LogLevel::Warn,
+                format!(
+                    "Skipping profile {}: could not resolve version",
+                    profile.name
+                )
+            );
+            continue;
+        };
+
+        let instance = instance_state.create(&profile.name, &version_id)?;
+        let instance_dir = instance_state.instance_dir(&instance.id);
+        let profile_dir = profile.game_dir.clone();
+        tokio::task::spawn_blocking(move || {
+            core::import::copy_user_content(&profile_dir, &instance_dir)
+        })
+        .await??;
+
+        emit_log!(
+            window,
+            format!("Created instance {} ({})", instance.name, version_id)
+        );
+        report.instances.push(instance);
+    }
+
+    Ok(report)
+}
+
+/// Import a MultiMC / Prism Launcher / ATLauncher instance from a folder or exported zip
+#[tauri::command]
+async fn import_launcher_instance(
+    window: Window,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    path: String,
+) -> Result<core::import::LauncherImportResult, LauncherError> {
+    let app_handle = window.app_handle();
+    let game_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    import_foreign_instance(
+        window.clone(),
+        &instance_state,
+        &game_dir,
+        std::path::PathBuf::from(&path),
+    )
+    .await
+}
+
+/// Import the instance at `source` (a folder or exported zip), emitting
+/// progress on `events`. Shared by the command and the CLI.
+async fn import_foreign_instance<T: EventTarget + Clone + Send + 'static>(
+    events: T,
+    instance_state: &core::instance::InstanceManager,
+    game_dir: &std::path::Path,
+    source: std::path::PathBuf,
+) -> Result<core::import::LauncherImportResult, LauncherError> {
+    let is_zip = source
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+
+    // Exported zips are extracted to a scratch directory first
+    let scratch_dir = game_dir
+        .join("cache")
+        .join(format!("import-{}", uuid::Uuid::new_v4()));
+    let instance_root = if is_zip {
+        emit_log!(events, format!("Extracting {}...", source.display()));
+        // Exports never contain links; skip any rather than trust them
+        core::extract::extract(
+            events.clone(),
+            source.clone(),
+            scratch_dir.clone(),
+            utils::zip::SymlinkPolicy::Skip,
+            None,
+        )
+        .await?;
+        scratch_dir.clone()
+    } else {
+        source
+    };
+
+    let result = async {
+        let foreign = core::import::read_foreign_instance(&instance_root)?;
+        emit_log!(
+            events,
+            format!(
+                "Importing instance {} ({})",
+                foreign.name, foreign.version_id
+            )
+        );
+
+        let mut instance = instance_state.create(&foreign.name, &foreign.version_id)?;
+        let instance_dir = instance_state.instance_dir(&instance.id);
+        let filled = async {
+            if let Some(icon_path) = &foreign.icon_path {
+                match core::icons::store_custom_icon(icon_path, &instance_dir) {
+                    Ok(icon) => {
+                        instance.icon = Some(icon);
+                        instance_state.save(&instance)?;
+                    }
+                    Err(e) => println!("[Import] Skipping pack icon: {}", e),
+                }
+            }
+            let (content_dir, instance_dir) = (foreign.game_dir.clone(), instance_dir.clone());
+            tokio::task::spawn_blocking(move || {
+                core::import::copy_user_content(&content_dir, &instance_dir)
+            })
+            .await
+            .map_err(|e| e.to_string())?
+        }
+        .await;
+        if let Err(e) = filled {
+            // Don't leave a half-imported instance behind
+            let _ = std::fs::remove_dir_all(&instance_dir);
+            let _ = instance_state.remove_from_index(&instance.id);
+            return Err(e);
+        }
+
+        let version_installed = game_dir
+            .join("versions")
+            .join(&foreign.version_id)
+            .join(format!("{}.json", foreign.version_id))
+            .exists();
+
+        Ok::<_, String>(core::import::LauncherImportResult {
+            instance,
+            source: foreign,
+            version_installed,
+        })
+    }
+    .await;
+
+    if is_zip {
+        let _ = tokio::fs::remove_dir_all(&scratch_dir).await;
+    }
+    Ok(result?)
+}
+
+/// Check if Fabric is installed for a specific version
+#[tauri::command]
+async fn is_fabric_installed(
+    window: Window,
+    game_version: String,
+    loader_version: String,
+) -> Result<bool, LauncherError> {
+    let app_handle = window.app_handle();
+    let game_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+
+    Ok(core::fabric::is_fabric_installed(
+        &game_dir,
+        &game_version,
+        &loader_version,
+    ))
+}
+
+/// Get Minecraft versions supported by Forge
+#[tauri::command]
+async fn get_forge_game_versions() -> Result<Vec<String>, LauncherError> {
+    core::forge::fetch_supported_game_versions()
+        .await
+        .map_err(|e| LauncherError::Network(e.to_string()))
+}
+
+/// Get available Forge versions for a specific Minecraft version
+#[tauri::command]
+async fn get_forge_versions_for_game(
+    game_version: String,
+) -> Result<Vec<core::forge::ForgeVersion>, LauncherError> {
+    core::forge::fetch_forge_versions(&game_version)
+        .await
+        .map_err(|e| LauncherError::Network(e.to_string()))
+}
+
+/// Install Forge for a specific Minecraft version
+#[tauri::command]
+async fn install_forge(
+    window: Window,
+    config_state: State<'_, core::config::ConfigState>,
+    game_version: String,
+    forge_version: String,
+) -> Result<core::forge::InstalledForgeVersion, LauncherError> {
+    emit_log!(
+        window,
+        format!(
+            "Installing Forge {} for Minecraft {}...",
+            forge_version, game_version
+        )
+    );
+
+    let app_handle = window.app_handle();
+    let game_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+
+    // Get Java path from config or detect
+    let config = config_state.config.lock().unwrap().clone();
+    let java_path_str = if !config.java_path.is_empty() && config.java_path != "java" {
+        config.java_path.clone()
     } else {
         // Try to find a suitable Java installation
         let javas = core::java::detect_all_java_installations(&app_handle);
         if let Some(java) = javas.first() {
             java.path.clone()
         } else {
-            return Err("No Java installation found. Please configure Java in settings.".to_string());
+            return Err(LauncherError::JavaMissing(
+                "No Java installation found. Please configure Java in settings.".to_string(),
+            ));
         }
     };
     let java_path = std::path::PathBuf::from(&java_path_str);
 
-    emit_log!(window, "Running Forge installer...".to_string());
-
     // Run the Forge installer to properly patch the client
-    core::forge::run_forge_installer(&game_dir, &game_version, &forge_version, &java_path)
+    // (legacy builds have no headless installer and need no patching)
+    if core::forge::has_headless_installer(&game_version, &forge_version) {
+        emit_log!(window, "Running Forge installer...".to_string());
+
+        let events = EventSink::new(window.clone(), EventScope::default());
+        let config = config_state.effective(None);
+        core::forge::run_forge_installer(
+            &events,
+            &config,
+            &game_dir,
+            &game_version,
+            &forge_version,
+            &java_path,
+        )
         .await
         .map_err(|e| format!("Forge installer failed: {}", e))?;
 
-    emit_log!(window, "Forge installer completed, creating version profile...".to_string());
+        emit_log!(
+            window,
+            "Forge installer completed, creating version profile...".to_string()
+        );
+    }
 
     // Now create the version JSON
-    let result = core::forge::install_forge(&game_dir, &game_version, &forge_version)
-        .await
-        .map_err(|e| e.to_string())?;
+    let result = core::forge::install_forge(&game_dir, &game_version, &forge_version).await?;
+
+    emit_log!(
+        window,
+        format!("Forge installed successfully: {}", result.id)
+    );
+
+    Ok(result)
+}
+
+/// Install Forge with a Java suited to the Minecraft version, streaming installer output
+/// as `forge-install-output` events
+#[tauri::command]
+async fn install_forge_full(
+    window: Window,
+    config_state: State<'_, core::config::ConfigState>,
+    game_version: String,
+    forge_version: String,
+    java_path: Option<String>,
+) -> Result<core::forge::InstalledForgeVersion, LauncherError> {
+    emit_log!(
+        window,
+        format!(
+            "Installing Forge {} for Minecraft {}...",
+            forge_version, game_version
+        )
+    );
+
+    let app_handle = window.app_handle();
+    let game_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+
+    // Legacy builds have no headless installer and need no patching
+    if core::forge::has_headless_installer(&game_version, &forge_version) {
+        let required = core::forge::required_java_major(&game_version);
+        let java_path = match java_path {
+            Some(path) => path,
+            None => {
+                let preferred = config_state.config.lock().unwrap().java_path.clone();
+                core::java::find_suitable_java(app_handle, required, &preferred).ok_or_else(|| {
+                    format!(
+                        "Forge for Minecraft {} needs Java {} or newer, but none was found. Install one from the Java settings.",
+                        game_version, required
+                    )
+                })?
+            }
+        };
+        emit_log!(
+            window,
+            format!("Running Forge installer with {}", java_path)
+        );
+
+        let output_window = window.clone();
+        let events = EventSink::new(window.clone(), EventScope::default());
+        let config = config_state.effective(None);
+        core::forge::run_forge_installer_with_output(
+            &events,
+            &config,
+            &game_dir,
+            &game_version,
+            &forge_version,
+            std::path::Path::new(&java_path),
+            move |line| {
+                output_window.emit_event(core::events::ForgeInstallOutput {
+                    line: line.to_string(),
+                });
+            },
+        )
+        .await?;
+
+        emit_log!(
+            window,
+            "Forge installer completed, creating version profile...".to_string()
+        );
+    } else {
+        emit_log!(
+            window,
+            "Legacy Forge build, installing from its install profile...".to_string()
+        );
+    }
+
+    let result = core::forge::install_forge(&game_dir, &game_version, &forge_version).await?;
 
     emit_log!(
         window,
@@ -1515,6 +4190,97 @@ async fn install_forge(
     Ok(result)
 }
 
+/// Install OptiFine (without Forge) from a user-provided installer jar
+#[tauri::command]
+async fn install_optifine(
+    window: Window,
+    config_state: State<'_, core::config::ConfigState>,
+    installer_path: String,
+) -> Result<core::optifine::InstalledOptiFineVersion, LauncherError> {
+    let installer = std::path::PathBuf::from(&installer_path);
+    let release = core::optifine::inspect_installer(&installer)?;
+    emit_log!(
+        window,
+        format!(
+            "Installing OptiFine {} for Minecraft {}...",
+            release.edition, release.minecraft_version
+        )
+    );
+
+    let app_handle = window.app_handle();
+    let game_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+
+    let required = core::optifine::required_java_major(&game_dir, &release.minecraft_version);
+    let preferred = config_state.config.lock().unwrap().java_path.clone();
+    let java_path =
+        core::java::find_suitable_java(app_handle, required, &preferred).ok_or_else(|| {
+            format!(
+                "OptiFine needs Java {} or newer, but none was found",
+                required
+            )
+        })?;
+
+    let result =
+        core::optifine::install_optifine(&game_dir, &installer, std::path::Path::new(&java_path))
+            .await?;
+
+    emit_log!(
+        window,
+        format!("OptiFine installed successfully: {}", result.id)
+    );
+    Ok(result)
+}
+
+/// Install LiteLoader from a user-provided installer jar
+#[tauri::command]
+async fn install_liteloader(
+    window: Window,
+    installer_path: String,
+) -> Result<core::liteloader::InstalledLiteLoaderVersion, LauncherError> {
+    let installer = std::path::PathBuf::from(&installer_path);
+    let minecraft_version = core::liteloader::inspect_installer(&installer)?;
+    emit_log!(
+        window,
+        format!(
+            "Installing LiteLoader for Minecraft {}...",
+            minecraft_version
+        )
+    );
+
+    let game_dir = window
+        .app_handle()
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    let result = tokio::task::spawn_blocking(move || {
+        core::liteloader::install_liteloader(&game_dir, &installer)
+    })
+    .await??;
+
+    emit_log!(
+        window,
+        format!("LiteLoader installed successfully: {}", result.id)
+    );
+    Ok(result)
+}
+
+/// Cancel running modpack and instance archive extractions
+#[tauri::command]
+async fn cancel_extraction() -> Result<(), LauncherError> {
+    core::extract::cancel_extraction();
+    Ok(())
+}
+
+/// Cancel the running Forge installer
+#[tauri::command]
+async fn cancel_forge_install() -> Result<(), LauncherError> {
+    core::forge::cancel_forge_install();
+    Ok(())
+}
+
 #[derive(serde::Serialize)]
 struct GithubRelease {
     tag_name: String,
@@ -1525,20 +4291,22 @@ struct GithubRelease {
 }
 
 #[tauri::command]
-async fn get_github_releases() -> Result<Vec<GithubRelease>, String> {
-    let client = reqwest::Client::new();
+async fn get_github_releases() -> Result<Vec<GithubRelease>, LauncherError> {
+    let client = core::http::client();
     let res = client
         .get("https://api.github.com/repos/HsiangNianian/DropOut/releases")
         .header("User-Agent", "DropOut-Launcher")
         .send()
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
 
     if !res.status().is_success() {
-        return Err(format!("GitHub API returned status: {}", res.status()));
+        return Err(LauncherError::Network(format!(
+            "GitHub API returned status: {}",
+            res.status()
+        )));
     }
 
-    let releases: Vec<serde_json::Value> = res.json().await.map_err(|e| e.to_string())?;
+    let releases: Vec<serde_json::Value> = res.json().await?;
 
     let mut result = Vec::new();
     for r in releases {
@@ -1561,6 +4329,23 @@ async fn get_github_releases() -> Result<Vec<GithubRelease>, String> {
     Ok(result)
 }
 
+/// Get official Minecraft news tiles and Java Edition patch notes
+#[tauri::command]
+async fn get_minecraft_news() -> Result<core::news::MinecraftNews, LauncherError> {
+    Ok(core::news::fetch_news().await?)
+}
+
+/// Get Mojang patch notes and the loader changelog for an installed or installable version
+#[tauri::command]
+async fn get_version_changelog(
+    version_id: String,
+) -> Result<core::changelog::VersionChangelog, LauncherError> {
+    Ok(core::changelog::fetch_changelog(&version_id).await?)
+}
+
+/// Largest log accepted by `upload_to_pastebin`
+const MAX_UPLOAD_SIZE: usize = 500 * 1024;
+
 #[derive(Serialize)]
 struct PastebinResponse {
     url: String,
@@ -1568,27 +4353,80 @@ struct PastebinResponse {
 
 #[tauri::command]
 async fn upload_to_pastebin(
+    app_handle: tauri::AppHandle,
     state: State<'_, core::config::ConfigState>,
     content: String,
-) -> Result<PastebinResponse, String> {
+) -> Result<PastebinResponse, LauncherError> {
     // Check content length limit
-    if content.len() > 500 * 1024 {
-        return Err("Log file too large (max 500KB)".to_string());
+    if content.len() > MAX_UPLOAD_SIZE {
+        return Err(LauncherError::Invalid(
+            "Log file too large (max 500KB)".to_string(),
+        ));
+    }
+
+    // System information goes at the top so reports are useful on their own
+    let content = match collect_diagnostics(&app_handle, &state).await {
+        Ok(diagnostics) => format!("{}\n{}", diagnostics.log_header(), content),
+        Err(_) => content,
+    };
+    let content = log_redactor(&app_handle, &state).redact(&content);
+    let url = upload_log(&state, content).await?;
+    Ok(PastebinResponse { url })
+}
+
+/// Redactor for logs leaving the machine, knowing the signed-in account's tokens
+fn log_redactor(
+    app_handle: &tauri::AppHandle,
+    config_state: &core::config::ConfigState,
+) -> core::redact::Redactor {
+    let mut redactor = core::redact::Redactor::new();
+    let refresh_token_state = app_handle.state::<MsRefreshTokenState>();
+    let pending_refresh_token = refresh_token_state.token.lock().unwrap().clone();
+    if let Some(token) = pending_refresh_token {
+        redactor = redactor.secret(&token);
+    }
+    let account = app_handle
+        .state::<core::auth::AccountState>()
+        .active_account
+        .lock()
+        .unwrap()
+        .clone();
+    if let Some(account) = account {
+        redactor = redactor.secret(&account.access_token());
+        if let core::auth::Account::Microsoft(ms) = &account {
+            if let Some(refresh_token) = &ms.refresh_token {
+                redactor = redactor.secret(refresh_token);
+            }
+        }
+        if config_state.config.lock().unwrap().redact_account_in_logs {
+            redactor = redactor.account(&account.username(), &account.uuid());
+        }
     }
+    redactor
+}
+
+#[derive(serde::Deserialize)]
+struct McLogsResponse {
+    success: bool,
+    url: Option<String>,
+    error: Option<String>,
+}
 
+/// Upload `content` to the configured paste service and return its URL
+async fn upload_log(
+    state: &core::config::ConfigState,
+    content: String,
+) -> Result<String, LauncherError> {
     // Extract config values before any async calls to avoid holding MutexGuard across await
     let (service, api_key) = {
         let config = state.config.lock().unwrap();
-        (
-            config.log_upload_service.clone(),
-            config.pastebin_api_key.clone(),
-        )
+        (config.log_upload_service, config.pastebin_api_key.clone())
     };
 
-    let client = reqwest::Client::new();
+    let client = core::http::client();
 
-    match service.as_str() {
-        "pastebin.com" => {
+    match service {
+        core::config::LogUploadService::Pastebin => {
             let api_key = api_key
                 .ok_or("Pastebin API Key not configured in settings")?;
 
@@ -1603,52 +4441,237 @@ async fn upload_to_pastebin(
                     ("api_paste_expire_date", "1W"),
                 ])
                 .send()
-                .await
-                .map_err(|e| e.to_string())?;
+                .await?;
 
             if !res.status().is_success() {
-                return Err(format!("Pastebin upload failed: {}", res.status()));
+                return Err(LauncherError::Network(format!(
+                    "Pastebin upload failed: {}",
+                    res.status()
+                )));
             }
 
-            let url = res.text().await.map_err(|e| e.to_string())?;
+            let url = res.text().await?;
             if url.starts_with("Bad API Request") {
-                return Err(format!("Pastebin API error: {}", url));
+                return Err(LauncherError::Network(format!(
+                    "Pastebin API error: {}",
+                    url
+                )));
+            }
+            Ok(url)
+        }
+        core::config::LogUploadService::McLogs => {
+            let res = client
+                .post("https://api.mclo.gs/1/log")
+                .form(&[("content", content.as_str())])
+                .send()
+                .await?;
+
+            if !res.status().is_success() {
+                return Err(LauncherError::Network(format!(
+                    "mclo.gs upload failed: {}",
+                    res.status()
+                )));
+            }
+
+            let body: McLogsResponse = res.json().await?;
+            match body.url {
+                Some(url) if body.success => Ok(url),
+                _ => Err(LauncherError::Network(format!(
+                    "mclo.gs API error: {}",
+                    body.error.unwrap_or_else(|| "unknown error".to_string())
+                ))),
             }
-            Ok(PastebinResponse { url })
         }
-        // Default to paste.rs
-        _ => {
+        core::config::LogUploadService::PasteRs => {
             let res = client
                 .post("https://paste.rs/")
                 .body(content)
                 .send()
-                .await
-                .map_err(|e| e.to_string())?;
+                .await?;
 
             if !res.status().is_success() {
-                return Err(format!("paste.rs upload failed: {}", res.status()));
+                return Err(LauncherError::Network(format!(
+                    "paste.rs upload failed: {}",
+                    res.status()
+                )));
             }
 
-            let url = res.text().await.map_err(|e| e.to_string())?;
-            let url = url.trim().to_string();
-            Ok(PastebinResponse { url })
+            let url = res.text().await?;
+            Ok(url.trim().to_string())
         }
     }
 }
 
+#[derive(Serialize)]
+struct DiagnosticBundleResult {
+    /// Zip written under the data directory's `diagnostics/`
+    path: String,
+    /// Paste of the bundle's text, when uploading was requested
+    url: Option<String>,
+}
+
+/// Open a folder of an instance, e.g. `mods`, in the system file manager
+#[tauri::command]
+async fn open_instance_folder(
+    window: Window,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: String,
+    subdir: Option<String>,
+) -> Result<(), LauncherError> {
+    let instance = instance_state.get(&instance_id)?;
+    let dir = core::folders::managed_dir(
+        &instance_state.instance_dir(&instance.id),
+        subdir.as_deref(),
+    )
+    .map_err(LauncherError::Invalid)?;
+    open_folder(&window, &dir)
+}
+
+/// Open the launcher data directory holding versions, libraries and assets
+#[tauri::command]
+async fn open_game_dir(window: Window) -> Result<(), LauncherError> {
+    let data_dir = window.app_handle().path().app_data_dir()?;
+    open_folder(&window, &core::folders::managed_dir(&data_dir, None)?)
+}
+
+/// Open the game logs of an instance, or of launches without one
+#[tauri::command]
+async fn open_logs_folder(
+    window: Window,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    instance_id: Option<String>,
+) -> Result<(), LauncherError> {
+    let root = match instance_id {
+        Some(id) => instance_state.instance_dir(&instance_state.get(&id)?.id),
+        None => window.app_handle().path().app_data_dir()?,
+    };
+    open_folder(&window, &core::folders::managed_dir(&root, Some("logs"))?)
+}
+
+fn open_folder(window: &Window, dir: &std::path::Path) -> Result<(), LauncherError> {
+    use tauri_plugin_opener::OpenerExt;
+    window
+        .opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| LauncherError::Other(format!("Failed to open {}: {}", dir.display(), e)))
+}
+
+/// Collect launcher and game logs, redacted settings, installed versions and
+/// system information into a zip, optionally uploading them as text
+#[tauri::command]
+async fn generate_diagnostic_bundle(
+    app_handle: tauri::AppHandle,
+    config_state: State<'_, core::config::ConfigState>,
+    instance_state: State<'_, core::instance::InstanceManager>,
+    launcher_log: String,
+    upload: bool,
+) -> Result<DiagnosticBundleResult, LauncherError> {
+    let data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    let diagnostics = collect_diagnostics(&app_handle, &config_state).await?;
+
+    let mut bundle = core::diagnostics::DiagnosticBundle::new(&diagnostics);
+    bundle.add("launcher.log", launcher_log);
+    let config = config_state.config.lock().unwrap().clone();
+    bundle.add("config.json", core::diagnostics::redacted_config(&config));
+    let versions = read_installed_versions(&data_dir)
+        .await?
+        .iter()
+        .map(|v| format!("{} ({})\n", v.id, v.version_type))
+        .collect::<String>();
+    bundle.add("versions.txt", versions);
+
+    let active_instance = instance_state.lock_index().active_instance_id.clone();
+    if let Some(id) = active_instance {
+        bundle.add_instance_files(&instance_state.instance_dir(&id));
+    }
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = data_dir
+        .join("diagnostics")
+        .join(format!("dropout-diagnostics-{}.zip", timestamp));
+    bundle.redact(&log_redactor(&app_handle, &config_state));
+    bundle.write_zip(&path)?;
+
+    let url = if upload {
+        Some(upload_log(&config_state, bundle.to_text(MAX_UPLOAD_SIZE)).await?)
+    } else {
+        None
+    };
+    Ok(DiagnosticBundleResult {
+        path: path.to_string_lossy().to_string(),
+        url,
+    })
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(command) = cli::parse(&args) {
+        let code = match command {
+            Ok(command) => cli::run(command),
+            Err(e) => {
+                eprintln!("{}", e);
+                2
+            }
+        };
+        std::process::exit(code);
+    }
+
     tauri::Builder::default()
+        // Must come first, so a second process exits before anything else
+        // touches the config and instance files
+        .plugin(tauri_plugin_single_instance::init(
+            forward_to_running_instance,
+        ))
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_deep_link::init())
         .manage(core::auth::AccountState::new())
         .manage(MsRefreshTokenState::new())
+        .manage(core::download_manager::DownloadManager::new())
+        .manage(core::launch::LaunchLocks::new())
+        .manage(core::game_metrics::MetricsState::new())
+        .manage(core::console_window::ConsoleWindows::new())
+        .manage(core::deeplink::DeepLinkState::new())
         .setup(|app| {
             let config_state = core::config::ConfigState::new(app.handle());
+            core::http::configure(&config_state.config.lock().unwrap());
+            core::logging::configure(&config_state.config.lock().unwrap());
             app.manage(config_state);
 
             // Load saved account on startup
             let app_dir = app.path().app_data_dir().unwrap();
+            if let Some(warning) = core::cloud_sync::check(&app_dir) {
+                println!("[Startup] {}", warning.message);
+                app.handle().emit_event(warning);
+            }
+            let retention = app
+                .state::<core::config::ConfigState>()
+                .config
+                .lock()
+                .unwrap()
+                .trash_retention_days;
+            let purged = core::trash::purge_expired(&core::trash::trash_dir(&app_dir), retention);
+            if purged > 0 {
+                println!("[Startup] Removed {} expired items from the trash", purged);
+            }
+            app.manage(core::instance::InstanceManager::new(&app_dir));
+            app.manage(core::instance_watcher::InstanceWatcher::new(
+                app.handle().clone(),
+            ));
+            watch_active_instance(app.handle());
+            app.manage(core::file_index::FileIndexState::new(&app_dir));
+            core::http_cache::set_cache_dir(app_dir.join("cache").join("http"));
+            app.manage(core::stats::StatsState::new(&app_dir));
+            app.manage(core::download_stats::DownloadStatsState::new(&app_dir));
+            app.manage(core::server::ServerManager::new(&app_dir));
             let storage = core::account_storage::AccountStorage::new(app_dir);
 
             if let Some((stored_account, ms_refresh)) = storage.get_active_account() {
@@ -1665,39 +4688,141 @@ fn main() {
                 println!("[Startup] Loaded saved account");
             }
 
+            setup_deep_links(app.handle());
+            start_prefetch_job(app.handle());
+
+            let tray_handle = app.handle().clone();
+            app.state::<core::instance::InstanceManager>()
+                .set_on_change(move || {
+                    let app = tray_handle.clone();
+                    // Runs with the index locked; the menu reads it afterwards
+                    tauri::async_runtime::spawn_blocking(move || tray::refresh(&app));
+                });
+            let show_tray_icon = app
+                .state::<core::config::ConfigState>()
+                .config
+                .lock()
+                .unwrap()
+                .show_tray_icon;
+            tray::apply(app.handle(), show_tray_icon);
+
             // Check for pending Java downloads and notify frontend
             let pending = core::java::get_pending_downloads(&app.app_handle());
             if !pending.is_empty() {
                 println!("[Startup] Found {} pending Java download(s)", pending.len());
                 let _ = app.emit("pending-java-downloads", pending.len());
             }
+            // Game files left by closing the launcher mid-download
+            let pending = core::downloader::PendingDownloads::load(app.app_handle());
+            if !pending.files.is_empty() {
+                println!(
+                    "[Startup] Found {} interrupted game file download(s)",
+                    pending.files.len()
+                );
+                let _ = app.emit("pending-downloads", &pending);
+            }
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             start_game,
+            export_launch_script,
             get_versions,
             check_version_installed,
             install_version,
             list_installed_versions,
+            list_local_versions,
+            delete_version,
+            import_custom_version,
+            garbage_collect,
+            prune_assets,
+            dedupe_mods,
+            get_disk_usage,
+            get_download_queue,
+            clear_meta_cache,
+            get_playtime_stats,
+            get_game_metrics,
+            get_download_stats,
+            open_console_window,
+            get_gc_report,
+            list_servers,
+            download_server_jar,
+            create_server,
+            update_server,
+            delete_server,
+            start_server,
+            send_server_command,
+            stop_server,
+            sync_world,
+            list_instances,
+            get_instance_icon,
+            set_instance_icon_from_file,
+            set_instance_icon_builtin,
+            list_builtin_icons,
+            set_instance_notes,
+            set_instance_network,
+            set_instance_modpack,
+            check_pack_update,
+            update_pack,
+            reorder_instances,
+            set_instance_group,
+            set_instance_tags,
+            add_jar_mod,
+            remove_jar_mod,
+            set_jar_mods,
+            list_instance_groups,
+            set_instance_group_order,
+            get_instance,
+            create_instance,
+            update_instance,
+            rollback_loader,
+            diff_instances,
+            suggest_jvm_settings,
+            list_instance_backups,
+            restore_instance_backup,
+            apply_instance_template,
+            delete_instance,
+            list_trash,
+            restore_deleted_item,
+            empty_trash,
+            archive_instance,
+            unarchive_instance,
+            set_active_instance,
+            import_dropped_file,
+            import_vanilla_directory,
+            import_launcher_instance,
             login_offline,
             get_active_account,
+            list_accounts,
+            get_account_avatar,
             logout,
             get_settings,
             save_settings,
             start_microsoft_login,
-            complete_microsoft_login,
+            cancel_microsoft_login,
             refresh_account,
+            get_account_entitlements,
+            create_minecraft_profile,
             // Java commands
             detect_java,
+            needs_initial_setup,
+            get_system_info,
+            check_cloud_sync,
+            propose_setup_defaults,
+            run_initial_setup,
             get_recommended_java,
             fetch_adoptium_java,
             download_adoptium_java,
+            verify_java_installation,
+            check_java_updates,
+            update_java,
             fetch_available_java_versions,
             fetch_java_catalog,
             refresh_java_catalog,
             cancel_java_download,
             get_pending_java_downloads,
+            get_pending_downloads,
+            resume_pending_downloads,
             resume_java_downloads,
             // Fabric commands
             get_fabric_game_versions,
@@ -1710,8 +4835,20 @@ fn main() {
             get_forge_game_versions,
             get_forge_versions_for_game,
             install_forge,
+            install_forge_full,
+            cancel_forge_install,
+            cancel_extraction,
+            install_optifine,
+            install_liteloader,
             get_github_releases,
-            upload_to_pastebin
+            get_minecraft_news,
+            get_version_changelog,
+            upload_to_pastebin,
+            generate_diagnostic_bundle,
+            open_instance_folder,
+            open_game_dir,
+            open_logs_folder,
+            take_pending_deep_link
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,135 @@
+//! System tray icon with a quick-launch menu.
+//!
+//! The menu lists the instances in display order, so a game can be started
+//! without opening the launcher window. It is rebuilt whenever an instance
+//! is created, deleted, renamed or reordered, and the icon is shown or
+//! removed when the `show_tray_icon` setting changes.
+
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::core;
+
+const TRAY_ID: &str = "main";
+const LAUNCH_PREFIX: &str = "launch:";
+const SHOW_ID: &str = "show";
+const QUIT_ID: &str = "quit";
+/// Instances beyond this are only reachable from the window
+const MAX_INSTANCES: usize = 15;
+
+/// Show or remove the tray icon according to `enabled`.
+pub fn apply(app: &AppHandle, enabled: bool) {
+    if !enabled {
+        app.remove_tray_by_id(TRAY_ID);
+        return;
+    }
+    if app.tray_by_id(TRAY_ID).is_some() {
+        return refresh(app);
+    }
+    let result = build_menu(app).and_then(|menu| {
+        let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+            .menu(&menu)
+            .tooltip("DropOut")
+            .show_menu_on_left_click(true)
+            .on_menu_event(on_menu_event);
+        if let Some(icon) = app.default_window_icon() {
+            builder = builder.icon(icon.clone());
+        }
+        builder.build(app)
+    });
+    if let Err(e) = result {
+        println!("[Tray] Failed to create tray icon: {}", e);
+    }
+}
+
+/// Rebuild the menu from the current instances.
+pub fn refresh(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    if let Err(e) = build_menu(app).and_then(|menu| tray.set_menu(Some(menu))) {
+        println!("[Tray] Failed to update tray menu: {}", e);
+    }
+}
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let menu = Menu::new(app)?;
+    let instances = app.state::<core::instance::InstanceManager>().list();
+    for instance in instances.iter().take(MAX_INSTANCES) {
+        let item = MenuItem::with_id(
+            app,
+            format!("{}{}", LAUNCH_PREFIX, instance.id),
+            format!("Launch {}", instance.name),
+            true,
+            None::<&str>,
+        )?;
+        menu.append(&item)?;
+    }
+    if !instances.is_empty() {
+        menu.append(&PredefinedMenuItem::separator(app)?)?;
+    }
+    menu.append(&MenuItem::with_id(
+        app,
+        SHOW_ID,
+        "Show launcher",
+        true,
+        None::<&str>,
+    )?)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        QUIT_ID,
+        "Quit",
+        true,
+        None::<&str>,
+    )?)?;
+    Ok(menu)
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        SHOW_ID => crate::show_main_window(app),
+        QUIT_ID => app.exit(0),
+        id => {
+            if let Some(instance_id) = id.strip_prefix(LAUNCH_PREFIX) {
+                launch(app, instance_id.to_string());
+            }
+        }
+    }
+}
+
+/// Launch an instance the same way the Play button does. Progress and game
+/// output go to the main window as usual.
+fn launch(app: &AppHandle, instance_id: String) {
+    let Some(webview) = app.get_webview_window("main") else {
+        return;
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let instance = match app
+            .state::<core::instance::InstanceManager>()
+            .get(&instance_id)
+        {
+            Ok(instance) => instance,
+            Err(e) => {
+                println!("[Tray] {}", e);
+                return;
+            }
+        };
+        let result = crate::start_game(
+            webview.as_ref().window(),
+            app.state(),
+            app.state(),
+            app.state(),
+            app.state(),
+            instance.version_id,
+            Some(instance.id),
+            None,
+        )
+        .await;
+        if let Err(e) = result {
+            println!("[Tray] Failed to launch {}: {}", instance.name, e);
+            crate::show_main_window(&app);
+        }
+    });
+}
@@ -13,6 +13,109 @@ pub mod file_utils {
         let mut file = fs::File::create(path)?;
         file.write_all(content.as_bytes())
     }
+
+    /// Total size in bytes of all files below `path` (symlinks are not followed).
+    /// Unreadable entries are skipped; a missing path has size 0.
+    pub fn dir_size(path: &std::path::Path) -> u64 {
+        let Ok(meta) = fs::symlink_metadata(path) else {
+            return 0;
+        };
+        if !meta.is_dir() {
+            return meta.len();
+        }
+
+        let mut total = 0;
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.flatten() {
+                total += dir_size(&entry.path());
+            }
+        }
+        total
+    }
+
+    /// Hard-link `src` to `dst`, falling back to a copy when linking is not
+    /// possible (different filesystem, unsupported FS, ...).
+    /// Parent directories of `dst` are created as needed.
+    pub fn link_or_copy(src: &std::path::Path, dst: &std::path::Path) -> io::Result<()> {
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if fs::hard_link(src, dst).is_ok() {
+            return Ok(());
+        }
+        fs::copy(src, dst).map(|_| ())
+    }
+
+    /// `path` with `suffix` appended to its file name, e.g. `config.json.bak`.
+    fn with_suffix(path: &std::path::Path, suffix: &str) -> std::path::PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(suffix);
+        path.with_file_name(name)
+    }
+
+    /// Write `value` as pretty JSON so that `path` always holds either the
+    /// old or the new contents, even if the launcher crashes mid-write: the
+    /// JSON goes to a synced temporary file that is renamed over `path`.
+    /// The previous contents are kept as `<path>.bak` if they were valid.
+    pub fn write_json_atomic<T: serde::Serialize + ?Sized>(
+        path: &std::path::Path,
+        value: &T,
+    ) -> io::Result<()> {
+        let content = serde_json::to_vec_pretty(value)?;
+        let parent = path.parent().unwrap_or(std::path::Path::new("."));
+        fs::create_dir_all(parent)?;
+
+        let tmp = with_suffix(path, ".tmp");
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(&content)?;
+        file.sync_all()?;
+        drop(file);
+
+        // A damaged file must not replace the last good backup
+        if let Ok(old) = fs::read(path) {
+            if serde_json::from_slice::<serde::de::IgnoredAny>(&old).is_ok() {
+                fs::write(with_suffix(path, ".bak"), old)?;
+            }
+        }
+        fs::rename(&tmp, path)?;
+        // Persist the rename itself
+        #[cfg(unix)]
+        fs::File::open(parent)?.sync_all()?;
+        Ok(())
+    }
+
+    /// Read JSON written by `write_json_atomic`, falling back to the `.bak`
+    /// copy if `path` is unreadable or damaged. The error for `path` is
+    /// returned when neither can be loaded.
+    pub fn read_json<T: serde::de::DeserializeOwned>(path: &std::path::Path) -> io::Result<T> {
+        let parse = |path: &std::path::Path| -> io::Result<T> {
+            Ok(serde_json::from_slice(&fs::read(path)?)?)
+        };
+        parse(path).or_else(|e| {
+            let backup = parse(&with_suffix(path, ".bak")).map_err(|_| e)?;
+            println!(
+                "[Storage] {} could not be read, using its backup",
+                path.display()
+            );
+            Ok(backup)
+        })
+    }
+
+    /// Recursively copy a directory. Existing files in `dst` are overwritten.
+    pub fn copy_dir_recursive(src: &std::path::Path, dst: &std::path::Path) -> io::Result<()> {
+        fs::create_dir_all(dst)?;
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            let target = dst.join(entry.file_name());
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                copy_dir_recursive(&entry.path(), &target)?;
+            } else if file_type.is_file() {
+                fs::copy(entry.path(), &target)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 // Configuration parsing utilities
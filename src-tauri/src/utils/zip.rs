@@ -1,43 +1,319 @@
+//! Archive extraction and creation.
+//!
+//! Zip and tar.gz extraction share `Extractor`, which keeps entries inside
+//! the target directory, also through links it created, restores Unix permissions, applies a
+//! `SymlinkPolicy`, caps the total extracted size and reports progress.
+
 use flate2::read::GzDecoder;
 use std::fs;
-use std::path::Path;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use tar::Archive;
 
-pub fn extract_zip(zip_path: &Path, extract_to: &Path) -> Result<(), String> {
+/// Default cap on the extracted size, against zip bombs.
+pub const DEFAULT_MAX_EXTRACTED_SIZE: u64 = 16 * 1024 * 1024 * 1024;
+
+/// What to do with symbolic links in an archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Leave them out
+    Skip,
+    /// Create links that resolve inside the target directory and skip the
+    /// rest. Links are skipped on Windows, where creating them needs
+    /// privileges.
+    Contained,
+}
+
+/// Extraction progress. Totals are known for zip archives only.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExtractProgress {
+    pub entries_done: usize,
+    pub entries_total: Option<usize>,
+    pub bytes_done: u64,
+    pub bytes_total: Option<u64>,
+}
+
+pub type ProgressFn<'a> = Box<dyn FnMut(&ExtractProgress) + 'a>;
+
+pub struct ExtractOptions<'a> {
+    pub symlinks: SymlinkPolicy,
+    /// Total uncompressed bytes allowed
+    pub max_total_size: u64,
+    /// Called after each entry
+    pub progress: Option<ProgressFn<'a>>,
+    /// Checked before each entry; extraction fails once it is set
+    pub cancel: Option<&'a AtomicBool>,
+}
+
+impl Default for ExtractOptions<'_> {
+    fn default() -> Self {
+        Self {
+            symlinks: SymlinkPolicy::Contained,
+            max_total_size: DEFAULT_MAX_EXTRACTED_SIZE,
+            progress: None,
+            cancel: None,
+        }
+    }
+}
+
+/// Join `entry` to `root`, refusing absolute paths and `..` escapes.
+fn contained_path(root: &Path, entry: &Path) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in entry.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !relative.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(root.join(relative))
+}
+
+/// `path` with the links along its existing part resolved; the part that
+/// does not exist yet is normalized as written.
+pub fn resolve(path: &Path) -> Option<PathBuf> {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    let mut real = loop {
+        match fs::canonicalize(existing) {
+            Ok(real) => break real,
+            Err(_) => {
+                rest.push(existing.components().next_back()?);
+                existing = existing.parent()?;
+            }
+        }
+    };
+    for component in rest.into_iter().rev() {
+        match component {
+            Component::ParentDir => {
+                real.pop();
+            }
+            Component::Normal(part) => real.push(part),
+            _ => {}
+        }
+    }
+    Some(real)
+}
+
+struct Extractor<'o, 'a> {
+    root: &'o Path,
+    /// `root` with links resolved
+    real_root: PathBuf,
+    options: &'o mut ExtractOptions<'a>,
+    progress: ExtractProgress,
+}
+
+impl<'o, 'a> Extractor<'o, 'a> {
+    fn new(root: &'o Path, options: &'o mut ExtractOptions<'a>) -> Result<Self, String> {
+        fs::create_dir_all(root)
+            .map_err(|e| format!("Failed to create extract directory: {}", e))?;
+        let real_root = fs::canonicalize(root)
+            .map_err(|e| format!("Failed to resolve extract directory: {}", e))?;
+        Ok(Self {
+            root,
+            real_root,
+            options,
+            progress: ExtractProgress::default(),
+        })
+    }
+
+    fn check_cancelled(&self) -> Result<(), String> {
+        match self.options.cancel {
+            Some(cancel) if cancel.load(Ordering::SeqCst) => {
+                Err("Extraction cancelled".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn entry_done(&mut self) {
+        self.progress.entries_done += 1;
+        if let Some(progress) = self.options.progress.as_mut() {
+            progress(&self.progress);
+        }
+    }
+
+    fn target(&self, entry: &Path) -> Result<PathBuf, String> {
+        contained_path(self.root, entry)
+            .filter(|path| self.inside(path))
+            .ok_or_else(|| format!("Archive entry escapes the target: {}", entry.display()))
+    }
+
+    /// Whether `path` stays inside the root once links are followed.
+    fn inside(&self, path: &Path) -> bool {
+        resolve(path).is_some_and(|real| real.starts_with(&self.real_root))
+    }
+
+    fn dir(&mut self, path: &Path) -> Result<(), String> {
+        fs::create_dir_all(path)
+            .map_err(|e| format!("Failed to create directory {}: {}", path.display(), e))
+    }
+
+    fn file(
+        &mut self,
+        path: &Path,
+        reader: &mut dyn Read,
+        mode: Option<u32>,
+    ) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create dir: {}", e))?;
+        }
+        let mut outfile = fs::File::create(path)
+            .map_err(|e| format!("Failed to create file {}: {}", path.display(), e))?;
+        // One byte past the budget tells an oversized entry apart
+        let budget = self.options.max_total_size - self.progress.bytes_done;
+        let written = std::io::copy(&mut reader.take(budget + 1), &mut outfile)
+            .map_err(|e| format!("Failed to extract {}: {}", path.display(), e))?;
+        if written > budget {
+            drop(outfile);
+            let _ = fs::remove_file(path);
+            return Err(self.too_large());
+        }
+        self.progress.bytes_done += written;
+
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o7777));
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+        Ok(())
+    }
+
+    fn symlink(&mut self, path: &Path, link_target: &Path) -> Result<(), String> {
+        match self.options.symlinks {
+            SymlinkPolicy::Skip => Ok(()),
+            SymlinkPolicy::Contained => {
+                let parent = path.parent().unwrap_or(self.root);
+                let relative_parent = parent.strip_prefix(self.root).unwrap_or(Path::new(""));
+                if link_target.is_absolute()
+                    || contained_path(self.root, &relative_parent.join(link_target)).is_none()
+                    || !self.inside(&parent.join(link_target))
+                {
+                    println!(
+                        "[Extract] Skipping link {} pointing outside: {}",
+                        path.display(),
+                        link_target.display()
+                    );
+                    return Ok(());
+                }
+                #[cfg(unix)]
+                {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| format!("Failed to create dir: {}", e))?;
+                    let _ = fs::remove_file(path);
+                    std::os::unix::fs::symlink(link_target, path)
+                        .map_err(|e| format!("Failed to create link {}: {}", path.display(), e))?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn too_large(&self) -> String {
+        format!(
+            "Archive expands beyond the {} MiB limit",
+            self.options.max_total_size / 1024 / 1024
+        )
+    }
+}
+
+/// Extract a zip archive.
+pub fn extract_zip(
+    zip_path: &Path,
+    extract_to: &Path,
+    mut options: ExtractOptions,
+) -> Result<(), String> {
     let file = fs::File::open(zip_path)
         .map_err(|e| format!("Failed to open zip {}: {}", zip_path.display(), e))?;
     let mut archive =
         zip::ZipArchive::new(file).map_err(|e| format!("Failed to read zip: {}", e))?;
 
+    let mut extractor = Extractor::new(extract_to, &mut options)?;
+    // Declared sizes can lie, so the copy is capped as well
+    let declared: u64 = (0..archive.len())
+        .filter_map(|i| archive.by_index_raw(i).ok().map(|f| f.size()))
+        .sum();
+    if declared > extractor.options.max_total_size {
+        return Err(extractor.too_large());
+    }
+    extractor.progress.bytes_total = Some(declared);
+    extractor.progress.entries_total = Some(archive.len());
+
     for i in 0..archive.len() {
+        extractor.check_cancelled()?;
         let mut file = archive
             .by_index(i)
             .map_err(|e| format!("Failed to read zip entry: {}", e))?;
-        let outpath = match file.enclosed_name() {
-            Some(path) => extract_to.join(path),
-            None => continue,
-        };
-
-        // Skip META-INF
-        if outpath.to_string_lossy().contains("META-INF") {
-            continue;
-        }
+        let outpath = extractor.target(Path::new(file.name()))?;
 
-        if file.name().ends_with('/') {
-            fs::create_dir_all(&outpath).map_err(|e| format!("Failed to create dir: {}", e))?;
+        if file.is_dir() {
+            extractor.dir(&outpath)?;
+        } else if file.is_symlink() {
+            let mut link_target = String::new();
+            file.read_to_string(&mut link_target)
+                .map_err(|e| format!("Failed to read zip entry: {}", e))?;
+            extractor.symlink(&outpath, Path::new(&link_target))?;
         } else {
-            if let Some(p) = outpath.parent() {
-                if !p.exists() {
-                    fs::create_dir_all(p).map_err(|e| format!("Failed to create dir: {}", e))?;
-                }
+            let mode = file.unix_mode();
+            extractor.file(&outpath, &mut file, mode)?;
+        }
+        extractor.entry_done();
+    }
+
+    Ok(())
+}
+
+/// Zip the contents of `src_dir` into `zip_path`, skipping top-level entries named in `exclude`.
+pub fn create_zip(src_dir: &Path, zip_path: &Path, exclude: &[&str]) -> Result<(), String> {
+    if let Some(parent) = zip_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create dir: {}", e))?;
+    }
+    let file = fs::File::create(zip_path)
+        .map_err(|e| format!("Failed to create zip {}: {}", zip_path.display(), e))?;
+    let mut writer = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    let mut pending = vec![src_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = fs::read_dir(&dir).map_err(|e| format!("Failed to read dir: {}", e))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(relative) = path.strip_prefix(src_dir) else {
+                continue;
+            };
+            if dir == src_dir && exclude.iter().any(|e| entry.file_name() == **e) {
+                continue;
+            }
+            let name = relative.to_string_lossy().replace('\\', "/");
+            if path.is_dir() {
+                writer
+                    .add_directory(format!("{}/", name), options)
+                    .map_err(|e| format!("Failed to write zip: {}", e))?;
+                pending.push(path);
+            } else if path.is_file() {
+                writer
+                    .start_file(name, options)
+                    .map_err(|e| format!("Failed to write zip: {}", e))?;
+                let mut source =
+                    fs::File::open(&path).map_err(|e| format!("Failed to read file: {}", e))?;
+                std::io::copy(&mut source, &mut writer)
+                    .map_err(|e| format!("Failed to write zip: {}", e))?;
             }
-            let mut outfile =
-                fs::File::create(&outpath).map_err(|e| format!("Failed to create file: {}", e))?;
-            std::io::copy(&mut file, &mut outfile)
-                .map_err(|e| format!("Failed to copy file: {}", e))?;
         }
     }
 
+    writer
+        .finish()
+        .map_err(|e| format!("Failed to write zip: {}", e))?;
     Ok(())
 }
 
@@ -45,16 +321,17 @@ pub fn extract_zip(zip_path: &Path, extract_to: &Path) -> Result<(), String> {
 ///
 /// Adoptium's tar.gz archives usually contain a top-level directory, such as `jdk-21.0.5+11-jre/`.
 /// This function returns the name of that directory to facilitate locating `bin/java` afterwards.
-pub fn extract_tar_gz(archive_path: &Path, extract_to: &Path) -> Result<String, String> {
+pub fn extract_tar_gz(
+    archive_path: &Path,
+    extract_to: &Path,
+    mut options: ExtractOptions,
+) -> Result<String, String> {
     let file = fs::File::open(archive_path)
         .map_err(|e| format!("Failed to open tar.gz {}: {}", archive_path.display(), e))?;
 
     let decoder = GzDecoder::new(file);
     let mut archive = Archive::new(decoder);
-
-    // Ensure the target directory exists
-    fs::create_dir_all(extract_to)
-        .map_err(|e| format!("Failed to create extract directory: {}", e))?;
+    let mut extractor = Extractor::new(extract_to, &mut options)?;
 
     // Track the top-level directory name
     let mut top_level_dir: Option<String> = None;
@@ -63,6 +340,7 @@ pub fn extract_tar_gz(archive_path: &Path, extract_to: &Path) -> Result<String,
         .entries()
         .map_err(|e| format!("Failed to read tar entries: {}", e))?
     {
+        extractor.check_cancelled()?;
         let mut entry = entry.map_err(|e| format!("Failed to read tar entry: {}", e))?;
         let entry_path = entry
             .path()
@@ -79,37 +357,61 @@ pub fn extract_tar_gz(archive_path: &Path, extract_to: &Path) -> Result<String,
             }
         }
 
-        let outpath = extract_to.join(&entry_path);
-
-        if entry.header().entry_type().is_dir() {
-            fs::create_dir_all(&outpath)
-                .map_err(|e| format!("Failed to create directory {}: {}", outpath.display(), e))?;
-        } else {
-            // Ensure parent directory exists
-            if let Some(parent) = outpath.parent() {
-                if !parent.exists() {
-                    fs::create_dir_all(parent)
-                        .map_err(|e| format!("Failed to create parent dir: {}", e))?;
-                }
-            }
-
-            let mut outfile = fs::File::create(&outpath)
-                .map_err(|e| format!("Failed to create file {}: {}", outpath.display(), e))?;
-
-            std::io::copy(&mut entry, &mut outfile)
-                .map_err(|e| format!("Failed to extract file: {}", e))?;
-
-            // Set executable permissions on Unix systems
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
-                if let Ok(mode) = entry.header().mode() {
-                    let permissions = fs::Permissions::from_mode(mode);
-                    let _ = fs::set_permissions(&outpath, permissions);
+        let outpath = extractor.target(&entry_path)?;
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_dir() {
+            extractor.dir(&outpath)?;
+        } else if entry_type.is_symlink() || entry_type.is_hard_link() {
+            let link_target = entry
+                .link_name()
+                .map_err(|e| format!("Failed to read tar entry: {}", e))?
+                .ok_or_else(|| format!("Link without target: {}", entry_path.display()))?
+                .into_owned();
+            if entry_type.is_hard_link() {
+                // Hard link targets are relative to the archive root; copy the file
+                let source = extractor.target(&link_target)?;
+                if source.is_file() {
+                    let mut reader = fs::File::open(&source)
+                        .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+                    let mode = entry.header().mode().ok();
+                    extractor.file(&outpath, &mut reader, mode)?;
                 }
+            } else {
+                extractor.symlink(&outpath, &link_target)?;
             }
+        } else if entry_type.is_file() {
+            let mode = entry.header().mode().ok();
+            extractor.file(&outpath, &mut entry, mode)?;
         }
+        extractor.entry_done();
     }
 
     top_level_dir.ok_or_else(|| "Archive appears to be empty".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_links_cannot_lead_outside() {
+        let dir = std::env::temp_dir().join(format!("dropout-extract-{}", uuid::Uuid::new_v4()));
+        let root = dir.join("root");
+        let mut options = ExtractOptions::default();
+        let mut extractor = Extractor::new(&root, &mut options).unwrap();
+
+        // `s/up` looks contained when read as written, but `s` is the root
+        let s = extractor.target(Path::new("s")).unwrap();
+        extractor.symlink(&s, Path::new(".")).unwrap();
+        let up = extractor.target(Path::new("s/up")).unwrap();
+        extractor.symlink(&up, Path::new("..")).unwrap();
+        assert!(fs::symlink_metadata(root.join("up")).is_err());
+
+        // An entry written through a link that does lead outside is refused
+        std::os::unix::fs::symlink(&dir, root.join("out")).unwrap();
+        assert!(extractor.target(Path::new("out/escaped.txt")).is_err());
+        assert!(extractor.target(Path::new("s/inside.txt")).is_ok());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,21 @@
+/**
+ * Helpers for errors thrown by `invoke`, which are `LauncherError` objects
+ * for backend commands and plain strings or `Error`s elsewhere.
+ */
+
+import type { LauncherError } from "../types";
+
+export function isLauncherError(e: unknown): e is LauncherError {
+  return typeof e === "object" && e !== null && "code" in e && "message" in e;
+}
+
+/** Readable text for any thrown value. */
+export function errorMessage(e: unknown): string {
+  if (isLauncherError(e)) {
+    return [...e.context, e.message].join(": ");
+  }
+  if (e instanceof Error) {
+    return e.message;
+  }
+  return String(e);
+}
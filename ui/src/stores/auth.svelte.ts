@@ -1,25 +1,59 @@
 import { invoke } from "@tauri-apps/api/core";
 import { open } from "@tauri-apps/plugin-shell";
 import { listen, type UnlistenFn } from "@tauri-apps/api/event";
-import type { Account, DeviceCodeResponse } from "../types";
+import type {
+  Account,
+  AccountProfileUpdatedEvent,
+  DeviceCodeResponse,
+  MsLoginCompleteEvent,
+  MsLoginPendingEvent,
+  SavedAccount,
+} from "../types";
 import { uiState } from "./ui.svelte";
 import { logsState } from "./logs.svelte";
+import { errorMessage } from "../lib/errors";
 
 export class AuthState {
   currentAccount = $state<Account | null>(null);
   isLoginModalOpen = $state(false);
   isLogoutConfirmOpen = $state(false);
-  loginMode = $state<"select" | "offline" | "microsoft">("select");
+  loginMode = $state<"select" | "offline" | "microsoft" | "create_profile">("select");
   offlineUsername = $state("");
+  /** Use the premium account's UUID so skins work on servers */
+  offlineUseOnlineUuid = $state(false);
   deviceCodeData = $state<DeviceCodeResponse | null>(null);
   msLoginLoading = $state(false);
   msLoginStatus = $state("Waiting for authorization...");
+  /** Where the user can fix a restricted account, e.g. a child account */
+  msLoginHelpUrl = $state<string | null>(null);
+  /** Name for the profile of a Game Pass account that has none yet */
+  profileName = $state("");
+  profileCreating = $state(false);
+  profileError = $state<string | null>(null);
 
-  private pollInterval: ReturnType<typeof setInterval> | null = null;
-  private isPollingRequestActive = false;
   private authProgressUnlisten: UnlistenFn | null = null;
+  private loginEventUnlisteners: UnlistenFn[] = [];
+  private profileUnlisten: UnlistenFn | null = null;
 
   async checkAccount() {
+    // A refreshed token may come with a new name (or UUID)
+    this.profileUnlisten ??= await listen<AccountProfileUpdatedEvent>(
+      "account-profile-updated",
+      ({ payload }) => {
+        logsState.addLog(
+          "info",
+          "Auth",
+          `${payload.previous_username} is now called ${payload.username}`,
+        );
+        if (this.currentAccount?.uuid === payload.previous_uuid) {
+          this.currentAccount = {
+            ...this.currentAccount,
+            username: payload.username,
+            uuid: payload.uuid,
+          };
+        }
+      },
+    );
     try {
       const acc = await invoke("get_active_account");
       this.currentAccount = acc as Account | null;
@@ -61,19 +95,29 @@ export class AuthState {
   resetLoginState() {
     this.loginMode = "select";
     this.offlineUsername = "";
+    this.offlineUseOnlineUuid = false;
     this.deviceCodeData = null;
     this.msLoginLoading = false;
+    this.profileName = "";
+    this.profileError = null;
   }
 
   async performOfflineLogin() {
     if (!this.offlineUsername) return;
     try {
-      this.currentAccount = (await invoke("login_offline", {
+      const { account, change } = (await invoke("login_offline", {
         username: this.offlineUsername,
-      })) as Account;
+        useOnlineUuid: this.offlineUseOnlineUuid,
+      })) as SavedAccount;
+      this.currentAccount = account;
       this.isLoginModalOpen = false;
+      uiState.setStatus(
+        change === "updated"
+          ? `Switched to ${account.username}, who was already added`
+          : `Added ${account.username}`,
+      );
     } catch (e) {
-      alert("Login failed: " + e);
+      alert("Login failed: " + errorMessage(e));
     }
   }
 
@@ -81,10 +125,12 @@ export class AuthState {
     this.loginMode = "microsoft";
     this.msLoginLoading = true;
     this.msLoginStatus = "Waiting for authorization...";
-    this.stopPolling();
+    this.msLoginHelpUrl = null;
+    // A new login supersedes any running one on the backend
+    this.cleanupAuthListener();
 
     // Setup auth progress listener
-    this.setupAuthProgressListener();
+    await this.setupAuthProgressListener();
 
     try {
       this.deviceCodeData = (await invoke("start_microsoft_login")) as DeviceCodeResponse;
@@ -103,16 +149,10 @@ export class AuthState {
           "Microsoft login started, waiting for browser authorization...",
         );
 
-        console.log("Starting polling for token...");
-        const intervalMs = (this.deviceCodeData.interval || 5) * 1000;
-        this.pollInterval = setInterval(
-          () => this.checkLoginStatus(this.deviceCodeData!.device_code),
-          intervalMs,
-        );
       }
     } catch (e) {
-      logsState.addLog("error", "Auth", `Failed to start Microsoft login: ${e}`);
-      alert("Failed to start Microsoft login: " + e);
+      logsState.addLog("error", "Auth", `Failed to start Microsoft login: ${errorMessage(e)}`);
+      alert("Failed to start Microsoft login: " + errorMessage(e));
       this.loginMode = "select";
     } finally {
       this.msLoginLoading = false;
@@ -131,6 +171,17 @@ export class AuthState {
       this.msLoginStatus = message;
       logsState.addLog("info", "Auth", message);
     });
+
+    // The backend polls for the sign-in and reports back through these
+    this.loginEventUnlisteners = [
+      await listen<MsLoginPendingEvent>("ms-login-pending", (event) => {
+        const minutes = Math.ceil(event.payload.seconds_remaining / 60);
+        this.msLoginStatus = `Waiting for authorization... (code expires in ${minutes} min)`;
+      }),
+      await listen<MsLoginCompleteEvent>("ms-login-complete", (event) => {
+        this.onLoginComplete(event.payload);
+      }),
+    ];
   }
 
   private cleanupAuthListener() {
@@ -138,53 +189,64 @@ export class AuthState {
       this.authProgressUnlisten();
       this.authProgressUnlisten = null;
     }
+    this.loginEventUnlisteners.forEach((unlisten) => unlisten());
+    this.loginEventUnlisteners = [];
   }
 
+  /** Stop the backend's polling, e.g. when the user goes back. */
   stopPolling() {
-    if (this.pollInterval) {
-      clearInterval(this.pollInterval);
-      this.pollInterval = null;
-    }
+    this.cleanupAuthListener();
+    invoke("cancel_microsoft_login").catch((e) => console.error("Failed to cancel login:", e));
   }
 
-  async checkLoginStatus(deviceCode: string) {
-    if (this.isPollingRequestActive) return;
-    this.isPollingRequestActive = true;
-
-    console.log("Polling Microsoft API...");
+  async createProfile() {
+    if (!this.profileName || this.profileCreating) return;
+    this.profileCreating = true;
+    this.profileError = null;
     try {
-      this.currentAccount = (await invoke("complete_microsoft_login", {
-        deviceCode,
-      })) as Account;
+      const { account } = (await invoke("create_minecraft_profile", {
+        name: this.profileName,
+      })) as SavedAccount;
+      this.currentAccount = account;
+      this.isLoginModalOpen = false;
+      logsState.addLog("info", "Auth", `Created profile ${account.username}`);
+      uiState.setStatus("Welcome, " + account.username);
+    } catch (e) {
+      this.profileError = errorMessage(e);
+    } finally {
+      this.profileCreating = false;
+    }
+  }
 
-      console.log("Login Successful!", this.currentAccount);
-      this.stopPolling();
-      this.cleanupAuthListener();
+  private onLoginComplete({ account, change, error, needs_profile }: MsLoginCompleteEvent) {
+    this.cleanupAuthListener();
+    if (needs_profile) {
+      this.loginMode = "create_profile";
+      this.profileError = null;
+      return;
+    }
+    if (account) {
+      this.currentAccount = account;
       this.isLoginModalOpen = false;
-      logsState.addLog(
-        "info",
-        "Auth",
-        `Login successful! Welcome, ${this.currentAccount.username}`,
+      logsState.addLog("info", "Auth", `Login successful! Welcome, ${account.username}`);
+      uiState.setStatus(
+        change === "updated"
+          ? `${account.username} was already added; refreshed the login`
+          : "Welcome, " + account.username,
       );
-      uiState.setStatus("Welcome back, " + this.currentAccount.username);
-    } catch (e: any) {
-      const errStr = e.toString();
-      if (errStr.includes("authorization_pending")) {
-        console.log("Status: Waiting for user to authorize...");
-      } else {
-        console.error("Polling Error:", errStr);
-        this.msLoginStatus = "Error: " + errStr;
-        logsState.addLog("error", "Auth", `Login error: ${errStr}`);
-
-        if (errStr.includes("expired_token") || errStr.includes("access_denied")) {
-          this.stopPolling();
-          this.cleanupAuthListener();
-          alert("Login failed: " + errStr);
-          this.loginMode = "select";
-        }
-      }
-    } finally {
-      this.isPollingRequestActive = false;
+      return;
+    }
+
+    const errStr = error ? errorMessage(error) : "Unknown error";
+    console.error("Login Error:", errStr);
+    this.msLoginStatus = "Error: " + errStr;
+    logsState.addLog("error", "Auth", `Login error: ${errStr}`);
+    if (error?.code === "account_restricted" || error?.code === "profile_missing") {
+      // Keep the dialog open with a link to where the account can be fixed
+      this.msLoginHelpUrl = error.help_url;
+    } else {
+      alert("Login failed: " + errStr);
+      this.loginMode = "select";
     }
   }
 }
@@ -1,30 +1,39 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { Version } from "../types";
+import type { Version, VersionManifest } from "../types";
 import { uiState } from "./ui.svelte";
 import { authState } from "./auth.svelte";
+import { errorMessage, isLauncherError } from "../lib/errors";
 
 export class GameState {
   versions = $state<Version[]>([]);
+  latest = $state<VersionManifest["latest"] | null>(null);
   selectedVersion = $state("");
+  /** Session id of the last launch */
+  sessionId = $state<string | null>(null);
 
   get latestRelease() {
-    return this.versions.find((v) => v.type === "release");
+    return (
+      this.versions.find((v) => v.id === this.latest?.release) ??
+      this.versions.find((v) => v.type === "release")
+    );
   }
 
   async loadVersions() {
     try {
-      this.versions = await invoke<Version[]>("get_versions");
+      const manifest = await invoke<VersionManifest>("get_versions");
+      this.versions = manifest.versions;
+      this.latest = manifest.latest;
       if (this.versions.length > 0) {
-        const latest = this.versions.find((v) => v.type === "release");
+        const latest = this.latestRelease;
         this.selectedVersion = latest ? latest.id : this.versions[0].id;
       }
     } catch (e) {
       console.error("Failed to fetch versions:", e);
-      uiState.setStatus("Error fetching versions: " + e);
+      uiState.setStatus("Error fetching versions: " + errorMessage(e));
     }
   }
 
-  async startGame() {
+  async startGame(allowMetered = false) {
     if (!authState.currentAccount) {
       alert("Please login first!");
       authState.openLoginModal();
@@ -39,12 +48,36 @@ export class GameState {
     uiState.setStatus("Preparing to launch " + this.selectedVersion + "...");
     console.log("Invoking start_game for version:", this.selectedVersion);
     try {
-      const msg = await invoke<string>("start_game", { versionId: this.selectedVersion });
-      console.log("Response:", msg);
-      uiState.setStatus(msg);
+      // Events of this launch carry the returned session id
+      this.sessionId = await invoke<string>("start_game", {
+        versionId: this.selectedVersion,
+        allowMetered,
+      });
+      console.log("Launch session:", this.sessionId);
+      uiState.setStatus(`Launched Minecraft ${this.selectedVersion}`);
     } catch (e) {
+      if (isLauncherError(e) && e.code === "metered_download") {
+        if (confirm(`${e.message}. Download anyway?`)) {
+          return this.startGame(true);
+        }
+        uiState.setStatus("Launch cancelled");
+        return;
+      }
       console.error(e);
-      uiState.setStatus("Error: " + e);
+      uiState.setStatus("Error: " + errorMessage(e));
+    }
+  }
+
+  /** Move the current session's game output to a window of its own */
+  async openConsoleWindow() {
+    if (!this.sessionId) return;
+    try {
+      await invoke<string>("open_console_window", {
+        sessionId: this.sessionId,
+        title: this.selectedVersion || null,
+      });
+    } catch (e) {
+      uiState.setStatus("Error: " + errorMessage(e));
     }
   }
 }
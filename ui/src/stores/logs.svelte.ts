@@ -1,4 +1,6 @@
 import { listen } from "@tauri-apps/api/event";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
+import type { DownloadStartedEvent, GameOutputEvent, LogEvent } from "../types";
 
 export interface LogEntry {
   id: number;
@@ -8,6 +10,9 @@ export interface LogEntry {
   message: string;
 }
 
+/** Session shown by a console window opened with `open_console_window` */
+export const consoleSession = new URLSearchParams(window.location.search).get("console");
+
 // Parse Minecraft/Java log format: [HH:MM:SS] [Thread/LEVEL]: message
 // or: [HH:MM:SS] [Thread/LEVEL] [Source]: message
 const GAME_LOG_REGEX = /^\[[\d:]+\]\s*\[([^\]]+)\/(\w+)\](?:\s*\[([^\]]+)\])?:\s*(.*)$/;
@@ -97,23 +102,25 @@ export class LogsState {
 
   private async setupListeners() {
     // General Launcher Logs
-    await listen<string>("launcher-log", (e) => {
-      this.addLog("info", "Launcher", e.payload);
-    });
-
-    // Game Stdout - parse log level
-    await listen<string>("game-stdout", (e) => {
-      this.addGameLog(e.payload, false);
+    await listen<LogEvent>("launcher-log", (e) => {
+      if (consoleSession && e.payload.session_id !== consoleSession) return;
+      this.addLog(e.payload.level, "Launcher", e.payload.message);
     });
 
-    // Game Stderr - parse log level, default to error
-    await listen<string>("game-stderr", (e) => {
-      this.addGameLog(e.payload, true);
+    // Game output - parse log level, stderr defaults to error. Listening on
+    // this window skips output sent only to a session's console window.
+    await getCurrentWebviewWindow().listen<GameOutputEvent>("game-output", (e) => {
+      if (consoleSession && e.payload.session_id !== consoleSession) return;
+      this.addGameLog(e.payload.line, e.payload.stream === "stderr");
     });
 
     // Download Events (Summarized)
-    await listen("download-start", (e) => {
-      this.addLog("info", "Downloader", `Starting batch download of ${e.payload} files...`);
+    await listen<DownloadStartedEvent>("download-start", (e) => {
+      this.addLog(
+        "info",
+        "Downloader",
+        `Starting batch download of ${e.payload.total_files} files...`,
+      );
     });
 
     await listen("download-complete", () => {
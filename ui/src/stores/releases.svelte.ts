@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import { errorMessage } from "../lib/errors";
 
 export interface GithubRelease {
   tag_name: string;
@@ -26,7 +27,7 @@ export class ReleasesState {
       this.isLoaded = true;
     } catch (e) {
       console.error("Failed to load releases:", e);
-      this.error = String(e);
+      this.error = errorMessage(e);
     } finally {
       this.isLoading = false;
     }
@@ -2,24 +2,40 @@ import { invoke } from "@tauri-apps/api/core";
 import { convertFileSrc } from "@tauri-apps/api/core";
 import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import type {
+  CloudSyncWarning,
+  DedupeReport,
   JavaCatalog,
   JavaDownloadProgress,
   JavaDownloadSource,
   JavaInstallation,
   JavaReleaseInfo,
   LauncherConfig,
+  PendingDownloads,
+  PendingFilesSummary,
   PendingJavaDownload,
+  ResumeReport,
+  SystemDiagnostics,
+  TrashItem,
 } from "../types";
 import { uiState } from "./ui.svelte";
+import { errorMessage } from "../lib/errors";
 
 export class SettingsState {
   settings = $state<LauncherConfig>({
     min_memory: 1024,
     max_memory: 2048,
+    jvm_preset: "default",
+    display_backend: "auto",
+    gpu_preference: "default",
+    gc_logging: false,
     java_path: "java",
     width: 854,
     height: 480,
     download_threads: 32,
+    max_connections_per_host: 16,
+    verification_mode: "full",
+    connect_timeout_secs: 15,
+    request_timeout_secs: 30,
     enable_gpu_acceleration: false,
     enable_visual_effects: true,
     active_effect: "constellation",
@@ -27,6 +43,35 @@ export class SettingsState {
     custom_background_path: undefined,
     log_upload_service: "paste.rs",
     pastebin_api_key: undefined,
+    redact_account_in_logs: false,
+    visible_version_types: ["release", "snapshot", "old_beta", "old_alpha"],
+    show_tray_icon: true,
+    trash_retention_days: 30,
+    log_level: "info",
+    debug_mode: false,
+    metered_policy: "when_metered",
+    metered_prompt_mb: 200,
+    mod_store: false,
+    curseforge_api_key: undefined,
+    prefetch: {
+      enabled: false,
+      interval_hours: 6,
+      max_kib_per_second: 1024,
+    },
+    instance_template: {
+      jvm: {},
+      options_txt: "",
+      resource_packs: [],
+    },
+    proxy: {
+      enabled: false,
+      kind: "http",
+      host: "",
+      port: 0,
+      username: undefined,
+      password: undefined,
+      apply_to_game: false,
+    },
   });
 
   // Convert background path to proper asset URL
@@ -38,6 +83,9 @@ export class SettingsState {
   }
   javaInstallations = $state<JavaInstallation[]>([]);
   isDetectingJava = $state(false);
+  systemInfo = $state<SystemDiagnostics | null>(null);
+  cloudSyncWarning = $state<CloudSyncWarning | null>(null);
+  trash = $state<TrashItem[]>([]);
 
   // Java download modal state
   showJavaDownloadModal = $state(false);
@@ -61,6 +109,7 @@ export class SettingsState {
 
   // Pending downloads
   pendingDownloads = $state<PendingJavaDownload[]>([]);
+  pendingFiles = $state<PendingFilesSummary[]>([]);
 
   // Event listener cleanup
   private progressUnlisten: UnlistenFn | null = null;
@@ -116,9 +165,10 @@ export class SettingsState {
   getInstallStatus(release: JavaReleaseInfo): "installed" | "download" {
     // Find installed Java that matches the major version and image type (by path pattern)
     const matchingInstallations = this.javaInstallations.filter((inst) => {
-      // Check if this is a DropOut-managed Java (path contains temurin-XX-jre/jdk pattern)
+      // Check if this is a DropOut-managed Java (path contains e.g. temurin-XX-jre/jdk pattern)
       const pathLower = inst.path.toLowerCase();
-      const pattern = `temurin-${release.major_version}-${release.image_type}`;
+      const prefix = this.selectedDownloadSource === "adoptium" ? "temurin" : this.selectedDownloadSource;
+      const pattern = `${prefix}-${release.major_version}-${release.image_type}`;
       return pathLower.includes(pattern);
     });
 
@@ -166,7 +216,64 @@ export class SettingsState {
       uiState.setStatus("Settings saved!");
     } catch (e) {
       console.error("Failed to save settings:", e);
-      uiState.setStatus("Error saving settings: " + e);
+      uiState.setStatus("Error saving settings: " + errorMessage(e));
+    }
+  }
+
+  async loadSystemInfo() {
+    try {
+      this.systemInfo = await invoke<SystemDiagnostics>("get_system_info");
+    } catch (e) {
+      console.error("Failed to load system info:", e);
+    }
+  }
+
+  async loadTrash() {
+    try {
+      this.trash = await invoke<TrashItem[]>("list_trash");
+    } catch (e) {
+      console.error("Failed to load the trash:", e);
+    }
+  }
+
+  async restoreTrashItem(itemId: string) {
+    try {
+      const item = await invoke<TrashItem>("restore_deleted_item", { itemId });
+      uiState.setStatus(`Restored ${item.name}`);
+    } catch (e) {
+      uiState.setStatus("Error restoring item: " + errorMessage(e));
+    }
+    await this.loadTrash();
+  }
+
+  async emptyTrash() {
+    try {
+      const freed = await invoke<number>("empty_trash");
+      uiState.setStatus(`Emptied the trash, freeing ${this.formatBytes(freed)}`);
+    } catch (e) {
+      uiState.setStatus("Error emptying the trash: " + errorMessage(e));
+    }
+    await this.loadTrash();
+  }
+
+  async dedupeMods() {
+    try {
+      const report = await invoke<DedupeReport>("dedupe_mods");
+      uiState.setStatus(
+        `Deduplicated ${report.files} mods; ${this.formatBytes(report.shared_bytes)} shared` +
+          (report.errors.length > 0 ? `, ${report.errors.length} failed` : ""),
+      );
+    } catch (e) {
+      uiState.setStatus("Error deduplicating mods: " + errorMessage(e));
+    }
+  }
+
+  async checkCloudSync() {
+    try {
+      this.cloudSyncWarning = await invoke<CloudSyncWarning | null>("check_cloud_sync");
+      if (this.cloudSyncWarning) uiState.setStatus(this.cloudSyncWarning.message);
+    } catch (e) {
+      console.error("Failed to check for cloud sync:", e);
     }
   }
 
@@ -181,7 +288,7 @@ export class SettingsState {
       }
     } catch (e) {
       console.error("Failed to detect Java:", e);
-      uiState.setStatus("Error detecting Java: " + e);
+      uiState.setStatus("Error detecting Java: " + errorMessage(e));
     } finally {
       this.isDetectingJava = false;
     }
@@ -258,7 +365,7 @@ export class SettingsState {
       }
     } catch (e) {
       console.error("Failed to load Java catalog:", e);
-      this.catalogError = `Failed to load Java catalog: ${e}`;
+      this.catalogError = `Failed to load Java catalog: ${errorMessage(e)}`;
     } finally {
       this.isLoadingCatalog = false;
     }
@@ -271,7 +378,9 @@ export class SettingsState {
 
   async loadPendingDownloads() {
     try {
-      this.pendingDownloads = await invoke<PendingJavaDownload[]>("get_pending_java_downloads");
+      const pending = await invoke<PendingDownloads>("get_pending_downloads");
+      this.pendingDownloads = pending.java;
+      this.pendingFiles = pending.files;
     } catch (e) {
       console.error("Failed to load pending downloads:", e);
     }
@@ -296,6 +405,7 @@ export class SettingsState {
         majorVersion: this.selectedMajorVersion,
         imageType: this.selectedImageType,
         customPath: null,
+        vendor: this.selectedDownloadSource,
       });
 
       this.settings.java_path = result.path;
@@ -307,7 +417,7 @@ export class SettingsState {
       }, 1500);
     } catch (e) {
       console.error("Failed to download Java:", e);
-      this.javaDownloadStatus = `Download failed: ${e}`;
+      this.javaDownloadStatus = `Download failed: ${errorMessage(e)}`;
     } finally {
       this.isDownloadingJava = false;
     }
@@ -326,22 +436,31 @@ export class SettingsState {
   }
 
   async resumeDownloads() {
-    if (this.pendingDownloads.length === 0) return;
+    if (this.pendingDownloads.length === 0 && this.pendingFiles.length === 0) return;
 
     this.isDownloadingJava = true;
     this.javaDownloadStatus = "Resuming download...";
 
     try {
-      const installed = await invoke<JavaInstallation[]>("resume_java_downloads");
-      if (installed.length > 0) {
-        this.settings.java_path = installed[0].path;
+      const report = await invoke<ResumeReport>("resume_pending_downloads");
+      if (report.java.length > 0) {
+        this.settings.java_path = report.java[0].path;
         await this.detectJava();
-        uiState.setStatus(`Resumed and installed ${installed.length} Java version(s)`);
+        uiState.setStatus(`Resumed and installed ${report.java.length} Java version(s)`);
+      } else if (report.files > 0) {
+        uiState.setStatus(`Resumed ${report.files} game file download(s)`);
+      } else if (report.reverted_pack_updates > 0) {
+        uiState.setStatus(
+          `Reverted ${report.reverted_pack_updates} interrupted modpack update(s); run the update again`,
+        );
+      }
+      if (report.errors.length > 0) {
+        this.javaDownloadStatus = `Resume failed: ${report.errors[0]}`;
       }
       await this.loadPendingDownloads();
     } catch (e) {
       console.error("Failed to resume downloads:", e);
-      this.javaDownloadStatus = `Resume failed: ${e}`;
+      this.javaDownloadStatus = `Resume failed: ${errorMessage(e)}`;
     } finally {
       this.isDownloadingJava = false;
     }
@@ -6,6 +6,15 @@ export interface Version {
   url: string;
   time: string;
   releaseTime: string;
+  /** SHA-1 of the version JSON */
+  sha1?: string | null;
+  /** 0 for versions lacking the current player safety features */
+  complianceLevel?: number | null;
+}
+
+export interface VersionManifest {
+  latest: { release: string; snapshot: string };
+  versions: Version[];
 }
 
 export interface Account {
@@ -17,6 +26,35 @@ export interface Account {
   expires_at?: number; // Unix timestamp for Microsoft accounts
 }
 
+/** Whether the active Microsoft account can play Java Edition */
+export interface AccountEntitlements {
+  owns_game: boolean;
+  /** New Game Pass accounts have no profile until one is created */
+  has_profile: boolean;
+  /** Set when the game is owned but the profile is missing */
+  create_profile_url: string | null;
+}
+
+/** Whether a login stored a new account or updated one with the same UUID */
+export type AccountChange = "added" | "updated";
+
+export interface SavedAccount {
+  account: Account;
+  change: AccountChange;
+}
+
+/** A stored account as returned by `list_accounts`, most recently used first */
+export interface AccountSummary {
+  uuid: string;
+  username: string;
+  type: "Offline" | "Microsoft";
+  active: boolean;
+  /** Unix time; 0 for accounts added before this was tracked */
+  added_at: number;
+  last_used_at: number | null;
+  last_instance_id: string | null;
+}
+
 export interface DeviceCodeResponse {
   user_code: string;
   device_code: string;
@@ -26,32 +64,288 @@ export interface DeviceCodeResponse {
   message?: string;
 }
 
+/** mclo.gs removes IP addresses and access tokens on upload */
+export type LogUploadService = "paste.rs" | "pastebin.com" | "mclo.gs";
+
+/** How files already on disk are checked before launching */
+export type VerificationMode = "full" | "fast" | "index";
+
 export interface LauncherConfig {
   min_memory: number;
   max_memory: number;
+  jvm_preset: JvmPreset;
+  display_backend: DisplayBackend;
+  gpu_preference: GpuPreference;
+  gc_logging: boolean;
   java_path: string;
   width: number;
   height: number;
   download_threads: number;
+  max_connections_per_host: number;
+  verification_mode: VerificationMode;
   custom_background_path?: string;
   enable_gpu_acceleration: boolean;
   enable_visual_effects: boolean;
   active_effect: string;
   theme: string;
-  log_upload_service: "paste.rs" | "pastebin.com";
+  log_upload_service: LogUploadService;
   pastebin_api_key?: string;
+  /** Hide the player's name and UUID in uploaded logs */
+  redact_account_in_logs: boolean;
+  visible_version_types: string[];
+  show_tray_icon: boolean;
+  /** Days deleted items stay in the trash; 0 keeps them until emptied */
+  trash_retention_days: number;
+  log_level: LogLevel;
+  /** Log HTTP requests, full launch arguments and rule decisions */
+  debug_mode: boolean;
+  metered_policy: MeteredPolicy;
+  /** Downloads above this size (MB) ask first, see `metered_policy` */
+  metered_prompt_mb: number;
+  /** Keep mods once and link them into instances */
+  mod_store: boolean;
+  /** Needed to update CurseForge modpacks */
+  curseforge_api_key?: string;
+  prefetch: PrefetchConfig;
+  instance_template: InstanceTemplate;
+  proxy: ProxyConfig;
+  user_agent?: string;
+  connect_timeout_secs: number;
+  /** Applies to API requests, not file downloads */
+  request_timeout_secs: number;
+}
+
+/** Extra JVM flags added to the launch arguments */
+export type JvmPreset = "default" | "performance" | "low_memory";
+
+/** How the game window is shown on a Linux Wayland session */
+export type DisplayBackend = "auto" | "wayland" | "xwayland";
+
+/** GPU the game runs on with integrated and discrete graphics */
+export type GpuPreference = "default" | "integrated" | "discrete";
+
+/** Memory and JVM settings of an instance; unset fields use the launcher settings */
+export interface JvmOverrides {
+  min_memory?: number | null;
+  max_memory?: number | null;
+  preset?: JvmPreset | null;
+  display_backend?: DisplayBackend | null;
+  gpu_preference?: GpuPreference | null;
+  gc_logging?: boolean | null;
+}
+
+/** A mod merged into an instance's client jar; later ones override earlier ones */
+export interface JarMod {
+  /** File name in the instance's `jarmods/` folder */
+  file: string;
+  enabled: boolean;
+}
+
+/** Replaces the version of a library the version JSON asks for */
+export interface LibraryOverride {
+  /** `group:artifact`, or `group:artifact:classifier` for one classifier */
+  library: string;
+  /** Null removes the library */
+  version: string | null;
+  /** Maven repository hosting the new version */
+  url?: string | null;
+  sha1?: string | null;
+}
+
+/** Defaults applied to new instances */
+export interface InstanceTemplate {
+  jvm: JvmOverrides;
+  /** `key:value` lines merged into options.txt */
+  options_txt: string;
+  /** Paths of resource pack files copied into the instance */
+  resource_packs: string[];
+}
+
+/** Background downloads of new versions, loaders and Java patches */
+export interface PrefetchConfig {
+  enabled: boolean;
+  interval_hours: number;
+  /** 0 for no cap */
+  max_kib_per_second: number;
+}
+
+export interface ProxyConfig {
+  enabled: boolean;
+  kind: "http" | "socks5";
+  host: string;
+  port: number;
+  username?: string;
+  password?: string;
+  apply_to_game: boolean;
 }
 
 export interface JavaInstallation {
   path: string;
   version: string;
   is_64bit: boolean;
+  /** Set for runtimes installed by DropOut */
+  managed: JavaProvenance | null;
+}
+
+/** Where a runtime installed by DropOut came from */
+export interface JavaProvenance {
+  vendor: string;
+  major_version: number;
+  image_type: "jre" | "jdk";
+  release_name: string;
+  /** Unix time */
+  installed_at: number;
+  source_url: string;
+  archive_checksum: string | null;
+}
+
+/** Result of `verify_java_installation` */
+export interface JavaVerification {
+  path: string;
+  valid: boolean;
+  version: string | null;
+  problems: string[];
+  /** Files were compared against the manifest recorded at install */
+  manifest_checked: boolean;
+  reinstalled: boolean;
+}
+
+export type BackupReason = "pack_update" | "loader_change";
+
+export interface InstanceBackup {
+  /** Zip in the instance's backups/ folder */
+  file_name: string;
+  reason: BackupReason;
+  created_at: number;
+  size: number;
+}
+
+export interface Mismatch {
+  a: string;
+  b: string;
+}
+
+export interface ModEntry {
+  file_name: string;
+  version?: string;
+}
+
+export interface ModDifference {
+  /** Mod id, or the file name for jars without metadata */
+  id: string;
+  a?: ModEntry;
+  b?: ModEntry;
+}
+
+export interface FileDifference {
+  /** Path relative to config/ */
+  path: string;
+  in_a: boolean;
+  in_b: boolean;
+  only_in_a: string[];
+  only_in_b: string[];
+}
+
+export interface InstanceDiff {
+  version?: Mismatch;
+  loader?: Mismatch;
+  mods: ModDifference[];
+  config_files: FileDifference[];
+}
+
+export interface GameMetrics {
+  timestamp: number;
+  /** Percent of one CPU core */
+  cpu_percent: number;
+  memory_bytes: number;
+  system_available_bytes: number;
+}
+
+/** Result of `suggest_jvm_settings`; maps onto an instance's `jvm` overrides */
+export interface JvmSuggestion {
+  min_memory: number;
+  max_memory: number;
+  preset: JvmPreset;
+  args: string[];
+  mod_count: number;
+  total_memory_mb: number;
+  /** 0 when the Java could not be checked */
+  java_major: number;
+  reasons: string[];
+}
+
+/** One download run, from `get_download_stats` */
+export interface DownloadRun {
+  instance_id?: string;
+  session_id?: string;
+  finished_at: number;
+  duration_ms: number;
+  downloaded_files: number;
+  downloaded_bytes: number;
+  /** Files already present and valid */
+  skipped_files: number;
+  skipped_bytes: number;
+  failed_files: number;
+  average_speed_bps: number;
+}
+
+export interface DownloadStats {
+  runs: number;
+  downloaded_bytes: number;
+  downloaded_files: number;
+  skipped_files: number;
+  failed_files: number;
+  last_30_days_bytes: number;
+  /** Share of files that were already valid, 0 to 1 */
+  cache_hit_rate: number;
+  average_speed_bps: number;
+  /** Newest first */
+  recent: DownloadRun[];
+}
+
+/** Summary of a GC log, from `get_gc_report` or the `gc-report` event */
+export interface GcReport {
+  pauses: number;
+  full_pauses: number;
+  total_pause_ms: number;
+  max_pause_ms: number;
+  average_pause_ms: number;
+  peak_heap_mb: number;
+  /** Heap still in use after a collection, at most */
+  max_live_heap_mb: number;
+  heap_capacity_mb: number;
+  uptime_secs: number;
+  notes: string[];
+}
+
+export type TrashKind = "instance" | "world" | "mod";
+
+export interface TrashItem {
+  id: string;
+  kind: TrashKind;
+  name: string;
+  original_path: string;
+  instance_id?: string;
+  deleted_at: number;
+  size: number;
+}
+
+export interface JavaUpdate {
+  vendor: JavaDownloadSource;
+  major_version: number;
+  image_type: string;
+  /** Installed release, e.g. jdk-21.0.4+7 */
+  installed: string;
+  latest: string;
+  update_available: boolean;
 }
 
 export interface JavaDownloadInfo {
   version: string;
   release_name: string;
   download_url: string;
+  /** Other URLs serving the same archive */
+  mirrors: string[];
   file_name: string;
   file_size: number;
   checksum: string | null;
@@ -93,6 +387,8 @@ export interface PendingJavaDownload {
   major_version: number;
   image_type: string;
   download_url: string;
+  /** Other URLs serving the same archive */
+  mirrors: string[];
   file_name: string;
   file_size: number;
   checksum: string | null;
@@ -100,7 +396,44 @@ export interface PendingJavaDownload {
   created_at: number;
 }
 
-export type JavaDownloadSource = "adoptium" | "mojang" | "azul";
+/** Game files whose download was interrupted by closing the launcher */
+export interface PendingFilesSummary {
+  id: string;
+  /** Interrupted modpack updates are rolled back instead of resumed */
+  kind: "game_files" | "pack_update";
+  instance_id: string | null;
+  file_count: number;
+  total_bytes: number;
+  created_at: number;
+}
+
+/** Result of `get_pending_downloads` */
+export interface PendingDownloads {
+  java: PendingJavaDownload[];
+  files: PendingFilesSummary[];
+}
+
+/** Result of `resume_pending_downloads` */
+export interface ResumeReport {
+  java: JavaInstallation[];
+  files: number;
+  /** Interrupted modpack updates whose partial files were removed */
+  reverted_pack_updates: number;
+  errors: string[];
+}
+
+/** Result of `dedupe_mods` */
+export interface DedupeReport {
+  files: number;
+  reflinked: number;
+  hard_linked: number;
+  /** Files the filesystem could not share */
+  copied: number;
+  shared_bytes: number;
+  errors: string[];
+}
+
+export type JavaDownloadSource = "adoptium" | "zulu" | "graalvm";
 
 // ==================== Fabric Types ====================
 
@@ -159,3 +492,180 @@ export interface InstalledForgeVersion {
 // ==================== Mod Loader Type ====================
 
 export type ModLoaderType = "vanilla" | "fabric" | "forge";
+
+// ==================== Dropped Files ====================
+
+/** What a file dropped onto an instance was detected as. */
+export type DroppedFile =
+  | { kind: "mod"; loader: "fabric" | "quilt" | "forge" | "neoforge" }
+  | { kind: "modpack"; platform: "modrinth" | "curseforge" }
+  | { kind: "resource_pack" }
+  | { kind: "world"; name: string };
+
+export interface DropImportReport {
+  detected: DroppedFile;
+  /** Null for modpacks, which are only detected */
+  installed_to: string | null;
+  replaced: boolean;
+}
+
+// ==================== Custom Versions ====================
+
+/** Result of `import_custom_version` */
+export interface CustomVersionImport {
+  /** Id the version was installed as */
+  id: string;
+  /** Original id, when it was taken and the version was renamed */
+  renamed_from: string | null;
+  loader: "vanilla" | "fabric" | "quilt" | "forge" | "neoforge";
+  inherits_from: string | null;
+  custom_jar: boolean;
+  warnings: string[];
+}
+
+// ==================== Deep Links ====================
+
+/** A validated `dropout://` link, acted on only after the user confirms. */
+export type DeepLink =
+  | { action: "install_modrinth"; project: string }
+  | { action: "join"; host: string; port: number | null };
+
+export interface DeepLinkReceivedEvent extends EventScope {
+  link: DeepLink;
+}
+
+/** Arguments of a second launcher start, other than `dropout://` links. */
+export interface ArgumentsForwardedEvent extends EventScope {
+  args: string[];
+  cwd: string;
+}
+
+// ==================== Errors ====================
+
+export type LauncherErrorCode =
+  | "network"
+  | "auth"
+  | "timeout"
+  | "io"
+  | "corrupt"
+  | "cancelled"
+  | "busy"
+  | "instance_busy"
+  | "java_missing"
+  | "invalid"
+  | "account_restricted"
+  | "profile_missing"
+  | "other";
+
+/** Error returned by backend commands. */
+export interface LauncherError {
+  code: LauncherErrorCode;
+  message: string;
+  /** What was being done, outermost first */
+  context: string[];
+  /** Page where the user can resolve the problem */
+  help_url: string | null;
+}
+
+/** Fields carried by every backend event. */
+export interface EventScope {
+  instance_id: string | null;
+  /** Id of a single launch or install run */
+  session_id: string | null;
+}
+
+export type LogLevel = "error" | "warn" | "info" | "debug";
+
+/** When to ask before a large download */
+export type MeteredPolicy = "never" | "when_metered" | "always";
+
+export interface LogEvent extends EventScope {
+  level: LogLevel;
+  message: string;
+}
+
+export interface GameOutputEvent extends EventScope {
+  stream: "stdout" | "stderr";
+  line: string;
+}
+
+export interface GameExitedEvent extends EventScope {
+  code: number;
+}
+
+export interface DownloadStartedEvent extends EventScope {
+  total_files: number;
+}
+
+/** Totals are only known for zip archives. */
+export interface ExtractProgressEvent extends EventScope {
+  archive: string;
+  entries_done: number;
+  entries_total: number | null;
+  bytes_done: number;
+  bytes_total: number | null;
+}
+
+/** Files in one of the instance's content folders changed on disk. */
+export interface InstanceContentChangedEvent extends EventScope {
+  folder: "mods" | "resourcepacks" | "saves";
+}
+
+export interface LauncherPaths {
+  data_dir: string;
+  config_file: string;
+  instances_dir: string;
+}
+
+export interface SystemDiagnostics {
+  launcher_version: string;
+  total_memory_mb: number;
+  cpu_count: number;
+  os: string;
+  arch: string;
+  os_version: string | null;
+  kernel_version: string | null;
+  cpu_model: string | null;
+  available_memory_mb: number;
+  gpus: string[];
+  /** "wayland" or "x11", Linux only */
+  display_server: string | null;
+  graphics_hints: string[];
+  paths: LauncherPaths;
+}
+
+export interface DiagnosticBundleResult {
+  /** Zip under the data directory's diagnostics folder */
+  path: string;
+  url: string | null;
+}
+
+export interface MsLoginPendingEvent extends EventScope {
+  seconds_remaining: number;
+}
+
+/** The launcher's data folder lies in a cloud-synced folder */
+export interface CloudSyncWarning {
+  path: string;
+  service: "one_drive" | "dropbox" | "i_cloud" | "google_drive";
+  message: string;
+}
+
+/** A Microsoft profile renamed since its last token refresh */
+export interface AccountProfileUpdatedEvent extends EventScope {
+  uuid: string;
+  username: string;
+  previous_uuid: string;
+  previous_username: string;
+}
+
+/**
+ * Either `account` and `change` or `error` is set, or neither if the account
+ * needs a Minecraft profile first (`needs_profile`).
+ */
+export interface MsLoginCompleteEvent extends EventScope {
+  account: Account | null;
+  change: AccountChange | null;
+  needs_profile: boolean;
+  error: LauncherError | null;
+}